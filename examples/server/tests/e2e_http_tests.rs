@@ -114,8 +114,8 @@ async fn e2e_rls_allows_updating_current_user() {
         &format!(
             r#"mutation {{
                 updateUser(
-                    patch: {{ bio: \"{}\" }}
-                    condition: {{ username: {{ equal: \"alice\" }} }}
+                    patch: {{ bio: "{}" }}
+                    condition: {{ username: {{ equal: "alice" }} }}
                 ) {{ id username bio }}
             }}"#,
             temp_bio
@@ -136,8 +136,8 @@ async fn e2e_rls_allows_updating_current_user() {
         &format!(
             r#"mutation {{
                 updateUser(
-                    patch: {{ bio: \"{}\" }}
-                    condition: {{ username: {{ equal: \"alice\" }} }}
+                    patch: {{ bio: "{}" }}
+                    condition: {{ username: {{ equal: "alice" }} }}
                 ) {{ id bio }}
             }}"#,
             original_bio
@@ -174,3 +174,83 @@ async fn e2e_rls_prevents_deleting_other_users_posts() {
         "RLS should block deleting posts not owned by app.current_user_id=1"
     );
 }
+
+#[tokio::test]
+async fn e2e_exports_filtered_rows_as_ndjson() {
+    let client = Client::new();
+    let url = graphql_url();
+    wait_for_server(&client, &url).await;
+    let export_url = url.replace("/graphql", "/export/users");
+
+    let resp = client
+        .get(&export_url)
+        .query(&[
+            ("format", "ndjson"),
+            ("filter", r#"{"username":{"equal":"bob"}}"#),
+        ])
+        .send()
+        .await
+        .expect("failed to send export request");
+    assert!(
+        resp.status().is_success(),
+        "export returned {}",
+        resp.status()
+    );
+    let body = resp.text().await.expect("failed to read export body");
+    let rows: Vec<Json> = body
+        .lines()
+        .map(|line| serde_json::from_str(line).expect("each line should be JSON"))
+        .collect();
+    assert_eq!(rows.len(), 1, "{body}");
+    assert_eq!(rows[0]["username"], "bob");
+
+    let resp = client
+        .get(&export_url)
+        .query(&[("filter", r#"{"username":{"between":"b"}}"#)])
+        .send()
+        .await
+        .expect("failed to send export request");
+    assert_eq!(resp.status(), reqwest::StatusCode::BAD_REQUEST);
+}
+
+#[tokio::test]
+async fn e2e_accepts_multipart_requests() {
+    let client = Client::new();
+    let url = graphql_url();
+    wait_for_server(&client, &url).await;
+
+    let form = |file: Vec<u8>| {
+        reqwest::multipart::Form::new()
+            .text(
+                "operations",
+                json!({ "query": "{ __typename }" }).to_string(),
+            )
+            .text("map", json!({ "0": ["variables.file"] }).to_string())
+            .part(
+                "0",
+                reqwest::multipart::Part::bytes(file).file_name("a.txt"),
+            )
+    };
+
+    let resp = client
+        .post(&url)
+        .multipart(form(b"hello".to_vec()))
+        .send()
+        .await
+        .expect("failed to send multipart request");
+    assert!(
+        resp.status().is_success(),
+        "multipart returned {}",
+        resp.status()
+    );
+    let body: Json = resp.json().await.expect("failed to parse JSON response");
+    assert_eq!(body["data"]["__typename"], "Query");
+
+    let resp = client
+        .post(&url)
+        .multipart(form(vec![0; 10 * 1024 * 1024 + 1]))
+        .send()
+        .await
+        .expect("failed to send multipart request");
+    assert_eq!(resp.status(), reqwest::StatusCode::PAYLOAD_TOO_LARGE);
+}
@@ -0,0 +1,69 @@
+//! Command-line tooling around a server's exported schema.
+//!
+//! ```text
+//! turbograph persisted-operations --sdl schema.graphql [--out persisted.json] <dir>
+//! ```
+//!
+//! `persisted-operations` validates every `.graphql` and `.gql` file under
+//! `<dir>` against the SDL the server exported with `TurboGraph::export_sdl`,
+//! so names and shapes follow its `Config`, and writes the
+//! persisted-operation manifest, to stdout without `--out`.
+
+use std::process::ExitCode;
+
+use turbograph::PersistedOperations;
+
+type BoxError = Box<dyn std::error::Error + Send + Sync>;
+
+const USAGE: &str =
+    "usage: turbograph persisted-operations --sdl <schema.graphql> [--out <file>] <dir>";
+
+#[tokio::main]
+async fn main() -> ExitCode {
+    let args: Vec<String> = std::env::args().skip(1).collect();
+    let result = match args.split_first() {
+        Some((command, rest)) if command == "persisted-operations" => {
+            persisted_operations(rest).await
+        }
+        _ => Err(USAGE.into()),
+    };
+    match result {
+        Ok(()) => ExitCode::SUCCESS,
+        Err(e) => {
+            eprintln!("{e}");
+            ExitCode::FAILURE
+        }
+    }
+}
+
+async fn persisted_operations(args: &[String]) -> Result<(), BoxError> {
+    let mut sdl = None;
+    let mut out = None;
+    let mut dir = None;
+    let mut args = args.iter();
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--sdl" => sdl = args.next(),
+            "--out" => out = args.next(),
+            _ if dir.is_none() && !arg.starts_with("--") => dir = Some(arg),
+            _ => return Err(USAGE.into()),
+        }
+    }
+    let (Some(sdl), Some(dir)) = (sdl, dir) else {
+        return Err(USAGE.into());
+    };
+
+    let sdl = std::fs::read_to_string(sdl)?;
+    let manifest = PersistedOperations::from_sdl(&sdl, dir).await?;
+    let json = manifest.to_json()?;
+    match out {
+        Some(out) => std::fs::write(out, json + "\n")?,
+        None => println!("{json}"),
+    }
+    let count = manifest.len();
+    eprintln!(
+        "[turbograph] {count} persisted operation{}",
+        if count == 1 { "" } else { "s" }
+    );
+    Ok(())
+}
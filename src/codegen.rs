@@ -0,0 +1,71 @@
+//! Build-time export of an introspected [`Catalog`] as Rust source, so a
+//! binary can embed its schema and start without runtime introspection.
+//!
+//! ```rust,ignore
+//! // build.rs
+//! let catalog = runtime.block_on(turbograph::introspect(config))?;
+//! let out = std::path::Path::new(&std::env::var("OUT_DIR")?).join("catalog.rs");
+//! std::fs::write(out, turbograph::static_catalog_module(&catalog)?)?;
+//!
+//! // main.rs
+//! include!(concat!(env!("OUT_DIR"), "/catalog.rs"));
+//! let server = TurboGraph::from_catalog(config, catalog()?)?;
+//! ```
+
+use crate::models::catalog::Catalog;
+
+/// Renders a Rust module exposing the catalog as a `CATALOG_JSON` constant
+/// and a `catalog()` constructor. The output is deterministic for a given
+/// database, so it can be checked in or diffed between builds.
+pub fn static_catalog_module(catalog: &Catalog) -> Result<String, serde_json::Error> {
+    let json = catalog.to_json()?;
+    let hashes = raw_string_hashes(&json);
+
+    Ok(format!(
+        "// @generated by turbograph. Do not edit.\n\
+         \n\
+         /// Introspected catalog embedded at build time.\n\
+         pub const CATALOG_JSON: &str = r{hashes}\"{json}\"{hashes};\n\
+         \n\
+         /// The embedded catalog, for [`turbograph::TurboGraph::from_catalog`].\n\
+         pub fn catalog() -> Result<turbograph::Catalog, turbograph::CatalogParseError> {{\n    \
+             turbograph::Catalog::from_json(CATALOG_JSON)\n\
+         }}\n"
+    ))
+}
+
+/// The shortest run of `#` that cannot terminate a raw string early.
+fn raw_string_hashes(content: &str) -> String {
+    let mut hashes = "#".to_string();
+    while content.contains(&format!("\"{hashes}")) {
+        hashes.push('#');
+    }
+    hashes
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::table::Table;
+
+    #[test]
+    fn test_module_embeds_round_trippable_json() {
+        let catalog = Catalog::new(vec![Table::new_for_test("users", vec![]).with_oid(7)]);
+        let module = static_catalog_module(&catalog).unwrap();
+
+        assert!(module.starts_with("// @generated by turbograph."));
+        let json = module
+            .split_once("r#\"")
+            .and_then(|(_, rest)| rest.split_once("\"#;"))
+            .map(|(json, _)| json)
+            .unwrap();
+        let reloaded = Catalog::from_json(json).unwrap();
+        assert_eq!(reloaded.table(7).unwrap().name(), "users");
+    }
+
+    #[test]
+    fn test_raw_string_hashes_avoid_terminators() {
+        assert_eq!(raw_string_hashes("plain"), "#");
+        assert_eq!(raw_string_hashes(r##"a "# b"##), "##");
+    }
+}
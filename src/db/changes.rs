@@ -0,0 +1,188 @@
+use std::collections::HashSet;
+use std::sync::{Arc, Mutex};
+
+use deadpool_postgres::Pool;
+use serde::Deserialize;
+use tokio::sync::broadcast;
+use tokio_postgres::AsyncMessage;
+
+use crate::models::catalog::Catalog;
+use crate::sql::{quote_ident, quote_qualified};
+
+/// Channel the change triggers notify on.
+const CHANGE_CHANNEL: &str = "turbograph_change";
+
+/// Prefix of the channels behind the `listen(topic:)` subscription, so
+/// clients can only listen to channels meant for them: `NOTIFY
+/// "turbograph:orders", '...'` reaches `listen(topic: "orders")`.
+pub(crate) const TOPIC_PREFIX: &str = "turbograph:";
+
+/// Longest channel name Postgres keeps; longer ones are truncated.
+const MAX_CHANNEL_LEN: usize = 63;
+
+/// Row trigger function reporting every change on the table it is attached
+/// to as a NOTIFY on `turbograph_change`. The trigger arguments name the
+/// primary key columns, whose values identify the changed row.
+const CHANGE_FUNCTION_SQL: &str = r"
+CREATE OR REPLACE FUNCTION turbograph_notify_change() RETURNS trigger AS $$
+DECLARE
+  changed jsonb := to_jsonb(CASE WHEN TG_OP = 'DELETE' THEN OLD ELSE NEW END);
+  key jsonb := '[]';
+  col text;
+BEGIN
+  FOREACH col IN ARRAY TG_ARGV LOOP
+    key := key || jsonb_build_array(changed -> col);
+  END LOOP;
+  PERFORM pg_notify('turbograph_change', json_build_object(
+    'schema', TG_TABLE_SCHEMA, 'table', TG_TABLE_NAME, 'op', TG_OP, 'key', key
+  )::text);
+  RETURN NULL;
+END;
+$$ LANGUAGE plpgsql;
+";
+
+/// One row change reported by `turbograph_notify_change()`.
+#[derive(Debug, Clone, Deserialize)]
+pub(crate) struct ChangeEvent {
+    pub schema: String,
+    pub table: String,
+    /// `INSERT`, `UPDATE` or `DELETE`.
+    pub op: String,
+    /// Primary key values of the row, in key order.
+    pub key: Vec<serde_json::Value>,
+}
+
+/// A notification on a [`TOPIC_PREFIX`] channel.
+#[derive(Debug, Clone)]
+pub(crate) struct TopicMessage {
+    /// The channel without its prefix.
+    pub topic: String,
+    pub payload: String,
+}
+
+/// Fans the notifications received on the LISTEN connection out to every
+/// running subscription. The connection closes once the last clone is
+/// dropped.
+#[derive(Clone)]
+pub(crate) struct ChangeFeed {
+    tx: broadcast::Sender<Arc<ChangeEvent>>,
+    topics: broadcast::Sender<Arc<TopicMessage>>,
+    /// Topics the connection LISTENs to. A topic stays subscribed once the
+    /// first `listen` asked for it.
+    listening: Arc<Mutex<HashSet<String>>>,
+    client: Arc<tokio_postgres::Client>,
+}
+
+impl ChangeFeed {
+    pub fn subscribe(&self) -> broadcast::Receiver<Arc<ChangeEvent>> {
+        self.tx.subscribe()
+    }
+
+    /// Receives the notifications on `topic`'s channel, LISTENing to it
+    /// first if no subscription has yet.
+    pub async fn listen(
+        &self,
+        topic: &str,
+    ) -> Result<broadcast::Receiver<Arc<TopicMessage>>, async_graphql::Error> {
+        let channel = format!("{TOPIC_PREFIX}{topic}");
+        if topic.is_empty() || channel.len() > MAX_CHANNEL_LEN {
+            return Err(crate::error::gql_err(format!(
+                "topic must be 1 to {} bytes long",
+                MAX_CHANNEL_LEN - TOPIC_PREFIX.len()
+            )));
+        }
+        // Subscribing before the LISTEN completes loses nothing sent after it.
+        let messages = self.topics.subscribe();
+        let first = self.listening.lock().unwrap().insert(topic.to_string());
+        if first
+            && let Err(e) = self
+                .client
+                .batch_execute(&format!("LISTEN {}", quote_ident(&channel)))
+                .await
+        {
+            self.listening.lock().unwrap().remove(topic);
+            return Err(crate::error::db_err("LISTEN error", e));
+        }
+        Ok(messages)
+    }
+}
+
+/// Installs `turbograph_notify_change()` and attaches it to every
+/// `@subscribe` table of `catalog`. Re-running replaces existing triggers.
+pub(crate) async fn install_change_triggers(
+    pool: &Pool,
+    catalog: &Catalog,
+) -> Result<(), Box<dyn std::error::Error + Send + Sync>> {
+    let mut sql = CHANGE_FUNCTION_SQL.to_string();
+    for table in catalog.tables().filter(|t| t.subscribe()) {
+        let target = quote_qualified(table.schema_name(), table.name());
+        let key = table
+            .primary_key()
+            .iter()
+            .map(|name| format!("'{}'", name.replace('\'', "''")))
+            .collect::<Vec<_>>()
+            .join(", ");
+        sql.push_str(&format!(
+            "DROP TRIGGER IF EXISTS turbograph_change ON {target};
+            CREATE TRIGGER turbograph_change AFTER INSERT OR UPDATE OR DELETE ON {target}
+              FOR EACH ROW EXECUTE FUNCTION turbograph_notify_change({key});\n"
+        ));
+    }
+
+    let client = pool.get().await?;
+    client.batch_execute(&sql).await?;
+    Ok(())
+}
+
+/// Opens a dedicated connection that LISTENs on `turbograph_change` and
+/// forwards every event to the returned feed, along with the notifications
+/// of the topics [`ChangeFeed::listen`] adds.
+pub(crate) async fn start_listening(
+    connection_url: &str,
+) -> Result<ChangeFeed, Box<dyn std::error::Error + Send + Sync>> {
+    let (client, mut connection) =
+        tokio_postgres::connect(connection_url, tokio_postgres::NoTls).await?;
+    let (tx, _) = broadcast::channel(1024);
+    let (topics, _) = broadcast::channel(1024);
+
+    let forward_tx = tx.clone();
+    let forward_topics = topics.clone();
+    tokio::spawn(async move {
+        loop {
+            match std::future::poll_fn(|cx| connection.poll_message(cx)).await {
+                Some(Ok(AsyncMessage::Notification(n))) if n.channel() == CHANGE_CHANNEL => {
+                    match serde_json::from_str::<ChangeEvent>(n.payload()) {
+                        // No receivers just means no subscription is running.
+                        Ok(event) => _ = forward_tx.send(Arc::new(event)),
+                        Err(e) => eprintln!("[turbograph] malformed change event: {e}"),
+                    }
+                }
+                Some(Ok(AsyncMessage::Notification(n))) => {
+                    if let Some(topic) = n.channel().strip_prefix(TOPIC_PREFIX) {
+                        _ = forward_topics.send(Arc::new(TopicMessage {
+                            topic: topic.to_string(),
+                            payload: n.payload().to_string(),
+                        }));
+                    }
+                }
+                Some(Ok(_)) => {}
+                Some(Err(e)) => {
+                    eprintln!("[turbograph] change feed connection error: {e}");
+                    break;
+                }
+                None => break,
+            }
+        }
+    });
+
+    client
+        .batch_execute(&format!("LISTEN {CHANGE_CHANNEL}"))
+        .await?;
+
+    Ok(ChangeFeed {
+        tx,
+        topics,
+        listening: Arc::default(),
+        client: Arc::new(client),
+    })
+}
@@ -0,0 +1,131 @@
+use std::sync::Arc;
+use std::sync::atomic::{AtomicBool, AtomicU32, Ordering};
+
+use async_graphql::ErrorExtensions;
+use deadpool_postgres::Pool;
+
+use crate::error::coded_err;
+use crate::models::config::CircuitBreakerConfig;
+
+/// `code` extension of errors returned while the breaker is open.
+pub(crate) const SERVICE_UNAVAILABLE: &str = "SERVICE_UNAVAILABLE";
+
+/// Counts consecutive failures to reach the database and, once
+/// [`CircuitBreakerConfig::failure_threshold`] is hit, opens: requests then
+/// fail at once instead of queueing on the pool, while a background probe
+/// retries every [`CircuitBreakerConfig::probe_interval`] and closes the
+/// breaker as soon as the database answers. Shared by every schema built for
+/// the pool.
+pub(crate) struct CircuitBreaker {
+    config: CircuitBreakerConfig,
+    /// Where the probe checks for recovery.
+    pool: Arc<Pool>,
+    failures: AtomicU32,
+    open: AtomicBool,
+}
+
+impl CircuitBreaker {
+    pub fn new(config: CircuitBreakerConfig, pool: Arc<Pool>) -> Self {
+        Self {
+            config,
+            pool,
+            failures: AtomicU32::new(0),
+            open: AtomicBool::new(false),
+        }
+    }
+
+    pub fn is_open(&self) -> bool {
+        self.open.load(Ordering::Acquire)
+    }
+
+    /// The `Retry-After` hint in whole seconds: the probe interval, rounded
+    /// up, as the breaker cannot close sooner.
+    pub fn retry_after(&self) -> u64 {
+        let interval = self.config.probe_interval;
+        (interval.as_secs() + u64::from(interval.subsec_nanos() > 0)).max(1)
+    }
+
+    /// The `SERVICE_UNAVAILABLE` error, with the `retryAfter` hint, while the
+    /// breaker is open.
+    pub fn check(&self) -> Result<(), async_graphql::Error> {
+        if !self.is_open() {
+            return Ok(());
+        }
+        let retry_after = self.retry_after();
+        Err(coded_err(
+            SERVICE_UNAVAILABLE,
+            "the database is unavailable; retry later",
+        )
+        .extend_with(|_, ext| ext.set("retryAfter", retry_after)))
+    }
+
+    /// The database was reached, which ends a run of failures.
+    pub fn record_success(&self) {
+        self.failures.store(0, Ordering::Relaxed);
+    }
+
+    /// The database could not be reached. Opens the breaker and starts the
+    /// probe on the failure that hits the threshold.
+    pub fn record_failure(self: &Arc<Self>) {
+        let failures = self.failures.fetch_add(1, Ordering::Relaxed) + 1;
+        if failures >= self.config.failure_threshold
+            && self
+                .open
+                .compare_exchange(false, true, Ordering::AcqRel, Ordering::Acquire)
+                .is_ok()
+        {
+            eprintln!(
+                "[turbograph] {failures} consecutive database connection failures; \
+                 failing requests fast until it is reachable again"
+            );
+            tokio::spawn(self.clone().probe());
+        }
+    }
+
+    async fn probe(self: Arc<Self>) {
+        loop {
+            tokio::time::sleep(self.config.probe_interval).await;
+            if let Ok(client) = self.pool.get().await
+                && client.simple_query("SELECT 1").await.is_ok()
+            {
+                break;
+            }
+        }
+        self.failures.store(0, Ordering::Relaxed);
+        self.open.store(false, Ordering::Release);
+        eprintln!("[turbograph] the database is reachable again");
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use super::*;
+
+    #[tokio::test]
+    async fn test_opens_after_consecutive_failures() {
+        let pool = Arc::new(crate::db::pool::unconnected().unwrap());
+        let breaker = Arc::new(CircuitBreaker::new(
+            CircuitBreakerConfig {
+                failure_threshold: 2,
+                probe_interval: Duration::from_millis(1500),
+            },
+            pool,
+        ));
+
+        breaker.record_failure();
+        breaker.record_success();
+        breaker.record_failure();
+        assert!(breaker.check().is_ok());
+
+        breaker.record_failure();
+        let err = breaker.check().unwrap_err();
+        let ext = err.extensions.unwrap();
+        assert_eq!(
+            ext.get("code"),
+            Some(&async_graphql::Value::from(SERVICE_UNAVAILABLE))
+        );
+        assert_eq!(ext.get("retryAfter"), Some(&async_graphql::Value::from(2)));
+    }
+}
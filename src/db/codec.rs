@@ -0,0 +1,240 @@
+//! The binary formats of `numeric` and `uuid`, which `tokio_postgres` only
+//! maps to Rust types behind extra crates. Values travel as text in GraphQL
+//! (`BigFloat`, `UUID`), so they are converted here without loss.
+
+use bytes::{BufMut, BytesMut};
+
+const NUMERIC_POS: u16 = 0x0000;
+const NUMERIC_NEG: u16 = 0x4000;
+const NUMERIC_NAN: u16 = 0xC000;
+const NUMERIC_PINF: u16 = 0xD000;
+const NUMERIC_NINF: u16 = 0xF000;
+
+/// A `numeric` value in its wire form: base-10000 digits, the power of
+/// 10000 of the first one, the sign and the number of decimal places.
+#[derive(Debug, PartialEq)]
+pub(crate) struct Numeric {
+    digits: Vec<i16>,
+    weight: i16,
+    sign: u16,
+    dscale: u16,
+}
+
+impl Numeric {
+    /// Parses decimal text such as `-12.50`, keeping its scale, or `NaN`,
+    /// `Infinity` and `-Infinity`.
+    pub(crate) fn parse(text: &str) -> Option<Self> {
+        let special = |sign| Self {
+            digits: Vec::new(),
+            weight: 0,
+            sign,
+            dscale: 0,
+        };
+        match text {
+            "NaN" => return Some(special(NUMERIC_NAN)),
+            "Infinity" => return Some(special(NUMERIC_PINF)),
+            "-Infinity" => return Some(special(NUMERIC_NINF)),
+            _ => {}
+        }
+
+        let (sign, unsigned) = match text.strip_prefix('-') {
+            Some(rest) => (NUMERIC_NEG, rest),
+            None => (NUMERIC_POS, text.strip_prefix('+').unwrap_or(text)),
+        };
+        let (int_part, frac_part) = unsigned.split_once('.').unwrap_or((unsigned, ""));
+        if int_part.is_empty() && frac_part.is_empty()
+            || !int_part
+                .bytes()
+                .chain(frac_part.bytes())
+                .all(|b| b.is_ascii_digit())
+        {
+            return None;
+        }
+
+        let int_part = int_part.trim_start_matches('0');
+        let int_groups = int_part.len().div_ceil(4);
+        let mut decimal = "0".repeat(int_groups * 4 - int_part.len());
+        decimal.push_str(int_part);
+        decimal.push_str(frac_part);
+        decimal.push_str(&"0".repeat((4 - frac_part.len() % 4) % 4));
+
+        let mut digits: Vec<i16> = decimal
+            .as_bytes()
+            .chunks(4)
+            .map(|group| std::str::from_utf8(group).unwrap().parse().unwrap())
+            .collect();
+        let mut weight = int_groups as i16 - 1;
+        let leading = digits.iter().take_while(|&&d| d == 0).count();
+        digits.drain(..leading);
+        weight -= leading as i16;
+        while digits.last() == Some(&0) {
+            digits.pop();
+        }
+
+        let dscale = u16::try_from(frac_part.len()).ok()?;
+        if digits.is_empty() {
+            return Some(Self {
+                digits,
+                weight: 0,
+                sign: NUMERIC_POS,
+                dscale,
+            });
+        }
+        Some(Self {
+            digits,
+            weight,
+            sign,
+            dscale,
+        })
+    }
+
+    pub(crate) fn write(&self, out: &mut BytesMut) {
+        out.put_i16(self.digits.len() as i16);
+        out.put_i16(self.weight);
+        out.put_u16(self.sign);
+        out.put_u16(self.dscale);
+        for &digit in &self.digits {
+            out.put_i16(digit);
+        }
+    }
+}
+
+/// Decodes a `numeric` value to its decimal text, with as many decimal
+/// places as Postgres shows.
+pub(crate) fn decode_numeric(mut raw: &[u8]) -> Option<String> {
+    let mut read = || -> Option<u16> {
+        let (bytes, rest) = raw.split_first_chunk::<2>()?;
+        raw = rest;
+        Some(u16::from_be_bytes(*bytes))
+    };
+    let ndigits = read()? as usize;
+    let weight = read()? as i16 as i32;
+    let sign = read()?;
+    let dscale = read()? as usize;
+    let digits = (0..ndigits).map(|_| read()).collect::<Option<Vec<u16>>>()?;
+
+    match sign {
+        NUMERIC_NAN => return Some("NaN".to_string()),
+        NUMERIC_PINF => return Some("Infinity".to_string()),
+        NUMERIC_NINF => return Some("-Infinity".to_string()),
+        _ => {}
+    }
+    let digit = |i: i32| {
+        usize::try_from(i)
+            .ok()
+            .and_then(|i| digits.get(i))
+            .copied()
+            .unwrap_or(0)
+    };
+
+    let mut text = String::new();
+    if sign == NUMERIC_NEG {
+        text.push('-');
+    }
+    if weight < 0 {
+        text.push('0');
+    } else {
+        text.push_str(&digit(0).to_string());
+        for i in 1..=weight {
+            text.push_str(&format!("{:04}", digit(i)));
+        }
+    }
+    if dscale > 0 {
+        let mut fraction = String::new();
+        let mut i = weight + 1;
+        while fraction.len() < dscale {
+            fraction.push_str(&format!("{:04}", digit(i)));
+            i += 1;
+        }
+        fraction.truncate(dscale);
+        text.push('.');
+        text.push_str(&fraction);
+    }
+    Some(text)
+}
+
+/// Parses a UUID written as 32 hex digits, optionally hyphenated.
+pub(crate) fn parse_uuid(text: &str) -> Option<[u8; 16]> {
+    let hex: Vec<u8> = text.bytes().filter(|&b| b != b'-').collect();
+    if hex.len() != 32 || text.len() - hex.len() > 4 {
+        return None;
+    }
+    let mut bytes = [0; 16];
+    for (byte, pair) in bytes.iter_mut().zip(hex.chunks(2)) {
+        *byte = u8::from_str_radix(std::str::from_utf8(pair).ok()?, 16).ok()?;
+    }
+    Some(bytes)
+}
+
+/// Formats a `uuid` value in its usual hyphenated form.
+pub(crate) fn format_uuid(raw: &[u8]) -> Option<String> {
+    let bytes: &[u8; 16] = raw.try_into().ok()?;
+    let hex: String = bytes.iter().map(|b| format!("{b:02x}")).collect();
+    Some(format!(
+        "{}-{}-{}-{}-{}",
+        &hex[..8],
+        &hex[8..12],
+        &hex[12..16],
+        &hex[16..20],
+        &hex[20..]
+    ))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn round_trip(text: &str) -> String {
+        let mut out = BytesMut::new();
+        Numeric::parse(text).unwrap().write(&mut out);
+        decode_numeric(&out).unwrap()
+    }
+
+    #[test]
+    fn test_numeric_round_trip() {
+        for text in [
+            "0",
+            "1",
+            "-1",
+            "12.50",
+            "10000",
+            "123456789012345678901234567890.000000001",
+            "0.0001",
+            "-0.00012300",
+            "NaN",
+            "Infinity",
+            "-Infinity",
+        ] {
+            assert_eq!(round_trip(text), text);
+        }
+        assert_eq!(round_trip("007.10"), "7.10");
+        assert_eq!(round_trip("-0.00"), "0.00");
+        assert_eq!(round_trip(".5"), "0.5");
+    }
+
+    #[test]
+    fn test_numeric_wire_form() {
+        assert_eq!(
+            Numeric::parse("-12345.678").unwrap(),
+            Numeric {
+                digits: vec![1, 2345, 6780],
+                weight: 1,
+                sign: NUMERIC_NEG,
+                dscale: 3,
+            }
+        );
+        assert_eq!(Numeric::parse("1e5"), None);
+        assert_eq!(Numeric::parse("."), None);
+        assert_eq!(Numeric::parse(""), None);
+    }
+
+    #[test]
+    fn test_uuid() {
+        let text = "a0eebc99-9c0b-4ef8-bb6d-6bb9bd380a11";
+        let bytes = parse_uuid(text).unwrap();
+        assert_eq!(format_uuid(&bytes).unwrap(), text);
+        assert_eq!(parse_uuid("A0EEBC999C0B4EF8BB6D6BB9BD380A11"), Some(bytes));
+        assert_eq!(parse_uuid("a0eebc99"), None);
+        assert_eq!(parse_uuid("z0eebc99-9c0b-4ef8-bb6d-6bb9bd380a11"), None);
+    }
+}
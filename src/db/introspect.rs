@@ -1,10 +1,35 @@
-use crate::models::table::{Column, Table};
+use crate::models::catalog::Catalog;
+use crate::models::config::IntrospectionConfig;
+use crate::models::foreign_key::ForeignKey;
+use crate::models::function::Function;
+use crate::models::procedure::Procedure;
+use crate::models::table::{Column, Omit, Table, UniqueKey};
+use crate::models::types::TypeRegistry;
+use crate::sql::{quote_ident, quote_qualified};
 use std::collections::HashMap;
 
-fn map_columns_to_table(tables: Vec<Table>, columns: Vec<Column>) -> Vec<Table> {
+/// `role_names`, every role a privilege can be checked for: `public` and the
+/// roles that are not predefined `pg_*` ones. Prepended to the queries that
+/// list the roles holding a privilege.
+///
+/// Privileges are checked by role OID, as a role dropped while the query
+/// runs would fail a check by name. OID 0 stands for `PUBLIC` in ACLs, and
+/// the checks treat it so.
+const ROLE_NAMES: &str = "WITH role_names AS (
+    SELECT 'public' AS rolname, 0::oid AS roloid
+    UNION ALL
+    SELECT rolname::text, oid FROM pg_catalog.pg_roles WHERE rolname !~ '^pg_'
+)";
+
+fn map_columns_to_table(
+    tables: Vec<Table>,
+    columns: Vec<Column>,
+    primary_keys: Vec<(u32, Vec<String>)>,
+    unique_keys: Vec<(u32, UniqueKey)>,
+) -> Vec<Table> {
     let mut table_map: HashMap<u32, Table> = tables
         .into_iter()
-        .map(|table| (table.oid().clone(), table))
+        .map(|table| (*table.oid(), table))
         .collect();
 
     for col in columns.into_iter() {
@@ -13,34 +38,218 @@ fn map_columns_to_table(tables: Vec<Table>, columns: Vec<Column>) -> Vec<Table>
         }
     }
 
+    for (table_oid, key) in primary_keys {
+        if let Some(table) = table_map.get_mut(&table_oid) {
+            table.set_primary_key(key);
+        }
+    }
+
+    for (table_oid, key) in unique_keys {
+        if let Some(table) = table_map.get_mut(&table_oid) {
+            table.push_unique_key(key);
+        }
+    }
+
+    for table in table_map.values_mut() {
+        table.apply_primary_key_tag();
+    }
+
     table_map.into_values().collect()
 }
 
-pub async fn get_tables(pool: &deadpool_postgres::Pool, schemas: &[String]) -> Vec<Table> {
-    let client = pool.get().await.unwrap();
-    let tables: Vec<Table> = client
-        .query(
-            "SELECT 
+/// Introspects every table, view, materialized view and foreign table, and
+/// every stored procedure and function, selected by `config` into an
+/// immutable [`Catalog`]. Partitioned tables are exposed as one table;
+/// their partitions are left out.
+///
+/// User-defined types are loaded first so that column and argument types can
+/// be resolved. The other catalog queries are independent of each other, so
+/// each one runs concurrently on its own pooled connection and the results
+/// are stitched together by table OID afterwards.
+pub async fn get_catalog(
+    pool: &deadpool_postgres::Pool,
+    config: &IntrospectionConfig,
+) -> Result<Catalog, Box<dyn std::error::Error + Send + Sync>> {
+    let types = fetch_types(pool).await?;
+    let (tables, columns, primary_keys, unique_keys, foreign_keys, procedures, functions) = tokio::try_join!(
+        fetch_tables(pool, config),
+        fetch_columns(pool, config, &types),
+        fetch_primary_keys(pool, config),
+        fetch_unique_keys(pool, config),
+        fetch_foreign_keys(pool, config),
+        fetch_procedures(pool, config, &types),
+        fetch_functions(pool, config, &types),
+    )?;
+
+    let mut tables = map_columns_to_table(tables, columns, primary_keys, unique_keys);
+    fetch_enum_values(pool, &mut tables).await?;
+
+    Ok(Catalog::new(tables)
+        .with_foreign_keys(foreign_keys)
+        .with_procedures(procedures)
+        .with_functions(functions))
+}
+
+/// Loads the primary key values of every `@enum` table; they become the
+/// items of its GraphQL enum.
+async fn fetch_enum_values(
+    pool: &deadpool_postgres::Pool,
+    tables: &mut [Table],
+) -> Result<(), Box<dyn std::error::Error + Send + Sync>> {
+    let client = pool.get().await?;
+    for table in tables.iter_mut().filter(|t| t.is_enum()) {
+        let sql = format!(
+            "SELECT {}::text FROM {} ORDER BY 1",
+            quote_ident(&table.primary_key()[0]),
+            quote_qualified(table.schema_name(), table.name())
+        );
+        let values = client
+            .query(&sql, &[])
+            .await?
+            .iter()
+            .map(|row| row.get::<_, String>(0))
+            .collect();
+        table.set_enum_values(values);
+    }
+    Ok(())
+}
+
+async fn fetch_tables(
+    pool: &deadpool_postgres::Pool,
+    config: &IntrospectionConfig,
+) -> Result<Vec<Table>, Box<dyn std::error::Error + Send + Sync>> {
+    let client = pool.get().await?;
+    let sql = format!(
+        "{ROLE_NAMES}
+            SELECT 
                 c.oid, 
                 n.nspname AS schema_name,
                 c.relname AS table_name,
                 c.relkind::text,
-                pg_catalog.obj_description(c.oid, 'pg_class') AS comment
+                pg_catalog.obj_description(c.oid, 'pg_class') AS comment,
+                pg_catalog.obj_description(n.oid, 'pg_namespace') AS schema_comment,
+                c.reltuples::float8 AS estimated_rows,
+                ARRAY(SELECT r.rolname FROM role_names r
+                    WHERE pg_catalog.has_any_column_privilege(r.roloid, c.oid, 'SELECT') ORDER BY 1) AS select_roles,
+                ARRAY(SELECT r.rolname FROM role_names r
+                    WHERE pg_catalog.has_any_column_privilege(r.roloid, c.oid, 'INSERT') ORDER BY 1) AS insert_roles,
+                ARRAY(SELECT r.rolname FROM role_names r
+                    WHERE pg_catalog.has_any_column_privilege(r.roloid, c.oid, 'UPDATE') ORDER BY 1) AS update_roles,
+                ARRAY(SELECT r.rolname FROM role_names r
+                    WHERE pg_catalog.has_table_privilege(r.roloid, c.oid, 'DELETE') ORDER BY 1) AS delete_roles,
+                pg_catalog.pg_relation_is_updatable(c.oid, true) & 8 <> 0 AS insertable,
+                pg_catalog.pg_relation_is_updatable(c.oid, true) & 4 <> 0 AS updatable,
+                pg_catalog.pg_relation_is_updatable(c.oid, true) & 16 <> 0 AS deletable,
+                ARRAY(SELECT DISTINCT a.attname::text
+                    FROM pg_catalog.pg_index i
+                    JOIN pg_catalog.pg_class ic ON ic.oid = i.indexrelid
+                    JOIN pg_catalog.pg_am am ON am.oid = ic.relam
+                    JOIN pg_catalog.pg_attribute a
+                        ON a.attrelid = i.indrelid AND a.attnum = i.indkey[0]
+                    WHERE i.indrelid = c.oid
+                    AND i.indisvalid
+                    AND i.indpred IS NULL
+                    AND am.amname = 'btree'
+                    ORDER BY 1) AS indexed_columns,
+                ARRAY(SELECT DISTINCT a.attname::text
+                    FROM pg_catalog.pg_index i
+                    CROSS JOIN LATERAL unnest(i.indkey::int2[], i.indclass::oid[]) k(attnum, opclass)
+                    JOIN pg_catalog.pg_opclass op ON op.oid = k.opclass
+                    JOIN pg_catalog.pg_attribute a
+                        ON a.attrelid = i.indrelid AND a.attnum = k.attnum
+                    WHERE i.indrelid = c.oid
+                    AND i.indisvalid
+                    AND i.indpred IS NULL
+                    AND op.opcname IN ('gin_trgm_ops', 'gist_trgm_ops')
+                    ORDER BY 1) AS trigram_indexed_columns,
+                ARRAY(SELECT DISTINCT a.attname::text
+                    FROM pg_catalog.pg_index i
+                    JOIN pg_catalog.pg_opclass op ON op.oid = i.indclass[0]
+                    JOIN pg_catalog.pg_attribute a
+                        ON a.attrelid = i.indrelid AND a.attnum = i.indkey[0]
+                    WHERE i.indrelid = c.oid
+                    AND i.indisvalid
+                    AND i.indpred IS NULL
+                    AND op.opcname IN ('text_pattern_ops', 'varchar_pattern_ops', 'bpchar_pattern_ops')
+                    ORDER BY 1) AS prefix_indexed_columns
             FROM pg_catalog.pg_class c
             JOIN pg_catalog.pg_namespace n ON n.oid = c.relnamespace     -- To filter schema
             WHERE n.nspname = ANY($1)
-            AND c.relkind IN ('r', 'm')
-            ORDER BY n.nspname, c.relname;",
-            &[&schemas],
+            AND c.relkind IN ('r', 'p', 'v', 'm', 'f')
+            AND NOT c.relispartition
+            AND ($2 OR NOT EXISTS (SELECT 1 FROM pg_catalog.pg_depend d
+                WHERE d.classid = 'pg_catalog.pg_class'::regclass
+                AND d.objid = c.oid
+                AND d.deptype = 'e'))
+            ORDER BY n.nspname, c.relname;"
+    );
+    let tables = client
+        .query(
+            &sql,
+            &[&config.schemas, &config.include_extension_resources],
         )
-        .await
-        .unwrap()
+        .await?
         .iter()
-        .map(|r| Table::from_row(r))
+        .filter(|row| {
+            // `@omit` on the schema comment hides every relation inside it.
+            let schema_comment = row.try_get::<_, String>(5).unwrap_or_default();
+            !Omit::new(&schema_comment).read()
+        })
+        .map(Table::from_row)
+        .filter(|table| config.exposes_table(table.schema_name(), table.name()))
         .collect();
 
-    let table_oids = tables.iter().map(|t| t.oid()).collect::<Vec<&u32>>();
+    Ok(tables)
+}
+
+/// Every user-defined enum, domain, standalone composite and base type (such
+/// as an extension's `citext`), and their arrays.
+async fn fetch_types(
+    pool: &deadpool_postgres::Pool,
+) -> Result<TypeRegistry, Box<dyn std::error::Error + Send + Sync>> {
+    let client = pool.get().await?;
+    let rows = client
+        .query(
+            "SELECT
+                t.oid,
+                n.nspname::text,
+                t.typname::text,
+                t.typtype::text,
+                t.typbasetype,
+                CASE WHEN t.typcategory = 'A' THEN t.typelem ELSE 0::oid END AS element,
+                ARRAY(SELECT e.enumlabel::text
+                    FROM pg_catalog.pg_enum e
+                    WHERE e.enumtypid = t.oid
+                    ORDER BY e.enumsortorder) AS labels,
+                ARRAY(SELECT a.attname::text
+                    FROM pg_catalog.pg_attribute a
+                    WHERE a.attrelid = t.typrelid AND a.attnum > 0 AND NOT a.attisdropped
+                    ORDER BY a.attnum) AS field_names,
+                ARRAY(SELECT a.atttypid
+                    FROM pg_catalog.pg_attribute a
+                    WHERE a.attrelid = t.typrelid AND a.attnum > 0 AND NOT a.attisdropped
+                    ORDER BY a.attnum) AS field_types
+            FROM pg_catalog.pg_type t
+            JOIN pg_catalog.pg_namespace n ON n.oid = t.typnamespace
+            LEFT JOIN pg_catalog.pg_class c ON c.oid = t.typrelid
+            WHERE t.oid >= 16384
+            AND t.typtype IN ('b', 'c', 'd', 'e')
+            AND (t.typtype <> 'c' OR c.relkind = 'c');",
+            &[],
+        )
+        .await?;
+
+    Ok(TypeRegistry::from_rows(&rows))
+}
 
+/// Columns of the exposed tables. Those whose type cannot be resolved (e.g.
+/// a range) are skipped with a warning.
+async fn fetch_columns(
+    pool: &deadpool_postgres::Pool,
+    config: &IntrospectionConfig,
+    types: &TypeRegistry,
+) -> Result<Vec<Column>, Box<dyn std::error::Error + Send + Sync>> {
+    let client = pool.get().await?;
     let columns = client
         .query(
             "SELECT 
@@ -50,22 +259,408 @@ pub async fn get_tables(pool: &deadpool_postgres::Pool, schemas: &[String]) -> V
                 a.atttypid AS type_oid, 
                 NOT a.attnotnull AS nullable,
                 a.atthasdef AS has_default,
-                pg_catalog.col_description(a.attrelid, a.attnum) AS comment
+                pg_catalog.col_description(a.attrelid, a.attnum) AS comment,
+                a.attidentity::text AS identity,
+                a.attgenerated::text AS generated,
+                pg_catalog.pg_get_expr(ad.adbin, ad.adrelid) AS default_expr,
+                c.relkind <> 'v'
+                    OR pg_catalog.pg_column_is_updatable(a.attrelid, a.attnum, false) AS updatable
             FROM 
                 pg_catalog.pg_attribute a
+            JOIN pg_catalog.pg_class c ON c.oid = a.attrelid
+            LEFT JOIN pg_catalog.pg_attrdef ad
+                ON ad.adrelid = a.attrelid AND ad.adnum = a.attnum
+            JOIN pg_catalog.pg_namespace n ON n.oid = c.relnamespace
             WHERE 
-                a.attrelid = ANY($1)              -- Your Table OID
+                n.nspname = ANY($1)
+                AND c.relkind IN ('r', 'p', 'v', 'm', 'f')
+                AND NOT c.relispartition
+                AND ($2 OR NOT EXISTS (SELECT 1 FROM pg_catalog.pg_depend d
+                    WHERE d.classid = 'pg_catalog.pg_class'::regclass
+                    AND d.objid = c.oid
+                    AND d.deptype = 'e'))
                 AND a.attnum > 0 
                 AND NOT a.attisdropped
             ORDER BY 
                 a.attnum;",
-            &[&table_oids],
+            &[&config.schemas, &config.include_extension_resources],
         )
-        .await
-        .unwrap()
+        .await?
         .iter()
-        .map(|r| Column::form_row(r))
-        .collect::<Vec<Column>>();
+        .filter_map(|row| {
+            let column = Column::form_row(row, types);
+            if column.is_none() {
+                eprintln!(
+                    "[turbograph] skipping column {}: unsupported type oid {}",
+                    row.get::<_, String>(2),
+                    row.get::<_, u32>(3)
+                );
+            }
+            column
+        })
+        .collect();
+
+    Ok(columns)
+}
+
+/// Primary key columns (in key order) of every table in the exposed schemas,
+/// keyed by table OID.
+async fn fetch_primary_keys(
+    pool: &deadpool_postgres::Pool,
+    config: &IntrospectionConfig,
+) -> Result<Vec<(u32, Vec<String>)>, Box<dyn std::error::Error + Send + Sync>> {
+    let client = pool.get().await?;
+    let primary_keys = client
+        .query(
+            "SELECT
+                con.conrelid,
+                ARRAY(SELECT a.attname::text
+                    FROM unnest(con.conkey) WITH ORDINALITY k(attnum, ord)
+                    JOIN pg_catalog.pg_attribute a
+                        ON a.attrelid = con.conrelid AND a.attnum = k.attnum
+                    ORDER BY k.ord) AS columns
+            FROM pg_catalog.pg_constraint con
+            JOIN pg_catalog.pg_namespace n ON n.oid = con.connamespace
+            WHERE con.contype = 'p'
+            AND n.nspname = ANY($1);",
+            &[&config.schemas],
+        )
+        .await?
+        .iter()
+        .map(|row| (row.get(0), row.get(1)))
+        .collect();
 
-    map_columns_to_table(tables, columns)
+    Ok(primary_keys)
+}
+
+/// Unique indexes (including those behind primary key and `UNIQUE`
+/// constraints) of tables in the exposed schemas, keyed by table OID.
+/// Expression indexes are skipped: they cannot be matched by column values.
+/// The comment is the constraint's, or else the index's.
+async fn fetch_unique_keys(
+    pool: &deadpool_postgres::Pool,
+    config: &IntrospectionConfig,
+) -> Result<Vec<(u32, UniqueKey)>, Box<dyn std::error::Error + Send + Sync>> {
+    let client = pool.get().await?;
+    let unique_keys = client
+        .query(
+            "SELECT
+                i.indrelid,
+                ic.relname::text,
+                ARRAY(SELECT a.attname::text
+                    FROM unnest(i.indkey::int2[]) WITH ORDINALITY k(attnum, ord)
+                    JOIN pg_catalog.pg_attribute a
+                        ON a.attrelid = i.indrelid AND a.attnum = k.attnum
+                    WHERE k.ord <= i.indnkeyatts
+                    ORDER BY k.ord) AS columns,
+                i.indpred IS NOT NULL AS partial,
+                coalesce(pg_catalog.obj_description(con.oid, 'pg_constraint'),
+                    pg_catalog.obj_description(ic.oid, 'pg_class')) AS comment
+            FROM pg_catalog.pg_index i
+            JOIN pg_catalog.pg_class ic ON ic.oid = i.indexrelid
+            JOIN pg_catalog.pg_namespace n ON n.oid = ic.relnamespace
+            LEFT JOIN pg_catalog.pg_constraint con
+                ON con.conindid = i.indexrelid AND con.contype IN ('p', 'u')
+            WHERE i.indisunique
+            AND i.indisvalid
+            AND NOT 0 = ANY(i.indkey::int2[])
+            AND n.nspname = ANY($1)
+            ORDER BY ic.relname;",
+            &[&config.schemas],
+        )
+        .await?
+        .iter()
+        .map(|row| {
+            (
+                row.get(0),
+                UniqueKey::new(
+                    row.get(1),
+                    row.get(2),
+                    row.get(3),
+                    row.try_get::<_, String>(4).unwrap_or_default(),
+                ),
+            )
+        })
+        .collect();
+
+    Ok(unique_keys)
+}
+
+/// Foreign key constraints declared on tables in the exposed schemas. Those
+/// pointing at tables that did not make it into the catalog are dropped by
+/// [`Catalog::with_foreign_keys`].
+async fn fetch_foreign_keys(
+    pool: &deadpool_postgres::Pool,
+    config: &IntrospectionConfig,
+) -> Result<Vec<ForeignKey>, Box<dyn std::error::Error + Send + Sync>> {
+    let client = pool.get().await?;
+    let foreign_keys = client
+        .query(
+            "SELECT
+                con.oid,
+                con.conname::text,
+                con.conrelid,
+                ARRAY(SELECT a.attname::text
+                    FROM unnest(con.conkey) WITH ORDINALITY k(attnum, ord)
+                    JOIN pg_catalog.pg_attribute a
+                        ON a.attrelid = con.conrelid AND a.attnum = k.attnum
+                    ORDER BY k.ord) AS columns,
+                con.confrelid,
+                ARRAY(SELECT a.attname::text
+                    FROM unnest(con.confkey) WITH ORDINALITY k(attnum, ord)
+                    JOIN pg_catalog.pg_attribute a
+                        ON a.attrelid = con.confrelid AND a.attnum = k.attnum
+                    ORDER BY k.ord) AS foreign_columns,
+                pg_catalog.obj_description(con.oid, 'pg_constraint') AS comment,
+                con.confdeltype::text,
+                con.confupdtype::text
+            FROM pg_catalog.pg_constraint con
+            JOIN pg_catalog.pg_namespace n ON n.oid = con.connamespace
+            WHERE con.contype = 'f'
+            AND n.nspname = ANY($1);",
+            &[&config.schemas],
+        )
+        .await?
+        .iter()
+        .map(ForeignKey::from_row)
+        .collect();
+
+    Ok(foreign_keys)
+}
+
+/// Stored procedures (`prokind = 'p'`, Postgres 11+) in the exposed schemas.
+/// Those with arguments that cannot be mapped are skipped with a warning.
+async fn fetch_procedures(
+    pool: &deadpool_postgres::Pool,
+    config: &IntrospectionConfig,
+    types: &TypeRegistry,
+) -> Result<Vec<Procedure>, Box<dyn std::error::Error + Send + Sync>> {
+    let client = pool.get().await?;
+    let sql = format!(
+        "{ROLE_NAMES}
+            SELECT
+                p.oid,
+                n.nspname::text,
+                p.proname::text,
+                pg_catalog.obj_description(p.oid, 'pg_proc') AS comment,
+                coalesce(p.proallargtypes, p.proargtypes::oid[]) AS arg_types,
+                coalesce(p.proargmodes::text[],
+                    array_fill('i'::text, ARRAY[cardinality(p.proargtypes::oid[])])) AS arg_modes,
+                coalesce(p.proargnames, '{{}}') AS arg_names,
+                p.pronargdefaults,
+                pg_catalog.obj_description(n.oid, 'pg_namespace') AS schema_comment,
+                p.provolatile::text,
+                p.proisstrict,
+                p.proretset,
+                p.prosecdef,
+                ARRAY(SELECT r.rolname FROM role_names r
+                    WHERE pg_catalog.has_function_privilege(r.roloid, p.oid, 'EXECUTE') ORDER BY 1) AS execute_roles
+            FROM pg_catalog.pg_proc p
+            JOIN pg_catalog.pg_namespace n ON n.oid = p.pronamespace
+            WHERE p.prokind = 'p'
+            AND n.nspname = ANY($1)
+            AND ($2 OR NOT EXISTS (SELECT 1 FROM pg_catalog.pg_depend d
+                WHERE d.classid = 'pg_catalog.pg_proc'::regclass
+                AND d.objid = p.oid
+                AND d.deptype = 'e'))
+            ORDER BY n.nspname, p.proname;"
+    );
+    let procedures = client
+        .query(
+            &sql,
+            &[&config.schemas, &config.include_extension_resources],
+        )
+        .await?
+        .iter()
+        .filter_map(|row| match Procedure::from_row(row, types) {
+            Ok(procedure) => Some(procedure),
+            Err(reason) => {
+                eprintln!(
+                    "[turbograph] skipping procedure {}.{}: {reason}",
+                    row.get::<_, String>(1),
+                    row.get::<_, String>(2)
+                );
+                None
+            }
+        })
+        .collect();
+
+    Ok(procedures)
+}
+
+/// Functions (`prokind = 'f'`) in the exposed schemas, leaving out those
+/// returning a pseudo-type such as `trigger` other than `void` and `record`.
+/// Those with arguments or a return type that cannot be mapped are skipped
+/// with a warning.
+///
+/// Besides the columns of [`fetch_procedures`], each row has the return type,
+/// the table whose row type that is, and the table whose row the function
+/// takes first when its name starts with that table's name and `_`.
+async fn fetch_functions(
+    pool: &deadpool_postgres::Pool,
+    config: &IntrospectionConfig,
+    types: &TypeRegistry,
+) -> Result<Vec<Function>, Box<dyn std::error::Error + Send + Sync>> {
+    let client = pool.get().await?;
+    let sql = format!(
+        "{ROLE_NAMES}
+            SELECT
+                p.oid,
+                n.nspname::text,
+                p.proname::text,
+                pg_catalog.obj_description(p.oid, 'pg_proc') AS comment,
+                coalesce(p.proallargtypes, p.proargtypes::oid[]) AS arg_types,
+                coalesce(p.proargmodes::text[],
+                    array_fill('i'::text, ARRAY[cardinality(p.proargtypes::oid[])])) AS arg_modes,
+                coalesce(p.proargnames, '{{}}') AS arg_names,
+                p.pronargdefaults,
+                pg_catalog.obj_description(n.oid, 'pg_namespace') AS schema_comment,
+                p.provolatile::text,
+                p.proisstrict,
+                p.proretset,
+                p.prorettype,
+                (SELECT c.oid FROM pg_catalog.pg_class c
+                    WHERE c.reltype = p.prorettype AND c.relkind IN ('r', 'p', 'v', 'm', 'f')
+                    AND NOT c.relispartition) AS return_table,
+                (SELECT c.oid FROM pg_catalog.pg_class c
+                    WHERE c.reltype = p.proargtypes[0] AND c.relkind IN ('r', 'p', 'v', 'm', 'f')
+                    AND NOT c.relispartition
+                    AND left(p.proname, length(c.relname) + 1) = c.relname || '_') AS row_table,
+                ARRAY(SELECT r.rolname FROM role_names r
+                    WHERE pg_catalog.has_function_privilege(r.roloid, p.oid, 'EXECUTE') ORDER BY 1) AS execute_roles
+            FROM pg_catalog.pg_proc p
+            JOIN pg_catalog.pg_namespace n ON n.oid = p.pronamespace
+            JOIN pg_catalog.pg_type rt ON rt.oid = p.prorettype
+            WHERE p.prokind = 'f'
+            AND (rt.typtype <> 'p' OR rt.typname IN ('void', 'record'))
+            AND n.nspname = ANY($1)
+            AND ($2 OR NOT EXISTS (SELECT 1 FROM pg_catalog.pg_depend d
+                WHERE d.classid = 'pg_catalog.pg_proc'::regclass
+                AND d.objid = p.oid
+                AND d.deptype = 'e'))
+            ORDER BY n.nspname, p.proname;"
+    );
+    let functions = client
+        .query(
+            &sql,
+            &[&config.schemas, &config.include_extension_resources],
+        )
+        .await?
+        .iter()
+        .filter_map(|row| match Function::from_row(row, types) {
+            Ok(function) => Some(function),
+            Err(reason) => {
+                eprintln!(
+                    "[turbograph] skipping function {}.{}: {reason}",
+                    row.get::<_, String>(1),
+                    row.get::<_, String>(2)
+                );
+                None
+            }
+        })
+        .collect();
+
+    Ok(functions)
+}
+
+/// Computes a fingerprint of every catalog row that feeds introspection for
+/// `config` (relations and their updatability, columns, keys and indexes,
+/// routines, user-defined types, and their comments and grants).
+///
+/// Two equal fingerprints mean a rebuild would produce the same schema, so the
+/// watch loop can skip re-introspecting after DDL that does not affect us
+/// (e.g. changes in other schemas, new rows).
+pub(crate) async fn catalog_fingerprint(
+    pool: &deadpool_postgres::Pool,
+    config: &IntrospectionConfig,
+) -> Result<String, Box<dyn std::error::Error + Send + Sync>> {
+    let client = pool.get().await?;
+    let row = client
+        .query_one(
+            "SELECT coalesce(md5(string_agg(entry, ',' ORDER BY entry)), '')
+            FROM (
+                SELECT concat_ws(':',
+                    c.oid, n.nspname, c.relname, c.relkind, c.relacl,
+                    pg_catalog.pg_relation_is_updatable(c.oid, true),
+                    pg_catalog.obj_description(c.oid, 'pg_class'),
+                    pg_catalog.obj_description(n.oid, 'pg_namespace')
+                ) AS entry
+                FROM pg_catalog.pg_class c
+                JOIN pg_catalog.pg_namespace n ON n.oid = c.relnamespace
+                WHERE n.nspname = ANY($1)
+                AND c.relkind IN ('r', 'p', 'v', 'm', 'f')
+                AND NOT c.relispartition
+                AND ($2 OR NOT EXISTS (SELECT 1 FROM pg_catalog.pg_depend d
+                    WHERE d.classid = 'pg_catalog.pg_class'::regclass
+                    AND d.objid = c.oid
+                    AND d.deptype = 'e'))
+                UNION ALL
+                SELECT concat_ws(':',
+                    a.attrelid, a.attnum, a.attname, a.atttypid, a.attnotnull, a.atthasdef,
+                    a.attidentity, a.attgenerated, a.attacl,
+                    pg_catalog.col_description(a.attrelid, a.attnum)
+                )
+                FROM pg_catalog.pg_attribute a
+                JOIN pg_catalog.pg_class c ON c.oid = a.attrelid
+                JOIN pg_catalog.pg_namespace n ON n.oid = c.relnamespace
+                WHERE n.nspname = ANY($1)
+                AND c.relkind IN ('r', 'p', 'v', 'm', 'f')
+                AND NOT c.relispartition
+                AND ($2 OR NOT EXISTS (SELECT 1 FROM pg_catalog.pg_depend d
+                    WHERE d.classid = 'pg_catalog.pg_class'::regclass
+                    AND d.objid = c.oid
+                    AND d.deptype = 'e'))
+                AND a.attnum > 0
+                AND NOT a.attisdropped
+                UNION ALL
+                SELECT concat_ws(':',
+                    con.oid, con.conname, con.conrelid, con.conkey, con.confrelid, con.confkey,
+                    con.confdeltype, con.confupdtype,
+                    pg_catalog.obj_description(con.oid, 'pg_constraint')
+                )
+                FROM pg_catalog.pg_constraint con
+                JOIN pg_catalog.pg_namespace n ON n.oid = con.connamespace
+                WHERE con.contype IN ('f', 'p', 'u')
+                AND n.nspname = ANY($1)
+                UNION ALL
+                SELECT concat_ws(':',
+                    i.indexrelid, ic.relname, i.indrelid, i.indkey, i.indisvalid,
+                    i.indisunique, ic.relam, i.indpred IS NOT NULL,
+                    pg_catalog.obj_description(i.indexrelid, 'pg_class')
+                )
+                FROM pg_catalog.pg_index i
+                JOIN pg_catalog.pg_class ic ON ic.oid = i.indexrelid
+                JOIN pg_catalog.pg_namespace n ON n.oid = ic.relnamespace
+                WHERE n.nspname = ANY($1)
+                UNION ALL
+                SELECT concat_ws(':',
+                    p.oid, n.nspname, p.proname, p.proallargtypes, p.proargtypes,
+                    p.proargmodes, p.proargnames, p.pronargdefaults,
+                    p.provolatile, p.proisstrict, p.proretset, p.prosecdef, p.prorettype, p.proacl,
+                    pg_catalog.obj_description(p.oid, 'pg_proc'),
+                    pg_catalog.obj_description(n.oid, 'pg_namespace')
+                )
+                FROM pg_catalog.pg_proc p
+                JOIN pg_catalog.pg_namespace n ON n.oid = p.pronamespace
+                WHERE p.prokind IN ('p', 'f')
+                AND n.nspname = ANY($1)
+                UNION ALL
+                SELECT concat_ws(':',
+                    t.oid, t.typname, t.typtype, t.typbasetype,
+                    (SELECT string_agg(e.enumlabel, ',' ORDER BY e.enumsortorder)
+                        FROM pg_catalog.pg_enum e WHERE e.enumtypid = t.oid),
+                    (SELECT string_agg(a.attname || ' ' || a.atttypid::text, ',' ORDER BY a.attnum)
+                        FROM pg_catalog.pg_attribute a
+                        WHERE a.attrelid = t.typrelid AND a.attnum > 0 AND NOT a.attisdropped)
+                )
+                FROM pg_catalog.pg_type t
+                LEFT JOIN pg_catalog.pg_class c ON c.oid = t.typrelid
+                WHERE t.oid >= 16384
+                AND t.typtype IN ('c', 'd', 'e')
+                AND (t.typtype <> 'c' OR c.relkind = 'c')
+            ) entries;",
+            &[&config.schemas, &config.include_extension_resources],
+        )
+        .await?;
+    Ok(row.get(0))
 }
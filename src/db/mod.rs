@@ -1,5 +1,9 @@
+pub(crate) mod changes;
+pub(crate) mod circuit_breaker;
+pub(crate) mod codec;
 pub mod introspect;
 pub(crate) mod pool;
+pub(crate) mod request;
 pub mod row;
 pub(crate) mod transaction;
 pub(crate) mod watch;
@@ -1,5 +1,31 @@
+use std::sync::Arc;
+use std::sync::atomic::{AtomicU64, Ordering};
+
 use crate::models::config::PoolConfig;
 
+/// Counters about a pool that `deadpool` does not keep itself, reported by
+/// the `poolStatus` admin field. Shared by every schema built for the pool.
+#[derive(Debug, Default)]
+pub(crate) struct PoolMetrics {
+    timeouts: AtomicU64,
+}
+
+impl PoolMetrics {
+    /// Counts a connection checkout that gave up waiting for the pool.
+    pub fn record_timeout(&self) {
+        self.timeouts.fetch_add(1, Ordering::Relaxed);
+    }
+
+    pub fn timeouts(&self) -> u64 {
+        self.timeouts.load(Ordering::Relaxed)
+    }
+}
+
+/// The pool for [`ExecutionLane::Background`](crate::ExecutionLane::Background)
+/// requests, passed to resolvers as request data.
+#[derive(Clone)]
+pub(crate) struct BackgroundPool(pub Arc<deadpool_postgres::Pool>);
+
 /// Resolves a [`PoolConfig`] into a concrete `deadpool_postgres::Pool`.
 pub(crate) fn resolve(
     config: PoolConfig,
@@ -16,3 +42,17 @@ pub(crate) fn resolve(
         PoolConfig::Pool(pool) => Ok(pool),
     }
 }
+
+/// A pool that is never connected, for schemas whose resolvers are answered
+/// from [`MockData`](crate::models::mock::MockData) or only planned. Creating
+/// it does not touch the network.
+pub(crate) fn unconnected()
+-> Result<deadpool_postgres::Pool, Box<dyn std::error::Error + Send + Sync>> {
+    let mut cfg = deadpool_postgres::Config::new();
+    // Required by deadpool to build the pool; never used to connect.
+    cfg.dbname = Some("turbograph_mock".to_string());
+    Ok(cfg.create_pool(
+        Some(deadpool_postgres::Runtime::Tokio1),
+        tokio_postgres::NoTls,
+    )?)
+}
@@ -0,0 +1,427 @@
+use std::any::Any;
+use std::collections::HashMap;
+use std::future::Future;
+use std::sync::atomic::{AtomicU32, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+
+use async_graphql::dynamic::ResolverContext;
+use deadpool_postgres::Pool;
+use serde_json::{Map, Value};
+use tokio::sync::OnceCell;
+
+use crate::db::circuit_breaker::CircuitBreaker;
+use crate::db::pool::{BackgroundPool, PoolMetrics};
+use crate::graphql::scalars::CustomScalars;
+use crate::models::encryption::Encryption;
+use crate::models::row_transform::RowTransforms;
+use crate::models::table::Table;
+use crate::models::transaction::{ExecutionLane, TransactionConfig};
+
+/// Mutable per-request bookkeeping shared by every resolver of one GraphQL
+/// operation. Injected by [`TurboGraph::execute`](crate::TurboGraph::execute)
+/// and reported back through the response extensions.
+#[derive(Debug)]
+pub(crate) struct RequestState {
+    started: Instant,
+    retries: AtomicU32,
+    /// Every SQL statement sent for this request, when capture is enabled.
+    statements: Option<Mutex<Vec<String>>>,
+    /// Statements are captured but never sent; see [`PLANNED`].
+    planning: bool,
+    query_cache: QueryCache,
+    identity_map: IdentityMap,
+    /// Hints for the client, reported as `extensions.warnings`.
+    warnings: Mutex<Vec<String>>,
+}
+
+/// Error code of the transactions a planning request does not open. Each
+/// resolver fails with it right after logging its statements.
+pub(crate) const PLANNED: &str = "PLANNED";
+
+type CachedResult = Arc<dyn Any + Send + Sync>;
+
+/// Results of read statements already run for this request, keyed by SQL
+/// text and parameters. Each entry is a cell so that identical statements
+/// issued concurrently (e.g. repeated fragments) wait for the first one
+/// instead of all hitting the database.
+#[derive(Default)]
+struct QueryCache(Mutex<HashMap<String, Arc<OnceCell<CachedResult>>>>);
+
+impl std::fmt::Debug for QueryCache {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let entries = self.0.lock().map(|m| m.len()).unwrap_or_default();
+        f.debug_struct("QueryCache")
+            .field("entries", &entries)
+            .finish()
+    }
+}
+
+/// Rows of this request by table OID and primary key, with the columns
+/// fetched for them so far. An entity reached along several paths (a row and
+/// the same row through a relation, or a `node` refetch) then reads the same
+/// everywhere, and a row already fetched in full is not fetched again.
+#[derive(Default)]
+struct IdentityMap(Mutex<HashMap<(u32, String), Map<String, Value>>>);
+
+impl std::fmt::Debug for IdentityMap {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let entries = self.0.lock().map(|m| m.len()).unwrap_or_default();
+        f.debug_struct("IdentityMap")
+            .field("entries", &entries)
+            .finish()
+    }
+}
+
+/// The identity map key of the row of `table` with primary key `key`, `None`
+/// when the table has no primary key or a key value is `NULL`.
+fn identity_key(table: &Table, key: &[&Value]) -> Option<(u32, String)> {
+    if table.primary_key().is_empty() || key.iter().any(|value| value.is_null()) {
+        return None;
+    }
+    Some((*table.oid(), serde_json::to_string(key).ok()?))
+}
+
+impl Default for RequestState {
+    fn default() -> Self {
+        Self {
+            started: Instant::now(),
+            retries: AtomicU32::new(0),
+            statements: None,
+            planning: false,
+            query_cache: QueryCache::default(),
+            identity_map: IdentityMap::default(),
+            warnings: Mutex::default(),
+        }
+    }
+}
+
+impl RequestState {
+    /// Time since the request started executing.
+    pub fn elapsed(&self) -> Duration {
+        self.started.elapsed()
+    }
+
+    pub fn record_retry(&self) {
+        self.retries.fetch_add(1, Ordering::Relaxed);
+    }
+
+    pub fn retries(&self) -> u32 {
+        self.retries.load(Ordering::Relaxed)
+    }
+
+    /// Adds `message` to the response's `extensions.warnings`, once however
+    /// many resolvers report it.
+    pub fn warn(&self, message: &str) {
+        let mut warnings = self.warnings.lock().unwrap();
+        if !warnings.iter().any(|w| w == message) {
+            warnings.push(message.to_string());
+        }
+    }
+
+    /// The warnings reported since the last call.
+    pub fn take_warnings(&self) -> Vec<String> {
+        std::mem::take(&mut self.warnings.lock().unwrap())
+    }
+
+    /// Whether statements are only captured, never sent.
+    pub fn is_planning(&self) -> bool {
+        self.planning
+    }
+
+    /// Records `sql` if statement capture is enabled; a no-op otherwise.
+    pub fn log_statement(&self, sql: &str) {
+        if let Some(statements) = &self.statements {
+            statements.lock().unwrap().push(sql.to_string());
+        }
+    }
+
+    /// Runs `fetch` once per distinct `key` for the lifetime of the request
+    /// and hands every caller a clone of the result. Failed fetches are not
+    /// cached, so a later identical statement retries.
+    ///
+    /// Only for reads: callers must include everything that affects the
+    /// result (SQL text, parameters, paging) in `key`. Writes clear the
+    /// cache through [`forget_rows`](Self::forget_rows).
+    pub async fn memoize<T, F, Fut>(&self, key: String, fetch: F) -> Result<T, async_graphql::Error>
+    where
+        T: Clone + Send + Sync + 'static,
+        F: FnOnce() -> Fut,
+        Fut: Future<Output = Result<T, async_graphql::Error>>,
+    {
+        let cell = self
+            .query_cache
+            .0
+            .lock()
+            .unwrap()
+            .entry(key)
+            .or_default()
+            .clone();
+        let cached = cell
+            .get_or_try_init(|| async { fetch().await.map(|v| Arc::new(v) as CachedResult) })
+            .await?;
+        cached
+            .downcast_ref::<T>()
+            .cloned()
+            .ok_or_else(|| crate::error::gql_err("query cache type mismatch"))
+    }
+
+    /// `rows` of `table` as this request first saw them: a column already
+    /// fetched for the same primary key keeps that value, and columns seen
+    /// for the first time are remembered. Rows without a primary key value
+    /// pass through unchanged.
+    pub fn identify(&self, table: &Table, mut rows: Vec<Value>) -> Vec<Value> {
+        let mut map = self.identity_map.0.lock().unwrap();
+        for row in &mut rows {
+            let Value::Object(row) = row else {
+                continue;
+            };
+            let key: Option<Vec<&Value>> = table.primary_key().iter().map(|c| row.get(c)).collect();
+            let Some(key) = key.and_then(|key| identity_key(table, &key)) else {
+                continue;
+            };
+            let known = map.entry(key).or_default();
+            for column in table.columns() {
+                let Some(value) = row.get_mut(column.name()) else {
+                    continue;
+                };
+                match known.get(column.name()) {
+                    Some(first) => *value = first.clone(),
+                    None => {
+                        known.insert(column.name().clone(), value.clone());
+                    }
+                }
+            }
+        }
+        rows
+    }
+
+    /// The row of `table` with primary key `key`, when every one of its
+    /// columns was fetched earlier in the request.
+    pub fn identified(&self, table: &Table, key: &[Value]) -> Option<Value> {
+        let key = identity_key(table, &key.iter().collect::<Vec<_>>())?;
+        let map = self.identity_map.0.lock().unwrap();
+        let row = map.get(&key)?;
+        table
+            .columns()
+            .iter()
+            .all(|column| row.contains_key(column.name()))
+            .then(|| Value::Object(row.clone()))
+    }
+
+    /// Forgets every row and every memoized read, after a statement that may
+    /// have changed some, so that later fields of a mutation read afresh.
+    pub fn forget_rows(&self) {
+        self.identity_map.0.lock().unwrap().clear();
+        self.query_cache.0.lock().unwrap().clear();
+    }
+}
+
+#[cfg(feature = "testing")]
+impl RequestState {
+    pub fn capturing_statements() -> Self {
+        Self {
+            statements: Some(Mutex::new(Vec::new())),
+            ..Self::default()
+        }
+    }
+
+    /// Captures statements without opening a transaction for them.
+    pub fn planning() -> Self {
+        Self {
+            planning: true,
+            ..Self::capturing_statements()
+        }
+    }
+
+    pub fn statements(&self) -> Vec<String> {
+        self.statements
+            .as_ref()
+            .map(|s| s.lock().unwrap().clone())
+            .unwrap_or_default()
+    }
+}
+
+#[cfg(test)]
+impl RequestState {
+    pub fn started_ago(elapsed: Duration) -> Self {
+        Self {
+            started: Instant::now() - elapsed,
+            ..Self::default()
+        }
+    }
+}
+
+/// Everything a resolver needs to run SQL on behalf of the current request.
+#[derive(Clone, Default)]
+pub(crate) struct RequestScope {
+    pub tx_config: Option<TransactionConfig>,
+    pub state: Option<Arc<RequestState>>,
+    pub pool_metrics: Option<Arc<PoolMetrics>>,
+    /// See [`Config::circuit_breaker`](crate::Config::circuit_breaker).
+    pub circuit_breaker: Option<Arc<CircuitBreaker>>,
+    /// Replaces the resolver's pool for requests in a dedicated lane.
+    pub lane_pool: Option<Arc<Pool>>,
+    /// Applied to `@encrypted` column values before they are bound.
+    pub encryption: Option<Encryption>,
+    /// Applied to rows after they are fetched, see [`Config::row_transforms`](crate::Config::row_transforms).
+    pub row_transforms: Option<RowTransforms>,
+    /// Decode columns of their types, see [`Config::custom_scalars`](crate::Config::custom_scalars).
+    pub custom_scalars: Option<CustomScalars>,
+}
+
+impl RequestScope {
+    pub fn from_ctx(ctx: &ResolverContext) -> Self {
+        Self {
+            tx_config: ctx.data_opt::<TransactionConfig>().cloned(),
+            state: ctx.data_opt::<Arc<RequestState>>().cloned(),
+            pool_metrics: ctx.data_opt::<Arc<PoolMetrics>>().cloned(),
+            circuit_breaker: ctx.data_opt::<Arc<CircuitBreaker>>().cloned(),
+            lane_pool: match ctx.data_opt::<ExecutionLane>() {
+                Some(ExecutionLane::Background) => {
+                    ctx.data_opt::<BackgroundPool>().map(|p| p.0.clone())
+                }
+                _ => None,
+            },
+            encryption: ctx.data_opt::<Encryption>().cloned(),
+            row_transforms: ctx.data_opt::<RowTransforms>().cloned(),
+            custom_scalars: ctx.data_opt::<CustomScalars>().cloned(),
+        }
+    }
+
+    /// Forwards `sql` to [`RequestState::log_statement`].
+    ///
+    /// Every generated statement passes through here before it is sent, so
+    /// debug builds also [`audit`](crate::sql::audit) it and panic on SQL
+    /// carrying values that bypassed identifier quoting or parameter binding.
+    pub fn log_statement(&self, sql: &str) {
+        if cfg!(debug_assertions)
+            && let Err(reason) = crate::sql::audit(sql)
+        {
+            panic!("generated SQL failed the injection audit ({reason}): {sql}");
+        }
+        if let Some(state) = &self.state {
+            state.log_statement(sql);
+        }
+    }
+
+    /// Forwards to [`RequestState::memoize`]; without request state every
+    /// call fetches.
+    pub async fn memoize<T, F, Fut>(&self, key: String, fetch: F) -> Result<T, async_graphql::Error>
+    where
+        T: Clone + Send + Sync + 'static,
+        F: FnOnce() -> Fut,
+        Fut: Future<Output = Result<T, async_graphql::Error>>,
+    {
+        match &self.state {
+            Some(state) => state.memoize(key, fetch).await,
+            None => fetch().await,
+        }
+    }
+
+    /// Forwards to [`RequestState::identify`]; without request state rows
+    /// pass through.
+    pub fn identify(&self, table: &Table, rows: Vec<Value>) -> Vec<Value> {
+        match &self.state {
+            Some(state) => state.identify(table, rows),
+            None => rows,
+        }
+    }
+
+    /// Forwards to [`RequestState::identified`].
+    pub fn identified(&self, table: &Table, key: &[Value]) -> Option<Value> {
+        self.state.as_ref()?.identified(table, key)
+    }
+
+    /// Forwards to [`RequestState::forget_rows`].
+    pub fn forget_rows(&self) {
+        if let Some(state) = &self.state {
+            state.forget_rows();
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::atomic::AtomicUsize;
+
+    #[tokio::test]
+    async fn test_memoize_runs_identical_keys_once() {
+        let state = RequestState::default();
+        let calls = AtomicUsize::new(0);
+        let fetch = || async {
+            calls.fetch_add(1, Ordering::SeqCst);
+            Ok(42)
+        };
+
+        let (a, b) = tokio::join!(
+            state.memoize("SELECT 1".into(), fetch),
+            state.memoize("SELECT 1".into(), fetch)
+        );
+        assert_eq!((a.unwrap(), b.unwrap()), (42, 42));
+        assert_eq!(state.memoize("SELECT 2".into(), fetch).await.unwrap(), 42);
+        assert_eq!(calls.load(Ordering::SeqCst), 2);
+    }
+
+    #[tokio::test]
+    async fn test_memoize_does_not_cache_errors() {
+        let state = RequestState::default();
+        let failed: Result<i32, _> = state
+            .memoize("SELECT 1".into(), || async {
+                Err(crate::error::gql_err("boom"))
+            })
+            .await;
+        assert!(failed.is_err());
+
+        let retried = state.memoize("SELECT 1".into(), || async { Ok(7) }).await;
+        assert_eq!(retried.unwrap(), 7);
+    }
+
+    #[tokio::test]
+    async fn test_forget_rows_clears_memoized_reads() {
+        let state = RequestState::default();
+        let first = state.memoize("SELECT bio".into(), || async { Ok("old") });
+        assert_eq!(first.await.unwrap(), "old");
+        state.forget_rows();
+        let reread = state.memoize("SELECT bio".into(), || async { Ok("new") });
+        assert_eq!(reread.await.unwrap(), "new");
+    }
+
+    #[test]
+    fn test_identity_map_keeps_the_first_values() {
+        use crate::models::table::Column;
+        use serde_json::json;
+        use tokio_postgres::types::Type;
+
+        let mut table = Table::new_for_test(
+            "users",
+            vec![
+                Column::new_for_test("id", Type::INT4, false, false),
+                Column::new_for_test("name", Type::TEXT, true, false),
+            ],
+        );
+        table.set_primary_key(vec!["id".into()]);
+        let state = RequestState::default();
+
+        let rows = state.identify(&table, vec![json!({ "id": 1, "name": "Ann" })]);
+        assert_eq!(rows, [json!({ "id": 1, "name": "Ann" })]);
+        let rows = state.identify(
+            &table,
+            vec![json!({ "id": 1, "name": "Bob" }), json!({ "id": 2 })],
+        );
+        assert_eq!(
+            rows,
+            [json!({ "id": 1, "name": "Ann" }), json!({ "id": 2 })]
+        );
+
+        assert_eq!(
+            state.identified(&table, &[json!(1)]),
+            Some(json!({ "id": 1, "name": "Ann" }))
+        );
+        // Only the key of row 2 was fetched.
+        assert_eq!(state.identified(&table, &[json!(2)]), None);
+        state.forget_rows();
+        assert_eq!(state.identified(&table, &[json!(1)]), None);
+    }
+}
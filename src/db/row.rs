@@ -1,85 +1,249 @@
+use base64::Engine;
+use chrono::{DateTime, NaiveDate, NaiveDateTime, NaiveTime, Utc};
 use serde_json::{Map, Value};
-use tokio_postgres::{Row, types::Type};
+use std::error::Error;
+use tokio_postgres::Row;
+use tokio_postgres::types::{Field, FromSql, Kind, Type};
 
+use super::codec::{decode_numeric, format_uuid};
+use crate::graphql::scalars::CustomScalars;
+
+/// Rows as JSON. Columns of types without a scalar of their own decode
+/// with their custom scalar in `scalars`, or as their text.
 pub trait JsonExt {
-    fn to_json(&self) -> Value;
+    fn to_json(&self, scalars: Option<&CustomScalars>) -> Value;
 }
 
 pub trait JsonListExt {
-    fn to_json_list(&self) -> Vec<Value>;
+    fn to_json_list(&self, scalars: Option<&CustomScalars>) -> Vec<Value>;
 }
 
 impl JsonExt for Row {
-    fn to_json(&self) -> Value {
+    fn to_json(&self, scalars: Option<&CustomScalars>) -> Value {
         let mut map = Map::new();
 
         for (i, col) in self.columns().iter().enumerate() {
-            let name = col.name().to_string();
-
-            let value = match *col.type_() {
-                Type::BOOL => self
-                    .try_get::<_, bool>(i)
-                    .map(Value::Bool)
-                    .unwrap_or(Value::Null),
-
-                Type::INT2 => self
-                    .try_get::<usize, i16>(i)
-                    .map(|v| Value::Number(v.into()))
-                    .unwrap_or(Value::Null),
-
-                Type::INT4 => self
-                    .try_get::<usize, i32>(i)
-                    .map(|v| Value::Number(v.into()))
-                    .unwrap_or(Value::Null),
-
-                Type::INT8 => self
-                    .try_get::<usize, i64>(i)
-                    .map(|v| Value::Number(v.into()))
-                    .unwrap_or(Value::Null),
-
-                Type::FLOAT4 => self
-                    .try_get::<usize, f32>(i)
-                    .ok()
-                    .and_then(|v| serde_json::Number::from_f64(v as f64))
-                    .map(Value::Number)
-                    .unwrap_or(Value::Null),
-
-                Type::FLOAT8 => self
-                    .try_get::<usize, f64>(i)
-                    .ok()
-                    .and_then(serde_json::Number::from_f64)
-                    .map(Value::Number)
-                    .unwrap_or(Value::Null),
-
-                Type::TEXT | Type::VARCHAR | Type::CHAR | Type::CHAR_ARRAY => self
-                    .try_get::<usize, String>(i)
-                    .map(Value::String)
-                    .unwrap_or(Value::Null),
-
-                Type::JSON | Type::JSONB => self.try_get::<usize, Value>(i).unwrap_or(Value::Null),
-
-                _ => self
-                    .try_get::<usize, String>(i)
-                    .map(Value::String)
-                    .unwrap_or(Value::Null),
+            let value = match self.try_get::<usize, RawValue>(i) {
+                Ok(RawValue(Some(raw))) => decode(col.type_(), raw, scalars),
+                _ => Value::Null,
             };
-
-            map.insert(name, value);
+            map.insert(col.name().to_string(), value);
         }
 
         Value::Object(map)
     }
 }
 
+/// The undecoded binary value of any column.
+pub(crate) struct RawValue<'a>(pub Option<&'a [u8]>);
+
+impl<'a> FromSql<'a> for RawValue<'a> {
+    fn from_sql(_: &Type, raw: &'a [u8]) -> Result<Self, Box<dyn Error + Sync + Send>> {
+        Ok(Self(Some(raw)))
+    }
+
+    fn from_sql_null(_: &Type) -> Result<Self, Box<dyn Error + Sync + Send>> {
+        Ok(Self(None))
+    }
+
+    fn accepts(_: &Type) -> bool {
+        true
+    }
+}
+
+/// Decodes `raw` as a value of `ty`, or `null` when it cannot be.
+fn decode(ty: &Type, raw: &[u8], scalars: Option<&CustomScalars>) -> Value {
+    match ty.kind() {
+        Kind::Domain(base) => return decode(base, raw, scalars),
+        // Enum labels travel as their text.
+        Kind::Enum(_) => return Value::String(String::from_utf8_lossy(raw).into_owned()),
+        Kind::Composite(fields) => {
+            return decode_composite(fields, raw, scalars).unwrap_or(Value::Null);
+        }
+        Kind::Array(member) => return decode_array(member, raw, scalars).unwrap_or(Value::Null),
+        _ => {}
+    }
+
+    match *ty {
+        Type::BOOL => get::<bool>(ty, raw).map(Value::Bool),
+        Type::INT2 => get::<i16>(ty, raw).map(|v| Value::Number(v.into())),
+        Type::INT4 => get::<i32>(ty, raw).map(|v| Value::Number(v.into())),
+        Type::INT8 => get::<i64>(ty, raw).map(|v| Value::Number(v.into())),
+        Type::FLOAT4 => get::<f32>(ty, raw).map(|v| float(v as f64)),
+        Type::FLOAT8 => get::<f64>(ty, raw).map(float),
+        Type::JSON | Type::JSONB => get::<Value>(ty, raw),
+        // Same ISO 8601 forms the mutation inputs accept.
+        Type::DATE => get::<NaiveDate>(ty, raw).map(|v| Value::String(v.to_string())),
+        Type::TIME => get::<NaiveTime>(ty, raw).map(|v| Value::String(v.to_string())),
+        Type::TIMESTAMP => get::<NaiveDateTime>(ty, raw)
+            .map(|v| Value::String(v.format("%Y-%m-%dT%H:%M:%S%.f").to_string())),
+        Type::TIMESTAMPTZ => get::<DateTime<Utc>>(ty, raw).map(|v| Value::String(v.to_rfc3339())),
+        // Binary data as base64, large object OIDs as decimal strings.
+        Type::BYTEA => get::<&[u8]>(ty, raw)
+            .map(|v| Value::String(base64::engine::general_purpose::STANDARD.encode(v))),
+        Type::OID => get::<u32>(ty, raw).map(|v| Value::String(v.to_string())),
+        // Decimal text keeps every digit a float would lose.
+        Type::NUMERIC => decode_numeric(raw).map(Value::String),
+        Type::UUID => format_uuid(raw).map(Value::String),
+        Type::TEXT | Type::VARCHAR | Type::BPCHAR => get::<String>(ty, raw).map(Value::String),
+        // Types without a scalar of their own: a custom one, or their text.
+        _ => scalars
+            .and_then(|scalars| scalars.decode(ty, raw))
+            .unwrap_or_else(|| get::<String>(ty, raw).map(Value::String)),
+    }
+    .unwrap_or(Value::Null)
+}
+
+/// A float as a JSON number, or as the text Postgres gives `NaN`, `Infinity`
+/// and `-Infinity`, which JSON has no number for. The field resolvers decide
+/// what clients see of those; see
+/// [`Config::non_finite_floats`](crate::Config::non_finite_floats).
+fn float(v: f64) -> Value {
+    match serde_json::Number::from_f64(v) {
+        Some(n) => Value::Number(n),
+        None if v.is_nan() => Value::String("NaN".into()),
+        None if v > 0.0 => Value::String("Infinity".into()),
+        None => Value::String("-Infinity".into()),
+    }
+}
+
+fn get<'a, T: FromSql<'a>>(ty: &Type, raw: &'a [u8]) -> Option<T> {
+    if T::accepts(ty) {
+        T::from_sql(ty, raw).ok()
+    } else {
+        None
+    }
+}
+
+/// Decodes a composite value: a field count, then each field's type OID,
+/// length (`-1` for `NULL`) and value.
+fn decode_composite(
+    fields: &[Field],
+    mut raw: &[u8],
+    scalars: Option<&CustomScalars>,
+) -> Option<Value> {
+    let count = read_i32(&mut raw)?;
+    if count as usize != fields.len() {
+        return None;
+    }
+
+    let mut map = Map::new();
+    for field in fields {
+        read_i32(&mut raw)?;
+        let value = read_value(&mut raw, field.type_(), scalars)?;
+        map.insert(field.name().to_string(), value);
+    }
+    Some(Value::Object(map))
+}
+
+/// Decodes an array: dimension count, null flag, element type, each
+/// dimension's length and lower bound, then every element length-prefixed
+/// like a composite field. Multi-dimensional arrays become nested arrays.
+fn decode_array(member: &Type, mut raw: &[u8], scalars: Option<&CustomScalars>) -> Option<Value> {
+    let dimensions = read_i32(&mut raw)?;
+    read_i32(&mut raw)?;
+    read_i32(&mut raw)?;
+    let mut lengths = Vec::new();
+    for _ in 0..dimensions {
+        lengths.push(read_i32(&mut raw)?.max(0) as usize);
+        read_i32(&mut raw)?;
+    }
+
+    let count = if lengths.is_empty() {
+        0
+    } else {
+        lengths.iter().product()
+    };
+    let mut elements = Vec::with_capacity(count);
+    for _ in 0..count {
+        elements.push(read_value(&mut raw, member, scalars)?);
+    }
+
+    // Group the flat elements by the innermost dimension first.
+    for &len in lengths.iter().skip(1).rev() {
+        elements = elements
+            .chunks(len.max(1))
+            .map(|chunk| Value::Array(chunk.to_vec()))
+            .collect();
+    }
+    Some(Value::Array(elements))
+}
+
+/// Reads a length-prefixed value of `ty`; a length of `-1` means `NULL`.
+fn read_value(raw: &mut &[u8], ty: &Type, scalars: Option<&CustomScalars>) -> Option<Value> {
+    Some(read_raw(raw)?.map_or(Value::Null, |value| decode(ty, value, scalars)))
+}
+
+/// Reads the fields of an anonymous record (`ROW(...)`) in order. Its binary
+/// form names each field's type only by OID, so the caller says which type
+/// to decode each one as.
+pub(crate) struct RecordReader<'a> {
+    raw: &'a [u8],
+    scalars: Option<&'a CustomScalars>,
+}
+
+impl<'a> RecordReader<'a> {
+    /// Starts reading `raw`, skipping the field count.
+    pub(crate) fn new(mut raw: &'a [u8], scalars: Option<&'a CustomScalars>) -> Option<Self> {
+        read_i32(&mut raw)?;
+        Some(Self { raw, scalars })
+    }
+
+    /// The next field, decoded as `ty`.
+    pub(crate) fn value(&mut self, ty: &Type) -> Option<Value> {
+        read_i32(&mut self.raw)?;
+        read_value(&mut self.raw, ty, self.scalars)
+    }
+
+    /// The next field undecoded, `None` inside when it is `NULL`.
+    pub(crate) fn raw(&mut self) -> Option<Option<&'a [u8]>> {
+        read_i32(&mut self.raw)?;
+        read_raw(&mut self.raw)
+    }
+}
+
+/// The undecoded elements of a one-dimensional array, `None` for `NULL`
+/// elements.
+pub(crate) fn array_elements(mut raw: &[u8]) -> Option<Vec<Option<&[u8]>>> {
+    let dimensions = read_i32(&mut raw)?;
+    read_i32(&mut raw)?;
+    read_i32(&mut raw)?;
+    if dimensions == 0 {
+        return Some(Vec::new());
+    }
+    let count = read_i32(&mut raw)?.max(0) as usize;
+    read_i32(&mut raw)?;
+    (0..count).map(|_| read_raw(&mut raw)).collect()
+}
+
+/// Reads a length-prefixed value without decoding it.
+fn read_raw<'a>(raw: &mut &'a [u8]) -> Option<Option<&'a [u8]>> {
+    let len = read_i32(raw)?;
+    if len < 0 {
+        return Some(None);
+    }
+    let (value, rest) = raw.split_at_checked(len as usize)?;
+    *raw = rest;
+    Some(Some(value))
+}
+
+fn read_i32(raw: &mut &[u8]) -> Option<i32> {
+    let (bytes, rest) = raw.split_first_chunk::<4>()?;
+    *raw = rest;
+    Some(i32::from_be_bytes(*bytes))
+}
+
 impl JsonExt for Vec<Row> {
-    fn to_json(&self) -> Value {
-        let values = self.to_json_list();
+    fn to_json(&self, scalars: Option<&CustomScalars>) -> Value {
+        let values = self.to_json_list(scalars);
         Value::Array(values)
     }
 }
 
 impl JsonListExt for Vec<Row> {
-    fn to_json_list(&self) -> Vec<Value> {
-        self.iter().map(|row| row.to_json()).collect::<Vec<Value>>()
+    fn to_json_list(&self, scalars: Option<&CustomScalars>) -> Vec<Value> {
+        self.iter()
+            .map(|row| row.to_json(scalars))
+            .collect::<Vec<Value>>()
     }
 }
@@ -2,55 +2,201 @@ use std::fmt::Write;
 use std::future::Future;
 use std::pin::Pin;
 
-use deadpool_postgres::Pool;
+use std::time::Duration;
 
-use crate::error::gql_err;
+use deadpool_postgres::{Object, Pool, PoolError, TimeoutType};
+
+use crate::db::request::{PLANNED, RequestScope};
+use crate::error::{coded_err, db_err, gql_err, sqlstate};
 use crate::models::transaction::TransactionConfig;
 
+/// SQLSTATEs after which re-running the transaction can succeed:
+/// `serialization_failure` and `deadlock_detected`.
+const RETRYABLE_SQLSTATES: [&str; 2] = ["40001", "40P01"];
+
+/// Base delay before the first retry; doubled on every further attempt.
+const RETRY_BASE_DELAY_MS: u64 = 10;
+
 /// Acquires a pooled connection, wraps the callback in `BEGIN` / `COMMIT`, and
 /// rolls back automatically on error. Works with or without a
-/// [`TransactionConfig`].
+/// [`TransactionConfig`]. The connection comes from the scope's lane pool
+/// when the request runs in [`ExecutionLane::Background`](crate::ExecutionLane),
+/// otherwise from `pool`.
+///
+/// Transactions that fail with a serialization failure or deadlock are rolled
+/// back and re-run from scratch, up to [`TransactionConfig::max_retries`]
+/// times with jittered exponential backoff. A failed attempt never committed
+/// anything, so re-running it is safe for mutations as well as queries. Each
+/// retry is counted on the request's [`RequestState`](crate::db::request::RequestState).
 pub(crate) async fn with_transaction<T>(
     pool: &Pool,
-    tx_config: Option<TransactionConfig>,
-    callback: impl for<'c> FnOnce(
+    scope: RequestScope,
+    callback: impl for<'c> Fn(
         &'c tokio_postgres::Client,
     ) -> Pin<
         Box<dyn Future<Output = Result<T, async_graphql::Error>> + Send + 'c>,
     >,
 ) -> Result<T, async_graphql::Error> {
-    let client = pool
-        .get()
-        .await
-        .map_err(|e| gql_err(format!("Pool error: {e}")))?;
+    let pool = scope.lane_pool.as_deref().unwrap_or(pool);
+    let max_retries = scope.tx_config.as_ref().map_or_else(
+        || TransactionConfig::default().max_retries,
+        |c| c.max_retries,
+    );
+    let mut attempt = 0;
 
-    let begin = build_begin_statement(&tx_config);
-    client
-        .batch_execute(&begin)
-        .await
-        .map_err(|e| gql_err(format!("BEGIN error: {e}")))?;
-
-    if let Some(ref cfg) = tx_config {
-        apply_settings(&*client, cfg).await?;
+    loop {
+        match run_transaction(pool, &scope, &callback).await {
+            Err(e) if attempt < max_retries && is_retryable(&e) => {
+                attempt += 1;
+                if let Some(state) = &scope.state {
+                    state.record_retry();
+                }
+                tokio::time::sleep(retry_delay(attempt)).await;
+            }
+            result => return result,
+        }
     }
+}
 
-    let result = callback(&*client).await;
+async fn run_transaction<T>(
+    pool: &Pool,
+    scope: &RequestScope,
+    callback: &impl for<'c> Fn(
+        &'c tokio_postgres::Client,
+    ) -> Pin<
+        Box<dyn Future<Output = Result<T, async_graphql::Error>> + Send + 'c>,
+    >,
+) -> Result<T, async_graphql::Error> {
+    let client = begin(pool, scope).await?;
+    let result = callback(&client).await;
 
-    match &result {
-        Ok(_) => {
-            client
-                .batch_execute("COMMIT")
-                .await
-                .map_err(|e| gql_err(format!("COMMIT error: {e}")))?;
-        }
-        Err(_) => {
+    let result = match result {
+        Ok(value) => client
+            .batch_execute("COMMIT")
+            .await
+            .map(|()| value)
+            .map_err(|e| db_err("COMMIT error", e)),
+        Err(e) => {
             let _ = client.batch_execute("ROLLBACK").await;
+            Err(e)
         }
+    };
+    // The connection died under the transaction rather than a statement failing.
+    if client.is_closed() {
+        record_connection(scope, false);
     }
 
     result
 }
 
+/// Acquires a connection from `pool` and opens a transaction on it with the
+/// scope's [`TransactionConfig`] applied. The caller commits or rolls back;
+/// [`with_transaction`] does both for work that fits in a callback.
+pub(crate) async fn begin(
+    pool: &Pool,
+    scope: &RequestScope,
+) -> Result<Object, async_graphql::Error> {
+    if scope
+        .state
+        .as_ref()
+        .is_some_and(|state| state.is_planning())
+    {
+        return Err(coded_err(PLANNED, "statements are planned, not executed"));
+    }
+    // Checked before touching the pool so an exhausted budget costs nothing.
+    let statement_timeout_ms = statement_timeout_ms(scope)?;
+    let tx_config = &scope.tx_config;
+
+    if let Some(breaker) = &scope.circuit_breaker {
+        breaker.check()?;
+    }
+
+    let client = pool.get().await.map_err(|e| {
+        if let PoolError::Timeout(_) = e
+            && let Some(metrics) = &scope.pool_metrics
+        {
+            metrics.record_timeout();
+        }
+        // Waiting for a free connection means the pool is busy, not that
+        // the database is down.
+        if !matches!(e, PoolError::Timeout(TimeoutType::Wait)) {
+            record_connection(scope, false);
+        }
+        gql_err(format!("Pool error: {e}"))
+    })?;
+
+    let begin = build_begin_statement(tx_config);
+    client.batch_execute(&begin).await.map_err(|e| {
+        if e.code().is_none() {
+            record_connection(scope, false);
+        }
+        db_err("BEGIN error", e)
+    })?;
+    record_connection(scope, true);
+
+    if let Some(cfg) = tx_config {
+        apply_settings(&client, cfg, statement_timeout_ms).await?;
+    }
+
+    Ok(client)
+}
+
+/// Reports whether the database could be reached to the scope's
+/// [`CircuitBreaker`](crate::db::circuit_breaker::CircuitBreaker).
+fn record_connection(scope: &RequestScope, reached: bool) {
+    if let Some(breaker) = &scope.circuit_breaker {
+        if reached {
+            breaker.record_success();
+        } else {
+            breaker.record_failure();
+        }
+    }
+}
+
+/// The `statement_timeout` for the next transaction: the configured
+/// [`TransactionConfig::timeout_seconds`], capped by whatever is left of the
+/// request's [`TransactionConfig::budget_ms`]. Fails once the budget is spent,
+/// so the field errors out while the rest of the response is still returned.
+fn statement_timeout_ms(scope: &RequestScope) -> Result<Option<u64>, async_graphql::Error> {
+    let Some(cfg) = &scope.tx_config else {
+        return Ok(None);
+    };
+
+    let fixed = cfg.timeout_seconds.map(|secs| secs * 1000);
+    let remaining = match (cfg.budget_ms, &scope.state) {
+        (Some(budget), Some(state)) => {
+            let elapsed = state.elapsed().as_millis() as u64;
+            if elapsed >= budget {
+                return Err(gql_err(format!(
+                    "Request time budget of {budget}ms exhausted"
+                )));
+            }
+            Some(budget - elapsed)
+        }
+        _ => None,
+    };
+
+    Ok(match (fixed, remaining) {
+        (Some(fixed), Some(remaining)) => Some(fixed.min(remaining)),
+        (fixed, remaining) => fixed.or(remaining),
+    })
+}
+
+fn is_retryable(err: &async_graphql::Error) -> bool {
+    sqlstate(err).is_some_and(|code| RETRYABLE_SQLSTATES.contains(&code))
+}
+
+/// Exponential backoff with up to 100% jitter so concurrent conflicting
+/// transactions do not retry in lockstep.
+fn retry_delay(attempt: u32) -> Duration {
+    let base = RETRY_BASE_DELAY_MS << attempt.saturating_sub(1).min(8);
+    let nanos = std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .map(|d| d.subsec_nanos() as u64)
+        .unwrap_or(0);
+    Duration::from_millis(base + nanos % base)
+}
+
 fn build_begin_statement(tx_config: &Option<TransactionConfig>) -> String {
     let mut begin = String::from("BEGIN");
     if let Some(cfg) = tx_config {
@@ -79,28 +225,121 @@ fn build_begin_statement(tx_config: &Option<TransactionConfig>) -> String {
 async fn apply_settings(
     client: &tokio_postgres::Client,
     cfg: &TransactionConfig,
+    statement_timeout_ms: Option<u64>,
 ) -> Result<(), async_graphql::Error> {
     if let Some(ref role) = cfg.role {
         client
             .query("SELECT set_config('role', $1, true)", &[role])
             .await
-            .map_err(|e| gql_err(format!("SET ROLE error: {e}")))?;
+            .map_err(|e| db_err("SET ROLE error", e))?;
     }
 
     for (key, val) in &cfg.settings {
         client
             .query("SELECT set_config($1, $2, true)", &[key, val])
             .await
-            .map_err(|e| gql_err(format!("set_config error: {e}")))?;
+            .map_err(|e| db_err("set_config error", e))?;
     }
 
-    if let Some(secs) = cfg.timeout_seconds {
-        let ms = (secs * 1000).to_string();
+    if let Some(ms) = statement_timeout_ms {
+        let ms = ms.to_string();
         client
             .query("SELECT set_config('statement_timeout', $1, true)", &[&ms])
             .await
-            .map_err(|e| gql_err(format!("SET timeout error: {e}")))?;
+            .map_err(|e| db_err("SET timeout error", e))?;
     }
 
     Ok(())
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::db::request::RequestState;
+    use async_graphql::ErrorExtensions;
+    use std::sync::Arc;
+
+    fn err_with_sqlstate(code: &str) -> async_graphql::Error {
+        gql_err("boom").extend_with(|_, ext| ext.set("sqlstate", code))
+    }
+
+    #[test]
+    fn test_serialization_failure_is_retryable() {
+        assert!(is_retryable(&err_with_sqlstate("40001")));
+    }
+
+    #[test]
+    fn test_deadlock_is_retryable() {
+        assert!(is_retryable(&err_with_sqlstate("40P01")));
+    }
+
+    #[test]
+    fn test_other_errors_are_not_retryable() {
+        assert!(!is_retryable(&err_with_sqlstate("23505")));
+        assert!(!is_retryable(&gql_err("no sqlstate")));
+    }
+
+    fn scope(cfg: TransactionConfig, elapsed_ms: u64) -> RequestScope {
+        RequestScope {
+            tx_config: Some(cfg),
+            state: Some(Arc::new(RequestState::started_ago(Duration::from_millis(
+                elapsed_ms,
+            )))),
+            pool_metrics: None,
+            circuit_breaker: None,
+            lane_pool: None,
+            encryption: None,
+            row_transforms: None,
+            custom_scalars: None,
+        }
+    }
+
+    #[test]
+    fn test_statement_timeout_without_config() {
+        assert_eq!(
+            statement_timeout_ms(&RequestScope::default()).unwrap(),
+            None
+        );
+    }
+
+    #[test]
+    fn test_statement_timeout_from_timeout_seconds() {
+        let cfg = TransactionConfig {
+            timeout_seconds: Some(2),
+            ..Default::default()
+        };
+        assert_eq!(statement_timeout_ms(&scope(cfg, 0)).unwrap(), Some(2000));
+    }
+
+    #[test]
+    fn test_statement_timeout_capped_by_remaining_budget() {
+        let cfg = TransactionConfig {
+            timeout_seconds: Some(5),
+            budget_ms: Some(1000),
+            ..Default::default()
+        };
+        let ms = statement_timeout_ms(&scope(cfg, 400)).unwrap().unwrap();
+        assert!(ms <= 600, "expected at most 600ms, got {ms}");
+    }
+
+    #[test]
+    fn test_statement_timeout_budget_exhausted() {
+        let cfg = TransactionConfig {
+            budget_ms: Some(100),
+            ..Default::default()
+        };
+        assert!(statement_timeout_ms(&scope(cfg, 150)).is_err());
+    }
+
+    #[test]
+    fn test_retry_delay_grows_with_attempts() {
+        for attempt in 1..=4 {
+            let base = RETRY_BASE_DELAY_MS << (attempt - 1);
+            let delay = retry_delay(attempt).as_millis() as u64;
+            assert!(
+                (base..base * 2).contains(&delay),
+                "attempt {attempt}: {delay}ms"
+            );
+        }
+    }
+}
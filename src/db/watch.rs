@@ -1,14 +1,21 @@
 use std::sync::Arc;
 
-use async_graphql::dynamic::Schema;
+use arc_swap::ArcSwap;
 use deadpool_postgres::Pool;
-use tokio::sync::RwLock;
+use tokio::sync::mpsc::UnboundedSender;
 use tokio_postgres::AsyncMessage;
 
+use crate::models::config::{IntrospectionConfig, SchemaOptions};
+use crate::schema::BuiltSchema;
+
 /// SQL to install DDL event triggers that send NOTIFY on schema changes.
-/// Requires superuser privileges.
+/// The trigger function lives in its own `turbograph_watch` schema, out of
+/// the way of introspected ones; the function earlier versions created in
+/// `public` is dropped. Requires superuser privileges.
 const INSTALL_TRIGGERS_SQL: &str = r"
-CREATE OR REPLACE FUNCTION turbograph_watch_ddl() RETURNS event_trigger AS $$
+CREATE SCHEMA IF NOT EXISTS turbograph_watch;
+
+CREATE OR REPLACE FUNCTION turbograph_watch.notify_watchers_ddl() RETURNS event_trigger AS $$
 BEGIN
   PERFORM pg_notify('turbograph_watch', TG_TAG);
 END;
@@ -16,11 +23,13 @@ $$ LANGUAGE plpgsql;
 
 DROP EVENT TRIGGER IF EXISTS turbograph_watch_ddl;
 CREATE EVENT TRIGGER turbograph_watch_ddl ON ddl_command_end
-  EXECUTE FUNCTION turbograph_watch_ddl();
+  EXECUTE FUNCTION turbograph_watch.notify_watchers_ddl();
 
 DROP EVENT TRIGGER IF EXISTS turbograph_watch_drop;
 CREATE EVENT TRIGGER turbograph_watch_drop ON sql_drop
-  EXECUTE FUNCTION turbograph_watch_ddl();
+  EXECUTE FUNCTION turbograph_watch.notify_watchers_ddl();
+
+DROP FUNCTION IF EXISTS public.turbograph_watch_ddl();
 ";
 
 /// Creates the event trigger function and event triggers in PostgreSQL.
@@ -32,25 +41,78 @@ pub(crate) async fn install_triggers(
     Ok(())
 }
 
-/// Opens a dedicated connection for `LISTEN`, then spawns a background task
-/// that rebuilds the schema whenever a DDL notification arrives.
+/// Spawns the task that rebuilds `live` from the database and returns
+/// the channel that requests a rebuild. Each message names its cause (a DDL
+/// command tag or `SCHEMA_OUT_OF_DATE`); bursts are coalesced. The task ends
+/// once every sender is dropped. A new schema is swapped in whole, without
+/// waiting for requests on the old one, which finish on the schema they
+/// started with.
+///
+/// `fingerprint` is the catalog fingerprint taken before `live` was
+/// built; requests that leave it unchanged do not trigger a rebuild.
+/// Pass `None` to rebuild on every request until one succeeds.
+pub(crate) fn spawn_rebuilder(
+    pool: Arc<Pool>,
+    introspection: IntrospectionConfig,
+    options: SchemaOptions,
+    live: Arc<ArcSwap<BuiltSchema>>,
+    mut fingerprint: Option<String>,
+) -> UnboundedSender<String> {
+    let (rebuild_tx, mut rebuild_rx) = tokio::sync::mpsc::unbounded_channel::<String>();
+
+    // Process requests: debounce, rebuild, and swap.
+    tokio::spawn(async move {
+        while let Some(cause) = rebuild_rx.recv().await {
+            eprintln!("[turbograph] schema change detected: {cause}");
+
+            // Debounce: wait briefly then drain any queued requests.
+            tokio::time::sleep(std::time::Duration::from_millis(200)).await;
+            while rebuild_rx.try_recv().is_ok() {}
+
+            let current =
+                match crate::db::introspect::catalog_fingerprint(&pool, &introspection).await {
+                    Ok(fp) => Some(fp),
+                    Err(e) => {
+                        eprintln!("[turbograph] failed to fingerprint catalog: {e}");
+                        None
+                    }
+                };
+            if current.is_some() && current == fingerprint {
+                eprintln!("[turbograph] no introspected objects changed, skipping rebuild");
+                continue;
+            }
+
+            match crate::schema::rebuild_schema(&pool, &introspection, &options).await {
+                Ok(built) => {
+                    eprintln!("[turbograph] schema rebuilt successfully");
+                    live.store(Arc::new(built));
+                    fingerprint = current;
+                }
+                Err(e) => {
+                    eprintln!("[turbograph] failed to rebuild schema: {e}");
+                }
+            }
+        }
+    });
+
+    rebuild_tx
+}
+
+/// Opens a dedicated connection for `LISTEN` and forwards every DDL
+/// notification to the rebuilder behind `rebuild_tx`.
 pub(crate) async fn start_watching(
     connection_url: String,
-    pool: Arc<Pool>,
-    schemas: Vec<String>,
-    live_schema: Arc<RwLock<Schema>>,
+    rebuild_tx: UnboundedSender<String>,
 ) -> Result<(), Box<dyn std::error::Error + Send + Sync>> {
     let (client, mut connection) =
         tokio_postgres::connect(&connection_url, tokio_postgres::NoTls).await?;
 
-    // Forward notifications from the connection driver to an mpsc channel.
-    let (notify_tx, mut notify_rx) = tokio::sync::mpsc::unbounded_channel::<String>();
-
+    let forward_tx = rebuild_tx.clone();
     tokio::spawn(async move {
         loop {
             match std::future::poll_fn(|cx| connection.poll_message(cx)).await {
                 Some(Ok(AsyncMessage::Notification(n))) => {
-                    if notify_tx.send(n.payload().to_string()).is_err() {
+                    if forward_tx.send(n.payload().to_string()).is_err() {
                         break;
                     }
                 }
@@ -67,28 +129,10 @@ pub(crate) async fn start_watching(
     client.batch_execute("LISTEN turbograph_watch").await?;
     eprintln!("[turbograph] watching for schema changes");
 
-    // Process notifications: debounce, rebuild, and swap.
+    // Keep the LISTEN client alive for as long as the rebuilder runs.
     tokio::spawn(async move {
-        // Keep the LISTEN client alive for the lifetime of this task.
         let _client = client;
-
-        while let Some(tag) = notify_rx.recv().await {
-            eprintln!("[turbograph] DDL change detected: {tag}");
-
-            // Debounce: wait briefly then drain any queued notifications.
-            tokio::time::sleep(std::time::Duration::from_millis(200)).await;
-            while notify_rx.try_recv().is_ok() {}
-
-            match crate::schema::rebuild_schema(&pool, &schemas).await {
-                Ok(new_schema) => {
-                    eprintln!("[turbograph] schema rebuilt successfully");
-                    *live_schema.write().await = new_schema;
-                }
-                Err(e) => {
-                    eprintln!("[turbograph] failed to rebuild schema: {e}");
-                }
-            }
-        }
+        rebuild_tx.closed().await;
     });
 
     Ok(())
@@ -0,0 +1,172 @@
+use std::any::TypeId;
+use std::collections::HashMap;
+use std::sync::Arc;
+
+use async_graphql::{ErrorExtensionValues, ServerError};
+use deadpool_postgres::Pool;
+
+use crate::models::config::{Config, IntrospectionConfig, PoolConfig, SchemaOptions};
+use crate::models::transaction::TransactionConfig;
+use crate::schema::TurboGraph;
+
+/// One independently configured schema served under its own route.
+pub struct Endpoint {
+    /// Route the schema is served under, e.g. `"/admin/graphql"`.
+    pub path: String,
+    /// Exposed Postgres schemas and options of this endpoint. Endpoints with
+    /// the same [`PoolConfig::ConnectionString`] share one pool.
+    pub config: Config,
+    /// Roles (matched against [`TransactionConfig::role`]) allowed to use the
+    /// endpoint. Empty allows every request, including ones without a role.
+    pub allowed_roles: Vec<String>,
+    /// Applied to requests that do not carry their own [`TransactionConfig`],
+    /// e.g. a restricted role for a public endpoint.
+    pub default_transaction: Option<TransactionConfig>,
+}
+
+/// Several [`TurboGraph`] schemas served from one server, keyed by route.
+///
+/// ```rust,ignore
+/// let endpoints = Endpoints::new(vec![
+///     Endpoint { path: "/public/graphql".into(), config: public_config, allowed_roles: vec![], default_transaction: None },
+///     Endpoint { path: "/admin/graphql".into(), config: admin_config, allowed_roles: vec!["admin".into()], default_transaction: None },
+/// ])
+/// .await?;
+///
+/// async fn handler(State(endpoints): State<Endpoints>, uri: Uri, req: GraphQLRequest) -> GraphQLResponse {
+///     endpoints.execute(uri.path(), req.into_inner()).await.unwrap_or_default().into()
+/// }
+/// ```
+#[derive(Clone)]
+pub struct Endpoints {
+    endpoints: Arc<HashMap<String, EndpointState>>,
+}
+
+struct EndpointState {
+    server: TurboGraph,
+    allowed_roles: Vec<String>,
+    default_transaction: Option<TransactionConfig>,
+}
+
+impl Endpoints {
+    /// Builds the schema of every endpoint. Fails on duplicate paths or if
+    /// any schema fails to build.
+    pub async fn new(
+        endpoints: Vec<Endpoint>,
+    ) -> Result<Self, Box<dyn std::error::Error + Send + Sync>> {
+        let mut pools: HashMap<String, Arc<Pool>> = HashMap::new();
+        let mut built = HashMap::new();
+
+        for endpoint in endpoints {
+            if built.contains_key(&endpoint.path) {
+                return Err(format!("duplicate endpoint path {}", endpoint.path).into());
+            }
+
+            let config = endpoint.config;
+            let introspection = IntrospectionConfig::from_config(&config);
+            let options = SchemaOptions::from_config(&config);
+            let (pool, url) = match config.pool {
+                PoolConfig::ConnectionString(url) => {
+                    let pool = match pools.get(&url) {
+                        Some(pool) => pool.clone(),
+                        None => {
+                            let pool = Arc::new(crate::db::pool::resolve(
+                                PoolConfig::ConnectionString(url.clone()),
+                            )?);
+                            pools.insert(url.clone(), pool.clone());
+                            pool
+                        }
+                    };
+                    (pool, Some(url))
+                }
+                PoolConfig::Pool(pool) => (Arc::new(pool), None),
+            };
+
+            let server = TurboGraph::with_pool(
+                pool,
+                url,
+                config.watch_pg,
+                introspection,
+                options,
+                config.background_pool,
+            )
+            .await?;
+            built.insert(
+                endpoint.path,
+                EndpointState {
+                    server,
+                    allowed_roles: endpoint.allowed_roles,
+                    default_transaction: endpoint.default_transaction,
+                },
+            );
+        }
+
+        Ok(Self {
+            endpoints: Arc::new(built),
+        })
+    }
+
+    /// One endpoint per named database, keyed by its name instead of a
+    /// route, e.g. `[("billing", billing_config), ("crm", crm_config)]`;
+    /// [`get`](Self::get) and [`execute`](Self::execute) then take the name.
+    /// Configs with the same connection string share one pool, as in
+    /// [`new`](Self::new).
+    pub async fn databases(
+        databases: Vec<(String, Config)>,
+    ) -> Result<Self, Box<dyn std::error::Error + Send + Sync>> {
+        Self::new(
+            databases
+                .into_iter()
+                .map(|(name, config)| Endpoint {
+                    path: name,
+                    config,
+                    allowed_roles: vec![],
+                    default_transaction: None,
+                })
+                .collect(),
+        )
+        .await
+    }
+
+    /// The routes of all endpoints.
+    pub fn paths(&self) -> impl Iterator<Item = &str> {
+        self.endpoints.keys().map(String::as_str)
+    }
+
+    /// The schema served under `path`.
+    pub fn get(&self, path: &str) -> Option<&TurboGraph> {
+        self.endpoints.get(path).map(|e| &e.server)
+    }
+
+    /// Executes `request` against the schema served under `path`, or returns
+    /// `None` for an unknown path. Applies the endpoint's default
+    /// transaction, and answers requests whose role is not allowed with a
+    /// `FORBIDDEN` error.
+    pub async fn execute(
+        &self,
+        path: &str,
+        mut request: async_graphql::Request,
+    ) -> Option<async_graphql::Response> {
+        let endpoint = self.endpoints.get(path)?;
+
+        let has_transaction = request
+            .data
+            .contains_key(&TypeId::of::<TransactionConfig>());
+        if !has_transaction && let Some(tx) = &endpoint.default_transaction {
+            request = request.data(tx.clone());
+        }
+
+        if !endpoint.allowed_roles.is_empty() {
+            let role = endpoint.server.request_role(&request);
+            if !role.is_some_and(|role| endpoint.allowed_roles.contains(&role)) {
+                let mut error = ServerError::new(format!("{path} requires an allowed role"), None);
+                let mut extensions = ErrorExtensionValues::default();
+                extensions.set("code", "FORBIDDEN");
+                error.extensions = Some(extensions);
+                return Some(async_graphql::Response::from_errors(vec![error]));
+            }
+        }
+
+        Some(endpoint.server.execute(request).await)
+    }
+}
@@ -1,5 +1,127 @@
+use std::sync::LazyLock;
+
+use async_graphql::ErrorExtensions;
+use tokio_postgres::error::{DbError, SqlState};
+
+/// `code` extension of errors caused by objects dropped after the schema was
+/// built.
+const SCHEMA_OUT_OF_DATE: &str = "SCHEMA_OUT_OF_DATE";
+
 /// Creates an [`async_graphql::Error`] from a displayable message.
 #[inline]
 pub(crate) fn gql_err(msg: impl std::fmt::Display) -> async_graphql::Error {
     async_graphql::Error::new(msg.to_string())
 }
+
+/// Creates an [`async_graphql::Error`] carrying a machine-readable `code`
+/// extension (e.g. `INVALID_CURSOR`) for clients to branch on.
+pub(crate) fn coded_err(code: &str, msg: impl std::fmt::Display) -> async_graphql::Error {
+    gql_err(msg).extend_with(|_, ext| ext.set("code", code))
+}
+
+/// Creates an [`async_graphql::Error`] from a database error, prefixing the
+/// message with `context` and keeping the SQLSTATE (if any) in the `sqlstate`
+/// extension so callers can react to specific failure classes.
+///
+/// Exclusion constraint violations (e.g. overlapping bookings) get a
+/// readable message and `code: "EXCLUSION_VIOLATION"`, `constraint` and
+/// `columns` extensions instead of the raw database error.
+///
+/// Missing tables and columns mean the schema was built before they were
+/// dropped; they get `code: "SCHEMA_OUT_OF_DATE"`, which makes
+/// [`TurboGraph`](crate::TurboGraph) re-introspect the database.
+pub(crate) fn db_err(context: &str, e: tokio_postgres::Error) -> async_graphql::Error {
+    if let Some(db) = e.as_db_error() {
+        match *db.code() {
+            SqlState::EXCLUSION_VIOLATION => return exclusion_err(context, db),
+            SqlState::UNDEFINED_TABLE | SqlState::UNDEFINED_COLUMN => {
+                return schema_out_of_date_err(context, db);
+            }
+            _ => {}
+        }
+    }
+
+    let err = gql_err(format!("{context}: {e}"));
+    match e.code() {
+        Some(code) => err.extend_with(|_, ext| ext.set("sqlstate", code.code())),
+        None => err,
+    }
+}
+
+fn exclusion_err(context: &str, db: &DbError) -> async_graphql::Error {
+    let constraint = db.constraint().unwrap_or_default().to_string();
+    let columns = db.detail().map(conflicting_columns).unwrap_or_default();
+    let message = if columns.is_empty() {
+        format!("{context}: conflicts with an existing row (constraint \"{constraint}\")")
+    } else {
+        format!(
+            "{context}: ({}) conflicts with an existing row (constraint \"{constraint}\")",
+            columns.join(", ")
+        )
+    };
+
+    gql_err(message).extend_with(|_, ext| {
+        ext.set("sqlstate", SqlState::EXCLUSION_VIOLATION.code());
+        ext.set("code", "EXCLUSION_VIOLATION");
+        ext.set("constraint", constraint.as_str());
+        ext.set("columns", columns.clone());
+    })
+}
+
+fn schema_out_of_date_err(context: &str, db: &DbError) -> async_graphql::Error {
+    let code = db.code().code();
+    gql_err(format!(
+        "{context}: the database schema changed since the GraphQL schema was built ({}); retry shortly",
+        db.message()
+    ))
+    .extend_with(|_, ext| {
+        ext.set("sqlstate", code);
+        ext.set("code", SCHEMA_OUT_OF_DATE);
+    })
+}
+
+/// Whether `err` is a [`db_err`] caused by a dropped table or column.
+pub(crate) fn is_schema_out_of_date(err: &async_graphql::ServerError) -> bool {
+    err.extensions
+        .as_ref()
+        .and_then(|ext| ext.get("code"))
+        .is_some_and(|code| *code == async_graphql::Value::from(SCHEMA_OUT_OF_DATE))
+}
+
+/// The columns named in an exclusion violation's detail, e.g. `room_id` and
+/// `during` from `Key (room_id, during)=(1, [...)) conflicts with existing
+/// key (...)`.
+fn conflicting_columns(detail: &str) -> Vec<String> {
+    static KEY_REGEX: LazyLock<regex::Regex> =
+        LazyLock::new(|| regex::Regex::new(r"^Key \(([^)]*)\)=").unwrap());
+
+    KEY_REGEX
+        .captures(detail)
+        .map(|caps| caps[1].split(',').map(|c| c.trim().to_string()).collect())
+        .unwrap_or_default()
+}
+
+/// Returns the SQLSTATE recorded by [`db_err`], if any.
+pub(crate) fn sqlstate(err: &async_graphql::Error) -> Option<&str> {
+    match err.extensions.as_ref()?.get("sqlstate")? {
+        async_graphql::Value::String(code) => Some(code),
+        _ => None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_conflicting_columns_from_exclusion_detail() {
+        assert_eq!(
+            conflicting_columns(
+                "Key (room_id, during)=(1, [\"2024-01-01 10:00:00+00\",\"2024-01-01 11:00:00+00\")) \
+                 conflicts with existing key (room_id, during)=(1, [...))."
+            ),
+            ["room_id", "during"]
+        );
+        assert!(conflicting_columns("something else").is_empty());
+    }
+}
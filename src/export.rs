@@ -0,0 +1,360 @@
+//! Bulk downloads of exposed tables as CSV or NDJSON, streamed with
+//! `COPY ... TO STDOUT` instead of paged through a connection. Filters take
+//! the shape of the table's `{T}Condition` and are compiled like GraphQL
+//! `condition` arguments.
+
+use std::collections::HashMap;
+use std::ops::Deref;
+use std::sync::Arc;
+
+use async_graphql::futures_util::stream::{self, BoxStream, StreamExt};
+use bytes::Bytes;
+use deadpool_postgres::{Object, Pool};
+
+use crate::db::request::RequestScope;
+use crate::db::transaction::begin;
+use crate::error::{coded_err, db_err};
+use crate::ir::{self, Filter};
+use crate::models::row_transform::has_transforms;
+use crate::models::table::{Column, Table};
+use crate::sql::{param_refs, quote_ident, quote_qualified};
+
+/// `COPY` takes no parameters, so filter values are bound into this
+/// one-row temporary table first and read back from it.
+const PARAMS_TABLE: &str = "\"turbograph_export_params\"";
+
+/// The encoding of an export.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ExportFormat {
+    /// Comma-separated values with a header row of field names.
+    Csv,
+    /// One JSON object per line, keyed by field name.
+    Ndjson,
+}
+
+impl ExportFormat {
+    /// `"csv"` or `"ndjson"`, e.g. from a `format` query parameter.
+    pub fn from_name(name: &str) -> Option<Self> {
+        match name {
+            "csv" => Some(Self::Csv),
+            "ndjson" => Some(Self::Ndjson),
+            _ => None,
+        }
+    }
+
+    /// The `Content-Type` of the download.
+    pub fn content_type(self) -> &'static str {
+        match self {
+            Self::Csv => "text/csv; charset=utf-8",
+            Self::Ndjson => "application/x-ndjson",
+        }
+    }
+}
+
+/// What [`TurboGraph::export`](crate::TurboGraph::export) downloads.
+#[derive(Clone, Debug)]
+pub struct ExportRequest {
+    /// The table's Postgres name, qualified as `schema.name` when several
+    /// exposed schemas have a table of that name.
+    pub table: String,
+    /// A `{T}Condition` as JSON, e.g. `{"status": {"equal": "open"}}`.
+    pub filter: Option<serde_json::Value>,
+    pub format: ExportFormat,
+}
+
+/// The body of an export, in chunks of whole rows.
+pub type ExportStream = BoxStream<'static, Result<Bytes, async_graphql::Error>>;
+
+/// The tables one schema build exposes to exports.
+pub(crate) struct Exports {
+    pool: Arc<Pool>,
+    roles: Vec<String>,
+    tables: Vec<Arc<Table>>,
+}
+
+impl Exports {
+    pub(crate) fn new(pool: Arc<Pool>, roles: Vec<String>, tables: Vec<Arc<Table>>) -> Self {
+        Self {
+            pool,
+            roles,
+            tables,
+        }
+    }
+
+    /// The table `name` refers to; see [`ExportRequest::table`].
+    fn table(&self, name: &str) -> Result<&Arc<Table>, async_graphql::Error> {
+        ir::find_table(&self.tables, name)
+    }
+}
+
+/// Streams the rows of `request.table` matching its filter. Fails before
+/// anything is sent when the role may not export or the table has row
+/// transforms (`FORBIDDEN`), the table is unknown (`NOT_FOUND`) or the
+/// filter is invalid (`INVALID_FILTER`).
+pub(crate) async fn export(
+    exports: &Exports,
+    request: ExportRequest,
+    scope: RequestScope,
+) -> Result<ExportStream, async_graphql::Error> {
+    let role = scope.tx_config.as_ref().and_then(|cfg| cfg.role.as_deref());
+    if !role.is_some_and(|role| exports.roles.iter().any(|r| r == role)) {
+        return Err(coded_err("FORBIDDEN", "exports require an export role"));
+    }
+    let table = exports.table(&request.table)?;
+    // `COPY` streams rows as Postgres encodes them, never through the row
+    // transforms that may redact or rewrite them.
+    if has_transforms(&scope, table.schema_name(), table.name()) {
+        return Err(coded_err(
+            "FORBIDDEN",
+            format!(
+                "`{}` has row transforms and cannot be exported",
+                table.name()
+            ),
+        ));
+    }
+
+    // `@encrypted` columns would only yield ciphertext.
+    let columns: Vec<Arc<Column>> = table
+        .columns()
+        .iter()
+        .filter(|c| !c.omit_read() && !c.encrypted())
+        .cloned()
+        .collect();
+    let col_by_name: HashMap<String, usize> = columns
+        .iter()
+        .enumerate()
+        .map(|(i, c)| (c.field_name().to_string(), i))
+        .collect();
+
+    let mut where_clause = String::new();
+    let mut params = Vec::new();
+    if let Some(filter) = request.filter {
+        let conditions = Filter::from_condition(filter)
+            .and_then(|filters| ir::filter_conditions(&filters, &columns, &col_by_name))
+            .map_err(|e| coded_err("INVALID_FILTER", e.message))?;
+        where_clause = ir::where_sql(conditions).build_after(&mut params);
+    }
+
+    let from = quote_qualified(table.schema_name(), table.name());
+    let pool = scope
+        .lane_pool
+        .clone()
+        .unwrap_or_else(|| exports.pool.clone());
+    // Creating the parameters table needs a writable transaction; a
+    // read-only one is switched over once it exists.
+    let read_only = scope.tx_config.as_ref().is_some_and(|cfg| cfg.read_only);
+    let mut begin_scope = scope.clone();
+    if let Some(cfg) = begin_scope
+        .tx_config
+        .as_mut()
+        .filter(|_| !params.is_empty())
+    {
+        cfg.read_only = false;
+    }
+    let client = OpenTransaction(Some(begin(&pool, &begin_scope).await?));
+
+    if !params.is_empty() {
+        let statement = client
+            .prepare(&format!("SELECT FROM {from}{where_clause}"))
+            .await
+            .map_err(|e| db_err("export error", e))?;
+        let values = statement
+            .params()
+            .iter()
+            .enumerate()
+            .map(|(i, ty)| {
+                format!(
+                    "${}::{} AS {}",
+                    i + 1,
+                    quote_qualified(ty.schema(), ty.name()),
+                    quote_ident(&format!("p{}", i + 1))
+                )
+            })
+            .collect::<Vec<_>>()
+            .join(", ");
+        let create = format!("CREATE TEMP TABLE {PARAMS_TABLE} ON COMMIT DROP AS SELECT {values}");
+        scope.log_statement(&create);
+        client
+            .execute(&create, &param_refs(&params))
+            .await
+            .map_err(|e| db_err("export error", e))?;
+        if read_only {
+            client
+                .batch_execute("SET TRANSACTION READ ONLY")
+                .await
+                .map_err(|e| db_err("export error", e))?;
+        }
+        where_clause = bind_to_params_table(&where_clause);
+    }
+
+    let select = columns
+        .iter()
+        .map(|c| {
+            format!(
+                "{} AS {}",
+                quote_ident(c.name()),
+                quote_ident(c.field_name())
+            )
+        })
+        .collect::<Vec<_>>()
+        .join(", ");
+    let order = if table.primary_key().is_empty() {
+        String::new()
+    } else {
+        let keys: Vec<String> = table.primary_key().iter().map(|k| quote_ident(k)).collect();
+        format!(" ORDER BY {}", keys.join(", "))
+    };
+    let query = format!("SELECT {select} FROM {from}{where_clause}{order}");
+    let copy = match request.format {
+        ExportFormat::Csv => format!("COPY ({query}) TO STDOUT (FORMAT csv, HEADER)"),
+        // jsonb output never spans lines, so each row stays on its own.
+        ExportFormat::Ndjson => format!(
+            "COPY (SELECT to_jsonb(\"turbograph_row\") FROM ({query}) AS \"turbograph_row\") TO STDOUT"
+        ),
+    };
+    scope.log_statement(&copy);
+    let rows = client
+        .copy_out(&copy)
+        .await
+        .map_err(|e| db_err("COPY error", e))?;
+
+    let format = request.format;
+    let state = Some((client, Box::pin(rows)));
+    Ok(stream::unfold(state, move |state| async move {
+        let (client, mut rows) = state?;
+        match rows.next().await {
+            Some(Ok(chunk)) => {
+                let chunk = match format {
+                    ExportFormat::Csv => chunk,
+                    ExportFormat::Ndjson => unescape_copy_text(&chunk),
+                };
+                Some((Ok(chunk), Some((client, rows))))
+            }
+            Some(Err(e)) => Some((Err(db_err("COPY error", e)), None)),
+            None => client.commit().await.err().map(|e| (Err(e), None)),
+        }
+    })
+    .boxed())
+}
+
+/// Rewrites the `$n` placeholders of `sql`, outside quoted identifiers, to
+/// read parameter `n` from the [`PARAMS_TABLE`].
+fn bind_to_params_table(sql: &str) -> String {
+    let mut out = String::with_capacity(sql.len());
+    let mut in_ident = false;
+    let mut chars = sql.chars().peekable();
+    while let Some(c) = chars.next() {
+        match c {
+            '"' => in_ident = !in_ident,
+            '$' if !in_ident => {
+                let mut n = String::new();
+                while let Some(digit) = chars.next_if(char::is_ascii_digit) {
+                    n.push(digit);
+                }
+                out.push_str(&format!(
+                    "(SELECT {} FROM pg_temp.{PARAMS_TABLE})",
+                    quote_ident(&format!("p{n}"))
+                ));
+                continue;
+            }
+            _ => {}
+        }
+        out.push(c);
+    }
+    out
+}
+
+/// Undoes the escaping of `COPY`'s text format in one row.
+fn unescape_copy_text(row: &[u8]) -> Bytes {
+    let mut out = Vec::with_capacity(row.len());
+    let mut bytes = row.iter();
+    while let Some(&b) = bytes.next() {
+        if b != b'\\' {
+            out.push(b);
+            continue;
+        }
+        match bytes.next() {
+            Some(b'n') => out.push(b'\n'),
+            Some(b'r') => out.push(b'\r'),
+            Some(b't') => out.push(b'\t'),
+            Some(b'b') => out.push(0x08),
+            Some(b'f') => out.push(0x0c),
+            Some(b'v') => out.push(0x0b),
+            Some(&other) => out.push(other),
+            None => out.push(b'\\'),
+        }
+    }
+    Bytes::from(out)
+}
+
+/// The connection of an export in progress. Dropping it before the last
+/// row, e.g. when the client disconnects, rolls the transaction back so the
+/// connection returns to the pool outside of it.
+struct OpenTransaction(Option<Object>);
+
+impl OpenTransaction {
+    async fn commit(mut self) -> Result<(), async_graphql::Error> {
+        let client = self.0.take().expect("transaction already finished");
+        client
+            .batch_execute("COMMIT")
+            .await
+            .map_err(|e| db_err("COMMIT error", e))
+    }
+}
+
+impl Deref for OpenTransaction {
+    type Target = Object;
+
+    fn deref(&self) -> &Object {
+        self.0.as_ref().expect("transaction already finished")
+    }
+}
+
+impl Drop for OpenTransaction {
+    fn drop(&mut self) {
+        if let Some(client) = self.0.take()
+            && let Ok(runtime) = tokio::runtime::Handle::try_current()
+        {
+            runtime.spawn(async move {
+                let _ = client.batch_execute("ROLLBACK").await;
+            });
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_bind_to_params_table() {
+        assert_eq!(
+            bind_to_params_table(" WHERE \"a$1\" = $1 AND \"b\" IN ($2, $10)"),
+            " WHERE \"a$1\" = (SELECT \"p1\" FROM pg_temp.\"turbograph_export_params\") \
+             AND \"b\" IN ((SELECT \"p2\" FROM pg_temp.\"turbograph_export_params\"), \
+             (SELECT \"p10\" FROM pg_temp.\"turbograph_export_params\"))"
+        );
+    }
+
+    #[test]
+    fn test_unescape_copy_text() {
+        assert_eq!(
+            unescape_copy_text(br#"{"a": "x\\"y", "b": "c:\\\\d"}"#),
+            Bytes::from_static(br#"{"a": "x\"y", "b": "c:\\d"}"#)
+        );
+        assert_eq!(
+            unescape_copy_text(b"a\\tb\\n"),
+            Bytes::from_static(b"a\tb\n")
+        );
+    }
+
+    #[test]
+    fn test_export_format_names() {
+        assert_eq!(ExportFormat::from_name("csv"), Some(ExportFormat::Csv));
+        assert_eq!(
+            ExportFormat::from_name("ndjson"),
+            Some(ExportFormat::Ndjson)
+        );
+        assert_eq!(ExportFormat::from_name("xlsx"), None);
+    }
+}
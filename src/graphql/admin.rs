@@ -0,0 +1,115 @@
+//! Operator-facing root fields, only added to the schema when
+//! [`Config::admin_roles`](crate::Config::admin_roles) is set and only
+//! answered for requests running as one of those roles.
+
+use std::sync::Arc;
+
+use async_graphql::dynamic::{Field, FieldFuture, FieldValue, Object, ResolverContext, TypeRef};
+use deadpool_postgres::Pool;
+
+use crate::db::pool::PoolMetrics;
+use crate::error::coded_err;
+use crate::models::transaction::TransactionConfig;
+
+/// Snapshot of the connection pool handed to the `PoolStatus` fields.
+struct PoolStatusPayload {
+    max_size: usize,
+    size: usize,
+    available: usize,
+    waiting: usize,
+    timeouts: u64,
+}
+
+/// The `PoolStatus` type and the `poolStatus` root field reporting the
+/// health of `pool`.
+pub(crate) fn generate_pool_status(
+    pool: Arc<Pool>,
+    admin_roles: Arc<Vec<String>>,
+) -> (Object, Field) {
+    let int_field = |name: &str, description: &str, get: fn(&PoolStatusPayload) -> u64| {
+        Field::new(name, TypeRef::named_nn(TypeRef::INT), move |ctx| {
+            FieldFuture::new(async move {
+                let status = ctx.parent_value.try_downcast_ref::<PoolStatusPayload>()?;
+                Ok(Some(FieldValue::value(
+                    i32::try_from(get(status)).unwrap_or(i32::MAX),
+                )))
+            })
+        })
+        .description(description)
+    };
+    let object = Object::new("PoolStatus")
+        .description("Health of the database connection pool.")
+        .field(int_field(
+            "maxSize",
+            "Most connections the pool will open.",
+            |s| s.max_size as u64,
+        ))
+        .field(int_field("size", "Connections currently open.", |s| {
+            s.size as u64
+        }))
+        .field(int_field(
+            "available",
+            "Open connections not checked out by a request.",
+            |s| s.available as u64,
+        ))
+        .field(int_field(
+            "waiting",
+            "Requests waiting for a connection.",
+            |s| s.waiting as u64,
+        ))
+        .field(int_field(
+            "timeouts",
+            "Requests that gave up waiting for a connection since startup.",
+            |s| s.timeouts,
+        ));
+
+    let field = Field::new("poolStatus", TypeRef::named_nn("PoolStatus"), move |ctx| {
+        let pool = pool.clone();
+        let admin_roles = admin_roles.clone();
+        FieldFuture::new(async move {
+            ensure_admin(&ctx, &admin_roles, "poolStatus")?;
+            let status = pool.status();
+            let timeouts = ctx
+                .data_opt::<Arc<PoolMetrics>>()
+                .map_or(0, |metrics| metrics.timeouts());
+            Ok(Some(FieldValue::owned_any(PoolStatusPayload {
+                max_size: status.max_size,
+                size: status.size,
+                available: status.available,
+                waiting: status.waiting,
+                timeouts,
+            })))
+        })
+    })
+    .description("Connection pool statistics. Requires an admin role.");
+
+    (object, field)
+}
+
+/// Fails with a `FORBIDDEN` error unless the request's
+/// [`TransactionConfig::role`] is one of `admin_roles`.
+fn ensure_admin(
+    ctx: &ResolverContext,
+    admin_roles: &[String],
+    field: &str,
+) -> Result<(), async_graphql::Error> {
+    ensure_role(ctx, admin_roles, || {
+        format!("{field} requires an admin role")
+    })
+}
+
+/// Fails with a `FORBIDDEN` error carrying `message` unless the request's
+/// [`TransactionConfig::role`] is one of `roles`.
+pub(crate) fn ensure_role(
+    ctx: &ResolverContext,
+    roles: &[String],
+    message: impl FnOnce() -> String,
+) -> Result<(), async_graphql::Error> {
+    let role = ctx
+        .data_opt::<TransactionConfig>()
+        .and_then(|cfg| cfg.role.as_deref());
+    match role {
+        Some(role) if roles.iter().any(|r| r == role) => Ok(()),
+        _ => Err(coded_err("FORBIDDEN", message())),
+    }
+}
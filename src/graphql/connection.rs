@@ -1,7 +1,17 @@
 use async_graphql::dynamic::{Field, FieldFuture, FieldValue, Object, TypeRef};
 use base64::Engine;
 
+use crate::error::coded_err;
 use crate::models::table::Table;
+use crate::utils::inflection::Inflector;
+
+/// Version of the cursor format. Bump it whenever the encoding or the meaning
+/// of the position changes, so cursors handed out by an older release are
+/// rejected instead of silently selecting the wrong page.
+const CURSOR_VERSION: u64 = 1;
+
+/// Error code of every cursor that cannot be used for the current query.
+pub const INVALID_CURSOR: &str = "INVALID_CURSOR";
 
 #[derive(Clone, Debug)]
 pub struct EdgePayload {
@@ -17,16 +27,145 @@ pub struct ConnectionPayload {
     pub edges: Vec<EdgePayload>,
 }
 
+/// FNV-1a hash of the ordering a cursor belongs to. Written out by hand
+/// because it must stay stable across Rust releases, unlike `std`'s hasher.
+fn order_hash(order_by: &[String]) -> String {
+    let spec = order_by.join(",").to_lowercase();
+    let hash = spec.bytes().fold(0xcbf2_9ce4_8422_2325_u64, |hash, byte| {
+        (hash ^ u64::from(byte)).wrapping_mul(0x0100_0000_01b3)
+    });
+    format!("{hash:016x}")
+}
+
+/// Cursor of the row at `abs_index` (0-based) of a result ordered by
+/// `order_by`: base64 of `[version, order hash, position]`.
 pub fn encode_cursor(order_by: &[String], abs_index: usize) -> String {
-    let json = if order_by.is_empty() {
-        serde_json::json!([abs_index + 1])
-    } else {
-        let keys: Vec<String> = order_by.iter().map(|s| s.to_lowercase()).collect();
-        serde_json::json!([keys, abs_index + 1])
-    };
+    let json = serde_json::json!([CURSOR_VERSION, order_hash(order_by), abs_index + 1]);
     base64::engine::general_purpose::STANDARD.encode(json.to_string())
 }
 
+/// The number of rows up to and including the one `cursor` points at, i.e.
+/// the offset of the first row after it. Fails with [`INVALID_CURSOR`] when
+/// the cursor is malformed, comes from another format version, or was
+/// created under a different ordering.
+pub fn decode_cursor(cursor: &str, order_by: &[String]) -> Result<i64, async_graphql::Error> {
+    let invalid = |reason: &str| coded_err(INVALID_CURSOR, format!("Invalid cursor: {reason}"));
+
+    let bytes = base64::engine::general_purpose::STANDARD
+        .decode(cursor)
+        .map_err(|_| invalid("not a cursor returned by this API"))?;
+    let parsed: Option<(u64, String, i64)> = serde_json::from_slice(&bytes).ok();
+    let Some((version, hash, position)) = parsed else {
+        return Err(invalid("not a cursor returned by this API"));
+    };
+    if version != CURSOR_VERSION {
+        return Err(invalid("created by an incompatible version"));
+    }
+    if hash != order_hash(order_by) {
+        return Err(invalid("created for a different orderBy"));
+    }
+    if position < 1 {
+        return Err(invalid("position out of range"));
+    }
+    Ok(position)
+}
+
+/// The rows the paging arguments of a connection select: the window after
+/// the `after` cursor (skipping `offset` more) and before the `before`
+/// cursor, then the `first` and `last` rows of it, as in the Relay spec.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Page {
+    /// Offset of the first row of the window.
+    pub start: i64,
+    /// Offset of the `before` cursor's row, which ends the window.
+    pub end: Option<i64>,
+    pub first: Option<i64>,
+    pub last: Option<i64>,
+}
+
+impl Page {
+    /// Whether the rows can only be picked once their total is known: `last`
+    /// counts back from the end, which without `before` is the last row.
+    pub fn needs_count(&self) -> bool {
+        self.last.is_some() && self.end.is_none()
+    }
+
+    /// The offset and number of the selected rows among `total` rows. Pass
+    /// `i64::MAX` when the total is not known and not [needed](Self::needs_count).
+    pub fn bounds(&self, total: i64) -> (i64, i64) {
+        let mut end = self.end.map_or(total, |end| end.min(total));
+        let mut start = self.start.min(end);
+        if let Some(first) = self.first {
+            end = end.min(start.saturating_add(first));
+        }
+        if let Some(last) = self.last {
+            start = start.max(end - last);
+        }
+        (start, end - start)
+    }
+}
+
+/// Added to the description of connections whose rows have no stable order.
+pub const OFFSET_CURSOR_WARNING: &str = "Rows have no unique key or `@stableOrder` tag, so \
+    cursors are plain offsets: rows inserted or deleted between requests may be skipped or \
+    returned twice.";
+
+/// How the rows of a connection keep their order between requests. Cursors
+/// are positions, so they only point at the same row again when ties in
+/// `orderBy` are always broken the same way.
+#[derive(Debug, PartialEq)]
+pub enum CursorStability {
+    /// The primary key identifies every row.
+    Keyed,
+    /// No unique key, but these columns (database names) from the
+    /// `@stableOrder` tag break ties after `orderBy`.
+    Tiebreak(Vec<String>),
+    /// Nothing breaks ties; see [`OFFSET_CURSOR_WARNING`].
+    Offset,
+}
+
+impl CursorStability {
+    /// The stability of `table`'s connection. `@stableOrder` columns that do
+    /// not exist or are not readable are skipped with a warning.
+    pub fn of(table: &Table) -> Self {
+        if !table.primary_key().is_empty() {
+            return Self::Keyed;
+        }
+        let columns: Vec<String> = table
+            .stable_order()
+            .iter()
+            .filter(|name| {
+                let readable = table
+                    .columns()
+                    .iter()
+                    .any(|c| c.name() == name.as_str() && !c.omit_read());
+                if !readable {
+                    eprintln!(
+                        "[turbograph] ignoring @stableOrder on {}.{}: unknown column {name}",
+                        table.schema_name(),
+                        table.name()
+                    );
+                }
+                readable
+            })
+            .cloned()
+            .collect();
+        if columns.is_empty() {
+            Self::Offset
+        } else {
+            Self::Tiebreak(columns)
+        }
+    }
+
+    /// The columns to order by after `orderBy`.
+    pub fn tiebreak(&self) -> &[String] {
+        match self {
+            Self::Tiebreak(columns) => columns,
+            Self::Keyed | Self::Offset => &[],
+        }
+    }
+}
+
 // ── Shared PageInfo type (register once globally) ───────────────────────────
 
 pub fn make_page_info_type() -> Object {
@@ -88,10 +227,10 @@ pub fn make_page_info_type() -> Object {
 /// The connection type includes totalCount, pageInfo, edges, and nodes fields; the edge type includes cursor and node fields.
 /// The node field in both types references the main entity type for the table.
 /// example: for a "User" table, generates "UserConnection" and "UserEdge" types with appropriate fields and resolvers.
-pub fn make_connection_types(table: &Table) -> (Object, Object) {
+pub fn make_connection_types(table: &Table, inflector: &dyn Inflector) -> (Object, Object) {
     let type_name = table.type_name();
-    let edge_type_name = format!("{}Edge", type_name);
-    let connection_type_name = format!("{}Connection", type_name);
+    let edge_type_name = inflector.edge_type(&type_name);
+    let connection_type_name = inflector.connection_type(&type_name);
 
     let node_type = type_name.clone();
     let edge = Object::new(&edge_type_name)
@@ -167,3 +306,96 @@ pub fn make_connection_types(table: &Table) -> (Object, Object) {
 
     (connection, edge)
 }
+
+#[cfg(test)]
+mod tests {
+    use tokio_postgres::types::Type;
+
+    use super::*;
+    use crate::models::table::Column;
+
+    fn code(err: &async_graphql::Error) -> Option<String> {
+        match err.extensions.as_ref()?.get("code")? {
+            async_graphql::Value::String(code) => Some(code.clone()),
+            _ => None,
+        }
+    }
+
+    #[test]
+    fn test_cursor_round_trip() {
+        let order_by = vec!["NAME_DESC".to_string(), "ID_ASC".to_string()];
+        let cursor = encode_cursor(&order_by, 4);
+        assert_eq!(decode_cursor(&cursor, &order_by).unwrap(), 5);
+    }
+
+    #[test]
+    fn test_cursor_from_other_ordering_is_rejected() {
+        let cursor = encode_cursor(&["NAME_DESC".to_string()], 0);
+        let err = decode_cursor(&cursor, &["NAME_ASC".to_string()]).unwrap_err();
+        assert_eq!(code(&err).as_deref(), Some(INVALID_CURSOR));
+        assert!(err.message.contains("orderBy"), "{}", err.message);
+    }
+
+    #[test]
+    fn test_cursor_from_other_version_is_rejected() {
+        // The unversioned format, base64 of `[["name_desc"],1]`.
+        let err =
+            decode_cursor("W1sibmFtZV9kZXNjIl0sMV0=", &["NAME_DESC".to_string()]).unwrap_err();
+        assert_eq!(code(&err).as_deref(), Some(INVALID_CURSOR));
+
+        let future = base64::engine::general_purpose::STANDARD
+            .encode(serde_json::json!([2, order_hash(&[]), 1]).to_string());
+        let err = decode_cursor(&future, &[]).unwrap_err();
+        assert!(err.message.contains("version"), "{}", err.message);
+    }
+
+    #[test]
+    fn test_cursor_stability() {
+        let columns = || {
+            vec![
+                Column::new_for_test("day", Type::DATE, false, false),
+                Column::new_for_test("secret", Type::TEXT, false, true),
+            ]
+        };
+        let keyed = Table::new_for_test("totals", columns()).with_primary_key(&["day"]);
+        assert_eq!(CursorStability::of(&keyed), CursorStability::Keyed);
+
+        let tagged = Table::new_for_test("totals", columns())
+            .with_comment("@stableOrder day, secret, missing");
+        assert_eq!(
+            CursorStability::of(&tagged),
+            CursorStability::Tiebreak(vec!["day".to_string()])
+        );
+
+        let bare = Table::new_for_test("totals", columns());
+        assert_eq!(CursorStability::of(&bare), CursorStability::Offset);
+    }
+
+    #[test]
+    fn test_page_bounds() {
+        let page = |start, end, first, last| Page {
+            start,
+            end,
+            first,
+            last,
+        };
+        // first: 2, after the 3rd row
+        assert_eq!(page(3, None, Some(2), None).bounds(i64::MAX), (3, 2));
+        // last: 2 of 10 rows
+        assert!(page(0, None, None, Some(2)).needs_count());
+        assert_eq!(page(0, None, None, Some(2)).bounds(10), (8, 2));
+        // last: 3, before the 5th row
+        assert_eq!(page(0, Some(4), None, Some(3)).bounds(i64::MAX), (1, 3));
+        // between cursors, fewer rows than asked for
+        assert_eq!(page(2, Some(4), Some(10), None).bounds(i64::MAX), (2, 2));
+        assert_eq!(page(6, Some(4), Some(10), None).bounds(i64::MAX), (4, 0));
+        // first, then last of those
+        assert_eq!(page(0, None, Some(5), Some(2)).bounds(10), (3, 2));
+    }
+
+    #[test]
+    fn test_garbage_cursor_is_rejected() {
+        let err = decode_cursor("not a cursor!", &[]).unwrap_err();
+        assert_eq!(code(&err).as_deref(), Some(INVALID_CURSOR));
+    }
+}
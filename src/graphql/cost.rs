@@ -0,0 +1,52 @@
+use std::sync::{Arc, Mutex};
+
+use async_graphql::extensions::{
+    Extension, ExtensionContext, ExtensionFactory, NextRequest, NextValidation,
+};
+use async_graphql::{Response, ServerError, ValidationResult, value};
+
+/// Reports the complexity of every successful operation next to the
+/// configured limit as `extensions.cost { complexity, limit }`, so clients
+/// can see how close a query is to being rejected.
+pub(crate) struct CostReport {
+    pub limit: usize,
+}
+
+impl ExtensionFactory for CostReport {
+    fn create(&self) -> Arc<dyn Extension> {
+        Arc::new(CostReportExtension {
+            limit: self.limit,
+            complexity: Mutex::new(None),
+        })
+    }
+}
+
+struct CostReportExtension {
+    limit: usize,
+    complexity: Mutex<Option<usize>>,
+}
+
+#[async_graphql::async_trait::async_trait]
+impl Extension for CostReportExtension {
+    async fn request(&self, ctx: &ExtensionContext<'_>, next: NextRequest<'_>) -> Response {
+        let response = next.run(ctx).await;
+        let complexity = self.complexity.lock().unwrap().take();
+        match complexity {
+            Some(complexity) if response.is_ok() => response.extension(
+                "cost",
+                value!({ "complexity": complexity, "limit": self.limit }),
+            ),
+            _ => response,
+        }
+    }
+
+    async fn validation(
+        &self,
+        ctx: &ExtensionContext<'_>,
+        next: NextValidation<'_>,
+    ) -> Result<ValidationResult, Vec<ServerError>> {
+        let result = next.run(ctx).await?;
+        *self.complexity.lock().unwrap() = Some(result.complexity);
+        Ok(result)
+    }
+}
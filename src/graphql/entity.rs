@@ -1,36 +1,129 @@
 use std::sync::Arc;
 
-use async_graphql::dynamic::{Field, FieldFuture, Object};
+use async_graphql::dynamic::{Field, FieldFuture, FieldValue, Object};
 
+use crate::models::config::NonFiniteFloats;
+use crate::models::description::Descriptions;
+use crate::models::encryption::{EncryptedColumn, Encryption, decrypt};
+use crate::models::federation::Federation;
 use crate::models::table::{Column, Table};
 
+use super::example;
+use super::node::{NODE_INTERFACE, node_id_field};
+use super::scalars::CustomScalars;
 use super::type_mapping::{get_field_value, get_type_ref};
 
-fn generate_field(column: Arc<Column>) -> Field {
-    Field::new(
-        column.name().to_string(),
-        get_type_ref(&column),
+/// The description for `locale` followed by the `@example` line, if any.
+fn description(
+    descriptions: &Descriptions,
+    example: Option<&serde_json::Value>,
+    locale: Option<&str>,
+) -> Option<String> {
+    let parts: Vec<String> = descriptions
+        .get(locale)
+        .map(str::to_string)
+        .into_iter()
+        .chain(example.map(example::describe))
+        .collect();
+    (!parts.is_empty()).then(|| parts.join("\n\n"))
+}
+
+fn generate_field(
+    table: &Table,
+    column: Arc<Column>,
+    locale: Option<&str>,
+    custom_scalars: Option<&CustomScalars>,
+) -> Field {
+    let federation = column.federation().clone();
+    let description = description(column.descriptions(), column.example(), locale);
+    let encrypted = column
+        .encrypted()
+        .then(|| EncryptedColumn::new(table.schema_name(), table.name(), column.name()));
+    let mut field = Field::new(
+        column.field_name().to_string(),
+        get_type_ref(&column, custom_scalars),
         move |ctx| {
             let column = column.clone();
+            let encrypted = encrypted.clone();
 
             FieldFuture::new(async move {
                 let parent_value = ctx.parent_value.try_downcast_ref::<serde_json::Value>()?;
-                let field_value = get_field_value(&column, parent_value);
-                Ok(field_value)
+                if let Some(target) = encrypted {
+                    let Some(ciphertext) = parent_value.get(column.name()).and_then(|v| v.as_str())
+                    else {
+                        return Ok(None);
+                    };
+                    let plaintext =
+                        decrypt(ctx.data_opt::<Encryption>(), target, ciphertext.to_string())
+                            .await?;
+                    return Ok(Some(FieldValue::value(plaintext)));
+                }
+                let floats = ctx.data_opt::<NonFiniteFloats>().copied();
+                get_field_value(&column, parent_value, floats.unwrap_or_default())
             })
         },
-    )
+    );
+    if let Some(description) = description {
+        field = field.description(description);
+    }
+    apply_field_federation(field, federation)
 }
 
-pub fn generate_entity(table: Arc<Table>) -> Object {
+fn apply_field_federation(mut field: Field, federation: Federation) -> Field {
+    if federation.shareable {
+        field = field.shareable();
+    }
+    if federation.inaccessible {
+        field = field.inaccessible();
+    }
+    if federation.external {
+        field = field.external();
+    }
+    if let Some(from) = federation.override_from {
+        field = field.override_from(from);
+    }
+    field.tags(federation.tags)
+}
+
+fn apply_object_federation(mut obj: Object, federation: &Federation) -> Object {
+    if federation.shareable {
+        obj = obj.shareable();
+    }
+    if federation.inaccessible {
+        obj = obj.inaccessible();
+    }
+    obj.tags(federation.tags.clone())
+}
+
+/// Generates the object type for a table. With `node_id` set the type
+/// implements `Node` and gets its global ID field under that name, first.
+/// Descriptions are taken in `locale` when the comments provide it.
+/// The object type of `table`. Columns named in `hidden` are left out of it.
+pub fn generate_entity(
+    table: Arc<Table>,
+    node_id: Option<&str>,
+    locale: Option<&str>,
+    hidden: &[String],
+    custom_scalars: Option<&CustomScalars>,
+) -> Object {
     let type_name = table.type_name();
-    let obj = Object::new(type_name.as_str());
+    let mut obj = apply_object_federation(Object::new(type_name.as_str()), table.federation());
+    if let Some(description) = description(table.descriptions(), table.example(), locale) {
+        obj = obj.description(description);
+    }
+    if let Some(field_name) = node_id {
+        obj = obj
+            .implement(NODE_INTERFACE)
+            .field(node_id_field(&table, field_name));
+    }
 
     table
         .columns()
         .iter()
-        .filter(|col| !col.omit_read())
-        .fold(obj, |obj, col| obj.field(generate_field(col.clone())))
+        .filter(|col| !col.omit_read() && !hidden.contains(col.name()))
+        .fold(obj, |obj, col| {
+            obj.field(generate_field(&table, col.clone(), locale, custom_scalars))
+        })
 }
 
 #[cfg(test)]
@@ -43,19 +136,28 @@ mod tests {
     #[test]
     fn test_entity_name_singularized_and_pascal_cased() {
         let table = Arc::new(Table::new_for_test("blog_posts", vec![]));
-        assert_eq!(generate_entity(table).type_name(), "BlogPost");
+        assert_eq!(
+            generate_entity(table, None, None, &[], None).type_name(),
+            "BlogPost"
+        );
     }
 
     #[test]
     fn test_entity_name_already_singular() {
         let table = Arc::new(Table::new_for_test("users", vec![]));
-        assert_eq!(generate_entity(table).type_name(), "User");
+        assert_eq!(
+            generate_entity(table, None, None, &[], None).type_name(),
+            "User"
+        );
     }
 
     #[test]
     fn test_entity_name_single_word() {
         let table = Arc::new(Table::new_for_test("orders", vec![]));
-        assert_eq!(generate_entity(table).type_name(), "Order");
+        assert_eq!(
+            generate_entity(table, None, None, &[], None).type_name(),
+            "Order"
+        );
     }
 
     #[test]
@@ -63,13 +165,13 @@ mod tests {
         let visible = Column::new_for_test("name", Type::TEXT, false, false);
         let hidden = Column::new_for_test("secret", Type::TEXT, false, true);
         let table = Arc::new(Table::new_for_test("users", vec![visible, hidden]));
-        generate_entity(table);
+        generate_entity(table, None, None, &[], None);
     }
 
     #[test]
     fn test_entity_no_columns_empty_object() {
         let table = Arc::new(Table::new_for_test("tokens", vec![]));
-        let obj = generate_entity(table);
+        let obj = generate_entity(table, None, None, &[], None);
         assert_eq!(obj.type_name(), "Token");
     }
 }
@@ -0,0 +1,95 @@
+//! `@example` tags: sample values shown in descriptions and preloaded into
+//! GraphiQL.
+
+use crate::models::catalog::Catalog;
+use crate::models::grants::Privilege;
+use crate::utils::inflection::to_camel_case;
+
+/// The description line documenting an `@example` value.
+pub(crate) fn describe(example: &serde_json::Value) -> String {
+    format!("Example: `{example}`")
+}
+
+/// A ready-to-run operation built from the `@example` tags of a catalog,
+/// used as the initial GraphiQL document and variables.
+#[derive(Clone, Debug, PartialEq)]
+pub(crate) struct GraphiqlExample {
+    pub query: String,
+    pub variables: serde_json::Value,
+}
+
+/// One `create{T}` mutation per table with an `@example` tag, fed by a
+/// `${t}Input` variable holding the example. `None` when no table has one.
+pub(crate) fn graphiql_example(catalog: &Catalog) -> Option<GraphiqlExample> {
+    let mut operations = Vec::new();
+    let mut variables = serde_json::Map::new();
+
+    for table in catalog.tables() {
+        let Some(example) = table.example() else {
+            continue;
+        };
+        if !table.is_exposed_for(Privilege::Insert) {
+            continue;
+        }
+
+        let type_name = table.type_name();
+        let variable = format!("{}Input", to_camel_case(&type_name));
+        let selection: Vec<&str> = table
+            .columns()
+            .iter()
+            .filter(|c| !c.omit_read())
+            .map(|c| c.field_name())
+            .collect();
+        operations.push(format!(
+            "mutation Create{type_name}Example(${variable}: Create{type_name}Input!) {{\n  \
+             create{type_name}(input: ${variable}) {{\n    {}\n  }}\n}}\n",
+            selection.join("\n    ")
+        ));
+        variables.insert(variable, example.clone());
+    }
+
+    if operations.is_empty() {
+        return None;
+    }
+    Some(GraphiqlExample {
+        query: operations.join("\n"),
+        variables: serde_json::Value::Object(variables),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::table::{Column, Table};
+    use tokio_postgres::types::Type;
+
+    #[test]
+    fn test_graphiql_example_from_table_tag() {
+        let columns = vec![
+            Column::new_for_test("id", Type::INT4, false, false),
+            Column::new_for_test("title", Type::TEXT, false, false),
+        ];
+        let catalog = Catalog::new(vec![
+            Table::new_for_test("blog_posts", columns)
+                .with_comment(r#"@example {"title": "Hello"}"#),
+            Table::new_for_test("tags", vec![]).with_oid(1),
+        ]);
+
+        let example = graphiql_example(&catalog).unwrap();
+        assert_eq!(
+            example.query,
+            "mutation CreateBlogPostExample($blogPostInput: CreateBlogPostInput!) {\n  \
+             createBlogPost(input: $blogPostInput) {\n    id\n    title\n  }\n}\n"
+        );
+        assert_eq!(
+            example.variables,
+            serde_json::json!({ "blogPostInput": { "title": "Hello" } })
+        );
+    }
+
+    #[test]
+    fn test_no_example_tags() {
+        let catalog = Catalog::new(vec![Table::new_for_test("tags", vec![])]);
+        assert_eq!(graphiql_example(&catalog), None);
+    }
+}
@@ -1,52 +1,13 @@
 use async_graphql::dynamic::{Enum, EnumItem, InputObject, InputValue, TypeRef};
 use tokio_postgres::types::Type;
 
-use crate::models::table::Table;
+use crate::models::config::UnindexedPatternFilters;
+use crate::models::table::{Column, Table};
 use crate::utils::inflection::to_pascal_case;
 
 use super::type_mapping::condition_type_ref;
 
-#[derive(Clone, Copy, Debug, PartialEq, Eq)]
-pub enum FilterOp {
-    Eq,
-    NotEqual,
-    In,
-    Gt,
-    Gte,
-    Lt,
-    Lte,
-}
-
-impl FilterOp {
-    pub fn from_key(key: &str) -> Option<Self> {
-        match key {
-            "equal" => Some(Self::Eq),
-            "notEqual" => Some(Self::NotEqual),
-            "in" => Some(Self::In),
-            "greaterThan" => Some(Self::Gt),
-            "greaterThanEqual" => Some(Self::Gte),
-            "lessThan" => Some(Self::Lt),
-            "lessThanEqual" => Some(Self::Lte),
-            _ => None,
-        }
-    }
-
-    pub fn sql_operator(self) -> &'static str {
-        match self {
-            Self::Eq => "=",
-            Self::NotEqual => "<>",
-            Self::Gt => ">",
-            Self::Gte => ">=",
-            Self::Lt => "<",
-            Self::Lte => "<=",
-            Self::In => unreachable!("IN is not a simple binary operator"),
-        }
-    }
-
-    pub fn is_range(self) -> bool {
-        matches!(self, Self::Gt | Self::Gte | Self::Lt | Self::Lte)
-    }
-}
+pub(crate) use crate::ir::FilterOp;
 
 pub fn supports_range(column_type: &Type) -> bool {
     matches!(
@@ -64,9 +25,30 @@ pub fn supports_range(column_type: &Type) -> bool {
     )
 }
 
+/// Whether `like` and `likeInsensitive` apply to the column: text columns
+/// that do not stand for an `@enum` table.
+pub fn supports_pattern(column: &Column) -> bool {
+    matches!(
+        *column.base_type(),
+        Type::TEXT | Type::VARCHAR | Type::BPCHAR
+    ) && column.enum_type().is_none()
+}
+
+/// Whether `op` applies to the column.
+pub(crate) fn supports_op(op: FilterOp, column: &Column) -> bool {
+    (!op.is_range() || supports_range(column.base_type()))
+        && (!op.is_pattern() || supports_pattern(column))
+}
+
 /// Builds per-column `{TypeName}{Column}Filter` input objects referenced by
 /// `{TypeName}Condition`. Exported so callers can register them with the schema.
-pub fn make_condition_filter_types(table: &Table) -> Vec<InputObject> {
+///
+/// Text columns get `like` and `likeInsensitive`, unless `unindexed_patterns`
+/// omits the ones no index serves (see [`Table::pattern_indexed`]).
+pub fn make_condition_filter_types(
+    table: &Table,
+    unindexed_patterns: UnindexedPatternFilters,
+) -> Vec<InputObject> {
     table
         .columns()
         .iter()
@@ -74,8 +56,11 @@ pub fn make_condition_filter_types(table: &Table) -> Vec<InputObject> {
         .filter_map(|col| {
             condition_type_ref(col).map(|tr| {
                 let scalar_name = tr.to_string();
-                let filter_name =
-                    format!("{}{}Filter", table.type_name(), to_pascal_case(col.name()));
+                let filter_name = format!(
+                    "{}{}Filter",
+                    table.type_name(),
+                    to_pascal_case(col.field_name())
+                );
 
                 // example generated input object for a "email" column of type String:
                 // input UserEmailFilter {
@@ -86,12 +71,31 @@ pub fn make_condition_filter_types(table: &Table) -> Vec<InputObject> {
                     .field(InputValue::new("notEqual", tr.clone()))
                     .field(InputValue::new("in", TypeRef::named_list(scalar_name)));
 
-                if supports_range(col._type()) {
+                if supports_range(col.base_type()) {
                     input = input
                         .field(InputValue::new("greaterThan", tr.clone()))
                         .field(InputValue::new("greaterThanEqual", tr.clone()))
                         .field(InputValue::new("lessThan", tr.clone()))
-                        .field(InputValue::new("lessThanEqual", tr));
+                        .field(InputValue::new("lessThanEqual", tr.clone()));
+                }
+
+                if supports_pattern(col) {
+                    for op in [FilterOp::Like, FilterOp::ILike] {
+                        let indexed = table.pattern_indexed(col, op == FilterOp::ILike);
+                        let field = InputValue::new(op.key(), tr.clone());
+                        input = match unindexed_patterns {
+                            UnindexedPatternFilters::Omit if !indexed => continue,
+                            UnindexedPatternFilters::Describe if !indexed => {
+                                input.field(field.description(format!(
+                                    "Potentially slow: no index on `{}` serves `{}`, so \
+                                     matching scans every row.",
+                                    col.name(),
+                                    op.sql_operator()
+                                )))
+                            }
+                            _ => input.field(field),
+                        };
+                    }
                 }
 
                 input
@@ -111,10 +115,13 @@ pub fn make_condition_type(table: &Table) -> InputObject {
         .filter(|c| !c.omit_read())
         .fold(InputObject::new(name), |obj, col| {
             if condition_type_ref(col).is_some() {
-                let filter_name =
-                    format!("{}{}Filter", table.type_name(), to_pascal_case(col.name()));
+                let filter_name = format!(
+                    "{}{}Filter",
+                    table.type_name(),
+                    to_pascal_case(col.field_name())
+                );
                 obj.field(InputValue::new(
-                    col.name().as_str(),
+                    col.field_name(),
                     TypeRef::named(filter_name),
                 ))
             } else {
@@ -125,14 +132,34 @@ pub fn make_condition_type(table: &Table) -> InputObject {
 
 /// Builds the `{TypeName}OrderBy` enum (COLUMN_ASC / COLUMN_DESC per column).
 /// Exported so callers can register it with the schema separately.
-pub fn make_order_by_enum(table: &Table) -> Enum {
+///
+/// See [`Table::orderable`] for the columns `indexed_order_by_rows` leaves
+/// out. A large table without any indexed column keeps them all, with a
+/// warning, as an enum needs at least one value.
+pub fn make_order_by_enum(table: &Table, indexed_order_by_rows: Option<u64>) -> Enum {
     let name = format!("{}OrderBy", table.type_name());
-    table
+    let columns: Vec<_> = table
         .columns()
         .iter()
-        .filter(|c| !c.omit_read())
+        .filter(|c| !c.omit_read() && !c.encrypted())
+        .collect();
+    let orderable: Vec<_> = columns
+        .iter()
+        .filter(|c| table.orderable(c, indexed_order_by_rows))
+        .collect();
+    let columns = if orderable.is_empty() && !columns.is_empty() {
+        eprintln!(
+            "[turbograph] {name}: `{}` has no indexed column to order by; offering every column",
+            table.name()
+        );
+        columns.iter().collect()
+    } else {
+        orderable
+    };
+    columns
+        .into_iter()
         .flat_map(|c| {
-            let upper = c.name().to_uppercase();
+            let upper = c.field_name().to_uppercase();
             [
                 EnumItem::new(format!("{}_ASC", upper)),
                 EnumItem::new(format!("{}_DESC", upper)),
@@ -162,55 +189,16 @@ mod tests {
     #[test]
     fn test_order_by_enum_name() {
         let table = Table::new_for_test("blog_posts", vec![]);
-        assert_eq!(make_order_by_enum(&table).type_name(), "BlogPostOrderBy");
+        assert_eq!(
+            make_order_by_enum(&table, None).type_name(),
+            "BlogPostOrderBy"
+        );
     }
 
     #[test]
     fn test_order_by_enum_name_users() {
         let table = Table::new_for_test("users", vec![]);
-        assert_eq!(make_order_by_enum(&table).type_name(), "UserOrderBy");
-    }
-
-    #[test]
-    fn test_filter_op_from_key_not_equal() {
-        assert_eq!(FilterOp::from_key("notEqual"), Some(FilterOp::NotEqual));
-    }
-
-    #[test]
-    fn test_filter_op_from_key_range() {
-        assert_eq!(FilterOp::from_key("greaterThanEqual"), Some(FilterOp::Gte));
-        assert_eq!(FilterOp::from_key("lessThan"), Some(FilterOp::Lt));
-    }
-
-    #[test]
-    fn test_filter_op_from_key_default_eq() {
-        assert_eq!(FilterOp::from_key("equal"), Some(FilterOp::Eq));
-    }
-
-    #[test]
-    fn test_filter_op_from_key_unknown() {
-        assert_eq!(FilterOp::from_key("between"), None);
-    }
-
-    #[test]
-    fn test_filter_op_sql_operator() {
-        assert_eq!(FilterOp::Eq.sql_operator(), "=");
-        assert_eq!(FilterOp::NotEqual.sql_operator(), "<>");
-        assert_eq!(FilterOp::Gt.sql_operator(), ">");
-        assert_eq!(FilterOp::Gte.sql_operator(), ">=");
-        assert_eq!(FilterOp::Lt.sql_operator(), "<");
-        assert_eq!(FilterOp::Lte.sql_operator(), "<=");
-    }
-
-    #[test]
-    fn test_filter_op_is_range() {
-        assert!(!FilterOp::Eq.is_range());
-        assert!(!FilterOp::NotEqual.is_range());
-        assert!(!FilterOp::In.is_range());
-        assert!(FilterOp::Gt.is_range());
-        assert!(FilterOp::Gte.is_range());
-        assert!(FilterOp::Lt.is_range());
-        assert!(FilterOp::Lte.is_range());
+        assert_eq!(make_order_by_enum(&table, None).type_name(), "UserOrderBy");
     }
 
     #[test]
@@ -0,0 +1,91 @@
+//! Root query fields that fetch a single row by one of its unique keys, e.g.
+//! `userById(id: 1)` or `membershipByOrgIdAndUserId(orgId: 1, userId: 2)`.
+
+use std::sync::Arc;
+
+use async_graphql::dynamic::{Field, FieldFuture, FieldValue, InputValue, TypeRef};
+use deadpool_postgres::Pool;
+
+use crate::db::request::RequestScope;
+use crate::error::gql_err;
+use crate::models::mock::MockData;
+use crate::models::table::{Column, Table, UniqueKey};
+use crate::utils::inflection::finder_name;
+
+use super::mock;
+use super::prefetch::Prefetch;
+use super::relation::fetch_by_key;
+use super::type_mapping::{condition_type_ref, to_sql_scalar};
+
+/// One finder per [`Table::finder_keys`] entry, taking every key column as a
+/// required argument and returning the matching row or `null`. Keys over a
+/// column that cannot be used as an argument (e.g. an array) get none. A
+/// `@fieldName` tag on the key's constraint renames its finder. Each comes
+/// with its name.
+pub(crate) fn generate_finders(table: &Arc<Table>, pool: &Arc<Pool>) -> Vec<(String, Field)> {
+    table
+        .finder_keys()
+        .into_iter()
+        .filter_map(|key| generate_finder(table.clone(), key, pool.clone()))
+        .collect()
+}
+
+fn generate_finder(table: Arc<Table>, key: &UniqueKey, pool: Arc<Pool>) -> Option<(String, Field)> {
+    let columns: Vec<Arc<Column>> = key
+        .columns()
+        .iter()
+        .map(|name| table.columns().iter().find(|c| c.name() == name).cloned())
+        .collect::<Option<_>>()?;
+    let arguments: Vec<InputValue> = columns
+        .iter()
+        .map(|c| {
+            let type_ref = condition_type_ref(c)?;
+            Some(InputValue::new(
+                c.field_name(),
+                TypeRef::named_nn(type_ref.to_string()),
+            ))
+        })
+        .collect::<Option<_>>()?;
+
+    let field_names: Vec<&str> = columns.iter().map(|c| c.field_name()).collect();
+    let name = key
+        .field_name_tag()
+        .unwrap_or_else(|| finder_name(&table.type_name(), &field_names));
+    let key_columns = Arc::new(key.columns().to_vec());
+
+    let field = Field::new(&name, TypeRef::named(table.type_name()), move |ctx| {
+        let table = table.clone();
+        let pool = pool.clone();
+        let columns = columns.clone();
+        let key_columns = key_columns.clone();
+        let scope = RequestScope::from_ctx(&ctx);
+        let mock_data = ctx.data_opt::<Arc<MockData>>().cloned();
+
+        FieldFuture::new(async move {
+            let mut params = Vec::with_capacity(columns.len());
+            for column in &columns {
+                let value = ctx.args.try_get(column.field_name())?;
+                let scalar = to_sql_scalar(column, value.as_value())
+                    .ok_or_else(|| gql_err(format!("Invalid value for {}", column.field_name())))?;
+                params.push(scalar);
+            }
+
+            let rows = match mock_data {
+                Some(mock_data) => {
+                    let key: Vec<serde_json::Value> = params.iter().map(|p| p.to_json()).collect();
+                    mock::filter_by_key(
+                        mock_data.rows(table.schema_name(), table.name()),
+                        &key_columns,
+                        &key,
+                    )
+                }
+                None => {
+                    let prefetch = Prefetch::for_field(&ctx, &table);
+                    fetch_by_key(&pool, &table, &key_columns, params, &prefetch, scope).await?
+                }
+            };
+            Ok(rows.into_iter().next().map(FieldValue::owned_any))
+        })
+    });
+    Some((name, arguments.into_iter().fold(field, Field::argument)))
+}
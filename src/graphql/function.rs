@@ -0,0 +1,466 @@
+//! Functions exposed as query fields (when `STABLE` or `IMMUTABLE`),
+//! mutations, and computed columns. Each field selects
+//! `schema.function(name => $1::type, ...)` and resolves the result as the
+//! function's return type: a scalar, the entity type of a table, a
+//! `{F}Record` type generated from its `OUT` arguments or `RETURNS TABLE`
+//! columns, or a list of those for a set-returning function.
+
+use std::sync::Arc;
+
+use async_graphql::Value as GqlValue;
+use async_graphql::dynamic::{
+    Field, FieldFuture, FieldValue, InputObject, InputValue, Object, TypeRef,
+};
+use deadpool_postgres::Pool;
+
+use crate::db::JsonExt;
+use crate::db::request::RequestScope;
+use crate::db::transaction::with_transaction;
+use crate::error::{db_err, gql_err};
+use crate::models::catalog::Catalog;
+use crate::models::config::NonFiniteFloats;
+use crate::models::function::{Function, FunctionReturn};
+use crate::models::grants::Privilege;
+use crate::models::mock::MockData;
+use crate::models::row_transform::transform_rows;
+use crate::models::table::{Column, Table};
+use crate::sql::{param_refs, quote_ident, quote_qualified};
+use crate::utils::inflection::{column_field_name, to_camel_case, to_pascal_case};
+
+use super::permission::row_key;
+use super::procedure::{
+    CallArg, bind_args, call_args, has_null_input, input_fields, input_values, output_object,
+};
+use super::scalars::CustomScalars;
+use super::sql_scalar::SqlScalar;
+use super::type_mapping::{get_field_value, get_type_ref};
+
+/// The root field generated for one function.
+pub(crate) struct GeneratedFunction {
+    pub field_name: String,
+    pub field: Field,
+    /// Whether the field goes on the query root.
+    pub read_only: bool,
+    /// `{F}Input`, for a mutation that takes arguments. Query fields take
+    /// their arguments directly.
+    pub input: Option<InputObject>,
+    /// `{F}Record`, for a function returning records.
+    pub record: Option<Object>,
+}
+
+/// The computed column fields of a table, and the `{F}Record` types of those
+/// returning records.
+pub(crate) struct ComputedColumns {
+    pub fields: Vec<Field>,
+    pub records: Vec<Object>,
+}
+
+/// What the resolvers of a function field share.
+struct Call {
+    function: Arc<Function>,
+    args: Vec<CallArg>,
+    pool: Arc<Pool>,
+    /// Schema and name of the table whose rows the function returns, which
+    /// pass through its row transforms.
+    row_table: Option<(String, String)>,
+}
+
+/// Generates the query or mutation field for `function`, or `None` (with a
+/// warning) when an argument has no GraphQL input type or the function
+/// returns rows of a table that is not exposed.
+pub(crate) fn generate_function(
+    function: Arc<Function>,
+    catalog: &Catalog,
+    pool: Arc<Pool>,
+    locale: Option<&str>,
+    camel_case_fields: bool,
+    custom_scalars: Option<&CustomScalars>,
+) -> Option<GeneratedFunction> {
+    let field_name = to_camel_case(function.name());
+    let read_only = function.volatility().is_read_only();
+    let return_type = return_type(&function, catalog, custom_scalars)?;
+    let record = record_type(&function, camel_case_fields, custom_scalars);
+    let args = call_args(function.args(), camel_case_fields);
+    let arguments = arguments(&function, &args)?;
+
+    let (mut input, mut direct) = (None, Vec::new());
+    if read_only {
+        direct = arguments;
+    } else if !arguments.is_empty() {
+        let object = InputObject::new(format!("{}Input", to_pascal_case(function.name())));
+        input = Some(arguments.into_iter().fold(object, InputObject::field));
+    }
+
+    let call = Arc::new(Call {
+        row_table: returned_table(&function, catalog),
+        function: function.clone(),
+        args,
+        pool,
+    });
+    let mut field = Field::new(&field_name, return_type, move |ctx| {
+        let input = if read_only {
+            direct_args(&ctx)
+        } else {
+            input_fields(&ctx)
+        };
+        let call = call.clone();
+        let scope = RequestScope::from_ctx(&ctx);
+        let mock_data = ctx.data_opt::<Arc<MockData>>().is_some();
+        let floats = ctx.data_opt::<NonFiniteFloats>().copied();
+
+        FieldFuture::new(async move {
+            if mock_data {
+                return Err(gql_err("Functions cannot be called in mock mode"));
+            }
+            let mut params = Vec::new();
+            let sql = call.root_sql(&input, &mut params)?;
+            call.execute(sql, params, &input, scope, floats.unwrap_or_default())
+                .await
+        })
+    });
+    if let Some(input) = &input {
+        field = field.argument(InputValue::new(
+            "input",
+            TypeRef::named_nn(input.type_name()),
+        ));
+    }
+    for argument in direct {
+        field = field.argument(argument);
+    }
+    if let Some(description) = function.descriptions().get(locale) {
+        field = field.description(description);
+    }
+
+    Some(GeneratedFunction {
+        field_name,
+        field,
+        read_only,
+        input,
+        record,
+    })
+}
+
+/// The computed columns of `table`: a field for each `STABLE` or
+/// `IMMUTABLE` function named `{table}_{field}` that takes a row of it first.
+/// Volatile functions are left out with a warning, as are all of them when
+/// the table has no primary key to find the row by and those named like a
+/// column.
+pub(crate) fn generate_computed_columns(
+    table: &Arc<Table>,
+    catalog: &Catalog,
+    pool: &Arc<Pool>,
+    locale: Option<&str>,
+    camel_case_fields: bool,
+    custom_scalars: Option<&CustomScalars>,
+) -> ComputedColumns {
+    let mut computed = ComputedColumns {
+        fields: Vec::new(),
+        records: Vec::new(),
+    };
+    let functions: Vec<&Arc<Function>> = catalog
+        .functions()
+        .filter(|f| !f.omit() && f.row_table() == Some(*table.oid()))
+        .collect();
+    if functions.is_empty() {
+        return computed;
+    }
+    if table.primary_key().is_empty() {
+        eprintln!(
+            "[turbograph] {} has no primary key; skipping its computed columns",
+            table.type_name()
+        );
+        return computed;
+    }
+
+    for function in functions {
+        let prefix = format!("{}_", table.name());
+        let name = function
+            .name()
+            .strip_prefix(&prefix)
+            .unwrap_or(function.name());
+        let field_name = column_field_name(name, camel_case_fields);
+        if !function.volatility().is_read_only() {
+            eprintln!(
+                "[turbograph] skipping computed column {}.{field_name}: {} is volatile",
+                table.type_name(),
+                function.name()
+            );
+            continue;
+        }
+        if table
+            .columns()
+            .iter()
+            .any(|c| !c.omit_read() && c.field_name() == field_name)
+        {
+            eprintln!(
+                "[turbograph] {} already has a field named {field_name}; skipping computed column",
+                table.type_name()
+            );
+            continue;
+        }
+        let Some(return_type) = return_type(function, catalog, custom_scalars) else {
+            continue;
+        };
+        let args = call_args(function.args(), camel_case_fields);
+        let Some(arguments) = arguments(function, &args) else {
+            continue;
+        };
+
+        let call = Arc::new(Call {
+            row_table: returned_table(function, catalog),
+            function: function.clone(),
+            args,
+            pool: pool.clone(),
+        });
+        let row_table = table.clone();
+        let mut field = Field::new(field_name, return_type, move |ctx| {
+            let input = direct_args(&ctx);
+            let call = call.clone();
+            let table = row_table.clone();
+            let scope = RequestScope::from_ctx(&ctx);
+            let mock_data = ctx.data_opt::<Arc<MockData>>().is_some();
+            let floats = ctx.data_opt::<NonFiniteFloats>().copied();
+
+            FieldFuture::new(async move {
+                if mock_data {
+                    return Err(gql_err("Computed columns cannot be evaluated in mock mode"));
+                }
+                let parent = ctx.parent_value.try_downcast_ref::<serde_json::Value>()?;
+                let mut params = row_key(&table, parent)?;
+                let sql = call.computed_sql(&table, &input, &mut params)?;
+                call.execute(sql, params, &input, scope, floats.unwrap_or_default())
+                    .await
+            })
+        });
+        for argument in arguments {
+            field = field.argument(argument);
+        }
+        if let Some(description) = function.descriptions().get(locale) {
+            field = field.description(description);
+        }
+        computed.fields.push(field);
+        computed
+            .records
+            .extend(record_type(function, camel_case_fields, custom_scalars));
+    }
+    computed
+}
+
+/// The GraphQL arguments of `function`, or `None` (with a warning) when one
+/// has no input type.
+fn arguments(function: &Function, args: &[CallArg]) -> Option<Vec<InputValue>> {
+    match input_values(args) {
+        Ok(arguments) => Some(arguments),
+        Err(name) => {
+            eprintln!(
+                "[turbograph] skipping function {}.{}: argument {name} has no input type",
+                function.schema_name(),
+                function.name()
+            );
+            None
+        }
+    }
+}
+
+/// The arguments given directly on the field, by name.
+fn direct_args(ctx: &async_graphql::dynamic::ResolverContext) -> Vec<(String, GqlValue)> {
+    ctx.args
+        .iter()
+        .map(|(k, v)| (k.to_string(), v.as_value().clone()))
+        .collect()
+}
+
+/// The GraphQL type of what `function` returns, or `None` (with a warning)
+/// when it returns rows of a table that is not exposed.
+fn return_type(
+    function: &Function,
+    catalog: &Catalog,
+    custom_scalars: Option<&CustomScalars>,
+) -> Option<TypeRef> {
+    let list = |item: TypeRef| TypeRef::NonNull(Box::new(TypeRef::List(Box::new(item))));
+    Some(match function.returns() {
+        FunctionReturn::Void => TypeRef::named_nn(TypeRef::BOOLEAN),
+        FunctionReturn::Scalar(ty) => {
+            let type_ref =
+                get_type_ref(&Column::procedure_arg("value", ty.clone()), custom_scalars);
+            match function.returns_set() {
+                true => list(type_ref),
+                false => type_ref,
+            }
+        }
+        FunctionReturn::Record(_) => {
+            let type_ref = TypeRef::named(record_type_name(function));
+            match function.returns_set() {
+                true => list(TypeRef::NonNull(Box::new(type_ref))),
+                false => type_ref,
+            }
+        }
+        FunctionReturn::Table(oid) => {
+            let Some(table) = catalog
+                .table(*oid)
+                .filter(|t| t.is_exposed_for(Privilege::Select))
+            else {
+                eprintln!(
+                    "[turbograph] skipping function {}.{}: it returns rows of a table that is not exposed",
+                    function.schema_name(),
+                    function.name()
+                );
+                return None;
+            };
+            match function.returns_set() {
+                true => list(TypeRef::named_nn(table.type_name())),
+                false => TypeRef::named(table.type_name()),
+            }
+        }
+    })
+}
+
+/// `{F}Record`, the type of the records `function` returns.
+fn record_type_name(function: &Function) -> String {
+    format!("{}Record", to_pascal_case(function.name()))
+}
+
+/// The `{F}Record` type of a function returning records, with a field for
+/// each output column, named like a column.
+fn record_type(
+    function: &Function,
+    camel_case_fields: bool,
+    custom_scalars: Option<&CustomScalars>,
+) -> Option<Object> {
+    let FunctionReturn::Record(outputs) = function.returns() else {
+        return None;
+    };
+    let columns = call_args(outputs, camel_case_fields);
+    Some(output_object(
+        record_type_name(function),
+        &columns,
+        custom_scalars,
+    ))
+}
+
+/// Schema and name of the table `function` returns rows of.
+fn returned_table(function: &Function, catalog: &Catalog) -> Option<(String, String)> {
+    let FunctionReturn::Table(oid) = function.returns() else {
+        return None;
+    };
+    let table = catalog.table(*oid)?;
+    Some((table.schema_name().to_string(), table.name().to_string()))
+}
+
+impl Call {
+    fn function_name(&self) -> String {
+        quote_qualified(self.function.schema_name(), self.function.name())
+    }
+
+    /// `SELECT r.* FROM f(...) AS r` for a function returning table rows or
+    /// records, else `SELECT f(...) AS value`.
+    fn root_sql(
+        &self,
+        input: &[(String, GqlValue)],
+        params: &mut Vec<SqlScalar>,
+    ) -> Result<String, async_graphql::Error> {
+        let call = format!(
+            "{}({})",
+            self.function_name(),
+            bind_args(&self.args, input, params)?.join(", ")
+        );
+        Ok(match self.function.returns() {
+            FunctionReturn::Table(_) => {
+                format!("SELECT r.* FROM {call} AS r WHERE NOT (r IS NULL)")
+            }
+            FunctionReturn::Record(_) => format!("SELECT r.* FROM {call} AS r"),
+            _ => format!("SELECT {call} AS value"),
+        })
+    }
+
+    /// Like [`root_sql`](Self::root_sql), for the row of `table` whose
+    /// primary key is already bound in `params`.
+    fn computed_sql(
+        &self,
+        table: &Table,
+        input: &[(String, GqlValue)],
+        params: &mut Vec<SqlScalar>,
+    ) -> Result<String, async_graphql::Error> {
+        let conditions: Vec<String> = table
+            .primary_key()
+            .iter()
+            .enumerate()
+            .map(|(i, column)| format!("t.{} = ${}", quote_ident(column), i + 1))
+            .collect();
+        let args = std::iter::once("t".to_string())
+            .chain(bind_args(&self.args, input, params)?)
+            .collect::<Vec<_>>();
+        let call = format!("{}({})", self.function_name(), args.join(", "));
+        let from = quote_qualified(table.schema_name(), table.name());
+        let conditions = conditions.join(" AND ");
+        Ok(match self.function.returns() {
+            FunctionReturn::Table(_) => format!(
+                "SELECT r.* FROM {from} AS t, LATERAL {call} AS r \
+                 WHERE {conditions} AND NOT (r IS NULL)"
+            ),
+            FunctionReturn::Record(_) => {
+                format!("SELECT r.* FROM {from} AS t, LATERAL {call} AS r WHERE {conditions}")
+            }
+            _ => format!("SELECT {call} AS value FROM {from} AS t WHERE {conditions}"),
+        })
+    }
+
+    /// Runs `sql` and resolves its rows as the function's return type. A
+    /// `STRICT` function given a `NULL` input is not called: it answers
+    /// `null`, an empty list, or `false` when it returns `void`.
+    async fn execute(
+        &self,
+        sql: String,
+        params: Vec<SqlScalar>,
+        input: &[(String, GqlValue)],
+        scope: RequestScope,
+        floats: NonFiniteFloats,
+    ) -> Result<Option<FieldValue<'static>>, async_graphql::Error> {
+        let rows = if self.function.is_strict() && has_null_input(&self.args, input) {
+            Vec::new()
+        } else {
+            let scalars = scope.custom_scalars.clone();
+            scope.log_statement(&sql);
+            with_transaction(&self.pool, scope.clone(), |client| {
+                let params = params.clone();
+                let sql = sql.clone();
+                let scalars = scalars.clone();
+                Box::pin(async move {
+                    let rows = client
+                        .query(sql.as_str(), &param_refs(&params))
+                        .await
+                        .map_err(|e| db_err("function error", e))?;
+                    Ok(rows
+                        .iter()
+                        .map(|row| row.to_json(scalars.as_ref()))
+                        .collect::<Vec<_>>())
+                })
+            })
+            .await?
+        };
+        if !self.function.volatility().is_read_only() {
+            scope.forget_rows();
+        }
+        let rows = match &self.row_table {
+            Some((schema, table)) => transform_rows(&scope, schema, table, rows).await?,
+            None => rows,
+        };
+
+        let value = |row: serde_json::Value| -> Result<FieldValue<'static>, async_graphql::Error> {
+            match self.function.returns() {
+                FunctionReturn::Scalar(ty) => {
+                    let column = Column::procedure_arg("value", ty.clone());
+                    Ok(get_field_value(&column, &row, floats)?.unwrap_or(FieldValue::NULL))
+                }
+                _ => Ok(FieldValue::owned_any(row)),
+            }
+        };
+        match self.function.returns() {
+            FunctionReturn::Void => Ok(Some(FieldValue::value(!rows.is_empty()))),
+            _ if self.function.returns_set() => Ok(Some(FieldValue::list(
+                rows.into_iter().map(value).collect::<Result<Vec<_>, _>>()?,
+            ))),
+            _ => rows.into_iter().next().map(value).transpose(),
+        }
+    }
+}
@@ -0,0 +1,610 @@
+//! Mock execution: answers the generated query and mutation fields from
+//! [`MockData`](crate::models::mock::MockData) fixtures instead of SQL.
+//!
+//! Filtering, ordering and paging follow the SQL executors closely enough for
+//! frontend development and contract tests; mutations return what the
+//! database would have returned but never change the fixtures.
+
+use std::cmp::Ordering;
+use std::collections::HashMap;
+use std::hash::{BuildHasher, Hasher, RandomState};
+use std::sync::Arc;
+
+use async_graphql::Value as GqlValue;
+use async_graphql::dynamic::FieldValue;
+use chrono::Utc;
+use serde_json::Value as JsonValue;
+use tokio_postgres::types::Type;
+
+use crate::error::gql_err;
+use crate::models::table::{AutoTimestamp, Column};
+
+use super::connection::{ConnectionPayload, EdgePayload, Page, encode_cursor};
+use super::filter::{FilterOp, supports_op};
+use super::sql_scalar::SqlScalar;
+use super::type_mapping::to_sql_scalar;
+
+/// A single `column <op> value(s)` test, the mock equivalent of one WHERE term.
+struct Predicate {
+    column: String,
+    op: FilterOp,
+    values: Vec<JsonValue>,
+    /// Compares strings as `lower(column) = lower(value)` does.
+    ignore_case: bool,
+}
+
+impl Predicate {
+    fn matches(&self, row: &JsonValue) -> bool {
+        let Some(actual) = row.get(&self.column).filter(|v| !v.is_null()) else {
+            // NULL never satisfies a comparison, as in SQL.
+            return false;
+        };
+        if self.ignore_case {
+            return json_eq(&lowercase(actual), &lowercase(&self.values[0]));
+        }
+        match self.op {
+            FilterOp::In => self.values.iter().any(|v| json_eq(actual, v)),
+            FilterOp::Eq => json_eq(actual, &self.values[0]),
+            FilterOp::NotEqual => !json_eq(actual, &self.values[0]),
+            FilterOp::Like | FilterOp::ILike => {
+                let (Some(actual), Some(pattern)) = (actual.as_str(), self.values[0].as_str())
+                else {
+                    return false;
+                };
+                if self.op == FilterOp::ILike {
+                    like(&actual.to_lowercase(), &pattern.to_lowercase())
+                } else {
+                    like(actual, pattern)
+                }
+            }
+            op => json_cmp(actual, &self.values[0]).is_some_and(|ord| match op {
+                FilterOp::Gt => ord == Ordering::Greater,
+                FilterOp::Gte => ord != Ordering::Less,
+                FilterOp::Lt => ord == Ordering::Less,
+                _ => ord != Ordering::Greater,
+            }),
+        }
+    }
+}
+
+/// Mirrors `build_where_clause`: unknown columns and unconvertible values
+/// are ignored rather than rejected.
+fn build_predicates(
+    pairs: Vec<(String, GqlValue)>,
+    columns: &[Arc<Column>],
+    col_by_name: &HashMap<String, usize>,
+) -> Result<Vec<Predicate>, async_graphql::Error> {
+    let mut predicates = Vec::new();
+
+    for (key, gql_val) in pairs {
+        let Some(&col_idx) = col_by_name.get(&key) else {
+            continue;
+        };
+        let col = &columns[col_idx];
+        let predicate = |op, values| Predicate {
+            column: col.name().clone(),
+            op,
+            values,
+            ignore_case: false,
+        };
+
+        let GqlValue::Object(op_obj) = gql_val else {
+            if let Some(value) = row_value(col, &gql_val) {
+                predicates.push(Predicate {
+                    ignore_case: col.case_insensitive(),
+                    ..predicate(FilterOp::Eq, vec![value])
+                });
+            }
+            continue;
+        };
+
+        for (op_key, op_val) in op_obj {
+            let Some(op) = FilterOp::from_key(op_key.as_str()) else {
+                continue;
+            };
+
+            if op == FilterOp::In {
+                if let GqlValue::List(items) = op_val {
+                    if items.len() > 10_000 {
+                        return Err(gql_err("IN filter exceeds maximum of 10,000 items"));
+                    }
+                    let values: Vec<JsonValue> =
+                        items.iter().filter_map(|v| row_value(col, v)).collect();
+                    if !values.is_empty() {
+                        predicates.push(predicate(op, values));
+                    }
+                }
+                continue;
+            }
+
+            if !supports_op(op, col) {
+                continue;
+            }
+
+            if let Some(value) = row_value(col, &op_val) {
+                predicates.push(predicate(op, vec![value]));
+            }
+        }
+    }
+    Ok(predicates)
+}
+
+/// One element of a `LIKE` pattern.
+#[derive(Clone, Copy, PartialEq)]
+enum LikeToken {
+    /// `%`, any run of characters.
+    Any,
+    /// `_`, any one character.
+    One,
+    Char(char),
+}
+
+/// Whether `value` matches the `LIKE` `pattern`, where `\` makes the next
+/// character literal.
+fn like(value: &str, pattern: &str) -> bool {
+    let mut tokens = Vec::new();
+    let mut chars = pattern.chars();
+    while let Some(c) = chars.next() {
+        tokens.push(match c {
+            '%' => LikeToken::Any,
+            '_' => LikeToken::One,
+            '\\' => LikeToken::Char(chars.next().unwrap_or('\\')),
+            c => LikeToken::Char(c),
+        });
+    }
+    let value: Vec<char> = value.chars().collect();
+    // Matches greedily, going back to the last `%` to let it take one more
+    // character when the rest fails.
+    let (mut v, mut t) = (0, 0);
+    let mut last_any: Option<(usize, usize)> = None;
+    while v < value.len() {
+        match tokens.get(t) {
+            Some(LikeToken::Any) => {
+                last_any = Some((t, v));
+                t += 1;
+            }
+            Some(LikeToken::One) => {
+                v += 1;
+                t += 1;
+            }
+            Some(LikeToken::Char(c)) if *c == value[v] => {
+                v += 1;
+                t += 1;
+            }
+            _ => match last_any {
+                Some((any_t, any_v)) => {
+                    last_any = Some((any_t, any_v + 1));
+                    t = any_t + 1;
+                    v = any_v + 1;
+                }
+                None => return false,
+            },
+        }
+    }
+    tokens[t..].iter().all(|token| *token == LikeToken::Any)
+}
+
+fn row_value(col: &Column, val: &GqlValue) -> Option<JsonValue> {
+    to_sql_scalar(col, val).map(|scalar| scalar.to_json())
+}
+
+/// The current time in the column's representation, standing in for the
+/// `now()` an `@createdAt` / `@updatedAt` column gets from the database.
+fn now_value(col: &Column) -> JsonValue {
+    let now = Utc::now();
+    let scalar = match *col.base_type() {
+        Type::DATE => SqlScalar::Date(now.date_naive()),
+        Type::TIMESTAMP => SqlScalar::Timestamp(now.naive_utc()),
+        _ => SqlScalar::Timestamptz(now),
+    };
+    scalar.to_json()
+}
+
+/// A version 4 UUID, standing in for a `gen_random_uuid()` default. The
+/// standard library's randomly keyed hasher is random enough for mocks.
+fn random_uuid() -> [u8; 16] {
+    let half = || RandomState::new().build_hasher().finish().to_be_bytes();
+    let mut bytes = [0; 16];
+    bytes[..8].copy_from_slice(&half());
+    bytes[8..].copy_from_slice(&half());
+    bytes[6] = (bytes[6] & 0x0f) | 0x40;
+    bytes[8] = (bytes[8] & 0x3f) | 0x80;
+    bytes
+}
+
+fn filter_rows(
+    rows: &[JsonValue],
+    condition: Option<Vec<(String, GqlValue)>>,
+    columns: &[Arc<Column>],
+    col_by_name: &HashMap<String, usize>,
+) -> Result<Vec<JsonValue>, async_graphql::Error> {
+    let predicates = match condition {
+        Some(pairs) => build_predicates(pairs, columns, col_by_name)?,
+        None => Vec::new(),
+    };
+    Ok(rows
+        .iter()
+        .filter(|row| predicates.iter().all(|p| p.matches(row)))
+        .cloned()
+        .collect())
+}
+
+fn lowercase(value: &JsonValue) -> JsonValue {
+    match value {
+        JsonValue::String(s) => JsonValue::String(s.to_lowercase()),
+        other => other.clone(),
+    }
+}
+
+fn json_eq(a: &JsonValue, b: &JsonValue) -> bool {
+    json_cmp(a, b).map_or(a == b, |ord| ord == Ordering::Equal)
+}
+
+/// Numbers compare numerically (fixtures may spell `bigint`s as strings),
+/// strings lexically, which matches ISO 8601 date ordering.
+fn json_cmp(a: &JsonValue, b: &JsonValue) -> Option<Ordering> {
+    let as_number = |v: &JsonValue| match v {
+        JsonValue::Number(n) => n.as_f64(),
+        JsonValue::String(s) => s.parse::<f64>().ok(),
+        _ => None,
+    };
+    match (a, b) {
+        (JsonValue::String(x), JsonValue::String(y)) => Some(x.cmp(y)),
+        (JsonValue::Bool(x), JsonValue::Bool(y)) => Some(x.cmp(y)),
+        _ => as_number(a)?.partial_cmp(&as_number(b)?),
+    }
+}
+
+/// Sorts like `ORDER BY`, with Postgres' default of NULLs sorting last
+/// ascending and first descending.
+fn sort_rows(
+    rows: &mut [JsonValue],
+    order_by: &[String],
+    columns: &[Arc<Column>],
+    col_by_upper: &HashMap<String, usize>,
+) -> Result<(), async_graphql::Error> {
+    let mut keys = Vec::with_capacity(order_by.len());
+    for s in order_by {
+        let (col_upper, desc) = if let Some(c) = s.strip_suffix("_DESC") {
+            (c, true)
+        } else if let Some(c) = s.strip_suffix("_ASC") {
+            (c, false)
+        } else {
+            continue;
+        };
+        let Some(&col_idx) = col_by_upper.get(col_upper) else {
+            return Err(gql_err(format!("unknown column for ordering: {col_upper}")));
+        };
+        keys.push((columns[col_idx].name().clone(), desc));
+    }
+
+    rows.sort_by(|a, b| {
+        keys.iter()
+            .map(|(name, desc)| {
+                let (x, y) = (
+                    a.get(name).filter(|v| !v.is_null()),
+                    b.get(name).filter(|v| !v.is_null()),
+                );
+                let ord = match (x, y) {
+                    (None, None) => Ordering::Equal,
+                    (None, Some(_)) => Ordering::Greater,
+                    (Some(_), None) => Ordering::Less,
+                    (Some(x), Some(y)) => json_cmp(x, y).unwrap_or(Ordering::Equal),
+                };
+                if *desc { ord.reverse() } else { ord }
+            })
+            .find(|ord| ord.is_ne())
+            .unwrap_or(Ordering::Equal)
+    });
+    Ok(())
+}
+
+/// Mock counterpart of `execute_connection_query`.
+#[allow(clippy::too_many_arguments)]
+pub(crate) fn connection(
+    rows: &[JsonValue],
+    condition: Option<Vec<(String, GqlValue)>>,
+    order_by: &[String],
+    page: &Page,
+    columns: &[Arc<Column>],
+    col_by_name: &HashMap<String, usize>,
+    col_by_upper: &HashMap<String, usize>,
+) -> Result<Option<FieldValue<'static>>, async_graphql::Error> {
+    let mut rows = filter_rows(rows, condition, columns, col_by_name)?;
+    sort_rows(&mut rows, order_by, columns, col_by_upper)?;
+
+    let total_count = rows.len() as i64;
+    let (offset, limit) = page.bounds(total_count);
+    let edges: Vec<EdgePayload> = rows
+        .into_iter()
+        .skip(offset as usize)
+        .take(limit as usize)
+        .enumerate()
+        .map(|(i, node)| EdgePayload {
+            cursor: encode_cursor(order_by, (offset as usize) + i),
+            node,
+        })
+        .collect();
+
+    Ok(Some(FieldValue::owned_any(ConnectionPayload {
+        total_count,
+        has_next_page: (offset + edges.len() as i64) < total_count,
+        has_previous_page: offset > 0,
+        edges,
+    })))
+}
+
+/// Mock counterpart of `execute_create`: echoes the input as the new row,
+/// with generated UUIDs filled in and other unspecified columns left null.
+pub(crate) fn create(
+    input: Vec<(String, GqlValue)>,
+    columns: &[Arc<Column>],
+    col_map: &HashMap<String, usize>,
+) -> Result<Option<FieldValue<'static>>, async_graphql::Error> {
+    let mut row = serde_json::Map::new();
+    for (key, val) in &input {
+        let Some(&idx) = col_map.get(key) else {
+            continue;
+        };
+        let col = &columns[idx];
+        if let Some(value) = row_value(col, val) {
+            row.insert(col.name().clone(), value);
+        }
+    }
+
+    if row.is_empty() {
+        return Err(gql_err("No valid columns provided for insert"));
+    }
+
+    for col in columns.iter().filter(|c| c.auto_timestamp().is_some()) {
+        row.insert(col.name().clone(), now_value(col));
+    }
+    for col in columns.iter().filter(|c| c.generates_uuid()) {
+        row.entry(col.name().clone())
+            .or_insert_with(|| SqlScalar::Uuid(random_uuid()).to_json());
+    }
+
+    for col in columns {
+        row.entry(col.name().clone()).or_insert(JsonValue::Null);
+    }
+    Ok(Some(FieldValue::owned_any(JsonValue::Object(row))))
+}
+
+/// Mock counterpart of `execute_update`: returns the matching rows with the
+/// patch applied.
+pub(crate) fn update(
+    rows: &[JsonValue],
+    patch: Vec<(String, GqlValue)>,
+    condition: Option<Vec<(String, GqlValue)>>,
+    columns: &[Arc<Column>],
+    update_col_map: &HashMap<String, usize>,
+    cond_col_map: &HashMap<String, usize>,
+) -> Result<Option<FieldValue<'static>>, async_graphql::Error> {
+    let mut changes = Vec::new();
+    for (key, val) in &patch {
+        let Some(&idx) = update_col_map.get(key) else {
+            continue;
+        };
+        let col = &columns[idx];
+        if matches!(val, GqlValue::Null) {
+            changes.push((col.name().clone(), JsonValue::Null));
+        } else if let Some(value) = row_value(col, val) {
+            changes.push((col.name().clone(), value));
+        }
+    }
+
+    if changes.is_empty() {
+        return Err(gql_err("No valid columns provided for update"));
+    }
+
+    for col in columns
+        .iter()
+        .filter(|c| c.auto_timestamp() == Some(AutoTimestamp::Updated))
+    {
+        changes.push((col.name().clone(), now_value(col)));
+    }
+
+    let list: Vec<FieldValue> = filter_rows(rows, condition, columns, cond_col_map)?
+        .into_iter()
+        .map(|mut row| {
+            if let JsonValue::Object(map) = &mut row {
+                for (name, value) in &changes {
+                    map.insert(name.clone(), value.clone());
+                }
+            }
+            FieldValue::owned_any(row)
+        })
+        .collect();
+    Ok(Some(FieldValue::list(list)))
+}
+
+/// Every row whose `columns` equal `key`, for relation fields.
+pub(crate) fn filter_by_key(
+    rows: &[JsonValue],
+    columns: &[String],
+    key: &[JsonValue],
+) -> Vec<JsonValue> {
+    rows.iter()
+        .filter(|row| {
+            columns
+                .iter()
+                .zip(key)
+                .all(|(column, value)| row.get(column).is_some_and(|v| json_eq(v, value)))
+        })
+        .cloned()
+        .collect()
+}
+
+/// The row whose `primary_key` columns equal `key`, for `node` lookups.
+pub(crate) fn find_by_key(
+    rows: &[JsonValue],
+    primary_key: &[String],
+    key: &[JsonValue],
+) -> Option<JsonValue> {
+    rows.iter()
+        .find(|row| {
+            primary_key
+                .iter()
+                .zip(key)
+                .all(|(column, value)| row.get(column).is_some_and(|v| json_eq(v, value)))
+        })
+        .cloned()
+}
+
+/// Mock counterpart of `execute_delete`: returns the matching rows.
+pub(crate) fn delete(
+    rows: &[JsonValue],
+    condition: Option<Vec<(String, GqlValue)>>,
+    columns: &[Arc<Column>],
+    cond_col_map: &HashMap<String, usize>,
+) -> Result<Option<FieldValue<'static>>, async_graphql::Error> {
+    let list: Vec<FieldValue> = filter_rows(rows, condition, columns, cond_col_map)?
+        .into_iter()
+        .map(FieldValue::owned_any)
+        .collect();
+    Ok(Some(FieldValue::list(list)))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use async_graphql::{Name, indexmap::IndexMap};
+    use serde_json::json;
+    use tokio_postgres::types::Type;
+
+    type ColumnMaps = (
+        Vec<Arc<Column>>,
+        HashMap<String, usize>,
+        HashMap<String, usize>,
+    );
+
+    fn columns() -> ColumnMaps {
+        let cols = vec![
+            Arc::new(Column::new_for_test("id", Type::INT4, false, false)),
+            Arc::new(Column::new_for_test("name", Type::TEXT, true, false)),
+        ];
+        let by_name = [("id".to_string(), 0), ("name".to_string(), 1)].into();
+        let by_upper = [("ID".to_string(), 0), ("NAME".to_string(), 1)].into();
+        (cols, by_name, by_upper)
+    }
+
+    fn rows() -> Vec<JsonValue> {
+        vec![
+            json!({ "id": 1, "name": "carol" }),
+            json!({ "id": 2, "name": null }),
+            json!({ "id": 3, "name": "alice" }),
+        ]
+    }
+
+    fn op(key: &str, value: GqlValue) -> GqlValue {
+        GqlValue::Object(IndexMap::from([(Name::new(key), value)]))
+    }
+
+    #[test]
+    fn test_create_fills_generated_uuids() {
+        let cols = vec![
+            Arc::new(Column::new_for_test("id", Type::UUID, false, false).with_uuid_default()),
+            Arc::new(Column::new_for_test("name", Type::TEXT, true, false)),
+        ];
+        let by_name = [("id".to_string(), 0), ("name".to_string(), 1)].into();
+        let row = |input| {
+            let value = create(input, &cols, &by_name).unwrap().unwrap();
+            value.try_downcast_ref::<JsonValue>().unwrap().clone()
+        };
+
+        let generated = row(vec![("name".to_string(), GqlValue::from("a"))]);
+        let id = generated["id"].as_str().unwrap();
+        assert_eq!((id.len(), &id[14..15]), (36, "4"));
+
+        let id = "a0eebc99-9c0b-4ef8-bb6d-6bb9bd380a11";
+        let supplied = row(vec![("id".to_string(), GqlValue::from(id))]);
+        assert_eq!(supplied["id"], id);
+    }
+
+    #[test]
+    fn test_filter_equality_and_range() {
+        let (cols, by_name, _) = columns();
+        let matched = filter_rows(
+            &rows(),
+            Some(vec![("id".into(), op("greaterThan", GqlValue::from(1)))]),
+            &cols,
+            &by_name,
+        )
+        .unwrap();
+        assert_eq!(matched.len(), 2);
+
+        let matched = filter_rows(
+            &rows(),
+            Some(vec![("name".into(), GqlValue::from("alice"))]),
+            &cols,
+            &by_name,
+        )
+        .unwrap();
+        assert_eq!(matched, [json!({ "id": 3, "name": "alice" })]);
+    }
+
+    #[test]
+    fn test_filter_null_never_matches() {
+        let (cols, by_name, _) = columns();
+        let matched = filter_rows(
+            &rows(),
+            Some(vec![(
+                "name".into(),
+                op("notEqual", GqlValue::from("alice")),
+            )]),
+            &cols,
+            &by_name,
+        )
+        .unwrap();
+        assert_eq!(matched, [json!({ "id": 1, "name": "carol" })]);
+    }
+
+    #[test]
+    fn test_filter_like_patterns() {
+        let (cols, by_name, _) = columns();
+        let ids = |key: &str, pattern: &str| {
+            let matched = filter_rows(
+                &rows(),
+                Some(vec![("name".into(), op(key, GqlValue::from(pattern)))]),
+                &cols,
+                &by_name,
+            )
+            .unwrap();
+            matched
+                .iter()
+                .map(|r| r["id"].as_i64().unwrap())
+                .collect::<Vec<_>>()
+        };
+        assert_eq!(ids("like", "%l%"), [1, 3]);
+        assert_eq!(ids("like", "a_ice"), [3]);
+        assert_eq!(ids("like", "A%"), Vec::<i64>::new());
+        assert_eq!(ids("likeInsensitive", "A%"), [3]);
+        assert!(like("50%", "50\\%"));
+        assert!(!like("500", "50\\%"));
+    }
+
+    #[test]
+    fn test_sort_puts_nulls_last_ascending() {
+        let (cols, _, by_upper) = columns();
+        let mut sorted = rows();
+        sort_rows(&mut sorted, &["NAME_ASC".into()], &cols, &by_upper).unwrap();
+        let ids: Vec<_> = sorted.iter().map(|r| r["id"].as_i64().unwrap()).collect();
+        assert_eq!(ids, [3, 1, 2]);
+
+        sort_rows(&mut sorted, &["NAME_DESC".into()], &cols, &by_upper).unwrap();
+        let ids: Vec<_> = sorted.iter().map(|r| r["id"].as_i64().unwrap()).collect();
+        assert_eq!(ids, [2, 1, 3]);
+    }
+
+    #[test]
+    fn test_sort_unknown_column_errors() {
+        let (cols, _, by_upper) = columns();
+        assert!(sort_rows(&mut rows(), &["EMAIL_ASC".into()], &cols, &by_upper).is_err());
+    }
+
+    #[test]
+    fn test_json_cmp_numeric_strings() {
+        assert_eq!(json_cmp(&json!(10), &json!("9")), Some(Ordering::Greater));
+        assert!(json_eq(&json!(5), &json!(5.0)));
+    }
+}
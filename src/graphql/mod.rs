@@ -1,12 +1,39 @@
+pub(crate) mod admin;
 mod connection;
+pub(crate) mod cost;
 mod entity;
+pub(crate) mod example;
 mod filter;
+mod finder;
+pub(crate) mod function;
+pub(crate) mod mock;
 pub(crate) mod mutation;
+pub(crate) mod node;
+pub(crate) mod permission;
+pub(crate) mod prefetch;
+pub(crate) mod procedure;
 pub(crate) mod query;
-mod sql_scalar;
+pub(crate) mod refresh;
+mod relation;
+pub(crate) mod scalars;
+pub(crate) mod sql_scalar;
+pub(crate) mod subscription;
 mod type_mapping;
+pub(crate) mod validation;
+pub(crate) mod version;
 
 pub(crate) use connection::make_page_info_type;
 pub(crate) use entity::generate_entity;
+pub(crate) use filter::supports_op;
+pub(crate) use finder::generate_finders;
 pub(crate) use mutation::generate_mutation;
 pub(crate) use query::generate_query;
+pub(crate) use relation::{
+    generate_backward_relation, generate_forward_relation, generate_reference,
+};
+pub(crate) use scalars::make_scalars;
+pub(crate) use type_mapping::{
+    condition_type_ref, create_input_type_ref, input_type_ref, is_supported_type,
+    make_composite_input_type, make_composite_type, make_enum_type, make_pg_enum_type,
+    to_sql_scalar,
+};
@@ -2,36 +2,265 @@ use std::collections::HashMap;
 use std::sync::Arc;
 
 use async_graphql::Value as GqlValue;
-use async_graphql::dynamic::{Field, FieldFuture, InputObject, InputValue, TypeRef};
+use async_graphql::dynamic::{
+    Field, FieldFuture, FieldValue, InputObject, InputValue, ObjectAccessor, ResolverContext,
+    TypeRef,
+};
 use deadpool_postgres::Pool;
+use serde_json::{Map, Value};
 
+use crate::db::request::RequestScope;
+use crate::error::gql_err;
+use crate::ir::{Delete, Filter, Insert, Operation, Planner, Update};
+use crate::models::grants::Privilege;
+use crate::models::mock::MockData;
 use crate::models::table::{Column, Table};
-use crate::models::transaction::TransactionConfig;
+use crate::utils::inflection::{Inflector, key_suffix};
 
-use super::type_mapping::condition_type_ref;
+use super::example;
+use super::mock;
+use super::node::decode_node_id;
+use super::query::sql::{condition_filters, selected_columns};
+use super::type_mapping::{condition_type_ref, create_input_type_ref, input_type_ref};
 
-mod executor;
+pub(crate) mod upload;
 
 /// All types and fields generated for a table's mutations.
 pub struct GeneratedMutation {
-    /// Mutation root fields (createX, updateX, deleteX).
-    pub fields: Vec<Field>,
+    /// Mutation root fields (createX, updateX, deleteX), with their names
+    /// and the privilege on the table each needs besides `SELECT`.
+    pub fields: Vec<(String, Privilege, Field)>,
     /// Input object types to register (CreateXInput, UpdateXPatch).
     pub input_objects: Vec<InputObject>,
 }
 
+/// The fields of the rows to return for the entity fields the mutation
+/// selects, or none for every column, see [`selected_columns`].
+fn returning(ctx: &ResolverContext, columns: &[Arc<Column>]) -> Vec<String> {
+    selected_columns(
+        columns,
+        ctx.field().selection_set().map(|field| field.name()),
+    )
+    .into_iter()
+    .map(|c| c.field_name().to_string())
+    .collect()
+}
+
+/// `pairs` as the values of an IR write.
+fn values(pairs: Vec<(String, GqlValue)>) -> Result<Map<String, Value>, async_graphql::Error> {
+    pairs
+        .into_iter()
+        .map(|(key, value)| Ok((key, value.into_json()?)))
+        .collect()
+}
+
+/// The IR filters of a `{T}Condition`, none matching every row.
+fn filters(
+    condition: Option<Vec<(String, GqlValue)>>,
+    columns: &[Arc<Column>],
+    cond_col_map: &HashMap<String, usize>,
+) -> Result<Vec<Filter>, async_graphql::Error> {
+    match condition {
+        Some(pairs) => condition_filters(pairs, columns, cond_col_map),
+        None => Ok(Vec::new()),
+    }
+}
+
+/// The rows a write returned, as the list of a `…s` mutation.
+fn row_list(rows: Vec<Value>) -> Option<FieldValue<'static>> {
+    Some(FieldValue::list(
+        rows.into_iter().map(FieldValue::owned_any),
+    ))
+}
+
+/// Fails when `patch` sets an `@insertOnly` column. `{T}Patch` leaves such
+/// columns out, so this is a last line of defence should a patch reach the
+/// resolver without going through that type's validation.
+fn reject_insert_only(
+    patch: &[(String, GqlValue)],
+    columns: &[Arc<Column>],
+) -> Result<(), async_graphql::Error> {
+    for (key, _) in patch {
+        if let Some(col) = columns
+            .iter()
+            .find(|c| c.insert_only() && c.field_name() == key)
+        {
+            return Err(gql_err(format!(
+                "Column `{}` is insert-only and cannot be updated",
+                col.field_name()
+            )));
+        }
+    }
+    Ok(())
+}
+
+/// `{T}Condition` pairs selecting exactly the row with the global ID `id`.
+fn node_id_condition(
+    table: &Table,
+    id: &str,
+) -> Result<Vec<(String, GqlValue)>, async_graphql::Error> {
+    let (type_name, key) = decode_node_id(id)?;
+    if type_name != table.type_name() || key.len() != table.primary_key().len() {
+        return Err(gql_err(format!(
+            "Node ID {id} does not identify a {}",
+            table.type_name()
+        )));
+    }
+
+    table
+        .primary_key()
+        .iter()
+        .zip(key)
+        .map(|(name, value)| {
+            let column = table
+                .columns()
+                .iter()
+                .find(|c| c.name() == name)
+                .ok_or_else(|| gql_err(format!("Unknown primary key column {name}")))?;
+            let value = GqlValue::from_json(value).map_err(|e| gql_err(e.to_string()))?;
+            Ok((column.field_name().to_string(), equal(value)))
+        })
+        .collect()
+}
+
+/// An `equal` filter of `{T}Condition`.
+fn equal(value: GqlValue) -> GqlValue {
+    GqlValue::Object([(async_graphql::Name::new("equal"), value)].into())
+}
+
+/// Whether every primary key column can be matched through `{T}Condition`,
+/// which the single-row `…By…` mutations rely on to target their row.
+fn supports_key_condition(table: &Table, cond_col_map: &HashMap<String, usize>) -> bool {
+    !table.primary_key().is_empty()
+        && table.primary_key().iter().all(|name| {
+            table.columns().iter().any(|c| {
+                c.name() == name
+                    && c.enum_type().is_none()
+                    && cond_col_map.contains_key(c.field_name())
+            })
+        })
+}
+
+/// How the input of a single-row `update{T}By…` / `delete{T}By…` mutation
+/// identifies its row.
+#[derive(Clone)]
+enum RowKey {
+    /// The primary key columns, each under its field name.
+    PrimaryKey(Arc<Vec<Arc<Column>>>),
+    /// The global ID under the given field name.
+    NodeId(String),
+}
+
+impl RowKey {
+    fn primary_key(table: &Table) -> Self {
+        Self::PrimaryKey(Arc::new(
+            table
+                .primary_key()
+                .iter()
+                .filter_map(|name| table.columns().iter().find(|c| c.name() == name))
+                .cloned()
+                .collect(),
+        ))
+    }
+
+    /// `ById`, `ByOrgIdAndUserId` or `ByNodeId`.
+    fn suffix(&self) -> String {
+        match self {
+            Self::PrimaryKey(columns) => {
+                let fields: Vec<&str> = columns.iter().map(|c| c.field_name()).collect();
+                key_suffix(&fields)
+            }
+            Self::NodeId(_) => "ByNodeId".to_string(),
+        }
+    }
+
+    /// The `{Update,Delete}{T}By…Input` type named `name`, with the fields
+    /// of the key.
+    fn input_object(&self, name: &str) -> InputObject {
+        match self {
+            Self::PrimaryKey(columns) => columns
+                .iter()
+                .filter_map(|c| Some((c, condition_type_ref(c)?)))
+                .fold(InputObject::new(name), |object, (c, type_ref)| {
+                    object.field(InputValue::new(
+                        c.field_name(),
+                        TypeRef::named_nn(type_ref.to_string()),
+                    ))
+                }),
+            Self::NodeId(field) => {
+                InputObject::new(name).field(InputValue::new(field, TypeRef::named_nn(TypeRef::ID)))
+            }
+        }
+    }
+
+    /// `{T}Condition` pairs selecting the row `input` identifies.
+    fn condition(
+        &self,
+        table: &Table,
+        input: Option<&ObjectAccessor>,
+    ) -> Result<Vec<(String, GqlValue)>, async_graphql::Error> {
+        match self {
+            Self::PrimaryKey(columns) => Ok(columns
+                .iter()
+                .map(|c| {
+                    let value = input
+                        .and_then(|obj| obj.get(c.field_name()))
+                        .map(|v| v.as_value().clone())
+                        .unwrap_or_default();
+                    (c.field_name().to_string(), equal(value))
+                })
+                .collect()),
+            Self::NodeId(field) => {
+                let id = input
+                    .and_then(|obj| obj.get(field))
+                    .and_then(|v| v.string().ok().map(str::to_string))
+                    .unwrap_or_default();
+                node_id_condition(table, &id)
+            }
+        }
+    }
+}
+
+/// The single row of a list of updated or deleted rows.
+fn first_row(list: Option<FieldValue<'static>>) -> Option<FieldValue<'static>> {
+    list.as_ref()
+        .and_then(|list| list.as_list())
+        .and_then(|rows| rows.first())
+        .and_then(|row| row.downcast_ref::<serde_json::Value>())
+        .cloned()
+        .map(FieldValue::owned_any)
+}
+
 /// Generates create / update / delete mutation fields for a single table.
 ///
 /// Respects `@omit create`, `@omit update`, `@omit delete` annotations at
 /// both the table and column level.  Materialized views are automatically
 /// excluded (handled by `Table::omit_*` methods).
-pub fn generate_mutation(table: Arc<Table>, pool: Arc<Pool>) -> GeneratedMutation {
+///
+/// Tables with a primary key also get `update{T}By{Key}` /
+/// `delete{T}By{Key}` (e.g. `updateUserById`), which take the key's fields
+/// instead of a condition and answer the affected row. With `node_id` set,
+/// `update{T}ByNodeId` / `delete{T}ByNodeId` do the same for the global ID
+/// under that name.
+///
+/// Upload columns take their files from the request, up to
+/// `max_upload_bytes` each.
+pub fn generate_mutation(
+    table: Arc<Table>,
+    pool: Arc<Pool>,
+    node_id: Option<&str>,
+    max_upload_bytes: Option<u64>,
+    inflector: &dyn Inflector,
+) -> GeneratedMutation {
     let mut fields = Vec::new();
     let mut input_objects = Vec::new();
 
     let type_name = table.type_name();
     let tbl_schema = table.schema_name().to_string();
     let tbl_name = table.name().to_string();
+    // Writes compile through the operation IR; GraphQL has already checked
+    // the table's exposure.
+    let planner = Arc::new(Planner::new(pool, vec![table.clone()], false, false));
 
     // Column indices used for condition WHERE clauses (reuses {Type}Condition)
     let all_columns: Arc<Vec<Arc<Column>>> = Arc::new(table.columns().to_vec());
@@ -40,76 +269,109 @@ pub fn generate_mutation(table: Arc<Table>, pool: Arc<Pool>) -> GeneratedMutatio
             .iter()
             .enumerate()
             .filter(|(_, c)| !c.omit_read() && condition_type_ref(c).is_some())
-            .map(|(i, c)| (c.name().to_string(), i))
+            .map(|(i, c)| (c.field_name().to_string(), i))
             .collect(),
     );
 
+    // Keys the single-row `…By…` mutations find their row by. A primary key
+    // column named like the node ID field would give both the same names.
+    let mut row_keys = Vec::new();
+    if supports_key_condition(&table, &cond_col_map) {
+        row_keys.push(RowKey::primary_key(&table));
+        if let Some(node_id) = node_id
+            && row_keys[0].suffix() != "ByNodeId"
+        {
+            row_keys.push(RowKey::NodeId(node_id.to_string()));
+        }
+    }
+
     // ── CREATE ────────────────────────────────────────────────────────────
-    if !table.omit_create() {
+    if table.is_exposed_for(Privilege::Insert) {
         let input_name = format!("Create{}Input", type_name);
         let mut create_input = InputObject::new(&input_name);
+        if let Some(example) = table.example() {
+            create_input = create_input.description(example::describe(example));
+        }
 
         let mut create_col_map = HashMap::new();
         for (i, col) in all_columns.iter().enumerate() {
             if col.omit_create() {
                 continue;
             }
-            if let Some(tr) = condition_type_ref(col) {
-                let type_ref = if !col.nullable() && !col.has_default() {
-                    TypeRef::named_nn(tr.to_string())
-                } else {
-                    tr
-                };
-                create_input = create_input.field(InputValue::new(col.name().as_str(), type_ref));
-                create_col_map.insert(col.name().to_string(), i);
+            if let Some(type_ref) = create_input_type_ref(col) {
+                let mut input = InputValue::new(col.field_name(), type_ref);
+                let mut description = Vec::new();
+                if col.generates_uuid() {
+                    description.push(
+                        "Generated by the database when omitted; supply one to know the ID \
+                         before the row is created."
+                            .to_string(),
+                    );
+                }
+                if let Some(example) = col.example() {
+                    description.push(example::describe(example));
+                }
+                if !description.is_empty() {
+                    input = input.description(description.join("\n\n"));
+                }
+                create_input = create_input.field(input);
+                create_col_map.insert(col.field_name().to_string(), i);
             }
         }
 
         let create_col_map = Arc::new(create_col_map);
         let cols = all_columns.clone();
-        let p = pool.clone();
-        let s = tbl_schema.clone();
+        let p = planner.clone();
         let n = tbl_name.clone();
         let inp_ref = input_name.clone();
 
-        let field = Field::new(
-            format!("create{}", type_name),
-            TypeRef::named(type_name.clone()),
-            move |ctx| {
-                let input_pairs: Vec<(String, GqlValue)> = ctx
-                    .args
-                    .get("input")
-                    .and_then(|v| v.object().ok())
-                    .map(|obj| {
-                        obj.iter()
-                            .map(|(k, v)| (k.to_string(), v.as_value().clone()))
-                            .collect()
-                    })
-                    .unwrap_or_default();
+        let name = inflector.create_field(&type_name);
+        let field = Field::new(&name, TypeRef::named(type_name.clone()), move |ctx| {
+            let mut input_pairs: Vec<(String, GqlValue)> = ctx
+                .args
+                .get("input")
+                .and_then(|v| v.object().ok())
+                .map(|obj| {
+                    obj.iter()
+                        .map(|(k, v)| (k.to_string(), v.as_value().clone()))
+                        .collect()
+                })
+                .unwrap_or_default();
 
-                let pool = p.clone();
-                let schema = s.clone();
-                let name = n.clone();
-                let columns = cols.clone();
-                let col_map = create_col_map.clone();
-                let tx_config = ctx.data_opt::<TransactionConfig>().cloned();
+            let planner = p.clone();
+            let name = n.clone();
+            let columns = cols.clone();
+            let col_map = create_col_map.clone();
+            let uploads =
+                upload::take_uploads(&ctx, &mut input_pairs, &columns, &col_map, max_upload_bytes);
+            let returning = returning(&ctx, &columns);
+            let scope = RequestScope::from_ctx(&ctx);
+            let mock_data = ctx.data_opt::<Arc<MockData>>().is_some();
 
-                FieldFuture::new(async move {
-                    executor::execute_create(
-                        &pool, &schema, &name, input_pairs, &columns, &col_map, tx_config,
-                    )
-                    .await
-                })
-            },
-        )
+            FieldFuture::new(async move {
+                let uploads = uploads?;
+                if mock_data {
+                    return mock::create(input_pairs, &columns, &col_map);
+                }
+                // Columns left null take their defaults.
+                input_pairs.retain(|(_, value)| *value != GqlValue::Null);
+                let insert = Operation::Insert(Insert {
+                    table: name,
+                    values: values(input_pairs)?,
+                    returning,
+                });
+                let rows = planner.mutate(&insert, uploads, scope).await?;
+                Ok(rows.into_iter().next().map(FieldValue::owned_any))
+            })
+        })
         .argument(InputValue::new("input", TypeRef::named_nn(inp_ref)));
 
-        fields.push(field);
+        fields.push((name, Privilege::Insert, field));
         input_objects.push(create_input);
     }
 
     // ── UPDATE ────────────────────────────────────────────────────────────
-    if !table.omit_update() {
+    if table.is_exposed_for(Privilege::Update) {
         let patch_name = format!("Update{}Patch", type_name);
         let mut patch_input = InputObject::new(&patch_name);
 
@@ -118,26 +380,106 @@ pub fn generate_mutation(table: Arc<Table>, pool: Arc<Pool>) -> GeneratedMutatio
             if col.omit_update() {
                 continue;
             }
-            if let Some(tr) = condition_type_ref(col) {
-                patch_input = patch_input.field(InputValue::new(col.name().as_str(), tr));
-                update_col_map.insert(col.name().to_string(), i);
+            if let Some(tr) = input_type_ref(col) {
+                patch_input = patch_input.field(InputValue::new(col.field_name(), tr));
+                update_col_map.insert(col.field_name().to_string(), i);
             }
         }
 
         let update_col_map = Arc::new(update_col_map);
+
+        for key in &row_keys {
+            let input_name = format!("Update{}{}Input", type_name, key.suffix());
+            input_objects.push(
+                key.input_object(&input_name)
+                    .field(InputValue::new("patch", TypeRef::named_nn(&patch_name))),
+            );
+
+            let tbl = table.clone();
+            let cols = all_columns.clone();
+            let ucm = update_col_map.clone();
+            let ccm = cond_col_map.clone();
+            let p = planner.clone();
+            let key = key.clone();
+
+            let name = inflector.update_field(&type_name, &key.suffix());
+            fields.push((
+                name.clone(),
+                Privilege::Update,
+                Field::new(&name, TypeRef::named(type_name.clone()), move |ctx| {
+                    let input = ctx.args.get("input").and_then(|v| v.object().ok());
+                    let condition = key.condition(&tbl, input.as_ref());
+                    let mut patch_pairs: Vec<(String, GqlValue)> = input
+                        .as_ref()
+                        .and_then(|obj| obj.get("patch"))
+                        .and_then(|v| v.object().ok())
+                        .map(|obj| {
+                            obj.iter()
+                                .map(|(k, v)| (k.to_string(), v.as_value().clone()))
+                                .collect()
+                        })
+                        .unwrap_or_default();
+
+                    let table = tbl.clone();
+                    let planner = p.clone();
+                    let columns = cols.clone();
+                    let ucm = ucm.clone();
+                    let ccm = ccm.clone();
+                    let uploads = upload::take_uploads(
+                        &ctx,
+                        &mut patch_pairs,
+                        &columns,
+                        &ucm,
+                        max_upload_bytes,
+                    );
+                    let returning = returning(&ctx, &columns);
+                    let scope = RequestScope::from_ctx(&ctx);
+                    let mock_data = ctx.data_opt::<Arc<MockData>>().cloned();
+
+                    FieldFuture::new(async move {
+                        let uploads = uploads?;
+                        reject_insert_only(&patch_pairs, &columns)?;
+                        let condition = Some(condition?);
+                        let updated = match mock_data {
+                            Some(mock_data) => mock::update(
+                                mock_data.rows(table.schema_name(), table.name()),
+                                patch_pairs,
+                                condition,
+                                &columns,
+                                &ucm,
+                                &ccm,
+                            )?,
+                            None => {
+                                let update = Operation::Update(Update {
+                                    table: table.name().to_string(),
+                                    filters: filters(condition, &columns, &ccm)?,
+                                    values: values(patch_pairs)?,
+                                    returning,
+                                });
+                                row_list(planner.mutate(&update, uploads, scope).await?)
+                            }
+                        };
+                        Ok(first_row(updated))
+                    })
+                })
+                .argument(InputValue::new("input", TypeRef::named_nn(input_name))),
+            ));
+        }
+
         let cols = all_columns.clone();
         let cm = cond_col_map.clone();
-        let p = pool.clone();
+        let p = planner.clone();
         let s = tbl_schema.clone();
         let n = tbl_name.clone();
         let patch_ref = patch_name.clone();
         let cond_ref = format!("{}Condition", type_name);
 
+        let name = inflector.update_field(&type_name, "");
         let field = Field::new(
-            format!("update{}", type_name),
+            &name,
             TypeRef::named_nn_list_nn(type_name.clone()),
             move |ctx| {
-                let patch_pairs: Vec<(String, GqlValue)> = ctx
+                let mut patch_pairs: Vec<(String, GqlValue)> = ctx
                     .args
                     .get("patch")
                     .and_then(|v| v.object().ok())
@@ -158,84 +500,152 @@ pub fn generate_mutation(table: Arc<Table>, pool: Arc<Pool>) -> GeneratedMutatio
                             .collect()
                     });
 
-                let pool = p.clone();
+                let planner = p.clone();
                 let schema = s.clone();
                 let name = n.clone();
                 let columns = cols.clone();
                 let ucm = update_col_map.clone();
                 let ccm = cm.clone();
-                let tx_config = ctx.data_opt::<TransactionConfig>().cloned();
+                let uploads =
+                    upload::take_uploads(&ctx, &mut patch_pairs, &columns, &ucm, max_upload_bytes);
+                let returning = returning(&ctx, &columns);
+                let scope = RequestScope::from_ctx(&ctx);
+                let mock_data = ctx.data_opt::<Arc<MockData>>().cloned();
 
                 FieldFuture::new(async move {
-                    executor::execute_update(
-                        &pool,
-                        &schema,
-                        &name,
-                        patch_pairs,
-                        condition_pairs,
-                        &columns,
-                        &ucm,
-                        &ccm,
-                        tx_config,
-                    )
-                    .await
+                    let uploads = uploads?;
+                    reject_insert_only(&patch_pairs, &columns)?;
+                    if let Some(mock_data) = mock_data {
+                        return mock::update(
+                            mock_data.rows(&schema, &name),
+                            patch_pairs,
+                            condition_pairs,
+                            &columns,
+                            &ucm,
+                            &ccm,
+                        );
+                    }
+                    let update = Operation::Update(Update {
+                        table: name,
+                        filters: filters(condition_pairs, &columns, &ccm)?,
+                        values: values(patch_pairs)?,
+                        returning,
+                    });
+                    Ok(row_list(planner.mutate(&update, uploads, scope).await?))
                 })
             },
         )
         .argument(InputValue::new("patch", TypeRef::named_nn(patch_ref)))
         .argument(InputValue::new("condition", TypeRef::named(cond_ref)));
 
-        fields.push(field);
+        fields.push((name, Privilege::Update, field));
         input_objects.push(patch_input);
     }
 
     // ── DELETE ─────────────────────────────────────────────────────────────
-    if !table.omit_delete() {
+    for key in row_keys
+        .iter()
+        .filter(|_| table.is_exposed_for(Privilege::Delete))
+    {
+        let input_name = format!("Delete{}{}Input", type_name, key.suffix());
+        input_objects.push(key.input_object(&input_name));
+
+        let tbl = table.clone();
+        let cols = all_columns.clone();
+        let ccm = cond_col_map.clone();
+        let p = planner.clone();
+        let key = key.clone();
+
+        let name = inflector.delete_field(&type_name, &key.suffix());
+        fields.push((
+            name.clone(),
+            Privilege::Delete,
+            Field::new(&name, TypeRef::named(type_name.clone()), move |ctx| {
+                let input = ctx.args.get("input").and_then(|v| v.object().ok());
+                let condition = key.condition(&tbl, input.as_ref());
+
+                let table = tbl.clone();
+                let planner = p.clone();
+                let columns = cols.clone();
+                let ccm = ccm.clone();
+                let returning = returning(&ctx, &columns);
+                let scope = RequestScope::from_ctx(&ctx);
+                let mock_data = ctx.data_opt::<Arc<MockData>>().cloned();
+
+                FieldFuture::new(async move {
+                    let condition = Some(condition?);
+                    let deleted = match mock_data {
+                        Some(mock_data) => mock::delete(
+                            mock_data.rows(table.schema_name(), table.name()),
+                            condition,
+                            &columns,
+                            &ccm,
+                        )?,
+                        None => {
+                            let delete = Operation::Delete(Delete {
+                                table: table.name().to_string(),
+                                filters: filters(condition, &columns, &ccm)?,
+                                returning,
+                            });
+                            row_list(planner.mutate(&delete, Vec::new(), scope).await?)
+                        }
+                    };
+                    Ok(first_row(deleted))
+                })
+            })
+            .argument(InputValue::new("input", TypeRef::named_nn(input_name))),
+        ));
+    }
+
+    if table.is_exposed_for(Privilege::Delete) {
         let cols = all_columns.clone();
         let cm = cond_col_map.clone();
-        let p = pool.clone();
+        let p = planner.clone();
         let s = tbl_schema;
         let n = tbl_name;
         let cond_ref = format!("{}Condition", type_name);
 
-        let field = Field::new(
-            format!("delete{}", type_name),
-            TypeRef::named_nn_list_nn(type_name),
-            move |ctx| {
-                let condition_pairs: Option<Vec<(String, GqlValue)>> = ctx
-                    .args
-                    .get("condition")
-                    .and_then(|v| v.object().ok())
-                    .map(|obj| {
-                        obj.iter()
-                            .map(|(k, v)| (k.to_string(), v.as_value().clone()))
-                            .collect()
-                    });
+        let name = inflector.delete_field(&type_name, "");
+        let field = Field::new(&name, TypeRef::named_nn_list_nn(type_name), move |ctx| {
+            let condition_pairs: Option<Vec<(String, GqlValue)>> = ctx
+                .args
+                .get("condition")
+                .and_then(|v| v.object().ok())
+                .map(|obj| {
+                    obj.iter()
+                        .map(|(k, v)| (k.to_string(), v.as_value().clone()))
+                        .collect()
+                });
 
-                let pool = p.clone();
-                let schema = s.clone();
-                let name = n.clone();
-                let columns = cols.clone();
-                let ccm = cm.clone();
-                let tx_config = ctx.data_opt::<TransactionConfig>().cloned();
+            let planner = p.clone();
+            let schema = s.clone();
+            let name = n.clone();
+            let columns = cols.clone();
+            let ccm = cm.clone();
+            let returning = returning(&ctx, &columns);
+            let scope = RequestScope::from_ctx(&ctx);
+            let mock_data = ctx.data_opt::<Arc<MockData>>().cloned();
 
-                FieldFuture::new(async move {
-                    executor::execute_delete(
-                        &pool,
-                        &schema,
-                        &name,
+            FieldFuture::new(async move {
+                if let Some(mock_data) = mock_data {
+                    return mock::delete(
+                        mock_data.rows(&schema, &name),
                         condition_pairs,
                         &columns,
                         &ccm,
-                        tx_config,
-                    )
-                    .await
-                })
-            },
-        )
+                    );
+                }
+                let delete = Operation::Delete(Delete {
+                    table: name,
+                    filters: filters(condition_pairs, &columns, &ccm)?,
+                    returning,
+                });
+                Ok(row_list(planner.mutate(&delete, Vec::new(), scope).await?))
+            })
+        })
         .argument(InputValue::new("condition", TypeRef::named(cond_ref)));
 
-        fields.push(field);
+        fields.push((name, Privilege::Delete, field));
     }
 
     GeneratedMutation {
@@ -243,3 +653,26 @@ pub fn generate_mutation(table: Arc<Table>, pool: Arc<Pool>) -> GeneratedMutatio
         input_objects,
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tokio_postgres::types::Type;
+
+    #[test]
+    fn test_reject_insert_only_column_in_patch() {
+        let mut created_by = Column::new_for_test("created_by", Type::TEXT, false, false);
+        created_by.set_insert_only_for_test();
+        let columns = vec![
+            Arc::new(Column::new_for_test("title", Type::TEXT, false, false)),
+            Arc::new(created_by),
+        ];
+
+        let title = [("title".to_string(), GqlValue::from("x"))];
+        assert!(reject_insert_only(&title, &columns).is_ok());
+
+        let author = [("created_by".to_string(), GqlValue::from("bob"))];
+        let err = reject_insert_only(&author, &columns).unwrap_err();
+        assert!(err.message.contains("insert-only"), "{}", err.message);
+    }
+}
@@ -0,0 +1,149 @@
+//! Files sent with the GraphQL multipart request spec for the `Upload`
+//! inputs of `bytea` and `@largeObject` columns. A `bytea` value is bound
+//! whole, while a large object is written in chunks with `lo_put` so the file
+//! never has to fit in memory.
+
+use std::collections::HashMap;
+use std::io::SeekFrom;
+use std::sync::Arc;
+
+use async_graphql::dynamic::ResolverContext;
+use async_graphql::{InputType, Upload, Value as GqlValue};
+use tokio::io::{AsyncReadExt, AsyncSeekExt};
+use tokio_postgres::Client;
+
+use crate::error::{coded_err, db_err, gql_err};
+use crate::models::table::Column;
+
+use super::super::sql_scalar::SqlScalar;
+
+/// Bytes written per `lo_put` call.
+const CHUNK_SIZE: usize = 256 * 1024;
+
+/// A file received for an upload column, spooled to a temporary file by the
+/// multipart parser.
+#[derive(Clone)]
+pub(crate) struct UploadedFile(Arc<std::fs::File>);
+
+impl UploadedFile {
+    /// The file, rewound, as read by each attempt of a transaction.
+    async fn open(&self) -> Result<tokio::fs::File, async_graphql::Error> {
+        let file = self.0.try_clone().map_err(upload_err)?;
+        let mut file = tokio::fs::File::from_std(file);
+        file.seek(SeekFrom::Start(0)).await.map_err(upload_err)?;
+        Ok(file)
+    }
+
+    /// The whole file, for a `bytea` column.
+    pub(super) async fn read(&self) -> Result<Vec<u8>, async_graphql::Error> {
+        let mut bytes = Vec::new();
+        self.open()
+            .await?
+            .read_to_end(&mut bytes)
+            .await
+            .map_err(upload_err)?;
+        Ok(bytes)
+    }
+}
+
+/// The uploads of a mutation: the column index of each, with its file.
+pub(crate) type Uploads = Vec<(usize, UploadedFile)>;
+
+/// Takes every upload out of `pairs`, leaving the other values (and `null`
+/// uploads) for the planner. Fails with `UPLOAD_TOO_LARGE` when a file
+/// exceeds `max_bytes`.
+pub(super) fn take_uploads(
+    ctx: &ResolverContext,
+    pairs: &mut Vec<(String, GqlValue)>,
+    columns: &[Arc<Column>],
+    col_map: &HashMap<String, usize>,
+    max_bytes: Option<u64>,
+) -> Result<Uploads, async_graphql::Error> {
+    let mut uploads = Vec::new();
+    let mut rest = Vec::with_capacity(pairs.len());
+    for (key, value) in pairs.drain(..) {
+        let idx = col_map.get(&key).copied();
+        let Some(idx) = idx.filter(|&i| columns[i].upload() && value != GqlValue::Null) else {
+            rest.push((key, value));
+            continue;
+        };
+        let upload = Upload::parse(Some(value))
+            .map_err(|_| gql_err(format!("`{key}` expects an upload")))?;
+        let file = upload.value(ctx).map_err(upload_err)?;
+        let size = file.size().map_err(upload_err)?;
+        if let Some(max) = max_bytes.filter(|&max| size > max) {
+            return Err(coded_err(
+                "UPLOAD_TOO_LARGE",
+                format!("`{key}` is {size} bytes; uploads are limited to {max}"),
+            ));
+        }
+        uploads.push((idx, UploadedFile(Arc::new(file.content))));
+    }
+    *pairs = rest;
+    Ok(uploads)
+}
+
+/// The value of each upload, with its column. Large objects are bound as
+/// [`SqlScalar::Oid`] placeholders that [`write_large_objects`] fills in,
+/// returned by their position among the values, so the values must be bound
+/// first, in order.
+pub(crate) async fn bind_uploads(
+    uploads: Uploads,
+    columns: &[Arc<Column>],
+) -> Result<(Vec<(&Arc<Column>, SqlScalar)>, Uploads), async_graphql::Error> {
+    let mut bound = Vec::with_capacity(uploads.len());
+    let mut large_objects = Vec::new();
+    for (idx, file) in uploads {
+        let col = &columns[idx];
+        if col.large_object() {
+            large_objects.push((bound.len(), file));
+            bound.push((col, SqlScalar::Oid(0)));
+        } else {
+            bound.push((col, SqlScalar::Bytea(file.read().await?)));
+        }
+    }
+    Ok((bound, large_objects))
+}
+
+/// Creates a large object for each of `large_objects` and streams its file
+/// into it, setting the parameter to the new object's OID.
+pub(crate) async fn write_large_objects(
+    client: &Client,
+    large_objects: &Uploads,
+    params: &mut [SqlScalar],
+) -> Result<(), async_graphql::Error> {
+    if large_objects.is_empty() {
+        return Ok(());
+    }
+    let put = client
+        .prepare("SELECT lo_put($1, $2, $3)")
+        .await
+        .map_err(|e| db_err("large object error", e))?;
+    for (param, file) in large_objects {
+        let oid: u32 = client
+            .query_one("SELECT lo_create(0)", &[])
+            .await
+            .map_err(|e| db_err("large object error", e))?
+            .get(0);
+        let mut file = file.open().await?;
+        let mut chunk = vec![0; CHUNK_SIZE];
+        let mut offset = 0i64;
+        loop {
+            let len = file.read(&mut chunk).await.map_err(upload_err)?;
+            if len == 0 {
+                break;
+            }
+            client
+                .execute(&put, &[&oid, &offset, &&chunk[..len]])
+                .await
+                .map_err(|e| db_err("large object error", e))?;
+            offset += len as i64;
+        }
+        params[*param] = SqlScalar::Oid(oid);
+    }
+    Ok(())
+}
+
+fn upload_err(e: std::io::Error) -> async_graphql::Error {
+    gql_err(format!("Failed to read upload: {e}"))
+}
@@ -0,0 +1,167 @@
+//! Boolean permission fields such as `canEdit` on an entity type, each backed
+//! by a SQL expression from
+//! [`Config::permission_fields`](crate::Config::permission_fields) and
+//! evaluated for the row being resolved, so clients can show or hide actions
+//! from the same policy the database enforces.
+
+use std::sync::Arc;
+
+use async_graphql::Value as GqlValue;
+use async_graphql::dynamic::{Field, FieldFuture, FieldValue, TypeRef};
+use deadpool_postgres::Pool;
+
+use crate::db::request::RequestScope;
+use crate::db::transaction::with_transaction;
+use crate::error::{db_err, gql_err};
+use crate::models::config::PermissionField;
+use crate::models::mock::MockData;
+use crate::models::table::Table;
+use crate::sql::{audit, param_refs, quote_ident, quote_qualified};
+
+use super::sql_scalar::SqlScalar;
+use super::type_mapping::to_sql_scalar;
+
+/// The fields of `permissions` configured for `table`, leaving out (with a
+/// warning) those named like a column or whose expression fails the
+/// injection audit, and all of them when the table has no primary key to
+/// find the row by.
+pub(crate) fn generate_permission_fields(
+    table: &Arc<Table>,
+    permissions: &[PermissionField],
+    pool: &Arc<Pool>,
+) -> Vec<Field> {
+    let permissions: Vec<&PermissionField> =
+        permissions.iter().filter(|p| p.applies_to(table)).collect();
+    if permissions.is_empty() {
+        return Vec::new();
+    }
+    if table.primary_key().is_empty() {
+        eprintln!(
+            "[turbograph] {} has no primary key; skipping its permission fields",
+            table.type_name()
+        );
+        return Vec::new();
+    }
+
+    permissions
+        .into_iter()
+        .filter_map(|permission| {
+            if table
+                .columns()
+                .iter()
+                .any(|c| !c.omit_read() && c.field_name() == permission.field)
+            {
+                eprintln!(
+                    "[turbograph] {} already has a field named {}; skipping permission field",
+                    table.type_name(),
+                    permission.field
+                );
+                return None;
+            }
+            if let Err(reason) = audit(&permission.expression) {
+                eprintln!(
+                    "[turbograph] skipping permission field {}.{}: {reason}",
+                    table.type_name(),
+                    permission.field
+                );
+                return None;
+            }
+            Some(generate_permission_field(
+                table.clone(),
+                permission,
+                pool.clone(),
+            ))
+        })
+        .collect()
+}
+
+/// `SELECT coalesce((<expression>), false) FROM <table> AS <table> WHERE
+/// <primary key> = <the row's key>`, so a `NULL` result reads as `false`.
+fn generate_permission_field(
+    table: Arc<Table>,
+    permission: &PermissionField,
+    pool: Arc<Pool>,
+) -> Field {
+    let conditions: Vec<String> = table
+        .primary_key()
+        .iter()
+        .enumerate()
+        .map(|(i, column)| format!("{} = ${}", quote_ident(column), i + 1))
+        .collect();
+    let sql = Arc::new(format!(
+        "SELECT coalesce(({}), false) FROM {} AS {} WHERE {}",
+        permission.expression,
+        quote_qualified(table.schema_name(), table.name()),
+        quote_ident(table.name()),
+        conditions.join(" AND ")
+    ));
+
+    Field::new(
+        &permission.field,
+        TypeRef::named_nn(TypeRef::BOOLEAN),
+        move |ctx| {
+            let table = table.clone();
+            let pool = pool.clone();
+            let sql = sql.clone();
+            let scope = RequestScope::from_ctx(&ctx);
+            let mock_data = ctx.data_opt::<Arc<MockData>>().is_some();
+
+            FieldFuture::new(async move {
+                if mock_data {
+                    return Err(gql_err(
+                        "Permission fields cannot be evaluated in mock mode",
+                    ));
+                }
+                let parent = ctx.parent_value.try_downcast_ref::<serde_json::Value>()?;
+                let params = row_key(&table, parent)?;
+                let cache_key = format!("{sql}\n{params:?}");
+                let fetch_scope = scope.clone();
+
+                let allowed = scope
+                    .memoize(cache_key, || async move {
+                        fetch_scope.log_statement(&sql);
+                        with_transaction(&pool, fetch_scope, |client| {
+                            let params = params.clone();
+                            let sql = sql.clone();
+                            Box::pin(async move {
+                                let row = client
+                                    .query_opt(sql.as_str(), &param_refs(&params))
+                                    .await
+                                    .map_err(|e| db_err("permission check error", e))?;
+                                Ok(row.is_some_and(|row| row.get::<_, bool>(0)))
+                            })
+                        })
+                        .await
+                    })
+                    .await?;
+                Ok(Some(FieldValue::value(allowed)))
+            })
+        },
+    )
+}
+
+/// The primary key of the row being resolved, as parameters.
+pub(super) fn row_key(
+    table: &Table,
+    row: &serde_json::Value,
+) -> Result<Vec<SqlScalar>, async_graphql::Error> {
+    table
+        .primary_key()
+        .iter()
+        .map(|name| {
+            let column = table
+                .columns()
+                .iter()
+                .find(|c| c.name() == name)
+                .ok_or_else(|| gql_err(format!("Unknown primary key column {name}")))?;
+            let value = row.get(name).cloned().unwrap_or_default();
+            let value = GqlValue::from_json(value).map_err(|e| gql_err(e.to_string()))?;
+            to_sql_scalar(column, &value).ok_or_else(|| {
+                gql_err(format!(
+                    "Unsupported primary key type for {}",
+                    column.field_name()
+                ))
+            })
+        })
+        .collect()
+}
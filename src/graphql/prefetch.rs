@@ -0,0 +1,265 @@
+//! Look-ahead planning of nested relation selections. Before fetching rows,
+//! the resolver inspects what the query selects on them; every foreign key
+//! relation among those fields, and the relations selected below it in turn,
+//! is fetched by the same statement as a correlated `ARRAY(SELECT ROW(...))`
+//! column, instead of by one statement per parent row:
+//!
+//! ```sql
+//! SELECT *, ROW(
+//!   ARRAY(SELECT ROW(__r1."id", __r1."body", ROW(
+//!     ARRAY(SELECT ROW(__r2."id", __r2."name") FROM "app"."users" __r2
+//!       WHERE __r2."id" = __r1."author_id" ORDER BY __r2."id")
+//!   )) FROM "app"."comments" __r1 WHERE __r1."post_id" = "posts"."id" ORDER BY __r1."id")
+//! ) AS "__turbograph_prefetch" FROM "app"."posts" ...
+//! ```
+//!
+//! The records are decoded with the column types the schema already knows,
+//! exactly like top-level rows, and stored on their parent row under
+//! [`PREFETCH_KEY`], where the relation resolvers pick them up. Rows that
+//! were fetched some other way (mutation payloads, `@ref` paths) have none,
+//! and their relation fields query as before.
+
+use std::collections::HashMap;
+use std::sync::Arc;
+
+use async_graphql::SelectionField;
+use async_graphql::dynamic::ResolverContext;
+use serde_json::{Map, Value};
+use tokio_postgres::Row;
+
+use crate::db::JsonExt;
+use crate::db::row::{RawValue, RecordReader, array_elements};
+use crate::graphql::scalars::CustomScalars;
+use crate::models::table::Table;
+use crate::sql::{quote_ident, quote_qualified};
+
+/// The key of a row's prefetched relations, and the alias of the column
+/// carrying them.
+pub(crate) const PREFETCH_KEY: &str = "__turbograph_prefetch";
+
+/// A foreign key relation field, seen from the rows it starts at.
+pub(crate) struct Join {
+    /// The table of the related rows.
+    pub table: Arc<Table>,
+    /// Columns of `table` that must equal the parent's `parent_columns`.
+    pub columns: Vec<String>,
+    pub parent_columns: Vec<String>,
+}
+
+/// The foreign key relation fields of each table's type, by table OID and
+/// field name. Handed to resolvers as schema data.
+#[derive(Default)]
+pub(crate) struct Joins(HashMap<u32, HashMap<String, Arc<Join>>>);
+
+impl Joins {
+    pub(crate) fn insert(&mut self, table_oid: u32, field_name: String, join: Join) {
+        self.0
+            .entry(table_oid)
+            .or_default()
+            .insert(field_name, Arc::new(join));
+    }
+}
+
+/// The relation fields selected on a set of rows, to fetch along with them.
+#[derive(Clone, Default)]
+pub(crate) struct Prefetch(Arc<Vec<PrefetchField>>);
+
+struct PrefetchField {
+    field_name: String,
+    join: Arc<Join>,
+    nested: Prefetch,
+}
+
+impl Prefetch {
+    /// Plans the relation fields among `selection`, the fields selected on
+    /// rows of the table with OID `table_oid`, and those below them. A field
+    /// selected several times (through aliases or fragments) is fetched once,
+    /// with the union of its selections.
+    pub(crate) fn plan<'a>(
+        ctx: &ResolverContext,
+        table_oid: u32,
+        selection: impl IntoIterator<Item = SelectionField<'a>>,
+    ) -> Self {
+        match ctx.data_opt::<Arc<Joins>>() {
+            Some(joins) => Self::plan_with(joins, table_oid, selection.into_iter().collect()),
+            None => Self::default(),
+        }
+    }
+
+    /// Plans the relations selected on the rows of `table` that the current
+    /// field returns.
+    pub(crate) fn for_field(ctx: &ResolverContext, table: &Table) -> Self {
+        let selection = ctx.look_ahead().selection_fields();
+        Self::plan(
+            ctx,
+            *table.oid(),
+            selection.iter().flat_map(|field| field.selection_set()),
+        )
+    }
+
+    fn plan_with(joins: &Joins, table_oid: u32, selection: Vec<SelectionField<'_>>) -> Self {
+        let Some(relations) = joins.0.get(&table_oid) else {
+            return Self::default();
+        };
+        let mut groups: Vec<(&str, &Arc<Join>, Vec<SelectionField<'_>>)> = Vec::new();
+        for field in selection {
+            let Some(join) = relations.get(field.name()) else {
+                continue;
+            };
+            let children = field.selection_set();
+            match groups.iter_mut().find(|(name, ..)| *name == field.name()) {
+                Some((_, _, selection)) => selection.extend(children),
+                None => groups.push((field.name(), join, children.collect())),
+            }
+        }
+        Self(Arc::new(
+            groups
+                .into_iter()
+                .map(|(name, join, selection)| PrefetchField {
+                    field_name: name.to_string(),
+                    join: join.clone(),
+                    nested: Self::plan_with(joins, *join.table.oid(), selection),
+                })
+                .collect(),
+        ))
+    }
+
+    pub(crate) fn is_empty(&self) -> bool {
+        self.0.is_empty()
+    }
+
+    /// `, ROW(...) AS "__turbograph_prefetch"` to append to the select list
+    /// of a statement whose rows are aliased `parent`, or nothing when no
+    /// relation is planned.
+    pub(crate) fn select(&self, parent: &str) -> String {
+        if self.is_empty() {
+            return String::new();
+        }
+        format!(
+            ", {} AS {}",
+            self.record(&quote_ident(parent), 1),
+            quote_ident(PREFETCH_KEY)
+        )
+    }
+
+    /// One array of related rows per planned field, each row a record of
+    /// its table's columns followed, when relations are selected below it,
+    /// by their own record.
+    fn record(&self, parent: &str, depth: usize) -> String {
+        let alias = format!("__r{depth}");
+        let arrays: Vec<String> = self
+            .0
+            .iter()
+            .map(|field| {
+                let table = &field.join.table;
+                let mut values: Vec<String> = table
+                    .columns()
+                    .iter()
+                    .map(|c| format!("{alias}.{}", quote_ident(c.name())))
+                    .collect();
+                if !field.nested.is_empty() {
+                    values.push(field.nested.record(&alias, depth + 1));
+                }
+                let on: Vec<String> = field
+                    .join
+                    .columns
+                    .iter()
+                    .zip(&field.join.parent_columns)
+                    .map(|(column, parent_column)| {
+                        format!(
+                            "{alias}.{} = {parent}.{}",
+                            quote_ident(column),
+                            quote_ident(parent_column)
+                        )
+                    })
+                    .collect();
+                let mut sql = format!(
+                    "ARRAY(SELECT ROW({}) FROM {} {alias} WHERE {}",
+                    values.join(", "),
+                    quote_qualified(table.schema_name(), table.name()),
+                    on.join(" AND ")
+                );
+                if !table.primary_key().is_empty() {
+                    let order: Vec<String> = table
+                        .primary_key()
+                        .iter()
+                        .map(|c| format!("{alias}.{}", quote_ident(c)))
+                        .collect();
+                    sql.push_str(&format!(" ORDER BY {}", order.join(", ")));
+                }
+                sql.push(')');
+                sql
+            })
+            .collect();
+        format!("ROW({})", arrays.join(", "))
+    }
+
+    /// `rows` as JSON, each with its prefetched relations under
+    /// [`PREFETCH_KEY`].
+    pub(crate) fn rows_to_json(&self, rows: &[Row], scalars: Option<&CustomScalars>) -> Vec<Value> {
+        rows.iter()
+            .map(|row| {
+                let mut json = row.to_json(scalars);
+                if !self.is_empty()
+                    && let Value::Object(map) = &mut json
+                {
+                    let raw = row.try_get::<_, RawValue>(PREFETCH_KEY).ok();
+                    match raw
+                        .and_then(|raw| raw.0)
+                        .and_then(|raw| self.decode(raw, scalars))
+                    {
+                        Some(prefetched) => map.insert(PREFETCH_KEY.to_string(), prefetched),
+                        None => map.remove(PREFETCH_KEY),
+                    };
+                }
+                json
+            })
+            .collect()
+    }
+
+    /// The object of related rows by field name that [`record`](Self::record)
+    /// selected, or `None` when `raw` does not have its shape.
+    fn decode(&self, raw: &[u8], scalars: Option<&CustomScalars>) -> Option<Value> {
+        let mut record = RecordReader::new(raw, scalars)?;
+        let mut prefetched = Map::new();
+        for field in self.0.iter() {
+            let rows = match record.raw()? {
+                Some(array) => array_elements(array)?
+                    .into_iter()
+                    .map(|row| field.decode_row(row?, scalars))
+                    .collect::<Option<Vec<_>>>()?,
+                None => Vec::new(),
+            };
+            prefetched.insert(field.field_name.clone(), Value::Array(rows));
+        }
+        Some(Value::Object(prefetched))
+    }
+}
+
+impl PrefetchField {
+    fn decode_row(&self, raw: &[u8], scalars: Option<&CustomScalars>) -> Option<Value> {
+        let mut record = RecordReader::new(raw, scalars)?;
+        let mut row = Map::new();
+        for column in self.join.table.columns() {
+            row.insert(column.name().clone(), record.value(column._type())?);
+        }
+        if !self.nested.is_empty()
+            && let Some(nested) = record.raw()?
+        {
+            row.insert(
+                PREFETCH_KEY.to_string(),
+                self.nested.decode(nested, scalars)?,
+            );
+        }
+        Some(Value::Object(row))
+    }
+}
+
+/// The rows of `field_name` fetched along with `parent`, if they were.
+pub(crate) fn prefetched(parent: &Value, field_name: &str) -> Option<Vec<Value>> {
+    parent
+        .get(PREFETCH_KEY)?
+        .get(field_name)?
+        .as_array()
+        .cloned()
+}
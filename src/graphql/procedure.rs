@@ -0,0 +1,392 @@
+//! Stored procedures exposed as mutations (or queries, when declared as not
+//! modifying the database). Each `call{P}` field runs
+//! `CALL schema.procedure(...)` with the arguments from `Call{P}Input` and
+//! maps the returned `INOUT` / `OUT` arguments into `Call{P}Payload`.
+
+use std::sync::Arc;
+
+use async_graphql::Value as GqlValue;
+use async_graphql::dynamic::{
+    Field, FieldFuture, FieldValue, InputObject, InputValue, Object, ResolverContext, TypeRef,
+};
+use deadpool_postgres::Pool;
+use tokio_postgres::types::{ToSql, Type};
+
+use crate::db::JsonExt;
+use crate::db::request::RequestScope;
+use crate::db::transaction::with_transaction;
+use crate::error::{db_err, gql_err};
+use crate::models::config::NonFiniteFloats;
+use crate::models::mock::MockData;
+use crate::models::procedure::{ArgMode, Procedure, ProcedureArg};
+use crate::models::table::Column;
+use crate::sql::{quote_ident, quote_qualified};
+use crate::utils::inflection::{column_field_name, to_pascal_case};
+
+use super::scalars::CustomScalars;
+use super::sql_scalar::SqlScalar;
+use super::type_mapping::{condition_type_ref, get_field_value, get_type_ref, to_sql_scalar};
+
+/// The mutation field and types generated for one procedure.
+pub(crate) struct GeneratedProcedure {
+    pub field_name: String,
+    pub field: Field,
+    /// Whether the field goes on the query root, see
+    /// [`Volatility::is_read_only`](crate::models::procedure::Volatility::is_read_only).
+    pub read_only: bool,
+    /// `Call{P}Input`, unless the procedure takes no arguments.
+    pub input: Option<InputObject>,
+    /// `Call{P}Payload`, unless the procedure returns nothing. Such
+    /// procedures answer `true` once the call has completed.
+    pub payload: Option<Object>,
+}
+
+/// An argument of the routine being called, with the column standing in
+/// for it in type mapping.
+pub(super) struct CallArg {
+    pub(super) column: Column,
+    pub(super) mode: ArgMode,
+    pub(super) has_default: bool,
+    /// Whether the routine names the argument, see [`bind_args`].
+    pub(super) named: bool,
+    pub(super) description: Option<String>,
+}
+
+/// The arguments of a routine, named like columns.
+pub(super) fn call_args(args: &[ProcedureArg], camel_case_fields: bool) -> Vec<CallArg> {
+    args.iter()
+        .map(|arg| {
+            let mut column = Column::procedure_arg(arg.name(), arg._type().clone());
+            column.set_field_name(&column_field_name(arg.name(), camel_case_fields));
+            CallArg {
+                column,
+                mode: arg.mode(),
+                has_default: arg.has_default(),
+                named: arg.is_named(),
+                description: arg.description().map(str::to_string),
+            }
+        })
+        .collect()
+}
+
+/// An object with a field for each of `outputs`, resolving the value of the
+/// result row's column of that name.
+pub(super) fn output_object<'a>(
+    type_name: String,
+    outputs: impl IntoIterator<Item = &'a CallArg>,
+    custom_scalars: Option<&CustomScalars>,
+) -> Object {
+    let mut object = Object::new(type_name);
+    for arg in outputs {
+        let column = Arc::new(arg.column.clone());
+        let mut field = Field::new(
+            column.field_name().to_string(),
+            get_type_ref(&column, custom_scalars),
+            move |ctx| {
+                let column = column.clone();
+                FieldFuture::new(async move {
+                    let row = ctx.parent_value.try_downcast_ref::<serde_json::Value>()?;
+                    let floats = ctx.data_opt::<NonFiniteFloats>().copied();
+                    get_field_value(&column, row, floats.unwrap_or_default())
+                })
+            },
+        );
+        if let Some(description) = &arg.description {
+            field = field.description(description);
+        }
+        object = object.field(field);
+    }
+    object
+}
+
+/// A GraphQL argument for each input argument, or the name of the first one
+/// that has no GraphQL input type.
+pub(super) fn input_values(args: &[CallArg]) -> Result<Vec<InputValue>, &str> {
+    args.iter()
+        .filter(|a| a.mode.is_input())
+        .map(|arg| {
+            let type_ref = condition_type_ref(&arg.column).ok_or(arg.column.name().as_str())?;
+            let value = InputValue::new(arg.column.field_name(), type_ref);
+            Ok(match &arg.description {
+                Some(description) => value.description(description),
+                None => value,
+            })
+        })
+        .collect()
+}
+
+/// The fields of the `input` argument, by name.
+pub(super) fn input_fields(ctx: &ResolverContext) -> Vec<(String, GqlValue)> {
+    ctx.args
+        .get("input")
+        .and_then(|v| v.object().ok())
+        .map(|obj| {
+            obj.iter()
+                .map(|(k, v)| (k.to_string(), v.as_value().clone()))
+                .collect()
+        })
+        .unwrap_or_default()
+}
+
+/// Generates the `call{P}` mutation for `procedure`, or `None` (with a
+/// warning) when one of its input arguments has no GraphQL input type.
+pub(crate) fn generate_procedure(
+    procedure: Arc<Procedure>,
+    pool: Arc<Pool>,
+    locale: Option<&str>,
+    camel_case_fields: bool,
+    custom_scalars: Option<&CustomScalars>,
+) -> Option<GeneratedProcedure> {
+    let type_name = format!("Call{}", to_pascal_case(procedure.name()));
+    let field_name = format!("call{}", to_pascal_case(procedure.name()));
+
+    let args: Arc<Vec<CallArg>> = Arc::new(call_args(procedure.args(), camel_case_fields));
+
+    let mut input = None;
+    let fields = match input_values(&args) {
+        Ok(fields) => fields,
+        Err(name) => {
+            eprintln!(
+                "[turbograph] skipping procedure {}.{}: argument {name} has no input type",
+                procedure.schema_name(),
+                procedure.name(),
+            );
+            return None;
+        }
+    };
+    if !fields.is_empty() {
+        let object = InputObject::new(format!("{type_name}Input"));
+        input = Some(fields.into_iter().fold(object, InputObject::field));
+    }
+
+    let mut payload = None;
+    if args.iter().any(|a| a.mode.is_output()) {
+        let outputs = args.iter().filter(|a| a.mode.is_output());
+        payload = Some(output_object(
+            format!("{type_name}Payload"),
+            outputs,
+            custom_scalars,
+        ));
+    }
+
+    let return_type = if payload.is_some() {
+        TypeRef::named(format!("{type_name}Payload"))
+    } else {
+        TypeRef::named_nn(TypeRef::BOOLEAN)
+    };
+    let proc = procedure.clone();
+    let call_args = args.clone();
+    let mut field = Field::new(&field_name, return_type, move |ctx| {
+        let input = input_fields(&ctx);
+
+        let pool = pool.clone();
+        let procedure = proc.clone();
+        let args = call_args.clone();
+        let scope = RequestScope::from_ctx(&ctx);
+        let mock_data = ctx.data_opt::<Arc<MockData>>().is_some();
+
+        FieldFuture::new(async move {
+            if mock_data {
+                return Err(gql_err("Procedures cannot be called in mock mode"));
+            }
+            if procedure.is_strict() && has_null_input(&args, &input) {
+                return Ok(skipped_call(&args));
+            }
+            execute_call(&pool, &procedure, &args, input, scope).await
+        })
+    });
+    if let Some(input) = &input {
+        field = field.argument(InputValue::new(
+            "input",
+            TypeRef::named_nn(input.type_name()),
+        ));
+    }
+    let mut description = procedure
+        .descriptions()
+        .get(locale)
+        .map(str::to_string)
+        .unwrap_or_default();
+    if procedure.is_security_definer() {
+        if !description.is_empty() {
+            description.push_str("\n\n");
+        }
+        description.push_str("Runs with the privileges of its owner (`SECURITY DEFINER`).");
+    }
+    if !description.is_empty() {
+        field = field.description(description);
+    }
+
+    Some(GeneratedProcedure {
+        field_name,
+        field,
+        read_only: procedure.volatility().is_read_only(),
+        input,
+        payload,
+    })
+}
+
+/// Whether a `STRICT` routine would get a `NULL` input: one given as
+/// `null`, or left out without a default.
+pub(super) fn has_null_input(args: &[CallArg], input: &[(String, GqlValue)]) -> bool {
+    args.iter().filter(|a| a.mode.is_input()).any(|arg| {
+        match input.iter().find(|(key, _)| key == arg.column.field_name()) {
+            Some((_, value)) => matches!(value, GqlValue::Null),
+            None => !arg.has_default,
+        }
+    })
+}
+
+/// The answer of a `STRICT` procedure called with a `NULL` input, which
+/// Postgres skips: a `null` payload, or `false` when there is none.
+fn skipped_call(args: &[CallArg]) -> Option<FieldValue<'static>> {
+    if args.iter().any(|a| a.mode.is_output()) {
+        None
+    } else {
+        Some(FieldValue::value(false))
+    }
+}
+
+/// Each argument as `name => $1::type`, binding its value into `params`.
+/// Arguments are passed by name so that omitted ones with a default keep it;
+/// other omitted arguments, and `OUT` arguments, are passed as a typed
+/// `NULL`.
+///
+/// A routine with an unnamed argument is called positionally instead, as
+/// `$1::type, ...`. Only its trailing arguments with a default can then be
+/// left out.
+pub(super) fn bind_args(
+    args: &[CallArg],
+    input: &[(String, GqlValue)],
+    params: &mut Vec<SqlScalar>,
+) -> Result<Vec<String>, async_graphql::Error> {
+    if args.iter().any(|a| !a.named) {
+        return bind_positional(args, input, params);
+    }
+    let mut parts = Vec::new();
+    for arg in args {
+        let name = arg.column.name();
+        let value = input
+            .iter()
+            .find(|(key, _)| key == arg.column.field_name())
+            .map(|(_, value)| value)
+            .filter(|_| arg.mode.is_input());
+        let value = match value {
+            None if arg.has_default => continue,
+            None | Some(GqlValue::Null) => "NULL".to_string(),
+            Some(value) => {
+                let scalar = to_sql_scalar(&arg.column, value)
+                    .ok_or_else(|| gql_err(format!("Invalid value for argument `{name}`")))?;
+                params.push(scalar);
+                format!("${}", params.len())
+            }
+        };
+        parts.push(format!(
+            "{} => {value}::{}",
+            quote_ident(name),
+            type_name(arg.column._type())
+        ));
+    }
+    Ok(parts)
+}
+
+/// [`bind_args`] for a routine with an unnamed argument.
+fn bind_positional(
+    args: &[CallArg],
+    input: &[(String, GqlValue)],
+    params: &mut Vec<SqlScalar>,
+) -> Result<Vec<String>, async_graphql::Error> {
+    let mut parts = Vec::new();
+    for arg in args {
+        let value = input
+            .iter()
+            .find(|(key, _)| key == arg.column.field_name())
+            .map(|(_, value)| value)
+            .filter(|_| arg.mode.is_input());
+        let value = match value {
+            None if arg.has_default => None,
+            None | Some(GqlValue::Null) => Some("NULL".to_string()),
+            Some(value) => {
+                let scalar = to_sql_scalar(&arg.column, value).ok_or_else(|| {
+                    gql_err(format!(
+                        "Invalid value for argument `{}`",
+                        arg.column.name()
+                    ))
+                })?;
+                params.push(scalar);
+                Some(format!("${}", params.len()))
+            }
+        };
+        parts.push(value.map(|value| format!("{value}::{}", type_name(arg.column._type()))));
+    }
+    while parts.last().is_some_and(Option::is_none) {
+        parts.pop();
+    }
+    parts
+        .into_iter()
+        .zip(args)
+        .map(|(part, arg)| {
+            part.ok_or_else(|| {
+                gql_err(format!(
+                    "Argument `{}` must be given when a later argument is",
+                    arg.column.field_name()
+                ))
+            })
+        })
+        .collect()
+}
+
+/// `CALL schema.procedure(name => $1::type, ...)`, see [`bind_args`].
+async fn execute_call(
+    pool: &Pool,
+    procedure: &Procedure,
+    args: &[CallArg],
+    input: Vec<(String, GqlValue)>,
+    scope: RequestScope,
+) -> Result<Option<FieldValue<'static>>, async_graphql::Error> {
+    let mut params = Vec::<SqlScalar>::new();
+    let parts = bind_args(args, &input, &mut params)?;
+
+    let sql = format!(
+        "CALL {}({})",
+        quote_qualified(procedure.schema_name(), procedure.name()),
+        parts.join(", ")
+    );
+    let returns_row = args.iter().any(|a| a.mode.is_output());
+
+    scope.log_statement(&sql);
+
+    let scalars = scope.custom_scalars.clone();
+    let result = with_transaction(pool, scope.clone(), |client| {
+        let params = params.clone();
+        let sql = sql.clone();
+        let scalars = scalars.clone();
+        Box::pin(async move {
+            let refs: Vec<&(dyn ToSql + Sync)> =
+                params.iter().map(|p| p as &(dyn ToSql + Sync)).collect();
+
+            if returns_row {
+                let row = client
+                    .query_one(&sql, &refs)
+                    .await
+                    .map_err(|e| db_err("CALL error", e))?;
+                Ok(Some(FieldValue::owned_any(row.to_json(scalars.as_ref()))))
+            } else {
+                client
+                    .execute(&sql, &refs)
+                    .await
+                    .map_err(|e| db_err("CALL error", e))?;
+                Ok(Some(FieldValue::value(true)))
+            }
+        })
+    })
+    .await?;
+    if !procedure.volatility().is_read_only() {
+        scope.forget_rows();
+    }
+    Ok(result)
+}
+
+/// The qualified name of a type, e.g. `"pg_catalog"."_int4"`.
+fn type_name(ty: &Type) -> String {
+    quote_qualified(ty.schema(), ty.name())
+}
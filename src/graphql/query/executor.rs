@@ -2,54 +2,149 @@ use async_graphql::dynamic::FieldValue;
 use deadpool_postgres::Pool;
 use tokio_postgres::types::ToSql;
 
-use crate::db::JsonListExt;
+use crate::db::request::RequestScope;
 use crate::db::transaction::with_transaction;
-use crate::error::gql_err;
-use crate::models::transaction::TransactionConfig;
+use crate::error::db_err;
+use crate::models::row_transform::transform_rows;
+use crate::models::table::Table;
 
-use super::super::connection::{ConnectionPayload, EdgePayload, encode_cursor};
+use super::super::connection::{ConnectionPayload, EdgePayload, Page, encode_cursor};
+use super::super::prefetch::Prefetch;
 use super::super::sql_scalar::SqlScalar;
 
+/// Runs the count and page queries of a connection over `from`, a table or an
+/// `asOf` source, the latter fetching the `select` list. Without `fetch_rows`
+/// (only `totalCount` was selected) the page query is skipped entirely. They
+/// run concurrently unless the page depends on the count, see
+/// [`Page::needs_count`]. The nodes carry the relations of `prefetch`, which
+/// `select` must fetch, and pass through the row transforms of `table`.
+/// Current rows (`current`, not read `asOf` a past time) join the request's
+/// identity map.
+#[allow(clippy::too_many_arguments)]
 pub(super) async fn execute_connection_query(
     pool: &Pool,
-    tbl_schema: &str,
-    tbl_name: &str,
+    table: &Table,
+    current: bool,
+    from: &str,
+    select: &str,
     where_clause: &str,
     order_clause: &str,
     params: Vec<SqlScalar>,
-    limit: i64,
-    offset: i64,
+    page: Page,
     order_by: &[String],
-    tx_config: Option<TransactionConfig>,
+    fetch_rows: bool,
+    prefetch: Prefetch,
+    scope: RequestScope,
 ) -> Result<Option<FieldValue<'static>>, async_graphql::Error> {
     let limit_param = params.len() + 1;
     let offset_param = params.len() + 2;
 
-    let count_sql =
-        format!("SELECT COUNT(*) FROM \"{tbl_schema}\".\"{tbl_name}\"{where_clause}");
-    let data_sql = format!(
-        "SELECT * FROM \"{tbl_schema}\".\"{tbl_name}\"{where_clause}{order_clause} LIMIT ${limit_param} OFFSET ${offset_param}"
-    );
+    let count_sql = format!("SELECT COUNT(*) FROM {from}{where_clause}");
+    let data_sql = fetch_rows.then(|| {
+        format!(
+            "SELECT {select} FROM {from}{where_clause}{order_clause} LIMIT ${limit_param} OFFSET ${offset_param}"
+        )
+    });
     let order_by = order_by.to_vec();
 
-    with_transaction(pool, tx_config, |client| {
+    // Identical statements within one request (e.g. a selection repeated
+    // through fragments or aliases) only reach the database once.
+    let cache_key = format!("{count_sql}\n{data_sql:?}\n{params:?}\n{page:?}\n{order_by:?}");
+    let fetch_scope = scope.clone();
+    let mut payload = scope
+        .memoize(cache_key, || {
+            fetch_connection(
+                pool,
+                fetch_scope,
+                count_sql,
+                data_sql,
+                params,
+                page,
+                order_by,
+                prefetch,
+            )
+        })
+        .await?;
+
+    let mut nodes = payload
+        .edges
+        .iter_mut()
+        .map(|edge| std::mem::take(&mut edge.node))
+        .collect();
+    if current {
+        nodes = scope.identify(table, nodes);
+    }
+    let nodes = transform_rows(&scope, table.schema_name(), table.name(), nodes).await?;
+    for (edge, node) in payload.edges.iter_mut().zip(nodes) {
+        edge.node = node;
+    }
+
+    Ok(Some(FieldValue::owned_any(payload)))
+}
+
+#[allow(clippy::too_many_arguments)]
+async fn fetch_connection(
+    pool: &Pool,
+    scope: RequestScope,
+    count_sql: String,
+    data_sql: Option<String>,
+    params: Vec<SqlScalar>,
+    page: Page,
+    order_by: Vec<String>,
+    prefetch: Prefetch,
+) -> Result<ConnectionPayload, async_graphql::Error> {
+    scope.log_statement(&count_sql);
+    if let Some(data_sql) = &data_sql {
+        scope.log_statement(data_sql);
+    }
+
+    let scalars = scope.custom_scalars.clone();
+    with_transaction(pool, scope, |client| {
+        let params = params.clone();
+        let count_sql = count_sql.clone();
+        let data_sql = data_sql.clone();
+        let order_by = order_by.clone();
+        let page = page.clone();
+        let prefetch = prefetch.clone();
+        let scalars = scalars.clone();
         Box::pin(async move {
             let base_refs: Vec<&(dyn ToSql + Sync)> =
                 params.iter().map(|p| p as &(dyn ToSql + Sync)).collect();
-            let data_refs: Vec<&(dyn ToSql + Sync)> = base_refs
-                .iter()
-                .copied()
-                .chain([&limit as &(dyn ToSql + Sync), &offset as _])
-                .collect();
 
-            let (count_row, data_rows) = tokio::try_join!(
-                client.query_one(&count_sql, &base_refs),
-                client.query(&data_sql, &data_refs),
-            )
-            .map_err(|e| gql_err(format!("DB query error: {e}")))?;
+            let (total_count, data_rows, offset) = match &data_sql {
+                Some(data_sql) if page.needs_count() => {
+                    let count_row = client
+                        .query_one(&count_sql, &base_refs)
+                        .await
+                        .map_err(|e| db_err("DB query error", e))?;
+                    let total_count: i64 = count_row.get(0);
+                    let bounds = page.bounds(total_count);
+                    let data_rows = client
+                        .query(data_sql, &with_bounds(&base_refs, &bounds))
+                        .await
+                        .map_err(|e| db_err("DB query error", e))?;
+                    (total_count, data_rows, bounds.0)
+                }
+                Some(data_sql) => {
+                    let bounds = page.bounds(i64::MAX);
+                    let data_refs = with_bounds(&base_refs, &bounds);
+                    let (count_row, data_rows) = tokio::try_join!(
+                        client.query_one(&count_sql, &base_refs),
+                        client.query(data_sql, &data_refs),
+                    )
+                    .map_err(|e| db_err("DB query error", e))?;
+                    (count_row.get(0), data_rows, bounds.0)
+                }
+                None => {
+                    let count_row = client
+                        .query_one(&count_sql, &base_refs)
+                        .await
+                        .map_err(|e| db_err("DB query error", e))?;
+                    (count_row.get(0), Vec::new(), 0)
+                }
+            };
 
-            let total_count: i64 = count_row.get(0);
-            let json_rows = data_rows.to_json_list();
+            let json_rows = prefetch.rows_to_json(&data_rows, scalars.as_ref());
             let edge_count = json_rows.len() as i64;
 
             let edges = json_rows
@@ -61,13 +156,25 @@ pub(super) async fn execute_connection_query(
                 })
                 .collect();
 
-            Ok(Some(FieldValue::owned_any(ConnectionPayload {
+            Ok(ConnectionPayload {
                 total_count,
                 has_next_page: (offset + edge_count) < total_count,
                 has_previous_page: offset > 0,
                 edges,
-            })))
+            })
         })
     })
     .await
 }
+
+/// `params` followed by the `LIMIT` and `OFFSET` of `(offset, limit)`.
+fn with_bounds<'a>(
+    params: &[&'a (dyn ToSql + Sync)],
+    (offset, limit): &'a (i64, i64),
+) -> Vec<&'a (dyn ToSql + Sync)> {
+    params
+        .iter()
+        .copied()
+        .chain([limit as &(dyn ToSql + Sync), offset as _])
+        .collect()
+}
@@ -3,23 +3,39 @@ use std::sync::Arc;
 
 use async_graphql::Value as GqlValue;
 use async_graphql::dynamic::{Enum, Field, FieldFuture, InputObject, InputValue, Object, TypeRef};
+use chrono::{DateTime, NaiveDateTime, Utc};
 use deadpool_postgres::Pool;
 
+use crate::db::request::RequestScope;
+use crate::error::gql_err;
+use crate::models::config::UnindexedPatternFilters;
+use crate::models::mock::MockData;
+use crate::models::row_transform::has_transforms;
 use crate::models::table::Table;
-use crate::models::transaction::TransactionConfig;
-use crate::utils::inflection::to_pascal_case;
+use crate::sql::quote_qualified;
+use crate::utils::inflection::{Inflector, pluralize};
 
-use super::connection::make_connection_types;
+use super::connection::{
+    CursorStability, OFFSET_CURSOR_WARNING, Page, decode_cursor, make_connection_types,
+};
 use super::filter::{make_condition_filter_types, make_condition_type, make_order_by_enum};
+use super::mock;
+use super::prefetch::Prefetch;
+use super::scalars;
 use super::sql_scalar::SqlScalar;
 
 mod executor;
 pub(crate) mod sql;
 
+/// Rows in a page when the query passes neither `first` nor `last`.
+const DEFAULT_PAGE_SIZE: i64 = 100;
+
 /// Everything the schema builder needs for one table.
 pub struct GeneratedQuery {
     /// The root Query field (e.g. `allUsers`).
     pub query_field: Field,
+    /// Name of `query_field`.
+    pub field_name: String,
     /// The `{T}Condition` input type - must be registered with the schema.
     pub condition_type: InputObject,
     /// Per-column filter input objects referenced by `{T}Condition`.
@@ -33,40 +49,118 @@ pub struct GeneratedQuery {
 }
 
 /// Generates a root Query field (e.g. `allUsers`) with Turbograph-style
-/// filtering arguments:
+/// filtering arguments and Relay cursor pagination:
 ///
 /// ```graphql
 /// allUsers(
 ///   condition: UserCondition   # equality filter per column
 ///   orderBy:   [UserOrderBy]   # COLUMN_ASC / COLUMN_DESC
-///   first:     Int             # LIMIT
-///   offset:    Int             # OFFSET
+///   first:     Int             # the first rows of the window
+///   last:      Int             # the last rows of the window
+///   offset:    Int             # skip rows after `after`
+///   before:    String          # end the window before this edge cursor
+///   after:     String          # start the window after this edge cursor
 /// ): UserConnection!
 /// ```
-pub fn generate_query(table: Arc<Table>, pool: Arc<Pool>) -> GeneratedQuery {
-    let condition_filter_types = make_condition_filter_types(&table);
+///
+/// Without `first` or `last` a page holds the first 100 rows. Without
+/// `orderBy` the rows follow the table's `@defaultSort` tag, if any. A
+/// cursor from another ordering or release fails with an `INVALID_CURSOR`
+/// error instead of returning the wrong page.
+/// Tables without a primary key break ties by their `@stableOrder` columns;
+/// without one the field's description warns that cursors are plain offsets.
+/// When the table's row estimate exceeds `large_table_rows`, a query
+/// relying on the default page also gets a hint in `extensions.warnings`;
+/// when it exceeds `indexed_order_by_rows`, `orderBy` only offers indexed
+/// columns. `unindexed_patterns` decides on the pattern filters no index
+/// serves.
+pub fn generate_query(
+    table: Arc<Table>,
+    pool: Arc<Pool>,
+    large_table_rows: Option<u64>,
+    indexed_order_by_rows: Option<u64>,
+    unindexed_patterns: UnindexedPatternFilters,
+    inflector: &dyn Inflector,
+) -> GeneratedQuery {
+    let condition_filter_types = make_condition_filter_types(&table, unindexed_patterns);
     let condition_type = make_condition_type(&table);
-    let order_by_enum = make_order_by_enum(&table);
-    let (connection_type, edge_type) = make_connection_types(&table);
+    let order_by_enum = make_order_by_enum(&table, indexed_order_by_rows);
+    let (connection_type, edge_type) = make_connection_types(&table, inflector);
 
     let connection_type_name = connection_type.type_name().to_string();
     let condition_type_name = condition_type.type_name().to_string();
     let order_by_type_name = order_by_enum.type_name().to_string();
-    let field_name = format!("all{}", to_pascal_case(table.name()));
+    // A `@name` tag renames the collection along with the type.
+    let field_name = match table.tags().word("name") {
+        Some(name) => format!("all{}", pluralize(name)),
+        None => inflector.all_rows_field(&table),
+    };
     let tbl_schema = table.schema_name().to_string();
     let tbl_name = table.name().to_string();
+    let table_oid = *table.oid();
 
     let columns = Arc::new(table.columns().to_vec());
     let (mut name_map, mut upper_map) = (HashMap::new(), HashMap::new());
     for (i, col) in columns.iter().enumerate().filter(|(_, c)| !c.omit_read()) {
-        name_map.insert(col.name().to_string(), i);
-        upper_map.insert(col.name().to_uppercase(), i);
+        name_map.insert(col.field_name().to_string(), i);
+        upper_map.insert(col.field_name().to_uppercase(), i);
     }
+    // `@defaultSort` names database columns; translate them to the exposed
+    // `{T}OrderBy` values in case a column field was renamed.
+    let default_sort: Arc<Vec<String>> = Arc::new(
+        table
+            .default_sort()
+            .iter()
+            .filter_map(|value| {
+                let (column, direction) = value.rsplit_once('_')?;
+                let field = columns
+                    .iter()
+                    .find(|c| !c.omit_read() && c.name().to_uppercase() == column)
+                    .map(|c| c.field_name().to_uppercase());
+                if field.is_none() {
+                    eprintln!(
+                        "[turbograph] ignoring @defaultSort on {tbl_schema}.{tbl_name}: unknown column {column}"
+                    );
+                }
+                field.map(|field| format!("{field}_{direction}"))
+            })
+            .collect(),
+    );
+    // Keyless tables break `orderBy` ties by their `@stableOrder` columns,
+    // so the offsets in their cursors keep pointing at the same rows.
+    let stability = CursorStability::of(&table);
+    let tiebreak: Arc<Vec<String>> = Arc::new(
+        stability
+            .tiebreak()
+            .iter()
+            .filter_map(|name| {
+                let column = columns.iter().find(|c| c.name() == name)?;
+                Some(column.field_name().to_uppercase())
+            })
+            .collect(),
+    );
     let col_by_name = Arc::new(name_map);
     let col_by_upper = Arc::new(upper_map);
+    if table.tags().has("temporal") && table.temporal().is_none() {
+        eprintln!(
+            "[turbograph] ignoring @temporal on {tbl_schema}.{tbl_name}: it needs history_table: and a tstzrange period column"
+        );
+    }
+    let temporal = table.temporal().cloned().map(Arc::new);
+    let pagination_hint: Option<Arc<str>> = table
+        .estimated_rows()
+        .filter(|rows| large_table_rows.is_some_and(|limit| *rows > limit))
+        .map(|rows| {
+            format!(
+                "{field_name} returned only the first {DEFAULT_PAGE_SIZE} of about {rows} rows; \
+                 page through them with `first` and `after`"
+            )
+            .into()
+        });
 
-    let query_field = Field::new(
-        field_name,
+    let resolver_table = table.clone();
+    let mut query_field = Field::new(
+        field_name.clone(),
         TypeRef::named_nn(connection_type_name),
         move |ctx| {
             let condition_pairs: Option<Vec<(String, GqlValue)>> = ctx
@@ -79,6 +173,8 @@ pub fn generate_query(table: Arc<Table>, pool: Arc<Pool>) -> GeneratedQuery {
                         .collect()
                 });
 
+            // Falls back to the table's `@defaultSort`, which then also
+            // determines the cursor keys.
             let order_by: Vec<String> = ctx
                 .args
                 .get("orderBy")
@@ -86,25 +182,119 @@ pub fn generate_query(table: Arc<Table>, pool: Arc<Pool>) -> GeneratedQuery {
                 .map(|list| {
                     list.iter()
                         .filter_map(|item| item.enum_name().ok().map(|s| s.to_string()))
-                        .collect()
+                        .collect::<Vec<_>>()
                 })
-                .unwrap_or_default();
+                .filter(|order_by| !order_by.is_empty())
+                .unwrap_or_else(|| default_sort.to_vec());
+
+            let mut sql_order_by = order_by.clone();
+            for field in tiebreak.iter() {
+                let ordered = order_by
+                    .iter()
+                    .any(|o| o.rsplit_once('_').is_some_and(|(col, _)| col == field));
+                if !ordered {
+                    sql_order_by.push(format!("{field}_ASC"));
+                }
+            }
 
             let first = ctx.args.get("first").and_then(|v| v.i64().ok());
+            let last = ctx.args.get("last").and_then(|v| v.i64().ok());
             let offset = ctx.args.get("offset").and_then(|v| v.i64().ok());
+            let cursor = |name: &str| {
+                ctx.args
+                    .get(name)
+                    .and_then(|v| v.string().ok().map(str::to_string))
+            };
+            let (after, before) = (cursor("after"), cursor("before"));
+            let as_of = cursor("asOf");
 
             let pool = pool.clone();
+            let table = resolver_table.clone();
             let tbl_schema = tbl_schema.clone();
             let tbl_name = tbl_name.clone();
             let columns = columns.clone();
             let col_by_name = col_by_name.clone();
             let col_by_upper = col_by_upper.clone();
-            let tx_config = ctx.data_opt::<TransactionConfig>().cloned();
+            let temporal = temporal.clone();
+            let pagination_hint = pagination_hint.clone();
+            // A selection of only `totalCount` needs no rows, just the count.
+            let look_ahead = ctx.look_ahead();
+            let fetch_rows = ["edges", "nodes", "pageInfo"]
+                .iter()
+                .any(|field| look_ahead.field(field).exists());
+            let nodes = look_ahead.field("nodes").selection_fields();
+            let edge_nodes = look_ahead.field("edges").field("node").selection_fields();
+            let scope = RequestScope::from_ctx(&ctx);
+            let node_fields = || {
+                nodes
+                    .iter()
+                    .chain(&edge_nodes)
+                    .flat_map(|field| field.selection_set())
+            };
+            let mut select = if has_transforms(&scope, &tbl_schema, &tbl_name) {
+                "*".to_string()
+            } else {
+                sql::select_list(&columns, node_fields().map(|field| field.name()))
+            };
+            // Relations selected on the nodes come back in the same rows.
+            let prefetch = Prefetch::plan(&ctx, table_oid, node_fields());
+            select.push_str(&prefetch.select(&tbl_name));
+            let mock_data = ctx.data_opt::<Arc<MockData>>().cloned();
 
             FieldFuture::new(async move {
+                let (first, last) = match (first, last) {
+                    (None, None) => {
+                        if let (Some(hint), Some(state)) = (&pagination_hint, &scope.state) {
+                            state.warn(hint);
+                        }
+                        (Some(DEFAULT_PAGE_SIZE), None)
+                    }
+                    (first, last) => (first, last),
+                };
+                // `offset` skips further rows past the `after` cursor; the
+                // `before` cursor's row is the first one left out.
+                let after = match after {
+                    Some(cursor) => decode_cursor(&cursor, &order_by)?,
+                    None => 0,
+                };
+                let page = Page {
+                    start: after + offset.unwrap_or(0).max(0),
+                    end: match before {
+                        Some(cursor) => Some(decode_cursor(&cursor, &order_by)? - 1),
+                        None => None,
+                    },
+                    first: first.map(|n| n.clamp(1, 1000)),
+                    last: last.map(|n| n.clamp(1, 1000)),
+                };
+
+                if let Some(mock_data) = mock_data {
+                    if as_of.is_some() {
+                        return Err(gql_err("asOf is not supported in mock mode"));
+                    }
+                    return mock::connection(
+                        mock_data.rows(&tbl_schema, &tbl_name),
+                        condition_pairs,
+                        &order_by,
+                        &page,
+                        &columns,
+                        &col_by_name,
+                        &col_by_upper,
+                    );
+                }
+
                 let mut where_clause = String::new();
                 let mut params = Vec::<SqlScalar>::with_capacity(8);
 
+                // `asOf` reads the rows valid at that time, bound as `$1`.
+                let current = as_of.is_none();
+                let from = match (as_of, &temporal) {
+                    (Some(as_of), Some(temporal)) => {
+                        params.push(parse_as_of(&as_of)?);
+                        sql::as_of_source(&tbl_schema, &tbl_name, temporal, &columns, 1)
+                    }
+                    _ => quote_qualified(&tbl_schema, &tbl_name),
+                };
+
                 if let Some(pairs) = condition_pairs {
                     sql::build_where_clause(
                         &mut where_clause,
@@ -116,22 +306,27 @@ pub fn generate_query(table: Arc<Table>, pool: Arc<Pool>) -> GeneratedQuery {
                 }
 
                 let mut order_clause = String::new();
-                sql::build_order_by_clause(&mut order_clause, &order_by, &columns, &col_by_upper)?;
-
-                let safe_limit = first.unwrap_or(100).clamp(1, 1000);
-                let off = offset.unwrap_or(0).max(0);
+                sql::build_order_by_clause(
+                    &mut order_clause,
+                    &sql_order_by,
+                    &columns,
+                    &col_by_upper,
+                )?;
 
                 executor::execute_connection_query(
                     &pool,
-                    &tbl_schema,
-                    &tbl_name,
+                    &table,
+                    current,
+                    &from,
+                    &select,
                     &where_clause,
                     &order_clause,
                     params,
-                    safe_limit,
-                    off,
+                    page,
                     &order_by,
-                    tx_config,
+                    fetch_rows,
+                    prefetch,
+                    scope,
                 )
                 .await
             })
@@ -146,10 +341,21 @@ pub fn generate_query(table: Arc<Table>, pool: Arc<Pool>) -> GeneratedQuery {
         TypeRef::named_list(order_by_type_name),
     ))
     .argument(InputValue::new("first", TypeRef::named(TypeRef::INT)))
-    .argument(InputValue::new("offset", TypeRef::named(TypeRef::INT)));
+    .argument(InputValue::new("last", TypeRef::named(TypeRef::INT)))
+    .argument(InputValue::new("offset", TypeRef::named(TypeRef::INT)))
+    .argument(InputValue::new("before", TypeRef::named(TypeRef::STRING)))
+    .argument(InputValue::new("after", TypeRef::named(TypeRef::STRING)));
+    if stability == CursorStability::Offset {
+        query_field = query_field.description(OFFSET_CURSOR_WARNING);
+    }
+    if table.temporal().is_some() {
+        query_field =
+            query_field.argument(InputValue::new("asOf", TypeRef::named(scalars::DATETIME)));
+    }
 
     GeneratedQuery {
         query_field,
+        field_name,
         condition_type,
         condition_filter_types,
         order_by_enum,
@@ -157,3 +363,13 @@ pub fn generate_query(table: Arc<Table>, pool: Arc<Pool>) -> GeneratedQuery {
         edge_type,
     }
 }
+
+/// Reads an `asOf` argument, an ISO 8601 timestamp. One without an offset
+/// is taken as UTC.
+fn parse_as_of(value: &str) -> Result<SqlScalar, async_graphql::Error> {
+    DateTime::parse_from_rfc3339(value)
+        .map(|dt| dt.with_timezone(&Utc))
+        .or_else(|_| value.parse::<NaiveDateTime>().map(|dt| dt.and_utc()))
+        .map(SqlScalar::Timestamptz)
+        .map_err(|_| gql_err(format!("invalid asOf timestamp: {value}")))
+}
@@ -1,17 +1,95 @@
 use std::collections::HashMap;
-use std::fmt::Write;
 use std::sync::Arc;
 
 use async_graphql::Value as GqlValue;
 
+use crate::ir::{self, Filter, FilterOp, Order};
 use crate::models::table::Column;
+use crate::models::temporal::Temporal;
+use crate::sql::{quote_ident, quote_qualified};
 
-use super::super::filter::{FilterOp, supports_range};
+use super::super::filter::supports_op;
 use super::super::sql_scalar::SqlScalar;
 use super::super::type_mapping::to_sql_scalar;
 
 use crate::error::gql_err;
 
+/// The `SELECT` / `RETURNING` list for rows whose `selected` entity fields
+/// will be resolved: just the columns behind them, or `*` when a field needs
+/// more than its own column (relations and `nodeId` read key columns).
+pub(crate) fn select_list<'a>(
+    columns: &[Arc<Column>],
+    selected: impl IntoIterator<Item = &'a str>,
+) -> String {
+    let columns = selected_columns(columns, selected);
+    if columns.is_empty() {
+        return "*".to_string();
+    }
+    columns
+        .iter()
+        .map(|c| quote_ident(c.name()))
+        .collect::<Vec<_>>()
+        .join(", ")
+}
+
+/// The columns behind the `selected` entity fields, each once, or none when
+/// a field needs more than its own column; see [`select_list`].
+pub(crate) fn selected_columns<'a, 'c>(
+    columns: &'c [Arc<Column>],
+    selected: impl IntoIterator<Item = &'a str>,
+) -> Vec<&'c Arc<Column>> {
+    let mut selected_columns: Vec<&Arc<Column>> = Vec::new();
+    for field in selected {
+        if field == "__typename" {
+            continue;
+        }
+        let Some(col) = columns
+            .iter()
+            .find(|c| !c.omit_read() && c.field_name() == field)
+        else {
+            return Vec::new();
+        };
+        if !selected_columns.iter().any(|c| c.name() == col.name()) {
+            selected_columns.push(col);
+        }
+    }
+    selected_columns
+}
+
+/// The `FROM` source of an `asOf` read of a `@temporal` table: the current
+/// and history rows whose period contains the timestamp bound to `$param`,
+/// aliased as the table so conditions and orderings apply unchanged.
+pub(crate) fn as_of_source(
+    tbl_schema: &str,
+    tbl_name: &str,
+    temporal: &Temporal,
+    columns: &[Arc<Column>],
+    param: usize,
+) -> String {
+    let select = columns
+        .iter()
+        .map(|c| quote_ident(c.name()))
+        .collect::<Vec<_>>()
+        .join(", ");
+    let history_schema = temporal.history_schema().unwrap_or(tbl_schema);
+    let period = format!(
+        "{} @> ${param}::timestamptz",
+        quote_ident(temporal.period())
+    );
+    format!(
+        "(SELECT {select} FROM {} WHERE {period} UNION ALL SELECT {select} FROM {} WHERE {period}) AS {}",
+        quote_qualified(tbl_schema, tbl_name),
+        quote_qualified(history_schema, temporal.history_table()),
+        quote_ident(tbl_name)
+    )
+}
+
+/// Appends the ` WHERE` clause of a `{T}Condition` argument, compiled
+/// through the operation IR. GraphQL has already validated the argument, so
+/// what the IR would reject (unknown fields and operators, `null`s, values
+/// the column cannot take) is skipped instead, as are empty `in` lists.
+/// Bare values compare for equality, ignoring case on case-insensitive
+/// columns.
 pub(crate) fn build_where_clause(
     sql: &mut String,
     params: &mut Vec<SqlScalar>,
@@ -19,7 +97,19 @@ pub(crate) fn build_where_clause(
     columns: &[Arc<Column>],
     col_by_name: &HashMap<String, usize>,
 ) -> Result<(), async_graphql::Error> {
-    let mut has_where = false;
+    let filters = condition_filters(pairs, columns, col_by_name)?;
+    let conditions = ir::filter_conditions(&filters, columns, col_by_name)?;
+    sql.push_str(&ir::where_sql(conditions).build_after(params));
+    Ok(())
+}
+
+/// The IR filters of a `{T}Condition` argument, see [`build_where_clause`].
+pub(crate) fn condition_filters(
+    pairs: Vec<(String, GqlValue)>,
+    columns: &[Arc<Column>],
+    col_by_name: &HashMap<String, usize>,
+) -> Result<Vec<Filter>, async_graphql::Error> {
+    let mut filters = Vec::new();
 
     for (key, gql_val) in pairs {
         let Some(&col_idx) = col_by_name.get(&key) else {
@@ -27,116 +117,252 @@ pub(crate) fn build_where_clause(
         };
         let col = &columns[col_idx];
 
-        if !matches!(gql_val, GqlValue::Object(_)) {
-            if let Some(scalar) = to_sql_scalar(col, &gql_val) {
-                write_where_sep(sql, &mut has_where);
-                write!(sql, "\"{}\" = ${}", col.name(), params.len() + 1).unwrap();
-                params.push(scalar);
+        let GqlValue::Object(op_obj) = gql_val else {
+            if to_sql_scalar(col, &gql_val).is_some() {
+                filters.push(Filter {
+                    ignore_case: col.case_insensitive(),
+                    ..Filter::new(key, FilterOp::Eq, to_json(gql_val)?)
+                });
             }
             continue;
-        }
+        };
 
-        if let GqlValue::Object(op_obj) = gql_val {
-            for (op_key, op_val) in op_obj {
-                let Some(op) = FilterOp::from_key(op_key.as_str()) else {
-                    continue;
-                };
+        for (op_key, op_val) in op_obj {
+            let Some(op) = FilterOp::from_key(op_key.as_str()) else {
+                continue;
+            };
 
-                if op == FilterOp::In {
-                    push_in_clause(sql, params, col, op_val, &mut has_where)?;
-                    continue;
+            if op == FilterOp::In {
+                if let GqlValue::List(values) = op_val {
+                    if values.len() > 10_000 {
+                        return Err(gql_err("IN filter exceeds maximum of 10,000 items"));
+                    }
+                    let values = values
+                        .into_iter()
+                        .filter(|val| to_sql_scalar(col, val).is_some())
+                        .map(to_json)
+                        .collect::<Result<Vec<_>, _>>()?;
+                    if !values.is_empty() {
+                        filters.push(Filter::new(key.clone(), op, values.into()));
+                    }
                 }
+                continue;
+            }
 
-                if op.is_range() && !supports_range(col._type()) {
-                    continue;
-                }
+            if !supports_op(op, col) {
+                continue;
+            }
 
-                if let Some(scalar) = to_sql_scalar(col, &op_val) {
-                    write_where_sep(sql, &mut has_where);
-                    write!(
-                        sql,
-                        "\"{}\" {} ${}",
-                        col.name(),
-                        op.sql_operator(),
-                        params.len() + 1
-                    )
-                    .unwrap();
-                    params.push(scalar);
-                }
+            if to_sql_scalar(col, &op_val).is_some() {
+                filters.push(Filter::new(key.clone(), op, to_json(op_val)?));
             }
         }
     }
-    Ok(())
+    Ok(filters)
 }
 
-fn push_in_clause(
-    sql: &mut String,
-    params: &mut Vec<SqlScalar>,
-    col: &Column,
-    op_val: GqlValue,
-    has_where: &mut bool,
-) -> Result<(), async_graphql::Error> {
-    if let GqlValue::List(values) = op_val {
-        if values.len() > 10_000 {
-            return Err(gql_err("IN filter exceeds maximum of 10,000 items"));
-        }
-        let scalars: Vec<SqlScalar> = values
-            .into_iter()
-            .filter_map(|val| to_sql_scalar(col, &val))
-            .collect();
-
-        if !scalars.is_empty() {
-            write_where_sep(sql, has_where);
-            let start = params.len() + 1;
-            write!(sql, "\"{}\" IN (", col.name()).unwrap();
-            for (i, scalar) in scalars.into_iter().enumerate() {
-                if i > 0 {
-                    sql.push_str(", ");
-                }
-                write!(sql, "${}", start + i).unwrap();
-                params.push(scalar);
-            }
-            sql.push(')');
-        }
-    }
-    Ok(())
+fn to_json(value: GqlValue) -> Result<serde_json::Value, async_graphql::Error> {
+    value.into_json().map_err(|e| gql_err(e.to_string()))
 }
 
+/// Appends the ` ORDER BY` clause of `{T}OrderBy` values such as
+/// `CREATED_AT_DESC`, compiled through the operation IR. Values without a
+/// direction suffix are skipped; unknown columns are rejected.
 pub(super) fn build_order_by_clause(
     sql: &mut String,
     order_by: &[String],
     columns: &[Arc<Column>],
     col_by_upper: &HashMap<String, usize>,
 ) -> Result<(), async_graphql::Error> {
-    if order_by.is_empty() {
-        return Ok(());
-    }
-    sql.push_str(" ORDER BY ");
-    for (i, s) in order_by.iter().enumerate() {
-        let (col_upper, dir) = if let Some(c) = s.strip_suffix("_DESC") {
-            (c, "DESC")
-        } else if let Some(c) = s.strip_suffix("_ASC") {
-            (c, "ASC")
+    let mut orders = Vec::with_capacity(order_by.len());
+    for value in order_by {
+        let (col_upper, descending) = if let Some(c) = value.strip_suffix("_DESC") {
+            (c, true)
+        } else if let Some(c) = value.strip_suffix("_ASC") {
+            (c, false)
         } else {
             continue;
         };
         let Some(&col_idx) = col_by_upper.get(col_upper) else {
             return Err(gql_err(format!("unknown column for ordering: {col_upper}")));
         };
-        if i > 0 {
-            sql.push_str(", ");
-        }
-        write!(sql, "\"{}\" {}", columns[col_idx].name(), dir).unwrap();
+        orders.push(Order {
+            field: columns[col_idx].field_name().to_string(),
+            descending,
+        });
     }
+    sql.push_str(&ir::order_clause(&orders, columns)?.build().0);
     Ok(())
 }
 
-#[inline]
-fn write_where_sep(sql: &mut String, has_where: &mut bool) {
-    if *has_where {
-        sql.push_str(" AND ");
-    } else {
-        sql.push_str(" WHERE ");
-        *has_where = true;
+#[cfg(test)]
+mod tests {
+    use async_graphql::indexmap::IndexMap;
+    use async_graphql::{Name, Number};
+    use tokio_postgres::types::Type;
+
+    use crate::sql::audit;
+
+    use super::*;
+
+    /// Characters that break out of naive quoting or comment out the rest of
+    /// a statement.
+    const HOSTILE: &[&str] = &[
+        "\"", "'", ";", "--", "/*", "*/", "$$", "$1", "1", " ", "\\", "é", "a", "_", "OR 1=1",
+    ];
+    const OPS: &[&str] = &[
+        "equal",
+        "notEqual",
+        "in",
+        "greaterThan",
+        "greaterThanEqual",
+        "lessThan",
+        "lessThanEqual",
+        "unknown",
+    ];
+
+    /// xorshift64: deterministic, so a failing case reproduces.
+    struct Rng(u64);
+
+    impl Rng {
+        fn next(&mut self) -> u64 {
+            self.0 ^= self.0 << 13;
+            self.0 ^= self.0 >> 7;
+            self.0 ^= self.0 << 17;
+            self.0
+        }
+
+        fn below(&mut self, n: usize) -> usize {
+            (self.next() % n as u64) as usize
+        }
+
+        fn hostile(&mut self) -> String {
+            (0..1 + self.below(6))
+                .map(|_| HOSTILE[self.below(HOSTILE.len())])
+                .collect()
+        }
+
+        fn value(&mut self, depth: u8) -> GqlValue {
+            match self.below(if depth == 0 { 4 } else { 3 }) {
+                0 => GqlValue::String(self.hostile()),
+                1 => GqlValue::Number(Number::from(self.next() as i32)),
+                2 => GqlValue::Null,
+                _ => {
+                    let mut ops = IndexMap::new();
+                    for _ in 0..1 + self.below(3) {
+                        let op = OPS[self.below(OPS.len())];
+                        let val = if op == "in" {
+                            GqlValue::List((0..self.below(4)).map(|_| self.value(1)).collect())
+                        } else {
+                            self.value(1)
+                        };
+                        ops.insert(Name::new(op), val);
+                    }
+                    GqlValue::Object(ops)
+                }
+            }
+        }
+    }
+
+    /// Placeholders must be numbered `$1..$n` in order, one per parameter.
+    fn placeholders(sql: &str) -> Vec<usize> {
+        let mut found = Vec::new();
+        let mut in_ident = false;
+        for (i, c) in sql.char_indices() {
+            match c {
+                '"' => in_ident = !in_ident,
+                '$' if !in_ident => {
+                    let digits: String = sql[i + 1..]
+                        .chars()
+                        .take_while(char::is_ascii_digit)
+                        .collect();
+                    found.push(digits.parse().unwrap());
+                }
+                _ => {}
+            }
+        }
+        found
+    }
+
+    #[test]
+    fn test_filter_and_order_by_compilers_only_emit_quoted_identifiers_and_params() {
+        let mut rng = Rng(0x5eed_cafe_f00d_d00d);
+
+        for _ in 0..2_000 {
+            let columns: Vec<Arc<Column>> = (0..1 + rng.below(4))
+                .map(|_| {
+                    let ty = if rng.below(2) == 0 {
+                        Type::TEXT
+                    } else {
+                        Type::INT4
+                    };
+                    Arc::new(Column::new_for_test(&rng.hostile(), ty, true, false))
+                })
+                .collect();
+            let col_by_name: HashMap<String, usize> = columns
+                .iter()
+                .enumerate()
+                .map(|(i, c)| (c.name().to_string(), i))
+                .collect();
+            let col_by_upper: HashMap<String, usize> = columns
+                .iter()
+                .enumerate()
+                .map(|(i, c)| (c.name().to_uppercase(), i))
+                .collect();
+
+            let pairs: Vec<(String, GqlValue)> = (0..rng.below(5))
+                .map(|_| {
+                    let key = if rng.below(4) == 0 {
+                        rng.hostile()
+                    } else {
+                        columns[rng.below(columns.len())].name().to_string()
+                    };
+                    (key, rng.value(0))
+                })
+                .collect();
+            let order_by: Vec<String> = (0..rng.below(4))
+                .map(|_| {
+                    let column = if rng.below(4) == 0 {
+                        rng.hostile()
+                    } else {
+                        columns[rng.below(columns.len())].name().to_uppercase()
+                    };
+                    let suffix = ["_ASC", "_DESC", ""][rng.below(3)];
+                    format!("{column}{suffix}")
+                })
+                .collect();
+
+            let selected: Vec<String> = (0..rng.below(4))
+                .map(|_| columns[rng.below(columns.len())].name().to_string())
+                .collect();
+            let select = select_list(&columns, selected.iter().map(String::as_str));
+
+            let mut sql = format!("SELECT {select} FROM \"s\".\"t\"");
+            let mut params = Vec::new();
+            build_where_clause(&mut sql, &mut params, pairs.clone(), &columns, &col_by_name)
+                .unwrap();
+            // Unknown orderings are rejected rather than interpolated.
+            let _ = build_order_by_clause(&mut sql, &order_by, &columns, &col_by_upper);
+
+            assert_eq!(audit(&sql), Ok(()), "{sql}\n{pairs:?}\n{order_by:?}");
+            let expected: Vec<usize> = (1..=params.len()).collect();
+            assert_eq!(placeholders(&sql), expected, "{sql}");
+        }
+    }
+
+    #[test]
+    fn test_select_list_prunes_to_selected_columns() {
+        let columns: Vec<Arc<Column>> = [("id", false), ("title", false), ("secret", true)]
+            .into_iter()
+            .map(|(name, omit)| Arc::new(Column::new_for_test(name, Type::TEXT, true, omit)))
+            .collect();
+
+        assert_eq!(
+            select_list(&columns, ["title", "__typename", "id", "title"]),
+            "\"title\", \"id\""
+        );
+        assert_eq!(select_list(&columns, ["id", "author"]), "*");
+        assert_eq!(select_list(&columns, ["secret"]), "*");
+        assert_eq!(select_list(&columns, ["__typename"]), "*");
     }
 }
@@ -0,0 +1,78 @@
+//! `refresh{T}` mutations for materialized views tagged `@refreshable`,
+//! running `REFRESH MATERIALIZED VIEW CONCURRENTLY` so dashboards can bring
+//! a view up to date without blocking its readers. Only added when
+//! [`Config::refresh_roles`](crate::Config::refresh_roles) is set and only
+//! answered for requests running as one of those roles.
+
+use std::sync::Arc;
+
+use async_graphql::dynamic::{Field, FieldFuture, FieldValue, TypeRef};
+use deadpool_postgres::Pool;
+
+use crate::db::request::RequestScope;
+use crate::db::transaction::with_transaction;
+use crate::error::{db_err, gql_err};
+use crate::models::mock::MockData;
+use crate::models::table::Table;
+use crate::sql::quote_qualified;
+
+use super::admin::ensure_role;
+
+/// The `refresh{T}` mutation field of `table`, with its name, answering
+/// `true` once the view has been refreshed. `CONCURRENTLY` needs a unique
+/// index on the view; without one Postgres rejects the refresh.
+pub(crate) fn generate_refresh(
+    table: Arc<Table>,
+    pool: Arc<Pool>,
+    refresh_roles: Arc<Vec<String>>,
+) -> (String, Field) {
+    let field_name = format!("refresh{}", table.type_name());
+    let description = format!(
+        "Refreshes the {} materialized view. Requires a refresh role.",
+        table.type_name()
+    );
+    let name = field_name.clone();
+    let field = Field::new(
+        &field_name,
+        TypeRef::named_nn(TypeRef::BOOLEAN),
+        move |ctx| {
+            let table = table.clone();
+            let pool = pool.clone();
+            let allowed = ensure_role(&ctx, &refresh_roles, || {
+                format!("{name} requires a refresh role")
+            });
+            let scope = RequestScope::from_ctx(&ctx);
+            let mock_data = ctx.data_opt::<Arc<MockData>>().is_some();
+
+            FieldFuture::new(async move {
+                allowed?;
+                if mock_data {
+                    return Err(gql_err(
+                        "Materialized views cannot be refreshed in mock mode",
+                    ));
+                }
+                let sql = format!(
+                    "REFRESH MATERIALIZED VIEW CONCURRENTLY {}",
+                    quote_qualified(table.schema_name(), table.name())
+                );
+                scope.log_statement(&sql);
+
+                let refreshed = with_transaction(&pool, scope.clone(), |client| {
+                    let sql = sql.clone();
+                    Box::pin(async move {
+                        client
+                            .batch_execute(&sql)
+                            .await
+                            .map_err(|e| db_err("REFRESH error", e))?;
+                        Ok(Some(FieldValue::value(true)))
+                    })
+                })
+                .await?;
+                scope.forget_rows();
+                Ok(refreshed)
+            })
+        },
+    )
+    .description(description);
+    (field_name, field)
+}
@@ -0,0 +1,412 @@
+//! Relation fields that navigate from a row to related rows: the row a
+//! foreign key references, rows referencing it through a foreign key or a
+//! `@belongsTo` relation, and rows reached through `@ref` tags.
+
+use std::sync::Arc;
+
+use async_graphql::Value as GqlValue;
+use async_graphql::dynamic::{Field, FieldFuture, FieldValue, TypeRef};
+use deadpool_postgres::Pool;
+use tokio_postgres::types::ToSql;
+
+use crate::db::JsonListExt;
+use crate::db::request::RequestScope;
+use crate::db::transaction::with_transaction;
+use crate::error::{db_err, gql_err};
+use crate::models::foreign_key::ForeignKey;
+use crate::models::mock::MockData;
+use crate::models::reference::{Reference, ResolvedHop};
+use crate::models::row_transform::transform_rows;
+use crate::models::table::Table;
+use crate::sql::{quote_ident, quote_qualified};
+
+use super::mock;
+use super::prefetch::{Prefetch, prefetched};
+use super::sql_scalar::SqlScalar;
+use super::type_mapping::to_sql_scalar;
+
+/// The field on the referencing table's type that resolves the row of
+/// `foreign_table` its `fk` columns point at, e.g. `userByAuthorId` on
+/// `Post`. `null` when a referencing column is `NULL` or the row is not
+/// visible. Uses the row fetched along with the parent when there is one,
+/// see [`prefetch`](super::prefetch).
+pub(crate) fn generate_forward_relation(
+    fk: Arc<ForeignKey>,
+    foreign_table: Arc<Table>,
+    pool: Arc<Pool>,
+) -> Field {
+    let type_ref = TypeRef::named(foreign_table.type_name());
+
+    Field::new(fk.field_name(&foreign_table), type_ref, move |ctx| {
+        let fk = fk.clone();
+        let table = foreign_table.clone();
+        let pool = pool.clone();
+        let scope = RequestScope::from_ctx(&ctx);
+        let mock_data = ctx.data_opt::<Arc<MockData>>().cloned();
+
+        FieldFuture::new(async move {
+            let parent = ctx.parent_value.try_downcast_ref::<serde_json::Value>()?;
+            if let Some(rows) = prefetched(parent, ctx.field().name()) {
+                let rows = scope.identify(&table, rows);
+                let rows = transform_rows(&scope, table.schema_name(), table.name(), rows).await?;
+                return Ok(rows.into_iter().next().map(FieldValue::owned_any));
+            }
+            let key: Option<Vec<serde_json::Value>> = fk
+                .columns()
+                .iter()
+                .map(|column| parent.get(column).filter(|v| !v.is_null()).cloned())
+                .collect();
+
+            let rows = match (key, mock_data) {
+                (None, _) => Vec::new(),
+                (Some(key), Some(mock_data)) => mock::filter_by_key(
+                    mock_data.rows(table.schema_name(), table.name()),
+                    fk.foreign_columns(),
+                    &key,
+                ),
+                (Some(key), None) => {
+                    let prefetch = Prefetch::for_field(&ctx, &table);
+                    fetch_related(&pool, &table, fk.foreign_columns(), key, &prefetch, scope)
+                        .await?
+                }
+            };
+            Ok(rows.into_iter().next().map(FieldValue::owned_any))
+        })
+    })
+}
+
+/// The field on the referenced table's type that lists the rows of `table`
+/// pointing at it through `fk`, e.g. `userStatsByUserId` on `User`. When the
+/// referencing columns are `table`'s whole primary key at most one row can
+/// match, so the field is a nullable object instead of a list.
+pub(crate) fn generate_backward_relation(
+    fk: Arc<ForeignKey>,
+    table: Arc<Table>,
+    pool: Arc<Pool>,
+) -> Field {
+    let mut key_columns = fk.columns().to_vec();
+    let mut primary_key = table.primary_key().to_vec();
+    key_columns.sort();
+    primary_key.sort();
+    let unique = !primary_key.is_empty() && key_columns == primary_key;
+
+    let type_ref = if unique {
+        TypeRef::named(table.type_name())
+    } else {
+        TypeRef::named_nn_list_nn(table.type_name())
+    };
+
+    Field::new(fk.foreign_field_name(&table), type_ref, move |ctx| {
+        let fk = fk.clone();
+        let table = table.clone();
+        let pool = pool.clone();
+        let scope = RequestScope::from_ctx(&ctx);
+        let mock_data = ctx.data_opt::<Arc<MockData>>().cloned();
+
+        FieldFuture::new(async move {
+            let parent = ctx.parent_value.try_downcast_ref::<serde_json::Value>()?;
+            let key: Option<Vec<serde_json::Value>> = fk
+                .foreign_columns()
+                .iter()
+                .map(|column| parent.get(column).filter(|v| !v.is_null()).cloned())
+                .collect();
+
+            let rows = match (prefetched(parent, ctx.field().name()), key, mock_data) {
+                (Some(rows), ..) => {
+                    let rows = scope.identify(&table, rows);
+                    transform_rows(&scope, table.schema_name(), table.name(), rows).await?
+                }
+                (None, None, _) => Vec::new(),
+                (None, Some(key), Some(mock_data)) => mock::filter_by_key(
+                    mock_data.rows(table.schema_name(), table.name()),
+                    fk.columns(),
+                    &key,
+                ),
+                (None, Some(key), None) => {
+                    let prefetch = Prefetch::for_field(&ctx, &table);
+                    fetch_related(&pool, &table, fk.columns(), key, &prefetch, scope).await?
+                }
+            };
+
+            let mut rows = rows.into_iter().map(FieldValue::owned_any);
+            if unique {
+                Ok(rows.next())
+            } else {
+                Ok(Some(FieldValue::list(rows.collect::<Vec<_>>())))
+            }
+        })
+    })
+}
+
+/// `SELECT * ... WHERE <columns> = <key>`, in primary key order when `table`
+/// has one. When `columns` are the primary key and no relations are
+/// prefetched, a row the request already fetched in full is taken from its
+/// identity map instead.
+async fn fetch_related(
+    pool: &Pool,
+    table: &Table,
+    columns: &[String],
+    key: Vec<serde_json::Value>,
+    prefetch: &Prefetch,
+    scope: RequestScope,
+) -> Result<Vec<serde_json::Value>, async_graphql::Error> {
+    if columns == table.primary_key()
+        && prefetch.is_empty()
+        && let Some(row) = scope.identified(table, &key)
+    {
+        return transform_rows(&scope, table.schema_name(), table.name(), vec![row]).await;
+    }
+    let mut params = Vec::<SqlScalar>::with_capacity(key.len());
+
+    for (column_name, value) in columns.iter().zip(key) {
+        let Some(column) = table.columns().iter().find(|c| c.name() == column_name) else {
+            return Ok(Vec::new());
+        };
+        let value = GqlValue::from_json(value).map_err(|e| gql_err(e.to_string()))?;
+        let Some(scalar) = to_sql_scalar(column, &value) else {
+            return Err(gql_err(format!(
+                "Unsupported relation key type for {}",
+                column.field_name()
+            )));
+        };
+        params.push(scalar);
+    }
+
+    fetch_by_key(pool, table, columns, params, prefetch, scope).await
+}
+
+/// `SELECT * ... WHERE <columns> = <params>`, in primary key order when
+/// `table` has one, through `table`'s row transforms, with the relations of
+/// `prefetch`. Memoized per request, and the rows join its identity map.
+pub(super) async fn fetch_by_key(
+    pool: &Pool,
+    table: &Table,
+    columns: &[String],
+    params: Vec<SqlScalar>,
+    prefetch: &Prefetch,
+    scope: RequestScope,
+) -> Result<Vec<serde_json::Value>, async_graphql::Error> {
+    let conditions: Vec<String> = columns
+        .iter()
+        .enumerate()
+        .map(|(i, column_name)| format!("{} = ${}", quote_ident(column_name), i + 1))
+        .collect();
+
+    let mut sql = format!(
+        "SELECT *{} FROM {} WHERE {}",
+        prefetch.select(table.name()),
+        quote_qualified(table.schema_name(), table.name()),
+        conditions.join(" AND ")
+    );
+    if !table.primary_key().is_empty() {
+        let order: Vec<String> = table.primary_key().iter().map(|c| quote_ident(c)).collect();
+        sql.push_str(&format!(" ORDER BY {}", order.join(", ")));
+    }
+    let cache_key = format!("{sql}\n{params:?}");
+    let fetch_scope = scope.clone();
+
+    let rows = scope
+        .memoize(cache_key, || async move {
+            let scalars = fetch_scope.custom_scalars.clone();
+            fetch_scope.log_statement(&sql);
+            let rows = with_transaction(pool, fetch_scope, |client| {
+                let params = params.clone();
+                let sql = sql.clone();
+                Box::pin(async move {
+                    let refs: Vec<&(dyn ToSql + Sync)> =
+                        params.iter().map(|p| p as &(dyn ToSql + Sync)).collect();
+                    client
+                        .query(&sql, &refs)
+                        .await
+                        .map_err(|e| db_err("DB query error", e))
+                })
+            })
+            .await?;
+            Ok(prefetch.rows_to_json(&rows, scalars.as_ref()))
+        })
+        .await?;
+    let rows = scope.identify(table, rows);
+    transform_rows(&scope, table.schema_name(), table.name(), rows).await
+}
+
+/// The field for a `@ref` tag, following each of its paths from the row
+/// being resolved and combining the rows they reach.
+pub(crate) fn generate_reference(reference: Arc<Reference>, pool: Arc<Pool>) -> Field {
+    let type_name = reference.target().type_name();
+    let type_ref = if reference.plural {
+        TypeRef::named_nn_list_nn(type_name)
+    } else {
+        TypeRef::named(type_name)
+    };
+
+    Field::new(reference.name.clone(), type_ref, move |ctx| {
+        let reference = reference.clone();
+        let pool = pool.clone();
+        let scope = RequestScope::from_ctx(&ctx);
+        let mock_data = ctx.data_opt::<Arc<MockData>>().cloned();
+
+        FieldFuture::new(async move {
+            let parent = ctx.parent_value.try_downcast_ref::<serde_json::Value>()?;
+            let mut rows = Vec::new();
+            for path in &reference.paths {
+                let key: Option<Vec<serde_json::Value>> = path[0]
+                    .columns
+                    .iter()
+                    .map(|column| parent.get(column).filter(|v| !v.is_null()).cloned())
+                    .collect();
+                let Some(key) = key else {
+                    continue;
+                };
+                match &mock_data {
+                    Some(mock_data) => rows.extend(follow_mock_path(mock_data, path, key)),
+                    None => rows.extend(fetch_path(&pool, path, key, scope.clone()).await?),
+                }
+                if !reference.plural && !rows.is_empty() {
+                    break;
+                }
+            }
+
+            let mut rows = rows.into_iter().map(FieldValue::owned_any);
+            if reference.plural {
+                Ok(Some(FieldValue::list(rows.collect::<Vec<_>>())))
+            } else {
+                Ok(rows.next())
+            }
+        })
+    })
+}
+
+/// Joins the hops of `path` into one statement and returns the rows of its
+/// last table whose first hop matches `key`, through that table's row
+/// transforms.
+async fn fetch_path(
+    pool: &Pool,
+    path: &[ResolvedHop],
+    key: Vec<serde_json::Value>,
+    scope: RequestScope,
+) -> Result<Vec<serde_json::Value>, async_graphql::Error> {
+    let first = &path[0];
+    let mut conditions = Vec::with_capacity(key.len());
+    let mut params = Vec::<SqlScalar>::with_capacity(key.len());
+
+    for (column_name, value) in first.foreign_columns.iter().zip(key) {
+        let Some(column) = first
+            .table
+            .columns()
+            .iter()
+            .find(|c| c.name() == column_name)
+        else {
+            return Ok(Vec::new());
+        };
+        let value = GqlValue::from_json(value).map_err(|e| gql_err(e.to_string()))?;
+        let Some(scalar) = to_sql_scalar(column, &value) else {
+            return Err(gql_err(format!(
+                "Unsupported reference key type for {}",
+                column.field_name()
+            )));
+        };
+        params.push(scalar);
+        conditions.push(format!(
+            "t1.{} = ${}",
+            quote_ident(column_name),
+            params.len()
+        ));
+    }
+
+    let mut from = format!(
+        "{} t1",
+        quote_qualified(first.table.schema_name(), first.table.name())
+    );
+    for (i, hop) in path.iter().enumerate().skip(1) {
+        let on: Vec<String> = hop
+            .foreign_columns
+            .iter()
+            .zip(&hop.columns)
+            .map(|(to, from)| {
+                format!(
+                    "t{}.{} = t{i}.{}",
+                    i + 1,
+                    quote_ident(to),
+                    quote_ident(from)
+                )
+            })
+            .collect();
+        from.push_str(&format!(
+            " JOIN {} t{} ON {}",
+            quote_qualified(hop.table.schema_name(), hop.table.name()),
+            i + 1,
+            on.join(" AND ")
+        ));
+    }
+
+    let last = path.len();
+    let target = &path[last - 1].table;
+    let mut sql = format!(
+        "SELECT t{last}.* FROM {from} WHERE {}",
+        conditions.join(" AND ")
+    );
+    if !target.primary_key().is_empty() {
+        let order: Vec<String> = target
+            .primary_key()
+            .iter()
+            .map(|c| format!("t{last}.{}", quote_ident(c)))
+            .collect();
+        sql.push_str(&format!(" ORDER BY {}", order.join(", ")));
+    }
+    let cache_key = format!("{sql}\n{params:?}");
+    let fetch_scope = scope.clone();
+
+    let rows = scope
+        .memoize(cache_key, || async move {
+            let scalars = fetch_scope.custom_scalars.clone();
+            fetch_scope.log_statement(&sql);
+            with_transaction(pool, fetch_scope, |client| {
+                let params = params.clone();
+                let sql = sql.clone();
+                let scalars = scalars.clone();
+                Box::pin(async move {
+                    let refs: Vec<&(dyn ToSql + Sync)> =
+                        params.iter().map(|p| p as &(dyn ToSql + Sync)).collect();
+                    let rows = client
+                        .query(&sql, &refs)
+                        .await
+                        .map_err(|e| db_err("DB query error", e))?;
+                    Ok(rows.to_json_list(scalars.as_ref()))
+                })
+            })
+            .await
+        })
+        .await?;
+    let rows = scope.identify(target, rows);
+    transform_rows(&scope, target.schema_name(), target.name(), rows).await
+}
+
+/// Mock counterpart of [`fetch_path`]: follows the hops through the fixture
+/// rows one table at a time.
+fn follow_mock_path(
+    mock_data: &MockData,
+    path: &[ResolvedHop],
+    key: Vec<serde_json::Value>,
+) -> Vec<serde_json::Value> {
+    let mut keys = vec![key];
+    let mut rows = Vec::new();
+    for (i, hop) in path.iter().enumerate() {
+        let table_rows = mock_data.rows(hop.table.schema_name(), hop.table.name());
+        rows = keys
+            .iter()
+            .flat_map(|key| mock::filter_by_key(table_rows, &hop.foreign_columns, key))
+            .collect();
+        if let Some(next) = path.get(i + 1) {
+            keys = rows
+                .iter()
+                .map(|row| {
+                    next.columns
+                        .iter()
+                        .map(|c| row.get(c).cloned().unwrap_or_default())
+                        .collect()
+                })
+                .collect();
+        }
+    }
+    rows
+}
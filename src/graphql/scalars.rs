@@ -0,0 +1,145 @@
+//! Custom scalars for Postgres types that no built-in GraphQL scalar holds
+//! without loss. Each travels as a string; inputs are checked by the
+//! scalar's validator before they reach a resolver. Types without any
+//! mapping, such as those of extensions, can be given one with
+//! [`Config::custom_scalars`](crate::Config::custom_scalars).
+
+use std::collections::HashMap;
+use std::sync::Arc;
+
+use async_graphql::Value as GqlValue;
+use async_graphql::dynamic::Scalar;
+use chrono::{DateTime, NaiveDate, NaiveDateTime};
+use serde::Serialize;
+use tokio_postgres::types::{FromSql, Type};
+
+use crate::db::codec::{Numeric, parse_uuid};
+
+/// `bigint`, beyond the 32 bits of `Int`.
+pub(crate) const BIG_INT: &str = "BigInt";
+/// `numeric`, as decimal text.
+pub(crate) const BIG_FLOAT: &str = "BigFloat";
+pub(crate) const UUID: &str = "UUID";
+pub(crate) const DATE: &str = "Date";
+/// `timestamp` and `timestamptz`.
+pub(crate) const DATETIME: &str = "Datetime";
+
+/// Decodes a binary value into the JSON a custom scalar answers.
+type Decode = fn(&Type, &[u8]) -> Option<serde_json::Value>;
+
+/// The GraphQL scalar for a Postgres type listed in
+/// [`Config::custom_scalars`](crate::Config::custom_scalars), and the
+/// [`FromSql`] type its values decode with.
+#[derive(Clone)]
+pub struct CustomScalar {
+    name: String,
+    description: Option<String>,
+    decode: Decode,
+}
+
+impl CustomScalar {
+    /// A scalar named `name` whose values are decoded as `T` and answered
+    /// as `T` serializes to JSON.
+    pub fn new<T>(name: impl Into<String>) -> Self
+    where
+        T: for<'a> FromSql<'a> + Serialize,
+    {
+        Self {
+            name: name.into(),
+            description: None,
+            decode: decode_as::<T>,
+        }
+    }
+
+    pub fn description(mut self, description: impl Into<String>) -> Self {
+        self.description = Some(description.into());
+        self
+    }
+}
+
+fn decode_as<T>(ty: &Type, raw: &[u8]) -> Option<serde_json::Value>
+where
+    T: for<'a> FromSql<'a> + Serialize,
+{
+    serde_json::to_value(T::from_sql(ty, raw).ok()?).ok()
+}
+
+impl std::fmt::Debug for CustomScalar {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("CustomScalar")
+            .field("name", &self.name)
+            .finish_non_exhaustive()
+    }
+}
+
+/// The configured custom scalars by type OID, handed to resolvers as schema
+/// data so rows decode with them.
+#[derive(Clone, Default)]
+pub(crate) struct CustomScalars(Arc<HashMap<u32, CustomScalar>>);
+
+impl CustomScalars {
+    /// `None` when nothing is configured.
+    pub(crate) fn new(scalars: &[(u32, CustomScalar)]) -> Option<Self> {
+        (!scalars.is_empty()).then(|| Self(Arc::new(scalars.iter().cloned().collect())))
+    }
+
+    /// The GraphQL name of the scalar configured for `ty`.
+    pub(crate) fn name(&self, ty: &Type) -> Option<&str> {
+        self.0.get(&ty.oid()).map(|scalar| scalar.name.as_str())
+    }
+
+    /// Decodes `raw` with the scalar configured for `ty`: `None` when there
+    /// is none, `Some(None)` when the value does not decode.
+    pub(crate) fn decode(&self, ty: &Type, raw: &[u8]) -> Option<Option<serde_json::Value>> {
+        let scalar = self.0.get(&ty.oid())?;
+        Some((scalar.decode)(ty, raw))
+    }
+}
+
+/// Every custom scalar, registered with each schema: the built-in ones and
+/// those of [`Config::custom_scalars`](crate::Config::custom_scalars).
+pub(crate) fn make_scalars(custom: Option<&CustomScalars>) -> Vec<Scalar> {
+    let mut custom: Vec<&CustomScalar> = custom.map_or_else(Vec::new, |c| c.0.values().collect());
+    // The map's order varies between runs; the SDL should not.
+    custom.sort_by(|a, b| a.name.cmp(&b.name));
+    custom.dedup_by(|a, b| a.name == b.name);
+    let custom = custom.into_iter().map(|scalar| {
+        let gql = Scalar::new(&scalar.name);
+        match &scalar.description {
+            Some(description) => gql.description(description),
+            None => gql,
+        }
+    });
+    let mut scalars = vec![
+        Scalar::new(BIG_INT)
+            .description("A signed 64-bit integer, as a string since it may not fit in an `Int`.")
+            .validator(|value| match value {
+                GqlValue::Number(n) => n.is_i64(),
+                GqlValue::String(s) => s.parse::<i64>().is_ok(),
+                _ => false,
+            }),
+        Scalar::new(BIG_FLOAT)
+            .description("An arbitrary-precision decimal number, as a string so no digit is lost.")
+            .validator(|value| match value {
+                GqlValue::Number(_) => true,
+                GqlValue::String(s) => Numeric::parse(s).is_some(),
+                _ => false,
+            }),
+        Scalar::new(UUID)
+            .description("A universally unique identifier, e.g. `a0eebc99-9c0b-4ef8-bb6d-6bb9bd380a11`.")
+            .validator(|value| matches!(value, GqlValue::String(s) if parse_uuid(s).is_some())),
+        Scalar::new(DATE)
+            .description("A calendar date in ISO 8601 form, e.g. `2024-01-31`.")
+            .validator(|value| {
+                matches!(value, GqlValue::String(s) if s.parse::<NaiveDate>().is_ok())
+            }),
+        Scalar::new(DATETIME)
+            .description("A point in time in ISO 8601 form, e.g. `2024-01-31T12:00:00+00:00`.")
+            .validator(|value| {
+                matches!(value, GqlValue::String(s)
+                    if DateTime::parse_from_rfc3339(s).is_ok() || s.parse::<NaiveDateTime>().is_ok())
+            }),
+    ];
+    scalars.extend(custom);
+    scalars
+}
@@ -1,25 +1,103 @@
-use bytes::BytesMut;
+use base64::Engine;
+use bytes::{BufMut, BytesMut};
 use chrono::{DateTime, NaiveDate, NaiveDateTime, NaiveTime, Utc};
-use tokio_postgres::types::{IsNull, ToSql, Type};
+use tokio_postgres::types::{IsNull, Kind, ToSql, Type};
+
+use crate::db::codec::{Numeric, format_uuid};
 
 /// Typed SQL parameter wrapper.
 /// Lets callers build a `Vec<SqlScalar>` and borrow as
 /// `&[&(dyn ToSql + Sync)]` for `tokio_postgres::Client::query`.
-#[derive(Debug)]
-pub(crate) enum SqlScalar {
+#[derive(Clone, Debug)]
+pub enum SqlScalar {
     Bool(bool),
     Int2(i16),
     Int4(i32),
     Int8(i64),
     Float4(f32),
     Float8(f64),
-    Numeric(f64),
+    /// Decimal text, already checked by [`Numeric::parse`].
+    Numeric(String),
     Text(String),
     Json(serde_json::Value),
     Date(NaiveDate),
     Time(NaiveTime),
     Timestamp(NaiveDateTime),
     Timestamptz(DateTime<Utc>),
+    /// The contents of an upload to a `bytea` column.
+    Bytea(Vec<u8>),
+    /// A large object, as written from an upload.
+    Oid(u32),
+    Uuid([u8; 16]),
+    /// A one-dimensional array; `None` elements are `NULL`.
+    Array(Vec<Option<SqlScalar>>),
+    /// A composite value: its attributes, in declaration order.
+    Record(Vec<(String, Option<SqlScalar>)>),
+}
+
+impl SqlScalar {
+    /// The value as it appears in a row serialized by Postgres, e.g. dates as
+    /// ISO 8601 strings. Used by mock execution to compare against fixtures.
+    pub(crate) fn to_json(&self) -> serde_json::Value {
+        match self {
+            SqlScalar::Bool(v) => (*v).into(),
+            SqlScalar::Int2(v) => (*v).into(),
+            SqlScalar::Int4(v) => (*v).into(),
+            SqlScalar::Int8(v) => (*v).into(),
+            SqlScalar::Float4(v) => (*v).into(),
+            SqlScalar::Float8(v) => (*v).into(),
+            SqlScalar::Numeric(v) => v.clone().into(),
+            SqlScalar::Text(v) => v.clone().into(),
+            SqlScalar::Json(v) => v.clone(),
+            SqlScalar::Date(v) => v.to_string().into(),
+            SqlScalar::Time(v) => v.to_string().into(),
+            SqlScalar::Timestamp(v) => v.format("%Y-%m-%dT%H:%M:%S%.f").to_string().into(),
+            SqlScalar::Timestamptz(v) => v.to_rfc3339().into(),
+            SqlScalar::Bytea(v) => base64::engine::general_purpose::STANDARD.encode(v).into(),
+            SqlScalar::Oid(v) => v.to_string().into(),
+            SqlScalar::Uuid(v) => format_uuid(v).unwrap_or_default().into(),
+            SqlScalar::Array(items) => items
+                .iter()
+                .map(|item| item.as_ref().map_or(serde_json::Value::Null, Self::to_json))
+                .collect(),
+            SqlScalar::Record(fields) => fields
+                .iter()
+                .map(|(name, value)| {
+                    let value = value
+                        .as_ref()
+                        .map_or(serde_json::Value::Null, Self::to_json);
+                    (name.clone(), value)
+                })
+                .collect::<serde_json::Map<_, _>>()
+                .into(),
+        }
+    }
+}
+
+macro_rules! impl_from {
+    ($($ty:ty => $variant:ident),* $(,)?) => {
+        $(impl From<$ty> for SqlScalar {
+            fn from(v: $ty) -> Self {
+                SqlScalar::$variant(v.into())
+            }
+        })*
+    };
+}
+
+impl_from! {
+    bool => Bool,
+    i16 => Int2,
+    i32 => Int4,
+    i64 => Int8,
+    f32 => Float4,
+    f64 => Float8,
+    String => Text,
+    &str => Text,
+    serde_json::Value => Json,
+    NaiveDate => Date,
+    NaiveTime => Time,
+    NaiveDateTime => Timestamp,
+    DateTime<Utc> => Timestamptz,
 }
 
 impl ToSql for SqlScalar {
@@ -35,17 +113,56 @@ impl ToSql for SqlScalar {
             SqlScalar::Int8(v) => v.to_sql(ty, out),
             SqlScalar::Float4(v) => v.to_sql(ty, out),
             SqlScalar::Float8(v) => v.to_sql(ty, out),
-            SqlScalar::Numeric(v) => v.to_sql(ty, out),
+            SqlScalar::Numeric(v) => {
+                Numeric::parse(v)
+                    .ok_or_else(|| format!("invalid numeric {v}"))?
+                    .write(out);
+                Ok(IsNull::No)
+            }
             SqlScalar::Text(v) => v.to_sql(ty, out),
             SqlScalar::Json(v) => v.to_sql(ty, out),
             SqlScalar::Date(v) => v.to_sql(ty, out),
             SqlScalar::Time(v) => v.to_sql(ty, out),
             SqlScalar::Timestamp(v) => v.to_sql(ty, out),
             SqlScalar::Timestamptz(v) => v.to_sql(ty, out),
+            SqlScalar::Bytea(v) => v.to_sql(ty, out),
+            SqlScalar::Oid(v) => v.to_sql(ty, out),
+            SqlScalar::Uuid(v) => {
+                out.put_slice(v);
+                Ok(IsNull::No)
+            }
+            SqlScalar::Array(items) => {
+                let Kind::Array(member) = ty.kind() else {
+                    return Err(format!("cannot write an array as {ty}").into());
+                };
+                write_array(items, member, out)?;
+                Ok(IsNull::No)
+            }
+            SqlScalar::Record(values) => {
+                let Kind::Composite(fields) = ty.kind() else {
+                    return Err(format!("cannot write a record as {ty}").into());
+                };
+                if fields.len() != values.len() {
+                    return Err(format!("wrong number of attributes for {ty}").into());
+                }
+                out.put_i32(fields.len() as i32);
+                for (field, (_, value)) in fields.iter().zip(values) {
+                    out.put_u32(field.type_().oid());
+                    write_element(value.as_ref(), field.type_(), out)?;
+                }
+                Ok(IsNull::No)
+            }
         }
     }
 
+    /// Domains take values of the type they constrain; enums take their
+    /// labels as text.
     fn accepts(ty: &Type) -> bool {
+        match ty.kind() {
+            Kind::Domain(base) | Kind::Array(base) => return Self::accepts(base),
+            Kind::Enum(_) | Kind::Composite(_) => return true,
+            _ => {}
+        }
         matches!(
             *ty,
             Type::BOOL
@@ -64,8 +181,51 @@ impl ToSql for SqlScalar {
                 | Type::TIME
                 | Type::TIMESTAMP
                 | Type::TIMESTAMPTZ
+                | Type::BYTEA
+                | Type::OID
+                | Type::UUID
         )
     }
 
     tokio_postgres::types::to_sql_checked!();
 }
+
+/// Writes a one-dimensional array in the binary format: dimension count,
+/// null flag, element type, the dimension's length and lower bound, then
+/// every element.
+fn write_array(
+    items: &[Option<SqlScalar>],
+    member: &Type,
+    out: &mut BytesMut,
+) -> Result<(), Box<dyn std::error::Error + Sync + Send>> {
+    out.put_i32(if items.is_empty() { 0 } else { 1 });
+    out.put_i32(items.iter().any(Option::is_none) as i32);
+    out.put_u32(member.oid());
+    if !items.is_empty() {
+        out.put_i32(items.len() as i32);
+        out.put_i32(1);
+    }
+    for item in items {
+        write_element(item.as_ref(), member, out)?;
+    }
+    Ok(())
+}
+
+/// Writes a length-prefixed array element or record attribute; `-1` stands
+/// for `NULL`.
+fn write_element(
+    value: Option<&SqlScalar>,
+    ty: &Type,
+    out: &mut BytesMut,
+) -> Result<(), Box<dyn std::error::Error + Sync + Send>> {
+    let start = out.len();
+    out.put_i32(-1);
+    let Some(value) = value else {
+        return Ok(());
+    };
+    if let IsNull::No = value.to_sql_checked(ty, out)? {
+        let len = (out.len() - start - 4) as i32;
+        out[start..start + 4].copy_from_slice(&len.to_be_bytes());
+    }
+    Ok(())
+}
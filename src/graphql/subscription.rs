@@ -0,0 +1,255 @@
+//! `{t}Changed` subscriptions for `@subscribe` tables, fed by the change
+//! events `turbograph_notify_change()` sends. Changed rows are fetched again
+//! by primary key, so each event sees the row as the subscriber's role does.
+//! `{t}Created`, `{t}Updated` and `{t}Deleted` report one kind of change
+//! each, and `listen(topic:)` relays `NOTIFY`s sent by the application.
+
+use std::sync::Arc;
+
+use async_graphql::dynamic::{
+    Enum, EnumItem, Field, FieldFuture, FieldValue, InputValue, Object, SubscriptionField,
+    SubscriptionFieldFuture, TypeRef,
+};
+use async_graphql::futures_util::stream;
+use deadpool_postgres::Pool;
+use tokio::sync::broadcast::error::RecvError;
+
+use crate::db::changes::{ChangeFeed, TOPIC_PREFIX, TopicMessage};
+use crate::db::request::RequestScope;
+use crate::error::gql_err;
+use crate::models::table::Table;
+use crate::utils::inflection::to_camel_case;
+
+use super::node::fetch_by_key;
+
+pub(crate) const CHANGE_OP_ENUM: &str = "ChangeOp";
+
+/// The `ChangeOp` enum shared by every change payload.
+pub(crate) fn make_change_op_enum() -> Enum {
+    Enum::new(CHANGE_OP_ENUM)
+        .description("The kind of change a subscription event reports.")
+        .item(EnumItem::new("INSERT"))
+        .item(EnumItem::new("UPDATE"))
+        .item(EnumItem::new("DELETE"))
+}
+
+/// What a `{T}ChangedPayload` resolves from.
+struct ChangePayload {
+    op: String,
+    key: Vec<serde_json::Value>,
+    row: Option<serde_json::Value>,
+}
+
+/// The `{T}ChangedPayload` type and the `{t}Changed`, `{t}Created`,
+/// `{t}Updated` and `{t}Deleted` subscription fields of `table`, with their
+/// names.
+pub(crate) fn generate_change_subscription(
+    table: Arc<Table>,
+    pool: Arc<Pool>,
+) -> (Object, Vec<(String, SubscriptionField)>) {
+    let type_name = table.type_name();
+    let payload_name = format!("{type_name}ChangedPayload");
+
+    let payload = Object::new(&payload_name)
+        .field(Field::new("op", TypeRef::named_nn(CHANGE_OP_ENUM), |ctx| {
+            FieldFuture::new(async move {
+                let payload = ctx.parent_value.try_downcast_ref::<ChangePayload>()?;
+                Ok(Some(FieldValue::value(async_graphql::Value::Enum(
+                    async_graphql::Name::new(&payload.op),
+                ))))
+            })
+        }))
+        .field(
+            Field::new("key", TypeRef::named_nn(TypeRef::STRING), |ctx| {
+                FieldFuture::new(async move {
+                    let payload = ctx.parent_value.try_downcast_ref::<ChangePayload>()?;
+                    Ok(Some(FieldValue::value(
+                        serde_json::Value::from(payload.key.clone()).to_string(),
+                    )))
+                })
+            })
+            .description("Primary key values of the changed row, as a JSON array."),
+        )
+        .field(
+            Field::new("node", TypeRef::named(&type_name), |ctx| {
+                FieldFuture::new(async move {
+                    let payload = ctx.parent_value.try_downcast_ref::<ChangePayload>()?;
+                    Ok(payload.row.clone().map(FieldValue::owned_any))
+                })
+            })
+            .description(
+                "The row after the change; null after a delete or when it is not visible.",
+            ),
+        );
+
+    let fields = [
+        (None, format!("Changes to {type_name} rows.")),
+        (
+            Some("INSERT"),
+            format!("{type_name} rows as they are inserted."),
+        ),
+        (
+            Some("UPDATE"),
+            format!("{type_name} rows as they are updated."),
+        ),
+        (
+            Some("DELETE"),
+            format!("Keys of {type_name} rows as they are deleted."),
+        ),
+    ]
+    .into_iter()
+    .map(|(op, description)| {
+        let field = change_field(&table, &pool, &payload_name, op).description(description);
+        (change_field_name(&table, op), field)
+    })
+    .collect();
+
+    (payload, fields)
+}
+
+/// `{t}Changed`, or `{t}Created`, `{t}Updated` or `{t}Deleted` for the
+/// changes of kind `op`.
+fn change_field_name(table: &Table, op: Option<&str>) -> String {
+    let suffix = match op {
+        None => "Changed",
+        Some("INSERT") => "Created",
+        Some("UPDATE") => "Updated",
+        Some(_) => "Deleted",
+    };
+    format!("{}{suffix}", to_camel_case(&table.type_name()))
+}
+
+/// The subscription field reporting the changes of `table` of kind `op`, or
+/// of every kind without one.
+fn change_field(
+    table: &Arc<Table>,
+    pool: &Arc<Pool>,
+    payload_name: &str,
+    op: Option<&'static str>,
+) -> SubscriptionField {
+    let field_name = change_field_name(table, op);
+    let table = table.clone();
+    let pool = pool.clone();
+    SubscriptionField::new(field_name, TypeRef::named_nn(payload_name), move |ctx| {
+        let table = table.clone();
+        let pool = pool.clone();
+        let scope = RequestScope::from_ctx(&ctx);
+        let feed = ctx.data_opt::<ChangeFeed>().cloned();
+
+        SubscriptionFieldFuture::new(async move {
+            let feed = feed.ok_or_else(|| gql_err("Subscriptions are not enabled"))?;
+            let events = feed.subscribe();
+            Ok(stream::unfold(events, move |mut events| {
+                let table = table.clone();
+                let pool = pool.clone();
+                let scope = scope.clone();
+                async move {
+                    loop {
+                        let event = match events.recv().await {
+                            Ok(event) => event,
+                            Err(RecvError::Lagged(missed)) => {
+                                eprintln!(
+                                    "[turbograph] {} subscriber missed {missed} change events",
+                                    table.type_name()
+                                );
+                                continue;
+                            }
+                            Err(RecvError::Closed) => return None,
+                        };
+                        if event.schema != table.schema_name()
+                            || event.table != table.name()
+                            || op.is_some_and(|op| op != event.op)
+                        {
+                            continue;
+                        }
+
+                        // The stream shares one request state; rows read for
+                        // earlier events predate this change.
+                        scope.forget_rows();
+                        let row = if event.op == "DELETE" {
+                            Ok(None)
+                        } else {
+                            fetch_by_key(&pool, &table, event.key.clone(), scope.clone()).await
+                        };
+                        let payload = row.map(|row| {
+                            FieldValue::owned_any(ChangePayload {
+                                op: event.op.clone(),
+                                key: event.key.clone(),
+                                row,
+                            })
+                        });
+                        return Some((payload, events));
+                    }
+                }
+            }))
+        })
+    })
+}
+
+pub(crate) const LISTEN_PAYLOAD: &str = "ListenPayload";
+
+/// The `ListenPayload` type and the `listen(topic:)` subscription field,
+/// which relays every `NOTIFY` on the `turbograph:{topic}` channel.
+pub(crate) fn generate_listen_subscription() -> (Object, SubscriptionField) {
+    let payload = Object::new(LISTEN_PAYLOAD)
+        .field(Field::new(
+            "topic",
+            TypeRef::named_nn(TypeRef::STRING),
+            |ctx| {
+                FieldFuture::new(async move {
+                    let message = ctx.parent_value.try_downcast_ref::<TopicMessage>()?;
+                    Ok(Some(FieldValue::value(message.topic.clone())))
+                })
+            },
+        ))
+        .field(
+            Field::new("payload", TypeRef::named(TypeRef::STRING), |ctx| {
+                FieldFuture::new(async move {
+                    let message = ctx.parent_value.try_downcast_ref::<TopicMessage>()?;
+                    Ok((!message.payload.is_empty())
+                        .then(|| FieldValue::value(message.payload.clone())))
+                })
+            })
+            .description("The text sent with the NOTIFY; null when it was empty."),
+        );
+
+    let field = SubscriptionField::new("listen", TypeRef::named_nn(LISTEN_PAYLOAD), |ctx| {
+        let feed = ctx.data_opt::<ChangeFeed>().cloned();
+        let topic = ctx
+            .args
+            .try_get("topic")
+            .and_then(|topic| topic.string().map(str::to_string));
+
+        SubscriptionFieldFuture::new(async move {
+            let feed = feed.ok_or_else(|| gql_err("Subscriptions are not enabled"))?;
+            let topic = topic?;
+            let messages = feed.listen(&topic).await?;
+            Ok(stream::unfold(messages, move |mut messages| {
+                let topic = topic.clone();
+                async move {
+                    loop {
+                        match messages.recv().await {
+                            Ok(message) if message.topic == topic => {
+                                let message = FieldValue::owned_any(TopicMessage::clone(&message));
+                                return Some((Ok::<_, async_graphql::Error>(message), messages));
+                            }
+                            Ok(_) => continue,
+                            Err(RecvError::Lagged(missed)) => {
+                                eprintln!(
+                                    "[turbograph] {topic} listener missed {missed} notifications"
+                                );
+                            }
+                            Err(RecvError::Closed) => return None,
+                        }
+                    }
+                }
+            }))
+        })
+    })
+    .argument(InputValue::new("topic", TypeRef::named_nn(TypeRef::STRING)))
+    .description(format!(
+        "Notifications sent with `NOTIFY \"{TOPIC_PREFIX}<topic>\", '<payload>'`."
+    ));
+
+    (payload, field)
+}
@@ -1,117 +1,306 @@
+use async_graphql::Name;
 use async_graphql::Value as GqlValue;
-use async_graphql::dynamic::{FieldValue, TypeRef};
+use async_graphql::dynamic::{
+    Enum, EnumItem, Field, FieldFuture, FieldValue, InputObject, InputValue, Object, TypeRef,
+};
+use base64::Engine;
 use chrono::{DateTime, NaiveDate, NaiveDateTime, NaiveTime, Utc};
-use tokio_postgres::types::Type;
+use tokio_postgres::types::{Field as PgField, FromSql, Kind, Type};
+
+use crate::db::codec::{Numeric, parse_uuid};
+use crate::error::coded_err;
+use crate::models::config::NonFiniteFloats;
+use crate::models::table::{Column, EnumType};
+use crate::models::types::base_type;
+use crate::utils::inflection::{
+    DefaultInflector, column_field_name, to_constant_case, to_pascal_case,
+};
+
+use super::scalars::{self, CustomScalars};
+use super::sql_scalar::SqlScalar;
 
-use crate::models::table::Column;
+/// The GraphQL enum for an `@enum` table: one item per primary key value.
+pub(crate) fn make_enum_type(enum_type: &EnumType) -> Enum {
+    enum_type
+        .items()
+        .iter()
+        .fold(Enum::new(enum_type.name()), |e, item| {
+            e.item(EnumItem::new(item))
+        })
+}
 
-use super::sql_scalar::SqlScalar;
+/// The GraphQL name of a Postgres enum or composite type, e.g. `PostalAddress`
+/// for `postal_address`.
+pub(crate) fn custom_type_name(ty: &Type) -> String {
+    to_pascal_case(ty.name())
+}
+
+/// A Postgres enum or composite type, or an array of one: the enum or
+/// composite type, and whether it is an array.
+fn custom_element(ty: &Type) -> Option<(&Type, bool)> {
+    let is_custom = |ty: &Type| matches!(ty.kind(), Kind::Enum(_) | Kind::Composite(_));
+    let ty = base_type(ty);
+    match ty.kind() {
+        Kind::Array(element) => Some((base_type(element), true)).filter(|(e, _)| is_custom(e)),
+        _ => Some((ty, false)).filter(|(ty, _)| is_custom(ty)),
+    }
+}
+
+/// The GraphQL enum for a Postgres enum type: one item per label, always
+/// in CONSTANT_CASE since labels are converted without the schema's
+/// inflector.
+pub(crate) fn make_pg_enum_type(ty: &Type) -> Option<Enum> {
+    let Kind::Enum(labels) = ty.kind() else {
+        return None;
+    };
+    Some(make_enum_type(&EnumType::new(
+        custom_type_name(ty),
+        labels.clone(),
+        &DefaultInflector,
+    )))
+}
 
+/// Columns standing in for the attributes of a composite type, so they share
+/// the type mapping of table columns.
+fn composite_columns(fields: &[PgField], camel_case_fields: bool) -> Vec<Column> {
+    fields
+        .iter()
+        .map(|field| {
+            let mut column = Column::procedure_arg(field.name(), field.type_().clone());
+            column.set_field_name(&column_field_name(field.name(), camel_case_fields));
+            column
+        })
+        .collect()
+}
+
+/// The GraphQL object for a Postgres composite type, resolving each field
+/// from the JSON object the row serializer decodes the value into.
+pub(crate) fn make_composite_type(
+    ty: &Type,
+    camel_case_fields: bool,
+    custom_scalars: Option<&CustomScalars>,
+) -> Option<Object> {
+    let Kind::Composite(fields) = ty.kind() else {
+        return None;
+    };
+    let object = composite_columns(fields, camel_case_fields)
+        .into_iter()
+        .fold(Object::new(custom_type_name(ty)), |object, column| {
+            let type_ref = get_type_ref(&column, custom_scalars);
+            let column = std::sync::Arc::new(column);
+            object.field(Field::new(
+                column.field_name().to_string(),
+                type_ref,
+                move |ctx| {
+                    let column = column.clone();
+                    FieldFuture::new(async move {
+                        let value = ctx.parent_value.try_downcast_ref::<serde_json::Value>()?;
+                        let floats = ctx.data_opt::<NonFiniteFloats>().copied();
+                        get_field_value(&column, value, floats.unwrap_or_default())
+                    })
+                },
+            ))
+        });
+    Some(object)
+}
+
+/// The `{T}Input` object a Postgres composite type is written with, or
+/// `None` when none of its attributes can be written.
+pub(crate) fn make_composite_input_type(ty: &Type, camel_case_fields: bool) -> Option<InputObject> {
+    let Kind::Composite(fields) = ty.kind() else {
+        return None;
+    };
+    let inputs: Vec<InputValue> = composite_columns(fields, camel_case_fields)
+        .iter()
+        .filter_map(|column| {
+            Some(InputValue::new(
+                column.field_name(),
+                input_type_ref(column)?,
+            ))
+        })
+        .collect();
+    if inputs.is_empty() {
+        return None;
+    }
+    Some(inputs.into_iter().fold(
+        InputObject::new(composite_input_name(ty)),
+        InputObject::field,
+    ))
+}
+
+fn composite_input_name(ty: &Type) -> String {
+    format!("{}Input", custom_type_name(ty))
+}
+
+/// The GraphQL scalar a Postgres type maps to, or `None` for types without
+/// a mapping. Shared by output types and inputs, so that every value a
+/// field answers is accepted back in the same form.
+fn scalar_name(ty: &Type) -> Option<&'static str> {
+    Some(match *ty {
+        Type::BOOL => TypeRef::BOOLEAN,
+        Type::INT2 | Type::INT4 => TypeRef::INT,
+        Type::INT8 => scalars::BIG_INT,
+        Type::FLOAT4 | Type::FLOAT8 => TypeRef::FLOAT,
+        Type::NUMERIC => scalars::BIG_FLOAT,
+        Type::UUID => scalars::UUID,
+        Type::DATE => scalars::DATE,
+        Type::TIMESTAMP | Type::TIMESTAMPTZ => scalars::DATETIME,
+        // JSON as its serialised text, binary data as base64, times as
+        // ISO 8601 strings
+        Type::TEXT
+        | Type::VARCHAR
+        | Type::BPCHAR
+        | Type::JSON
+        | Type::JSONB
+        | Type::BYTEA
+        | Type::TIME => TypeRef::STRING,
+        _ => return None,
+    })
+}
+
+/// A Postgres type with a [`scalar_name`], or an array of one: the scalar
+/// (element) type, its GraphQL scalar, and whether it is an array.
+fn scalar_element(ty: &Type) -> Option<(&Type, &'static str, bool)> {
+    let ty = base_type(ty);
+    let (ty, is_list) = match ty.kind() {
+        Kind::Array(element) => (base_type(element), true),
+        _ => (ty, false),
+    };
+    Some((ty, scalar_name(ty)?, is_list))
+}
+
+/// A Postgres type without a [`scalar_name`] but with one of
+/// `custom_scalars`, or an array of one: the custom scalar, and whether it
+/// is an array.
+fn custom_scalar_element<'a>(
+    ty: &Type,
+    custom_scalars: Option<&'a CustomScalars>,
+) -> Option<(&'a str, bool)> {
+    let ty = base_type(ty);
+    let (ty, is_list) = match ty.kind() {
+        Kind::Array(element) => (base_type(element), true),
+        _ => (ty, false),
+    };
+    if scalar_name(ty).is_some() {
+        return None;
+    }
+    Some((custom_scalars?.name(ty)?, is_list))
+}
+
+/// The output value of `column` in `value`, a row as the row serializer
+/// decoded it. Fails only for a non-finite float under
+/// [`NonFiniteFloats::Error`].
 pub(crate) fn get_field_value<'a>(
     column: &Column,
     value: &serde_json::Value,
-) -> Option<FieldValue<'a>> {
-    let raw_val = value.get(column.name())?;
+    floats: NonFiniteFloats,
+) -> Result<Option<FieldValue<'a>>, async_graphql::Error> {
+    let Some(raw_val) = value.get(column.name()).filter(|v| !v.is_null()) else {
+        return Ok(None);
+    };
 
-    if raw_val.is_null() {
-        return None;
+    if let Some(enum_type) = column.enum_type() {
+        let Some(item) = raw_val.as_str().and_then(|label| enum_type.item(label)) else {
+            return Ok(None);
+        };
+        return Ok(Some(FieldValue::value(GqlValue::Enum(Name::new(item)))));
+    }
+    match custom_element(column.base_type()) {
+        Some((_, false)) => return Ok(custom_field_value(raw_val)),
+        // Dynamic lists cannot hold a null enum or object, so `NULL`
+        // elements are left out.
+        Some((_, true)) => {
+            let Some(items) = raw_val.as_array() else {
+                return Ok(None);
+            };
+            let items = items.iter().filter_map(custom_field_value);
+            return Ok(Some(FieldValue::list(items.collect::<Vec<_>>())));
+        }
+        None => {}
     }
 
-    let field_val = match *column._type() {
-        Type::BOOL => FieldValue::value(raw_val.as_bool()),
-        Type::INT2 | Type::INT4 => FieldValue::value(raw_val.as_i64().map(|v| v as i32)),
-        // i64 exceeds GraphQL Int (i32), so serialise as String
-        Type::INT8 => FieldValue::value(raw_val.as_i64().map(|v| v.to_string())),
-        Type::FLOAT4 | Type::FLOAT8 => FieldValue::value(raw_val.as_f64()),
-        Type::NUMERIC => FieldValue::value(raw_val.as_f64()),
-        Type::TEXT | Type::VARCHAR | Type::BPCHAR => FieldValue::value(raw_val.as_str()),
-        // JSON/JSONB: serialise to a JSON string
-        Type::JSON | Type::JSONB => FieldValue::value(Some(raw_val.to_string())),
-        // date/time: already serialised as ISO 8601 strings by Postgres row JSON
-        Type::DATE | Type::TIME | Type::TIMETZ | Type::TIMESTAMP | Type::TIMESTAMPTZ => {
-            FieldValue::value(raw_val.as_str())
-        }
-        // --- array types ---
-        Type::BOOL_ARRAY => FieldValue::list(
+    Ok(Some(match scalar_element(column.base_type()) {
+        Some((ty, _, false)) => FieldValue::value(scalar_value(ty, raw_val, floats)?),
+        Some((ty, _, true)) => FieldValue::list(
             raw_val
                 .as_array()
                 .into_iter()
                 .flatten()
-                .map(|v| FieldValue::value(v.as_bool()))
-                .collect::<Vec<_>>(),
+                .map(|v| scalar_value(ty, v, floats).map(FieldValue::value))
+                .collect::<Result<Vec<_>, _>>()?,
         ),
-        Type::INT2_ARRAY | Type::INT4_ARRAY => FieldValue::list(
-            raw_val
-                .as_array()
-                .into_iter()
-                .flatten()
-                .map(|v| FieldValue::value(v.as_i64().map(|n| n as i32)))
-                .collect::<Vec<_>>(),
-        ),
-        Type::INT8_ARRAY => FieldValue::list(
-            raw_val
-                .as_array()
-                .into_iter()
-                .flatten()
-                .map(|v| FieldValue::value(v.as_i64().map(|n| n.to_string())))
-                .collect::<Vec<_>>(),
-        ),
-        Type::FLOAT4_ARRAY | Type::FLOAT8_ARRAY => FieldValue::list(
-            raw_val
-                .as_array()
-                .into_iter()
-                .flatten()
-                .map(|v| FieldValue::value(v.as_f64()))
-                .collect::<Vec<_>>(),
-        ),
-        Type::TEXT_ARRAY | Type::VARCHAR_ARRAY | Type::BPCHAR_ARRAY => FieldValue::list(
-            raw_val
-                .as_array()
-                .into_iter()
-                .flatten()
-                .map(|v| FieldValue::value(v.as_str()))
-                .collect::<Vec<_>>(),
-        ),
-        Type::JSON_ARRAY | Type::JSONB_ARRAY => FieldValue::list(
-            raw_val
-                .as_array()
-                .into_iter()
-                .flatten()
-                .map(|v| FieldValue::value(Some(v.to_string())))
-                .collect::<Vec<_>>(),
-        ),
-        _ => FieldValue::value(raw_val.as_str()),
-    };
+        // Unsupported types answer their text, if they have one, and custom
+        // scalars what their type decodes to.
+        None => match raw_val {
+            serde_json::Value::String(text) => FieldValue::value(text.as_str()),
+            value => {
+                FieldValue::value(GqlValue::from_json(value.clone()).unwrap_or(GqlValue::Null))
+            }
+        },
+    }))
+}
 
-    Some(field_val)
+/// The output value of a scalar as the row serializer decoded it.
+fn scalar_value(
+    ty: &Type,
+    value: &serde_json::Value,
+    floats: NonFiniteFloats,
+) -> Result<GqlValue, async_graphql::Error> {
+    Ok(match *ty {
+        Type::INT2 | Type::INT4 => value.as_i64().map(|v| GqlValue::from(v as i32)),
+        Type::INT8 => value.as_i64().map(|v| GqlValue::String(v.to_string())),
+        Type::JSON | Type::JSONB => Some(GqlValue::String(value.to_string())),
+        // `NaN` and the infinities, which the row serializer keeps as text.
+        Type::FLOAT4 | Type::FLOAT8 if value.is_string() => match floats {
+            NonFiniteFloats::Null => None,
+            NonFiniteFloats::String => GqlValue::from_json(value.clone()).ok(),
+            NonFiniteFloats::Error => {
+                return Err(coded_err(
+                    "NON_FINITE_FLOAT",
+                    format!("{value} cannot be represented as a Float"),
+                ));
+            }
+        },
+        // Numbers, booleans, and the strings every other scalar decodes to.
+        _ => GqlValue::from_json(value.clone()).ok(),
+    }
+    .unwrap_or(GqlValue::Null))
 }
 
-pub(crate) fn get_type_ref(column: &Column) -> TypeRef {
-    let (base, is_list): (&str, bool) = match *column._type() {
-        Type::BOOL => (TypeRef::BOOLEAN, false),
-        Type::INT2 | Type::INT4 => (TypeRef::INT, false),
-        // i64 exceeds GraphQL Int (i32), expose as String
-        Type::INT8 => (TypeRef::STRING, false),
-        Type::FLOAT4 | Type::FLOAT8 => (TypeRef::FLOAT, false),
-        Type::NUMERIC => (TypeRef::FLOAT, false),
-        Type::TEXT | Type::VARCHAR | Type::BPCHAR => (TypeRef::STRING, false),
-        // JSON/JSONB serialised as a JSON string
-        Type::JSON | Type::JSONB => (TypeRef::STRING, false),
-        // date/time types serialised as ISO 8601 strings
-        Type::DATE | Type::TIME | Type::TIMETZ | Type::TIMESTAMP | Type::TIMESTAMPTZ => {
-            (TypeRef::STRING, false)
-        }
-        // --- array types ---
-        Type::BOOL_ARRAY => (TypeRef::BOOLEAN, true),
-        Type::INT2_ARRAY | Type::INT4_ARRAY => (TypeRef::INT, true),
-        Type::INT8_ARRAY => (TypeRef::STRING, true),
-        Type::FLOAT4_ARRAY | Type::FLOAT8_ARRAY => (TypeRef::FLOAT, true),
-        Type::TEXT_ARRAY | Type::VARCHAR_ARRAY | Type::BPCHAR_ARRAY => (TypeRef::STRING, true),
-        Type::JSON_ARRAY | Type::JSONB_ARRAY => (TypeRef::STRING, true),
-        _ => (TypeRef::STRING, false),
-    };
+/// The output value of a Postgres enum label (as its item) or composite
+/// value (as the JSON object its fields resolve from).
+fn custom_field_value<'a>(value: &serde_json::Value) -> Option<FieldValue<'a>> {
+    match value {
+        serde_json::Value::Null => None,
+        serde_json::Value::String(label) => Some(FieldValue::value(GqlValue::Enum(Name::new(
+            to_constant_case(label),
+        )))),
+        value => Some(FieldValue::owned_any(value.clone())),
+    }
+}
+
+pub(crate) fn get_type_ref(column: &Column, custom_scalars: Option<&CustomScalars>) -> TypeRef {
+    if let Some(enum_type) = column.enum_type() {
+        return list_type_ref(enum_type.name(), false, column.nullable());
+    }
+    if let Some((ty, is_list)) = custom_element(column.base_type()) {
+        return list_type_ref(&custom_type_name(ty), is_list, column.nullable());
+    }
+
+    if let Some((name, is_list)) = custom_scalar_element(column.base_type(), custom_scalars) {
+        return list_type_ref(name, is_list, column.nullable());
+    }
 
-    match (is_list, column.nullable()) {
+    // Unsupported types answer their text, see `UnsupportedTypes::String`.
+    let (base, is_list) = scalar_element(column.base_type())
+        .map_or((TypeRef::STRING, false), |(_, name, is_list)| {
+            (name, is_list)
+        });
+    list_type_ref(base, is_list, column.nullable())
+}
+
+fn list_type_ref(base: &str, is_list: bool, nullable: bool) -> TypeRef {
+    match (is_list, nullable) {
         (false, true) => TypeRef::named(base),
         (false, false) => TypeRef::named_nn(base),
         (true, true) => TypeRef::named_list(base),
@@ -119,125 +308,200 @@ pub(crate) fn get_type_ref(column: &Column) -> TypeRef {
     }
 }
 
+/// The `TypeRef` of a column in `Create{T}Input`: like the condition type,
+/// but required when the column is `NOT NULL` and the database has no way to
+/// fill it in. Columns with a default or an identity stay optional, even
+/// though they are non-null in the output type.
+pub(crate) fn create_input_type_ref(column: &Column) -> Option<TypeRef> {
+    let type_ref = input_type_ref(column)?;
+    Some(if !column.nullable() && !column.has_default() {
+        TypeRef::NonNull(Box::new(type_ref))
+    } else {
+        type_ref
+    })
+}
+
+/// Whether the type mapping knows the column's type, rather than falling
+/// back to its text as a `String`; see
+/// [`UnsupportedTypes`](crate::UnsupportedTypes).
+pub(crate) fn is_supported_type(column: &Column, custom_scalars: Option<&CustomScalars>) -> bool {
+    column.enum_type().is_some()
+        || custom_element(column.base_type()).is_some()
+        || scalar_element(column.base_type()).is_some()
+        || custom_scalar_element(column.base_type(), custom_scalars).is_some()
+        || *column.base_type() == Type::OID
+        || <String as FromSql>::accepts(column.base_type())
+}
+
 /// Returns a nullable scalar `TypeRef` for use in a condition input object.
-/// Returns `None` for array / unsupported types (they cannot be equality-filtered).
+/// Returns `None` for array, composite, binary and unsupported types (they
+/// cannot be equality-filtered), for `@encrypted` columns, whose ciphertext
+/// cannot be compared, and for upload columns.
 pub(crate) fn condition_type_ref(column: &Column) -> Option<TypeRef> {
-    let scalar = match *column._type() {
-        Type::BOOL => TypeRef::BOOLEAN,
-        Type::INT2 | Type::INT4 => TypeRef::INT,
-        // INT8 mapped to String (i64 > i32 GraphQL range)
-        Type::INT8 => TypeRef::STRING,
-        Type::FLOAT4 | Type::FLOAT8 => TypeRef::FLOAT,
-        Type::TEXT | Type::VARCHAR | Type::BPCHAR => TypeRef::STRING,
-        // JSON/JSONB accept a serialised JSON string for filtering
-        Type::JSON | Type::JSONB => TypeRef::STRING,
-        // NUMERIC: accept as Float for filtering
-        Type::NUMERIC => TypeRef::FLOAT,
-        // date/time: accept ISO 8601 strings for filtering
-        Type::DATE | Type::TIME | Type::TIMESTAMP | Type::TIMESTAMPTZ => TypeRef::STRING,
-        // arrays and everything else are excluded from condition
-        _ => return None,
-    };
+    if column.encrypted() || column.upload() || *column.base_type() == Type::BYTEA {
+        return None;
+    }
+    if let Some((ty, is_list)) = custom_element(column.base_type())
+        && (is_list || matches!(ty.kind(), Kind::Composite(_)))
+    {
+        return None;
+    }
+    if scalar_element(column.base_type()).is_some_and(|(_, _, is_list)| is_list) {
+        return None;
+    }
+    input_type_ref(column)
+}
+
+/// Returns the nullable `TypeRef` a column takes in input objects
+/// (`{T}Patch`, `Create{T}Input`), or `None` for unsupported types. Arrays
+/// take a list of their elements; upload columns take an `Upload`.
+pub(crate) fn input_type_ref(column: &Column) -> Option<TypeRef> {
+    if column.upload() {
+        return Some(TypeRef::named(TypeRef::UPLOAD));
+    }
+    if let Some(enum_type) = column.enum_type() {
+        return Some(TypeRef::named(enum_type.name()));
+    }
+    if let Some((ty, is_list)) = custom_element(column.base_type()) {
+        let name = match ty.kind() {
+            Kind::Composite(fields) => {
+                // An input object needs at least one field.
+                composite_columns(fields, false)
+                    .iter()
+                    .find_map(input_type_ref)?;
+                composite_input_name(ty)
+            }
+            _ => custom_type_name(ty),
+        };
+        return Some(list_type_ref(&name, is_list, true));
+    }
+
+    let (_, name, is_list) = scalar_element(column.base_type())?;
     // Always nullable — every condition field is optional
-    Some(TypeRef::named(scalar))
+    Some(list_type_ref(name, is_list, true))
 }
 
 /// Converts an incoming GraphQL argument value to a typed SQL parameter.
+/// Strings stand in for enum values, as in the JSON of [`crate::ir`]
+/// operations.
 pub(crate) fn to_sql_scalar(column: &Column, val: &GqlValue) -> Option<SqlScalar> {
-    match *column._type() {
-        Type::BOOL => {
-            if let GqlValue::Boolean(b) = val {
-                Some(SqlScalar::Bool(*b))
-            } else {
-                None
-            }
-        }
-        Type::INT2 => {
-            if let GqlValue::Number(n) = val {
-                n.as_i64().map(|v| SqlScalar::Int2(v as i16))
-            } else {
-                None
-            }
+    if let Some(enum_type) = column.enum_type() {
+        let item = match val {
+            GqlValue::Enum(item) => item.as_str(),
+            GqlValue::String(item) => item.as_str(),
+            _ => return None,
+        };
+        return enum_type
+            .db_value(item)
+            .map(|value| SqlScalar::Text(value.to_string()));
+    }
+    match custom_element(column.base_type()) {
+        Some((ty, false)) => return custom_sql_scalar(ty, val),
+        Some((ty, true)) => {
+            let GqlValue::List(items) = val else {
+                return None;
+            };
+            return items
+                .iter()
+                .map(|item| match item {
+                    GqlValue::Null => Some(None),
+                    item => custom_sql_scalar(ty, item).map(Some),
+                })
+                .collect::<Option<_>>()
+                .map(SqlScalar::Array);
         }
-        Type::INT4 => {
-            if let GqlValue::Number(n) = val {
-                n.as_i64().map(|v| SqlScalar::Int4(v as i32))
-            } else {
-                None
-            }
+        None => {}
+    }
+
+    match scalar_element(column.base_type()) {
+        Some((ty, _, true)) => {
+            let GqlValue::List(items) = val else {
+                return None;
+            };
+            items
+                .iter()
+                .map(|item| match item {
+                    GqlValue::Null => Some(None),
+                    item => scalar_sql(ty, item).map(Some),
+                })
+                .collect::<Option<_>>()
+                .map(SqlScalar::Array)
         }
-        // INT8 is exposed as String in the schema
-        Type::INT8 => match val {
-            GqlValue::Number(n) => n.as_i64().map(SqlScalar::Int8),
-            GqlValue::String(s) => s.parse::<i64>().ok().map(SqlScalar::Int8),
-            _ => None,
-        },
-        Type::FLOAT4 => {
-            if let GqlValue::Number(n) = val {
-                n.as_f64().map(|v| SqlScalar::Float4(v as f32))
-            } else {
-                None
-            }
-        }
-        Type::FLOAT8 => {
-            if let GqlValue::Number(n) = val {
-                n.as_f64().map(SqlScalar::Float8)
-            } else {
-                None
-            }
-        }
-        Type::TEXT | Type::VARCHAR | Type::BPCHAR => {
-            if let GqlValue::String(s) = val {
-                Some(SqlScalar::Text(s.clone()))
-            } else {
-                None
-            }
-        }
-        // JSON/JSONB condition value is a serialised JSON string
-        Type::JSON | Type::JSONB => {
-            if let GqlValue::String(s) = val {
-                serde_json::from_str(s).ok().map(SqlScalar::Json)
-            } else {
-                None
-            }
-        }
-        Type::NUMERIC => {
-            if let GqlValue::Number(n) = val {
-                n.as_f64().map(SqlScalar::Numeric)
-            } else {
-                None
-            }
+        _ => scalar_sql(column.base_type(), val),
+    }
+}
+
+/// Converts a scalar input to the parameter of a column of type `ty`, in
+/// the form its [`scalar_name`] accepts and the row serializer answers.
+fn scalar_sql(ty: &Type, val: &GqlValue) -> Option<SqlScalar> {
+    match (ty, val) {
+        (&Type::BOOL, GqlValue::Boolean(b)) => Some(SqlScalar::Bool(*b)),
+        (&Type::INT2, GqlValue::Number(n)) => n.as_i64().map(|v| SqlScalar::Int2(v as i16)),
+        (&Type::INT4, GqlValue::Number(n)) => n.as_i64().map(|v| SqlScalar::Int4(v as i32)),
+        // BigInt travels as a string but accepts a number too
+        (&Type::INT8, GqlValue::Number(n)) => n.as_i64().map(SqlScalar::Int8),
+        (&Type::INT8, GqlValue::String(s)) => s.parse::<i64>().ok().map(SqlScalar::Int8),
+        (&Type::FLOAT4, GqlValue::Number(n)) => n.as_f64().map(|v| SqlScalar::Float4(v as f32)),
+        (&Type::FLOAT8, GqlValue::Number(n)) => n.as_f64().map(SqlScalar::Float8),
+        (&Type::TEXT | &Type::VARCHAR | &Type::BPCHAR, GqlValue::String(s)) => {
+            Some(SqlScalar::Text(s.clone()))
         }
-        Type::DATE => {
-            if let GqlValue::String(s) = val {
-                s.parse::<NaiveDate>().ok().map(SqlScalar::Date)
-            } else {
-                None
-            }
+        // JSON/JSONB value is a serialised JSON string
+        (&Type::JSON | &Type::JSONB, GqlValue::String(s)) => {
+            serde_json::from_str(s).ok().map(SqlScalar::Json)
         }
-        Type::TIME => {
-            if let GqlValue::String(s) = val {
-                s.parse::<NaiveTime>().ok().map(SqlScalar::Time)
-            } else {
-                None
-            }
+        // Decimal text keeps every digit; a number is taken as written
+        (&Type::NUMERIC, GqlValue::Number(n)) => {
+            Numeric::parse(&n.to_string()).map(|_| SqlScalar::Numeric(n.to_string()))
         }
-        Type::TIMESTAMP => {
-            if let GqlValue::String(s) = val {
-                s.parse::<NaiveDateTime>().ok().map(SqlScalar::Timestamp)
-            } else {
-                None
-            }
+        (&Type::NUMERIC, GqlValue::String(s)) => {
+            Numeric::parse(s).map(|_| SqlScalar::Numeric(s.clone()))
         }
-        Type::TIMESTAMPTZ => {
-            if let GqlValue::String(s) = val {
-                DateTime::parse_from_rfc3339(s)
-                    .ok()
-                    .map(|dt| SqlScalar::Timestamptz(dt.with_timezone(&Utc)))
-            } else {
-                None
-            }
+        (&Type::UUID, GqlValue::String(s)) => parse_uuid(s).map(SqlScalar::Uuid),
+        (&Type::BYTEA, GqlValue::String(s)) => base64::engine::general_purpose::STANDARD
+            .decode(s)
+            .ok()
+            .map(SqlScalar::Bytea),
+        (&Type::DATE, GqlValue::String(s)) => s.parse::<NaiveDate>().ok().map(SqlScalar::Date),
+        (&Type::TIME, GqlValue::String(s)) => s.parse::<NaiveTime>().ok().map(SqlScalar::Time),
+        (&Type::TIMESTAMP, GqlValue::String(s)) => {
+            s.parse::<NaiveDateTime>().ok().map(SqlScalar::Timestamp)
         }
+        (&Type::TIMESTAMPTZ, GqlValue::String(s)) => DateTime::parse_from_rfc3339(s)
+            .ok()
+            .map(|dt| SqlScalar::Timestamptz(dt.with_timezone(&Utc))),
+        _ => None,
+    }
+}
+
+/// Converts an enum item to its label, or an input object to a record of
+/// the composite type's attributes. Keys may be the attribute names or their
+/// camelCase form, whichever the input object was generated with.
+fn custom_sql_scalar(ty: &Type, val: &GqlValue) -> Option<SqlScalar> {
+    match (ty.kind(), val) {
+        (Kind::Enum(labels), GqlValue::Enum(item)) => labels
+            .iter()
+            .find(|label| to_constant_case(label) == item.as_str())
+            .map(|label| SqlScalar::Text(label.clone())),
+        (Kind::Enum(labels), GqlValue::String(item)) => labels
+            .iter()
+            .find(|label| to_constant_case(label) == *item)
+            .map(|label| SqlScalar::Text(label.clone())),
+        (Kind::Composite(fields), GqlValue::Object(input)) => composite_columns(fields, false)
+            .iter()
+            .map(|column| {
+                let camel = column_field_name(column.name(), true);
+                let value = input
+                    .get(column.field_name())
+                    .or_else(|| input.get(camel.as_str()));
+                let scalar = match value {
+                    None | Some(GqlValue::Null) => None,
+                    Some(value) => Some(to_sql_scalar(column, value)?),
+                };
+                Some((column.name().clone(), scalar))
+            })
+            .collect::<Option<_>>()
+            .map(SqlScalar::Record),
         _ => None,
     }
 }
@@ -256,103 +520,119 @@ mod tests {
     #[test]
     fn test_type_ref_bool_non_nullable() {
         let col = Column::new_for_test("active", Type::BOOL, false, false);
-        assert_eq!(get_type_ref(&col).to_string(), "Boolean!");
+        assert_eq!(get_type_ref(&col, None).to_string(), "Boolean!");
     }
 
     #[test]
     fn test_type_ref_bool_nullable() {
         let col = Column::new_for_test("active", Type::BOOL, true, false);
-        assert_eq!(get_type_ref(&col).to_string(), "Boolean");
+        assert_eq!(get_type_ref(&col, None).to_string(), "Boolean");
     }
 
     #[test]
     fn test_type_ref_int4_non_nullable() {
         let col = Column::new_for_test("count", Type::INT4, false, false);
-        assert_eq!(get_type_ref(&col).to_string(), "Int!");
+        assert_eq!(get_type_ref(&col, None).to_string(), "Int!");
     }
 
     #[test]
     fn test_type_ref_int4_nullable() {
         let col = Column::new_for_test("count", Type::INT4, true, false);
-        assert_eq!(get_type_ref(&col).to_string(), "Int");
+        assert_eq!(get_type_ref(&col, None).to_string(), "Int");
     }
 
     #[test]
-    fn test_type_ref_int8_exposed_as_string() {
+    fn test_type_ref_int8_exposed_as_big_int() {
         let col = Column::new_for_test("big_id", Type::INT8, false, false);
-        assert_eq!(get_type_ref(&col).to_string(), "String!");
+        assert_eq!(get_type_ref(&col, None).to_string(), "BigInt!");
+    }
+
+    #[test]
+    fn test_type_ref_custom_scalars() {
+        for (ty, expected) in [
+            (Type::NUMERIC, "BigFloat!"),
+            (Type::UUID, "UUID!"),
+            (Type::DATE, "Date!"),
+            (Type::TIMESTAMP, "Datetime!"),
+            (Type::TIMESTAMPTZ, "Datetime!"),
+            (Type::BYTEA, "String!"),
+            (Type::UUID_ARRAY, "[UUID!]"),
+        ] {
+            let col = Column::new_for_test("value", ty, false, false);
+            assert_eq!(get_type_ref(&col, None).to_string(), expected);
+        }
     }
 
     #[test]
     fn test_type_ref_float4_non_nullable() {
         let col = Column::new_for_test("price", Type::FLOAT4, false, false);
-        assert_eq!(get_type_ref(&col).to_string(), "Float!");
+        assert_eq!(get_type_ref(&col, None).to_string(), "Float!");
     }
 
     #[test]
     fn test_type_ref_float8_nullable() {
         let col = Column::new_for_test("price", Type::FLOAT8, true, false);
-        assert_eq!(get_type_ref(&col).to_string(), "Float");
+        assert_eq!(get_type_ref(&col, None).to_string(), "Float");
     }
 
     #[test]
     fn test_type_ref_text_non_nullable() {
         let col = Column::new_for_test("title", Type::TEXT, false, false);
-        assert_eq!(get_type_ref(&col).to_string(), "String!");
+        assert_eq!(get_type_ref(&col, None).to_string(), "String!");
     }
 
     #[test]
     fn test_type_ref_varchar_non_nullable() {
         let col = Column::new_for_test("code", Type::VARCHAR, false, false);
-        assert_eq!(get_type_ref(&col).to_string(), "String!");
+        assert_eq!(get_type_ref(&col, None).to_string(), "String!");
     }
 
     #[test]
     fn test_type_ref_jsonb_non_nullable() {
         let col = Column::new_for_test("meta", Type::JSONB, false, false);
-        assert_eq!(get_type_ref(&col).to_string(), "String!");
+        assert_eq!(get_type_ref(&col, None).to_string(), "String!");
     }
 
     #[test]
     fn test_type_ref_json_nullable() {
         let col = Column::new_for_test("meta", Type::JSON, true, false);
-        assert_eq!(get_type_ref(&col).to_string(), "String");
+        assert_eq!(get_type_ref(&col, None).to_string(), "String");
     }
 
     #[test]
     fn test_type_ref_bool_array_non_nullable() {
         let col = Column::new_for_test("flags", Type::BOOL_ARRAY, false, false);
-        assert_eq!(get_type_ref(&col).to_string(), "[Boolean!]");
+        assert_eq!(get_type_ref(&col, None).to_string(), "[Boolean!]");
     }
 
     #[test]
     fn test_type_ref_bool_array_nullable() {
         let col = Column::new_for_test("flags", Type::BOOL_ARRAY, true, false);
-        assert_eq!(get_type_ref(&col).to_string(), "[Boolean]");
+        assert_eq!(get_type_ref(&col, None).to_string(), "[Boolean]");
     }
 
     #[test]
     fn test_type_ref_int4_array_non_nullable() {
         let col = Column::new_for_test("ids", Type::INT4_ARRAY, false, false);
-        assert_eq!(get_type_ref(&col).to_string(), "[Int!]");
+        assert_eq!(get_type_ref(&col, None).to_string(), "[Int!]");
     }
 
     #[test]
     fn test_type_ref_int4_array_nullable() {
         let col = Column::new_for_test("ids", Type::INT4_ARRAY, true, false);
-        assert_eq!(get_type_ref(&col).to_string(), "[Int]");
+        assert_eq!(get_type_ref(&col, None).to_string(), "[Int]");
     }
 
     #[test]
     fn test_type_ref_text_array_non_nullable() {
         let col = Column::new_for_test("tags", Type::TEXT_ARRAY, false, false);
-        assert_eq!(get_type_ref(&col).to_string(), "[String!]");
+        assert_eq!(get_type_ref(&col, None).to_string(), "[String!]");
     }
 
     #[test]
     fn test_type_ref_jsonb_array_non_nullable() {
         let col = Column::new_for_test("payloads", Type::JSONB_ARRAY, false, false);
-        assert_eq!(get_type_ref(&col).to_string(), "[String!]");
+        assert_eq!(get_type_ref(&col, None).to_string(), "[String!]");
     }
 
     // ── get_field_value ───────────────────────────────────────────────────────
@@ -361,105 +641,184 @@ mod tests {
     fn test_field_value_missing_key_returns_none() {
         let col = Column::new_for_test("name", Type::TEXT, false, false);
         let val = json!({ "other": "value" });
-        assert!(get_field_value(&col, &val).is_none());
+        assert!(
+            get_field_value(&col, &val, NonFiniteFloats::Null)
+                .unwrap()
+                .is_none()
+        );
     }
 
     #[test]
     fn test_field_value_null_returns_none() {
         let col = Column::new_for_test("name", Type::TEXT, true, false);
         let val = json!({ "name": null });
-        assert!(get_field_value(&col, &val).is_none());
+        assert!(
+            get_field_value(&col, &val, NonFiniteFloats::Null)
+                .unwrap()
+                .is_none()
+        );
     }
 
     #[test]
     fn test_field_value_bool_present() {
         let col = Column::new_for_test("active", Type::BOOL, false, false);
         let val = json!({ "active": true });
-        assert!(get_field_value(&col, &val).is_some());
+        assert!(
+            get_field_value(&col, &val, NonFiniteFloats::Null)
+                .unwrap()
+                .is_some()
+        );
     }
 
     #[test]
     fn test_field_value_int2_present() {
         let col = Column::new_for_test("score", Type::INT2, false, false);
         let val = json!({ "score": 7 });
-        assert!(get_field_value(&col, &val).is_some());
+        assert!(
+            get_field_value(&col, &val, NonFiniteFloats::Null)
+                .unwrap()
+                .is_some()
+        );
     }
 
     #[test]
     fn test_field_value_int4_present() {
         let col = Column::new_for_test("count", Type::INT4, false, false);
         let val = json!({ "count": 42 });
-        assert!(get_field_value(&col, &val).is_some());
+        assert!(
+            get_field_value(&col, &val, NonFiniteFloats::Null)
+                .unwrap()
+                .is_some()
+        );
     }
 
     #[test]
     fn test_field_value_int8_present() {
         let col = Column::new_for_test("big_id", Type::INT8, false, false);
         let val = json!({ "big_id": 9223372036854775807_i64 });
-        assert!(get_field_value(&col, &val).is_some());
+        assert!(
+            get_field_value(&col, &val, NonFiniteFloats::Null)
+                .unwrap()
+                .is_some()
+        );
     }
 
     #[test]
     fn test_field_value_float8_present() {
         let col = Column::new_for_test("price", Type::FLOAT8, false, false);
-        let val = json!({ "price": 3.14 });
-        assert!(get_field_value(&col, &val).is_some());
+        let val = json!({ "price": 9.99 });
+        assert!(
+            get_field_value(&col, &val, NonFiniteFloats::Null)
+                .unwrap()
+                .is_some()
+        );
     }
 
     #[test]
     fn test_field_value_text_present() {
         let col = Column::new_for_test("title", Type::TEXT, false, false);
         let val = json!({ "title": "hello" });
-        assert!(get_field_value(&col, &val).is_some());
+        assert!(
+            get_field_value(&col, &val, NonFiniteFloats::Null)
+                .unwrap()
+                .is_some()
+        );
     }
 
     #[test]
     fn test_field_value_jsonb_present() {
         let col = Column::new_for_test("meta", Type::JSONB, false, false);
         let val = json!({ "meta": { "key": "value" } });
-        assert!(get_field_value(&col, &val).is_some());
+        assert!(
+            get_field_value(&col, &val, NonFiniteFloats::Null)
+                .unwrap()
+                .is_some()
+        );
     }
 
     #[test]
     fn test_field_value_bool_array_present() {
         let col = Column::new_for_test("flags", Type::BOOL_ARRAY, false, false);
         let val = json!({ "flags": [true, false, true] });
-        assert!(get_field_value(&col, &val).is_some());
+        assert!(
+            get_field_value(&col, &val, NonFiniteFloats::Null)
+                .unwrap()
+                .is_some()
+        );
     }
 
     #[test]
     fn test_field_value_int4_array_present() {
         let col = Column::new_for_test("ids", Type::INT4_ARRAY, false, false);
         let val = json!({ "ids": [1, 2, 3] });
-        assert!(get_field_value(&col, &val).is_some());
+        assert!(
+            get_field_value(&col, &val, NonFiniteFloats::Null)
+                .unwrap()
+                .is_some()
+        );
     }
 
     #[test]
     fn test_field_value_int8_array_present() {
         let col = Column::new_for_test("ids", Type::INT8_ARRAY, false, false);
         let val = json!({ "ids": [1000000000000_i64, 2000000000000_i64] });
-        assert!(get_field_value(&col, &val).is_some());
+        assert!(
+            get_field_value(&col, &val, NonFiniteFloats::Null)
+                .unwrap()
+                .is_some()
+        );
     }
 
     #[test]
     fn test_field_value_float8_array_present() {
         let col = Column::new_for_test("scores", Type::FLOAT8_ARRAY, false, false);
         let val = json!({ "scores": [1.1, 2.2] });
-        assert!(get_field_value(&col, &val).is_some());
+        assert!(
+            get_field_value(&col, &val, NonFiniteFloats::Null)
+                .unwrap()
+                .is_some()
+        );
     }
 
     #[test]
     fn test_field_value_text_array_present() {
         let col = Column::new_for_test("tags", Type::TEXT_ARRAY, false, false);
         let val = json!({ "tags": ["rust", "graphql"] });
-        assert!(get_field_value(&col, &val).is_some());
+        assert!(
+            get_field_value(&col, &val, NonFiniteFloats::Null)
+                .unwrap()
+                .is_some()
+        );
     }
 
     #[test]
     fn test_field_value_jsonb_array_present() {
         let col = Column::new_for_test("payloads", Type::JSONB_ARRAY, false, false);
         let val = json!({ "payloads": [{"a": 1}, {"b": 2}] });
-        assert!(get_field_value(&col, &val).is_some());
+        assert!(
+            get_field_value(&col, &val, NonFiniteFloats::Null)
+                .unwrap()
+                .is_some()
+        );
+    }
+
+    #[test]
+    fn test_scalar_value_non_finite_floats() {
+        let nan = json!("NaN");
+        assert_eq!(
+            scalar_value(&Type::FLOAT8, &nan, NonFiniteFloats::Null).unwrap(),
+            GqlValue::Null
+        );
+        assert_eq!(
+            scalar_value(&Type::FLOAT4, &nan, NonFiniteFloats::String).unwrap(),
+            GqlValue::String("NaN".into())
+        );
+        let err = scalar_value(&Type::FLOAT8, &json!("-Infinity"), NonFiniteFloats::Error);
+        assert!(err.unwrap_err().message.contains("-Infinity"));
+        assert_eq!(
+            scalar_value(&Type::FLOAT8, &json!(1.5), NonFiniteFloats::Error).unwrap(),
+            GqlValue::from(1.5)
+        );
     }
 
     // ── condition_type_ref ───────────────────────────────────────────────────
@@ -477,9 +836,16 @@ mod tests {
     }
 
     #[test]
-    fn test_condition_type_ref_int8_as_string() {
+    fn test_condition_type_ref_int8_as_big_int() {
         let col = Column::new_for_test("big_id", Type::INT8, false, false);
-        assert_eq!(condition_type_ref(&col).unwrap().to_string(), "String");
+        assert_eq!(condition_type_ref(&col).unwrap().to_string(), "BigInt");
+    }
+
+    #[test]
+    fn test_condition_type_ref_bytea_excluded() {
+        let col = Column::new_for_test("data", Type::BYTEA, false, false);
+        assert!(condition_type_ref(&col).is_none());
+        assert_eq!(input_type_ref(&col).unwrap().to_string(), "String");
     }
 
     #[test]
@@ -506,6 +872,31 @@ mod tests {
         assert!(condition_type_ref(&col).is_none());
     }
 
+    // ── create_input_type_ref ────────────────────────────────────────────────
+
+    #[test]
+    fn test_create_input_required_without_default() {
+        let col = Column::new_for_test("name", Type::TEXT, false, false);
+        assert_eq!(create_input_type_ref(&col).unwrap().to_string(), "String!");
+    }
+
+    #[test]
+    fn test_create_input_optional_with_default_or_identity() {
+        use crate::models::table::Identity;
+
+        let col = Column::new_for_test("id", Type::INT4, false, false);
+        let with_default = col.clone().with_default();
+        assert_eq!(
+            create_input_type_ref(&with_default).unwrap().to_string(),
+            "Int"
+        );
+        assert_eq!(get_type_ref(&with_default, None).to_string(), "Int!");
+
+        let identity = col.with_identity(Identity::ByDefault);
+        assert_eq!(create_input_type_ref(&identity).unwrap().to_string(), "Int");
+        assert_eq!(get_type_ref(&identity, None).to_string(), "Int!");
+    }
+
     // ── to_sql_scalar ────────────────────────────────────────────────────────
 
     #[test]
@@ -560,4 +951,39 @@ mod tests {
         let val = GqlValue::Number(serde_json::Number::from(1_i64));
         assert!(to_sql_scalar(&col, &val).is_none());
     }
+
+    #[test]
+    fn test_to_sql_scalar_array_of_scalars() {
+        let col = Column::new_for_test("ids", Type::INT4_ARRAY, false, false);
+        let val = GqlValue::List(vec![GqlValue::from(1), GqlValue::Null]);
+        assert!(matches!(
+            to_sql_scalar(&col, &val),
+            Some(SqlScalar::Array(items))
+                if matches!(items.as_slice(), [Some(SqlScalar::Int4(1)), None])
+        ));
+    }
+
+    #[test]
+    fn test_to_sql_scalar_numeric_keeps_digits() {
+        let col = Column::new_for_test("amount", Type::NUMERIC, false, false);
+        let val = GqlValue::String("12345678901234567890.000000001".to_string());
+        assert!(matches!(
+            to_sql_scalar(&col, &val),
+            Some(SqlScalar::Numeric(s)) if s == "12345678901234567890.000000001"
+        ));
+        let val = GqlValue::String("1e5".to_string());
+        assert!(to_sql_scalar(&col, &val).is_none());
+    }
+
+    #[test]
+    fn test_to_sql_scalar_uuid() {
+        let col = Column::new_for_test("id", Type::UUID, false, false);
+        let val = GqlValue::String("a0eebc99-9c0b-4ef8-bb6d-6bb9bd380a11".to_string());
+        assert!(matches!(
+            to_sql_scalar(&col, &val),
+            Some(SqlScalar::Uuid(bytes)) if bytes[0] == 0xa0
+        ));
+        let val = GqlValue::String("not-a-uuid".to_string());
+        assert!(to_sql_scalar(&col, &val).is_none());
+    }
 }
@@ -0,0 +1,98 @@
+use std::collections::HashMap;
+use std::collections::hash_map::Entry;
+
+/// The origin of the types and root fields every schema has.
+pub(crate) const BUILT_IN: &str = "turbograph's built-ins";
+
+/// What would make a generated schema invalid, or silently replace part of
+/// it, collected while the schema is built so that [`finish`](Self::finish)
+/// reports every problem at once, each naming the tables, columns or
+/// routines it comes from. Without it the build stops at the first error of
+/// async-graphql's registry, which knows only GraphQL names, and a type or
+/// field generated twice replaces the earlier one without any error.
+#[derive(Debug, Default)]
+pub(crate) struct Validation {
+    problems: Vec<String>,
+    /// What each type name and `Type.field` coordinate was generated for,
+    /// e.g. `table app.users`.
+    claimed: HashMap<String, String>,
+}
+
+impl Validation {
+    pub fn problem(&mut self, problem: impl Into<String>) {
+        self.problems.push(problem.into());
+    }
+
+    /// Records that type `name` is generated for `origin`, reporting a
+    /// collision when something else already was.
+    pub fn claim_type(&mut self, name: &str, origin: &str) {
+        self.claim(format!("type {name}"), origin);
+    }
+
+    /// Records that field `name` of `type_name` is generated for `origin`,
+    /// reporting a collision when something else already was. Returns
+    /// whether the field may be added, as adding a field twice panics.
+    pub fn claim_field(&mut self, type_name: &str, name: &str, origin: &str) -> bool {
+        self.claim(format!("field {type_name}.{name}"), origin)
+    }
+
+    fn claim(&mut self, coordinate: String, origin: &str) -> bool {
+        match self.claimed.entry(coordinate) {
+            Entry::Occupied(entry) if entry.get() != origin => {
+                let problem = format!(
+                    "{} is generated for both {} and {origin}; rename one with @name",
+                    entry.key(),
+                    entry.get()
+                );
+                self.problems.push(problem);
+                false
+            }
+            Entry::Occupied(_) => true,
+            Entry::Vacant(entry) => {
+                entry.insert(origin.to_string());
+                true
+            }
+        }
+    }
+
+    /// Fails with every problem found, one per line.
+    pub fn finish(self) -> Result<(), String> {
+        if self.problems.is_empty() {
+            return Ok(());
+        }
+        let mut message = format!(
+            "the generated GraphQL schema is invalid ({} problem{}):",
+            self.problems.len(),
+            if self.problems.len() == 1 { "" } else { "s" }
+        );
+        for problem in self.problems {
+            message.push_str("\n  - ");
+            message.push_str(&problem);
+        }
+        Err(message)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_reports_every_problem() {
+        let mut validation = Validation::default();
+        validation.claim_type("User", "table app.users");
+        validation.claim_type("User", "table auth.users");
+        validation.claim_field("Query", "allUsers", "table app.users");
+        validation.claim_field("Query", "allUsers", "table app.users");
+        validation.problem("table app.secrets has no readable columns");
+
+        let message = validation.finish().unwrap_err();
+        assert_eq!(
+            message,
+            "the generated GraphQL schema is invalid (2 problems):\n  \
+             - type User is generated for both table app.users and table auth.users; rename one with @name\n  \
+             - table app.secrets has no readable columns"
+        );
+        assert!(Validation::default().finish().is_ok());
+    }
+}
@@ -0,0 +1,50 @@
+//! The `schemaVersion` root field, answering the hash of the SDL the request
+//! runs against. The same hash is sent in the `X-GraphQL-Schema-Hash`
+//! response header, so clients can tell when a rebuild (e.g. in watch mode)
+//! changed the schema and drop what they cached for the old one.
+
+use std::sync::Arc;
+
+use async_graphql::dynamic::{Field, FieldFuture, FieldValue, TypeRef};
+
+/// The response header carrying the schema hash.
+pub(crate) const SCHEMA_HASH_HEADER: &str = "x-graphql-schema-hash";
+
+/// The hash of the schema a request runs against, added to its data.
+#[derive(Clone)]
+pub(crate) struct SchemaHash(pub Arc<str>);
+
+/// `schemaVersion: String!`.
+pub(crate) fn generate_schema_version() -> Field {
+    Field::new("schemaVersion", TypeRef::named_nn(TypeRef::STRING), |ctx| {
+        FieldFuture::new(async move {
+            let hash = ctx.data::<SchemaHash>()?;
+            Ok(Some(FieldValue::value(hash.0.to_string())))
+        })
+    })
+    .description(
+        "Hash of the schema's SDL, also sent as the `X-GraphQL-Schema-Hash` response header. It changes whenever the schema does.",
+    )
+}
+
+/// The 64-bit FNV-1a hash of `sdl` as 16 hex digits. Unlike the std
+/// hashers it is the same across processes and Rust versions, so replicas
+/// built from the same database agree on it.
+pub(crate) fn schema_hash(sdl: &str) -> String {
+    let hash = sdl.bytes().fold(0xcbf2_9ce4_8422_2325u64, |hash, byte| {
+        (hash ^ u64::from(byte)).wrapping_mul(0x0000_0100_0000_01b3)
+    });
+    format!("{hash:016x}")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_schema_hash() {
+        assert_eq!(schema_hash(""), "cbf29ce484222325");
+        assert_eq!(schema_hash("a"), "af63dc4c8601ec8c");
+        assert_ne!(schema_hash("type Query"), schema_hash("type Query "));
+    }
+}
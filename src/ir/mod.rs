@@ -0,0 +1,373 @@
+//! Reads and writes against exposed tables as plain data, independent of
+//! GraphQL. The generated resolvers compile their `condition` and `orderBy`
+//! arguments into these types before planning SQL, and other frontends (a
+//! REST bridge, gRPC, batch jobs) can build [`Operation`]s directly and hand
+//! them to [`TurboGraph::plan`](crate::TurboGraph::plan) or
+//! [`TurboGraph::run`](crate::TurboGraph::run), which apply the same column
+//! exposure, read-only mode and request transaction settings.
+//!
+//! ```rust,ignore
+//! use turbograph::ir::{Filter, FilterOp, Operation, Order, Select};
+//!
+//! let rows = server
+//!     .run(
+//!         Operation::Select(Select {
+//!             table: "tickets".into(),
+//!             filters: vec![Filter::new("status", FilterOp::Eq, "open".into())],
+//!             order_by: vec![Order::desc("created_at")],
+//!             limit: Some(20),
+//!             ..Select::default()
+//!         }),
+//!         TransactionConfig::default(),
+//!     )
+//!     .await?;
+//! ```
+//!
+//! Fields are named as in the GraphQL schema and values are JSON, read the
+//! way the matching GraphQL input would be (e.g. enums as strings). Unlike
+//! GraphQL arguments, nothing has validated an operation up front, so
+//! unknown tables, fields and operators and values of the wrong type fail
+//! planning instead of being skipped.
+
+use serde_json::{Map, Value};
+
+use crate::sql::SqlScalar;
+
+pub(crate) mod plan;
+
+pub(crate) use plan::{Planner, filter_conditions, find_table, order_clause, where_sql};
+
+/// One statement against one table.
+#[derive(Clone, Debug, PartialEq)]
+pub enum Operation {
+    Select(Select),
+    Insert(Insert),
+    Update(Update),
+    Delete(Delete),
+}
+
+impl Operation {
+    /// See [`Select::table`].
+    pub fn table(&self) -> &str {
+        match self {
+            Self::Select(select) => &select.table,
+            Self::Insert(insert) => &insert.table,
+            Self::Update(update) => &update.table,
+            Self::Delete(delete) => &delete.table,
+        }
+    }
+
+    /// The fields the operation returns, [`Select::fields`] or `returning`.
+    pub(crate) fn fields_mut(&mut self) -> &mut Vec<String> {
+        match self {
+            Self::Select(select) => &mut select.fields,
+            Self::Insert(insert) => &mut insert.returning,
+            Self::Update(update) => &mut update.returning,
+            Self::Delete(delete) => &mut delete.returning,
+        }
+    }
+}
+
+/// Rows of `table` matching every filter.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct Select {
+    /// The table's Postgres name, qualified as `schema.name` when several
+    /// exposed schemas have a table of that name.
+    pub table: String,
+    /// Fields to return; empty returns every readable field.
+    pub fields: Vec<String>,
+    pub filters: Vec<Filter>,
+    pub order_by: Vec<Order>,
+    pub limit: Option<i64>,
+    pub offset: Option<i64>,
+}
+
+/// A new row of `table`; fields left out take their column defaults.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct Insert {
+    /// See [`Select::table`].
+    pub table: String,
+    pub values: Map<String, Value>,
+    /// Fields of the inserted row to return; empty returns every readable
+    /// field.
+    pub returning: Vec<String>,
+}
+
+/// Sets `values` on the rows of `table` matching every filter. At least one
+/// filter is required, so an operation cannot touch every row by accident.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct Update {
+    /// See [`Select::table`].
+    pub table: String,
+    pub filters: Vec<Filter>,
+    /// New values by field; `null` clears a column.
+    pub values: Map<String, Value>,
+    /// See [`Insert::returning`].
+    pub returning: Vec<String>,
+}
+
+/// Deletes the rows of `table` matching every filter, of which there must be
+/// at least one.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct Delete {
+    /// See [`Select::table`].
+    pub table: String,
+    pub filters: Vec<Filter>,
+    /// See [`Insert::returning`].
+    pub returning: Vec<String>,
+}
+
+/// `field op value`, the IR form of one operator of a `{T}Condition` field.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Filter {
+    pub field: String,
+    pub op: FilterOp,
+    /// A list for [`FilterOp::In`], a single value otherwise.
+    pub value: Value,
+    /// Compares `lower(column) = lower(value)`; only applies to
+    /// [`FilterOp::Eq`].
+    pub ignore_case: bool,
+}
+
+impl Filter {
+    pub fn new(field: impl Into<String>, op: FilterOp, value: Value) -> Self {
+        Self {
+            field: field.into(),
+            op,
+            value,
+            ignore_case: false,
+        }
+    }
+
+    /// Reads a `{T}Condition` given as JSON, e.g.
+    /// `{"status": {"equal": "open"}, "age": {"greaterThan": 3}}`, into
+    /// filters. Only the shape is checked here; fields and values are checked
+    /// when the operation is planned.
+    pub fn from_condition(condition: Value) -> Result<Vec<Self>, async_graphql::Error> {
+        let Value::Object(fields) = condition else {
+            return Err(crate::error::gql_err("condition must be an object"));
+        };
+        let mut filters = Vec::with_capacity(fields.len());
+        for (field, ops) in fields {
+            let Value::Object(ops) = ops else {
+                return Err(crate::error::gql_err(format!(
+                    "condition on `{field}` must be an object"
+                )));
+            };
+            for (op_key, value) in ops {
+                let op = FilterOp::from_key(&op_key).ok_or_else(|| {
+                    crate::error::gql_err(format!("unknown operator `{op_key}` on `{field}`"))
+                })?;
+                filters.push(Self::new(field.clone(), op, value));
+            }
+        }
+        Ok(filters)
+    }
+}
+
+/// The operators of a `{T}{Column}Filter`.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum FilterOp {
+    Eq,
+    NotEqual,
+    In,
+    Gt,
+    Gte,
+    Lt,
+    Lte,
+    /// `LIKE`, on text columns.
+    Like,
+    /// `ILIKE`, on text columns.
+    ILike,
+}
+
+impl FilterOp {
+    /// The operator named by a `{T}{Column}Filter` field, e.g. `greaterThan`.
+    pub fn from_key(key: &str) -> Option<Self> {
+        match key {
+            "equal" => Some(Self::Eq),
+            "notEqual" => Some(Self::NotEqual),
+            "in" => Some(Self::In),
+            "greaterThan" => Some(Self::Gt),
+            "greaterThanEqual" => Some(Self::Gte),
+            "lessThan" => Some(Self::Lt),
+            "lessThanEqual" => Some(Self::Lte),
+            "like" => Some(Self::Like),
+            "likeInsensitive" => Some(Self::ILike),
+            _ => None,
+        }
+    }
+
+    /// The `{T}{Column}Filter` field naming the operator.
+    pub fn key(self) -> &'static str {
+        match self {
+            Self::Eq => "equal",
+            Self::NotEqual => "notEqual",
+            Self::In => "in",
+            Self::Gt => "greaterThan",
+            Self::Gte => "greaterThanEqual",
+            Self::Lt => "lessThan",
+            Self::Lte => "lessThanEqual",
+            Self::Like => "like",
+            Self::ILike => "likeInsensitive",
+        }
+    }
+
+    pub fn sql_operator(self) -> &'static str {
+        match self {
+            Self::Eq => "=",
+            Self::NotEqual => "<>",
+            Self::Gt => ">",
+            Self::Gte => ">=",
+            Self::Lt => "<",
+            Self::Lte => "<=",
+            Self::Like => "LIKE",
+            Self::ILike => "ILIKE",
+            Self::In => unreachable!("IN is not a simple binary operator"),
+        }
+    }
+
+    /// Whether the operator needs an ordered column type.
+    pub fn is_range(self) -> bool {
+        matches!(self, Self::Gt | Self::Gte | Self::Lt | Self::Lte)
+    }
+
+    /// Whether the operator matches a pattern and needs a text column.
+    pub fn is_pattern(self) -> bool {
+        matches!(self, Self::Like | Self::ILike)
+    }
+}
+
+/// One key of an `ORDER BY`.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Order {
+    pub field: String,
+    pub descending: bool,
+}
+
+impl Order {
+    pub fn asc(field: impl Into<String>) -> Self {
+        Self {
+            field: field.into(),
+            descending: false,
+        }
+    }
+
+    pub fn desc(field: impl Into<String>) -> Self {
+        Self {
+            field: field.into(),
+            descending: true,
+        }
+    }
+}
+
+/// The statement an [`Operation`] compiles to.
+#[derive(Clone, Debug)]
+pub struct Plan {
+    pub(crate) sql: String,
+    pub(crate) params: Vec<SqlScalar>,
+}
+
+impl Plan {
+    /// The statement text, with values as `$n` placeholders.
+    pub fn sql(&self) -> &str {
+        &self.sql
+    }
+
+    /// The values bound to the placeholders, in order.
+    pub fn params(&self) -> &[SqlScalar] {
+        &self.params
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_filter_op_from_key_not_equal() {
+        assert_eq!(FilterOp::from_key("notEqual"), Some(FilterOp::NotEqual));
+    }
+
+    #[test]
+    fn test_filter_op_from_key_range() {
+        assert_eq!(FilterOp::from_key("greaterThanEqual"), Some(FilterOp::Gte));
+        assert_eq!(FilterOp::from_key("lessThan"), Some(FilterOp::Lt));
+    }
+
+    #[test]
+    fn test_filter_op_from_key_default_eq() {
+        assert_eq!(FilterOp::from_key("equal"), Some(FilterOp::Eq));
+    }
+
+    #[test]
+    fn test_filter_op_from_key_unknown() {
+        assert_eq!(FilterOp::from_key("between"), None);
+    }
+
+    #[test]
+    fn test_filter_op_sql_operator() {
+        assert_eq!(FilterOp::Eq.sql_operator(), "=");
+        assert_eq!(FilterOp::NotEqual.sql_operator(), "<>");
+        assert_eq!(FilterOp::Gt.sql_operator(), ">");
+        assert_eq!(FilterOp::Gte.sql_operator(), ">=");
+        assert_eq!(FilterOp::Lt.sql_operator(), "<");
+        assert_eq!(FilterOp::Lte.sql_operator(), "<=");
+        assert_eq!(FilterOp::Like.sql_operator(), "LIKE");
+        assert_eq!(FilterOp::ILike.sql_operator(), "ILIKE");
+    }
+
+    #[test]
+    fn test_filter_op_is_range() {
+        assert!(!FilterOp::Eq.is_range());
+        assert!(!FilterOp::NotEqual.is_range());
+        assert!(!FilterOp::In.is_range());
+        assert!(FilterOp::Gt.is_range());
+        assert!(FilterOp::Gte.is_range());
+        assert!(FilterOp::Lt.is_range());
+        assert!(FilterOp::Lte.is_range());
+    }
+
+    #[test]
+    fn test_filter_op_key_round_trips() {
+        for op in [
+            FilterOp::Eq,
+            FilterOp::NotEqual,
+            FilterOp::In,
+            FilterOp::Gt,
+            FilterOp::Gte,
+            FilterOp::Lt,
+            FilterOp::Lte,
+            FilterOp::Like,
+            FilterOp::ILike,
+        ] {
+            assert_eq!(FilterOp::from_key(op.key()), Some(op));
+        }
+    }
+
+    #[test]
+    fn test_filters_from_condition() {
+        let filters = Filter::from_condition(serde_json::json!({
+            "age": { "greaterThan": 3, "in": [4, 5] }
+        }))
+        .unwrap();
+        assert_eq!(
+            filters,
+            [
+                Filter::new("age", FilterOp::Gt, 3.into()),
+                Filter::new("age", FilterOp::In, serde_json::json!([4, 5])),
+            ]
+        );
+
+        for invalid in [
+            serde_json::json!([]),
+            serde_json::json!({ "name": "a" }),
+            serde_json::json!({ "name": { "between": "a" } }),
+        ] {
+            assert!(
+                Filter::from_condition(invalid.clone()).is_err(),
+                "{invalid}"
+            );
+        }
+    }
+}
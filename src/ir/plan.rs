@@ -0,0 +1,819 @@
+use std::collections::HashMap;
+use std::sync::Arc;
+
+use async_graphql::Value as GqlValue;
+use deadpool_postgres::Pool;
+use serde_json::{Map, Value};
+
+use crate::db::JsonListExt;
+use crate::db::request::RequestScope;
+use crate::db::transaction::with_transaction;
+use crate::error::{coded_err, db_err, gql_err};
+use crate::graphql::mutation::upload::{Uploads, bind_uploads, write_large_objects};
+use crate::graphql::{
+    condition_type_ref, create_input_type_ref, input_type_ref, supports_op, to_sql_scalar,
+};
+use crate::models::encryption::{EncryptedColumn, encrypt};
+use crate::models::grants::Privilege;
+use crate::models::row_transform::{has_transforms, transform_rows};
+use crate::models::table::{AutoTimestamp, Column, Table};
+use crate::sql::{Condition, Sql, SqlScalar, param_refs};
+
+use super::{Delete, Filter, FilterOp, Insert, Operation, Order, Plan, Select, Update};
+
+/// Largest list an `in` filter takes.
+const MAX_IN_ITEMS: usize = 10_000;
+
+/// Plans and runs [`Operation`]s against the tables one schema build
+/// exposes.
+pub(crate) struct Planner {
+    pool: Arc<Pool>,
+    tables: Vec<Arc<Table>>,
+    read_only: bool,
+    /// Mock schemas have no database to run plans against.
+    mock: bool,
+}
+
+impl Planner {
+    pub(crate) fn new(
+        pool: Arc<Pool>,
+        tables: Vec<Arc<Table>>,
+        read_only: bool,
+        mock: bool,
+    ) -> Self {
+        Self {
+            pool,
+            tables,
+            read_only,
+            mock,
+        }
+    }
+
+    /// Compiles `operation` to a single statement. Fails with `NOT_FOUND`
+    /// for unknown tables, `READ_ONLY` for writes the schema does not
+    /// offer, and a plain error for anything else that does not fit the
+    /// table.
+    pub(crate) fn plan(&self, operation: &Operation) -> Result<Plan, async_graphql::Error> {
+        let sql = match operation {
+            Operation::Select(select) => self.plan_select(select)?,
+            Operation::Insert(insert) => self.plan_insert(insert)?,
+            Operation::Update(update) => self.plan_update(update)?,
+            Operation::Delete(delete) => self.plan_delete(delete)?,
+        };
+        let (sql, params) = sql.build();
+        Ok(Plan { sql, params })
+    }
+
+    /// Plans `operation` and runs it in one transaction of `scope`,
+    /// returning the selected or affected rows keyed by field name, after
+    /// the table's row transforms.
+    pub(crate) async fn run(
+        &self,
+        operation: &Operation,
+        scope: RequestScope,
+    ) -> Result<Vec<Value>, async_graphql::Error> {
+        if self.mock {
+            return Err(gql_err("operations cannot run in mock mode"));
+        }
+        let table = find_table(&self.tables, operation.table())?;
+        // Transforms see whole rows, so every readable field is fetched and
+        // the requested ones are picked afterwards.
+        let transformed = has_transforms(&scope, table.schema_name(), table.name());
+        let mut operation = operation.clone();
+        let fields = if transformed {
+            std::mem::take(operation.fields_mut())
+        } else {
+            Vec::new()
+        };
+        let Plan { sql, params } = self.plan(&operation)?;
+        let scalars = scope.custom_scalars.clone();
+        scope.log_statement(&sql);
+        let rows = with_transaction(&self.pool, scope.clone(), |client| {
+            let sql = sql.clone();
+            let params = params.clone();
+            let scalars = scalars.clone();
+            Box::pin(async move {
+                let rows = client
+                    .query(&sql, &param_refs(&params))
+                    .await
+                    .map_err(|e| db_err("DB query error", e))?;
+                Ok(rows.to_json_list(scalars.as_ref()))
+            })
+        })
+        .await?;
+        if !transformed {
+            return Ok(rows);
+        }
+        transform_fields(&scope, table, rows, &fields).await
+    }
+
+    fn plan_select(&self, select: &Select) -> Result<Sql, async_graphql::Error> {
+        let table = find_table(&self.tables, &select.table)?;
+        let mut sql = Sql::raw("SELECT ")
+            .push(output_list(table, &select.fields)?)
+            .push(Sql::raw(" FROM "))
+            .push(Sql::qualified(table.schema_name(), table.name()))
+            .push(where_clause(table, &select.filters)?)
+            .push(order_clause(&select.order_by, table.columns())?);
+        if let Some(limit) = select.limit {
+            sql = sql.push(Sql::raw(" LIMIT ")).push(Sql::value(limit.max(0)));
+        }
+        if let Some(offset) = select.offset {
+            sql = sql
+                .push(Sql::raw(" OFFSET "))
+                .push(Sql::value(offset.max(0)));
+        }
+        Ok(sql)
+    }
+
+    fn plan_insert(&self, insert: &Insert) -> Result<Sql, async_graphql::Error> {
+        let table = self.writable_table(&insert.table, Privilege::Insert)?;
+        let values = insert
+            .values
+            .iter()
+            .map(|(field, value)| {
+                let col = plain_column(table, field, can_insert)?;
+                Ok((col, write_value(col, field, value)?))
+            })
+            .collect::<Result<_, async_graphql::Error>>()?;
+        Ok(insert_sql(table, values)
+            .push(Sql::raw(" RETURNING "))
+            .push(output_list(table, &insert.returning)?))
+    }
+
+    fn plan_update(&self, update: &Update) -> Result<Sql, async_graphql::Error> {
+        let table = self.writable_table(&update.table, Privilege::Update)?;
+        if update.filters.is_empty() {
+            return Err(gql_err("an update needs at least one filter"));
+        }
+        if update.values.is_empty() {
+            return Err(gql_err("an update needs at least one value"));
+        }
+        let values = update
+            .values
+            .iter()
+            .map(|(field, value)| {
+                let col = plain_column(table, field, can_update)?;
+                Ok((col, write_value(col, field, value)?))
+            })
+            .collect::<Result<_, async_graphql::Error>>()?;
+        Ok(
+            update_sql(table, values, where_clause(table, &update.filters)?)
+                .push(Sql::raw(" RETURNING "))
+                .push(output_list(table, &update.returning)?),
+        )
+    }
+
+    fn plan_delete(&self, delete: &Delete) -> Result<Sql, async_graphql::Error> {
+        let table = self.writable_table(&delete.table, Privilege::Delete)?;
+        if delete.filters.is_empty() {
+            return Err(gql_err("a delete needs at least one filter"));
+        }
+        Ok(delete_sql(table, where_clause(table, &delete.filters)?)
+            .push(Sql::raw(" RETURNING "))
+            .push(output_list(table, &delete.returning)?))
+    }
+
+    /// Runs the write a GraphQL mutation compiled to, an insert, update or
+    /// delete, in one transaction of `scope`, and returns the affected rows
+    /// after the table's row transforms. Beyond what [`plan`](Self::plan)
+    /// takes, `@encrypted` values are sealed, `uploads` are bound to their
+    /// columns, and updates and deletes may go without filters, as
+    /// `update{T}s` and `delete{T}s` do without a `condition`. Rows are keyed
+    /// by column name, as the entity resolvers read them: the columns behind
+    /// the `returning` fields, or every column when there are none.
+    pub(crate) async fn mutate(
+        &self,
+        operation: &Operation,
+        uploads: Uploads,
+        scope: RequestScope,
+    ) -> Result<Vec<Value>, async_graphql::Error> {
+        let table = find_table(&self.tables, operation.table())?;
+        // Uploads are bound first, so the n-th is parameter `$n`, as
+        // `write_large_objects` expects.
+        let (bound, large_objects) = bind_uploads(uploads, table.columns()).await?;
+        let mut values: Vec<(&Arc<Column>, Sql)> = bound
+            .into_iter()
+            .map(|(col, value)| (col, Sql::value(value)))
+            .collect();
+        let transformed = has_transforms(&scope, table.schema_name(), table.name());
+        let (sql, returning) = match operation {
+            Operation::Insert(insert) => {
+                let table = self.writable_table(&insert.table, Privilege::Insert)?;
+                values.extend(sealed_values(&scope, table, &insert.values, can_insert).await?);
+                if values.is_empty() {
+                    return Err(gql_err("No valid columns provided for insert"));
+                }
+                (insert_sql(table, values), &insert.returning)
+            }
+            Operation::Update(update) => {
+                let table = self.writable_table(&update.table, Privilege::Update)?;
+                values.extend(sealed_values(&scope, table, &update.values, can_update).await?);
+                if values.is_empty() {
+                    return Err(gql_err("No valid columns provided for update"));
+                }
+                let filters = where_clause(table, &update.filters)?;
+                (update_sql(table, values, filters), &update.returning)
+            }
+            Operation::Delete(delete) => {
+                let table = self.writable_table(&delete.table, Privilege::Delete)?;
+                let filters = where_clause(table, &delete.filters)?;
+                (delete_sql(table, filters), &delete.returning)
+            }
+            Operation::Select(_) => return Err(gql_err("a mutation must write")),
+        };
+        // Transforms see whole rows.
+        let returning = match transformed {
+            true => Sql::raw("*"),
+            false => returning_columns(table, returning)?,
+        };
+        let (sql, params) = sql.push(Sql::raw(" RETURNING ")).push(returning).build();
+
+        let scalars = scope.custom_scalars.clone();
+        scope.log_statement(&sql);
+        let rows = with_transaction(&self.pool, scope.clone(), |client| {
+            let mut params = params.clone();
+            let large_objects = large_objects.clone();
+            let sql = sql.clone();
+            let scalars = scalars.clone();
+            Box::pin(async move {
+                write_large_objects(client, &large_objects, &mut params).await?;
+                let rows = client
+                    .query(&sql, &param_refs(&params))
+                    .await
+                    .map_err(|e| db_err("DB query error", e))?;
+                Ok(rows.to_json_list(scalars.as_ref()))
+            })
+        })
+        .await?;
+        scope.forget_rows();
+        transform_rows(&scope, table.schema_name(), table.name(), rows).await
+    }
+
+    /// The table `name` refers to, when the schema offers writes with
+    /// `privilege` to it.
+    fn writable_table(
+        &self,
+        name: &str,
+        privilege: Privilege,
+    ) -> Result<&Arc<Table>, async_graphql::Error> {
+        let table = find_table(&self.tables, name)?;
+        if self.read_only || !table.is_exposed_for(privilege) {
+            let action = match privilege {
+                Privilege::Insert => "insert into",
+                Privilege::Update => "update",
+                _ => "delete from",
+            };
+            return Err(not_writable(table, action));
+        }
+        Ok(table)
+    }
+}
+
+/// `INSERT INTO … (…) VALUES (…)` setting `values`, and the auto-timestamp
+/// columns they leave out to `now()`; `DEFAULT VALUES` without any.
+fn insert_sql<'a>(table: &'a Table, mut values: Vec<(&'a Arc<Column>, Sql)>) -> Sql {
+    for col in table
+        .columns()
+        .iter()
+        .filter(|c| c.auto_timestamp().is_some())
+    {
+        if !values.iter().any(|(set, _)| set.name() == col.name()) {
+            values.push((col, Sql::raw("now()")));
+        }
+    }
+
+    let target = Sql::qualified(table.schema_name(), table.name());
+    if values.is_empty() {
+        return Sql::raw("INSERT INTO ")
+            .push(target)
+            .push(Sql::raw(" DEFAULT VALUES"));
+    }
+    let (names, values): (Vec<_>, Vec<_>) = values
+        .into_iter()
+        .map(|(col, value)| (Sql::ident(col.name()), value))
+        .unzip();
+    Sql::raw("INSERT INTO ")
+        .push(target)
+        .push(Sql::raw(" ("))
+        .push(Sql::separated(names, ", "))
+        .push(Sql::raw(") VALUES ("))
+        .push(Sql::separated(values, ", "))
+        .push(Sql::raw(")"))
+}
+
+/// `UPDATE … SET …` setting `values`, and the `updated` auto-timestamp
+/// columns to `now()`, on the rows `filters` match.
+fn update_sql(table: &Table, values: Vec<(&Arc<Column>, Sql)>, filters: Sql) -> Sql {
+    let assignments = values
+        .into_iter()
+        .map(|(col, value)| Sql::ident(col.name()).push(Sql::raw(" = ")).push(value))
+        .chain(
+            table
+                .columns()
+                .iter()
+                .filter(|c| c.auto_timestamp() == Some(AutoTimestamp::Updated))
+                .map(|col| Sql::ident(col.name()).push(Sql::raw(" = now()"))),
+        );
+    Sql::raw("UPDATE ")
+        .push(Sql::qualified(table.schema_name(), table.name()))
+        .push(Sql::raw(" SET "))
+        .push(Sql::separated(assignments, ", "))
+        .push(filters)
+}
+
+fn delete_sql(table: &Table, filters: Sql) -> Sql {
+    Sql::raw("DELETE FROM ")
+        .push(Sql::qualified(table.schema_name(), table.name()))
+        .push(filters)
+}
+
+/// The exposed table `name` refers to, see [`Select::table`].
+pub(crate) fn find_table<'a>(
+    tables: &'a [Arc<Table>],
+    name: &str,
+) -> Result<&'a Arc<Table>, async_graphql::Error> {
+    let mut matches = tables.iter().filter(|t| {
+        t.name() == name
+            || name
+                .split_once('.')
+                .is_some_and(|(schema, table)| t.schema_name() == schema && t.name() == table)
+    });
+    match (matches.next(), matches.next()) {
+        (Some(table), None) => Ok(table),
+        (Some(_), Some(_)) => Err(coded_err(
+            "NOT_FOUND",
+            format!("table `{name}` is ambiguous; qualify it with its schema"),
+        )),
+        (None, _) => Err(coded_err("NOT_FOUND", format!("unknown table `{name}`"))),
+    }
+}
+
+fn not_writable(table: &Table, action: &str) -> async_graphql::Error {
+    coded_err("READ_ONLY", format!("cannot {action} `{}`", table.name()))
+}
+
+/// `"column" AS "field"` for each of `fields`, or for every readable
+/// column when `fields` is empty. `@encrypted` columns would only yield
+/// ciphertext and are not readable here.
+/// Runs `rows` of `table`, keyed by field name, through the row transforms,
+/// which take rows keyed by column name, and keeps `fields` of each (every
+/// readable one when empty).
+async fn transform_fields(
+    scope: &RequestScope,
+    table: &Table,
+    rows: Vec<Value>,
+    fields: &[String],
+) -> Result<Vec<Value>, async_graphql::Error> {
+    let readable: Vec<&Arc<Column>> = table
+        .columns()
+        .iter()
+        .filter(|c| !c.omit_read() && !c.encrypted())
+        .collect();
+    let rows = rows
+        .into_iter()
+        .map(|mut row| {
+            readable
+                .iter()
+                .filter_map(|c| Some((c.name().to_string(), row.get_mut(c.field_name())?.take())))
+                .collect::<Map<_, _>>()
+                .into()
+        })
+        .collect();
+    let rows = transform_rows(scope, table.schema_name(), table.name(), rows).await?;
+    Ok(rows
+        .into_iter()
+        .map(|mut row| {
+            readable
+                .iter()
+                .filter(|c| fields.is_empty() || fields.iter().any(|f| f == c.field_name()))
+                .filter_map(|c| Some((c.field_name().to_string(), row.get_mut(c.name())?.take())))
+                .collect::<Map<_, _>>()
+                .into()
+        })
+        .collect())
+}
+
+fn output_list(table: &Table, fields: &[String]) -> Result<Sql, async_graphql::Error> {
+    let readable = |c: &&Arc<Column>| !c.omit_read() && !c.encrypted();
+    let columns: Vec<&Arc<Column>> = if fields.is_empty() {
+        table.columns().iter().filter(readable).collect()
+    } else {
+        fields
+            .iter()
+            .map(|field| {
+                table
+                    .columns()
+                    .iter()
+                    .filter(readable)
+                    .find(|c| c.field_name() == field)
+                    .ok_or_else(|| gql_err(format!("unknown field `{field}`")))
+            })
+            .collect::<Result<_, _>>()?
+    };
+    Ok(Sql::separated(
+        columns.into_iter().map(|c| {
+            Sql::ident(c.name())
+                .push(Sql::raw(" AS "))
+                .push(Sql::ident(c.field_name()))
+        }),
+        ", ",
+    ))
+}
+
+/// Whether `Create{T}Input` has a field for `col`.
+fn can_insert(col: &Column) -> bool {
+    !col.omit_create() && create_input_type_ref(col).is_some()
+}
+
+/// Whether `{T}Patch` has a field for `col` that updates may set.
+fn can_update(col: &Column) -> bool {
+    !col.omit_update() && !col.insert_only() && input_type_ref(col).is_some()
+}
+
+/// The column a write sets through `field`, when `settable` allows it.
+fn settable_column<'a>(
+    table: &'a Table,
+    field: &str,
+    settable: fn(&Column) -> bool,
+) -> Result<&'a Arc<Column>, async_graphql::Error> {
+    table
+        .columns()
+        .iter()
+        .find(|c| c.field_name() == field)
+        .filter(|c| settable(c))
+        .ok_or_else(|| gql_err(format!("field `{field}` cannot be written")))
+}
+
+/// Like [`settable_column`], but only readable columns, and not
+/// `@encrypted` and upload ones: only [`Planner::mutate`] seals and streams
+/// their values.
+fn plain_column<'a>(
+    table: &'a Table,
+    field: &str,
+    settable: fn(&Column) -> bool,
+) -> Result<&'a Arc<Column>, async_graphql::Error> {
+    settable_column(table, field, settable)
+        .ok()
+        .filter(|c| !c.omit_read() && !c.encrypted() && !c.upload())
+        .ok_or_else(|| gql_err(format!("field `{field}` cannot be written")))
+}
+
+/// `values` bound for the columns `settable` allows, `@encrypted` ones
+/// sealed.
+async fn sealed_values<'a>(
+    scope: &RequestScope,
+    table: &'a Table,
+    values: &Map<String, Value>,
+    settable: fn(&Column) -> bool,
+) -> Result<Vec<(&'a Arc<Column>, Sql)>, async_graphql::Error> {
+    let mut sealed = Vec::with_capacity(values.len());
+    for (field, value) in values {
+        let col = settable_column(table, field, settable)?;
+        let value = match scalar(col, field, value.clone()) {
+            Ok(SqlScalar::Text(plaintext)) if col.encrypted() => {
+                let target = EncryptedColumn::new(table.schema_name(), table.name(), col.name());
+                let ciphertext = encrypt(scope.encryption.as_ref(), target, plaintext).await?;
+                Sql::value(SqlScalar::Text(ciphertext))
+            }
+            _ => write_value(col, field, value)?,
+        };
+        sealed.push((col, value));
+    }
+    Ok(sealed)
+}
+
+/// The `RETURNING` list of [`Planner::mutate`]: the columns behind
+/// `fields`, unaliased, or every column when there are none.
+fn returning_columns(table: &Table, fields: &[String]) -> Result<Sql, async_graphql::Error> {
+    if fields.is_empty() {
+        return Ok(Sql::raw("*"));
+    }
+    let columns = fields
+        .iter()
+        .map(|field| {
+            table
+                .columns()
+                .iter()
+                .find(|c| !c.omit_read() && c.field_name() == field)
+                .map(|c| Sql::ident(c.name()))
+                .ok_or_else(|| gql_err(format!("unknown field `{field}`")))
+        })
+        .collect::<Result<Vec<_>, _>>()?;
+    Ok(Sql::separated(columns, ", "))
+}
+
+/// `value` bound for `col`, or `NULL`.
+fn write_value(col: &Column, field: &str, value: &Value) -> Result<Sql, async_graphql::Error> {
+    match value {
+        Value::Null => Ok(Sql::raw("NULL")),
+        value => Ok(Sql::value(scalar(col, field, value.clone())?)),
+    }
+}
+
+/// The filters of `table`, checked against its `{T}Condition` fields.
+fn where_clause(table: &Table, filters: &[Filter]) -> Result<Sql, async_graphql::Error> {
+    let columns = table.columns();
+    let col_by_name: HashMap<String, usize> = columns
+        .iter()
+        .enumerate()
+        .filter(|(_, c)| !c.omit_read() && condition_type_ref(c).is_some())
+        .map(|(i, c)| (c.field_name().to_string(), i))
+        .collect();
+    Ok(where_sql(filter_conditions(
+        filters,
+        columns,
+        &col_by_name,
+    )?))
+}
+
+/// ` WHERE …` joining `conditions` with `AND`, or nothing when there are
+/// none.
+pub(crate) fn where_sql(conditions: Vec<Condition>) -> Sql {
+    if conditions.is_empty() {
+        return Sql::default();
+    }
+    Sql::raw(" WHERE ").push(Sql::separated(
+        conditions.into_iter().map(Condition::into_sql),
+        " AND ",
+    ))
+}
+
+/// One condition per filter, fields looked up in `col_by_name`. Fails on
+/// unknown fields, operators the column's type has no order for, and values
+/// the column cannot take.
+pub(crate) fn filter_conditions(
+    filters: &[Filter],
+    columns: &[Arc<Column>],
+    col_by_name: &HashMap<String, usize>,
+) -> Result<Vec<Condition>, async_graphql::Error> {
+    let mut conditions = Vec::with_capacity(filters.len());
+    for filter in filters {
+        let field = filter.field.as_str();
+        let Some(&col_idx) = col_by_name.get(field) else {
+            return Err(gql_err(format!("unknown condition field `{field}`")));
+        };
+        let col = &columns[col_idx];
+        let column = Sql::ident(col.name());
+
+        let condition = match filter.op {
+            FilterOp::In => {
+                let Value::Array(items) = &filter.value else {
+                    return Err(gql_err(format!("`in` on `{field}` must be a list")));
+                };
+                if items.len() > MAX_IN_ITEMS {
+                    return Err(gql_err("IN filter exceeds maximum of 10,000 items"));
+                }
+                if items.is_empty() {
+                    Condition::any([])
+                } else {
+                    let values = items
+                        .iter()
+                        .map(|item| Ok(Sql::value(scalar(col, field, item.clone())?)))
+                        .collect::<Result<Vec<_>, async_graphql::Error>>()?;
+                    Condition::fragment(
+                        column
+                            .push(Sql::raw(" IN ("))
+                            .push(Sql::separated(values, ", "))
+                            .push(Sql::raw(")")),
+                    )
+                }
+            }
+            op if !supports_op(op, col) => {
+                return Err(gql_err(format!(
+                    "unknown operator `{}` on `{field}`",
+                    op.key()
+                )));
+            }
+            FilterOp::Eq if filter.ignore_case => Condition::fragment(
+                Sql::raw("lower(")
+                    .push(column)
+                    .push(Sql::raw(") = lower("))
+                    .push(Sql::value(scalar(col, field, filter.value.clone())?))
+                    .push(Sql::raw(")")),
+            ),
+            op => Condition::compare(
+                column,
+                op.sql_operator(),
+                scalar(col, field, filter.value.clone())?,
+            ),
+        };
+        conditions.push(condition);
+    }
+    Ok(conditions)
+}
+
+/// ` ORDER BY …` for `orders`, or nothing when there are none.
+pub(crate) fn order_clause(
+    orders: &[Order],
+    columns: &[Arc<Column>],
+) -> Result<Sql, async_graphql::Error> {
+    if orders.is_empty() {
+        return Ok(Sql::default());
+    }
+    let keys = orders
+        .iter()
+        .map(|order| {
+            let col = columns
+                .iter()
+                .find(|c| !c.omit_read() && !c.encrypted() && c.field_name() == order.field)
+                .ok_or_else(|| gql_err(format!("unknown column for ordering: {}", order.field)))?;
+            let direction = if order.descending { " DESC" } else { " ASC" };
+            Ok(Sql::ident(col.name()).push(Sql::raw(direction)))
+        })
+        .collect::<Result<Vec<_>, async_graphql::Error>>()?;
+    Ok(Sql::raw(" ORDER BY ").push(Sql::separated(keys, ", ")))
+}
+
+/// `value` as a parameter for `col`, read as the GraphQL input of the
+/// column's type would be, see [`to_sql_scalar`].
+fn scalar(col: &Column, field: &str, value: Value) -> Result<SqlScalar, async_graphql::Error> {
+    let invalid = || gql_err(format!("invalid value for `{field}`"));
+    let value = GqlValue::from_json(value).map_err(|_| invalid())?;
+    to_sql_scalar(col, &value).ok_or_else(invalid)
+}
+
+#[cfg(test)]
+mod tests {
+    use tokio_postgres::types::Type;
+
+    use crate::sql::audit;
+
+    use super::*;
+
+    fn planner(read_only: bool) -> Planner {
+        let table = Table::new_for_test(
+            "tickets",
+            vec![
+                Column::new_for_test("id", Type::INT4, false, false),
+                Column::new_for_test("title", Type::TEXT, true, false),
+                Column::new_for_test("secret", Type::TEXT, true, true),
+            ],
+        );
+        Planner::new(
+            Arc::new(crate::db::pool::unconnected().unwrap()),
+            vec![Arc::new(table)],
+            read_only,
+            false,
+        )
+    }
+
+    fn select(filters: Vec<Filter>) -> Operation {
+        Operation::Select(Select {
+            table: "tickets".into(),
+            filters,
+            ..Select::default()
+        })
+    }
+
+    #[test]
+    fn test_plan_select() {
+        let plan = planner(false)
+            .plan(&Operation::Select(Select {
+                table: "public.tickets".into(),
+                fields: vec!["title".into()],
+                filters: vec![
+                    Filter::new("id", FilterOp::In, serde_json::json!([1, 2])),
+                    Filter {
+                        ignore_case: true,
+                        ..Filter::new("title", FilterOp::Eq, "Bug".into())
+                    },
+                ],
+                order_by: vec![Order::desc("id")],
+                limit: Some(10),
+                offset: None,
+            }))
+            .unwrap();
+        assert_eq!(
+            plan.sql(),
+            "SELECT \"title\" AS \"title\" FROM \"public\".\"tickets\" \
+             WHERE \"id\" IN ($1, $2) AND lower(\"title\") = lower($3) \
+             ORDER BY \"id\" DESC LIMIT $4"
+        );
+        assert_eq!(plan.params().len(), 4);
+        assert_eq!(audit(plan.sql()), Ok(()));
+    }
+
+    #[test]
+    fn test_plan_select_rejects_hidden_and_unknown_fields() {
+        let planner = planner(false);
+        assert!(
+            planner
+                .plan(&select(Vec::new()))
+                .unwrap()
+                .sql()
+                .starts_with("SELECT \"id\" AS \"id\", \"title\" AS \"title\" FROM")
+        );
+        for filters in [
+            vec![Filter::new("secret", FilterOp::Eq, "a".into())],
+            vec![Filter::new("title", FilterOp::Gt, "a".into())],
+            vec![Filter::new("id", FilterOp::Eq, "one".into())],
+            vec![Filter::new("id", FilterOp::Eq, serde_json::Value::Null)],
+        ] {
+            assert!(
+                planner.plan(&select(filters.clone())).is_err(),
+                "{filters:?}"
+            );
+        }
+        let err = planner
+            .plan(&Operation::Select(Select {
+                table: "users".into(),
+                ..Select::default()
+            }))
+            .unwrap_err();
+        assert_eq!(err.message, "unknown table `users`");
+    }
+
+    #[test]
+    fn test_plan_writes() {
+        let planner = planner(false);
+        let values = |json: serde_json::Value| json.as_object().unwrap().clone();
+
+        let insert = planner
+            .plan(&Operation::Insert(Insert {
+                table: "tickets".into(),
+                values: values(serde_json::json!({ "title": "Bug" })),
+                returning: vec!["id".into()],
+            }))
+            .unwrap();
+        assert_eq!(
+            insert.sql(),
+            "INSERT INTO \"public\".\"tickets\" (\"title\") VALUES ($1) RETURNING \"id\" AS \"id\""
+        );
+
+        let update = planner
+            .plan(&Operation::Update(Update {
+                table: "tickets".into(),
+                filters: vec![Filter::new("id", FilterOp::Eq, 1.into())],
+                values: values(serde_json::json!({ "title": null })),
+                returning: vec!["id".into()],
+            }))
+            .unwrap();
+        assert_eq!(
+            update.sql(),
+            "UPDATE \"public\".\"tickets\" SET \"title\" = NULL WHERE \"id\" = $1 RETURNING \"id\" AS \"id\""
+        );
+
+        let delete = Operation::Delete(Delete {
+            table: "tickets".into(),
+            filters: Vec::new(),
+            returning: Vec::new(),
+        });
+        assert!(planner.plan(&delete).is_err());
+        assert!(
+            planner
+                .plan(&Operation::Insert(Insert {
+                    table: "tickets".into(),
+                    values: values(serde_json::json!({ "secret": "x" })),
+                    returning: Vec::new(),
+                }))
+                .is_err()
+        );
+    }
+
+    #[test]
+    fn test_plan_writes_in_read_only_mode() {
+        let err = planner(true)
+            .plan(&Operation::Delete(Delete {
+                table: "tickets".into(),
+                filters: vec![Filter::new("id", FilterOp::Eq, 1.into())],
+                returning: Vec::new(),
+            }))
+            .unwrap_err();
+        assert_eq!(
+            err.extensions.unwrap().get("code"),
+            Some(&async_graphql::Value::from("READ_ONLY"))
+        );
+    }
+
+    #[test]
+    fn test_filters_reject_what_graphql_would() {
+        let columns = vec![
+            Arc::new(Column::new_for_test("name", Type::TEXT, true, false)),
+            Arc::new(Column::new_for_test("age", Type::INT4, true, false)),
+        ];
+        let col_by_name: HashMap<String, usize> =
+            [("name".to_string(), 0), ("age".to_string(), 1)].into();
+        let parse = |json| {
+            Filter::from_condition(json)
+                .and_then(|filters| filter_conditions(&filters, &columns, &col_by_name))
+        };
+
+        let conditions =
+            parse(serde_json::json!({ "age": { "greaterThan": 3, "in": [4, 5] } })).unwrap();
+        let (sql, params) = where_sql(conditions).build();
+        assert_eq!(sql, " WHERE \"age\" > $1 AND \"age\" IN ($2, $3)");
+        assert_eq!(params.len(), 3);
+
+        for invalid in [
+            serde_json::json!([]),
+            serde_json::json!({ "email": { "equal": "a" } }),
+            serde_json::json!({ "name": "a" }),
+            serde_json::json!({ "name": { "greaterThan": "a" } }),
+            serde_json::json!({ "age": { "equal": "three" } }),
+            serde_json::json!({ "age": { "in": 3 } }),
+        ] {
+            assert!(parse(invalid.clone()).is_err(), "{invalid}");
+        }
+    }
+}
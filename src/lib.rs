@@ -1,13 +1,53 @@
+mod codegen;
 mod db;
+mod endpoints;
 mod error;
+mod export;
 mod graphql;
+pub mod ir;
 mod models;
+mod persisted;
 mod schema;
+#[cfg(feature = "server")]
+pub mod server;
+pub mod sql;
+#[cfg(feature = "testing")]
+pub mod testing;
 mod utils;
 
-pub use models::config::{Config, PoolConfig};
-pub use models::transaction::{TransactionConfig, TransactionSettingsValue};
-pub use schema::TurboGraph;
+pub use codegen::static_catalog_module;
+pub use endpoints::{Endpoint, Endpoints};
+pub use export::{ExportFormat, ExportRequest, ExportStream};
+pub use graphql::scalars::CustomScalar;
+pub use models::catalog::{Catalog, CatalogParseError};
+pub use models::config::{
+    CircuitBreakerConfig, Config, NodeIdStrategy, NonFiniteFloats, PermissionField, PoolConfig,
+    SchemaOptions, UnindexedPatternFilters,
+};
+pub use models::description::{Descriptions, negotiate_locale};
+pub use models::encryption::{CipherResult, EncryptedColumn, Encryption};
+pub use models::federation::Federation;
+pub use models::foreign_key::{FkAction, ForeignKey};
+pub use models::grants::{PUBLIC_ROLE, Privilege, TablePrivileges};
+pub use models::mock::MockData;
+pub use models::operation_matrix::OperationMatrix;
+pub use models::row_transform::{RowContext, RowTransform, RowTransformResult};
+pub use models::smart_tags::SmartTags;
+pub use models::table::{
+    AutoTimestamp, Column, Identity, Relkind, Table, UnsupportedTypes, Updatable,
+};
+pub use models::temporal::Temporal;
+pub use models::transaction::{
+    ExecutionLane, PgSettings, TransactionConfig, TransactionSettingsValue,
+};
+pub use persisted::{PersistedOperations, operation_hash};
+pub use schema::{TurboGraph, introspect};
+#[cfg(feature = "server")]
+pub use server::{HttpServer, serve};
+pub use utils::inflection::{
+    DefaultInflector, Inflector, pluralize, register_acronym, register_plural, singularize,
+    to_camel_case, to_constant_case, to_pascal_case,
+};
 
 /// Convenience wrapper around [`TurboGraph::new`].
 pub async fn build_schema(
@@ -0,0 +1,64 @@
+use std::cmp::Ordering;
+
+use super::smart_tags::SmartTags;
+
+/// Whether a table, column or routine whose comment carries `tags` is part
+/// of API `version`, see [`Config::api_version`](crate::Config::api_version).
+/// `@since 2` includes it from version 2 on and `@until 3` up to, but not
+/// including, version 3; without either it is part of every version.
+pub(crate) fn in_version(tags: &SmartTags, version: &str) -> bool {
+    let since = tags
+        .word("since")
+        .is_none_or(|since| compare(version, since).is_ge());
+    let until = tags
+        .word("until")
+        .is_none_or(|until| compare(version, until).is_lt());
+    since && until
+}
+
+/// Orders versions such as `v1`, `2` and `2.10` by their dot-separated
+/// parts, numerically where both parts are numbers. A leading `v` is
+/// ignored and missing parts count as `0`, so `v2` equals `2.0`.
+fn compare(a: &str, b: &str) -> Ordering {
+    let parts = |version: &str| -> Vec<String> {
+        version
+            .trim_start_matches(['v', 'V'])
+            .split('.')
+            .map(str::to_string)
+            .collect()
+    };
+    let (a, b) = (parts(a), parts(b));
+    (0..a.len().max(b.len()))
+        .map(|i| {
+            let a = a.get(i).map_or("0", String::as_str);
+            let b = b.get(i).map_or("0", String::as_str);
+            match (a.parse::<u64>(), b.parse::<u64>()) {
+                (Ok(a), Ok(b)) => a.cmp(&b),
+                _ => a.cmp(b),
+            }
+        })
+        .find(|ordering| ordering.is_ne())
+        .unwrap_or(Ordering::Equal)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_compare_versions() {
+        assert_eq!(compare("v2", "2.0"), Ordering::Equal);
+        assert_eq!(compare("2.10", "2.9"), Ordering::Greater);
+        assert_eq!(compare("v1", "v2"), Ordering::Less);
+    }
+
+    #[test]
+    fn test_since_and_until_bound_the_versions() {
+        let tags = SmartTags::parse("@since v2 @until v4");
+        assert!(!in_version(&tags, "v1"));
+        assert!(in_version(&tags, "v2"));
+        assert!(in_version(&tags, "3.5"));
+        assert!(!in_version(&tags, "v4"));
+        assert!(in_version(&SmartTags::default(), "v1"));
+    }
+}
@@ -0,0 +1,495 @@
+use std::collections::HashMap;
+use std::sync::Arc;
+
+use serde::{Deserialize, Serialize, Serializer};
+
+use super::foreign_key::{ForeignKey, parse_belongs_to};
+use super::function::Function;
+use super::grants::Privilege;
+use super::procedure::Procedure;
+use super::table::Table;
+use crate::utils::inflection::Inflector;
+
+/// Immutable snapshot of everything introspected from the database.
+///
+/// Tables are keyed by OID and shared behind `Arc`, so the schema generator
+/// and the resolvers it produces can hold on to them across threads without
+/// copying or locking.
+///
+/// A catalog serializes to JSON (`{ "tables": [...], "foreign_keys": [...] }`),
+/// so it can be saved with [`introspect`](crate::introspect) and later fed to
+/// [`TurboGraph::mock`](crate::TurboGraph::mock) or
+/// [`TurboGraph::from_snapshot`](crate::TurboGraph::from_snapshot) without a
+/// database.
+#[derive(Debug, Default, Deserialize)]
+#[serde(from = "CatalogData")]
+pub struct Catalog {
+    tables: HashMap<u32, Arc<Table>>,
+    /// Table OIDs ordered by `(schema, name)` for deterministic schema output.
+    table_order: Vec<u32>,
+    /// Foreign keys whose tables are both in the catalog.
+    foreign_keys: Vec<Arc<ForeignKey>>,
+    /// Foreign keys from a table in the catalog to one outside it.
+    external_foreign_keys: Vec<Arc<ForeignKey>>,
+    /// Stored procedures ordered by `(schema, name)`.
+    procedures: Vec<Arc<Procedure>>,
+    /// Functions ordered by `(schema, name)`.
+    functions: Vec<Arc<Function>>,
+}
+
+/// Returned by [`Catalog::from_json`] for malformed or incompatible input.
+pub type CatalogParseError = serde_json::Error;
+
+/// The serialized form of a [`Catalog`].
+#[derive(Deserialize)]
+struct CatalogData {
+    tables: Vec<Table>,
+    #[serde(default)]
+    foreign_keys: Vec<ForeignKey>,
+    #[serde(default)]
+    procedures: Vec<Procedure>,
+    #[serde(default)]
+    functions: Vec<Function>,
+}
+
+#[derive(Serialize)]
+struct CatalogDataRef<'a> {
+    tables: Vec<&'a Arc<Table>>,
+    foreign_keys: Vec<&'a Arc<ForeignKey>>,
+    procedures: Vec<&'a Arc<Procedure>>,
+    functions: Vec<&'a Arc<Function>>,
+}
+
+impl Catalog {
+    pub(crate) fn new(tables: Vec<Table>) -> Self {
+        let mut tables: Vec<Arc<Table>> = tables.into_iter().map(Arc::new).collect();
+        tables.sort_by(|a, b| (a.schema_name(), a.name()).cmp(&(b.schema_name(), b.name())));
+
+        let table_order = tables.iter().map(|t| *t.oid()).collect();
+        let tables = tables.into_iter().map(|t| (*t.oid(), t)).collect();
+
+        Self {
+            tables,
+            table_order,
+            foreign_keys: Vec::new(),
+            external_foreign_keys: Vec::new(),
+            procedures: Vec::new(),
+            functions: Vec::new(),
+        }
+    }
+
+    /// Adds the stored procedures exposed as mutations.
+    pub(crate) fn with_procedures(mut self, procedures: Vec<Procedure>) -> Self {
+        let mut procedures: Vec<Arc<Procedure>> = procedures.into_iter().map(Arc::new).collect();
+        procedures.sort_by(|a, b| (a.schema_name(), a.name()).cmp(&(b.schema_name(), b.name())));
+        self.procedures = procedures;
+        self
+    }
+
+    /// Adds the functions exposed as query fields, mutations and computed
+    /// columns.
+    pub(crate) fn with_functions(mut self, functions: Vec<Function>) -> Self {
+        let mut functions: Vec<Arc<Function>> = functions.into_iter().map(Arc::new).collect();
+        functions.sort_by(|a, b| (a.schema_name(), a.name()).cmp(&(b.schema_name(), b.name())));
+        self.functions = functions;
+        self
+    }
+
+    /// Adds the foreign keys between tables of this catalog. Constraints that
+    /// reference a table outside it (e.g. in a schema that is not exposed)
+    /// are kept apart as [`external_foreign_keys`](Self::external_foreign_keys).
+    /// Relations declared with `@belongsTo` tags are added as synthetic
+    /// foreign keys.
+    pub(crate) fn with_foreign_keys(mut self, foreign_keys: Vec<ForeignKey>) -> Self {
+        let (mut foreign_keys, mut external): (Vec<_>, Vec<_>) = foreign_keys
+            .into_iter()
+            .chain(self.belongs_to_relations())
+            .filter(|fk| self.tables.contains_key(&fk.table_oid()))
+            .map(Arc::new)
+            .partition(|fk| self.tables.contains_key(&fk.foreign_table_oid()));
+        foreign_keys.sort_by(|a, b| a.name().cmp(b.name()));
+        external.sort_by(|a, b| a.name().cmp(b.name()));
+        self.foreign_keys = foreign_keys;
+        self.external_foreign_keys = external;
+        self
+    }
+
+    /// Resolves every `@belongsTo` and `@foreignKey` tag against the
+    /// catalog. Tags naming an unknown table, columns that do not exist, or
+    /// referenced columns that are not the parent's primary key or a unique
+    /// key are skipped with a warning.
+    fn belongs_to_relations(&self) -> Vec<ForeignKey> {
+        let mut relations = Vec::new();
+        for table in self.tables() {
+            for tag in parse_belongs_to(table.tags()) {
+                let schema = tag.schema.as_deref().unwrap_or(table.schema_name());
+                let parent = self
+                    .tables()
+                    .find(|t| t.schema_name() == schema && t.name() == tag.table);
+                let columns_exist = tag
+                    .columns
+                    .iter()
+                    .all(|c| table.columns().iter().any(|col| col.name() == c));
+                let foreign_columns = parent
+                    .and_then(|parent| referenced_key(parent, &tag.foreign_columns))
+                    .filter(|key| key.len() == tag.columns.len());
+
+                match (parent, foreign_columns) {
+                    (Some(parent), Some(foreign_columns)) if columns_exist => {
+                        relations.push(ForeignKey::belongs_to(
+                            table,
+                            tag.columns,
+                            parent,
+                            foreign_columns,
+                        ));
+                    }
+                    _ => eprintln!(
+                        "[turbograph] ignoring relation tag {schema}.{}({}) on {}.{}",
+                        tag.table,
+                        tag.columns.join(", "),
+                        table.schema_name(),
+                        table.name()
+                    ),
+                }
+            }
+        }
+        relations
+    }
+
+    /// A copy of the catalog with every `@omit` tag cleared, from which the
+    /// admin schema is built.
+    pub(crate) fn without_omit(&self) -> Self {
+        let tables = self
+            .tables
+            .iter()
+            .map(|(oid, table)| {
+                let mut table = Table::clone(table);
+                table.clear_omit();
+                (*oid, Arc::new(table))
+            })
+            .collect();
+        Self {
+            tables,
+            table_order: self.table_order.clone(),
+            foreign_keys: self.foreign_keys.clone(),
+            external_foreign_keys: self.external_foreign_keys.clone(),
+            procedures: self
+                .procedures
+                .iter()
+                .map(|procedure| {
+                    let mut procedure = Procedure::clone(procedure);
+                    procedure.clear_omit();
+                    Arc::new(procedure)
+                })
+                .collect(),
+            functions: self
+                .functions
+                .iter()
+                .map(|function| {
+                    let mut function = Function::clone(function);
+                    function.clear_omit();
+                    Arc::new(function)
+                })
+                .collect(),
+        }
+    }
+
+    /// A copy of the catalog with every table, column, function and
+    /// procedure that `@since` and `@until` tags leave out of API `version`
+    /// omitted.
+    pub(crate) fn for_api_version(&self, version: &str) -> Self {
+        let tables = self
+            .tables
+            .iter()
+            .map(|(oid, table)| {
+                let mut table = Table::clone(table);
+                table.restrict_to_version(version);
+                (*oid, Arc::new(table))
+            })
+            .collect();
+        Self {
+            tables,
+            table_order: self.table_order.clone(),
+            foreign_keys: self.foreign_keys.clone(),
+            external_foreign_keys: self.external_foreign_keys.clone(),
+            procedures: self
+                .procedures
+                .iter()
+                .map(|procedure| {
+                    let mut procedure = Procedure::clone(procedure);
+                    procedure.restrict_to_version(version);
+                    Arc::new(procedure)
+                })
+                .collect(),
+            functions: self
+                .functions
+                .iter()
+                .map(|function| {
+                    let mut function = Function::clone(function);
+                    function.restrict_to_version(version);
+                    Arc::new(function)
+                })
+                .collect(),
+        }
+    }
+
+    /// The catalog with every table's type named by `inflector`.
+    /// Leaves out, with a warning, the tables whose columns are all omitted
+    /// from reads, as their type would have no fields. With `node_ids` set,
+    /// those with a primary key keep a type with just their global ID,
+    /// reachable through `node` and relations, but lose their mutations.
+    pub(crate) fn without_empty_types(mut self, node_ids: bool) -> Self {
+        for table in self.tables.values_mut() {
+            if !table.is_exposed_for(Privilege::Select) || table.has_readable_columns() {
+                continue;
+            }
+            let coordinate = format!("{}.{}", table.schema_name(), table.name());
+            if node_ids && !table.primary_key().is_empty() {
+                eprintln!(
+                    "[turbograph] every column of {coordinate} is omitted; exposing only its global ID"
+                );
+                Arc::make_mut(table).omit_mutations();
+            } else {
+                eprintln!("[turbograph] every column of {coordinate} is omitted; leaving it out");
+                Arc::make_mut(table).omit_all();
+            }
+        }
+        self
+    }
+
+    pub(crate) fn inflected(mut self, inflector: &dyn Inflector) -> Self {
+        for table in self.tables.values_mut() {
+            let name = inflector.type_name(table);
+            Arc::make_mut(table).set_inflected_type_name(name);
+        }
+        self
+    }
+
+    /// Loads a catalog saved with `serde_json`, e.g. one embedded by
+    /// [`static_catalog_module`](crate::static_catalog_module).
+    pub fn from_json(json: &str) -> Result<Self, CatalogParseError> {
+        serde_json::from_str(json)
+    }
+
+    /// The catalog as pretty-printed JSON, for [`from_json`](Self::from_json).
+    /// The output is deterministic for a given database, so snapshots can be
+    /// checked in and diffed.
+    pub fn to_json(&self) -> Result<String, serde_json::Error> {
+        serde_json::to_string_pretty(self)
+    }
+
+    /// The table with the given OID.
+    pub fn table(&self, oid: u32) -> Option<&Arc<Table>> {
+        self.tables.get(&oid)
+    }
+
+    /// All tables in `(schema, name)` order.
+    pub fn tables(&self) -> impl Iterator<Item = &Arc<Table>> {
+        self.table_order
+            .iter()
+            .filter_map(|oid| self.tables.get(oid))
+    }
+
+    /// All foreign keys, ordered by constraint name.
+    pub fn foreign_keys(&self) -> impl Iterator<Item = &Arc<ForeignKey>> {
+        self.foreign_keys.iter()
+    }
+
+    /// Foreign keys from a table of the catalog to a table outside it, e.g.
+    /// in a schema that is not exposed, ordered by constraint name. They are
+    /// not turned into relations.
+    pub fn external_foreign_keys(&self) -> impl Iterator<Item = &Arc<ForeignKey>> {
+        self.external_foreign_keys.iter()
+    }
+
+    /// The foreign key constraint from `columns` of table `table_oid` to
+    /// `foreign_columns` of table `foreign_table_oid`, if one is declared.
+    pub fn constraint_between(
+        &self,
+        table_oid: u32,
+        columns: &[String],
+        foreign_table_oid: u32,
+        foreign_columns: &[String],
+    ) -> Option<&Arc<ForeignKey>> {
+        self.foreign_keys.iter().find(|fk| {
+            !fk.is_synthetic()
+                && fk.table_oid() == table_oid
+                && fk.columns() == columns
+                && fk.foreign_table_oid() == foreign_table_oid
+                && fk.foreign_columns() == foreign_columns
+        })
+    }
+
+    /// All stored procedures in `(schema, name)` order.
+    pub fn procedures(&self) -> impl Iterator<Item = &Arc<Procedure>> {
+        self.procedures.iter()
+    }
+
+    /// All functions in `(schema, name)` order.
+    pub fn functions(&self) -> impl Iterator<Item = &Arc<Function>> {
+        self.functions.iter()
+    }
+}
+
+/// The columns of `parent` a relation tag references: `columns` when they
+/// make up its primary key or a unique key, or the primary key when the tag
+/// names none.
+fn referenced_key(parent: &Table, columns: &[String]) -> Option<Vec<String>> {
+    if columns.is_empty() {
+        return (!parent.primary_key().is_empty()).then(|| parent.primary_key().to_vec());
+    }
+    let mut wanted = columns.to_vec();
+    wanted.sort();
+    let is_key = |key: &[String]| {
+        let mut key = key.to_vec();
+        key.sort();
+        key == wanted
+    };
+    let unique = parent
+        .unique_keys()
+        .iter()
+        .any(|key| !key.is_partial() && is_key(key.columns()));
+    (unique || is_key(parent.primary_key())).then(|| columns.to_vec())
+}
+
+impl From<CatalogData> for Catalog {
+    fn from(data: CatalogData) -> Self {
+        Self::new(data.tables)
+            .with_foreign_keys(data.foreign_keys)
+            .with_procedures(data.procedures)
+            .with_functions(data.functions)
+    }
+}
+
+impl Serialize for Catalog {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        CatalogDataRef {
+            tables: self.tables().collect(),
+            // Synthetic relations are derived from table comments again on load.
+            foreign_keys: self
+                .foreign_keys()
+                .filter(|fk| !fk.is_synthetic())
+                .chain(self.external_foreign_keys())
+                .collect(),
+            procedures: self.procedures().collect(),
+            functions: self.functions().collect(),
+        }
+        .serialize(serializer)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::table::Column;
+    use tokio_postgres::types::Type;
+
+    fn assert_send_sync<T: Send + Sync>() {}
+
+    #[test]
+    fn test_catalog_is_send_and_sync() {
+        assert_send_sync::<Catalog>();
+        assert_send_sync::<Arc<Catalog>>();
+    }
+
+    #[test]
+    fn test_belongs_to_tag_adds_synthetic_relation() {
+        let user_id = Column::new_for_test("user_id", Type::INT4, false, false);
+        let catalog = Catalog::new(vec![
+            Table::new_for_test("users", vec![])
+                .with_oid(1)
+                .with_primary_key(&["id"]),
+            Table::new_for_test("user_stats", vec![user_id])
+                .with_oid(2)
+                .with_comment("@belongsTo users(user_id)"),
+            Table::new_for_test("orphans", vec![])
+                .with_oid(3)
+                .with_comment("@belongsTo missing(user_id)"),
+        ])
+        .with_foreign_keys(vec![]);
+
+        let fks: Vec<_> = catalog.foreign_keys().collect();
+        assert_eq!(fks.len(), 1);
+        assert!(fks[0].is_synthetic());
+        assert_eq!((fks[0].table_oid(), fks[0].foreign_table_oid()), (2, 1));
+        assert_eq!(fks[0].columns(), ["user_id"]);
+        assert_eq!(fks[0].foreign_columns(), ["id"]);
+
+        let json = serde_json::to_value(&catalog).unwrap();
+        assert_eq!(json["foreign_keys"], serde_json::json!([]));
+    }
+
+    #[test]
+    fn test_foreign_key_tag_references_unique_key() {
+        let col = |name: &str| Column::new_for_test(name, Type::TEXT, false, false);
+        let catalog = Catalog::new(vec![
+            Table::new_for_test("users", vec![col("id"), col("login")])
+                .with_oid(1)
+                .with_primary_key(&["id"])
+                .with_unique_key("users_login_key", &["login"], false),
+            Table::new_for_test("activity", vec![col("login"), col("user_id")])
+                .with_oid(2)
+                .with_comment(
+                    "@foreignKey (login) references users(login)\n\
+                     @foreignKey (user_id) references users(id)\n\
+                     @foreignKey (user_id) references users(user_id)",
+                ),
+        ])
+        .with_foreign_keys(vec![]);
+
+        let fks: Vec<_> = catalog.foreign_keys().collect();
+        assert_eq!(fks.len(), 2);
+        assert!(fks.iter().all(|fk| fk.is_synthetic()));
+        assert_eq!(fks[0].foreign_columns(), ["login"]);
+        assert_eq!(fks[1].foreign_columns(), ["id"]);
+    }
+
+    #[test]
+    fn test_catalog_tables_sorted_by_name() {
+        let catalog = Catalog::new(vec![
+            Table::new_for_test("users", vec![]).with_oid(1),
+            Table::new_for_test("posts", vec![]).with_oid(2),
+        ]);
+        let names: Vec<&str> = catalog.tables().map(|t| t.name()).collect();
+        assert_eq!(names, ["posts", "users"]);
+    }
+
+    #[test]
+    fn test_catalog_json_round_trip() {
+        let catalog = Catalog::new(vec![
+            Table::new_for_test(
+                "users",
+                vec![Column::new_for_test("id", Type::INT4, false, false)],
+            )
+            .with_oid(1),
+        ]);
+        let json = serde_json::to_string(&catalog).unwrap();
+        let restored: Catalog = serde_json::from_str(&json).unwrap();
+
+        let table = restored.tables().next().unwrap();
+        assert_eq!(table.name(), "users");
+        assert_eq!(table.columns()[0]._type(), &Type::INT4);
+    }
+
+    #[test]
+    fn test_foreign_keys_to_unknown_tables_are_kept_apart() {
+        let catalog = Catalog::new(vec![
+            Table::new_for_test("users", vec![]).with_oid(1),
+            Table::new_for_test("posts", vec![]).with_oid(2),
+        ])
+        .with_foreign_keys(vec![
+            ForeignKey::new_for_test(2, &["author_id"], 1, ""),
+            ForeignKey::new_for_test(2, &["category_id"], 99, ""),
+        ]);
+        let fks: Vec<_> = catalog.foreign_keys().collect();
+        assert_eq!(fks.len(), 1);
+        assert_eq!(fks[0].columns(), ["author_id"]);
+
+        let external: Vec<_> = catalog.external_foreign_keys().collect();
+        assert_eq!(external.len(), 1);
+        assert_eq!(external[0].columns(), ["category_id"]);
+        let json = serde_json::to_string(&catalog).unwrap();
+        let restored: Catalog = serde_json::from_str(&json).unwrap();
+        assert_eq!(restored.external_foreign_keys().count(), 1);
+    }
+}
@@ -1,3 +1,16 @@
+use std::sync::Arc;
+use std::time::Duration;
+
+use async_graphql::extensions::ExtensionFactory;
+
+use crate::graphql::scalars::CustomScalar;
+use crate::models::encryption::Encryption;
+use crate::models::operation_matrix::OperationMatrix;
+use crate::models::row_transform::RowTransform;
+use crate::models::table::{Table, UnsupportedTypes};
+use crate::utils::glob::glob_match;
+use crate::utils::inflection::{DefaultInflector, Inflector};
+
 /// How the library should obtain a database connection.
 pub enum PoolConfig {
     /// A `postgres://` (or `postgresql://`) connection string.
@@ -16,4 +29,596 @@ pub struct Config {
     /// When `true`, the library installs PostgreSQL event triggers and spawns
     /// a background listener that rebuilds the schema on DDL changes.
     pub watch_pg: bool,
+    /// When `false` (recommended), tables and views that belong to an
+    /// extension (e.g. PostGIS's `spatial_ref_sys`) are skipped even if they
+    /// live in one of the exposed `schemas`.
+    pub include_extension_resources: bool,
+    /// Glob patterns (`*`, `?`) of tables to expose. When non-empty, only
+    /// matching tables are introspected. A pattern containing a `.` is matched
+    /// against `schema.table`, otherwise against the bare table name.
+    pub include_tables: Vec<String>,
+    /// Glob patterns of tables to skip, matched like `include_tables`.
+    /// Applied after `include_tables`.
+    pub ignore_tables: Vec<String>,
+    /// Enables global object identification (a Relay `Node` interface, a
+    /// global ID on every table with a primary key, and a `node` query) and
+    /// picks how the global ID is named. `None` disables it.
+    pub node_id: Option<NodeIdStrategy>,
+    /// `async_graphql` extensions (Apollo tracing, logging, custom ones)
+    /// attached to the built schema, and to every schema rebuilt in watch mode.
+    pub extensions: Vec<Arc<dyn ExtensionFactory>>,
+    /// Rejects operations whose complexity (async-graphql's count of selected
+    /// fields) exceeds this before they reach the database, and reports the
+    /// cost of every successful one in `extensions.cost`. `None` disables it.
+    pub max_complexity: Option<usize>,
+    /// Locale (e.g. `"fr"` or `"fr-CA"`) whose `@description(<locale>)` tags
+    /// become the type and field descriptions, falling back to the language
+    /// and then to plain `@description` tags. `None` uses the plain tags.
+    pub locale: Option<String>,
+    /// Roles (matched against [`TransactionConfig::role`](crate::TransactionConfig::role))
+    /// allowed to query admin fields such as `poolStatus`. Empty leaves those
+    /// fields out of the schema.
+    pub admin_roles: Vec<String>,
+    /// Dedicated pool for requests in the
+    /// [`ExecutionLane::Background`](crate::ExecutionLane::Background) lane,
+    /// typically smaller than `pool`. `None` runs them on `pool`.
+    pub background_pool: Option<PoolConfig>,
+    /// Also builds an admin variant of the schema that ignores every `@omit`
+    /// tag, for internal tools. Requests whose role is one of `admin_roles`
+    /// run against it; [`TurboGraph::execute_admin`](crate::TurboGraph::execute_admin)
+    /// serves it on a separate endpoint.
+    pub admin_schema: bool,
+    /// Installs a change-event trigger on every table tagged `@subscribe`
+    /// and exposes a `{t}Changed` subscription for each. Installing the
+    /// triggers modifies the database, so it only happens when this is set.
+    /// Requires [`PoolConfig::ConnectionString`].
+    pub subscriptions: bool,
+    /// Encrypts the values of `@encrypted` columns before mutations write
+    /// them and decrypts them when they are read. Without it, reading or
+    /// writing such a column fails.
+    pub encryption: Option<Encryption>,
+    /// Exposes columns and procedure arguments under camelCase field names
+    /// (`created_at` becomes `createdAt`). When `false` they keep their
+    /// database names. Characters that are not valid in GraphQL names are
+    /// replaced with `_` either way.
+    pub camel_case_fields: bool,
+    /// Keeps the columns a relation field is generated from, e.g. the
+    /// `author_id` behind an `author` `@ref`, as scalar fields of the type.
+    /// When `false` they are left out of the type, but can still be used in
+    /// conditions and mutation inputs. Defaults to `true` in
+    /// [`SchemaOptions`].
+    pub expose_fk_columns: bool,
+    /// Makes `condition` equality on text columns ignore case, comparing
+    /// `lower(column) = lower($n)`. Columns tagged `@caseSensitive` opt out;
+    /// with this off, columns tagged `@caseInsensitive` opt in.
+    pub case_insensitive_conditions: bool,
+    /// Keeps the columns of foreign keys into tables outside the catalog,
+    /// e.g. in a schema that is not exposed, as plain scalar fields. When
+    /// `false` they are left out of the type like [`expose_fk_columns`](Self::expose_fk_columns)
+    /// does, since no relation can lead there. Defaults to `true` in
+    /// [`SchemaOptions`].
+    pub expose_external_fk_columns: bool,
+    /// Generates no mutations, leaving out `create` / `update` / `delete`
+    /// fields and procedures that may modify the database, and rejects every
+    /// mutation operation with a `READ_ONLY` error before it reaches the
+    /// schema. For replicas and analytics databases.
+    pub read_only: bool,
+    /// Roles (matched against [`TransactionConfig::role`](crate::TransactionConfig::role))
+    /// allowed to call the `refresh{T}` mutations of materialized views
+    /// tagged `@refreshable`. Empty leaves those mutations out of the schema.
+    pub refresh_roles: Vec<String>,
+    /// Roles (matched against [`TransactionConfig::role`](crate::TransactionConfig::role))
+    /// allowed to download tables through [`TurboGraph::export`](crate::TurboGraph::export).
+    /// Empty disables exports.
+    pub export_roles: Vec<String>,
+    /// Accepts file uploads following the GraphQL multipart request spec:
+    /// `bytea` columns, and `oid` columns tagged `@largeObject`, take an
+    /// `Upload` in `Create{T}Input` and `{T}Patch`.
+    pub uploads: bool,
+    /// Largest upload, in bytes, a mutation accepts; larger files fail with
+    /// `UPLOAD_TOO_LARGE`. `None` accepts any size.
+    pub max_upload_bytes: Option<u64>,
+    /// Boolean fields computed for every row of a table, such as `canEdit`
+    /// on `Post`, so clients can show or hide actions without repeating the
+    /// policy behind them.
+    pub permission_fields: Vec<PermissionField>,
+    /// What happens to columns whose type has no GraphQL mapping, unless
+    /// the column's `@unsupported` tag says otherwise. Defaults to
+    /// [`UnsupportedTypes::String`].
+    pub unsupported_types: UnsupportedTypes,
+    /// What `Float` fields return for `NaN`, `Infinity` and `-Infinity`,
+    /// which JSON has no number for. Defaults to [`NonFiniteFloats::Null`].
+    pub non_finite_floats: NonFiniteFloats,
+    /// Row estimate above which a connection queried without `first` or
+    /// `last` adds a hint to `extensions.warnings` that only the default
+    /// page of 100 rows was returned. Estimates come from the table
+    /// statistics (`pg_class.reltuples`) read when the schema was built.
+    /// `None` disables the hints.
+    pub large_table_rows: Option<u64>,
+    /// Row estimate above which a table's `{T}OrderBy` only offers columns
+    /// that lead a btree index, plus those tagged `@sortable`, so clients
+    /// cannot make a large table sort in full. Estimates come from the
+    /// table statistics, as for [`large_table_rows`](Self::large_table_rows).
+    /// `None` offers every column.
+    pub indexed_order_by_rows: Option<u64>,
+    /// What becomes of a text column's `like` and `likeInsensitive` filters
+    /// when no index serves them, so that every match scans the table.
+    /// Defaults to [`UnindexedPatternFilters::Keep`].
+    pub unindexed_pattern_filters: UnindexedPatternFilters,
+    /// Role for requests whose [`PgSettings`](crate::PgSettings) and
+    /// [`TransactionConfig`](crate::TransactionConfig) name none, typically
+    /// an unprivileged `anonymous` role, so that unauthenticated requests
+    /// never run as the pool's user. `None` leaves them on it.
+    pub default_role: Option<String>,
+    /// Rewrites the rows of a table after every fetch, before their fields
+    /// resolve, e.g. to redact values or sign storage URLs. Transformed
+    /// tables are always read in full.
+    pub row_transforms: Vec<RowTransform>,
+    /// The reviewed root fields and roles allowed to run them, see
+    /// [`OperationMatrix`](crate::OperationMatrix). Requests selecting a
+    /// root field the matrix does not allow for their role, or an operation
+    /// name it does not list once it lists any, are rejected with a
+    /// `FORBIDDEN` error. `None` checks nothing.
+    pub operation_matrix: Option<OperationMatrix>,
+    /// The API version this schema serves, e.g. `"v1"`. Tables, columns,
+    /// functions and procedures tagged `@since v2` are left out of versions
+    /// before `v2`, and those tagged `@until v2` out of `v2` and later, so
+    /// that [`Endpoints`](crate::Endpoints) can serve `/v1/graphql` and
+    /// `/v2/graphql` from one database. `None` ignores the tags.
+    pub api_version: Option<String>,
+    /// Names the generated types, fields and enum items, in place of
+    /// [`DefaultInflector`](crate::DefaultInflector). `@name` tags still
+    /// take precedence.
+    pub inflector: Option<Arc<dyn Inflector>>,
+    /// Stops sending requests to a database that keeps failing to connect,
+    /// see [`CircuitBreakerConfig`]. `None` always tries the database.
+    pub circuit_breaker: Option<CircuitBreakerConfig>,
+    /// Scalars for Postgres types without a GraphQL mapping, e.g. from an
+    /// extension, by type OID. Columns of such a type, and arrays of it,
+    /// are exposed as the scalar, their values decoded with its
+    /// [`FromSql`](tokio_postgres::types::FromSql) type instead of answered
+    /// as text, and `null` when they do not decode. They are read-only, like
+    /// other types without an input mapping, and types that already map to a
+    /// scalar keep it. Extension types have a different OID in each
+    /// database; look it up with `SELECT 'ltree'::regtype::oid`.
+    pub custom_scalars: Vec<(u32, CustomScalar)>,
+}
+
+impl Config {
+    /// A config exposing `schemas` of the database behind `pool`, with every
+    /// optional feature off and the defaults of [`SchemaOptions`]. Set other
+    /// fields with struct update syntax:
+    ///
+    /// ```rust,ignore
+    /// let config = Config {
+    ///     watch_pg: true,
+    ///     ..Config::new(PoolConfig::ConnectionString(url), vec!["public".into()])
+    /// };
+    /// ```
+    pub fn new(pool: PoolConfig, schemas: Vec<String>) -> Self {
+        Self {
+            pool,
+            schemas,
+            watch_pg: false,
+            include_extension_resources: false,
+            include_tables: Vec::new(),
+            ignore_tables: Vec::new(),
+            node_id: None,
+            extensions: Vec::new(),
+            max_complexity: None,
+            locale: None,
+            admin_roles: Vec::new(),
+            background_pool: None,
+            admin_schema: false,
+            subscriptions: false,
+            encryption: None,
+            camel_case_fields: false,
+            expose_fk_columns: true,
+            case_insensitive_conditions: false,
+            expose_external_fk_columns: true,
+            read_only: false,
+            refresh_roles: Vec::new(),
+            export_roles: Vec::new(),
+            uploads: false,
+            max_upload_bytes: None,
+            permission_fields: Vec::new(),
+            unsupported_types: UnsupportedTypes::String,
+            non_finite_floats: NonFiniteFloats::Null,
+            large_table_rows: None,
+            indexed_order_by_rows: None,
+            unindexed_pattern_filters: UnindexedPatternFilters::Keep,
+            default_role: None,
+            row_transforms: Vec::new(),
+            operation_matrix: None,
+            api_version: None,
+            inflector: None,
+            circuit_breaker: None,
+            custom_scalars: Vec::new(),
+        }
+    }
+}
+
+/// When to stop trying an unreachable database, see
+/// [`Config::circuit_breaker`]. After `failure_threshold` consecutive
+/// failures to connect, requests fail at once with a `SERVICE_UNAVAILABLE`
+/// error carrying a `retryAfter` extension, and a `Retry-After` header over
+/// HTTP, instead of waiting on the pool. A background probe tries the
+/// database every `probe_interval` and lets requests through again once it
+/// answers.
+#[derive(Clone, Debug, PartialEq)]
+pub struct CircuitBreakerConfig {
+    /// Consecutive connection failures that open the breaker.
+    pub failure_threshold: u32,
+    /// How often the probe tries the database; also the `Retry-After` hint.
+    pub probe_interval: Duration,
+}
+
+impl Default for CircuitBreakerConfig {
+    fn default() -> Self {
+        Self {
+            failure_threshold: 5,
+            probe_interval: Duration::from_secs(5),
+        }
+    }
+}
+
+/// A boolean field on a table's type, see [`Config::permission_fields`].
+///
+/// ```rust,ignore
+/// PermissionField {
+///     table: "posts".into(),
+///     field: "canEdit".into(),
+///     expression: "app.user_can_edit(posts, app.current_user_id())".into(),
+/// }
+/// ```
+#[derive(Clone, Debug, PartialEq)]
+pub struct PermissionField {
+    /// The table's Postgres name, qualified as `schema.name` when several
+    /// exposed schemas have a table of that name.
+    pub table: String,
+    /// The GraphQL field name.
+    pub field: String,
+    /// A SQL expression evaluated for the row, which it refers to by the
+    /// table's name; `NULL` reads as `false`. Like generated SQL it may not
+    /// contain literals, so constants belong in the functions it calls.
+    pub expression: String,
+}
+
+impl PermissionField {
+    /// Whether the field belongs on `table`.
+    pub(crate) fn applies_to(&self, table: &Table) -> bool {
+        match self.table.split_once('.') {
+            Some((schema, name)) => table.schema_name() == schema && table.name() == name,
+            None => table.name() == self.table,
+        }
+    }
+}
+
+/// How the global object ID is exposed when node identification is enabled.
+#[derive(Clone, Debug, PartialEq)]
+pub enum NodeIdStrategy {
+    /// Expose the global ID under its own name, e.g. `"nodeId"`, leaving an
+    /// `id` column as is. Tables with a column of that name get no global ID.
+    Field(String),
+    /// Expose the global ID as `id`, as Relay expects, and rename an `id`
+    /// column to the given name, e.g. `"rowId"`.
+    RenameIdColumn(String),
+}
+
+impl NodeIdStrategy {
+    /// The name of the global ID field and of the `node` query argument.
+    pub fn field_name(&self) -> &str {
+        match self {
+            Self::Field(name) => name,
+            Self::RenameIdColumn(_) => "id",
+        }
+    }
+}
+
+/// How a `real` or `double precision` value that is `NaN`, `Infinity` or
+/// `-Infinity` is returned, see [`Config::non_finite_floats`].
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum NonFiniteFloats {
+    /// Returns `null`, as if the column held no value.
+    #[default]
+    Null,
+    /// Returns the value's text, `"NaN"`, `"Infinity"` or `"-Infinity"`, in
+    /// place of a number.
+    String,
+    /// Fails the field with a `NON_FINITE_FLOAT` error.
+    Error,
+}
+
+/// What becomes of a `like` or `likeInsensitive` filter that no index
+/// serves, see [`Config::unindexed_pattern_filters`]. A trigram index
+/// (`gin_trgm_ops` or `gist_trgm_ops`) serves both; a btree index with a
+/// `text_pattern_ops` class led by the column serves `like` with a fixed
+/// prefix.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum UnindexedPatternFilters {
+    /// Offers it like any other filter.
+    #[default]
+    Keep,
+    /// Offers it with a description warning that it may be slow.
+    Describe,
+    /// Leaves it out of the filter input.
+    Omit,
+}
+
+/// Options that shape the generated schema, independent of where the catalog
+/// came from. Derived from [`Config`]; passed directly to
+/// [`TurboGraph::mock`](crate::TurboGraph::mock).
+#[derive(Clone)]
+pub struct SchemaOptions {
+    /// See [`Config::node_id`].
+    pub node_id: Option<NodeIdStrategy>,
+    /// See [`Config::extensions`].
+    pub extensions: Vec<Arc<dyn ExtensionFactory>>,
+    /// See [`Config::max_complexity`].
+    pub max_complexity: Option<usize>,
+    /// See [`Config::locale`].
+    pub locale: Option<String>,
+    /// See [`Config::admin_roles`].
+    pub admin_roles: Vec<String>,
+    /// See [`Config::admin_schema`].
+    pub admin_schema: bool,
+    /// See [`Config::subscriptions`].
+    pub subscriptions: bool,
+    /// See [`Config::encryption`].
+    pub encryption: Option<Encryption>,
+    /// See [`Config::camel_case_fields`].
+    pub camel_case_fields: bool,
+    /// See [`Config::expose_fk_columns`].
+    pub expose_fk_columns: bool,
+    /// See [`Config::case_insensitive_conditions`].
+    pub case_insensitive_conditions: bool,
+    /// See [`Config::expose_external_fk_columns`].
+    pub expose_external_fk_columns: bool,
+    /// See [`Config::read_only`].
+    pub read_only: bool,
+    /// See [`Config::refresh_roles`].
+    pub refresh_roles: Vec<String>,
+    /// See [`Config::export_roles`].
+    pub export_roles: Vec<String>,
+    /// See [`Config::uploads`].
+    pub uploads: bool,
+    /// See [`Config::max_upload_bytes`].
+    pub max_upload_bytes: Option<u64>,
+    /// See [`Config::permission_fields`].
+    pub permission_fields: Vec<PermissionField>,
+    /// See [`Config::unsupported_types`].
+    pub unsupported_types: UnsupportedTypes,
+    /// See [`Config::non_finite_floats`].
+    pub non_finite_floats: NonFiniteFloats,
+    /// See [`Config::large_table_rows`].
+    pub large_table_rows: Option<u64>,
+    /// See [`Config::indexed_order_by_rows`].
+    pub indexed_order_by_rows: Option<u64>,
+    /// See [`Config::unindexed_pattern_filters`].
+    pub unindexed_pattern_filters: UnindexedPatternFilters,
+    /// See [`Config::default_role`].
+    pub default_role: Option<String>,
+    /// See [`Config::row_transforms`].
+    pub row_transforms: Vec<RowTransform>,
+    /// See [`Config::operation_matrix`].
+    pub operation_matrix: Option<OperationMatrix>,
+    /// See [`Config::api_version`].
+    pub api_version: Option<String>,
+    /// See [`Config::inflector`].
+    pub inflector: Option<Arc<dyn Inflector>>,
+    /// See [`Config::circuit_breaker`].
+    pub circuit_breaker: Option<CircuitBreakerConfig>,
+    /// See [`Config::custom_scalars`].
+    pub custom_scalars: Vec<(u32, CustomScalar)>,
+}
+
+impl SchemaOptions {
+    /// The configured inflector, or [`DefaultInflector`].
+    pub(crate) fn inflector(&self) -> &dyn Inflector {
+        self.inflector.as_deref().unwrap_or(&DefaultInflector)
+    }
+}
+
+impl Default for SchemaOptions {
+    fn default() -> Self {
+        Self {
+            node_id: None,
+            extensions: Vec::new(),
+            max_complexity: None,
+            locale: None,
+            admin_roles: Vec::new(),
+            admin_schema: false,
+            subscriptions: false,
+            encryption: None,
+            camel_case_fields: false,
+            expose_fk_columns: true,
+            case_insensitive_conditions: false,
+            expose_external_fk_columns: true,
+            read_only: false,
+            refresh_roles: Vec::new(),
+            export_roles: Vec::new(),
+            uploads: false,
+            max_upload_bytes: None,
+            permission_fields: Vec::new(),
+            unsupported_types: UnsupportedTypes::String,
+            non_finite_floats: NonFiniteFloats::Null,
+            large_table_rows: None,
+            indexed_order_by_rows: None,
+            unindexed_pattern_filters: UnindexedPatternFilters::Keep,
+            default_role: None,
+            row_transforms: Vec::new(),
+            operation_matrix: None,
+            api_version: None,
+            inflector: None,
+            circuit_breaker: None,
+            custom_scalars: Vec::new(),
+        }
+    }
+}
+
+impl SchemaOptions {
+    pub fn from_config(config: &Config) -> Self {
+        Self {
+            node_id: config.node_id.clone(),
+            extensions: config.extensions.clone(),
+            max_complexity: config.max_complexity,
+            locale: config.locale.clone(),
+            admin_roles: config.admin_roles.clone(),
+            admin_schema: config.admin_schema,
+            subscriptions: config.subscriptions,
+            encryption: config.encryption.clone(),
+            camel_case_fields: config.camel_case_fields,
+            expose_fk_columns: config.expose_fk_columns,
+            case_insensitive_conditions: config.case_insensitive_conditions,
+            expose_external_fk_columns: config.expose_external_fk_columns,
+            read_only: config.read_only,
+            refresh_roles: config.refresh_roles.clone(),
+            export_roles: config.export_roles.clone(),
+            uploads: config.uploads,
+            max_upload_bytes: config.max_upload_bytes,
+            permission_fields: config.permission_fields.clone(),
+            unsupported_types: config.unsupported_types,
+            non_finite_floats: config.non_finite_floats,
+            large_table_rows: config.large_table_rows,
+            indexed_order_by_rows: config.indexed_order_by_rows,
+            unindexed_pattern_filters: config.unindexed_pattern_filters,
+            default_role: config.default_role.clone(),
+            row_transforms: config.row_transforms.clone(),
+            operation_matrix: config.operation_matrix.clone(),
+            api_version: config.api_version.clone(),
+            inflector: config.inflector.clone(),
+            circuit_breaker: config.circuit_breaker.clone(),
+            custom_scalars: config.custom_scalars.clone(),
+        }
+    }
+}
+
+impl std::fmt::Debug for SchemaOptions {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("SchemaOptions")
+            .field("node_id", &self.node_id)
+            .field("extensions", &self.extensions.len())
+            .field("max_complexity", &self.max_complexity)
+            .field("locale", &self.locale)
+            .field("admin_roles", &self.admin_roles)
+            .field("admin_schema", &self.admin_schema)
+            .field("subscriptions", &self.subscriptions)
+            .field("encryption", &self.encryption.is_some())
+            .field("camel_case_fields", &self.camel_case_fields)
+            .field("expose_fk_columns", &self.expose_fk_columns)
+            .field(
+                "case_insensitive_conditions",
+                &self.case_insensitive_conditions,
+            )
+            .field(
+                "expose_external_fk_columns",
+                &self.expose_external_fk_columns,
+            )
+            .field("read_only", &self.read_only)
+            .field("refresh_roles", &self.refresh_roles)
+            .field("export_roles", &self.export_roles)
+            .field("uploads", &self.uploads)
+            .field("max_upload_bytes", &self.max_upload_bytes)
+            .field("permission_fields", &self.permission_fields)
+            .field("unsupported_types", &self.unsupported_types)
+            .field("non_finite_floats", &self.non_finite_floats)
+            .field("large_table_rows", &self.large_table_rows)
+            .field("indexed_order_by_rows", &self.indexed_order_by_rows)
+            .field("unindexed_pattern_filters", &self.unindexed_pattern_filters)
+            .field("default_role", &self.default_role)
+            .field("row_transforms", &self.row_transforms)
+            .field("operation_matrix", &self.operation_matrix.is_some())
+            .field("api_version", &self.api_version)
+            .field("inflector", &self.inflector.is_some())
+            .field("circuit_breaker", &self.circuit_breaker)
+            .field("custom_scalars", &self.custom_scalars)
+            .finish()
+    }
+}
+
+/// Lets a shared extension factory be registered with every schema build.
+pub(crate) struct SharedExtension(pub Arc<dyn ExtensionFactory>);
+
+impl ExtensionFactory for SharedExtension {
+    fn create(&self) -> Arc<dyn async_graphql::extensions::Extension> {
+        self.0.create()
+    }
+}
+
+/// The subset of [`Config`] that decides what gets introspected. Kept
+/// separately because it outlives the pool configuration and is shared with
+/// the watch task for rebuilds.
+#[derive(Clone, Debug)]
+pub(crate) struct IntrospectionConfig {
+    pub schemas: Vec<String>,
+    pub include_extension_resources: bool,
+    pub include_tables: Vec<String>,
+    pub ignore_tables: Vec<String>,
+}
+
+impl IntrospectionConfig {
+    pub fn from_config(config: &Config) -> Self {
+        Self {
+            schemas: config.schemas.clone(),
+            include_extension_resources: config.include_extension_resources,
+            include_tables: config.include_tables.clone(),
+            ignore_tables: config.ignore_tables.clone(),
+        }
+    }
+
+    /// Whether `schema.table` passes the `include_tables` / `ignore_tables`
+    /// filters.
+    pub fn exposes_table(&self, schema: &str, table: &str) -> bool {
+        let qualified = format!("{schema}.{table}");
+        let matches = |pattern: &String| {
+            if pattern.contains('.') {
+                glob_match(pattern, &qualified)
+            } else {
+                glob_match(pattern, table)
+            }
+        };
+
+        (self.include_tables.is_empty() || self.include_tables.iter().any(matches))
+            && !self.ignore_tables.iter().any(matches)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn config(include: &[&str], ignore: &[&str]) -> IntrospectionConfig {
+        IntrospectionConfig {
+            schemas: vec!["public".into()],
+            include_extension_resources: false,
+            include_tables: include.iter().map(|s| s.to_string()).collect(),
+            ignore_tables: ignore.iter().map(|s| s.to_string()).collect(),
+        }
+    }
+
+    #[test]
+    fn test_exposes_everything_without_patterns() {
+        assert!(config(&[], &[]).exposes_table("public", "users"));
+    }
+
+    #[test]
+    fn test_include_tables_restricts() {
+        let cfg = config(&["user*"], &[]);
+        assert!(cfg.exposes_table("public", "users"));
+        assert!(!cfg.exposes_table("public", "posts"));
+    }
+
+    #[test]
+    fn test_ignore_tables_wins_over_include() {
+        let cfg = config(&["*"], &["*_archive"]);
+        assert!(cfg.exposes_table("public", "posts"));
+        assert!(!cfg.exposes_table("public", "posts_archive"));
+    }
+
+    #[test]
+    fn test_qualified_pattern_matches_schema() {
+        let cfg = config(&[], &["audit.*"]);
+        assert!(!cfg.exposes_table("audit", "events"));
+        assert!(cfg.exposes_table("public", "events"));
+    }
 }
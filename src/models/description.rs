@@ -0,0 +1,120 @@
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+
+use crate::models::smart_tags::SmartTags;
+
+/// Descriptions from `@description` tags on a table or column comment, by
+/// locale:
+/// - `@description Registered users` is the default description.
+/// - `@description(fr) Utilisateurs inscrits` applies to `fr` and its
+///   regional variants (`fr-CA`) unless those have their own tag.
+///
+/// The locale is the parenthesized suffix of the tag name, and the text is
+/// the tag's [`SmartTags`] value.
+#[derive(Serialize, Deserialize, Clone, Debug, Default, PartialEq)]
+pub struct Descriptions(BTreeMap<String, String>);
+
+impl Descriptions {
+    pub(crate) fn new(tags: &SmartTags) -> Self {
+        Self(
+            tags.get_all("description")
+                .map(|value| {
+                    let localized = value.strip_prefix('(').and_then(|rest| {
+                        let (locale, text) = rest.split_once(')')?;
+                        let valid = !locale.is_empty()
+                            && locale
+                                .chars()
+                                .all(|c| c.is_ascii_alphanumeric() || c == '-');
+                        valid.then_some((locale, text))
+                    });
+                    let (locale, text) = localized.unwrap_or(("", value));
+                    (locale.to_lowercase(), text.trim().to_string())
+                })
+                .filter(|(_, text)| !text.is_empty())
+                .collect(),
+        )
+    }
+
+    /// The description for `locale` (e.g. `fr-CA`), falling back to its
+    /// language (`fr`) and then to the default description.
+    pub fn get(&self, locale: Option<&str>) -> Option<&str> {
+        let locale = locale.map(str::to_lowercase).unwrap_or_default();
+        let language = locale.split('-').next().unwrap_or_default();
+        [locale.as_str(), language, ""]
+            .into_iter()
+            .find_map(|key| self.0.get(key))
+            .map(String::as_str)
+    }
+}
+
+/// Picks the best of `locales` for an `Accept-Language` header value such as
+/// `fr-CA,fr;q=0.9,en;q=0.8`: the highest-weighted language that is offered
+/// exactly or by its primary subtag. `None` when nothing matches.
+pub fn negotiate_locale<'a>(accept_language: &str, locales: &'a [String]) -> Option<&'a str> {
+    let mut ranges: Vec<(&str, f32)> = accept_language
+        .split(',')
+        .filter_map(|range| {
+            let mut parts = range.split(';');
+            let tag = parts.next()?.trim();
+            let quality = parts
+                .find_map(|p| p.trim().strip_prefix("q="))
+                .map_or(Some(1.0), |q| q.parse().ok())?;
+            (!tag.is_empty() && quality > 0.0).then_some((tag, quality))
+        })
+        .collect();
+    ranges.sort_by(|a, b| b.1.total_cmp(&a.1));
+
+    ranges.into_iter().find_map(|(tag, _)| {
+        let language = tag.split('-').next().unwrap_or(tag);
+        locales
+            .iter()
+            .find(|l| l.eq_ignore_ascii_case(tag))
+            .or_else(|| locales.iter().find(|l| l.eq_ignore_ascii_case(language)))
+            .map(String::as_str)
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_localized_descriptions() {
+        let descriptions = Descriptions::new(&SmartTags::parse(
+            "@description Registered users\n@description(fr) Utilisateurs inscrits @omit delete",
+        ));
+        assert_eq!(descriptions.get(None), Some("Registered users"));
+        assert_eq!(descriptions.get(Some("fr")), Some("Utilisateurs inscrits"));
+        assert_eq!(
+            descriptions.get(Some("fr-CA")),
+            Some("Utilisateurs inscrits")
+        );
+        assert_eq!(descriptions.get(Some("de")), Some("Registered users"));
+    }
+
+    #[test]
+    fn test_no_default_description() {
+        let descriptions = Descriptions::new(&SmartTags::parse("@description(de) Benutzer"));
+        assert_eq!(descriptions.get(None), None);
+        assert_eq!(descriptions.get(Some("de-AT")), Some("Benutzer"));
+    }
+
+    #[test]
+    fn test_description_tag_rules() {
+        let descriptions = Descriptions::new(&SmartTags::parse(
+            "Mail docs@description.org @descriptions x",
+        ));
+        assert_eq!(descriptions, Descriptions::default());
+    }
+
+    #[test]
+    fn test_negotiate_locale() {
+        let locales = vec!["de".to_string(), "fr".to_string()];
+        assert_eq!(
+            negotiate_locale("fr-CA,fr;q=0.9,en;q=0.8", &locales),
+            Some("fr")
+        );
+        assert_eq!(negotiate_locale("en;q=0.9, de;q=0.5", &locales), Some("de"));
+        assert_eq!(negotiate_locale("en, fr;q=0", &locales), None);
+    }
+}
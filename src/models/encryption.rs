@@ -0,0 +1,94 @@
+use std::future::Future;
+use std::pin::Pin;
+use std::sync::Arc;
+
+use crate::error::gql_err;
+
+/// Result of an encrypt or decrypt callback.
+pub type CipherResult = Result<String, Box<dyn std::error::Error + Send + Sync>>;
+
+type CipherFn = dyn Fn(EncryptedColumn, String) -> Pin<Box<dyn Future<Output = CipherResult> + Send>>
+    + Send
+    + Sync;
+
+/// The `@encrypted` column a value belongs to, e.g. to pick a key.
+#[derive(Debug, Clone, PartialEq)]
+pub struct EncryptedColumn {
+    pub schema: String,
+    pub table: String,
+    pub column: String,
+}
+
+impl EncryptedColumn {
+    pub(crate) fn new(schema: &str, table: &str, column: &str) -> Self {
+        Self {
+            schema: schema.to_string(),
+            table: table.to_string(),
+            column: column.to_string(),
+        }
+    }
+}
+
+/// Transforms the values of `@encrypted` columns: `encrypt` runs on every
+/// value a mutation writes, `decrypt` on every value read back.
+///
+/// ```rust,ignore
+/// let encryption = Encryption::new(
+///     move |column, plaintext| async move { kms.encrypt(&column.table, plaintext).await },
+///     move |column, ciphertext| async move { kms.decrypt(&column.table, ciphertext).await },
+/// );
+/// ```
+#[derive(Clone)]
+pub struct Encryption {
+    encrypt: Arc<CipherFn>,
+    decrypt: Arc<CipherFn>,
+}
+
+impl Encryption {
+    pub fn new<E, EF, D, DF>(encrypt: E, decrypt: D) -> Self
+    where
+        E: Fn(EncryptedColumn, String) -> EF + Send + Sync + 'static,
+        EF: Future<Output = CipherResult> + Send + 'static,
+        D: Fn(EncryptedColumn, String) -> DF + Send + Sync + 'static,
+        DF: Future<Output = CipherResult> + Send + 'static,
+    {
+        Self {
+            encrypt: Arc::new(move |column, value| Box::pin(encrypt(column, value))),
+            decrypt: Arc::new(move |column, value| Box::pin(decrypt(column, value))),
+        }
+    }
+}
+
+/// Encrypts `plaintext` for `column`. Fails closed when no [`Encryption`] is
+/// configured, so a tagged column is never written in the clear.
+pub(crate) async fn encrypt(
+    encryption: Option<&Encryption>,
+    column: EncryptedColumn,
+    plaintext: String,
+) -> Result<String, async_graphql::Error> {
+    let name = column.column.clone();
+    let encryption = encryption.ok_or_else(|| missing(&name))?;
+    (encryption.encrypt)(column, plaintext)
+        .await
+        .map_err(|e| gql_err(format!("Failed to encrypt `{name}`: {e}")))
+}
+
+/// Decrypts `ciphertext` read from `column`. Fails when no [`Encryption`] is
+/// configured.
+pub(crate) async fn decrypt(
+    encryption: Option<&Encryption>,
+    column: EncryptedColumn,
+    ciphertext: String,
+) -> Result<String, async_graphql::Error> {
+    let name = column.column.clone();
+    let encryption = encryption.ok_or_else(|| missing(&name))?;
+    (encryption.decrypt)(column, ciphertext)
+        .await
+        .map_err(|e| gql_err(format!("Failed to decrypt `{name}`: {e}")))
+}
+
+fn missing(column: &str) -> async_graphql::Error {
+    gql_err(format!(
+        "Column `{column}` is @encrypted but no encryption is configured"
+    ))
+}
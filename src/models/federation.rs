@@ -0,0 +1,76 @@
+use serde::{Deserialize, Serialize};
+
+use crate::models::smart_tags::SmartTags;
+
+/// Apollo Federation directives requested through smart tags on a table or
+/// column comment, emitted onto the generated type or field:
+/// - `@shareable`, `@inaccessible` on tables and columns.
+/// - `@external` and `@override <subgraph>` on columns.
+/// - `@tag <name>` on either, repeatable.
+///
+/// Entity keys (`@key`) are not supported: they need an entity resolver.
+#[derive(Serialize, Deserialize, Clone, Debug, Default, PartialEq)]
+pub struct Federation {
+    pub shareable: bool,
+    pub inaccessible: bool,
+    pub external: bool,
+    pub override_from: Option<String>,
+    pub tags: Vec<String>,
+}
+
+impl Federation {
+    pub(crate) fn new(tags: &SmartTags) -> Self {
+        let mut federation = Federation::default();
+        for (name, value) in tags.iter() {
+            let arg = value.split_whitespace().next().map(str::to_string);
+            match name {
+                "shareable" => federation.shareable = true,
+                "inaccessible" => federation.inaccessible = true,
+                "external" => federation.external = true,
+                "override" => federation.override_from = arg,
+                "tag" => federation.tags.extend(arg),
+                _ => {}
+            }
+        }
+        federation
+    }
+
+    /// Whether any directive was requested.
+    pub fn is_empty(&self) -> bool {
+        *self == Self::default()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_no_tags() {
+        assert!(Federation::new(&SmartTags::parse("Registered users. @omit delete")).is_empty());
+    }
+
+    #[test]
+    fn test_flags_and_arguments() {
+        let federation = Federation::new(&SmartTags::parse(
+            "Price. @shareable @override inventory @tag public @tag internal-api",
+        ));
+        assert_eq!(
+            federation,
+            Federation {
+                shareable: true,
+                inaccessible: false,
+                external: false,
+                override_from: Some("inventory".into()),
+                tags: vec!["public".into(), "internal-api".into()],
+            }
+        );
+    }
+
+    #[test]
+    fn test_external_and_inaccessible() {
+        let federation = Federation::new(&SmartTags::parse("@external\n@inaccessible"));
+        assert!(federation.external && federation.inaccessible);
+        assert!(!federation.shareable);
+    }
+}
@@ -0,0 +1,375 @@
+use serde::{Deserialize, Serialize};
+use std::sync::LazyLock;
+
+use super::smart_tags::SmartTags;
+use super::table::{Omit, Table};
+use crate::utils::inflection::{backward_relation_name, forward_relation_name};
+
+/// A foreign key constraint between two introspected tables.
+///
+/// The constraint comment may carry smart tags that rename the relation
+/// fields generated for it:
+/// - `@fieldName author` renames the forward field on the referencing table
+///   (default `userByAuthorId`).
+/// - `@foreignFieldName writtenPosts` renames the backward field on the
+///   referenced table (default `postsByAuthorId`).
+/// - `@omit` stops the constraint from being treated as a relation.
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct ForeignKey {
+    oid: u32,
+    name: String,
+    table_oid: u32,
+    columns: Vec<String>,
+    foreign_table_oid: u32,
+    foreign_columns: Vec<String>,
+    #[serde(default)]
+    comment: String,
+    #[serde(default)]
+    on_delete: FkAction,
+    #[serde(default)]
+    on_update: FkAction,
+    /// Declared by a `@belongsTo` or `@foreignKey` tag rather than a
+    /// constraint.
+    #[serde(skip)]
+    synthetic: bool,
+}
+
+/// What happens to referencing rows when the referenced row is deleted or
+/// its key updated (`pg_constraint.confdeltype` / `confupdtype`).
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, Default, PartialEq)]
+#[serde(rename_all = "snake_case")]
+pub enum FkAction {
+    /// Fail at the end of the statement; the default.
+    #[default]
+    NoAction,
+    /// Fail immediately, even when deferred.
+    Restrict,
+    /// Delete or update the referencing rows along.
+    Cascade,
+    /// Set the referencing columns to `NULL`.
+    SetNull,
+    /// Set the referencing columns to their defaults.
+    SetDefault,
+}
+
+impl FkAction {
+    fn from_confaction(action: &str) -> Self {
+        match action {
+            "r" => Self::Restrict,
+            "c" => Self::Cascade,
+            "n" => Self::SetNull,
+            "d" => Self::SetDefault,
+            _ => Self::NoAction,
+        }
+    }
+
+    /// The action as written in SQL, e.g. `SET NULL`.
+    pub fn as_sql(self) -> &'static str {
+        match self {
+            Self::NoAction => "NO ACTION",
+            Self::Restrict => "RESTRICT",
+            Self::Cascade => "CASCADE",
+            Self::SetNull => "SET NULL",
+            Self::SetDefault => "SET DEFAULT",
+        }
+    }
+}
+
+/// A relation declared on a table or materialized view comment, without a
+/// constraint behind it:
+/// - `@belongsTo users(user_id)`: the tagged relation's `user_id` column
+///   references the primary key of `users`.
+/// - `@foreignKey (user_id) references users(id)`: the same, naming the
+///   referenced columns, which must be the primary key or a unique key of
+///   `users`. Without them the primary key is meant.
+///
+/// The parent may be schema-qualified and defaults to the tagged relation's
+/// schema.
+#[derive(Clone, Debug, PartialEq)]
+pub(crate) struct BelongsTo {
+    pub schema: Option<String>,
+    pub table: String,
+    pub columns: Vec<String>,
+    /// The referenced columns; empty for the parent's primary key.
+    pub foreign_columns: Vec<String>,
+}
+
+pub(crate) fn parse_belongs_to(tags: &SmartTags) -> Vec<BelongsTo> {
+    static BELONGS_TO_REGEX: LazyLock<regex::Regex> =
+        LazyLock::new(|| regex::Regex::new(r"^(?:(\w+)\.)?(\w+)\s*\(([^)]*)\)").unwrap());
+    static FOREIGN_KEY_REGEX: LazyLock<regex::Regex> = LazyLock::new(|| {
+        regex::Regex::new(r"(?i)^\(([^)]*)\)\s*references\s+(?:(\w+)\.)?(\w+)\s*(?:\(([^)]*)\))?")
+            .unwrap()
+    });
+
+    let columns = |list: &str| -> Vec<String> {
+        list.split(',')
+            .map(|c| c.trim().to_string())
+            .filter(|c| !c.is_empty())
+            .collect()
+    };
+
+    let belongs_to = tags.get_all("belongsTo").filter_map(|value| {
+        let caps = BELONGS_TO_REGEX.captures(value)?;
+        Some(BelongsTo {
+            schema: caps.get(1).map(|m| m.as_str().to_string()),
+            table: caps[2].to_string(),
+            columns: columns(&caps[3]),
+            foreign_columns: Vec::new(),
+        })
+    });
+    let foreign_keys = tags.get_all("foreignKey").filter_map(|value| {
+        let caps = FOREIGN_KEY_REGEX.captures(value)?;
+        Some(BelongsTo {
+            schema: caps.get(2).map(|m| m.as_str().to_string()),
+            table: caps[3].to_string(),
+            columns: columns(&caps[1]),
+            foreign_columns: caps.get(4).map_or_else(Vec::new, |m| columns(m.as_str())),
+        })
+    });
+    belongs_to.chain(foreign_keys).collect()
+}
+
+impl ForeignKey {
+    pub(crate) fn from_row(row: &tokio_postgres::Row) -> Self {
+        Self {
+            oid: row.get(0),
+            name: row.get(1),
+            table_oid: row.get(2),
+            columns: row.get(3),
+            foreign_table_oid: row.get(4),
+            foreign_columns: row.get(5),
+            comment: row.try_get::<_, String>(6).unwrap_or_default(),
+            on_delete: FkAction::from_confaction(&row.get::<_, String>(7)),
+            on_update: FkAction::from_confaction(&row.get::<_, String>(8)),
+            synthetic: false,
+        }
+    }
+
+    /// The relation declared by a `@belongsTo` or `@foreignKey` tag on
+    /// `table`, referencing `foreign_columns` of `parent`.
+    pub(crate) fn belongs_to(
+        table: &Table,
+        columns: Vec<String>,
+        parent: &Table,
+        foreign_columns: Vec<String>,
+    ) -> Self {
+        Self {
+            oid: 0,
+            name: format!("{}_belongs_to_{}", table.name(), parent.name()),
+            table_oid: *table.oid(),
+            columns,
+            foreign_table_oid: *parent.oid(),
+            foreign_columns,
+            comment: String::new(),
+            on_delete: FkAction::default(),
+            on_update: FkAction::default(),
+            synthetic: true,
+        }
+    }
+
+    /// Whether this relation comes from a `@belongsTo` or `@foreignKey` tag
+    /// instead of a foreign key constraint.
+    pub fn is_synthetic(&self) -> bool {
+        self.synthetic
+    }
+
+    pub fn oid(&self) -> u32 {
+        self.oid
+    }
+
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    /// OID of the referencing table.
+    pub fn table_oid(&self) -> u32 {
+        self.table_oid
+    }
+
+    /// Referencing columns, in constraint order.
+    pub fn columns(&self) -> &[String] {
+        &self.columns
+    }
+
+    /// OID of the referenced table.
+    pub fn foreign_table_oid(&self) -> u32 {
+        self.foreign_table_oid
+    }
+
+    /// Referenced columns, in constraint order.
+    pub fn foreign_columns(&self) -> &[String] {
+        &self.foreign_columns
+    }
+
+    pub fn comment(&self) -> &str {
+        &self.comment
+    }
+
+    /// The `ON DELETE` action of the constraint.
+    pub fn on_delete(&self) -> FkAction {
+        self.on_delete
+    }
+
+    /// The `ON UPDATE` action of the constraint.
+    pub fn on_update(&self) -> FkAction {
+        self.on_update
+    }
+
+    /// Documents the referential actions for relation field descriptions,
+    /// e.g. `` Foreign key `posts_author_id_fkey`: ON DELETE CASCADE, ON UPDATE NO ACTION. ``
+    pub(crate) fn describe_actions(&self) -> String {
+        format!(
+            "Foreign key `{}`: ON DELETE {}, ON UPDATE {}.",
+            self.name,
+            self.on_delete.as_sql(),
+            self.on_update.as_sql()
+        )
+    }
+
+    /// Whether an `@omit` tag on the constraint hides the relation.
+    pub fn omit(&self) -> bool {
+        Omit::new(&self.comment).read()
+    }
+
+    /// Name of the field on the referencing table that resolves the
+    /// referenced row: the `@fieldName` tag, or e.g. `userByAuthorId`.
+    pub fn field_name(&self, foreign_table: &Table) -> String {
+        SmartTags::parse(&self.comment)
+            .word("fieldName")
+            .map(str::to_string)
+            .unwrap_or_else(|| forward_relation_name(foreign_table.name(), &self.columns))
+    }
+
+    /// Name of the field on the referenced table that lists the referencing
+    /// rows: the `@foreignFieldName` tag, or e.g. `postsByAuthorId`.
+    pub fn foreign_field_name(&self, table: &Table) -> String {
+        SmartTags::parse(&self.comment)
+            .word("foreignFieldName")
+            .map(str::to_string)
+            .unwrap_or_else(|| backward_relation_name(table.name(), &self.columns))
+    }
+}
+
+#[cfg(test)]
+impl ForeignKey {
+    pub fn new_for_test(
+        table_oid: u32,
+        columns: &[&str],
+        foreign_table_oid: u32,
+        comment: &str,
+    ) -> Self {
+        Self {
+            oid: 0,
+            name: "fk".to_string(),
+            table_oid,
+            columns: columns.iter().map(|c| c.to_string()).collect(),
+            foreign_table_oid,
+            foreign_columns: vec!["id".to_string()],
+            comment: comment.to_string(),
+            on_delete: FkAction::default(),
+            on_update: FkAction::default(),
+            synthetic: false,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_belongs_to() {
+        assert_eq!(
+            parse_belongs_to(&SmartTags::parse(
+                "Per-user totals. @belongsTo users(user_id)"
+            )),
+            [BelongsTo {
+                schema: None,
+                table: "users".into(),
+                columns: vec!["user_id".into()],
+                foreign_columns: Vec::new(),
+            }]
+        );
+        assert_eq!(
+            parse_belongs_to(&SmartTags::parse(
+                "@belongsTo app.memberships(org_id, user_id)"
+            ))[0],
+            BelongsTo {
+                schema: Some("app".into()),
+                table: "memberships".into(),
+                columns: vec!["org_id".into(), "user_id".into()],
+                foreign_columns: Vec::new(),
+            }
+        );
+        assert!(parse_belongs_to(&SmartTags::parse("@belongsTo users")).is_empty());
+    }
+
+    #[test]
+    fn test_parse_foreign_key_tag() {
+        let tags = SmartTags::parse(
+            "@foreignKey (owner_login) references app.users(login)\n@foreignKey (org_id) references orgs",
+        );
+        assert_eq!(
+            parse_belongs_to(&tags),
+            [
+                BelongsTo {
+                    schema: Some("app".into()),
+                    table: "users".into(),
+                    columns: vec!["owner_login".into()],
+                    foreign_columns: vec!["login".into()],
+                },
+                BelongsTo {
+                    schema: None,
+                    table: "orgs".into(),
+                    columns: vec!["org_id".into()],
+                    foreign_columns: Vec::new(),
+                },
+            ]
+        );
+        assert!(parse_belongs_to(&SmartTags::parse("@foreignKey owner_id users")).is_empty());
+    }
+
+    #[test]
+    fn test_fk_actions() {
+        assert_eq!(FkAction::from_confaction("c"), FkAction::Cascade);
+        assert_eq!(FkAction::from_confaction("n").as_sql(), "SET NULL");
+        assert_eq!(FkAction::from_confaction("a"), FkAction::NoAction);
+        assert_eq!(
+            ForeignKey::new_for_test(1, &["author_id"], 2, "").describe_actions(),
+            "Foreign key `fk`: ON DELETE NO ACTION, ON UPDATE NO ACTION."
+        );
+    }
+
+    #[test]
+    fn test_default_relation_field_names() {
+        let posts = Table::new_for_test("posts", vec![]);
+        let users = Table::new_for_test("users", vec![]);
+        let fk = ForeignKey::new_for_test(1, &["author_id"], 2, "");
+        assert_eq!(fk.field_name(&users), "userByAuthorId");
+        assert_eq!(fk.foreign_field_name(&posts), "postsByAuthorId");
+    }
+
+    #[test]
+    fn test_tags_override_relation_field_names() {
+        let posts = Table::new_for_test("posts", vec![]);
+        let users = Table::new_for_test("users", vec![]);
+        let fk = ForeignKey::new_for_test(
+            1,
+            &["author_id"],
+            2,
+            "Post author. @fieldName author @foreignFieldName writtenPosts",
+        );
+        assert_eq!(fk.field_name(&users), "author");
+        assert_eq!(fk.foreign_field_name(&posts), "writtenPosts");
+    }
+
+    #[test]
+    fn test_unrelated_tags_are_ignored() {
+        let users = Table::new_for_test("users", vec![]);
+        let fk = ForeignKey::new_for_test(1, &["owner_id"], 2, "@omit read");
+        assert_eq!(fk.field_name(&users), "userByOwnerId");
+        assert!(fk.omit());
+        assert!(!ForeignKey::new_for_test(1, &["owner_id"], 2, "@fieldName owner").omit());
+    }
+}
@@ -0,0 +1,189 @@
+use serde::{Deserialize, Serialize};
+use tokio_postgres::types::Type;
+
+use crate::models::api_version::in_version;
+use crate::models::description::Descriptions;
+use crate::models::grants::public_or;
+use crate::models::procedure::{ProcedureArg, Volatility, parse_args};
+use crate::models::smart_tags::SmartTags;
+use crate::models::table::{Omit, type_oid};
+use crate::models::types::TypeRegistry;
+
+/// What a [`Function`] returns, one value or (when it returns a set) many.
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+#[serde(rename_all = "snake_case")]
+pub enum FunctionReturn {
+    /// `void`: the field answers `true` once the call has completed.
+    Void,
+    /// A value of a type the GraphQL mapping knows.
+    Scalar(#[serde(with = "type_oid")] Type),
+    /// Rows of the table with this OID, resolved as its entity type.
+    Table(u32),
+    /// Records of the function's `OUT` arguments or `RETURNS TABLE (...)`
+    /// columns, resolved as a type generated for the function.
+    Record(Vec<ProcedureArg>),
+}
+
+/// A function (`CREATE FUNCTION`), exposed as a query field when declared
+/// `STABLE` or `IMMUTABLE` and as a mutation otherwise. A function named
+/// `{table}_{field}` whose first argument is a row of `table` becomes the
+/// computed column `field` on that table's type instead.
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct Function {
+    oid: u32,
+    schema_name: String,
+    name: String,
+    #[serde(default)]
+    comment: String,
+    /// The arguments the caller supplies, without the row of a computed
+    /// column. Output arguments are part of [`FunctionReturn::Record`].
+    args: Vec<ProcedureArg>,
+    returns: FunctionReturn,
+    #[serde(default)]
+    omit: Omit,
+    #[serde(default)]
+    descriptions: Descriptions,
+    #[serde(default)]
+    volatility: Volatility,
+    #[serde(default)]
+    strict: bool,
+    #[serde(default)]
+    returns_set: bool,
+    /// For a computed column, the OID of the table whose row the function
+    /// takes first.
+    #[serde(default)]
+    row_table: Option<u32>,
+    /// The roles that can execute the function, see
+    /// [`TablePrivileges`](crate::TablePrivileges).
+    #[serde(default)]
+    execute_roles: Vec<String>,
+}
+
+impl Function {
+    /// Reads a row of the function introspection query. Fails with the
+    /// reason when the function cannot be exposed: a variadic, unnamed
+    /// output or unsupported-type argument, or an unsupported return type.
+    pub(crate) fn from_row(
+        row: &tokio_postgres::Row,
+        types: &TypeRegistry,
+    ) -> Result<Self, String> {
+        let comment = row.try_get::<_, String>(3).unwrap_or_default();
+        let arg_types: Vec<u32> = row.get(4);
+        let modes: Vec<String> = row.get(5);
+        let names: Vec<String> = row.get(6);
+        let defaults = row.get::<_, i16>(7) as usize;
+        let schema_comment = row.try_get::<_, String>(8).unwrap_or_default();
+        let volatility = Volatility::from_provolatile(&row.get::<_, String>(9));
+        let return_type: u32 = row.get(12);
+        let return_table: Option<u32> = row.get(13);
+        let row_table: Option<u32> = row.get(14);
+
+        let skip = usize::from(row_table.is_some());
+        let mut args = parse_args(
+            &arg_types[skip..],
+            &modes[skip..],
+            names.get(skip..).unwrap_or_default(),
+            defaults,
+            &comment,
+            types,
+        )?;
+        let outputs: Vec<ProcedureArg> = args
+            .iter()
+            .filter(|arg| arg.mode().is_output())
+            .cloned()
+            .collect();
+        args.retain(|arg| arg.mode().is_input());
+        // A single output is returned as a plain value of its type.
+        let returns = match (return_table, types.resolve(return_type)) {
+            (Some(table), _) => FunctionReturn::Table(table),
+            (None, Some(Type::VOID)) => FunctionReturn::Void,
+            (None, Some(Type::RECORD)) if !outputs.is_empty() => FunctionReturn::Record(outputs),
+            (None, Some(ty)) if ty != Type::RECORD => FunctionReturn::Scalar(ty),
+            _ => return Err("its return type is not supported".into()),
+        };
+
+        Ok(Self {
+            oid: row.get(0),
+            schema_name: row.get(1),
+            name: row.get(2),
+            omit: Omit::new(&schema_comment).union(Omit::new(&comment)),
+            descriptions: Descriptions::new(&SmartTags::parse(&comment)),
+            comment,
+            args,
+            returns,
+            volatility,
+            strict: row.get(10),
+            returns_set: row.get(11),
+            row_table,
+            execute_roles: public_or(row.try_get(15).unwrap_or_default()),
+        })
+    }
+
+    pub fn oid(&self) -> u32 {
+        self.oid
+    }
+
+    pub fn schema_name(&self) -> &str {
+        &self.schema_name
+    }
+
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    pub fn comment(&self) -> &str {
+        &self.comment
+    }
+
+    pub fn args(&self) -> &[ProcedureArg] {
+        &self.args
+    }
+
+    pub fn returns(&self) -> &FunctionReturn {
+        &self.returns
+    }
+
+    pub fn volatility(&self) -> Volatility {
+        self.volatility
+    }
+
+    pub fn is_strict(&self) -> bool {
+        self.strict
+    }
+
+    pub fn returns_set(&self) -> bool {
+        self.returns_set
+    }
+
+    pub fn execute_roles(&self) -> &[String] {
+        &self.execute_roles
+    }
+
+    /// The OID of the table this function is a computed column of.
+    pub fn row_table(&self) -> Option<u32> {
+        self.row_table
+    }
+
+    /// Whether an `@omit` tag hides the function.
+    pub fn omit(&self) -> bool {
+        self.omit.read()
+    }
+
+    /// Descriptions from `@description` tags, by locale.
+    pub fn descriptions(&self) -> &Descriptions {
+        &self.descriptions
+    }
+
+    /// Forgets the function's `@omit` tag.
+    /// Omits the routine when its `@since` and `@until` tags leave it out of
+    /// API `version`.
+    pub(crate) fn restrict_to_version(&mut self, version: &str) {
+        if !in_version(&SmartTags::parse(&self.comment), version) {
+            self.omit = Omit::all();
+        }
+    }
+
+    pub(crate) fn clear_omit(&mut self) {
+        self.omit = Omit::default();
+    }
+}
@@ -0,0 +1,96 @@
+use serde::{Deserialize, Serialize};
+
+/// Stands for every role in a list of roles: the privilege is granted to
+/// `PUBLIC`.
+pub const PUBLIC_ROLE: &str = "public";
+
+/// A privilege on a table that a generated root field needs.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Privilege {
+    Select,
+    Insert,
+    Update,
+    Delete,
+}
+
+/// The roles holding each privilege on a table when the catalog was read,
+/// directly, through role membership or as superusers. Roles named `pg_*`
+/// are left out. A privilege granted to `PUBLIC` lists only
+/// [`PUBLIC_ROLE`].
+#[derive(Serialize, Deserialize, Clone, Debug, Default, PartialEq)]
+pub struct TablePrivileges {
+    /// Roles that can read at least one column.
+    #[serde(default)]
+    pub select: Vec<String>,
+    #[serde(default)]
+    pub insert: Vec<String>,
+    #[serde(default)]
+    pub update: Vec<String>,
+    #[serde(default)]
+    pub delete: Vec<String>,
+}
+
+impl TablePrivileges {
+    pub fn roles(&self, privilege: Privilege) -> &[String] {
+        match privilege {
+            Privilege::Select => &self.select,
+            Privilege::Insert => &self.insert,
+            Privilege::Update => &self.update,
+            Privilege::Delete => &self.delete,
+        }
+    }
+}
+
+/// `roles` as listed in a catalog: only [`PUBLIC_ROLE`] when it is among
+/// them, since every role then holds the privilege.
+pub(crate) fn public_or(roles: Vec<String>) -> Vec<String> {
+    if roles.iter().any(|role| role == PUBLIC_ROLE) {
+        vec![PUBLIC_ROLE.to_string()]
+    } else {
+        roles
+    }
+}
+
+/// The roles in both `a` and `b`, where [`PUBLIC_ROLE`] holds every role.
+pub(crate) fn intersect(a: &[String], b: &[String]) -> Vec<String> {
+    let public = |roles: &[String]| roles.iter().any(|role| role == PUBLIC_ROLE);
+    match (public(a), public(b)) {
+        (true, _) => b.to_vec(),
+        (_, true) => a.to_vec(),
+        _ => a.iter().filter(|role| b.contains(role)).cloned().collect(),
+    }
+}
+
+/// The roles in `a` or `b`, where [`PUBLIC_ROLE`] holds every role.
+pub(crate) fn union(a: &[String], b: &[String]) -> Vec<String> {
+    let mut roles: Vec<String> = a.iter().chain(b).cloned().collect();
+    roles.sort();
+    roles.dedup();
+    public_or(roles)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn roles(names: &[&str]) -> Vec<String> {
+        names.iter().map(|name| name.to_string()).collect()
+    }
+
+    #[test]
+    fn test_public_holds_every_role() {
+        assert_eq!(
+            intersect(&roles(&["public"]), &roles(&["editor"])),
+            ["editor"]
+        );
+        assert_eq!(
+            intersect(&roles(&["admin", "editor"]), &roles(&["editor"])),
+            ["editor"]
+        );
+        assert_eq!(
+            union(&roles(&["editor"]), &roles(&["admin", "editor"])),
+            ["admin", "editor"]
+        );
+        assert_eq!(union(&roles(&["editor"]), &roles(&["public"])), ["public"]);
+    }
+}
@@ -0,0 +1,77 @@
+use std::collections::HashMap;
+
+/// Canned rows served by a schema built with
+/// [`TurboGraph::mock`](crate::TurboGraph::mock) instead of querying the
+/// database.
+///
+/// Rows are JSON objects keyed by column name, grouped by table. A table is
+/// looked up by its qualified `schema.table` name first and its bare name
+/// second, so fixtures only need qualifying when names collide across schemas.
+#[derive(Clone, Debug, Default)]
+pub struct MockData {
+    tables: HashMap<String, Vec<serde_json::Value>>,
+}
+
+impl MockData {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Parses a JSON object of the form `{ "users": [{ "id": 1, ... }], ... }`.
+    pub fn from_json(
+        json: serde_json::Value,
+    ) -> Result<Self, Box<dyn std::error::Error + Send + Sync>> {
+        let serde_json::Value::Object(map) = json else {
+            return Err("mock data must be a JSON object keyed by table name".into());
+        };
+
+        let mut data = Self::new();
+        for (table, rows) in map {
+            let serde_json::Value::Array(rows) = rows else {
+                return Err(format!("mock data for `{table}` must be an array of rows").into());
+            };
+            if rows.iter().any(|row| !row.is_object()) {
+                return Err(format!("mock rows for `{table}` must be JSON objects").into());
+            }
+            data.tables.insert(table, rows);
+        }
+        Ok(data)
+    }
+
+    /// Sets the rows for `table`, replacing any existing ones.
+    pub fn with_rows(mut self, table: impl Into<String>, rows: Vec<serde_json::Value>) -> Self {
+        self.tables.insert(table.into(), rows);
+        self
+    }
+
+    /// The rows for `schema.table`, or an empty slice when none were given.
+    pub(crate) fn rows(&self, schema: &str, table: &str) -> &[serde_json::Value] {
+        self.tables
+            .get(&format!("{schema}.{table}"))
+            .or_else(|| self.tables.get(table))
+            .map_or(&[], Vec::as_slice)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn test_rows_prefers_qualified_name() {
+        let data = MockData::new()
+            .with_rows("users", vec![json!({ "id": 1 })])
+            .with_rows("auth.users", vec![json!({ "id": 2 })]);
+        assert_eq!(data.rows("auth", "users"), [json!({ "id": 2 })]);
+        assert_eq!(data.rows("public", "users"), [json!({ "id": 1 })]);
+        assert!(data.rows("public", "posts").is_empty());
+    }
+
+    #[test]
+    fn test_from_json_rejects_non_array_tables() {
+        assert!(MockData::from_json(json!({ "users": { "id": 1 } })).is_err());
+        assert!(MockData::from_json(json!({ "users": [1, 2] })).is_err());
+        assert!(MockData::from_json(json!({ "users": [{ "id": 1 }] })).is_ok());
+    }
+}
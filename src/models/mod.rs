@@ -1,3 +1,19 @@
+pub(crate) mod api_version;
+pub mod catalog;
 pub mod config;
+pub mod description;
+pub mod encryption;
+pub mod federation;
+pub mod foreign_key;
+pub mod function;
+pub mod grants;
+pub mod mock;
+pub mod operation_matrix;
+pub mod procedure;
+pub(crate) mod reference;
+pub mod row_transform;
+pub mod smart_tags;
 pub mod table;
+pub mod temporal;
 pub mod transaction;
+pub(crate) mod types;
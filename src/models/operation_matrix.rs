@@ -0,0 +1,237 @@
+use std::collections::BTreeMap;
+
+use async_graphql::parser::types::{ExecutableDocument, OperationType, Selection, SelectionSet};
+use serde::{Deserialize, Serialize};
+
+use super::grants::PUBLIC_ROLE;
+
+/// The root fields of a schema and the roles allowed to run each, keyed by
+/// coordinate (`Query.allPosts`, `Mutation.createPost`). Serializes to JSON
+/// as `{ "Query.allPosts": ["anonymous", "editor"], ... }`.
+///
+/// [`TurboGraph::operation_matrix`](crate::TurboGraph::operation_matrix)
+/// exports the one the database's grants imply, for review; set the reviewed
+/// copy as [`Config::operation_matrix`](crate::Config::operation_matrix) to
+/// reject every root field it does not allow for the request's role.
+/// [`PUBLIC_ROLE`](crate::PUBLIC_ROLE) allows a field for every role, and
+/// for requests without one.
+///
+/// The matrix may also list operation names under `"operations"`, e.g.
+/// `{ "operations": { "FeedPage": ["anonymous"] }, ... }`. Once any is
+/// listed, requests must run a named operation the matrix allows for their
+/// role, on top of the root field check; the exported matrix lists none,
+/// since operation names come from the clients.
+#[derive(Serialize, Deserialize, Clone, Debug, Default, PartialEq)]
+pub struct OperationMatrix {
+    #[serde(flatten)]
+    fields: BTreeMap<String, Vec<String>>,
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    operations: BTreeMap<String, Vec<String>>,
+}
+
+impl OperationMatrix {
+    /// Loads a matrix saved with [`to_json`](Self::to_json).
+    pub fn from_json(json: &str) -> Result<Self, serde_json::Error> {
+        serde_json::from_str(json)
+    }
+
+    /// The matrix as pretty-printed JSON, sorted by coordinate so reviews
+    /// can diff it.
+    pub fn to_json(&self) -> Result<String, serde_json::Error> {
+        serde_json::to_string_pretty(self)
+    }
+
+    /// Allows the field at `coordinate` for `roles`, replacing any roles it
+    /// had.
+    pub fn allow(&mut self, coordinate: impl Into<String>, roles: Vec<String>) {
+        self.fields.insert(coordinate.into(), roles);
+    }
+
+    /// Allows operations named `name` for `roles`, replacing any roles the
+    /// name had. See the [type docs](Self) for what listing one enforces.
+    pub fn allow_operation(&mut self, name: impl Into<String>, roles: Vec<String>) {
+        self.operations.insert(name.into(), roles);
+    }
+
+    /// The roles allowed to run operations named `name`, `None` when the
+    /// matrix does not list it.
+    pub fn operation_roles(&self, name: &str) -> Option<&[String]> {
+        self.operations.get(name).map(Vec::as_slice)
+    }
+
+    /// The roles allowed to run the field at `coordinate`, `None` when the
+    /// matrix does not list it.
+    pub fn roles(&self, coordinate: &str) -> Option<&[String]> {
+        self.fields.get(coordinate).map(Vec::as_slice)
+    }
+
+    /// Every coordinate with its roles, in coordinate order.
+    pub fn iter(&self) -> impl Iterator<Item = (&str, &[String])> {
+        self.fields
+            .iter()
+            .map(|(coordinate, roles)| (coordinate.as_str(), roles.as_slice()))
+    }
+
+    /// Whether `role` may run the field at `coordinate`. Fields the matrix
+    /// does not list are allowed for no one.
+    pub fn allows(&self, coordinate: &str, role: Option<&str>) -> bool {
+        self.roles(coordinate)
+            .is_some_and(|roles| allows_role(roles, role))
+    }
+
+    /// The operation of `document` named `operation_name`, when its name is
+    /// not one the matrix allows for `role`, or else the first root field it
+    /// selects that `role` may not run, as a message. Introspection fields
+    /// are always allowed.
+    pub(crate) fn check(
+        &self,
+        document: &ExecutableDocument,
+        operation_name: Option<&str>,
+        role: Option<&str>,
+    ) -> Result<(), String> {
+        let mut operations = document.operations.iter();
+        let operation = match operation_name {
+            Some(name) => operations.find(|(op_name, _)| op_name.is_some_and(|n| n == name)),
+            None => operations.next(),
+        };
+        let Some((name, operation)) = operation else {
+            return Ok(());
+        };
+        if !self.operations.is_empty() {
+            let allowed = name.is_some_and(|name| {
+                self.operation_roles(name)
+                    .is_some_and(|roles| allows_role(roles, role))
+            });
+            if !allowed {
+                return Err(match (name, role) {
+                    (None, _) => "anonymous operations are not allowed".into(),
+                    (Some(name), Some(role)) => {
+                        format!("operation `{name}` is not allowed for role `{role}`")
+                    }
+                    (Some(name), None) => {
+                        format!("operation `{name}` is not allowed without a role")
+                    }
+                });
+            }
+        }
+        let root = match operation.node.ty {
+            OperationType::Query => "Query",
+            OperationType::Mutation => "Mutation",
+            OperationType::Subscription => "Subscription",
+        };
+        let mut fields = Vec::new();
+        root_fields(document, &operation.node.selection_set.node, &mut fields);
+        for field in fields.into_iter().filter(|f| !f.starts_with("__")) {
+            let coordinate = format!("{root}.{field}");
+            if !self.allows(&coordinate, role) {
+                return Err(match role {
+                    Some(role) => format!("`{coordinate}` is not allowed for role `{role}`"),
+                    None => format!("`{coordinate}` is not allowed without a role"),
+                });
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Whether `roles` include `role` or [`PUBLIC_ROLE`].
+fn allows_role(roles: &[String], role: Option<&str>) -> bool {
+    roles
+        .iter()
+        .any(|r| r == PUBLIC_ROLE || Some(r.as_str()) == role)
+}
+
+/// The names of the fields `selection_set` selects, looking through
+/// fragments.
+fn root_fields<'a>(
+    document: &'a ExecutableDocument,
+    selection_set: &'a SelectionSet,
+    fields: &mut Vec<&'a str>,
+) {
+    for selection in &selection_set.items {
+        match &selection.node {
+            Selection::Field(field) => fields.push(field.node.name.node.as_str()),
+            Selection::InlineFragment(fragment) => {
+                root_fields(document, &fragment.node.selection_set.node, fields)
+            }
+            Selection::FragmentSpread(spread) => {
+                if let Some(fragment) = document.fragments.get(&spread.node.fragment_name.node) {
+                    root_fields(document, &fragment.node.selection_set.node, fields);
+                }
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn matrix() -> OperationMatrix {
+        let mut matrix = OperationMatrix::default();
+        matrix.allow("Query.allPosts", vec!["editor".into()]);
+        matrix.allow("Query.schemaVersion", vec![PUBLIC_ROLE.into()]);
+        matrix
+    }
+
+    fn check(query: &str, role: Option<&str>) -> Result<(), String> {
+        let document = async_graphql::parser::parse_query(query).unwrap();
+        matrix().check(&document, None, role)
+    }
+
+    #[test]
+    fn test_check_allows_listed_roles() {
+        assert!(check("{ allPosts { totalCount } __typename }", Some("editor")).is_ok());
+        assert!(check("{ schemaVersion }", None).is_ok());
+        assert_eq!(
+            check("{ allPosts { totalCount } }", Some("viewer")),
+            Err("`Query.allPosts` is not allowed for role `viewer`".into())
+        );
+    }
+
+    #[test]
+    fn test_check_looks_through_fragments() {
+        let query = "query { ...Root } fragment Root on Query { allPosts { totalCount } }";
+        assert!(check(query, None).is_err());
+        assert!(check("mutation { createPost { id } }", Some("editor")).is_err());
+    }
+
+    #[test]
+    fn test_check_listed_operation_names() {
+        let mut matrix = matrix();
+        matrix.allow_operation("Feed", vec!["editor".into()]);
+        let check = |query: &str, name: Option<&str>, role: Option<&str>| {
+            let document = async_graphql::parser::parse_query(query).unwrap();
+            matrix.check(&document, name, role)
+        };
+
+        let feed = "query Feed { allPosts { totalCount } }";
+        assert!(check(feed, None, Some("editor")).is_ok());
+        assert_eq!(
+            check(feed, Some("Feed"), Some("viewer")),
+            Err("operation `Feed` is not allowed for role `viewer`".into())
+        );
+        assert_eq!(
+            check("{ allPosts { totalCount } }", None, Some("editor")),
+            Err("anonymous operations are not allowed".into())
+        );
+        let renamed = "query Other { allPosts { totalCount } }";
+        assert!(check(renamed, None, Some("editor")).is_err());
+    }
+
+    #[test]
+    fn test_json_keeps_operations_apart_from_fields() {
+        let mut matrix = matrix();
+        let json = matrix.to_json().unwrap();
+        assert!(!json.contains("operations"), "{json}");
+        matrix.allow_operation("Feed", vec![PUBLIC_ROLE.into()]);
+        let json = matrix.to_json().unwrap();
+        let loaded = OperationMatrix::from_json(&json).unwrap();
+        assert_eq!(loaded, matrix);
+        assert_eq!(
+            loaded.roles("Query.allPosts"),
+            Some(&["editor".to_string()][..])
+        );
+        assert_eq!(loaded.iter().count(), 2);
+    }
+}
@@ -0,0 +1,344 @@
+use serde::{Deserialize, Serialize};
+use tokio_postgres::types::Type;
+
+use crate::models::api_version::in_version;
+use crate::models::description::Descriptions;
+use crate::models::grants::public_or;
+use crate::models::smart_tags::SmartTags;
+use crate::models::table::{Omit, type_oid};
+use crate::models::types::TypeRegistry;
+
+/// How a procedure argument passes its value (`pg_proc.proargmodes`).
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq)]
+#[serde(rename_all = "snake_case")]
+pub enum ArgMode {
+    /// Taken from the caller.
+    In,
+    /// Taken from the caller and returned in the result row.
+    InOut,
+    /// Only returned in the result row (Postgres 14+ for procedures), or a
+    /// column of a function's `RETURNS TABLE (...)`.
+    Out,
+}
+
+impl ArgMode {
+    /// Decodes one `pg_proc.proargmodes` entry. Variadic arguments are not
+    /// supported.
+    fn from_proargmode(mode: &str) -> Option<Self> {
+        match mode {
+            "i" => Some(Self::In),
+            "b" => Some(Self::InOut),
+            "o" | "t" => Some(Self::Out),
+            _ => None,
+        }
+    }
+
+    /// Whether the caller supplies a value for the argument.
+    pub fn is_input(self) -> bool {
+        matches!(self, Self::In | Self::InOut)
+    }
+
+    /// Whether the argument comes back in the result row.
+    pub fn is_output(self) -> bool {
+        matches!(self, Self::InOut | Self::Out)
+    }
+}
+
+/// How a routine may touch the database (`pg_proc.provolatile`).
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, Default, PartialEq)]
+#[serde(rename_all = "snake_case")]
+pub enum Volatility {
+    /// Always returns the same result for the same arguments.
+    Immutable,
+    /// Does not modify the database.
+    Stable,
+    /// May modify the database; the default.
+    #[default]
+    Volatile,
+}
+
+impl Volatility {
+    pub(crate) fn from_provolatile(provolatile: &str) -> Self {
+        match provolatile {
+            "i" => Self::Immutable,
+            "s" => Self::Stable,
+            _ => Self::Volatile,
+        }
+    }
+
+    /// Whether the routine only reads, so it belongs on the query root
+    /// rather than the mutation root.
+    pub fn is_read_only(self) -> bool {
+        self != Self::Volatile
+    }
+}
+
+/// One argument of a [`Procedure`], in declaration order.
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+pub struct ProcedureArg {
+    /// The declared name, or `arg{n}` (counting from 1) for an unnamed
+    /// input argument.
+    name: String,
+    /// Whether the declaration names the argument. Unnamed arguments are
+    /// passed by position.
+    #[serde(default = "named_default")]
+    named: bool,
+    mode: ArgMode,
+    #[serde(rename = "type", with = "type_oid")]
+    r#type: Type,
+    /// Whether the declaration has a `DEFAULT`, so callers may leave it out.
+    #[serde(default)]
+    has_default: bool,
+    /// From an `@arg <name> <text>` tag on the routine's comment.
+    #[serde(default)]
+    description: Option<String>,
+}
+
+fn named_default() -> bool {
+    true
+}
+
+impl ProcedureArg {
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    pub fn is_named(&self) -> bool {
+        self.named
+    }
+
+    pub fn description(&self) -> Option<&str> {
+        self.description.as_deref()
+    }
+
+    pub fn mode(&self) -> ArgMode {
+        self.mode
+    }
+
+    pub fn _type(&self) -> &Type {
+        &self.r#type
+    }
+
+    pub fn has_default(&self) -> bool {
+        self.has_default
+    }
+}
+
+/// A stored procedure (`CREATE PROCEDURE`), exposed as a mutation that runs
+/// `CALL`. Its `INOUT` and `OUT` arguments make up the mutation's payload.
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct Procedure {
+    oid: u32,
+    schema_name: String,
+    name: String,
+    #[serde(default)]
+    comment: String,
+    args: Vec<ProcedureArg>,
+    #[serde(default)]
+    omit: Omit,
+    #[serde(default)]
+    descriptions: Descriptions,
+    #[serde(default)]
+    volatility: Volatility,
+    /// `STRICT`: any `NULL` argument makes the result `NULL` without a call.
+    #[serde(default)]
+    strict: bool,
+    /// Whether the routine returns a set of rows.
+    #[serde(default)]
+    returns_set: bool,
+    /// `SECURITY DEFINER`: runs with the privileges of its owner.
+    #[serde(default)]
+    security_definer: bool,
+    /// The roles that can execute the procedure, see
+    /// [`TablePrivileges`](crate::TablePrivileges).
+    #[serde(default)]
+    execute_roles: Vec<String>,
+}
+
+impl Procedure {
+    /// Reads a row of the procedure introspection query. Fails with the
+    /// reason when the procedure cannot be exposed: an unnamed output, a
+    /// variadic, or an unsupported-type argument.
+    pub(crate) fn from_row(
+        row: &tokio_postgres::Row,
+        types: &TypeRegistry,
+    ) -> Result<Self, String> {
+        let comment = row.try_get::<_, String>(3).unwrap_or_default();
+        let arg_types: Vec<u32> = row.get(4);
+        let modes: Vec<String> = row.get(5);
+        let names: Vec<String> = row.get(6);
+        let defaults = row.get::<_, i16>(7) as usize;
+        let schema_comment = row.try_get::<_, String>(8).unwrap_or_default();
+        let volatility = Volatility::from_provolatile(&row.get::<_, String>(9));
+
+        let args = parse_args(&arg_types, &modes, &names, defaults, &comment, types)?;
+
+        Ok(Self {
+            oid: row.get(0),
+            schema_name: row.get(1),
+            name: row.get(2),
+            omit: Omit::new(&schema_comment).union(Omit::new(&comment)),
+            descriptions: Descriptions::new(&SmartTags::parse(&comment)),
+            comment,
+            args,
+            volatility,
+            strict: row.get(10),
+            returns_set: row.get(11),
+            security_definer: row.get(12),
+            execute_roles: public_or(row.try_get(13).unwrap_or_default()),
+        })
+    }
+
+    pub fn oid(&self) -> u32 {
+        self.oid
+    }
+
+    pub fn schema_name(&self) -> &str {
+        &self.schema_name
+    }
+
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    pub fn comment(&self) -> &str {
+        &self.comment
+    }
+
+    pub fn args(&self) -> &[ProcedureArg] {
+        &self.args
+    }
+
+    pub fn volatility(&self) -> Volatility {
+        self.volatility
+    }
+
+    pub fn is_strict(&self) -> bool {
+        self.strict
+    }
+
+    pub fn returns_set(&self) -> bool {
+        self.returns_set
+    }
+
+    pub fn is_security_definer(&self) -> bool {
+        self.security_definer
+    }
+
+    pub fn execute_roles(&self) -> &[String] {
+        &self.execute_roles
+    }
+
+    /// Whether an `@omit` tag hides the procedure.
+    pub fn omit(&self) -> bool {
+        self.omit.read()
+    }
+
+    /// Descriptions from `@description` tags, by locale.
+    pub fn descriptions(&self) -> &Descriptions {
+        &self.descriptions
+    }
+
+    /// Forgets the procedure's `@omit` tag.
+    /// Omits the routine when its `@since` and `@until` tags leave it out of
+    /// API `version`.
+    pub(crate) fn restrict_to_version(&mut self, version: &str) {
+        if !in_version(&SmartTags::parse(&self.comment), version) {
+            self.omit = Omit::all();
+        }
+    }
+
+    pub(crate) fn clear_omit(&mut self) {
+        self.omit = Omit::default();
+    }
+}
+
+/// Reads the arguments of a routine from its `pg_proc` arrays: the type,
+/// mode and name of each, and how many trailing input arguments have a
+/// `DEFAULT`. Descriptions come from `@arg` tags on the routine's `comment`.
+/// Fails with the reason when one cannot be exposed.
+pub(crate) fn parse_args(
+    arg_types: &[u32],
+    modes: &[String],
+    names: &[String],
+    defaults: usize,
+    comment: &str,
+    types: &TypeRegistry,
+) -> Result<Vec<ProcedureArg>, String> {
+    let tags = SmartTags::parse(comment);
+    let descriptions = arg_descriptions(&tags);
+    let inputs = modes
+        .iter()
+        .filter(|m| ArgMode::from_proargmode(m).is_some_and(ArgMode::is_input))
+        .count();
+    let mut input_index = 0;
+    let mut args = Vec::with_capacity(arg_types.len());
+    for (i, oid) in arg_types.iter().enumerate() {
+        let mode = modes
+            .get(i)
+            .and_then(|m| ArgMode::from_proargmode(m))
+            .ok_or("variadic arguments are not supported")?;
+        let name = names.get(i).filter(|n| !n.is_empty());
+        if name.is_none() && mode.is_output() {
+            return Err("unnamed output arguments are not supported".into());
+        }
+        let named = name.is_some();
+        let name = name.cloned().unwrap_or_else(|| format!("arg{}", i + 1));
+        let r#type = types
+            .resolve(*oid)
+            .ok_or_else(|| format!("argument {name} has an unsupported type"))?;
+        let has_default = mode.is_input() && {
+            input_index += 1;
+            input_index > inputs.saturating_sub(defaults)
+        };
+        args.push(ProcedureArg {
+            description: descriptions
+                .iter()
+                .find(|(arg, _)| *arg == name)
+                .map(|(_, text)| text.to_string()),
+            name,
+            named,
+            mode,
+            r#type,
+            has_default,
+        });
+    }
+    Ok(args)
+}
+
+/// The `@arg <name> <text>` tags of a routine comment, e.g.
+/// `@arg query Words to look for`. The text runs to the end of the line or
+/// the next tag.
+fn arg_descriptions(tags: &SmartTags) -> Vec<(&str, &str)> {
+    tags.get_all("arg")
+        .filter_map(|value| value.split_once(char::is_whitespace))
+        .map(|(name, text)| (name, text.trim()))
+        .filter(|(_, text)| !text.is_empty())
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_volatility() {
+        assert_eq!(Volatility::from_provolatile("i"), Volatility::Immutable);
+        assert_eq!(Volatility::from_provolatile("s"), Volatility::Stable);
+        assert_eq!(Volatility::from_provolatile("v"), Volatility::Volatile);
+        assert!(Volatility::Stable.is_read_only());
+        assert!(!Volatility::default().is_read_only());
+    }
+
+    #[test]
+    fn test_arg_descriptions() {
+        assert_eq!(
+            arg_descriptions(&SmartTags::parse(
+                "@description Search posts\n@arg query Words to look for @arg arg2 Page size"
+            )),
+            [("query", "Words to look for"), ("arg2", "Page size")]
+        );
+        assert!(arg_descriptions(&SmartTags::parse("@arguments none")).is_empty());
+        assert!(arg_descriptions(&SmartTags::parse("Ask me@arg query Words")).is_empty());
+    }
+}
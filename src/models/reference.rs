@@ -0,0 +1,263 @@
+use std::sync::{Arc, LazyLock};
+
+use super::catalog::Catalog;
+use super::smart_tags::SmartTags;
+use super::table::Table;
+
+/// A navigable reference declared in a table comment, without needing a
+/// constraint:
+///
+/// ```text
+/// @ref author to:User singular via:(author_id)->users(id)
+/// @ref tags to:Tag plural via:(id)->post_tags(post_id);(tag_id)->tags(id)
+/// @refVia author via:(author_id)->admins(id)
+/// ```
+///
+/// `via:` is a chain of hops `(columns)->table(columns)` separated by `;`:
+/// each hop joins the columns of the previous relation (the tagged table for
+/// the first hop) to columns of the next one, so join tables can be crossed.
+/// `@refVia` adds another path to an existing `@ref`; the rows of every path
+/// are combined. `plural` exposes a list, otherwise at most one row.
+#[derive(Clone, Debug, PartialEq)]
+pub(crate) struct RefTag {
+    pub name: String,
+    pub to: Option<String>,
+    pub plural: bool,
+    pub paths: Vec<Vec<RefHop>>,
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub(crate) struct RefHop {
+    pub columns: Vec<String>,
+    pub schema: Option<String>,
+    pub table: String,
+    pub foreign_columns: Vec<String>,
+}
+
+/// A hop whose target table was found in the catalog.
+#[derive(Clone, Debug)]
+pub(crate) struct ResolvedHop {
+    pub columns: Vec<String>,
+    pub table: Arc<Table>,
+    pub foreign_columns: Vec<String>,
+}
+
+/// A `@ref` whose paths all lead to tables of the catalog, ending at the same
+/// table.
+#[derive(Clone, Debug)]
+pub(crate) struct Reference {
+    pub name: String,
+    pub plural: bool,
+    pub paths: Vec<Vec<ResolvedHop>>,
+}
+
+impl Reference {
+    /// The table every path ends at.
+    pub fn target(&self) -> &Arc<Table> {
+        &self.paths[0].last().expect("paths are never empty").table
+    }
+}
+
+fn split_columns(list: &str) -> Vec<String> {
+    list.split(',')
+        .map(|c| c.trim().to_string())
+        .filter(|c| !c.is_empty())
+        .collect()
+}
+
+fn parse_path(via: &str) -> Option<Vec<RefHop>> {
+    static HOP_REGEX: LazyLock<regex::Regex> = LazyLock::new(|| {
+        regex::Regex::new(r"^\(([^)]*)\)->(?:(\w+)\.)?(\w+)\(([^)]*)\)$").unwrap()
+    });
+
+    via.split(';')
+        .map(|hop| {
+            let caps = HOP_REGEX.captures(hop.trim())?;
+            Some(RefHop {
+                columns: split_columns(&caps[1]),
+                schema: caps.get(2).map(|m| m.as_str().to_string()),
+                table: caps[3].to_string(),
+                foreign_columns: split_columns(&caps[4]),
+            })
+        })
+        .collect()
+}
+
+/// Parses every `@ref` and `@refVia` tag. `@refVia` tags are merged into
+/// the `@ref` of the same name; malformed tags are ignored.
+pub(crate) fn parse_refs(tags: &SmartTags) -> Vec<RefTag> {
+    let mut refs: Vec<RefTag> = Vec::new();
+    let mut extra_paths = Vec::new();
+    for (tag, value) in tags
+        .iter()
+        .filter(|(tag, _)| matches!(*tag, "ref" | "refVia"))
+    {
+        let mut words = value.split_whitespace();
+        let Some(name) = words.next() else {
+            continue;
+        };
+        let mut to = None;
+        let mut plural = false;
+        let mut path = None;
+        for word in words {
+            if let Some(type_name) = word.strip_prefix("to:") {
+                to = Some(type_name.to_string());
+            } else if let Some(via) = word.strip_prefix("via:") {
+                path = parse_path(via);
+            } else if word == "plural" {
+                plural = true;
+            }
+        }
+        let Some(path) = path else {
+            continue;
+        };
+
+        if tag == "ref" {
+            refs.push(RefTag {
+                name: name.to_string(),
+                to,
+                plural,
+                paths: vec![path],
+            });
+        } else {
+            extra_paths.push((name.to_string(), path));
+        }
+    }
+
+    for (name, path) in extra_paths {
+        if let Some(r) = refs.iter_mut().find(|r| r.name == name) {
+            r.paths.push(path);
+        }
+    }
+    refs
+}
+
+impl RefTag {
+    /// Looks up every hop of every path in `catalog`, starting from `table`.
+    /// Fails with a description of the first hop that does not resolve.
+    pub fn resolve(&self, table: &Table, catalog: &Catalog) -> Result<Reference, String> {
+        let mut paths = Vec::with_capacity(self.paths.len());
+        for path in &self.paths {
+            let mut from: &Table = table;
+            let mut resolved = Vec::with_capacity(path.len());
+            for hop in path {
+                let schema = hop.schema.as_deref().unwrap_or(table.schema_name());
+                let to = catalog
+                    .tables()
+                    .find(|t| t.schema_name() == schema && t.name() == hop.table)
+                    .ok_or_else(|| format!("unknown table {schema}.{}", hop.table))?;
+                let has_columns = |t: &Table, columns: &[String]| {
+                    columns
+                        .iter()
+                        .all(|c| t.columns().iter().any(|col| col.name() == c))
+                };
+                if hop.columns.is_empty()
+                    || hop.columns.len() != hop.foreign_columns.len()
+                    || !has_columns(from, &hop.columns)
+                    || !has_columns(to, &hop.foreign_columns)
+                {
+                    return Err(format!("columns do not match for hop to {}", hop.table));
+                }
+                resolved.push(ResolvedHop {
+                    columns: hop.columns.clone(),
+                    table: to.clone(),
+                    foreign_columns: hop.foreign_columns.clone(),
+                });
+                from = to;
+            }
+            paths.push(resolved);
+        }
+
+        let reference = Reference {
+            name: self.name.clone(),
+            plural: self.plural,
+            paths,
+        };
+        let target = reference.target().oid();
+        if reference
+            .paths
+            .iter()
+            .any(|p| p.last().map(|h| h.table.oid()) != Some(target))
+        {
+            return Err("every path must end at the same table".to_string());
+        }
+        if let Some(to) = &self.to
+            && *to != reference.target().type_name()
+        {
+            return Err(format!(
+                "to:{to} does not match {}",
+                reference.target().type_name()
+            ));
+        }
+        Ok(reference)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::table::Column;
+    use tokio_postgres::types::Type;
+
+    #[test]
+    fn test_parse_single_hop_ref() {
+        let refs = parse_refs(&SmartTags::parse(
+            "Post. @ref author to:User via:(author_id)->users(id)",
+        ));
+        assert_eq!(
+            refs,
+            [RefTag {
+                name: "author".into(),
+                to: Some("User".into()),
+                plural: false,
+                paths: vec![vec![RefHop {
+                    columns: vec!["author_id".into()],
+                    schema: None,
+                    table: "users".into(),
+                    foreign_columns: vec!["id".into()],
+                }]],
+            }]
+        );
+    }
+
+    #[test]
+    fn test_parse_multi_hop_ref_and_ref_via() {
+        let refs = parse_refs(&SmartTags::parse(
+            "@ref tags plural via:(id)->post_tags(post_id);(tag_id)->app.tags(id)\n\
+             @refVia tags via:(id)->legacy_tags(post_id)",
+        ));
+        assert_eq!(refs.len(), 1);
+        assert!(refs[0].plural);
+        assert_eq!(refs[0].paths.len(), 2);
+        assert_eq!(refs[0].paths[0].len(), 2);
+        assert_eq!(refs[0].paths[0][1].schema.as_deref(), Some("app"));
+        assert_eq!(refs[0].paths[1][0].table, "legacy_tags");
+    }
+
+    #[test]
+    fn test_malformed_ref_is_ignored() {
+        assert!(parse_refs(&SmartTags::parse("@ref author to:User")).is_empty());
+        assert!(parse_refs(&SmartTags::parse("@ref author via:author_id->users")).is_empty());
+    }
+
+    #[test]
+    fn test_resolve_checks_tables_and_columns() {
+        let col = |name: &str| Column::new_for_test(name, Type::INT4, false, false);
+        let posts = Table::new_for_test("posts", vec![col("author_id")]).with_oid(1);
+        let users = Table::new_for_test("users", vec![col("id")]).with_oid(2);
+        let catalog = Catalog::new(vec![posts.clone(), users]);
+
+        let tag = &parse_refs(&SmartTags::parse(
+            "@ref author to:User via:(author_id)->users(id)",
+        ))[0];
+        let reference = tag.resolve(&posts, &catalog).unwrap();
+        assert_eq!(reference.target().name(), "users");
+
+        let tag = &parse_refs(&SmartTags::parse("@ref author via:(writer_id)->users(id)"))[0];
+        assert!(tag.resolve(&posts, &catalog).is_err());
+        let tag = &parse_refs(&SmartTags::parse(
+            "@ref author to:Person via:(author_id)->users(id)",
+        ))[0];
+        assert!(tag.resolve(&posts, &catalog).is_err());
+    }
+}
@@ -0,0 +1,168 @@
+use std::future::Future;
+use std::pin::Pin;
+use std::sync::Arc;
+
+use serde_json::Value;
+
+use crate::db::request::RequestScope;
+use crate::error::gql_err;
+use crate::graphql::prefetch::PREFETCH_KEY;
+
+/// Result of a row transform.
+pub type RowTransformResult = Result<Value, Box<dyn std::error::Error + Send + Sync>>;
+
+type TransformFn = dyn Fn(Value, RowContext) -> Pin<Box<dyn Future<Output = RowTransformResult> + Send>>
+    + Send
+    + Sync;
+
+/// The table a row was read from and the request reading it.
+#[derive(Debug, Clone, PartialEq)]
+pub struct RowContext {
+    pub schema: String,
+    pub table: String,
+    /// The role the request runs as, if it names one.
+    pub role: Option<String>,
+    /// The request's custom settings, e.g. `jwt.claims.user_id`.
+    pub settings: Vec<(String, String)>,
+}
+
+/// Rewrites every row of one table after it is fetched and before its
+/// fields resolve, e.g. to redact values per role or turn a storage key into
+/// a signed URL. Rows are JSON objects keyed by column name.
+///
+/// ```rust,ignore
+/// RowTransform::new("files", move |mut row, _ctx| {
+///     let storage = storage.clone();
+///     async move {
+///         let key = row["storage_key"].as_str().unwrap_or_default().to_string();
+///         row["storage_key"] = storage.sign(&key).await?.into();
+///         Ok(row)
+///     }
+/// })
+/// ```
+#[derive(Clone)]
+pub struct RowTransform {
+    table: String,
+    transform: Arc<TransformFn>,
+}
+
+impl RowTransform {
+    /// A transform for `table`, its Postgres name, qualified as
+    /// `schema.name` when several exposed schemas have a table of that name.
+    pub fn new<F, Fut>(table: impl Into<String>, transform: F) -> Self
+    where
+        F: Fn(Value, RowContext) -> Fut + Send + Sync + 'static,
+        Fut: Future<Output = RowTransformResult> + Send + 'static,
+    {
+        Self {
+            table: table.into(),
+            transform: Arc::new(move |row, ctx| Box::pin(transform(row, ctx))),
+        }
+    }
+
+    pub fn table(&self) -> &str {
+        &self.table
+    }
+
+    fn applies_to(&self, schema: &str, table: &str) -> bool {
+        match self.table.split_once('.') {
+            Some((s, t)) => schema == s && table == t,
+            None => table == self.table,
+        }
+    }
+}
+
+impl std::fmt::Debug for RowTransform {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("RowTransform")
+            .field("table", &self.table)
+            .finish_non_exhaustive()
+    }
+}
+
+/// The configured transforms, handed to resolvers as schema data.
+#[derive(Clone, Default)]
+pub(crate) struct RowTransforms(pub Arc<Vec<RowTransform>>);
+
+impl RowTransforms {
+    /// `None` when nothing is configured, so requests skip the lookup.
+    pub(crate) fn new(transforms: &[RowTransform]) -> Option<Self> {
+        (!transforms.is_empty()).then(|| Self(Arc::new(transforms.to_vec())))
+    }
+}
+
+/// The transforms of `schema.table` configured for the request.
+fn transforms_of<'a>(scope: &'a RequestScope, schema: &str, table: &str) -> Vec<&'a RowTransform> {
+    scope
+        .row_transforms
+        .as_ref()
+        .map_or_else(Vec::new, |transforms| {
+            transforms
+                .0
+                .iter()
+                .filter(|t| t.applies_to(schema, table))
+                .collect()
+        })
+}
+
+/// Whether rows of `schema.table` are transformed. Their statements then
+/// fetch every column instead of only the selected ones, so transforms see
+/// whole rows.
+pub(crate) fn has_transforms(scope: &RequestScope, schema: &str, table: &str) -> bool {
+    !transforms_of(scope, schema, table).is_empty()
+}
+
+/// Runs `rows` of `schema.table` through its transforms, in configuration
+/// order. A failing transform fails the field reading the rows.
+pub(crate) async fn transform_rows(
+    scope: &RequestScope,
+    schema: &str,
+    table: &str,
+    rows: Vec<Value>,
+) -> Result<Vec<Value>, async_graphql::Error> {
+    let transforms = transforms_of(scope, schema, table);
+    if transforms.is_empty() || rows.is_empty() {
+        return Ok(rows);
+    }
+
+    let context = RowContext {
+        schema: schema.to_string(),
+        table: table.to_string(),
+        role: scope.tx_config.as_ref().and_then(|cfg| cfg.role.clone()),
+        settings: scope
+            .tx_config
+            .as_ref()
+            .map(|cfg| cfg.settings.clone())
+            .unwrap_or_default(),
+    };
+    let mut transformed = Vec::with_capacity(rows.len());
+    for mut row in rows {
+        // Transforms see only the columns; relations fetched along with the
+        // row stay attached to it.
+        let prefetched = row.as_object_mut().and_then(|row| row.remove(PREFETCH_KEY));
+        for transform in &transforms {
+            row = (transform.transform)(row, context.clone())
+                .await
+                .map_err(|e| gql_err(format!("Failed to transform a `{table}` row: {e}")))?;
+        }
+        if let (Some(prefetched), Some(row)) = (prefetched, row.as_object_mut()) {
+            row.insert(PREFETCH_KEY.to_string(), prefetched);
+        }
+        transformed.push(row);
+    }
+    Ok(transformed)
+}
+
+/// [`transform_rows`] for at most one row.
+pub(crate) async fn transform_row(
+    scope: &RequestScope,
+    schema: &str,
+    table: &str,
+    row: Option<Value>,
+) -> Result<Option<Value>, async_graphql::Error> {
+    Ok(
+        transform_rows(scope, schema, table, row.into_iter().collect())
+            .await?
+            .pop(),
+    )
+}
@@ -0,0 +1,129 @@
+use serde::{Deserialize, Serialize};
+
+/// The smart tags of a table, column or constraint comment, in comment
+/// order, e.g. `Registered users. @name Member @omit delete`.
+///
+/// A tag is an `@` at the start of the comment or after whitespace,
+/// followed by its name. Its value is the text after the name, up to the end
+/// of the line or the next tag, trimmed; flags such as `@enum` have an empty
+/// value. An `@` inside a word (`admin@example.com`) does not start a tag.
+///
+/// Tags the generators understand are read from here, and the rest are kept
+/// so custom code can act on its own, e.g. `@owner billing`.
+#[derive(Serialize, Deserialize, Clone, Debug, Default, PartialEq)]
+pub struct SmartTags(Vec<(String, String)>);
+
+impl SmartTags {
+    pub fn parse(comment: &str) -> Self {
+        let starts: Vec<usize> = comment
+            .char_indices()
+            .filter(|&(i, c)| c == '@' && (i == 0 || comment[..i].ends_with(char::is_whitespace)))
+            .map(|(i, _)| i)
+            .collect();
+
+        let tags = starts
+            .iter()
+            .enumerate()
+            .filter_map(|(n, &start)| {
+                let end = starts.get(n + 1).copied().unwrap_or(comment.len());
+                let tag = &comment[start + 1..end];
+                let tag = tag.split('\n').next().unwrap_or_default();
+                let name_len = tag
+                    .find(|c: char| !(c.is_alphanumeric() || c == '_'))
+                    .unwrap_or(tag.len());
+                if name_len == 0 {
+                    return None;
+                }
+                let (name, value) = tag.split_at(name_len);
+                Some((name.to_string(), value.trim().to_string()))
+            })
+            .collect();
+        Self(tags)
+    }
+
+    /// Whether the comment carries the tag `name`.
+    pub fn has(&self, name: &str) -> bool {
+        self.0.iter().any(|(tag, _)| tag == name)
+    }
+
+    /// The value of the first `name` tag, empty for a bare flag.
+    pub fn get(&self, name: &str) -> Option<&str> {
+        self.0
+            .iter()
+            .find(|(tag, _)| tag == name)
+            .map(|(_, value)| value.as_str())
+    }
+
+    /// The values of every `name` tag, for tags that may repeat such as
+    /// `@belongsTo`.
+    pub fn get_all<'a>(&'a self, name: &str) -> impl Iterator<Item = &'a str> {
+        self.0
+            .iter()
+            .filter(move |(tag, _)| tag == name)
+            .map(|(_, value)| value.as_str())
+    }
+
+    /// The first word of the first `name` tag's value, e.g. `author` for
+    /// `@fieldName author`. `None` when the tag is missing or has no value.
+    pub fn word(&self, name: &str) -> Option<&str> {
+        self.get(name)?.split_whitespace().next()
+    }
+
+    /// Every tag as `(name, value)`, in comment order.
+    pub fn iter(&self) -> impl Iterator<Item = (&str, &str)> {
+        self.0
+            .iter()
+            .map(|(name, value)| (name.as_str(), value.as_str()))
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.0.is_empty()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_flags_and_values() {
+        let tags = SmartTags::parse("Registered users. @name Member @enum\n@defaultSort id DESC");
+        assert_eq!(
+            tags.iter().collect::<Vec<_>>(),
+            [("name", "Member"), ("enum", ""), ("defaultSort", "id DESC")]
+        );
+        assert_eq!(tags.word("name"), Some("Member"));
+        assert_eq!(tags.get("enum"), Some(""));
+        assert_eq!(tags.word("enum"), None);
+        assert!(tags.has("defaultSort") && !tags.has("omit"));
+    }
+
+    #[test]
+    fn test_value_runs_to_end_of_line() {
+        let tags = SmartTags::parse("@foreignKey (user_id) references users(id)\nTotals per user.");
+        assert_eq!(
+            tags.get("foreignKey"),
+            Some("(user_id) references users(id)")
+        );
+    }
+
+    #[test]
+    fn test_repeated_tags() {
+        let tags = SmartTags::parse("@belongsTo users(user_id) @belongsTo orgs(org_id)");
+        assert_eq!(
+            tags.get_all("belongsTo").collect::<Vec<_>>(),
+            ["users(user_id)", "orgs(org_id)"]
+        );
+        assert_eq!(tags.get("belongsTo"), Some("users(user_id)"));
+    }
+
+    #[test]
+    fn test_at_inside_words_is_not_a_tag() {
+        let tags = SmartTags::parse("Contact admin@example.com. @owner ops@example.com");
+        assert_eq!(
+            tags.iter().collect::<Vec<_>>(),
+            [("owner", "ops@example.com")]
+        );
+        assert!(SmartTags::parse("No tags @ all.").is_empty());
+    }
+}
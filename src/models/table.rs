@@ -2,14 +2,21 @@ use serde::{Deserialize, Serialize};
 use std::sync::{Arc, LazyLock};
 use tokio_postgres::types::Type;
 
-use crate::utils::inflection::{singularize, to_pascal_case};
+use crate::models::api_version::in_version;
+use crate::models::description::Descriptions;
+use crate::models::federation::Federation;
+use crate::models::grants::{Privilege, TablePrivileges, public_or};
+use crate::models::smart_tags::SmartTags;
+use crate::models::temporal::Temporal;
+use crate::models::types::{TypeRegistry, base_type};
+use crate::utils::inflection::{DefaultInflector, Inflector};
 
 /// Omit is used to determine which operations (create, read, update, delete) should be omitted for a given table or column based on its comment.
 /// The comment can contain an @omit annotation followed by a comma-separated list of operations to omit. For example:
 /// - `@omit read,update` would indicate that the read and update operations should be omitted for that table or column.
-/// - `@omit` without any operations would indicate that all operations
-/// from this struct false means it is not omitted, true means it is omitted
-#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+/// - `@omit` (or `@omit all`) without any operations would indicate that all operations
+///   from this struct false means it is not omitted, true means it is omitted
+#[derive(Serialize, Deserialize, Clone, Debug, Default, PartialEq)]
 pub struct Omit {
     create: bool,
     read: bool,
@@ -19,37 +26,343 @@ pub struct Omit {
 
 impl Omit {
     pub(crate) fn new(comment: &str) -> Self {
-        static OMIT_REGEX: LazyLock<regex::Regex> =
-            LazyLock::new(|| regex::Regex::new(r"@omit\s+([^\s]+)").unwrap());
+        Self::from_tags(&SmartTags::parse(comment))
+    }
 
-        let have_omit = comment.contains("@omit");
+    pub(crate) fn from_tags(tags: &SmartTags) -> Self {
+        let Some(value) = tags.get("omit") else {
+            return Self::default();
+        };
 
         // omit all if there is only omit string
         let mut omit = Omit {
-            read: have_omit,
-            create: have_omit,
-            update: have_omit,
-            delete: have_omit,
+            read: true,
+            create: true,
+            update: true,
+            delete: true,
         };
 
-        if let Some(caps) = OMIT_REGEX.captures(comment) {
-            let res = &caps[1];
-            let parts = res.split(",").collect::<Vec<&str>>();
+        if !value.is_empty() {
+            let parts = value.split(',').map(str::trim).collect::<Vec<&str>>();
 
-            omit.read = parts.contains(&"read");
-            omit.create = parts.contains(&"create");
-            omit.update = parts.contains(&"update");
-            omit.delete = parts.contains(&"delete");
+            let all = parts.contains(&"all");
+
+            omit.read = all || parts.contains(&"read");
+            omit.create = all || parts.contains(&"create");
+            omit.update = all || parts.contains(&"update");
+            omit.delete = all || parts.contains(&"delete");
         }
 
         omit
     }
+
+    pub(crate) fn read(&self) -> bool {
+        self.read
+    }
+
+    /// Omits every operation, like a bare `@omit`.
+    pub(crate) fn all() -> Self {
+        Self {
+            create: true,
+            read: true,
+            update: true,
+            delete: true,
+        }
+    }
+
+    /// Omits every operation either `self` or `other` omits, e.g. a schema's
+    /// `@omit` combined with a table's own.
+    pub(crate) fn union(self, other: Self) -> Self {
+        Self {
+            create: self.create || other.create,
+            read: self.read || other.read,
+            update: self.update || other.update,
+            delete: self.delete || other.delete,
+        }
+    }
+}
+
+/// Defaults that generate a random UUID: `gen_random_uuid()`, Postgres 18's
+/// `uuidv4()` and `uuidv7()`, and the `uuid-ossp` functions, which may be
+/// schema-qualified.
+static UUID_DEFAULT_REGEX: LazyLock<regex::Regex> = LazyLock::new(|| {
+    regex::Regex::new(
+        r"^(\w+\.)?(gen_random_uuid|uuidv4|uuidv7|uuid_generate_v1|uuid_generate_v1mc|uuid_generate_v4)\(\)$",
+    )
+    .unwrap()
+});
+
+/// A timestamp column maintained by the generated mutations instead of a
+/// trigger, selected with a `@createdAt` or `@updatedAt` column tag.
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq)]
+#[serde(rename_all = "snake_case")]
+pub enum AutoTimestamp {
+    /// Set to `now()` on insert.
+    Created,
+    /// Set to `now()` on insert and on every update.
+    Updated,
+}
+
+impl AutoTimestamp {
+    fn new(tags: &SmartTags) -> Option<Self> {
+        if tags.has("createdAt") {
+            Some(Self::Created)
+        } else if tags.has("updatedAt") {
+            Some(Self::Updated)
+        } else {
+            None
+        }
+    }
+}
+
+/// How an identity column gets its value (`GENERATED ... AS IDENTITY`).
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq)]
+#[serde(rename_all = "snake_case")]
+pub enum Identity {
+    /// `GENERATED ALWAYS`: the database rejects explicit values.
+    Always,
+    /// `GENERATED BY DEFAULT`: explicit values override the sequence.
+    ByDefault,
+}
+
+impl Identity {
+    /// Decodes `pg_attribute.attidentity`.
+    fn from_attidentity(attidentity: &str) -> Option<Self> {
+        match attidentity {
+            "a" => Some(Self::Always),
+            "d" => Some(Self::ByDefault),
+            _ => None,
+        }
+    }
+}
+
+/// What happens to a column whose type the GraphQL mapping does not know,
+/// e.g. `uuid` or `point`: set for every column by
+/// [`Config::unsupported_types`](crate::Config::unsupported_types) and for
+/// one column by an `@unsupported skip|string|error` tag.
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, Default, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum UnsupportedTypes {
+    /// Leaves the column out of the schema with a warning.
+    Skip,
+    /// Exposes the column as a `String` holding the value's text, when it
+    /// has one. Such columns cannot be filtered on or written.
+    #[default]
+    String,
+    /// Fails the schema build.
+    Error,
+}
+
+impl UnsupportedTypes {
+    fn from_tag(tags: &SmartTags) -> Option<Self> {
+        match tags.word("unsupported")? {
+            "skip" => Some(Self::Skip),
+            "string" => Some(Self::String),
+            "error" => Some(Self::Error),
+            _ => None,
+        }
+    }
+}
+
+/// Parses a `@defaultSort created_at DESC, id` table tag into `{T}OrderBy`
+/// values (`CREATED_AT_DESC`, `ID_ASC`). The direction defaults to ascending.
+fn parse_default_sort(tags: &SmartTags) -> Vec<String> {
+    let Some(value) = tags.get("defaultSort") else {
+        return Vec::new();
+    };
+
+    value
+        .split(',')
+        .filter_map(|part| {
+            let mut words = part.split_whitespace();
+            let column = words.next()?;
+            let direction = match words.next().map(str::to_uppercase).as_deref() {
+                Some("DESC") => "DESC",
+                _ => "ASC",
+            };
+            Some(format!("{}_{direction}", column.to_uppercase()))
+        })
+        .collect()
 }
 
+/// Parses a `@stableOrder created_at, label` table tag into the column
+/// names it lists.
+fn parse_stable_order(tags: &SmartTags) -> Vec<String> {
+    let Some(value) = tags.get("stableOrder") else {
+        return Vec::new();
+    };
+
+    value
+        .split(',')
+        .map(str::trim)
+        .filter(|column| !column.is_empty())
+        .map(str::to_string)
+        .collect()
+}
+
+/// Parses an `@example <json>` tag, e.g. `@example {"title": "Hello"}` or
+/// `@example "Hello"`. Text after the JSON value is ignored; a tag whose value
+/// is not valid JSON is ignored as well.
+fn parse_example(tags: &SmartTags) -> Option<serde_json::Value> {
+    serde_json::Deserializer::from_str(tags.get("example")?)
+        .into_iter::<serde_json::Value>()
+        .next()?
+        .ok()
+}
+
+/// The GraphQL enum generated for an `@enum` table, shared by every column
+/// that references the table's primary key.
 #[derive(Clone, Debug, PartialEq)]
+pub struct EnumType {
+    name: String,
+    /// Primary key values, in the table's key order.
+    values: Vec<String>,
+    /// The enum item of each value, named by the inflector.
+    items: Vec<String>,
+}
+
+impl EnumType {
+    pub(crate) fn new(name: String, values: Vec<String>, inflector: &dyn Inflector) -> Self {
+        let items = values
+            .iter()
+            .map(|value| inflector.enum_value(value))
+            .collect();
+        Self {
+            name,
+            values,
+            items,
+        }
+    }
+
+    /// The GraphQL type name, e.g. `PostStatus` for `post_statuses`.
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    pub fn values(&self) -> &[String] {
+        &self.values
+    }
+
+    /// The enum items, in the order of [`values`](Self::values).
+    pub fn items(&self) -> &[String] {
+        &self.items
+    }
+
+    /// The enum item for the stored value `value`, e.g. `IN_REVIEW` for
+    /// `in_review`.
+    pub fn item(&self, value: &str) -> Option<&str> {
+        self.values
+            .iter()
+            .position(|v| v == value)
+            .map(|i| self.items[i].as_str())
+    }
+
+    /// The stored value for the enum item `item`, e.g. `in_review` for
+    /// `IN_REVIEW`.
+    pub fn db_value(&self, item: &str) -> Option<&str> {
+        self.items
+            .iter()
+            .position(|i| i == item)
+            .map(|i| self.values[i].as_str())
+    }
+}
+
+/// A unique index (or the unique index behind a primary key or `UNIQUE`
+/// constraint) over plain columns.
+///
+/// The constraint comment (or the index comment, for a bare unique index)
+/// may carry smart tags for the finder generated from the key:
+/// - `@fieldName userByLogin` renames it (default `userByEmail`).
+/// - `@omit` hides it.
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+pub struct UniqueKey {
+    name: String,
+    /// Key columns in index order.
+    columns: Vec<String>,
+    /// Whether the index has a `WHERE` predicate. Such an index only keeps
+    /// the rows it covers unique, so it cannot back a single-row lookup.
+    #[serde(default)]
+    partial: bool,
+    #[serde(default)]
+    comment: String,
+}
+
+impl UniqueKey {
+    pub(crate) fn new(name: String, columns: Vec<String>, partial: bool, comment: String) -> Self {
+        Self {
+            name,
+            columns,
+            partial,
+            comment,
+        }
+    }
+
+    pub fn comment(&self) -> &str {
+        &self.comment
+    }
+
+    /// The finder name set with a `@fieldName` tag.
+    pub fn field_name_tag(&self) -> Option<String> {
+        SmartTags::parse(&self.comment)
+            .word("fieldName")
+            .map(str::to_string)
+    }
+
+    /// Whether an `@omit` tag hides the key's finder.
+    pub fn omit(&self) -> bool {
+        Omit::new(&self.comment).read()
+    }
+
+    /// The index name.
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    pub fn columns(&self) -> &[String] {
+        &self.columns
+    }
+
+    pub fn is_partial(&self) -> bool {
+        self.partial
+    }
+}
+
+/// The kind of relation a [`Table`] was introspected from
+/// (`pg_class.relkind`).
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq)]
+#[serde(rename_all = "snake_case")]
 pub enum Relkind {
     Table,
+    /// A table declared `PARTITION BY`. Its partitions are not exposed.
+    PartitionedTable,
+    View,
     MaterializedView,
+    /// A table of a foreign data wrapper.
+    ForeignTable,
+}
+
+impl Relkind {
+    fn from_relkind(relkind: &str) -> Self {
+        match relkind {
+            "p" => Self::PartitionedTable,
+            "v" => Self::View,
+            "m" => Self::MaterializedView,
+            "f" => Self::ForeignTable,
+            _ => Self::Table,
+        }
+    }
+}
+
+/// Which statements Postgres accepts on a relation, as
+/// `pg_relation_is_updatable` reports them: always all three for tables,
+/// never any for materialized views, and for views and foreign tables
+/// whatever automatic updatability, `INSTEAD OF` triggers, rules or the
+/// foreign data wrapper provide.
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq)]
+pub struct Updatable {
+    pub insert: bool,
+    pub update: bool,
+    pub delete: bool,
 }
 
 #[cfg(test)]
@@ -64,20 +377,106 @@ impl Omit {
     }
 }
 
-#[derive(Clone, Debug)]
+#[derive(Serialize, Deserialize, Clone, Debug)]
 pub struct Column {
+    #[allow(dead_code)]
     id: u32,
     table_oid: u32,
     name: String,
+    #[allow(dead_code)]
+    #[serde(default)]
     comment: String,
+    #[serde(rename = "type", with = "type_oid")]
     r#type: Type,
     nullable: bool,
+    #[serde(default)]
     has_default: bool,
+    #[serde(default)]
+    identity: Option<Identity>,
+    /// A `GENERATED ALWAYS AS (...) STORED` column, computed by the database.
+    #[serde(default)]
+    generated: bool,
+    /// A view column computed from an expression, which cannot be written
+    /// through the view.
+    #[serde(default)]
+    derived: bool,
+    /// A `uuid` column whose default generates a random value.
+    #[serde(default)]
+    generates_uuid: bool,
+    #[serde(default)]
     omit: Omit,
+    #[serde(default)]
+    federation: Federation,
+    #[serde(default)]
+    descriptions: Descriptions,
+    /// Every smart tag of the comment.
+    #[serde(default)]
+    tags: SmartTags,
+    /// Set by the `@insertOnly` tag: writable on create, never updated.
+    #[serde(default)]
+    insert_only: bool,
+    /// Value of the `@example` tag, shown in descriptions and GraphiQL.
+    #[serde(default)]
+    example: Option<serde_json::Value>,
+    /// Set by `@createdAt` / `@updatedAt`: filled in by mutations, never
+    /// taken from input.
+    #[serde(default)]
+    auto_timestamp: Option<AutoTimestamp>,
+    /// Set by the `@encrypted` tag on a text column: values pass through
+    /// [`Config::encryption`](crate::Config::encryption) on write and read.
+    #[serde(default)]
+    encrypted: bool,
+    /// Field name chosen with the `@name` (or `@fieldName`) tag.
+    #[serde(default)]
+    name_tag: Option<String>,
+    /// Set by `@caseInsensitive` / `@caseSensitive`, or else from
+    /// [`Config::case_insensitive_conditions`](crate::Config::case_insensitive_conditions).
+    #[serde(default)]
+    case_insensitive: Option<bool>,
+    /// Set by the `@largeObject` tag on an `oid` column: the column holds a
+    /// large object, written from an upload.
+    #[serde(default)]
+    large_object: bool,
+    /// Set by the `@unsupported` tag; see [`UnsupportedTypes`].
+    #[serde(default)]
+    unsupported: Option<UnsupportedTypes>,
+    /// Whether mutations take the column's value as an `Upload`, see
+    /// [`Config::uploads`](crate::Config::uploads).
+    #[serde(skip)]
+    upload: bool,
+    /// GraphQL field name when it differs from the column name, e.g. an `id`
+    /// column exposed as `rowId` next to a Relay `id` global ID.
+    #[serde(skip)]
+    field_name: Option<String>,
+    /// Set when the column references an `@enum` table, which replaces its
+    /// scalar type with the table's enum.
+    #[serde(skip)]
+    enum_type: Option<Arc<EnumType>>,
+}
+
+/// Serializes a [`Type`] as its OID so catalogs can be saved and reloaded.
+/// Types unknown to `tokio_postgres` also carry their structure.
+pub(crate) mod type_oid {
+    use serde::{Deserialize, Deserializer, Serialize, Serializer, de::Error};
+    use tokio_postgres::types::Type;
+
+    use crate::models::types::SavedType;
+
+    pub fn serialize<S: Serializer>(ty: &Type, serializer: S) -> Result<S::Ok, S::Error> {
+        SavedType::new(ty).serialize(serializer)
+    }
+
+    pub fn deserialize<'de, D: Deserializer<'de>>(deserializer: D) -> Result<Type, D::Error> {
+        SavedType::deserialize(deserializer)?
+            .into_type()
+            .map_err(D::Error::custom)
+    }
 }
 
 impl Column {
-    pub(crate) fn form_row(row: &tokio_postgres::Row) -> Self {
+    /// Reads a row of the column introspection query, or `None` when the
+    /// column's type cannot be resolved.
+    pub(crate) fn form_row(row: &tokio_postgres::Row, types: &TypeRegistry) -> Option<Self> {
         let table_oid = row.try_get::<_, u32>(0).unwrap();
         let column_id = row.try_get::<_, i32>(1).unwrap() as u32;
         let column_name = row.try_get::<_, String>(2).unwrap();
@@ -85,10 +484,36 @@ impl Column {
         let nullable = row.try_get::<_, bool>(4).unwrap();
         let has_default = row.try_get::<_, bool>(5).unwrap();
         let comment = row.try_get::<_, String>(6).unwrap_or("".to_string());
-        let data_type = Type::from_oid(type_oid).expect("Data type is not supported");
-        let omit = Omit::new(&comment);
+        let identity = Identity::from_attidentity(&row.try_get::<_, String>(7).unwrap_or_default());
+        let generated = !row.try_get::<_, String>(8).unwrap_or_default().is_empty();
+        let derived = !row.try_get::<_, bool>(10).unwrap_or(true);
+        let data_type = types.resolve(type_oid)?;
+        let generates_uuid = *base_type(&data_type) == Type::UUID
+            && UUID_DEFAULT_REGEX.is_match(&row.try_get::<_, String>(9).unwrap_or_default());
+        let tags = SmartTags::parse(&comment);
+        let omit = Omit::from_tags(&tags);
+        let federation = Federation::new(&tags);
+        let descriptions = Descriptions::new(&tags);
+        let insert_only = tags.has("insertOnly");
+        let example = parse_example(&tags);
+        let auto_timestamp = AutoTimestamp::new(&tags);
+        let encrypted =
+            tags.has("encrypted") && matches!(data_type, Type::TEXT | Type::VARCHAR | Type::BPCHAR);
+        let name_tag = tags
+            .word("name")
+            .or_else(|| tags.word("fieldName"))
+            .map(str::to_string);
+        let case_insensitive = if tags.has("caseInsensitive") {
+            Some(true)
+        } else if tags.has("caseSensitive") {
+            Some(false)
+        } else {
+            None
+        };
+        let large_object = tags.has("largeObject") && *base_type(&data_type) == Type::OID;
+        let unsupported = UnsupportedTypes::from_tag(&tags);
 
-        Self {
+        Some(Self {
             id: column_id,
             table_oid,
             name: column_name,
@@ -96,10 +521,66 @@ impl Column {
             r#type: data_type,
             nullable,
             has_default,
+            identity,
+            generated,
+            derived,
+            generates_uuid,
             omit,
+            federation,
+            descriptions,
+            tags,
+            insert_only,
+            example,
+            auto_timestamp,
+            encrypted,
+            name_tag,
+            case_insensitive,
+            large_object,
+            unsupported,
+            upload: false,
+            field_name: None,
+            enum_type: None,
+        })
+    }
+
+    /// A column standing in for a procedure argument, so arguments share the
+    /// type mapping of table columns. Arguments are always nullable.
+    pub(crate) fn procedure_arg(name: &str, r#type: Type) -> Self {
+        Self {
+            id: 0,
+            table_oid: 0,
+            name: name.to_string(),
+            comment: String::new(),
+            r#type,
+            nullable: true,
+            has_default: false,
+            identity: None,
+            generated: false,
+            derived: false,
+            generates_uuid: false,
+            omit: Omit::default(),
+            federation: Federation::default(),
+            descriptions: Descriptions::default(),
+            tags: SmartTags::default(),
+            insert_only: false,
+            example: None,
+            auto_timestamp: None,
+            encrypted: false,
+            name_tag: None,
+            case_insensitive: None,
+            large_object: false,
+            unsupported: None,
+            upload: false,
+            field_name: None,
+            enum_type: None,
         }
     }
 
+    /// Exposes the column under `field_name` in GraphQL.
+    pub(crate) fn set_field_name(&mut self, field_name: &str) {
+        self.field_name = Some(field_name.to_string());
+    }
+
     pub fn table_oid(&self) -> &u32 {
         &self.table_oid
     }
@@ -108,10 +589,21 @@ impl Column {
         &self.name
     }
 
+    /// The name this column is exposed under in GraphQL: its own name unless
+    /// renamed to make room for a global ID field.
+    pub fn field_name(&self) -> &str {
+        self.field_name.as_deref().unwrap_or(&self.name)
+    }
+
     pub fn _type(&self) -> &Type {
         &self.r#type
     }
 
+    /// The column's type with domains unwrapped to the type they constrain.
+    pub fn base_type(&self) -> &Type {
+        base_type(&self.r#type)
+    }
+
     pub fn nullable(&self) -> bool {
         self.nullable
     }
@@ -120,20 +612,118 @@ impl Column {
         self.omit.read
     }
 
+    /// Whether the column is left out of `Create{T}Input`: `@omit create`, an
+    /// automatic timestamp, or a value only the database may write.
     pub fn omit_create(&self) -> bool {
-        self.omit.create
+        self.omit.create || self.auto_timestamp.is_some() || self.read_only()
     }
 
+    /// Whether the column is left out of `{T}Patch`: `@omit update`,
+    /// `@insertOnly`, an automatic timestamp, or a value only the database
+    /// may write.
     pub fn omit_update(&self) -> bool {
-        self.omit.update
+        self.omit.update || self.insert_only || self.auto_timestamp.is_some() || self.read_only()
+    }
+
+    /// Generated columns, `GENERATED ALWAYS` identities and derived view
+    /// columns reject explicit values.
+    fn read_only(&self) -> bool {
+        self.generated || self.derived || self.identity == Some(Identity::Always)
     }
 
+    /// Whether mutations set the column to `now()` themselves.
+    pub fn auto_timestamp(&self) -> Option<AutoTimestamp> {
+        self.auto_timestamp
+    }
+
+    /// Whether the column carries the `@encrypted` tag. Its stored values are
+    /// ciphertext, so it cannot be filtered or ordered by.
+    pub fn encrypted(&self) -> bool {
+        self.encrypted
+    }
+
+    /// The field name chosen with the column's `@name` or `@fieldName` tag. It is not
+    /// camelCased, and the column keeps it even when a relation wants the
+    /// same name.
+    pub fn name_tag(&self) -> Option<&str> {
+        self.name_tag.as_deref()
+    }
+
+    /// Whether `condition` equality on the column ignores case, comparing
+    /// `lower(column) = lower($n)`. Only text columns can.
+    pub fn case_insensitive(&self) -> bool {
+        self.case_insensitive == Some(true)
+            && matches!(*self.base_type(), Type::TEXT | Type::VARCHAR | Type::BPCHAR)
+    }
+
+    /// Whether the column carries the `@largeObject` tag.
+    pub fn large_object(&self) -> bool {
+        self.large_object
+    }
+
+    /// The column's `@unsupported` tag, if it has one.
+    pub fn unsupported_tag(&self) -> Option<UnsupportedTypes> {
+        self.unsupported
+    }
+
+    /// Whether mutations take an `Upload` for the column: a `bytea` or
+    /// `@largeObject` column, with uploads enabled.
+    pub fn upload(&self) -> bool {
+        self.upload
+    }
+
+    /// Whether the column carries the `@insertOnly` tag.
+    pub fn insert_only(&self) -> bool {
+        self.insert_only
+    }
+
+    #[allow(dead_code)]
     pub fn omit_delete(&self) -> bool {
         self.omit.delete
     }
 
+    /// Whether an insert may leave the column out: it has a default
+    /// expression or is an identity column.
     pub fn has_default(&self) -> bool {
-        self.has_default
+        self.has_default || self.identity.is_some()
+    }
+
+    pub fn identity(&self) -> Option<Identity> {
+        self.identity
+    }
+
+    /// Whether the column is a `uuid` whose default generates a random
+    /// value, e.g. `gen_random_uuid()`. Clients may still supply their own,
+    /// to know a row's ID before it is created.
+    pub fn generates_uuid(&self) -> bool {
+        self.generates_uuid
+    }
+
+    /// The enum this column is exposed as, when it references an `@enum`
+    /// table.
+    pub fn enum_type(&self) -> Option<&EnumType> {
+        self.enum_type.as_deref()
+    }
+
+    /// Federation directives from the column's smart tags.
+    pub fn federation(&self) -> &Federation {
+        &self.federation
+    }
+
+    /// Descriptions from `@description` tags, by locale.
+    pub fn descriptions(&self) -> &Descriptions {
+        &self.descriptions
+    }
+
+    /// The value of the column's `@example` tag.
+    pub fn example(&self) -> Option<&serde_json::Value> {
+        self.example.as_ref()
+    }
+
+    /// Every smart tag of the column's comment, including those no generator
+    /// reads.
+    pub fn tags(&self) -> &SmartTags {
+        &self.tags
     }
 }
 
@@ -148,20 +738,120 @@ impl Column {
             r#type,
             nullable,
             has_default: false,
+            identity: None,
+            generated: false,
+            derived: false,
+            generates_uuid: false,
             omit: Omit::for_test(omit_read),
+            federation: Federation::default(),
+            descriptions: Descriptions::default(),
+            tags: SmartTags::default(),
+            insert_only: false,
+            example: None,
+            auto_timestamp: None,
+            encrypted: false,
+            name_tag: None,
+            case_insensitive: None,
+            large_object: false,
+            unsupported: None,
+            upload: false,
+            field_name: None,
+            enum_type: None,
         }
     }
+
+    pub fn set_insert_only_for_test(&mut self) {
+        self.insert_only = true;
+    }
+
+    pub fn with_identity(mut self, identity: Identity) -> Self {
+        self.identity = Some(identity);
+        self
+    }
+
+    pub fn with_default(mut self) -> Self {
+        self.has_default = true;
+        self
+    }
+
+    pub fn with_uuid_default(mut self) -> Self {
+        self.has_default = true;
+        self.generates_uuid = true;
+        self
+    }
 }
 
-#[derive(Clone, Debug)]
+#[derive(Serialize, Deserialize, Clone, Debug)]
 pub struct Table {
     oid: u32,
     name: String,
     schema_name: String,
     relkind: Relkind,
+    #[serde(default)]
     comment: String,
+    /// Comment of the table's schema, whose `@omit` and `@subscribe` tags
+    /// apply to every table in it.
+    #[serde(default)]
+    schema_comment: String,
     columns: Vec<Arc<Column>>,
+    #[serde(default)]
     omit: Omit,
+    #[serde(default)]
+    federation: Federation,
+    #[serde(default)]
+    descriptions: Descriptions,
+    /// Every smart tag of the comment.
+    #[serde(default)]
+    tags: SmartTags,
+    /// `{T}OrderBy` values from the `@defaultSort` tag.
+    #[serde(default)]
+    default_sort: Vec<String>,
+    /// Column names from the `@stableOrder` tag.
+    #[serde(default)]
+    stable_order: Vec<String>,
+    /// Value of the `@example` tag: a sample `Create{T}Input`.
+    #[serde(default)]
+    example: Option<serde_json::Value>,
+    /// Set by the `@temporal` tag.
+    #[serde(default)]
+    temporal: Option<Temporal>,
+    /// The planner's row count estimate (`pg_class.reltuples`) when the
+    /// catalog was read; `None` if the table was never analyzed.
+    #[serde(default)]
+    estimated_rows: Option<u64>,
+    /// Primary key columns in key order; empty when the table has none.
+    #[serde(default)]
+    primary_key: Vec<String>,
+    /// Primary key values of an `@enum` table, loaded during introspection.
+    /// Empty for every other table.
+    #[serde(default)]
+    enum_values: Vec<String>,
+    /// Unique indexes, the primary key's included, in index name order.
+    #[serde(default)]
+    unique_keys: Vec<UniqueKey>,
+    /// The roles holding each privilege on the table.
+    #[serde(default)]
+    privileges: TablePrivileges,
+    /// `None` in catalogs saved before it was introspected; see
+    /// [`Table::updatable`].
+    #[serde(default)]
+    updatable: Option<Updatable>,
+    /// Columns leading a valid, non-partial btree index, which can return
+    /// rows in their order without sorting them.
+    #[serde(default)]
+    indexed_columns: Vec<String>,
+    /// Columns a valid, non-partial trigram index covers, which serves
+    /// `LIKE` and `ILIKE` with any pattern.
+    #[serde(default)]
+    trigram_indexed_columns: Vec<String>,
+    /// Columns leading a valid, non-partial btree index with a
+    /// `*_pattern_ops` class, which serves `LIKE` with a fixed prefix.
+    #[serde(default)]
+    prefix_indexed_columns: Vec<String>,
+    /// The configured inflector's name for the type, see
+    /// [`Table::type_name`].
+    #[serde(skip)]
+    inflected_type_name: Option<String>,
 }
 
 impl Table {
@@ -171,20 +861,64 @@ impl Table {
         let table_name = row.try_get::<_, String>(2).unwrap();
         let relkind_str = row.try_get::<_, String>(3).unwrap();
         let comment = row.try_get::<_, String>(4).unwrap_or("".to_string());
-        let omit = Omit::new(&comment);
+        let schema_comment = row.try_get::<_, String>(5).unwrap_or_default();
+        // Postgres reports -1 for tables that were never vacuumed or analyzed.
+        let estimated_rows = row
+            .try_get::<_, f64>(6)
+            .ok()
+            .filter(|rows| *rows >= 0.0)
+            .map(|rows| rows as u64);
+        let roles = |i: usize| public_or(row.try_get::<_, Vec<String>>(i).unwrap_or_default());
+        let privileges = TablePrivileges {
+            select: roles(7),
+            insert: roles(8),
+            update: roles(9),
+            delete: roles(10),
+        };
+        let updatable = Updatable {
+            insert: row.try_get::<_, bool>(11).unwrap_or_default(),
+            update: row.try_get::<_, bool>(12).unwrap_or_default(),
+            delete: row.try_get::<_, bool>(13).unwrap_or_default(),
+        };
+        let indexed_columns = row.try_get::<_, Vec<String>>(14).unwrap_or_default();
+        let trigram_indexed_columns = row.try_get::<_, Vec<String>>(15).unwrap_or_default();
+        let prefix_indexed_columns = row.try_get::<_, Vec<String>>(16).unwrap_or_default();
+        // Schema tags come first; the table's own tags add to them.
+        let tags = SmartTags::parse(&comment);
+        let omit = Omit::new(&schema_comment).union(Omit::from_tags(&tags));
+        let default_sort = parse_default_sort(&tags);
+        let stable_order = parse_stable_order(&tags);
+        let example = parse_example(&tags);
+        let temporal = Temporal::new(&tags);
+        let federation = Federation::new(&tags);
+        let descriptions = Descriptions::new(&tags);
 
         Self {
             oid,
             schema_name,
             name: table_name,
-            relkind: if relkind_str == "r" {
-                Relkind::Table
-            } else {
-                Relkind::MaterializedView
-            },
+            relkind: Relkind::from_relkind(&relkind_str),
             comment,
+            schema_comment,
             columns: Vec::new(),
             omit,
+            federation,
+            descriptions,
+            tags,
+            default_sort,
+            stable_order,
+            example,
+            temporal,
+            estimated_rows,
+            primary_key: Vec::new(),
+            enum_values: Vec::new(),
+            unique_keys: Vec::new(),
+            privileges,
+            updatable: Some(updatable),
+            indexed_columns,
+            trigram_indexed_columns,
+            prefix_indexed_columns,
+            inflected_type_name: None,
         }
     }
 
@@ -192,10 +926,192 @@ impl Table {
         self.columns.push(Arc::new(column));
     }
 
+    pub(crate) fn set_primary_key(&mut self, columns: Vec<String>) {
+        self.primary_key = columns;
+    }
+
+    pub(crate) fn push_unique_key(&mut self, key: UniqueKey) {
+        self.unique_keys.push(key);
+    }
+
+    /// Takes the columns of a `@primaryKey id` (or `@primaryKey a, b`) tag as
+    /// the primary key of a table or materialized view that has none, so it
+    /// gets finders, relations and mutations keyed on them. A tag naming an
+    /// unknown column is ignored with a warning.
+    pub(crate) fn apply_primary_key_tag(&mut self) {
+        let Some(value) = self.tags.get("primaryKey") else {
+            return;
+        };
+        if !self.primary_key.is_empty() {
+            return;
+        }
+        let columns: Vec<String> = value
+            .split(',')
+            .map(str::trim)
+            .filter(|column| !column.is_empty())
+            .map(str::to_string)
+            .collect();
+        let known = columns
+            .iter()
+            .all(|name| self.columns.iter().any(|c| c.name() == name));
+        if columns.is_empty() || !known {
+            eprintln!(
+                "[turbograph] ignoring @primaryKey {value} on {}.{}",
+                self.schema_name, self.name
+            );
+            return;
+        }
+        self.unique_keys.push(UniqueKey::new(
+            format!("{}_primary_key_tag", self.name),
+            columns.clone(),
+            false,
+            String::new(),
+        ));
+        self.primary_key = columns;
+    }
+
+    /// Exposes the column `name` under `field_name` in GraphQL.
+    pub(crate) fn rename_column_field(&mut self, name: &str, field_name: &str) {
+        for column in &mut self.columns {
+            if column.name() == name {
+                Arc::make_mut(column).set_field_name(field_name);
+            }
+        }
+    }
+
+    /// Forgets the `@omit` tags of the table and its columns.
+    pub(crate) fn set_inflected_type_name(&mut self, name: String) {
+        self.inflected_type_name = Some(name);
+    }
+
+    /// Omits the table, or those of its columns, that `@since` and `@until`
+    /// tags leave out of API `version`.
+    pub(crate) fn restrict_to_version(&mut self, version: &str) {
+        if !in_version(&self.tags, version) {
+            self.omit = Omit::all();
+        }
+        for column in &mut self.columns {
+            if !in_version(&column.tags, version) {
+                Arc::make_mut(column).omit = Omit::all();
+            }
+        }
+    }
+
+    /// Whether any column is readable, so that the type has a field for it.
+    pub fn has_readable_columns(&self) -> bool {
+        self.columns.iter().any(|c| !c.omit_read())
+    }
+
+    /// Leaves the table out, as `@omit` would.
+    pub(crate) fn omit_all(&mut self) {
+        self.omit = Omit::all();
+    }
+
+    /// Leaves the table's mutations out, as `@omit create,update,delete`
+    /// would.
+    pub(crate) fn omit_mutations(&mut self) {
+        self.omit = Omit {
+            read: self.omit.read,
+            ..Omit::all()
+        };
+    }
+
+    pub(crate) fn clear_omit(&mut self) {
+        self.omit = Omit::default();
+        for column in &mut self.columns {
+            Arc::make_mut(column).omit = Omit::default();
+        }
+    }
+
+    pub(crate) fn set_enum_values(&mut self, values: Vec<String>) {
+        self.enum_values = values;
+    }
+
+    /// Exposes the column `name` as `enum_type` instead of its scalar type.
+    pub(crate) fn set_column_enum(&mut self, name: &str, enum_type: Arc<EnumType>) {
+        for column in &mut self.columns {
+            if column.name() == name {
+                Arc::make_mut(column).enum_type = Some(enum_type.clone());
+            }
+        }
+    }
+
+    /// Makes `condition` equality case-insensitive on every column without a
+    /// `@caseInsensitive` or `@caseSensitive` tag, when `default` is set.
+    pub(crate) fn set_case_insensitive_default(&mut self, default: bool) {
+        for column in &mut self.columns {
+            if column.case_insensitive.is_none() && default {
+                Arc::make_mut(column).case_insensitive = Some(true);
+            }
+        }
+    }
+
+    /// Leaves the column named `name` out of every type and input, as an
+    /// `@omit` tag would.
+    pub(crate) fn omit_column(&mut self, name: &str) {
+        for column in self.columns.iter_mut().filter(|c| c.name() == name) {
+            Arc::make_mut(column).omit = Omit {
+                create: true,
+                read: true,
+                update: true,
+                delete: true,
+            };
+        }
+    }
+
+    /// Takes uploads for every `bytea` and `@largeObject` column.
+    pub(crate) fn enable_uploads(&mut self) {
+        for column in &mut self.columns {
+            if *column.base_type() == Type::BYTEA || column.large_object {
+                Arc::make_mut(column).upload = true;
+            }
+        }
+    }
+
     pub fn columns(&self) -> &[Arc<Column>] {
         &self.columns
     }
 
+    /// Primary key column names in key order; empty when there is none.
+    pub fn primary_key(&self) -> &[String] {
+        &self.primary_key
+    }
+
+    pub fn unique_keys(&self) -> &[UniqueKey] {
+        &self.unique_keys
+    }
+
+    /// The unique keys that identify at most one row and can be looked up by
+    /// GraphQL arguments: partial indexes, `@omit` keys and keys over hidden
+    /// columns are skipped, and keys over the same columns are only kept
+    /// once. The primary key comes first.
+    pub fn finder_keys(&self) -> Vec<&UniqueKey> {
+        let mut seen: Vec<Vec<&String>> = Vec::new();
+        let mut keys: Vec<&UniqueKey> = self
+            .unique_keys
+            .iter()
+            .filter(|key| !key.is_partial() && !key.omit())
+            .filter(|key| {
+                key.columns().iter().all(|name| {
+                    self.columns
+                        .iter()
+                        .any(|c| c.name() == name && !c.omit_read())
+                })
+            })
+            .collect();
+        keys.sort_by_key(|key| key.columns() != self.primary_key.as_slice());
+        keys.retain(|key| {
+            let mut columns: Vec<&String> = key.columns().iter().collect();
+            columns.sort();
+            if seen.contains(&columns) {
+                return false;
+            }
+            seen.push(columns);
+            true
+        });
+        keys
+    }
+
     pub fn oid(&self) -> &u32 {
         &self.oid
     }
@@ -208,24 +1124,189 @@ impl Table {
         &self.schema_name
     }
 
+    pub fn comment(&self) -> &str {
+        &self.comment
+    }
+
+    /// The comment of the table's schema.
+    pub fn schema_comment(&self) -> &str {
+        &self.schema_comment
+    }
+
+    /// The GraphQL type name: the `@name` tag, or the configured
+    /// [`Inflector::type_name`], by default the singular PascalCase table
+    /// name (`BlogPost` for `blog_posts`).
     pub fn type_name(&self) -> String {
-        to_pascal_case(&singularize(self.name()))
+        match (self.tags.word("name"), &self.inflected_type_name) {
+            (Some(name), _) => name.to_string(),
+            (None, Some(name)) => name.clone(),
+            (None, None) => DefaultInflector.type_name(self),
+        }
     }
 
     pub fn omit_read(&self) -> bool {
         self.omit.read
     }
 
+    pub fn relkind(&self) -> Relkind {
+        self.relkind
+    }
+
+    /// Which statements Postgres accepts on the relation. Catalogs saved
+    /// before updatability was introspected treat every relation but a
+    /// materialized view as a table.
+    pub fn updatable(&self) -> Updatable {
+        self.updatable.unwrap_or(Updatable {
+            insert: self.relkind != Relkind::MaterializedView,
+            update: self.relkind != Relkind::MaterializedView,
+            delete: self.relkind != Relkind::MaterializedView,
+        })
+    }
+
     pub fn omit_create(&self) -> bool {
-        self.omit.create || self.relkind == Relkind::MaterializedView
+        self.omit.create || !self.updatable().insert
     }
 
     pub fn omit_update(&self) -> bool {
-        self.omit.update || self.relkind == Relkind::MaterializedView
+        self.omit.update || !self.updatable().update
     }
 
     pub fn omit_delete(&self) -> bool {
-        self.omit.delete || self.relkind == Relkind::MaterializedView
+        self.omit.delete || !self.updatable().delete
+    }
+
+    /// The ordering applied when a query passes no `orderBy`, as `{T}OrderBy`
+    /// values. Empty unless the table has a `@defaultSort` tag.
+    pub fn default_sort(&self) -> &[String] {
+        &self.default_sort
+    }
+
+    /// Columns that order rows with equal `orderBy` values, from the
+    /// `@stableOrder` tag. Empty unless the table has one.
+    pub fn stable_order(&self) -> &[String] {
+        &self.stable_order
+    }
+
+    /// The value of the table's `@example` tag.
+    pub fn example(&self) -> Option<&serde_json::Value> {
+        self.example.as_ref()
+    }
+
+    /// The history table of a table tagged `@temporal`, whose queries then
+    /// take an `asOf` argument. `None` unless the tag's period column exists
+    /// and is a `tstzrange`.
+    pub fn temporal(&self) -> Option<&Temporal> {
+        let temporal = self.temporal.as_ref()?;
+        let period = self
+            .columns
+            .iter()
+            .find(|c| c.name() == temporal.period())?;
+        (*period.base_type() == Type::TSTZ_RANGE).then_some(temporal)
+    }
+
+    /// The planner's row estimate when the catalog was read; `None` if the
+    /// table was never analyzed.
+    pub fn estimated_rows(&self) -> Option<u64> {
+        self.estimated_rows
+    }
+
+    pub fn indexed_columns(&self) -> &[String] {
+        &self.indexed_columns
+    }
+
+    /// Whether `{T}OrderBy` offers `column` when tables estimated above
+    /// `indexed_order_by_rows` rows only order by indexed columns: always
+    /// on smaller tables, and on larger ones for indexed columns and those
+    /// tagged `@sortable`.
+    pub fn orderable(&self, column: &Column, indexed_order_by_rows: Option<u64>) -> bool {
+        let large = self
+            .estimated_rows
+            .is_some_and(|rows| indexed_order_by_rows.is_some_and(|limit| rows > limit));
+        !large || column.tags().has("sortable") || self.indexed_columns.contains(column.name())
+    }
+
+    /// Whether an index serves `LIKE` on `column`, or `ILIKE` when
+    /// `case_insensitive`.
+    pub fn pattern_indexed(&self, column: &Column, case_insensitive: bool) -> bool {
+        self.trigram_indexed_columns.contains(column.name())
+            || (!case_insensitive && self.prefix_indexed_columns.contains(column.name()))
+    }
+
+    pub fn privileges(&self) -> &TablePrivileges {
+        &self.privileges
+    }
+
+    /// Whether the table carries the `@enum` tag: its primary key values
+    /// become a GraphQL enum and the table itself is not exposed. Only
+    /// tables with a single text primary key column qualify.
+    pub fn is_enum(&self) -> bool {
+        let [key] = self.primary_key.as_slice() else {
+            return false;
+        };
+        let text_key = self.columns.iter().any(|c| {
+            c.name() == key && matches!(*c._type(), Type::TEXT | Type::VARCHAR | Type::BPCHAR)
+        });
+        text_key && self.tags.has("enum")
+    }
+
+    /// Whether the schema exposes the table for `privilege`: every query
+    /// field, relation, function result and type membership that would
+    /// reach its rows is generated only when it is exposed for `Select`, and
+    /// each mutation only when it is exposed for the matching write. Tables
+    /// tagged `@omit read` are exposed for nothing, and `@enum` tables only
+    /// as their enum.
+    pub fn is_exposed_for(&self, privilege: Privilege) -> bool {
+        if self.omit.read || self.is_enum() {
+            return false;
+        }
+        match privilege {
+            Privilege::Select => true,
+            Privilege::Insert => !self.omit_create(),
+            Privilege::Update => !self.omit_update(),
+            Privilege::Delete => !self.omit_delete(),
+        }
+    }
+
+    /// Whether the table, or its schema, carries the `@subscribe` tag and
+    /// the table can report its changes: change events identify rows by
+    /// primary key, so tables without one do not qualify, and come from row
+    /// triggers, which views and materialized views cannot have.
+    pub fn subscribe(&self) -> bool {
+        !self.primary_key.is_empty()
+            && !matches!(self.relkind, Relkind::View | Relkind::MaterializedView)
+            && (self.tags.has("subscribe")
+                || SmartTags::parse(&self.schema_comment).has("subscribe"))
+    }
+
+    /// Whether the table is a materialized view tagged `@refreshable`.
+    pub fn refreshable(&self) -> bool {
+        self.relkind == Relkind::MaterializedView && self.tags.has("refreshable")
+    }
+
+    /// Primary key values of an `@enum` table.
+    pub fn enum_values(&self) -> &[String] {
+        &self.enum_values
+    }
+
+    /// Federation directives from the table's smart tags.
+    pub fn federation(&self) -> &Federation {
+        &self.federation
+    }
+
+    /// Descriptions from `@description` tags, by locale.
+    pub fn descriptions(&self) -> &Descriptions {
+        &self.descriptions
+    }
+
+    /// Every smart tag of the table's comment, including those no generator
+    /// reads.
+    pub fn tags(&self) -> &SmartTags {
+        &self.tags
+    }
+
+    /// Whether the table or any of its columns asks for a federation directive.
+    pub fn uses_federation(&self) -> bool {
+        !self.federation.is_empty() || self.columns.iter().any(|c| !c.federation.is_empty())
     }
 }
 
@@ -238,8 +1319,312 @@ impl Table {
             schema_name: "public".to_string(),
             relkind: Relkind::Table,
             comment: String::new(),
+            schema_comment: String::new(),
             columns: columns.into_iter().map(Arc::new).collect(),
             omit: Omit::for_test(false),
+            federation: Federation::default(),
+            descriptions: Descriptions::default(),
+            tags: SmartTags::default(),
+            default_sort: Vec::new(),
+            stable_order: Vec::new(),
+            example: None,
+            temporal: None,
+            estimated_rows: None,
+            primary_key: Vec::new(),
+            enum_values: Vec::new(),
+            unique_keys: Vec::new(),
+            privileges: TablePrivileges::default(),
+            updatable: None,
+            indexed_columns: Vec::new(),
+            trigram_indexed_columns: Vec::new(),
+            prefix_indexed_columns: Vec::new(),
+            inflected_type_name: None,
+        }
+    }
+
+    pub fn with_unique_key(self, name: &str, columns: &[&str], partial: bool) -> Self {
+        self.with_commented_unique_key(name, columns, partial, "")
+    }
+
+    pub fn with_commented_unique_key(
+        mut self,
+        name: &str,
+        columns: &[&str],
+        partial: bool,
+        comment: &str,
+    ) -> Self {
+        let columns = columns.iter().map(|c| c.to_string()).collect();
+        self.unique_keys.push(UniqueKey::new(
+            name.to_string(),
+            columns,
+            partial,
+            comment.to_string(),
+        ));
+        self
+    }
+
+    pub fn with_oid(mut self, oid: u32) -> Self {
+        self.oid = oid;
+        self
+    }
+
+    pub fn with_primary_key(mut self, columns: &[&str]) -> Self {
+        self.primary_key = columns.iter().map(|c| c.to_string()).collect();
+        self
+    }
+
+    pub fn with_comment(mut self, comment: &str) -> Self {
+        self.comment = comment.to_string();
+        self.tags = SmartTags::parse(comment);
+        self.example = parse_example(&self.tags);
+        self.temporal = Temporal::new(&self.tags);
+        self.stable_order = parse_stable_order(&self.tags);
+        self
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_omit_bare_omits_everything() {
+        let omit = Omit::new("Internal. @omit");
+        assert!(omit.read && omit.create && omit.update && omit.delete);
+    }
+
+    #[test]
+    fn test_omit_all_keyword() {
+        let omit = Omit::new("@omit all");
+        assert!(omit.read && omit.create && omit.update && omit.delete);
+    }
+
+    #[test]
+    fn test_omit_listed_operations() {
+        let omit = Omit::new("@omit create,update,delete");
+        assert!(!omit.read);
+        assert!(omit.create && omit.update && omit.delete);
+    }
+
+    #[test]
+    fn test_insert_only_tag_omits_update() {
+        assert!(SmartTags::parse("Author. @insertOnly").has("insertOnly"));
+        assert!(!SmartTags::parse("@insertOnlyish").has("insertOnly"));
+        let mut column = Column::new_for_test("created_by", Type::TEXT, false, false);
+        assert!(!column.omit_update());
+        column.set_insert_only_for_test();
+        assert!(column.omit_update() && !column.omit_create());
+    }
+
+    #[test]
+    fn test_timestamp_tags() {
+        assert_eq!(
+            AutoTimestamp::new(&SmartTags::parse("Created. @createdAt")),
+            Some(AutoTimestamp::Created)
+        );
+        assert_eq!(
+            AutoTimestamp::new(&SmartTags::parse("@updatedAt")),
+            Some(AutoTimestamp::Updated)
+        );
+        assert_eq!(AutoTimestamp::new(&SmartTags::parse("@updatedAtish")), None);
+        assert_eq!(AutoTimestamp::new(&SmartTags::parse("Last change.")), None);
+    }
+
+    #[test]
+    fn test_unsupported_tag() {
+        assert_eq!(
+            UnsupportedTypes::from_tag(&SmartTags::parse("Location. @unsupported skip")),
+            Some(UnsupportedTypes::Skip)
+        );
+        assert_eq!(
+            UnsupportedTypes::from_tag(&SmartTags::parse("@unsupported error")),
+            Some(UnsupportedTypes::Error)
+        );
+        assert_eq!(
+            UnsupportedTypes::from_tag(&SmartTags::parse("@unsupported maybe")),
+            None
+        );
+        assert_eq!(
+            UnsupportedTypes::from_tag(&SmartTags::parse("Location.")),
+            None
+        );
+    }
+
+    #[test]
+    fn test_identity_columns() {
+        assert_eq!(Identity::from_attidentity("a"), Some(Identity::Always));
+        assert_eq!(Identity::from_attidentity("d"), Some(Identity::ByDefault));
+        assert_eq!(Identity::from_attidentity(""), None);
+
+        let id = Column::new_for_test("id", Type::INT4, false, false);
+        let always = id.clone().with_identity(Identity::Always);
+        assert!(always.has_default() && always.omit_create() && always.omit_update());
+        let by_default = id.with_identity(Identity::ByDefault);
+        assert!(by_default.has_default() && !by_default.omit_create());
+    }
+
+    #[test]
+    fn test_uuid_default_regex() {
+        for default in [
+            "gen_random_uuid()",
+            "public.uuid_generate_v4()",
+            "extensions.uuid_generate_v1mc()",
+            "uuidv7()",
+        ] {
+            assert!(UUID_DEFAULT_REGEX.is_match(default), "{default}");
         }
+        assert!(!UUID_DEFAULT_REGEX.is_match("'00000000-0000-0000-0000-000000000000'::uuid"));
+        assert!(!UUID_DEFAULT_REGEX.is_match("uuid_generate_v5(uuid_ns_url(), 'x')"));
+    }
+
+    #[test]
+    fn test_finder_keys_skip_partial_and_duplicate_indexes() {
+        let col = |name: &str| Column::new_for_test(name, Type::INT4, false, false);
+        let table = Table::new_for_test("members", vec![col("id"), col("org_id"), col("user_id")])
+            .with_primary_key(&["id"])
+            .with_unique_key("members_org_user", &["org_id", "user_id"], false)
+            .with_unique_key("members_pkey", &["id"], false)
+            .with_unique_key("members_user_org", &["user_id", "org_id"], false)
+            .with_unique_key("members_active_user", &["user_id"], true)
+            .with_unique_key("members_ghost", &["ghost_id"], false);
+
+        let names: Vec<&str> = table.finder_keys().iter().map(|k| k.name()).collect();
+        assert_eq!(names, ["members_pkey", "members_org_user"]);
+    }
+
+    #[test]
+    fn test_unique_key_comment_tags() {
+        let col = |name: &str| Column::new_for_test(name, Type::TEXT, false, false);
+        let table = Table::new_for_test("users", vec![col("id"), col("email"), col("login")])
+            .with_primary_key(&["id"])
+            .with_unique_key("users_pkey", &["id"], false)
+            .with_commented_unique_key(
+                "users_email_key",
+                &["email"],
+                false,
+                "@fieldName userByMail",
+            )
+            .with_commented_unique_key("users_login_key", &["login"], false, "Legacy. @omit");
+
+        let keys = table.finder_keys();
+        let names: Vec<&str> = keys.iter().map(|k| k.name()).collect();
+        assert_eq!(names, ["users_pkey", "users_email_key"]);
+        assert_eq!(keys[0].field_name_tag(), None);
+        assert_eq!(keys[1].field_name_tag().as_deref(), Some("userByMail"));
+    }
+
+    #[test]
+    fn test_example_tag() {
+        let example = |comment| parse_example(&SmartTags::parse(comment));
+        assert_eq!(
+            example("Posts. @example {\"title\": \"Hello\"} @omit delete"),
+            Some(serde_json::json!({ "title": "Hello" }))
+        );
+        assert_eq!(
+            example("@example \"Hello\""),
+            Some(serde_json::json!("Hello"))
+        );
+        assert_eq!(example("@example {broken"), None);
+        assert_eq!(example("No tags."), None);
+        assert_eq!(
+            example("Mail support@example \"Hello\" @example 1"),
+            Some(serde_json::json!(1))
+        );
+        assert_eq!(example("@examples \"Hello\""), None);
+    }
+
+    #[test]
+    fn test_omit_union() {
+        let schema = Omit::new("@omit create,delete");
+        let table = Omit::new("@omit update");
+        let omit = schema.union(table);
+        assert!(omit.create && omit.update && omit.delete && !omit.read);
+    }
+
+    #[test]
+    fn test_omit_absent() {
+        assert_eq!(Omit::new("Registered users."), Omit::for_test(false));
+    }
+
+    #[test]
+    fn test_default_sort_single_column() {
+        assert_eq!(
+            parse_default_sort(&SmartTags::parse("Posts. @defaultSort created_at DESC")),
+            ["CREATED_AT_DESC"]
+        );
+    }
+
+    #[test]
+    fn test_default_sort_multiple_columns_default_ascending() {
+        assert_eq!(
+            parse_default_sort(&SmartTags::parse("@defaultSort rank desc, id @omit delete")),
+            ["RANK_DESC", "ID_ASC"]
+        );
+    }
+
+    #[test]
+    fn test_default_sort_absent() {
+        assert!(parse_default_sort(&SmartTags::parse("Registered users.")).is_empty());
+    }
+
+    #[test]
+    fn test_name_tag_renames_type() {
+        assert_eq!(
+            Table::new_for_test("blog_posts", vec![]).type_name(),
+            "BlogPost"
+        );
+        let table = Table::new_for_test("blog_posts", vec![]).with_comment("@name Article");
+        assert_eq!(table.type_name(), "Article");
+    }
+
+    #[test]
+    fn test_temporal_needs_a_tstzrange_period() {
+        let comment = "@temporal history_table:posts_history";
+        let period = |ty| Column::new_for_test("sys_period", ty, false, false);
+        let table =
+            Table::new_for_test("posts", vec![period(Type::TSTZ_RANGE)]).with_comment(comment);
+        assert_eq!(table.temporal().unwrap().history_table(), "posts_history");
+
+        let table =
+            Table::new_for_test("posts", vec![period(Type::TS_RANGE)]).with_comment(comment);
+        assert!(table.temporal().is_none());
+        assert!(
+            Table::new_for_test("posts", vec![])
+                .with_comment(comment)
+                .temporal()
+                .is_none()
+        );
+    }
+
+    #[test]
+    fn test_primary_key_tag() {
+        let col = |name: &str| Column::new_for_test(name, Type::INT4, false, false);
+        let mut table = Table::new_for_test("user_stats", vec![col("user_id"), col("day")])
+            .with_comment("@primaryKey user_id, day");
+        table.apply_primary_key_tag();
+        assert_eq!(table.primary_key(), ["user_id", "day"]);
+        assert_eq!(table.finder_keys()[0].columns(), ["user_id", "day"]);
+
+        let mut keyed = Table::new_for_test("users", vec![col("id"), col("day")])
+            .with_primary_key(&["id"])
+            .with_comment("@primaryKey day");
+        keyed.apply_primary_key_tag();
+        assert_eq!(keyed.primary_key(), ["id"]);
+
+        let mut unknown =
+            Table::new_for_test("totals", vec![col("day")]).with_comment("@primaryKey missing");
+        unknown.apply_primary_key_tag();
+        assert!(unknown.primary_key().is_empty());
+    }
+
+    #[test]
+    fn test_stable_order_columns() {
+        assert_eq!(
+            parse_stable_order(&SmartTags::parse(
+                "Daily totals. @stableOrder day, region @omit create"
+            )),
+            ["day", "region"]
+        );
+        assert!(parse_stable_order(&SmartTags::parse("@defaultSort day")).is_empty());
     }
 }
@@ -0,0 +1,92 @@
+use serde::{Deserialize, Serialize};
+
+use crate::models::smart_tags::SmartTags;
+
+/// A system-versioned table, declared with a table tag such as
+/// `@temporal history_table:posts_history period:sys_period`: the table
+/// holds the current rows and `history_table` the rows they replaced, each
+/// stamped with the `tstzrange` it was valid for. The history table may be
+/// schema-qualified and defaults to the table's schema; the period column
+/// defaults to `sys_period`, as with the `temporal_tables` extension.
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+pub struct Temporal {
+    history_schema: Option<String>,
+    history_table: String,
+    period: String,
+}
+
+impl Temporal {
+    /// Reads the `@temporal` tag; `None` without one or without a
+    /// `history_table:`.
+    pub(crate) fn new(tags: &SmartTags) -> Option<Self> {
+        let mut history = None;
+        let mut period = "sys_period".to_string();
+        for word in tags.get("temporal")?.split_whitespace() {
+            if let Some(table) = word.strip_prefix("history_table:") {
+                history = Some(table);
+            } else if let Some(column) = word.strip_prefix("period:") {
+                period = column.to_string();
+            }
+        }
+        let (history_schema, history_table) = match history?.split_once('.') {
+            Some((schema, table)) => (Some(schema.to_string()), table.to_string()),
+            None => (None, history?.to_string()),
+        };
+        (!history_table.is_empty()).then_some(Self {
+            history_schema,
+            history_table,
+            period,
+        })
+    }
+
+    /// The schema of the history table, when the tag names one.
+    pub fn history_schema(&self) -> Option<&str> {
+        self.history_schema.as_deref()
+    }
+
+    pub fn history_table(&self) -> &str {
+        &self.history_table
+    }
+
+    /// The `tstzrange` column each row is valid for.
+    pub fn period(&self) -> &str {
+        &self.period
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_history_table_and_default_period() {
+        let temporal = Temporal::new(&SmartTags::parse(
+            "Posts. @temporal history_table:posts_history",
+        ))
+        .unwrap();
+        assert_eq!(temporal.history_schema(), None);
+        assert_eq!(temporal.history_table(), "posts_history");
+        assert_eq!(temporal.period(), "sys_period");
+    }
+
+    #[test]
+    fn test_qualified_history_table_and_period() {
+        let temporal = Temporal::new(&SmartTags::parse(
+            "@temporal period:valid history_table:audit.posts",
+        ))
+        .unwrap();
+        assert_eq!(temporal.history_schema(), Some("audit"));
+        assert_eq!(temporal.history_table(), "posts");
+        assert_eq!(temporal.period(), "valid");
+    }
+
+    #[test]
+    fn test_missing_history_table() {
+        assert_eq!(Temporal::new(&SmartTags::parse("@temporal")), None);
+        assert_eq!(
+            Temporal::new(&SmartTags::parse("@temporal history_table:")),
+            None
+        );
+        assert_eq!(Temporal::new(&SmartTags::parse("Posts.")), None);
+    }
+}
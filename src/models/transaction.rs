@@ -1,3 +1,5 @@
+use std::collections::BTreeMap;
+
 /// A value type used in per-transaction `SET LOCAL` settings.
 #[derive(Clone)]
 pub enum TransactionSettingsValue {
@@ -18,6 +20,14 @@ pub struct TransactionConfig {
     pub role: Option<String>,
     pub timeout_seconds: Option<u64>,
     pub settings: Vec<(String, String)>,
+    /// How many times a transaction that fails with a serialization failure
+    /// (`40001`) or deadlock (`40P01`) is re-run before the error is returned.
+    pub max_retries: u32,
+    /// Total wall-clock budget for the whole GraphQL request, in milliseconds.
+    /// Every statement runs with a `statement_timeout` no larger than what is
+    /// left of it, and fields that start after it is spent fail immediately
+    /// instead of stalling the response.
+    pub budget_ms: Option<u64>,
 }
 
 impl Default for TransactionConfig {
@@ -29,6 +39,146 @@ impl Default for TransactionConfig {
             role: None,
             timeout_seconds: None,
             settings: Vec::new(),
+            max_retries: 3,
+            budget_ms: None,
         }
     }
 }
+
+/// The role and custom settings a request's transactions run with, for
+/// row-level security policies that read them with `current_setting()`.
+///
+/// Inject via `Request::new(query).data(PgSettings::from_jwt_claims(&claims))`
+/// next to, or instead of, a [`TransactionConfig`]: the role replaces the
+/// configuration's, the settings are applied after its own, and a request
+/// without a role runs as [`Config::default_role`](crate::Config::default_role).
+///
+/// ```rust,ignore
+/// let settings = PgSettings::new()
+///     .role("app_user")
+///     .set("jwt.claims.user_id", "42");
+/// ```
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct PgSettings {
+    pub role: Option<String>,
+    /// Settings by name, e.g. `jwt.claims.user_id`. Custom names need a
+    /// prefix followed by a dot.
+    pub settings: BTreeMap<String, String>,
+}
+
+impl PgSettings {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn role(mut self, role: impl Into<String>) -> Self {
+        self.role = Some(role.into());
+        self
+    }
+
+    pub fn set(mut self, name: impl Into<String>, value: impl Into<String>) -> Self {
+        self.settings.insert(name.into(), value.into());
+        self
+    }
+
+    /// The settings for a verified JWT's claims: each claim becomes
+    /// `jwt.claims.<claim>`, and the `role` claim also picks the role.
+    /// Strings are set as is and other values as their JSON text; `null`
+    /// claims are left out.
+    pub fn from_jwt_claims(claims: &serde_json::Map<String, serde_json::Value>) -> Self {
+        let mut settings = Self::new();
+        for (claim, value) in claims {
+            let value = match value {
+                serde_json::Value::Null => continue,
+                serde_json::Value::String(text) => text.clone(),
+                other => other.to_string(),
+            };
+            if claim == "role" {
+                settings.role = Some(value.clone());
+            }
+            settings
+                .settings
+                .insert(format!("jwt.claims.{claim}"), value);
+        }
+        settings
+    }
+
+    /// Adds the role and settings to `transaction`.
+    pub fn apply_to(&self, transaction: &mut TransactionConfig) {
+        if let Some(role) = &self.role {
+            transaction.role = Some(role.clone());
+        }
+        transaction.settings.extend(
+            self.settings
+                .iter()
+                .map(|(name, value)| (name.clone(), value.clone())),
+        );
+    }
+}
+
+/// Which pool a request's statements run on.
+///
+/// Inject via `Request::new(query).data(ExecutionLane::Background)` for work
+/// that must not compete with interactive traffic, such as the initial fetch
+/// and refetches behind subscriptions. Background requests run through the
+/// same resolvers on [`Config::background_pool`](crate::Config::background_pool)
+/// when one is configured, so a burst of them can only exhaust that pool.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum ExecutionLane {
+    /// The main pool. Used when no lane is given.
+    #[default]
+    Interactive,
+    /// The background pool, falling back to the main pool without one.
+    Background,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_settings_from_jwt_claims() {
+        let claims = serde_json::json!({
+            "role": "app_user",
+            "user_id": 42,
+            "email": "a@example.com",
+            "scopes": ["read"],
+            "org": null,
+        });
+        let settings = PgSettings::from_jwt_claims(claims.as_object().unwrap());
+        assert_eq!(
+            settings,
+            PgSettings::new()
+                .role("app_user")
+                .set("jwt.claims.role", "app_user")
+                .set("jwt.claims.user_id", "42")
+                .set("jwt.claims.email", "a@example.com")
+                .set("jwt.claims.scopes", r#"["read"]"#)
+        );
+    }
+
+    #[test]
+    fn test_apply_replaces_role_and_adds_settings() {
+        let mut transaction = TransactionConfig {
+            role: Some("anonymous".into()),
+            settings: vec![("app.tenant".into(), "1".into())],
+            ..TransactionConfig::default()
+        };
+        PgSettings::new()
+            .set("jwt.claims.user_id", "7")
+            .apply_to(&mut transaction);
+        assert_eq!(transaction.role.as_deref(), Some("anonymous"));
+
+        PgSettings::new()
+            .role("app_user")
+            .apply_to(&mut transaction);
+        assert_eq!(transaction.role.as_deref(), Some("app_user"));
+        assert_eq!(
+            transaction.settings,
+            [
+                ("app.tenant".to_string(), "1".to_string()),
+                ("jwt.claims.user_id".to_string(), "7".to_string()),
+            ]
+        );
+    }
+}
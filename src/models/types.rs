@@ -0,0 +1,270 @@
+//! Resolution of type OIDs that `tokio_postgres` does not know: enums,
+//! domains, composite types, extension base types and their arrays. The
+//! [`TypeRegistry`] is loaded from `pg_type` during introspection and turns
+//! such an OID into a [`Type`] carrying its structure in [`Type::kind`].
+
+use std::collections::HashMap;
+use std::sync::Mutex;
+
+use serde::{Deserialize, Serialize};
+use tokio_postgres::types::{Field, Kind, Type};
+
+/// The structure of a non-builtin type as read from `pg_type`; other types
+/// are referenced by OID.
+#[derive(Clone, Debug, PartialEq)]
+enum TypeShape {
+    /// An extension base type such as `citext`, with its own I/O functions.
+    Base,
+    Enum(Vec<String>),
+    Domain(u32),
+    Composite(Vec<(String, u32)>),
+    Array(u32),
+}
+
+#[derive(Clone, Debug)]
+struct TypeEntry {
+    schema: String,
+    name: String,
+    shape: TypeShape,
+}
+
+/// Resolves type OIDs to [`Type`]s, caching every type it has built.
+#[derive(Default)]
+pub(crate) struct TypeRegistry {
+    entries: HashMap<u32, TypeEntry>,
+    resolved: Mutex<HashMap<u32, Type>>,
+}
+
+impl TypeRegistry {
+    /// Reads the rows of the type introspection query.
+    pub(crate) fn from_rows(rows: &[tokio_postgres::Row]) -> Self {
+        let entries = rows
+            .iter()
+            .filter_map(|row| {
+                let oid: u32 = row.get(0);
+                let shape = match row.get::<_, String>(3).as_str() {
+                    _ if row.get::<_, u32>(5) != 0 => TypeShape::Array(row.get(5)),
+                    "b" => TypeShape::Base,
+                    "e" => TypeShape::Enum(row.get(6)),
+                    "d" => TypeShape::Domain(row.get(4)),
+                    "c" => {
+                        let names: Vec<String> = row.get(7);
+                        let types: Vec<u32> = row.get(8);
+                        TypeShape::Composite(names.into_iter().zip(types).collect())
+                    }
+                    _ => return None,
+                };
+                let entry = TypeEntry {
+                    schema: row.get(1),
+                    name: row.get(2),
+                    shape,
+                };
+                Some((oid, entry))
+            })
+            .collect();
+
+        Self {
+            entries,
+            resolved: Mutex::default(),
+        }
+    }
+
+    /// The type with `oid`, or `None` when neither `tokio_postgres` nor the
+    /// registry knows it (e.g. a range type).
+    pub(crate) fn resolve(&self, oid: u32) -> Option<Type> {
+        if let Some(ty) = Type::from_oid(oid) {
+            return Some(ty);
+        }
+        if let Some(ty) = self.resolved.lock().unwrap().get(&oid) {
+            return Some(ty.clone());
+        }
+
+        let entry = self.entries.get(&oid)?.clone();
+        let kind = match entry.shape {
+            TypeShape::Base => Kind::Simple,
+            TypeShape::Enum(labels) => Kind::Enum(labels),
+            TypeShape::Domain(base) => Kind::Domain(self.resolve(base)?),
+            TypeShape::Array(element) => Kind::Array(self.resolve(element)?),
+            TypeShape::Composite(fields) => Kind::Composite(
+                fields
+                    .into_iter()
+                    .map(|(name, oid)| Some(Field::new(name, self.resolve(oid)?)))
+                    .collect::<Option<_>>()?,
+            ),
+        };
+        let ty = Type::new(entry.name, oid, kind, entry.schema);
+        self.resolved.lock().unwrap().insert(oid, ty.clone());
+        Some(ty)
+    }
+}
+
+/// A [`Type`] as saved in a catalog: builtins by OID, other types with the
+/// structure needed to rebuild them without a database.
+#[derive(Serialize, Deserialize)]
+#[serde(untagged)]
+pub(crate) enum SavedType {
+    Builtin(u32),
+    Custom {
+        oid: u32,
+        schema: String,
+        name: String,
+        #[serde(flatten)]
+        kind: SavedKind,
+    },
+}
+
+#[derive(Serialize, Deserialize)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+pub(crate) enum SavedKind {
+    Base,
+    Enum { labels: Vec<String> },
+    Domain { base: Box<SavedType> },
+    Composite { fields: Vec<(String, SavedType)> },
+    Array { element: Box<SavedType> },
+}
+
+impl SavedType {
+    pub(crate) fn new(ty: &Type) -> Self {
+        if Type::from_oid(ty.oid()).is_some() {
+            return Self::Builtin(ty.oid());
+        }
+        let kind = match ty.kind() {
+            Kind::Enum(labels) => SavedKind::Enum {
+                labels: labels.clone(),
+            },
+            Kind::Domain(base) => SavedKind::Domain {
+                base: Box::new(Self::new(base)),
+            },
+            Kind::Composite(fields) => SavedKind::Composite {
+                fields: fields
+                    .iter()
+                    .map(|f| (f.name().to_string(), Self::new(f.type_())))
+                    .collect(),
+            },
+            Kind::Array(element) => SavedKind::Array {
+                element: Box::new(Self::new(element)),
+            },
+            _ => SavedKind::Base,
+        };
+        Self::Custom {
+            oid: ty.oid(),
+            schema: ty.schema().to_string(),
+            name: ty.name().to_string(),
+            kind,
+        }
+    }
+
+    pub(crate) fn into_type(self) -> Result<Type, String> {
+        let (oid, schema, name, kind) = match self {
+            Self::Builtin(oid) => {
+                return Type::from_oid(oid).ok_or_else(|| format!("unsupported type oid {oid}"));
+            }
+            Self::Custom {
+                oid,
+                schema,
+                name,
+                kind,
+            } => (oid, schema, name, kind),
+        };
+        let kind = match kind {
+            SavedKind::Base => Kind::Simple,
+            SavedKind::Enum { labels } => Kind::Enum(labels),
+            SavedKind::Domain { base } => Kind::Domain(base.into_type()?),
+            SavedKind::Array { element } => Kind::Array(element.into_type()?),
+            SavedKind::Composite { fields } => Kind::Composite(
+                fields
+                    .into_iter()
+                    .map(|(name, ty)| Ok(Field::new(name, ty.into_type()?)))
+                    .collect::<Result<_, String>>()?,
+            ),
+        };
+        Ok(Type::new(name, oid, kind, schema))
+    }
+}
+
+/// `ty` with domains unwrapped to the type they constrain.
+pub(crate) fn base_type(ty: &Type) -> &Type {
+    match ty.kind() {
+        Kind::Domain(base) => base_type(base),
+        _ => ty,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn registry(entries: Vec<(u32, &str, TypeShape)>) -> TypeRegistry {
+        TypeRegistry {
+            entries: entries
+                .into_iter()
+                .map(|(oid, name, shape)| {
+                    let entry = TypeEntry {
+                        schema: "app".to_string(),
+                        name: name.to_string(),
+                        shape,
+                    };
+                    (oid, entry)
+                })
+                .collect(),
+            resolved: Mutex::default(),
+        }
+    }
+
+    #[test]
+    fn test_resolve_custom_types() {
+        let types = registry(vec![
+            (
+                90001,
+                "mood",
+                TypeShape::Enum(vec!["happy".into(), "sad".into()]),
+            ),
+            (90002, "positive_int", TypeShape::Domain(Type::INT4.oid())),
+            (
+                90003,
+                "address",
+                TypeShape::Composite(vec![
+                    ("street".into(), Type::TEXT.oid()),
+                    ("mood".into(), 90001),
+                ]),
+            ),
+            (90004, "_mood", TypeShape::Array(90001)),
+            (90005, "broken", TypeShape::Domain(99999)),
+        ]);
+
+        assert_eq!(types.resolve(Type::INT4.oid()), Some(Type::INT4));
+        let mood = types.resolve(90001).unwrap();
+        assert_eq!((mood.schema(), mood.name()), ("app", "mood"));
+        assert_eq!(mood.kind(), &Kind::Enum(vec!["happy".into(), "sad".into()]));
+        assert_eq!(base_type(&types.resolve(90002).unwrap()), &Type::INT4);
+
+        let address = types.resolve(90003).unwrap();
+        let Kind::Composite(fields) = address.kind() else {
+            panic!("{address:?}");
+        };
+        assert_eq!(fields[1].type_(), &mood);
+        assert_eq!(types.resolve(90004).unwrap().kind(), &Kind::Array(mood));
+        assert_eq!(types.resolve(90005), None);
+        assert_eq!(types.resolve(90006), None);
+    }
+
+    #[test]
+    fn test_saved_type_round_trip() {
+        let types = registry(vec![
+            (90001, "mood", TypeShape::Enum(vec!["happy".into()])),
+            (
+                90002,
+                "address",
+                TypeShape::Composite(vec![("mood".into(), 90001)]),
+            ),
+            (90003, "addresses", TypeShape::Domain(90004)),
+            (90004, "_address", TypeShape::Array(90002)),
+        ]);
+        for oid in [Type::INT4.oid(), 90001, 90002, 90003] {
+            let ty = types.resolve(oid).unwrap();
+            let json = serde_json::to_string(&SavedType::new(&ty)).unwrap();
+            let saved: SavedType = serde_json::from_str(&json).unwrap();
+            assert_eq!(saved.into_type().unwrap(), ty, "{json}");
+        }
+    }
+}
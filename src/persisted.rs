@@ -0,0 +1,443 @@
+//! Persisted operations: an allow-list of the documents a server runs,
+//! keyed by their SHA-256 hash.
+//! [`TurboGraph::persisted_operations`](crate::TurboGraph::persisted_operations)
+//! builds the manifest from a directory of client `.graphql` files,
+//! validating each against the schema, and [`PersistedOperations::resolve`]
+//! enforces it, so a server in strict persisted mode runs nothing else.
+//! Without a server, [`PersistedOperations::from_sdl`] validates against the
+//! SDL it exported.
+//!
+//! ```rust,ignore
+//! // In CI, or with `turbograph persisted-operations`:
+//! let manifest = server.persisted_operations("client/operations").await?;
+//! std::fs::write("persisted.json", manifest.to_json()?)?;
+//!
+//! // On the server:
+//! let manifest = PersistedOperations::from_json(&std::fs::read_to_string("persisted.json")?)?;
+//! let app = HttpServer::new(server).persisted_operations(manifest).router();
+//! ```
+
+use std::collections::BTreeMap;
+use std::path::{Path, PathBuf};
+
+use async_graphql::Value;
+use async_graphql::dynamic::{
+    Enum, Field, FieldFuture, FieldValue, InputObject, InputValue, Interface, InterfaceField,
+    Object, Scalar, Schema, Subscription, SubscriptionField, SubscriptionFieldFuture, TypeRef,
+    Union,
+};
+use async_graphql::futures_util::stream;
+use async_graphql::parser::types::{
+    BaseType, FieldDefinition, InputValueDefinition, Type, TypeKind, TypeSystemDefinition,
+};
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+
+use crate::schema::coded_response;
+
+type BoxError = Box<dyn std::error::Error + Send + Sync>;
+
+/// `code` extension of requests rejected in strict persisted mode.
+pub(crate) const PERSISTED_QUERY_NOT_FOUND: &str = "PERSISTED_QUERY_NOT_FOUND";
+
+/// An operation name that is not a GraphQL name, so it matches nothing.
+const NO_OPERATION: &str = "-";
+
+/// The manifest of persisted operations: every allowed document by its
+/// [`operation_hash`]. It serializes as a JSON object from hash to document,
+/// sorted by hash, so it can be checked in and diffed.
+#[derive(Clone, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(transparent)]
+pub struct PersistedOperations {
+    operations: BTreeMap<String, String>,
+}
+
+impl PersistedOperations {
+    pub fn from_json(json: &str) -> Result<Self, serde_json::Error> {
+        serde_json::from_str(json)
+    }
+
+    pub fn to_json(&self) -> Result<String, serde_json::Error> {
+        serde_json::to_string_pretty(self)
+    }
+
+    /// The manifest of the `.graphql` and `.gql` files under `dir`,
+    /// validated against the schema `sdl` describes, such as the SDL
+    /// [`TurboGraph::export_sdl`](crate::TurboGraph::export_sdl) prints.
+    /// Unlike [`TurboGraph::persisted_operations`](crate::TurboGraph::persisted_operations)
+    /// it needs neither the database nor the server's `Config`. Fails with
+    /// every invalid file and its errors.
+    pub async fn from_sdl(sdl: &str, dir: impl AsRef<Path>) -> Result<Self, BoxError> {
+        collect(&sdl_schema(sdl)?, dir.as_ref()).await
+    }
+
+    /// Adds `document` and returns its hash.
+    pub fn insert(&mut self, document: impl Into<String>) -> String {
+        let document = document.into();
+        let hash = operation_hash(&document);
+        self.operations.insert(hash.clone(), document);
+        hash
+    }
+
+    /// The document persisted under `hash`.
+    pub fn get(&self, hash: &str) -> Option<&str> {
+        self.operations.get(hash).map(String::as_str)
+    }
+
+    pub fn len(&self) -> usize {
+        self.operations.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.operations.is_empty()
+    }
+
+    /// Resolves `request` against the manifest, the strict persisted mode
+    /// of [`HttpServer::persisted_operations`](crate::HttpServer::persisted_operations).
+    ///
+    /// A request naming its document by hash, in the
+    /// `extensions.persistedQuery.sha256Hash` of Apollo's automatic
+    /// persisted queries, gets the persisted document as its query; one
+    /// sending its document must send a persisted one. Anything else fails
+    /// with a `PERSISTED_QUERY_NOT_FOUND` response.
+    #[allow(clippy::result_large_err)]
+    pub fn resolve(
+        &self,
+        mut request: async_graphql::Request,
+    ) -> Result<async_graphql::Request, async_graphql::Response> {
+        let hash = match request.extensions.0.get("persistedQuery") {
+            Some(Value::Object(persisted)) => match persisted.get("sha256Hash") {
+                Some(Value::String(hash)) => hash.clone(),
+                _ => return Err(not_found()),
+            },
+            _ => operation_hash(&request.query),
+        };
+        let document = self.get(&hash).ok_or_else(not_found)?;
+        if request.query.is_empty() {
+            request.query = document.to_string();
+        } else if request.query != document {
+            return Err(not_found());
+        }
+        Ok(request)
+    }
+}
+
+fn not_found() -> async_graphql::Response {
+    coded_response(
+        PERSISTED_QUERY_NOT_FOUND,
+        "only persisted operations run on this server",
+    )
+}
+
+/// The lowercase hex SHA-256 of `document`, as Apollo clients compute it
+/// for `extensions.persistedQuery.sha256Hash`. Whitespace counts, so clients
+/// must send each document exactly as persisted.
+pub fn operation_hash(document: &str) -> String {
+    Sha256::digest(document.as_bytes())
+        .iter()
+        .map(|byte| format!("{byte:02x}"))
+        .collect()
+}
+
+/// Every `.graphql` and `.gql` file under `dir`, validated against
+/// `schema`; see
+/// [`TurboGraph::persisted_operations`](crate::TurboGraph::persisted_operations).
+pub(crate) async fn collect(schema: &Schema, dir: &Path) -> Result<PersistedOperations, BoxError> {
+    let mut files = Vec::new();
+    find_documents(dir, &mut files)?;
+    files.sort();
+
+    let mut manifest = PersistedOperations::default();
+    let mut problems = Vec::new();
+    for file in files {
+        let document = std::fs::read_to_string(&file)?;
+        let errors = validate(schema, &document).await;
+        if errors.is_empty() {
+            manifest.insert(document);
+        }
+        for error in errors {
+            problems.push(format!("{}: {error}", file.display()));
+        }
+    }
+
+    if problems.is_empty() {
+        return Ok(manifest);
+    }
+    let mut message = format!(
+        "{} problem{} in the operations under {}:",
+        problems.len(),
+        if problems.len() == 1 { "" } else { "s" },
+        dir.display()
+    );
+    for problem in problems {
+        message.push_str("\n  - ");
+        message.push_str(&problem);
+    }
+    Err(message.into())
+}
+
+fn find_documents(dir: &Path, files: &mut Vec<PathBuf>) -> std::io::Result<()> {
+    for entry in std::fs::read_dir(dir)? {
+        let path = entry?.path();
+        if path.is_dir() {
+            find_documents(&path, files)?;
+        } else if path
+            .extension()
+            .is_some_and(|ext| ext == "graphql" || ext == "gql")
+        {
+            files.push(path);
+        }
+    }
+    Ok(())
+}
+
+/// The validation errors of `document` on `schema`. The request names an
+/// operation no document has, so async-graphql checks the whole document,
+/// then fails to pick an operation instead of running one.
+async fn validate(schema: &Schema, document: &str) -> Vec<String> {
+    let request = async_graphql::Request::new(document).operation_name(NO_OPERATION);
+    let unknown = format!(r#"Unknown operation named "{NO_OPERATION}""#);
+    let response = schema.execute(request).await;
+    response
+        .errors
+        .into_iter()
+        .map(|e| e.message)
+        .filter(|message| *message != unknown)
+        .collect()
+}
+
+/// A schema with the types, fields and arguments `sdl` declares, to
+/// validate documents with. Its resolvers answer `null`, but nothing runs:
+/// [`validate`] stops before picking an operation.
+fn sdl_schema(sdl: &str) -> Result<Schema, BoxError> {
+    let document = async_graphql::parser::parse_schema(sdl)?;
+    let mut roots = [None, None, None];
+    let mut types = Vec::new();
+    for definition in document.definitions {
+        match definition {
+            TypeSystemDefinition::Schema(schema) => {
+                let schema = schema.node;
+                roots = [schema.query, schema.mutation, schema.subscription]
+                    .map(|root| root.map(|name| name.node.to_string()));
+            }
+            TypeSystemDefinition::Type(ty) => types.push(ty.node),
+            TypeSystemDefinition::Directive(_) => {}
+        }
+    }
+    // Without a `schema` block the roots go by their default names.
+    if roots.iter().all(Option::is_none) {
+        let declared = |name: &str| {
+            types
+                .iter()
+                .any(|ty| ty.name.node == name)
+                .then(|| name.to_string())
+        };
+        roots = ["Query", "Mutation", "Subscription"].map(declared);
+    }
+    let [query, mutation, subscription] = roots;
+    let query = query.ok_or("the SDL declares no query type")?;
+
+    let mut builder = Schema::build(&query, mutation.as_deref(), subscription.as_deref());
+    for ty in types {
+        let name = ty.name.node.to_string();
+        if is_built_in(&name) {
+            continue;
+        }
+        builder = match ty.kind {
+            TypeKind::Scalar => builder.register(Scalar::new(name)),
+            TypeKind::Object(object) if subscription.as_deref() == Some(name.as_str()) => {
+                let fields = object.fields.into_iter().map(|field| field.node);
+                builder.register(fields.fold(Subscription::new(name), |subscription, field| {
+                    subscription.field(subscription_field(field))
+                }))
+            }
+            TypeKind::Object(object) => {
+                let fields = object.fields.into_iter().map(|field| field.node);
+                let implements = object.implements.into_iter().map(|i| i.node.to_string());
+                let object = fields.fold(Object::new(name), |object, field| {
+                    object.field(field_of(field))
+                });
+                builder.register(implements.fold(object, Object::implement))
+            }
+            TypeKind::Interface(interface) => {
+                let fields = interface.fields.into_iter().map(|field| field.node);
+                let implements = interface.implements.into_iter().map(|i| i.node.to_string());
+                let interface = fields.fold(Interface::new(name), |interface, field| {
+                    interface.field(interface_field(field))
+                });
+                builder.register(implements.fold(interface, Interface::implement))
+            }
+            TypeKind::Union(union) => builder.register(
+                union
+                    .members
+                    .into_iter()
+                    .fold(Union::new(name), |union, member| {
+                        union.possible_type(member.node.to_string())
+                    }),
+            ),
+            TypeKind::Enum(values) => builder.register(
+                values
+                    .values
+                    .into_iter()
+                    .fold(Enum::new(name), |values, value| {
+                        values.item(value.node.value.node.to_string())
+                    }),
+            ),
+            TypeKind::InputObject(input) => {
+                let oneof = ty.directives.iter().any(|d| d.node.name.node == "oneOf");
+                let object = input
+                    .fields
+                    .into_iter()
+                    .fold(InputObject::new(name), |object, field| {
+                        object.field(input_value(field.node))
+                    });
+                builder.register(if oneof { object.oneof() } else { object })
+            }
+        };
+    }
+    Ok(builder.finish()?)
+}
+
+/// The scalars every schema has, and the introspection types.
+fn is_built_in(name: &str) -> bool {
+    matches!(name, "String" | "Int" | "Float" | "Boolean" | "ID") || name.starts_with("__")
+}
+
+fn field_of(definition: FieldDefinition) -> Field {
+    let field = Field::new(
+        definition.name.node.to_string(),
+        type_ref(&definition.ty.node),
+        |_| FieldFuture::new(async { Ok(None::<FieldValue>) }),
+    );
+    arguments(definition.arguments)
+        .into_iter()
+        .fold(field, Field::argument)
+}
+
+fn interface_field(definition: FieldDefinition) -> InterfaceField {
+    let field = InterfaceField::new(
+        definition.name.node.to_string(),
+        type_ref(&definition.ty.node),
+    );
+    arguments(definition.arguments)
+        .into_iter()
+        .fold(field, InterfaceField::argument)
+}
+
+fn subscription_field(definition: FieldDefinition) -> SubscriptionField {
+    let field = SubscriptionField::new(
+        definition.name.node.to_string(),
+        type_ref(&definition.ty.node),
+        |_| SubscriptionFieldFuture::new(async { Ok(stream::empty::<Result<Value, _>>()) }),
+    );
+    arguments(definition.arguments)
+        .into_iter()
+        .fold(field, SubscriptionField::argument)
+}
+
+fn arguments(arguments: Vec<async_graphql::Positioned<InputValueDefinition>>) -> Vec<InputValue> {
+    arguments
+        .into_iter()
+        .map(|argument| input_value(argument.node))
+        .collect()
+}
+
+fn input_value(definition: InputValueDefinition) -> InputValue {
+    let value = InputValue::new(
+        definition.name.node.to_string(),
+        type_ref(&definition.ty.node),
+    );
+    match definition.default_value {
+        Some(default) => value.default_value(default.node),
+        None => value,
+    }
+}
+
+fn type_ref(ty: &Type) -> TypeRef {
+    let base = match &ty.base {
+        BaseType::Named(name) => TypeRef::Named(name.to_string().into()),
+        BaseType::List(item) => TypeRef::List(Box::new(type_ref(item))),
+    };
+    match ty.nullable {
+        true => base,
+        false => TypeRef::NonNull(Box::new(base)),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_resolve_only_admits_persisted_documents() {
+        let mut manifest = PersistedOperations::default();
+        let hash = manifest.insert("{ allUsers { totalCount } }");
+        assert_eq!(hash, operation_hash("{ allUsers { totalCount } }"));
+
+        let mut by_hash = async_graphql::Request::new("");
+        by_hash.extensions.0.insert(
+            "persistedQuery".into(),
+            async_graphql::value!({ "version": 1, "sha256Hash": hash }),
+        );
+        let resolved = manifest.resolve(by_hash).unwrap();
+        assert_eq!(resolved.query, "{ allUsers { totalCount } }");
+
+        let by_document = async_graphql::Request::new("{ allUsers { totalCount } }");
+        assert!(manifest.resolve(by_document).is_ok());
+
+        let other = async_graphql::Request::new("{ allUsers { nodes { id } } }");
+        let response = manifest.resolve(other).unwrap_err();
+        let ext = response.errors[0].extensions.as_ref().unwrap();
+        assert_eq!(
+            ext.get("code"),
+            Some(&Value::from(PERSISTED_QUERY_NOT_FOUND))
+        );
+    }
+
+    #[tokio::test]
+    async fn test_from_sdl_validates_against_the_sdl() {
+        let sdl = r#"
+            interface Node { nodeId: ID! }
+            type User implements Node { nodeId: ID! user_name: String! }
+            input UserFilter { user_name: String }
+            type Query { all_users(filter: UserFilter, first: Int = 10): [User!]! node(nodeId: ID!): Node }
+            type Subscription { changed(topic: String!): String }
+        "#;
+        let dir = std::env::temp_dir().join(format!("turbograph_sdl_{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let users = "{ all_users(filter: { user_name: \"a\" }) { user_name } }";
+        std::fs::write(dir.join("users.graphql"), users).unwrap();
+        std::fs::write(
+            dir.join("node.graphql"),
+            "query($id: ID!) { node(nodeId: $id) { ... on User { nodeId } } }",
+        )
+        .unwrap();
+        std::fs::write(
+            dir.join("changed.graphql"),
+            "subscription { changed(topic: \"users\") }",
+        )
+        .unwrap();
+        let manifest = PersistedOperations::from_sdl(sdl, &dir).await.unwrap();
+        assert_eq!(manifest.len(), 3);
+        assert_eq!(manifest.get(&operation_hash(users)), Some(users));
+
+        std::fs::write(dir.join("camel.graphql"), "{ allUsers { userName } }").unwrap();
+        let err = PersistedOperations::from_sdl(sdl, &dir)
+            .await
+            .unwrap_err()
+            .to_string();
+        std::fs::remove_dir_all(&dir).unwrap();
+        assert!(
+            err.contains("camel.graphql: Unknown field \"allUsers\""),
+            "{err}"
+        );
+    }
+
+    #[test]
+    fn test_operation_hash_is_sha256() {
+        assert_eq!(
+            operation_hash(""),
+            "e3b0c44298fc1c149afbf4c8996fb92427ae41e4649b934ca495991b7852b855"
+        );
+    }
+}
@@ -1,11 +1,46 @@
+use std::any::TypeId;
+use std::collections::{HashMap, HashSet};
 use std::sync::Arc;
 
-use async_graphql::dynamic::{Object, Schema};
+use arc_swap::ArcSwap;
+use async_graphql::dynamic::{Field, Object, Schema, Subscription};
+use async_graphql::futures_util::stream::{self, BoxStream, StreamExt};
+use async_graphql::http::MultipartOptions;
+use async_graphql::parser::types::OperationType;
 use deadpool_postgres::Pool;
-use tokio::sync::RwLock;
+use http::HeaderValue;
+use http::header::RETRY_AFTER;
+use tokio::sync::mpsc::UnboundedSender;
+use tokio_postgres::types::{Kind, Type};
 
+use crate::db::changes::ChangeFeed;
+use crate::db::circuit_breaker::CircuitBreaker;
+use crate::db::pool::{BackgroundPool, PoolMetrics};
+use crate::db::request::{RequestScope, RequestState};
+use crate::error::is_schema_out_of_date;
+use crate::export::{ExportRequest, ExportStream, Exports};
 use crate::graphql;
-use crate::models::config::{Config, PoolConfig};
+use crate::graphql::example::{GraphiqlExample, graphiql_example};
+use crate::graphql::prefetch::{Join, Joins};
+use crate::graphql::scalars::CustomScalars;
+use crate::graphql::validation::{BUILT_IN, Validation};
+use crate::graphql::version::{SCHEMA_HASH_HEADER, SchemaHash, schema_hash};
+use crate::ir::{Operation, Plan, Planner};
+use crate::models::catalog::Catalog;
+use crate::models::config::{
+    Config, IntrospectionConfig, NodeIdStrategy, PoolConfig, SchemaOptions, SharedExtension,
+};
+use crate::models::foreign_key::ForeignKey;
+use crate::models::function::FunctionReturn;
+use crate::models::grants::{PUBLIC_ROLE, Privilege, intersect, union};
+use crate::models::mock::MockData;
+use crate::models::operation_matrix::OperationMatrix;
+use crate::models::reference::{Reference, parse_refs};
+use crate::models::row_transform::RowTransforms;
+use crate::models::table::{EnumType, Table, UnsupportedTypes};
+use crate::models::transaction::{ExecutionLane, PgSettings, TransactionConfig};
+use crate::persisted::PersistedOperations;
+use crate::utils::inflection::{Inflector, column_field_name, sanitize_name};
 
 /// The main entry point for consuming the library.
 ///
@@ -25,7 +60,34 @@ use crate::models::config::{Config, PoolConfig};
 /// ```
 #[derive(Clone)]
 pub struct TurboGraph {
-    schema: Arc<RwLock<Schema>>,
+    /// The current schema. A rebuild swaps in a new one without waiting:
+    /// requests already running finish on the schema they started with.
+    built: Arc<ArcSwap<BuiltSchema>>,
+    /// See [`Config::admin_roles`].
+    admin_roles: Arc<Vec<String>>,
+    /// See [`Config::default_role`].
+    default_role: Option<String>,
+    /// Counters reported by `poolStatus`, kept across schema rebuilds.
+    pool_metrics: Arc<PoolMetrics>,
+    /// See [`Config::circuit_breaker`]; kept across schema rebuilds.
+    circuit_breaker: Option<Arc<CircuitBreaker>>,
+    /// See [`Config::background_pool`].
+    background_pool: Option<BackgroundPool>,
+    /// Requests a rebuild from the database; `None` for static catalogs.
+    rebuild_tx: Option<UnboundedSender<String>>,
+    /// Change events behind subscriptions, see [`Config::subscriptions`].
+    changes: Option<ChangeFeed>,
+    /// See [`Config::read_only`].
+    read_only: bool,
+    /// See [`Config::max_upload_bytes`].
+    max_upload_bytes: Option<u64>,
+    /// See [`Config::operation_matrix`].
+    operation_matrix: Option<Arc<OperationMatrix>>,
+    /// See [`Config::row_transforms`]; applied by [`run`](Self::run) as by
+    /// the GraphQL resolvers.
+    row_transforms: Option<RowTransforms>,
+    /// See [`Config::custom_scalars`].
+    custom_scalars: Option<CustomScalars>,
 }
 
 impl TurboGraph {
@@ -35,39 +97,447 @@ impl TurboGraph {
     /// background task is spawned that automatically swaps in a freshly built
     /// schema whenever a DDL change is detected.
     pub async fn new(config: Config) -> Result<Self, Box<dyn std::error::Error + Send + Sync>> {
-        let watch_pg = config.watch_pg;
+        let connection_url = match &config.pool {
+            PoolConfig::ConnectionString(url) => Some(url.clone()),
+            PoolConfig::Pool(_) => None,
+        };
 
-        let connection_url = if watch_pg {
-            match &config.pool {
-                PoolConfig::ConnectionString(url) => Some(url.clone()),
-                PoolConfig::Pool(_) => {
-                    return Err("watch_pg requires PoolConfig::ConnectionString".into());
-                }
+        let introspection = IntrospectionConfig::from_config(&config);
+        let options = SchemaOptions::from_config(&config);
+        let pool = Arc::new(crate::db::pool::resolve(config.pool)?);
+        Self::with_pool(
+            pool,
+            connection_url,
+            config.watch_pg,
+            introspection,
+            options,
+            config.background_pool,
+        )
+        .await
+    }
+
+    /// [`TurboGraph::new`] on an already resolved pool, which may be shared
+    /// with other instances. Watch mode and subscriptions need
+    /// `connection_url` for their LISTEN connections.
+    pub(crate) async fn with_pool(
+        pool: Arc<Pool>,
+        connection_url: Option<String>,
+        watch_pg: bool,
+        introspection: IntrospectionConfig,
+        options: SchemaOptions,
+        background_pool: Option<PoolConfig>,
+    ) -> Result<Self, Box<dyn std::error::Error + Send + Sync>> {
+        if connection_url.is_none() && watch_pg {
+            return Err("watch_pg requires PoolConfig::ConnectionString".into());
+        }
+        if connection_url.is_none() && options.subscriptions {
+            return Err("subscriptions require PoolConfig::ConnectionString".into());
+        }
+        let admin_roles = Arc::new(options.admin_roles.clone());
+        let default_role = options.default_role.clone();
+        let read_only = options.read_only;
+        let max_upload_bytes = options.max_upload_bytes;
+        let operation_matrix = options.operation_matrix.clone().map(Arc::new);
+        let row_transforms = RowTransforms::new(&options.row_transforms);
+        let custom_scalars = CustomScalars::new(&options.custom_scalars);
+        let circuit_breaker = circuit_breaker(&options, &pool);
+        let background_pool = resolve_background_pool(background_pool)?;
+        let changes = match &connection_url {
+            Some(url) if options.subscriptions => {
+                Some(crate::db::changes::start_listening(url).await?)
             }
+            _ => None,
+        };
+
+        // Taken before the build so that DDL racing with it still triggers a rebuild.
+        let fingerprint = if watch_pg {
+            crate::db::introspect::catalog_fingerprint(&pool, &introspection)
+                .await
+                .ok()
         } else {
             None
         };
+        let built = Arc::new(ArcSwap::from_pointee(
+            rebuild_schema(&pool, &introspection, &options).await?,
+        ));
 
-        let pool = Arc::new(crate::db::pool::resolve(config.pool)?);
-        let built_schema = rebuild_schema(&pool, &config.schemas).await?;
-        let schema = Arc::new(RwLock::new(built_schema));
-
-        if watch_pg {
-            let url = connection_url.unwrap();
+        let rebuild_tx = crate::db::watch::spawn_rebuilder(
+            pool.clone(),
+            introspection,
+            options,
+            built.clone(),
+            fingerprint,
+        );
+        if let Some(url) = connection_url.filter(|_| watch_pg) {
             crate::db::watch::install_triggers(&pool).await?;
-            crate::db::watch::start_watching(url, pool, config.schemas, schema.clone()).await?;
+            crate::db::watch::start_watching(url, rebuild_tx.clone()).await?;
+        }
+
+        Ok(Self {
+            built,
+            admin_roles,
+            default_role,
+            pool_metrics: Arc::default(),
+            circuit_breaker,
+            background_pool,
+            rebuild_tx: Some(rebuild_tx),
+            changes,
+            read_only,
+            max_upload_bytes,
+            operation_matrix,
+            row_transforms,
+            custom_scalars,
+        })
+    }
+
+    /// Build the GraphQL schema from a previously saved [`Catalog`] instead of
+    /// introspecting the database, e.g. one embedded with
+    /// [`static_catalog_module`](crate::static_catalog_module). Queries still
+    /// run against the pool from `config`.
+    ///
+    /// The schema cannot follow DDL changes, so [`Config::watch_pg`] must be
+    /// `false`.
+    pub fn from_catalog(
+        config: Config,
+        catalog: Catalog,
+    ) -> Result<Self, Box<dyn std::error::Error + Send + Sync>> {
+        if config.watch_pg {
+            return Err("watch_pg is not supported with a static catalog".into());
+        }
+        if config.subscriptions {
+            return Err("subscriptions are not supported with a static catalog".into());
         }
 
-        Ok(Self { schema })
+        let options = SchemaOptions::from_config(&config);
+        let background_pool = resolve_background_pool(config.background_pool)?;
+        let pool = Arc::new(crate::db::pool::resolve(config.pool)?);
+        let built = build_schemas(catalog, &pool, None, &options)?;
+        Ok(Self {
+            built: Arc::new(ArcSwap::from_pointee(built)),
+            circuit_breaker: circuit_breaker(&options, &pool),
+            admin_roles: Arc::new(options.admin_roles),
+            default_role: options.default_role,
+            pool_metrics: Arc::default(),
+            background_pool,
+            rebuild_tx: None,
+            changes: None,
+            read_only: options.read_only,
+            max_upload_bytes: options.max_upload_bytes,
+            operation_matrix: options.operation_matrix.map(Arc::new),
+            row_transforms: RowTransforms::new(&options.row_transforms),
+            custom_scalars: CustomScalars::new(&options.custom_scalars),
+        })
+    }
+
+    /// Build the GraphQL schema from a previously saved [`Catalog`] and answer
+    /// every query and mutation from `data` instead of the database.
+    ///
+    /// The schema is exactly the one [`TurboGraph::new`] would generate for
+    /// the same catalog, so frontends and contract tests can run against it
+    /// while no database is available. Mutations return the rows they would
+    /// have produced but leave `data` unchanged.
+    ///
+    /// ```rust,ignore
+    /// // With a database: save the catalog once.
+    /// let catalog = turbograph::introspect(config).await?;
+    /// std::fs::write("catalog.json", serde_json::to_string(&catalog)?)?;
+    ///
+    /// // Without one: serve fixtures through the generated schema.
+    /// let catalog = serde_json::from_str(&std::fs::read_to_string("catalog.json")?)?;
+    /// let data = MockData::from_json(serde_json::json!({ "users": [{ "id": 1 }] }))?;
+    /// let server = TurboGraph::mock(catalog, data, SchemaOptions::from_config(&config))?;
+    /// ```
+    pub fn mock(
+        catalog: Catalog,
+        data: MockData,
+        options: SchemaOptions,
+    ) -> Result<Self, Box<dyn std::error::Error + Send + Sync>> {
+        Self::unconnected(catalog, Some(data), options)
+    }
+
+    /// Build the GraphQL schema from a catalog snapshot, without a database,
+    /// to inspect or [export](Self::export_sdl) it, e.g. for code generation
+    /// in CI. Requests fail as no database can be reached.
+    ///
+    /// ```rust,ignore
+    /// // With a database: save the snapshot once.
+    /// let catalog = turbograph::introspect(config).await?;
+    /// std::fs::write("schema.json", catalog.to_json()?)?;
+    ///
+    /// // Without one: regenerate the SDL.
+    /// let catalog = Catalog::from_json(&std::fs::read_to_string("schema.json")?)?;
+    /// let server = TurboGraph::from_snapshot(catalog, SchemaOptions::default())?;
+    /// std::fs::write("schema.graphql", server.export_sdl().await)?;
+    /// ```
+    pub fn from_snapshot(
+        catalog: Catalog,
+        options: SchemaOptions,
+    ) -> Result<Self, Box<dyn std::error::Error + Send + Sync>> {
+        Self::unconnected(catalog, None, options)
+    }
+
+    /// The schema for `catalog` on a pool that never connects: answered from
+    /// `data` in mock mode, or only planned, see
+    /// [`SqlPlanner`](crate::testing::SqlPlanner).
+    pub(crate) fn unconnected(
+        catalog: Catalog,
+        data: Option<MockData>,
+        options: SchemaOptions,
+    ) -> Result<Self, Box<dyn std::error::Error + Send + Sync>> {
+        let pool = Arc::new(crate::db::pool::unconnected()?);
+        let built = build_schemas(catalog, &pool, data.map(Arc::new), &options)?;
+        Ok(Self {
+            built: Arc::new(ArcSwap::from_pointee(built)),
+            admin_roles: Arc::new(options.admin_roles),
+            default_role: options.default_role,
+            pool_metrics: Arc::default(),
+            circuit_breaker: None,
+            background_pool: None,
+            rebuild_tx: None,
+            changes: None,
+            read_only: options.read_only,
+            max_upload_bytes: options.max_upload_bytes,
+            operation_matrix: options.operation_matrix.map(Arc::new),
+            row_transforms: RowTransforms::new(&options.row_transforms),
+            custom_scalars: CustomScalars::new(&options.custom_scalars),
+        })
     }
 
     /// Execute a GraphQL request against the current schema.
+    ///
+    /// If any transaction had to be retried after a serialization failure or
+    /// deadlock, the response carries a `transactionRetries` extension with
+    /// the total number of retries.
+    ///
+    /// Requests carrying [`ExecutionLane::Background`](crate::ExecutionLane)
+    /// run on [`Config::background_pool`] when one is configured. With
+    /// [`Config::admin_schema`], requests whose
+    /// [`TransactionConfig::role`](crate::TransactionConfig::role) is one of
+    /// [`Config::admin_roles`] run against the admin schema.
+    ///
+    /// Requests carrying [`PgSettings`] run with its role and settings, see
+    /// [`Config::default_role`] for those without a role.
     pub async fn execute(&self, request: async_graphql::Request) -> async_graphql::Response {
-        // SAFETY: The schema is only swapped out in its entirety after a fresh build completes,
-        // so there are no concerns about concurrent mutation. Readers will always see a consistent schema,
-        // albeit possibly an older one if a rebuild is in progress.
-        let schema = self.schema.read().await;
-        schema.execute(request).await
+        self.execute_with_state(request, Arc::new(RequestState::default()))
+            .await
+    }
+
+    /// Executes `request` against the admin schema, which ignores `@omit`,
+    /// whatever its role. For a separate endpoint that is already restricted
+    /// to internal tools; fails unless [`Config::admin_schema`] is set.
+    pub async fn execute_admin(&self, request: async_graphql::Request) -> async_graphql::Response {
+        self.execute_on(request, Arc::new(RequestState::default()), Variant::Admin)
+            .await
+    }
+
+    #[cfg_attr(not(feature = "testing"), allow(dead_code))]
+    pub(crate) async fn execute_with_state(
+        &self,
+        request: async_graphql::Request,
+        state: Arc<RequestState>,
+    ) -> async_graphql::Response {
+        match self.request_variant(&request) {
+            Ok(variant) => self.execute_on(request, state, variant).await,
+            Err(response) => *response,
+        }
+    }
+
+    /// The schema `request` runs against: the admin one for
+    /// [`Config::admin_roles`], else the public one, provided
+    /// [`Config::operation_matrix`] allows the operation.
+    fn request_variant(
+        &self,
+        request: &async_graphql::Request,
+    ) -> Result<Variant, Box<async_graphql::Response>> {
+        let role = self.request_role(request);
+        if role
+            .as_ref()
+            .is_some_and(|role| self.admin_roles.contains(role))
+        {
+            return Ok(Variant::AdminIfEnabled);
+        }
+        match self.check_operation_matrix(request, role.as_deref()) {
+            Some(response) => Err(Box::new(response)),
+            None => Ok(Variant::Public),
+        }
+    }
+
+    async fn execute_on(
+        &self,
+        request: async_graphql::Request,
+        state: Arc<RequestState>,
+        variant: Variant,
+    ) -> async_graphql::Response {
+        if self.read_only && is_mutation(&request) {
+            return read_only_response();
+        }
+        if let Some(response) = self.check_circuit_breaker() {
+            return response;
+        }
+        // The request keeps this schema alive until it completes, even if a
+        // rebuild swaps in a new one meanwhile.
+        let built = self.built.load_full();
+        let (schema, hash) = match built.variant(variant) {
+            Ok(variant) => variant,
+            Err(response) => return *response,
+        };
+        let request = self.with_pg_settings(request);
+        let request = self.with_request_data(request.data(state.clone()).data(hash.clone()));
+        let mut response = schema.execute(request).await;
+        if let Ok(value) = HeaderValue::from_str(&hash.0) {
+            response.http_headers.insert(SCHEMA_HASH_HEADER, value);
+        }
+
+        if let Some(rebuild_tx) = &self.rebuild_tx
+            && response.errors.iter().any(is_schema_out_of_date)
+        {
+            let _ = rebuild_tx.send("SCHEMA_OUT_OF_DATE".to_string());
+        }
+
+        report_state(&mut response, &state);
+        response
+    }
+
+    /// Executes a subscription (or any other operation) as a stream of
+    /// responses, e.g. for a WebSocket transport. Unless the request picks a
+    /// lane, it runs in [`ExecutionLane::Background`], so the fetches behind
+    /// long-lived subscriptions stay off the interactive pool.
+    ///
+    /// Roles pick their schema as in [`execute`](Self::execute), and each
+    /// response carries the `transactionRetries` and `warnings` extensions
+    /// of the stream so far.
+    pub fn execute_stream(
+        &self,
+        request: async_graphql::Request,
+    ) -> BoxStream<'static, async_graphql::Response> {
+        if self.read_only && is_mutation(&request) {
+            return stream::once(async { read_only_response() }).boxed();
+        }
+        let variant = match self.request_variant(&request) {
+            Ok(variant) => variant,
+            Err(response) => return stream::once(async { *response }).boxed(),
+        };
+        if let Some(response) = self.check_circuit_breaker() {
+            return stream::once(async { response }).boxed();
+        }
+        let server = self.clone();
+        let state = Arc::new(RequestState::default());
+        stream::once(async move {
+            let (schema, hash) = {
+                let built = server.built.load();
+                match built.variant(variant) {
+                    Ok((schema, hash)) => (schema.clone(), hash.clone()),
+                    Err(response) => return stream::once(async { *response }).boxed(),
+                }
+            };
+            let request = server.with_pg_settings(request);
+            let mut request = server.with_request_data(request.data(state.clone()).data(hash));
+            if !request.data.contains_key(&TypeId::of::<ExecutionLane>()) {
+                request = request.data(ExecutionLane::Background);
+            }
+            schema
+                .execute_stream(request)
+                .map(move |mut response| {
+                    report_state(&mut response, &state);
+                    response
+                })
+                .boxed()
+        })
+        .flatten()
+        .boxed()
+    }
+
+    /// The `FORBIDDEN` response to `request` when [`Config::operation_matrix`]
+    /// does not allow its operation name or one of its root fields for
+    /// `role`.
+    fn check_operation_matrix(
+        &self,
+        request: &async_graphql::Request,
+        role: Option<&str>,
+    ) -> Option<async_graphql::Response> {
+        let matrix = self.operation_matrix.as_ref()?;
+        let document = async_graphql::parser::parse_query(&request.query).ok()?;
+        let message = matrix
+            .check(&document, request.operation_name.as_deref(), role)
+            .err()?;
+        Some(coded_response("FORBIDDEN", message))
+    }
+
+    /// The `SERVICE_UNAVAILABLE` response, with a `Retry-After` header, while
+    /// [`Config::circuit_breaker`] is open.
+    fn check_circuit_breaker(&self) -> Option<async_graphql::Response> {
+        let breaker = self.circuit_breaker.as_ref()?;
+        let error = breaker.check().err()?;
+        let mut response = async_graphql::Response::from_errors(vec![
+            error.into_server_error(async_graphql::Pos::default()),
+        ]);
+        response
+            .http_headers
+            .insert(RETRY_AFTER, HeaderValue::from(breaker.retry_after()));
+        Some(response)
+    }
+
+    /// The role `request` runs as: its [`PgSettings`] role, else its
+    /// [`TransactionConfig`] role, else [`Config::default_role`].
+    pub(crate) fn request_role(&self, request: &async_graphql::Request) -> Option<String> {
+        request
+            .data
+            .get(&TypeId::of::<PgSettings>())
+            .and_then(|data| data.downcast_ref::<PgSettings>())
+            .and_then(|settings| settings.role.clone())
+            .or_else(|| {
+                request
+                    .data
+                    .get(&TypeId::of::<TransactionConfig>())
+                    .and_then(|data| data.downcast_ref::<TransactionConfig>())
+                    .and_then(|cfg| cfg.role.clone())
+            })
+            .or_else(|| self.default_role.clone())
+    }
+
+    /// Folds the request's [`PgSettings`] and [`Config::default_role`] into
+    /// its [`TransactionConfig`], which every resolver's transaction applies.
+    fn with_pg_settings(&self, request: async_graphql::Request) -> async_graphql::Request {
+        let settings = request
+            .data
+            .get(&TypeId::of::<PgSettings>())
+            .and_then(|data| data.downcast_ref::<PgSettings>())
+            .cloned();
+        if settings.is_none() && self.default_role.is_none() {
+            return request;
+        }
+        let mut transaction = request
+            .data
+            .get(&TypeId::of::<TransactionConfig>())
+            .and_then(|data| data.downcast_ref::<TransactionConfig>())
+            .cloned()
+            .unwrap_or_default();
+        if let Some(settings) = settings {
+            settings.apply_to(&mut transaction);
+        }
+        self.with_default_role(&mut transaction);
+        request.data(transaction)
+    }
+
+    fn with_default_role(&self, transaction: &mut TransactionConfig) {
+        if transaction.role.is_none() {
+            transaction.role = self.default_role.clone();
+        }
+    }
+
+    /// Adds the shared state every resolver may need to `request`.
+    fn with_request_data(&self, request: async_graphql::Request) -> async_graphql::Request {
+        let mut request = request.data(self.pool_metrics.clone());
+        if let Some(circuit_breaker) = &self.circuit_breaker {
+            request = request.data(circuit_breaker.clone());
+        }
+        if let Some(background_pool) = &self.background_pool {
+            request = request.data(background_pool.clone());
+        }
+        if let Some(changes) = &self.changes {
+            request = request.data(changes.clone());
+        }
+        request
     }
 
     /// Returns the GraphiQL HTML page pointing at the given `endpoint`.
@@ -77,58 +547,613 @@ impl TurboGraph {
             .finish()
     }
 
+    /// Like [`TurboGraph::graphiql`], but opens with a `create{T}` mutation and
+    /// its variables for every table with an `@example` tag.
+    pub async fn graphiql_with_examples(&self, endpoint: &str) -> String {
+        let page = Self::graphiql(endpoint);
+        let Some(example) = self.built.load().example.clone() else {
+            return page;
+        };
+
+        // JSON strings are valid JS literals; `<\/` keeps a `</script>` inside
+        // an example from closing the page's script element.
+        let js = |value: String| value.replace("</", "<\\/");
+        let props = format!(
+            "defaultEditorToolsVisibility: true,\n          defaultQuery: {},\n          variables: {},",
+            js(serde_json::Value::String(example.query).to_string()),
+            js(serde_json::Value::String(
+                serde_json::to_string_pretty(&example.variables).unwrap_or_default()
+            )
+            .to_string()),
+        );
+        page.replacen("defaultEditorToolsVisibility: true,", &props, 1)
+    }
+
+    /// Options for reading GraphQL multipart requests (file uploads) with
+    /// [`async_graphql::http::receive_body`], which stop a file larger than
+    /// [`Config::max_upload_bytes`] while it is still being received.
+    ///
+    /// ```rust,ignore
+    /// let request = receive_body(content_type, body, server.multipart_options()).await?;
+    /// ```
+    pub fn multipart_options(&self) -> MultipartOptions {
+        let options = MultipartOptions::default();
+        match self.max_upload_bytes {
+            Some(max) => options.max_file_size(usize::try_from(max).unwrap_or(usize::MAX)),
+            None => options,
+        }
+    }
+
+    /// Streams the rows of an exposed table as CSV or NDJSON with
+    /// `COPY ... TO STDOUT`, for downloads too large to page through a
+    /// connection. [`ExportRequest::filter`] takes the table's
+    /// `{T}Condition` as JSON.
+    ///
+    /// Only answered when `transaction`'s role is one of
+    /// [`Config::export_roles`]; row-level security applies as it does to
+    /// queries, and a `transaction` without a role runs as
+    /// [`Config::default_role`]. Exports run on [`Config::background_pool`]
+    /// when one is configured. Tables with [`Config::row_transforms`] are
+    /// not exported, since `COPY` bypasses them. Errors carry a
+    /// `FORBIDDEN`, `NOT_FOUND` or `INVALID_FILTER` code and are returned
+    /// before anything is streamed, except for failures of the `COPY` itself.
+    pub async fn export(
+        &self,
+        request: ExportRequest,
+        mut transaction: TransactionConfig,
+    ) -> Result<ExportStream, async_graphql::Error> {
+        self.with_default_role(&mut transaction);
+        let Some(exports) = self.built.load().exports.clone() else {
+            return Err(crate::error::coded_err(
+                "FORBIDDEN",
+                "exports are not enabled",
+            ));
+        };
+        let scope = RequestScope {
+            tx_config: Some(transaction),
+            pool_metrics: Some(self.pool_metrics.clone()),
+            circuit_breaker: self.circuit_breaker.clone(),
+            lane_pool: self.background_pool.as_ref().map(|p| p.0.clone()),
+            row_transforms: self.row_transforms.clone(),
+            custom_scalars: self.custom_scalars.clone(),
+            ..RequestScope::default()
+        };
+        crate::export::export(&exports, request, scope).await
+    }
+
+    /// Compiles `operation` to the statement [`run`](Self::run) would send,
+    /// without running it. See [`crate::ir`].
+    pub fn plan(&self, operation: &Operation) -> Result<Plan, async_graphql::Error> {
+        self.built.load().planner.plan(operation)
+    }
+
+    /// Runs `operation`, an operation built without GraphQL, in one
+    /// transaction configured by `transaction`, and returns the rows it
+    /// selected or wrote, keyed by field name. A `transaction` without a role
+    /// runs as [`Config::default_role`]. Writes fail with `READ_ONLY`
+    /// in [`Config::read_only`] mode or where the table's `@omit` tags leave
+    /// the matching mutation out. See [`crate::ir`].
+    pub async fn run(
+        &self,
+        operation: Operation,
+        mut transaction: TransactionConfig,
+    ) -> Result<Vec<serde_json::Value>, async_graphql::Error> {
+        self.with_default_role(&mut transaction);
+        let planner = self.built.load().planner.clone();
+        let scope = RequestScope {
+            tx_config: Some(transaction),
+            pool_metrics: Some(self.pool_metrics.clone()),
+            circuit_breaker: self.circuit_breaker.clone(),
+            row_transforms: self.row_transforms.clone(),
+            custom_scalars: self.custom_scalars.clone(),
+            ..RequestScope::default()
+        };
+        planner.run(&operation, scope).await
+    }
+
     /// Returns a clone of the current underlying dynamic schema.
     pub async fn schema(&self) -> Schema {
-        self.schema.read().await.clone()
+        self.built.load().schema.clone()
+    }
+
+    /// The current schema in GraphQL SDL, as served by introspection. The
+    /// output is deterministic for a given catalog and options.
+    pub async fn export_sdl(&self) -> String {
+        self.built.load().schema.sdl()
+    }
+
+    /// Returns a clone of the current admin schema, if
+    /// [`Config::admin_schema`] is set.
+    pub async fn admin_schema(&self) -> Option<Schema> {
+        let built = self.built.load();
+        built.admin.as_ref().map(|(admin, _)| admin.clone())
+    }
+
+    /// The root fields of the current schema and the roles its grants allow
+    /// to run each, as Postgres reported them when the schema was built.
+    /// Review it, then enforce the reviewed copy with
+    /// [`Config::operation_matrix`].
+    ///
+    /// A table's query fields need `SELECT` on it, and its mutations `SELECT`
+    /// plus `INSERT`, `UPDATE` or `DELETE`; functions and procedures need
+    /// `EXECUTE`. `refresh{T}` and `poolStatus` list
+    /// [`Config::refresh_roles`] and [`Config::admin_roles`], and
+    /// `schemaVersion` and `listen` are public.
+    pub async fn operation_matrix(&self) -> OperationMatrix {
+        OperationMatrix::clone(&self.built.load().matrix)
+    }
+
+    /// The manifest of the `.graphql` and `.gql` files under `dir`, for
+    /// [`HttpServer::persisted_operations`](crate::HttpServer::persisted_operations).
+    /// Each file is one document, persisted as written and validated
+    /// against the current schema without touching the database. Fails with
+    /// every invalid file and its errors.
+    pub async fn persisted_operations(
+        &self,
+        dir: impl AsRef<std::path::Path>,
+    ) -> Result<PersistedOperations, Box<dyn std::error::Error + Send + Sync>> {
+        crate::persisted::collect(&self.schema().await, dir.as_ref()).await
+    }
+
+    /// The hash of the current schema's SDL, as answered by `schemaVersion`
+    /// and sent in the `X-GraphQL-Schema-Hash` header of every response.
+    pub async fn schema_hash(&self) -> String {
+        self.built.load().hash.0.to_string()
+    }
+}
+
+/// Whether `request` runs a mutation. Requests that do not parse are left
+/// to the schema to reject.
+fn is_mutation(request: &async_graphql::Request) -> bool {
+    let Ok(document) = async_graphql::parser::parse_query(&request.query) else {
+        return false;
+    };
+    let mut operations = document.operations.iter();
+    let operation = match &request.operation_name {
+        Some(name) => operations.find(|(op_name, _)| op_name.is_some_and(|n| n == name)),
+        None => operations.next(),
+    };
+    operation.is_some_and(|(_, op)| op.node.ty == OperationType::Mutation)
+}
+
+/// The answer to a mutation sent to a [`Config::read_only`] server.
+fn read_only_response() -> async_graphql::Response {
+    coded_response("READ_ONLY", "mutations are disabled on this server")
+}
+
+/// A response failing with `message` and a `code` extension.
+pub(crate) fn coded_response(code: &str, message: impl Into<String>) -> async_graphql::Response {
+    let mut error = async_graphql::ServerError::new(message, None);
+    let mut extensions = async_graphql::ErrorExtensionValues::default();
+    extensions.set("code", code);
+    error.extensions = Some(extensions);
+    async_graphql::Response::from_errors(vec![error])
+}
+
+/// Adds what `state` recorded to `response`: the `transactionRetries`
+/// extension with the total number of retries, and the `warnings` not
+/// reported yet.
+fn report_state(response: &mut async_graphql::Response, state: &RequestState) {
+    let retries = state.retries();
+    if retries > 0 {
+        response.extensions.insert(
+            "transactionRetries".into(),
+            async_graphql::Value::from(retries),
+        );
+    }
+    let warnings = state.take_warnings();
+    if !warnings.is_empty() {
+        response
+            .extensions
+            .insert("warnings".into(), async_graphql::Value::from(warnings));
     }
 }
 
+/// Which schema a request runs against.
+#[derive(Clone, Copy)]
+enum Variant {
+    Public,
+    /// The admin schema when [`Config::admin_schema`] is set, else the public one.
+    AdminIfEnabled,
+    Admin,
+}
+
+/// Everything generated from one catalog, swapped as a unit on rebuilds.
+pub(crate) struct BuiltSchema {
+    pub schema: Schema,
+    /// Answered by `schemaVersion` and sent as `X-GraphQL-Schema-Hash`.
+    pub hash: SchemaHash,
+    /// The variant ignoring `@omit`, see [`Config::admin_schema`], with its
+    /// hash.
+    pub admin: Option<(Schema, SchemaHash)>,
+    /// Operation built from `@example` tags.
+    pub example: Option<GraphiqlExample>,
+    /// Tables [`TurboGraph::export`] serves, when [`Config::export_roles`]
+    /// is set.
+    pub exports: Option<Arc<Exports>>,
+    /// Plans the [`Operation`]s of [`TurboGraph::run`].
+    pub planner: Arc<Planner>,
+    /// See [`TurboGraph::operation_matrix`].
+    pub matrix: Arc<OperationMatrix>,
+}
+
+impl BuiltSchema {
+    /// The schema of `variant`, with its hash.
+    fn variant(
+        &self,
+        variant: Variant,
+    ) -> Result<(&Schema, &SchemaHash), Box<async_graphql::Response>> {
+        match (&self.admin, variant) {
+            (Some((admin, hash)), Variant::Admin | Variant::AdminIfEnabled) => Ok((admin, hash)),
+            (None, Variant::Admin) => Err(Box::new(async_graphql::Response::from_errors(vec![
+                async_graphql::ServerError::new("the admin schema is not enabled", None),
+            ]))),
+            _ => Ok((&self.schema, &self.hash)),
+        }
+    }
+}
+
+/// Generates the schema for `catalog`, its admin variant when enabled and
+/// the GraphiQL example.
+fn build_schemas(
+    catalog: Catalog,
+    pool: &Arc<Pool>,
+    mock: Option<Arc<MockData>>,
+    options: &SchemaOptions,
+) -> Result<BuiltSchema, Box<dyn std::error::Error + Send + Sync>> {
+    let catalog = match &options.api_version {
+        Some(version) => catalog.for_api_version(version),
+        None => catalog,
+    };
+    let catalog = catalog
+        .without_empty_types(options.node_id.is_some())
+        .inflected(options.inflector());
+    let admin = if options.admin_schema {
+        let catalog = Arc::new(catalog.without_omit());
+        let (admin, ..) = build_schema_from_catalog(&catalog, pool, mock.clone(), options)?;
+        let hash = SchemaHash(schema_hash(&admin.sdl()).into());
+        Some((admin, hash))
+    } else {
+        None
+    };
+    // Exports stream from the database, which mock schemas do not have.
+    let exportable = mock.is_none() && !options.export_roles.is_empty();
+    let example = graphiql_example(&catalog);
+    let is_mock = mock.is_some();
+    let (schema, tables, matrix) =
+        build_schema_from_catalog(&Arc::new(catalog), pool, mock, options)?;
+    let planner = Arc::new(Planner::new(
+        pool.clone(),
+        tables.clone(),
+        options.read_only,
+        is_mock,
+    ));
+    Ok(BuiltSchema {
+        example,
+        hash: SchemaHash(schema_hash(&schema.sdl()).into()),
+        schema,
+        admin,
+        exports: exportable.then(|| {
+            Arc::new(Exports::new(
+                pool.clone(),
+                options.export_roles.clone(),
+                tables,
+            ))
+        }),
+        planner,
+        matrix: Arc::new(matrix),
+    })
+}
+
+/// Resolves [`Config::background_pool`], if set.
+fn resolve_background_pool(
+    config: Option<PoolConfig>,
+) -> Result<Option<BackgroundPool>, Box<dyn std::error::Error + Send + Sync>> {
+    Ok(match config {
+        Some(config) => Some(BackgroundPool(Arc::new(crate::db::pool::resolve(config)?))),
+        None => None,
+    })
+}
+
+/// The breaker for [`Config::circuit_breaker`], if set, probing `pool`.
+fn circuit_breaker(options: &SchemaOptions, pool: &Arc<Pool>) -> Option<Arc<CircuitBreaker>> {
+    let config = options.circuit_breaker.clone()?;
+    Some(Arc::new(CircuitBreaker::new(config, pool.clone())))
+}
+
 /// Builds a schema from the current database state.
 ///
 /// Used for the initial build and for automatic rebuilds triggered by DDL
 /// changes.
 pub(crate) async fn rebuild_schema(
     pool: &Arc<Pool>,
-    schemas: &[String],
-) -> Result<Schema, Box<dyn std::error::Error + Send + Sync>> {
-    let tables = crate::db::introspect::get_tables(pool, schemas).await;
+    introspection: &IntrospectionConfig,
+    options: &SchemaOptions,
+) -> Result<BuiltSchema, Box<dyn std::error::Error + Send + Sync>> {
+    let catalog = crate::db::introspect::get_catalog(pool, introspection).await?;
+    if options.subscriptions {
+        crate::db::changes::install_change_triggers(pool, &catalog).await?;
+    }
+    build_schemas(catalog, pool, None, options)
+}
+
+/// A generated schema, the tables it exposes and the roles its grants allow
+/// to run each root field.
+type GeneratedSchema = (Schema, Vec<Arc<Table>>, OperationMatrix);
 
+/// Generates the schema for `catalog`, lists the tables it exposes as their
+/// types see them (field names applied), and the roles each root field
+/// needs. With `mock` set, resolvers answer from the fixtures and never use
+/// `pool`.
+fn build_schema_from_catalog(
+    catalog: &Arc<Catalog>,
+    pool: &Arc<Pool>,
+    mock: Option<Arc<MockData>>,
+    options: &SchemaOptions,
+) -> Result<GeneratedSchema, Box<dyn std::error::Error + Send + Sync>> {
     let mut query_root = Object::new("Query");
     let mut mutation_root = Object::new("Mutation");
 
     // First pass: collect entity, query, and mutation artefacts per table.
     struct TableArtefacts {
         entity: Object,
-        query: crate::graphql::query::GeneratedQuery,
+        query: Option<crate::graphql::query::GeneratedQuery>,
         mutation: Option<crate::graphql::mutation::GeneratedMutation>,
+        finders: Vec<(String, Field)>,
+        records: Vec<Object>,
+        origin: String,
     }
 
     let mut artefacts = Vec::new();
+    let mut node_tables = Vec::new();
+    let mut change_subscriptions = Vec::new();
+    let mut refreshes = Vec::new();
+    let mut exposed = Vec::new();
+    let refresh_roles = Arc::new(options.refresh_roles.clone());
+    let mut joins = Joins::default();
+    let mut matrix = OperationMatrix::default();
+    let public = vec![PUBLIC_ROLE.to_string()];
+    let mut validation = Validation::default();
+
+    let inflector = options.inflector();
+    let enum_types = enum_types(catalog, inflector);
+    let custom_scalars = CustomScalars::new(&options.custom_scalars);
 
-    for table in tables {
-        if table.omit_read() {
+    for table in catalog.tables() {
+        if !table.is_exposed_for(Privilege::Select) {
             continue;
         }
 
-        let table = Arc::new(table);
-        let entity = graphql::generate_entity(table.clone());
-        let gq = graphql::generate_query(table.clone(), pool.clone());
-        let gm = if !table.omit_create() || !table.omit_update() || !table.omit_delete() {
-            Some(graphql::generate_mutation(table, pool.clone()))
+        let origin = format!("table {}.{}", table.schema_name(), table.name());
+        let table = apply_enum_columns(table, catalog, &enum_types);
+        let table = apply_unsupported_types(
+            &table,
+            options.unsupported_types,
+            custom_scalars.as_ref(),
+            &mut validation,
+        );
+        let table = apply_field_names(&table, options.camel_case_fields, inflector);
+        let table = apply_case_insensitive(&table, options.case_insensitive_conditions);
+        let table = apply_uploads(&table, options.uploads);
+        let (table, node_id) = apply_node_id_strategy(&table, options.node_id.as_ref());
+        // Just the global ID, see `Catalog::without_empty_types`; there is
+        // nothing to filter, order or find the rows by.
+        let id_only = !table.has_readable_columns();
+        if id_only && node_id.is_none() {
+            validation.problem(format!(
+                "{origin} has no readable columns, so type {} would have no fields; \
+                 expose a column or tag the table @omit read",
+                table.type_name()
+            ));
+            continue;
+        }
+        let relations = plan_relations(catalog, &table, &mut validation);
+        let table = apply_relation_precedence(&table, &relations, options.camel_case_fields);
+        let columns_collide =
+            table
+                .columns()
+                .iter()
+                .filter(|c| !c.omit_read())
+                .fold(false, |collide, column| {
+                    let origin = format!(
+                        "column {}.{}.{}",
+                        table.schema_name(),
+                        table.name(),
+                        column.name()
+                    );
+                    !validation.claim_field(&table.type_name(), column.field_name(), &origin)
+                        || collide
+                });
+        if columns_collide {
+            continue;
+        }
+        if node_id.is_some() {
+            node_tables.push(table.clone());
+        }
+        if !id_only {
+            exposed.push(table.clone());
+        }
+        let table = &table;
+        let select = table.privileges().roles(Privilege::Select);
+        if options.subscriptions && table.subscribe() {
+            let (payload, fields) =
+                graphql::subscription::generate_change_subscription(table.clone(), pool.clone());
+            for (name, _) in &fields {
+                matrix.allow(format!("Subscription.{name}"), select.to_vec());
+            }
+            change_subscriptions.push((origin.clone(), payload, fields));
+        }
+
+        if !refresh_roles.is_empty() && !options.read_only && table.refreshable() {
+            let (name, field) = graphql::refresh::generate_refresh(
+                table.clone(),
+                pool.clone(),
+                refresh_roles.clone(),
+            );
+            matrix.allow(format!("Mutation.{name}"), refresh_roles.to_vec());
+            refreshes.push((name, origin.clone(), field));
+        }
+
+        let (relations, fk_columns) =
+            relation_fields(catalog, table, relations, node_id, pool, &mut joins);
+        let mut hidden = if options.expose_fk_columns {
+            Vec::new()
+        } else {
+            fk_columns
+        };
+        if !options.expose_external_fk_columns {
+            hidden.extend(
+                catalog
+                    .external_foreign_keys()
+                    .filter(|fk| fk.table_oid() == *table.oid())
+                    .flat_map(|fk| fk.columns().iter().cloned()),
+            );
+        }
+        let permissions = graphql::permission::generate_permission_fields(
+            table,
+            &options.permission_fields,
+            pool,
+        );
+        let computed = graphql::function::generate_computed_columns(
+            table,
+            catalog,
+            pool,
+            options.locale.as_deref(),
+            options.camel_case_fields,
+            custom_scalars.as_ref(),
+        );
+        let entity = relations
+            .into_iter()
+            .chain(permissions)
+            .chain(computed.fields)
+            .fold(
+                graphql::generate_entity(
+                    table.clone(),
+                    node_id,
+                    options.locale.as_deref(),
+                    &hidden,
+                    custom_scalars.as_ref(),
+                ),
+                |obj, field| obj.field(field),
+            );
+        let gq = (!id_only).then(|| {
+            graphql::generate_query(
+                table.clone(),
+                pool.clone(),
+                options.large_table_rows,
+                options.indexed_order_by_rows,
+                options.unindexed_pattern_filters,
+                inflector,
+            )
+        });
+        let finders = if id_only {
+            Vec::new()
+        } else {
+            graphql::generate_finders(table, pool)
+        };
+        let writable = [Privilege::Insert, Privilege::Update, Privilege::Delete]
+            .into_iter()
+            .any(|privilege| table.is_exposed_for(privilege));
+        let gm = if writable && !id_only && !options.read_only {
+            Some(graphql::generate_mutation(
+                table.clone(),
+                pool.clone(),
+                node_id,
+                options.max_upload_bytes,
+                inflector,
+            ))
         } else {
             None
         };
 
+        if let Some(gq) = &gq {
+            matrix.allow(format!("Query.{}", gq.field_name), select.to_vec());
+        }
+        for (name, _) in &finders {
+            matrix.allow(format!("Query.{name}"), select.to_vec());
+        }
+        for (name, privilege, _) in gm.iter().flat_map(|gm| &gm.fields) {
+            let roles = intersect(select, table.privileges().roles(*privilege));
+            matrix.allow(format!("Mutation.{name}"), roles);
+        }
+
         artefacts.push(TableArtefacts {
             entity,
             query: gq,
             mutation: gm,
+            finders,
+            records: computed.records,
+            origin,
         });
     }
 
-    let has_mutations = artefacts
-        .iter()
-        .any(|a| a.mutation.as_ref().is_some_and(|m| !m.fields.is_empty()));
+    // Overloads share a name; only the first one is exposed.
+    let mut procedure_names = HashSet::new();
+    let procedures: Vec<_> = catalog
+        .procedures()
+        .filter(|p| !p.omit() && (p.volatility().is_read_only() || !options.read_only))
+        .filter_map(|p| {
+            graphql::procedure::generate_procedure(
+                p.clone(),
+                pool.clone(),
+                options.locale.as_deref(),
+                options.camel_case_fields,
+                custom_scalars.as_ref(),
+            )
+            .map(|generated| {
+                let origin = format!("procedure {}.{}", p.schema_name(), p.name());
+                (p.execute_roles().to_vec(), origin, generated)
+            })
+        })
+        .filter(|(_, _, p)| {
+            let free = procedure_names.insert(p.field_name.clone());
+            if !free {
+                eprintln!(
+                    "[turbograph] {} is overloaded; skipping all but the first",
+                    p.field_name
+                );
+            }
+            free
+        })
+        .collect();
+
+    let mut function_names = HashSet::new();
+    let functions: Vec<_> = catalog
+        .functions()
+        .filter(|f| !f.omit() && f.row_table().is_none())
+        .filter(|f| f.volatility().is_read_only() || !options.read_only)
+        .filter_map(|f| {
+            graphql::function::generate_function(
+                f.clone(),
+                catalog,
+                pool.clone(),
+                options.locale.as_deref(),
+                options.camel_case_fields,
+                custom_scalars.as_ref(),
+            )
+            .map(|generated| {
+                let origin = format!("function {}.{}", f.schema_name(), f.name());
+                (f.execute_roles().to_vec(), origin, generated)
+            })
+        })
+        .filter(|(_, _, f)| {
+            let free = function_names.insert(f.field_name.clone());
+            if !free {
+                eprintln!(
+                    "[turbograph] {} is overloaded; skipping all but the first",
+                    f.field_name
+                );
+            }
+            free
+        })
+        .collect();
+
+    let has_mutations = procedures.iter().any(|(_, _, p)| !p.read_only)
+        || functions.iter().any(|(_, _, f)| !f.read_only)
+        || !refreshes.is_empty()
+        || artefacts
+            .iter()
+            .any(|a| a.mutation.as_ref().is_some_and(|m| !m.fields.is_empty()));
 
     let mut builder = Schema::build(
         "Query",
@@ -137,27 +1162,123 @@ pub(crate) async fn rebuild_schema(
         } else {
             None
         },
-        None,
+        if options.subscriptions {
+            Some("Subscription")
+        } else {
+            None
+        },
     );
 
-    builder = builder.register(graphql::make_page_info_type());
+    for root in ["Query", "Mutation", "Subscription"] {
+        validation.claim_type(root, BUILT_IN);
+    }
+    let page_info = graphql::make_page_info_type();
+    validation.claim_type(page_info.type_name(), BUILT_IN);
+    builder = builder.register(page_info);
+    for scalar in graphql::make_scalars(custom_scalars.as_ref()) {
+        validation.claim_type(scalar.type_name(), BUILT_IN);
+        builder = builder.register(scalar);
+    }
+    for (oid, enum_type) in &enum_types {
+        let enum_type = graphql::make_enum_type(enum_type);
+        if let Some(table) = catalog.table(*oid) {
+            let origin = format!("enum table {}.{}", table.schema_name(), table.name());
+            validation.claim_type(enum_type.type_name(), &origin);
+        }
+        builder = builder.register(enum_type);
+    }
+    for ty in custom_types(catalog) {
+        let origin = format!("type {}.{}", ty.schema(), ty.name());
+        if let Some(enum_type) = graphql::make_pg_enum_type(&ty) {
+            validation.claim_type(enum_type.type_name(), &origin);
+            builder = builder.register(enum_type);
+        }
+        if let Some(object) =
+            graphql::make_composite_type(&ty, options.camel_case_fields, custom_scalars.as_ref())
+        {
+            validation.claim_type(object.type_name(), &origin);
+            builder = builder.register(object);
+        }
+        if let Some(input) = graphql::make_composite_input_type(&ty, options.camel_case_fields) {
+            validation.claim_type(input.type_name(), &origin);
+            builder = builder.register(input);
+        }
+    }
+    if catalog
+        .tables()
+        .any(|t| t.is_exposed_for(Privilege::Select) && t.uses_federation())
+    {
+        builder = builder.enable_federation();
+    }
+    if options.uploads {
+        builder = builder.enable_uploading();
+    }
+
+    if !options.admin_roles.is_empty() {
+        matrix.allow("Query.poolStatus", options.admin_roles.clone());
+        validation.claim_field("Query", "poolStatus", BUILT_IN);
+        let (pool_status, field) = graphql::admin::generate_pool_status(
+            pool.clone(),
+            Arc::new(options.admin_roles.clone()),
+        );
+        builder = builder.register(pool_status);
+        query_root = query_root.field(field);
+    }
 
-    for a in artefacts {
-        query_root = query_root.field(a.query.query_field);
-        builder = builder
-            .register(a.entity)
-            .register(a.query.condition_type)
-            .register(a.query.order_by_enum)
-            .register(a.query.connection_type)
-            .register(a.query.edge_type);
+    if let Some(strategy) = &options.node_id
+        && !node_tables.is_empty()
+    {
+        let roles = node_tables.iter().fold(Vec::new(), |roles, table| {
+            union(&roles, table.privileges().roles(Privilege::Select))
+        });
+        matrix.allow("Query.node", roles);
+        validation.claim_field("Query", "node", BUILT_IN);
+        builder = builder.register(graphql::node::make_node_interface(strategy.field_name()));
+        query_root = query_root.field(graphql::node::generate_node_query(
+            node_tables,
+            pool.clone(),
+            strategy.field_name(),
+        ));
+    }
 
-        for ft in a.query.condition_filter_types {
-            builder = builder.register(ft);
+    for a in artefacts {
+        validation.claim_type(a.entity.type_name(), &a.origin);
+        builder = builder.register(a.entity);
+        if let Some(query) = a.query {
+            for name in [
+                query.condition_type.type_name(),
+                query.order_by_enum.type_name(),
+                query.connection_type.type_name(),
+                query.edge_type.type_name(),
+            ] {
+                validation.claim_type(name, &a.origin);
+            }
+            if validation.claim_field("Query", &query.field_name, &a.origin) {
+                query_root = query_root.field(query.query_field);
+            }
+            builder = builder
+                .register(query.condition_type)
+                .register(query.order_by_enum)
+                .register(query.connection_type)
+                .register(query.edge_type);
+            for ft in query.condition_filter_types {
+                builder = builder.register(ft);
+            }
+        }
+        for (name, finder) in a.finders {
+            if validation.claim_field("Query", &name, &a.origin) {
+                query_root = query_root.field(finder);
+            }
+        }
+        for record in a.records {
+            builder = builder.register(record);
         }
 
         if let Some(gm) = a.mutation {
-            for field in gm.fields {
-                mutation_root = mutation_root.field(field);
+            for (name, _, field) in gm.fields {
+                if validation.claim_field("Mutation", &name, &a.origin) {
+                    mutation_root = mutation_root.field(field);
+                }
             }
             for input in gm.input_objects {
                 builder = builder.register(input);
@@ -165,11 +1286,560 @@ pub(crate) async fn rebuild_schema(
         }
     }
 
+    for (name, origin, field) in refreshes {
+        if validation.claim_field("Mutation", &name, &origin) {
+            mutation_root = mutation_root.field(field);
+        }
+    }
+
+    for (roles, origin, procedure) in procedures {
+        let root = if procedure.read_only {
+            "Query"
+        } else {
+            "Mutation"
+        };
+        matrix.allow(format!("{root}.{}", procedure.field_name), roles);
+        if validation.claim_field(root, &procedure.field_name, &origin) {
+            if procedure.read_only {
+                query_root = query_root.field(procedure.field);
+            } else {
+                mutation_root = mutation_root.field(procedure.field);
+            }
+        }
+        if let Some(input) = procedure.input {
+            builder = builder.register(input);
+        }
+        if let Some(payload) = procedure.payload {
+            builder = builder.register(payload);
+        }
+    }
+
+    for (roles, origin, function) in functions {
+        let root = if function.read_only {
+            "Query"
+        } else {
+            "Mutation"
+        };
+        matrix.allow(format!("{root}.{}", function.field_name), roles);
+        if validation.claim_field(root, &function.field_name, &origin) {
+            if function.read_only {
+                query_root = query_root.field(function.field);
+            } else {
+                mutation_root = mutation_root.field(function.field);
+            }
+        }
+        if let Some(input) = function.input {
+            builder = builder.register(input);
+        }
+        if let Some(record) = function.record {
+            builder = builder.register(record);
+        }
+    }
+
+    if options.subscriptions {
+        let (listen_payload, listen) = graphql::subscription::generate_listen_subscription();
+        let mut subscription_root = Subscription::new("Subscription");
+        if validation.claim_field("Subscription", "listen", BUILT_IN) {
+            subscription_root = subscription_root.field(listen);
+        }
+        matrix.allow("Subscription.listen", public.clone());
+        builder = builder.register(listen_payload);
+        builder = builder.register(graphql::subscription::make_change_op_enum());
+        for (origin, payload, fields) in change_subscriptions {
+            builder = builder.register(payload);
+            for (name, field) in fields {
+                if validation.claim_field("Subscription", &name, &origin) {
+                    subscription_root = subscription_root.field(field);
+                }
+            }
+        }
+        builder = builder.register(subscription_root);
+    }
+
+    if validation.claim_field("Query", "schemaVersion", BUILT_IN) {
+        query_root = query_root.field(graphql::version::generate_schema_version());
+    }
+    matrix.allow("Query.schemaVersion", public);
     builder = builder.register(query_root);
     if has_mutations {
         builder = builder.register(mutation_root);
     }
+    if let Some(mock) = mock {
+        builder = builder.data(mock);
+    }
+    if let Some(encryption) = &options.encryption {
+        builder = builder.data(encryption.clone());
+    }
+    builder = builder.data(options.non_finite_floats);
+    builder = builder.data(Arc::new(joins));
+    if let Some(transforms) = RowTransforms::new(&options.row_transforms) {
+        builder = builder.data(transforms);
+    }
+    if let Some(scalars) = custom_scalars {
+        builder = builder.data(scalars);
+    }
+    for extension in &options.extensions {
+        builder = builder.extension(SharedExtension(extension.clone()));
+    }
+    if let Some(limit) = options.max_complexity {
+        builder = builder
+            .limit_complexity(limit)
+            .extension(graphql::cost::CostReport { limit });
+    }
 
+    validation.finish()?;
     let schema = builder.finish()?;
-    Ok(schema)
+    Ok((schema, exposed, matrix))
+}
+
+/// The Postgres enum and composite types that exposed columns, routine
+/// arguments and function results use, directly or through domains, arrays
+/// and composite fields.
+fn custom_types(catalog: &Catalog) -> Vec<Type> {
+    fn visit(ty: &Type, found: &mut Vec<Type>) {
+        match ty.kind() {
+            Kind::Domain(inner) | Kind::Array(inner) => visit(inner, found),
+            Kind::Enum(_) if !found.contains(ty) => found.push(ty.clone()),
+            Kind::Composite(fields) if !found.contains(ty) => {
+                found.push(ty.clone());
+                for field in fields {
+                    visit(field.type_(), found);
+                }
+            }
+            _ => {}
+        }
+    }
+
+    let mut found = Vec::new();
+    for table in catalog
+        .tables()
+        .filter(|t| t.is_exposed_for(Privilege::Select))
+    {
+        for column in table.columns().iter().filter(|c| !c.omit_read()) {
+            visit(column._type(), &mut found);
+        }
+    }
+    for procedure in catalog.procedures().filter(|p| !p.omit()) {
+        for arg in procedure.args() {
+            visit(arg._type(), &mut found);
+        }
+    }
+    for function in catalog.functions().filter(|f| !f.omit()) {
+        for arg in function.args() {
+            visit(arg._type(), &mut found);
+        }
+        match function.returns() {
+            FunctionReturn::Scalar(ty) => visit(ty, &mut found),
+            FunctionReturn::Record(outputs) => {
+                for output in outputs {
+                    visit(output._type(), &mut found);
+                }
+            }
+            _ => {}
+        }
+    }
+    found
+}
+
+/// The GraphQL enum of every `@enum` table, keyed by table OID.
+fn enum_types(catalog: &Catalog, inflector: &dyn Inflector) -> HashMap<u32, Arc<EnumType>> {
+    catalog
+        .tables()
+        .filter(|t| t.is_enum() && !t.enum_values().is_empty())
+        .map(|t| {
+            let enum_type = EnumType::new(t.type_name(), t.enum_values().to_vec(), inflector);
+            (*t.oid(), Arc::new(enum_type))
+        })
+        .collect()
+}
+
+/// Exposes every single-column foreign key of `table` that references an
+/// `@enum` table as that table's enum instead of the underlying scalar,
+/// unless the constraint is tagged `@omit`.
+fn apply_enum_columns(
+    table: &Arc<Table>,
+    catalog: &Catalog,
+    enum_types: &HashMap<u32, Arc<EnumType>>,
+) -> Arc<Table> {
+    let references: Vec<_> = catalog
+        .foreign_keys()
+        .filter(|fk| fk.table_oid() == *table.oid() && fk.columns().len() == 1 && !fk.omit())
+        .filter_map(|fk| Some((&fk.columns()[0], enum_types.get(&fk.foreign_table_oid())?)))
+        .collect();
+    if references.is_empty() {
+        return table.clone();
+    }
+
+    let mut table = Table::clone(table);
+    for (column, enum_type) in references {
+        table.set_column_enum(column, enum_type.clone());
+    }
+    Arc::new(table)
+}
+
+/// Applies [`Config::case_insensitive_conditions`](crate::Config::case_insensitive_conditions)
+/// to the columns without a `@caseInsensitive` / `@caseSensitive` tag.
+fn apply_case_insensitive(table: &Arc<Table>, default: bool) -> Arc<Table> {
+    if !default {
+        return table.clone();
+    }
+    let mut table = Table::clone(table);
+    table.set_case_insensitive_default(default);
+    Arc::new(table)
+}
+
+/// Applies [`Config::unsupported_types`](crate::Config::unsupported_types),
+/// or a column's `@unsupported` tag, to the readable columns whose type has
+/// no GraphQL mapping. Columns that must fail the build are reported to
+/// `validation`.
+fn apply_unsupported_types(
+    table: &Arc<Table>,
+    default: UnsupportedTypes,
+    custom_scalars: Option<&CustomScalars>,
+    validation: &mut Validation,
+) -> Arc<Table> {
+    let mut skipped = Vec::new();
+    for column in table.columns() {
+        if column.omit_read() || graphql::is_supported_type(column, custom_scalars) {
+            continue;
+        }
+        match column.unsupported_tag().unwrap_or(default) {
+            UnsupportedTypes::String => {}
+            UnsupportedTypes::Skip => {
+                eprintln!(
+                    "[turbograph] skipping column {}.{}: unsupported type {}",
+                    table.name(),
+                    column.name(),
+                    column._type().name()
+                );
+                skipped.push(column.name().clone());
+            }
+            UnsupportedTypes::Error => validation.problem(format!(
+                "column {}.{} has unsupported type {}",
+                table.name(),
+                column.name(),
+                column._type().name()
+            )),
+        }
+    }
+    if skipped.is_empty() {
+        return table.clone();
+    }
+    let mut table = Table::clone(table);
+    for name in &skipped {
+        table.omit_column(name);
+    }
+    Arc::new(table)
+}
+
+/// Takes uploads for the `bytea` and `@largeObject` columns of `table`
+/// when [`Config::uploads`](crate::Config::uploads) is set.
+fn apply_uploads(table: &Arc<Table>, uploads: bool) -> Arc<Table> {
+    if !uploads {
+        return table.clone();
+    }
+    let mut table = Table::clone(table);
+    table.enable_uploads();
+    Arc::new(table)
+}
+
+/// Exposes every column of `table` under its `@name` tag, or else its
+/// camelCase or sanitized field name, see [`Config::camel_case_fields`].
+fn apply_field_names(
+    table: &Arc<Table>,
+    camel_case: bool,
+    inflector: &dyn Inflector,
+) -> Arc<Table> {
+    let renames: Vec<(String, String)> = table
+        .columns()
+        .iter()
+        .map(|c| {
+            let field_name = match c.name_tag() {
+                Some(tag) => sanitize_name(tag),
+                None => inflector.field_name(c.name(), camel_case),
+            };
+            (c.name().clone(), field_name)
+        })
+        .filter(|(name, field_name)| name != field_name)
+        .collect();
+    if renames.is_empty() {
+        return table.clone();
+    }
+
+    let mut table = Table::clone(table);
+    for (name, field_name) in renames {
+        table.rename_column_field(&name, &field_name);
+    }
+    Arc::new(table)
+}
+
+/// A relation field of a table's type, planned before the type is generated
+/// so that its columns can make way for it.
+enum PlannedRelation {
+    /// The row a foreign key constraint of the table references.
+    Forward(Arc<ForeignKey>, Arc<Table>),
+    /// Rows of the referencing table, through a foreign key constraint or
+    /// `@belongsTo`.
+    BelongsTo(Arc<ForeignKey>, Arc<Table>),
+    /// A reference declared with `@ref`.
+    Ref(Reference),
+}
+
+/// The relation fields of `table`'s type, with their field names: the rows
+/// its foreign keys reference, rows referencing it through a foreign key or
+/// `@belongsTo`, and references declared with `@ref`. Constraints tagged
+/// `@omit` and relations that lead to a table without a type are left out;
+/// `@ref` tags that cannot be resolved are reported to `validation`.
+fn plan_relations(
+    catalog: &Catalog,
+    table: &Table,
+    validation: &mut Validation,
+) -> Vec<(String, PlannedRelation)> {
+    let mut relations = Vec::new();
+
+    for fk in catalog
+        .foreign_keys()
+        .filter(|fk| !fk.is_synthetic() && !fk.omit() && fk.table_oid() == *table.oid())
+    {
+        let Some(referenced) = catalog
+            .table(fk.foreign_table_oid())
+            .filter(|t| t.is_exposed_for(Privilege::Select))
+        else {
+            continue;
+        };
+        relations.push((
+            fk.field_name(referenced),
+            PlannedRelation::Forward(fk.clone(), referenced.clone()),
+        ));
+    }
+
+    for fk in catalog
+        .foreign_keys()
+        .filter(|fk| !fk.omit() && fk.foreign_table_oid() == *table.oid())
+    {
+        let Some(referencing) = catalog
+            .table(fk.table_oid())
+            .filter(|t| t.is_exposed_for(Privilege::Select))
+        else {
+            continue;
+        };
+        relations.push((
+            fk.foreign_field_name(referencing),
+            PlannedRelation::BelongsTo(fk.clone(), referencing.clone()),
+        ));
+    }
+
+    for tag in parse_refs(table.tags()) {
+        match tag.resolve(table, catalog) {
+            Ok(reference) if reference.target().is_exposed_for(Privilege::Select) => {
+                relations.push((reference.name.clone(), PlannedRelation::Ref(reference)));
+            }
+            Ok(_) => {}
+            Err(reason) => validation.problem(format!(
+                "@ref {} on table {}.{} cannot be resolved: {reason}",
+                tag.name,
+                table.schema_name(),
+                table.name()
+            )),
+        }
+    }
+    relations
+}
+
+/// Relations take precedence over columns: a column whose field name a
+/// relation wants is exposed with a `Raw` suffix instead (`author` becomes
+/// `authorRaw`, or `author_raw` unless `camel_case` is set). Columns named
+/// with an `@name` tag keep their name, and the relation is skipped.
+fn apply_relation_precedence(
+    table: &Arc<Table>,
+    relations: &[(String, PlannedRelation)],
+    camel_case: bool,
+) -> Arc<Table> {
+    let renames: Vec<(String, String)> = table
+        .columns()
+        .iter()
+        .filter(|c| !c.omit_read() && c.name_tag().is_none())
+        .filter(|c| relations.iter().any(|(name, _)| name == c.field_name()))
+        .map(|c| {
+            let field_name = column_field_name(&format!("{}_raw", c.field_name()), camel_case);
+            eprintln!(
+                "[turbograph] {}.{} is taken by a relation; exposing column {} as {field_name}",
+                table.type_name(),
+                c.field_name(),
+                c.name()
+            );
+            (c.name().clone(), field_name)
+        })
+        .collect();
+    if renames.is_empty() {
+        return table.clone();
+    }
+
+    let mut table = Table::clone(table);
+    for (name, field_name) in renames {
+        table.rename_column_field(&name, &field_name);
+    }
+    Arc::new(table)
+}
+
+/// Generates the planned relation fields of `table`'s type, and lists the
+/// columns of `table` the generated foreign key and `@ref` fields start from.
+/// Relations whose field name is already taken, by a column or an earlier
+/// relation, are skipped with a warning. Foreign key relations are added to
+/// `joins` so queries can fetch them along with their parent rows.
+fn relation_fields(
+    catalog: &Catalog,
+    table: &Table,
+    relations: Vec<(String, PlannedRelation)>,
+    node_id: Option<&str>,
+    pool: &Arc<Pool>,
+    joins: &mut Joins,
+) -> (Vec<Field>, Vec<String>) {
+    let mut taken: HashSet<String> = table
+        .columns()
+        .iter()
+        .filter(|c| !c.omit_read())
+        .map(|c| c.field_name().to_string())
+        .chain(node_id.map(str::to_string))
+        .collect();
+
+    let mut fields = Vec::new();
+    let mut fk_columns = Vec::new();
+    for (field_name, relation) in relations {
+        let kind = match relation {
+            PlannedRelation::Forward(..) | PlannedRelation::BelongsTo(..) => "relation",
+            PlannedRelation::Ref(_) => "@ref",
+        };
+        if !taken.insert(field_name.clone()) {
+            eprintln!(
+                "[turbograph] {} already has a field named {field_name}; skipping {kind}",
+                table.type_name()
+            );
+            continue;
+        }
+        let description = relation_description(catalog, table, &relation);
+        let field = match relation {
+            PlannedRelation::Forward(fk, referenced) => {
+                fk_columns.extend(fk.columns().iter().cloned());
+                let join = Join {
+                    table: referenced.clone(),
+                    columns: fk.foreign_columns().to_vec(),
+                    parent_columns: fk.columns().to_vec(),
+                };
+                joins.insert(*table.oid(), field_name.clone(), join);
+                graphql::generate_forward_relation(fk, referenced, pool.clone())
+            }
+            PlannedRelation::BelongsTo(fk, referencing) => {
+                let join = Join {
+                    table: referencing.clone(),
+                    columns: fk.columns().to_vec(),
+                    parent_columns: fk.foreign_columns().to_vec(),
+                };
+                joins.insert(*table.oid(), field_name.clone(), join);
+                graphql::generate_backward_relation(fk, referencing, pool.clone())
+            }
+            PlannedRelation::Ref(reference) => {
+                let first_hops = reference.paths.iter().filter_map(|path| path.first());
+                fk_columns.extend(first_hops.flat_map(|hop| hop.columns.iter().cloned()));
+                graphql::generate_reference(Arc::new(reference), pool.clone())
+            }
+        };
+        fields.push(match description {
+            Some(description) => field.description(description),
+            None => field,
+        });
+    }
+    (fields, fk_columns)
+}
+
+/// Documents the `ON DELETE` / `ON UPDATE` actions of the foreign key
+/// constraints a relation follows, in either direction. Relations no
+/// constraint backs get no description.
+fn relation_description(
+    catalog: &Catalog,
+    table: &Table,
+    relation: &PlannedRelation,
+) -> Option<String> {
+    let mut constraints: Vec<&Arc<ForeignKey>> = Vec::new();
+    match relation {
+        PlannedRelation::Forward(fk, _) => constraints.push(fk),
+        PlannedRelation::BelongsTo(fk, _) => constraints.extend(catalog.constraint_between(
+            fk.table_oid(),
+            fk.columns(),
+            fk.foreign_table_oid(),
+            fk.foreign_columns(),
+        )),
+        PlannedRelation::Ref(reference) => {
+            for path in &reference.paths {
+                let mut from = *table.oid();
+                for hop in path {
+                    let to = *hop.table.oid();
+                    constraints.extend(
+                        catalog
+                            .constraint_between(from, &hop.columns, to, &hop.foreign_columns)
+                            .or_else(|| {
+                                catalog.constraint_between(
+                                    to,
+                                    &hop.foreign_columns,
+                                    from,
+                                    &hop.columns,
+                                )
+                            }),
+                    );
+                    from = to;
+                }
+            }
+        }
+    }
+    let mut seen = HashSet::new();
+    constraints.retain(|fk| seen.insert(fk.oid()));
+    let lines: Vec<String> = constraints.iter().map(|fk| fk.describe_actions()).collect();
+    (!lines.is_empty()).then(|| lines.join("\n"))
+}
+
+/// Decides whether `table` gets a global ID and under which field name,
+/// renaming its `id` column when the strategy asks for it. Only tables with a
+/// primary key can be identified.
+fn apply_node_id_strategy<'a>(
+    table: &Arc<Table>,
+    strategy: Option<&'a NodeIdStrategy>,
+) -> (Arc<Table>, Option<&'a str>) {
+    let Some(strategy) = strategy.filter(|_| !table.primary_key().is_empty()) else {
+        return (table.clone(), None);
+    };
+
+    match strategy {
+        NodeIdStrategy::Field(name) => {
+            if table
+                .columns()
+                .iter()
+                .any(|c| !c.omit_read() && c.field_name() == name)
+            {
+                eprintln!(
+                    "[turbograph] {}.{} has a column named {name}; not exposing a global ID for it",
+                    table.schema_name(),
+                    table.name()
+                );
+                return (table.clone(), None);
+            }
+            (table.clone(), Some(strategy.field_name()))
+        }
+        NodeIdStrategy::RenameIdColumn(column_field) => {
+            let mut renamed = Table::clone(table);
+            renamed.rename_column_field("id", column_field);
+            (Arc::new(renamed), Some(strategy.field_name()))
+        }
+    }
+}
+
+/// Introspects the database described by `config` without building a schema.
+///
+/// The returned [`Catalog`] can be serialized and later passed to
+/// [`TurboGraph::mock`]. `config.watch_pg` is ignored.
+pub async fn introspect(
+    config: Config,
+) -> Result<Catalog, Box<dyn std::error::Error + Send + Sync>> {
+    let introspection = IntrospectionConfig::from_config(&config);
+    let pool = crate::db::pool::resolve(config.pool)?;
+    crate::db::introspect::get_catalog(&pool, &introspection).await
 }
@@ -0,0 +1,166 @@
+//! A ready-made HTTP server for the generated schema, built on axum.
+//!
+//! Enabled by the `server` feature. [`serve`] builds the schema from a
+//! [`Config`] and answers GraphQL requests until the process stops, like the
+//! PostGraphile CLI; [`HttpServer`] exposes the same routes as a [`Router`]
+//! to merge into an existing application.
+//!
+//! ```rust,ignore
+//! let server = TurboGraph::new(config).await?;
+//! let app = HttpServer::new(server)
+//!     .auth(|headers| async move {
+//!         let Some(token) = bearer_token(&headers) else {
+//!             return Ok(None);
+//!         };
+//!         let claims = verify_jwt(token).map_err(|e| e.to_string())?;
+//!         Ok(Some(PgSettings::from_jwt_claims(&claims)))
+//!     })
+//!     .router();
+//! ```
+
+use std::future::Future;
+use std::pin::Pin;
+use std::sync::Arc;
+
+use async_graphql::ParseRequestError;
+use async_graphql::futures_util::TryStreamExt;
+use async_graphql::http::receive_body;
+use axum::Router;
+use axum::body::Body;
+use axum::extract::State;
+use axum::http::{HeaderMap, StatusCode, header};
+use axum::response::{Html, IntoResponse, Json, Response};
+use axum::routing::{get, post};
+
+use crate::models::config::Config;
+use crate::models::transaction::PgSettings;
+use crate::persisted::PersistedOperations;
+use crate::schema::TurboGraph;
+
+type BoxError = Box<dyn std::error::Error + Send + Sync>;
+
+/// Address [`serve`] listens on, PostGraphile's default port.
+pub const DEFAULT_ADDR: &str = "0.0.0.0:5000";
+
+/// Result of an auth hook: the request's settings, `None` for an anonymous
+/// request, or a message to reject it with `401 Unauthorized`.
+pub type AuthResult = Result<Option<PgSettings>, String>;
+
+type AuthFn = dyn Fn(HeaderMap) -> Pin<Box<dyn Future<Output = AuthResult> + Send>> + Send + Sync;
+
+/// The `/graphql` and `/graphiql` routes of a [`TurboGraph`].
+///
+/// `POST /graphql` takes JSON bodies and, with [`Config::uploads`], GraphQL
+/// multipart requests. `GET /graphiql` serves the GraphiQL IDE, opening with
+/// the schema's `@example` mutation when there is one.
+#[derive(Clone)]
+pub struct HttpServer {
+    server: TurboGraph,
+    auth: Option<Arc<AuthFn>>,
+    persisted: Option<Arc<PersistedOperations>>,
+}
+
+impl HttpServer {
+    pub fn new(server: TurboGraph) -> Self {
+        Self {
+            server,
+            auth: None,
+            persisted: None,
+        }
+    }
+
+    /// Derives each request's [`PgSettings`] from its headers, e.g. by
+    /// verifying the JWT in `Authorization`. Requests the hook returns
+    /// `None` for run without settings, as [`Config::default_role`].
+    pub fn auth<F, Fut>(mut self, hook: F) -> Self
+    where
+        F: Fn(HeaderMap) -> Fut + Send + Sync + 'static,
+        Fut: Future<Output = AuthResult> + Send + 'static,
+    {
+        self.auth = Some(Arc::new(move |headers| Box::pin(hook(headers))));
+        self
+    }
+
+    /// Strict persisted mode: `/graphql` only runs the documents of
+    /// `manifest`, sent by hash or in full, see
+    /// [`PersistedOperations::resolve`]. Build the manifest with
+    /// [`TurboGraph::persisted_operations`].
+    pub fn persisted_operations(mut self, manifest: PersistedOperations) -> Self {
+        self.persisted = Some(Arc::new(manifest));
+        self
+    }
+
+    pub fn router(self) -> Router {
+        Router::new()
+            .route("/graphql", post(graphql))
+            .route("/graphiql", get(graphiql))
+            .with_state(self)
+    }
+
+    /// Serves [`router`](Self::router) on `addr` until the process stops.
+    pub async fn serve(self, addr: &str) -> Result<(), BoxError> {
+        let listener = tokio::net::TcpListener::bind(addr).await?;
+        let local = listener.local_addr()?;
+        eprintln!("[turbograph] GraphQL endpoint: http://{local}/graphql");
+        eprintln!("[turbograph] GraphiQL: http://{local}/graphiql");
+        axum::serve(listener, self.router()).await?;
+        Ok(())
+    }
+}
+
+/// Builds the schema described by `config` and serves it on
+/// [`DEFAULT_ADDR`] until the process stops.
+///
+/// ```rust,ignore
+/// turbograph::serve(config).await?;
+/// ```
+pub async fn serve(config: Config) -> Result<(), BoxError> {
+    let server = TurboGraph::new(config).await?;
+    HttpServer::new(server).serve(DEFAULT_ADDR).await
+}
+
+async fn graphql(State(http): State<HttpServer>, headers: HeaderMap, body: Body) -> Response {
+    let settings = match &http.auth {
+        Some(auth) => match auth(headers.clone()).await {
+            Ok(settings) => settings,
+            Err(message) => return (StatusCode::UNAUTHORIZED, message).into_response(),
+        },
+        None => None,
+    };
+
+    let content_type = headers
+        .get(header::CONTENT_TYPE)
+        .and_then(|value| value.to_str().ok());
+    let body = body
+        .into_data_stream()
+        .map_err(std::io::Error::other)
+        .into_async_read();
+    let options = http.server.multipart_options();
+    let mut request = match receive_body(content_type, body, options).await {
+        Ok(request) => request,
+        Err(ParseRequestError::PayloadTooLarge) => {
+            return (StatusCode::PAYLOAD_TOO_LARGE, "upload too large").into_response();
+        }
+        Err(e) => return (StatusCode::BAD_REQUEST, e.to_string()).into_response(),
+    };
+    if let Some(settings) = settings {
+        request = request.data(settings);
+    }
+    if let Some(persisted) = &http.persisted {
+        request = match persisted.resolve(request) {
+            Ok(request) => request,
+            Err(response) => return Json(&response).into_response(),
+        };
+    }
+
+    let response = http.server.execute(request).await;
+    let mut http_response = Json(&response).into_response();
+    for (name, value) in &response.http_headers {
+        http_response.headers_mut().insert(name, value.clone());
+    }
+    http_response
+}
+
+async fn graphiql(State(http): State<HttpServer>) -> Html<String> {
+    Html(http.server.graphiql_with_examples("/graphql").await)
+}
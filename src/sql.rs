@@ -0,0 +1,368 @@
+//! Building blocks for generated SQL, shared by the planner and available to
+//! code that runs its own statements next to the generated schema.
+//!
+//! Statements may only contain two kinds of dynamic content: identifiers
+//! written through [`quote_ident`] / [`quote_qualified`] and values bound as
+//! `$n` parameters. [`Sql`] fragments enforce this by construction: text can
+//! only come from `'static` strings, everything else is an identifier or a
+//! value, and placeholders are numbered when the statement is built so
+//! fragments compose freely.
+//!
+//! ```rust,ignore
+//! use turbograph::sql::{Condition, Sql};
+//!
+//! let condition = Condition::all([
+//!     Condition::compare(Sql::ident("org_id"), "=", 7),
+//!     Condition::any([
+//!         Condition::compare(Sql::ident("status"), "=", "open"),
+//!         Condition::fragment(Sql::ident("archived_at").push(Sql::raw(" IS NULL"))),
+//!     ]),
+//! ]);
+//! let (text, params) = Sql::raw("SELECT * FROM ")
+//!     .push(Sql::qualified("app", "tickets"))
+//!     .push(Sql::raw(" WHERE "))
+//!     .push(condition.into_sql())
+//!     .build();
+//! // SELECT * FROM "app"."tickets" WHERE ("org_id" = $1 AND ("status" = $2 OR "archived_at" IS NULL))
+//! ```
+//!
+//! [`audit`] checks a finished statement; debug builds run it on every
+//! generated statement before it is sent.
+
+use std::fmt::Write;
+
+use tokio_postgres::types::ToSql;
+
+pub use crate::graphql::sql_scalar::SqlScalar;
+
+/// Quotes `name` as a PostgreSQL identifier, doubling embedded quotes:
+/// `user"s` -> `"user""s"`.
+pub fn quote_ident(name: &str) -> String {
+    format!("\"{}\"", name.replace('"', "\"\""))
+}
+
+/// `"schema"."name"`, each part quoted with [`quote_ident`].
+pub fn quote_qualified(schema: &str, name: &str) -> String {
+    format!("{}.{}", quote_ident(schema), quote_ident(name))
+}
+
+/// A piece of SQL: static text, quoted identifiers and bound values.
+#[derive(Clone, Debug, Default)]
+pub struct Sql {
+    parts: Vec<Part>,
+}
+
+#[derive(Clone, Debug)]
+enum Part {
+    Text(String),
+    Value(SqlScalar),
+}
+
+impl Sql {
+    /// Keywords, operators and punctuation. Only `'static` text is accepted
+    /// so runtime strings cannot end up in the statement unquoted.
+    pub fn raw(text: &'static str) -> Self {
+        Self::text(text.to_string())
+    }
+
+    /// A quoted identifier, e.g. a column name.
+    pub fn ident(name: &str) -> Self {
+        Self::text(quote_ident(name))
+    }
+
+    /// A schema-qualified, quoted table or function name.
+    pub fn qualified(schema: &str, name: &str) -> Self {
+        Self::text(quote_qualified(schema, name))
+    }
+
+    /// A column of a table alias, e.g. `t1."author_id"`.
+    pub fn column(alias: &str, name: &str) -> Self {
+        Self::text(format!("{}.{}", quote_ident(alias), quote_ident(name)))
+    }
+
+    /// A bound parameter.
+    pub fn value(value: impl Into<SqlScalar>) -> Self {
+        Self {
+            parts: vec![Part::Value(value.into())],
+        }
+    }
+
+    fn text(text: String) -> Self {
+        Self {
+            parts: vec![Part::Text(text)],
+        }
+    }
+
+    /// Appends `other` to this fragment.
+    pub fn push(mut self, other: Sql) -> Self {
+        self.parts.extend(other.parts);
+        self
+    }
+
+    /// `fragments` separated by `separator`, e.g. a column list.
+    pub fn separated(fragments: impl IntoIterator<Item = Sql>, separator: &'static str) -> Self {
+        fragments
+            .into_iter()
+            .enumerate()
+            .fold(Self::default(), |sql, (i, fragment)| {
+                let sql = if i > 0 {
+                    sql.push(Sql::raw(separator))
+                } else {
+                    sql
+                };
+                sql.push(fragment)
+            })
+    }
+
+    /// `{kind} {table} {alias} ON {on}`, e.g.
+    /// `Sql::join("LEFT JOIN", Sql::qualified("app", "users"), "u", condition)`.
+    pub fn join(kind: &'static str, table: Sql, alias: &str, on: Condition) -> Self {
+        Sql::raw(" ")
+            .push(Sql::raw(kind))
+            .push(Sql::raw(" "))
+            .push(table)
+            .push(Sql::raw(" "))
+            .push(Sql::ident(alias))
+            .push(Sql::raw(" ON "))
+            .push(on.into_sql())
+    }
+
+    /// Whether the fragment has no text and no values.
+    pub fn is_empty(&self) -> bool {
+        self.parts.is_empty()
+    }
+
+    /// The statement text with values numbered `$1..$n`, and the values.
+    pub fn build(self) -> (String, Vec<SqlScalar>) {
+        let mut params = Vec::new();
+        let text = self.build_after(&mut params);
+        (text, params)
+    }
+
+    /// Like [`build`](Self::build), but continues the numbering of `params`
+    /// and appends the values to it, so the fragment can be spliced into a
+    /// statement that already binds parameters.
+    pub fn build_after(self, params: &mut Vec<SqlScalar>) -> String {
+        let mut text = String::new();
+        for part in self.parts {
+            match part {
+                Part::Text(t) => text.push_str(&t),
+                Part::Value(v) => {
+                    params.push(v);
+                    write!(text, "${}", params.len()).unwrap();
+                }
+            }
+        }
+        text
+    }
+}
+
+/// Borrows `params` in the form `tokio_postgres::Client::query` expects.
+pub fn param_refs(params: &[SqlScalar]) -> Vec<&(dyn ToSql + Sync)> {
+    params.iter().map(|p| p as &(dyn ToSql + Sync)).collect()
+}
+
+/// A `WHERE` tree. Empty `All` is `TRUE`, empty `Any` is `FALSE`.
+#[derive(Clone, Debug)]
+pub enum Condition {
+    /// Every condition holds (`AND`).
+    All(Vec<Condition>),
+    /// At least one condition holds (`OR`).
+    Any(Vec<Condition>),
+    /// The condition does not hold.
+    Not(Box<Condition>),
+    /// Any boolean SQL expression.
+    Fragment(Sql),
+}
+
+impl Condition {
+    pub fn all(conditions: impl IntoIterator<Item = Condition>) -> Self {
+        Self::All(conditions.into_iter().collect())
+    }
+
+    pub fn any(conditions: impl IntoIterator<Item = Condition>) -> Self {
+        Self::Any(conditions.into_iter().collect())
+    }
+
+    pub fn negate(condition: Condition) -> Self {
+        Self::Not(Box::new(condition))
+    }
+
+    pub fn fragment(sql: Sql) -> Self {
+        Self::Fragment(sql)
+    }
+
+    /// `{left} {operator} $n`, e.g. `Condition::compare(Sql::ident("age"), ">=", 18)`.
+    pub fn compare(left: Sql, operator: &'static str, value: impl Into<SqlScalar>) -> Self {
+        Self::Fragment(
+            left.push(Sql::raw(" "))
+                .push(Sql::raw(operator))
+                .push(Sql::raw(" "))
+                .push(Sql::value(value)),
+        )
+    }
+
+    /// The condition as SQL; groups are parenthesized.
+    pub fn into_sql(self) -> Sql {
+        match self {
+            Self::All(conditions) if conditions.is_empty() => Sql::raw("TRUE"),
+            Self::Any(conditions) if conditions.is_empty() => Sql::raw("FALSE"),
+            Self::All(conditions) => Self::group(conditions, " AND "),
+            Self::Any(conditions) => Self::group(conditions, " OR "),
+            Self::Not(condition) => Sql::raw("NOT ").push(Self::group(vec![*condition], "")),
+            Self::Fragment(sql) => sql,
+        }
+    }
+
+    fn group(conditions: Vec<Condition>, separator: &'static str) -> Sql {
+        Sql::raw("(")
+            .push(Sql::separated(
+                conditions.into_iter().map(Condition::into_sql),
+                separator,
+            ))
+            .push(Sql::raw(")"))
+    }
+}
+
+/// Rejects statements carrying anything that could only have been
+/// interpolated as a raw value: string literals, dollar-quoted strings,
+/// numeric literals, comments, statement separators and unterminated
+/// identifiers. Text inside quoted identifiers is skipped.
+pub fn audit(sql: &str) -> Result<(), String> {
+    let bytes = sql.as_bytes();
+    let mut i = 0;
+
+    while i < bytes.len() {
+        let next = bytes.get(i + 1).copied();
+        match bytes[i] {
+            b'"' => {
+                let start = i;
+                i += 1;
+                loop {
+                    match bytes.get(i) {
+                        None => return Err(format!("unterminated identifier at byte {start}")),
+                        Some(b'"') if bytes.get(i + 1) == Some(&b'"') => i += 2,
+                        Some(b'"') => break,
+                        Some(_) => i += 1,
+                    }
+                }
+            }
+            b'\'' => return Err(format!("string literal at byte {i}")),
+            b';' => return Err(format!("statement separator at byte {i}")),
+            b'-' if next == Some(b'-') => return Err(format!("comment at byte {i}")),
+            b'/' if next == Some(b'*') => return Err(format!("comment at byte {i}")),
+            b'$' => {
+                if !next.is_some_and(|c| c.is_ascii_digit()) {
+                    return Err(format!("dollar-quoted string at byte {i}"));
+                }
+                i += 1;
+                while bytes.get(i + 1).is_some_and(u8::is_ascii_digit) {
+                    i += 1;
+                }
+            }
+            c if c.is_ascii_digit() && !is_word_byte(bytes, i.wrapping_sub(1)) => {
+                return Err(format!("numeric literal at byte {i}"));
+            }
+            _ => {}
+        }
+        i += 1;
+    }
+    Ok(())
+}
+
+/// Whether the byte at `i` continues a keyword or alias such as `t1`.
+fn is_word_byte(bytes: &[u8], i: usize) -> bool {
+    bytes
+        .get(i)
+        .is_some_and(|&c| c.is_ascii_alphanumeric() || c == b'_')
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_quote_ident_doubles_quotes() {
+        assert_eq!(quote_ident("users"), "\"users\"");
+        assert_eq!(quote_ident("user\"s"), "\"user\"\"s\"");
+        assert_eq!(quote_qualified("app", "a\"b"), "\"app\".\"a\"\"b\"");
+    }
+
+    #[test]
+    fn test_fragments_number_values_when_built() {
+        let condition = Condition::all([
+            Condition::compare(Sql::ident("org_id"), "=", 7),
+            Condition::any([
+                Condition::compare(Sql::ident("status"), "=", "open"),
+                Condition::negate(Condition::fragment(
+                    Sql::ident("archived_at").push(Sql::raw(" IS NULL")),
+                )),
+            ]),
+        ]);
+        let sql = Sql::raw("SELECT * FROM ")
+            .push(Sql::qualified("app", "tickets"))
+            .push(Sql::raw(" t"))
+            .push(Sql::join(
+                "JOIN",
+                Sql::qualified("app", "orgs"),
+                "o",
+                Condition::fragment(
+                    Sql::column("o", "id")
+                        .push(Sql::raw(" = "))
+                        .push(Sql::column("t", "org_id")),
+                ),
+            ))
+            .push(Sql::raw(" WHERE "))
+            .push(condition.into_sql());
+
+        let mut params = vec![SqlScalar::Bool(true)];
+        let text = sql.clone().build_after(&mut params);
+        assert_eq!(
+            text,
+            "SELECT * FROM \"app\".\"tickets\" t JOIN \"app\".\"orgs\" \"o\" ON \"o\".\"id\" = \"t\".\"org_id\" \
+             WHERE (\"org_id\" = $2 AND (\"status\" = $3 OR NOT (\"archived_at\" IS NULL)))"
+        );
+        assert_eq!(params.len(), 3);
+        assert_eq!(audit(&text), Ok(()));
+
+        let (text, params) = sql.build();
+        assert!(text.contains("\"org_id\" = $1"), "{text}");
+        assert_eq!(params.len(), 2);
+    }
+
+    #[test]
+    fn test_empty_groups() {
+        assert_eq!(Condition::all([]).into_sql().build().0, "TRUE");
+        assert_eq!(Condition::any([]).into_sql().build().0, "FALSE");
+        assert!(Sql::separated([], ", ").is_empty());
+    }
+
+    #[test]
+    fn test_audit_accepts_generated_shapes() {
+        for sql in [
+            "SELECT COUNT(*) FROM \"app\".\"users\" WHERE \"id\" = $1",
+            "SELECT t2.* FROM \"app\".\"a\" t1 JOIN \"app\".\"b\" t2 ON t2.\"id\" = t1.\"b_id\" WHERE t1.\"id\" = $12",
+            "UPDATE \"app\".\"x\" SET \"updated_at\" = now(), \"n\" = NULL WHERE \"a\" <> $1 RETURNING *",
+            "SELECT * FROM \"it's; -- /* $$ 1\"\"\".\"t\" ORDER BY \"1\" DESC LIMIT $1 OFFSET $2",
+        ] {
+            assert_eq!(audit(sql), Ok(()), "{sql}");
+        }
+    }
+
+    #[test]
+    fn test_audit_rejects_interpolated_values() {
+        for sql in [
+            "SELECT * FROM \"t\" WHERE \"name\" = 'bob'",
+            "SELECT * FROM \"t\" WHERE \"id\" = 1",
+            "SELECT * FROM \"t\" LIMIT 10",
+            "SELECT * FROM \"t\"; DROP TABLE \"t\"",
+            "SELECT * FROM \"t\" -- trailing",
+            "SELECT * FROM \"t\" /* hidden */",
+            "SELECT $$text$$",
+            "SELECT * FROM \"t\" WHERE \"a\" = $x",
+            "SELECT * FROM \"unterminated",
+        ] {
+            assert!(audit(sql).is_err(), "{sql}");
+        }
+    }
+}
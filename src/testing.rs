@@ -0,0 +1,410 @@
+//! Helpers for writing integration tests against a real PostgreSQL database.
+//!
+//! Enabled by the `testing` feature. Each [`TestDatabase`] owns a uniquely
+//! named schema, so tests can run in parallel against one shared database
+//! without stepping on each other.
+//!
+//! ```rust,ignore
+//! let db = TestDatabase::new(&url).await?;
+//! db.execute_sql("CREATE TABLE widgets (id serial PRIMARY KEY, name text NOT NULL);")
+//!     .await?;
+//! let schema = db.build().await?;
+//!
+//! let res = schema.execute("{ allWidgets { totalCount } }").await;
+//! res.assert_ok();
+//! res.assert_sql_contains("FROM \"{schema}\".\"widgets\"");
+//! assert_snapshot("widgets", &schema.sdl().await);
+//! db.drop().await?;
+//! ```
+//!
+//! [`SqlPlanner`] renders the SQL of a GraphQL document from a saved
+//! catalog without any database, for golden files checked with
+//! [`assert_sql_snapshot`].
+
+use std::path::Path;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Arc, Mutex};
+
+use async_graphql::ServerError;
+
+use crate::db::request::{PLANNED, RequestState};
+use crate::models::catalog::Catalog;
+use crate::models::config::{Config, PoolConfig, SchemaOptions};
+use crate::schema::TurboGraph;
+
+type BoxError = Box<dyn std::error::Error + Send + Sync>;
+
+/// A throwaway schema inside an existing database, populated from SQL
+/// fixtures.
+///
+/// Tests end with [`drop`](Self::drop). A test that fails before then still
+/// has its schemas dropped when the value goes out of scope, over a
+/// connection of its own.
+pub struct TestDatabase {
+    url: String,
+    schema: String,
+    client: tokio_postgres::Client,
+    /// Created through [`create_schema`](Self::create_schema).
+    extra_schemas: Mutex<Vec<String>>,
+    dropped: bool,
+}
+
+impl TestDatabase {
+    /// Connects to `url` and creates a fresh, uniquely named schema. Fixture
+    /// SQL executed through this value resolves unqualified names against it.
+    pub async fn new(url: &str) -> Result<Self, BoxError> {
+        static COUNTER: AtomicU64 = AtomicU64::new(0);
+
+        let (client, connection) = tokio_postgres::connect(url, tokio_postgres::NoTls).await?;
+        tokio::spawn(async move {
+            let _ = connection.await;
+        });
+
+        let schema = format!(
+            "turbograph_test_{}_{}",
+            std::process::id(),
+            COUNTER.fetch_add(1, Ordering::Relaxed)
+        );
+        client
+            .batch_execute(&format!(
+                "DROP SCHEMA IF EXISTS \"{schema}\" CASCADE;
+                CREATE SCHEMA \"{schema}\";
+                SET search_path TO \"{schema}\", public;"
+            ))
+            .await?;
+
+        Ok(Self {
+            url: url.to_string(),
+            schema,
+            client,
+            extra_schemas: Mutex::default(),
+            dropped: false,
+        })
+    }
+
+    /// Name of the schema owned by this test database.
+    pub fn schema_name(&self) -> &str {
+        &self.schema
+    }
+
+    /// Creates the schema `{schema}_{suffix}`, for fixtures spanning several
+    /// schemas, and returns its name. It is dropped along with the test
+    /// schema.
+    pub async fn create_schema(&self, suffix: &str) -> Result<String, BoxError> {
+        let name = format!("{}_{suffix}", self.schema);
+        self.client
+            .batch_execute(&format!("CREATE SCHEMA \"{name}\""))
+            .await?;
+        self.extra_schemas.lock().unwrap().push(name.clone());
+        Ok(name)
+    }
+
+    /// Runs `sql` (possibly several statements) inside the test schema.
+    pub async fn execute_sql(&self, sql: &str) -> Result<(), BoxError> {
+        self.client.batch_execute(sql).await?;
+        Ok(())
+    }
+
+    /// Runs the SQL fixture file at `path` inside the test schema.
+    pub async fn load_fixture(&self, path: impl AsRef<Path>) -> Result<(), BoxError> {
+        let sql = tokio::fs::read_to_string(path).await?;
+        self.execute_sql(&sql).await
+    }
+
+    /// Introspects the test schema and builds a GraphQL schema from it.
+    pub async fn build(&self) -> Result<TestSchema, BoxError> {
+        self.build_with(|_| {}).await
+    }
+
+    /// Like [`build`](Self::build), but lets `configure` adjust the
+    /// [`Config`] first, e.g. to enable optional schema features.
+    pub async fn build_with(
+        &self,
+        configure: impl FnOnce(&mut Config),
+    ) -> Result<TestSchema, BoxError> {
+        let mut config = self.config();
+        configure(&mut config);
+        let server = TurboGraph::new(config).await?;
+
+        Ok(TestSchema {
+            server,
+            schema: self.schema.clone(),
+        })
+    }
+
+    /// The [`Config`] [`build`](Self::build) uses: the test schema only,
+    /// every optional feature off.
+    pub fn config(&self) -> Config {
+        Config::new(
+            PoolConfig::ConnectionString(self.url.clone()),
+            vec![self.schema.clone()],
+        )
+    }
+
+    /// Drops the test schema, the schemas made with
+    /// [`create_schema`](Self::create_schema), and everything in them.
+    pub async fn drop(mut self) -> Result<(), BoxError> {
+        self.client.batch_execute(&self.drop_statement()).await?;
+        self.dropped = true;
+        Ok(())
+    }
+
+    fn drop_statement(&self) -> String {
+        let schemas: Vec<String> = std::iter::once(&self.schema)
+            .chain(self.extra_schemas.lock().unwrap().iter())
+            .map(|name| format!("\"{name}\""))
+            .collect();
+        format!("DROP SCHEMA IF EXISTS {} CASCADE", schemas.join(", "))
+    }
+}
+
+impl Drop for TestDatabase {
+    fn drop(&mut self) {
+        if self.dropped {
+            return;
+        }
+        // The test's runtime may be unwinding from a panic, so the cleanup
+        // connects on a thread and runtime of its own.
+        let url = self.url.clone();
+        let sql = self.drop_statement();
+        let cleanup = std::thread::spawn(move || -> Result<(), BoxError> {
+            let runtime = tokio::runtime::Builder::new_current_thread()
+                .enable_all()
+                .build()?;
+            runtime.block_on(async {
+                let (client, connection) =
+                    tokio_postgres::connect(&url, tokio_postgres::NoTls).await?;
+                tokio::spawn(connection);
+                client.batch_execute(&sql).await?;
+                Ok(())
+            })
+        });
+        if let Ok(Err(e)) = cleanup.join() {
+            eprintln!(
+                "[turbograph] failed to drop test schema {}: {e}",
+                self.schema
+            );
+        }
+    }
+}
+
+/// A GraphQL schema built from a [`TestDatabase`].
+pub struct TestSchema {
+    server: TurboGraph,
+    schema: String,
+}
+
+impl TestSchema {
+    /// The underlying server, for tests that need the full API.
+    pub fn server(&self) -> &TurboGraph {
+        &self.server
+    }
+
+    /// The generated schema rendered as SDL.
+    pub async fn sdl(&self) -> String {
+        self.server.schema().await.sdl()
+    }
+
+    /// Executes `request` and captures every SQL statement it issued.
+    pub async fn execute(&self, request: impl Into<async_graphql::Request>) -> TestResponse {
+        let state = Arc::new(RequestState::capturing_statements());
+        let response = self
+            .server
+            .execute_with_state(request.into(), state.clone())
+            .await;
+
+        TestResponse {
+            response,
+            sql: state.statements(),
+            schema: self.schema.clone(),
+        }
+    }
+}
+
+/// The outcome of [`TestSchema::execute`].
+pub struct TestResponse {
+    pub response: async_graphql::Response,
+    /// SQL statements in the order they were sent.
+    pub sql: Vec<String>,
+    schema: String,
+}
+
+impl TestResponse {
+    /// The response `data` as JSON.
+    pub fn data(&self) -> serde_json::Value {
+        self.response.data.clone().into_json().unwrap_or_default()
+    }
+
+    /// Panics if the response contains any GraphQL errors.
+    pub fn assert_ok(&self) -> &Self {
+        assert!(
+            self.response.errors.is_empty(),
+            "GraphQL response contains errors: {:?}",
+            self.response.errors
+        );
+        self
+    }
+
+    /// Panics unless some executed statement contains `needle`. The
+    /// placeholder `{schema}` is replaced with the test schema name.
+    pub fn assert_sql_contains(&self, needle: &str) -> &Self {
+        let needle = needle.replace("{schema}", &self.schema);
+        assert!(
+            self.sql.iter().any(|stmt| stmt.contains(&needle)),
+            "no executed SQL contains {needle:?}; statements: {:#?}",
+            self.sql
+        );
+        self
+    }
+}
+
+/// Renders the SQL the generated resolvers send for a GraphQL document,
+/// from a saved [`Catalog`] and without connecting to a database. Lock the
+/// output in with [`assert_sql_snapshot`] to catch changes to the generated
+/// queries:
+///
+/// ```rust,ignore
+/// let catalog = serde_json::from_str(&std::fs::read_to_string("catalog.json")?)?;
+/// let planner = SqlPlanner::new(catalog, SchemaOptions::default())?;
+/// let sql = planner.plan("{ allUsers(first: 10) { nodes { id } } }").await.unwrap();
+/// assert_sql_snapshot("all_users", &sql);
+/// ```
+pub struct SqlPlanner {
+    server: TurboGraph,
+}
+
+impl SqlPlanner {
+    /// Builds the schema `options` generate for `catalog`, as
+    /// [`TurboGraph::mock`] does.
+    pub fn new(catalog: Catalog, options: SchemaOptions) -> Result<Self, BoxError> {
+        Ok(Self {
+            server: TurboGraph::unconnected(catalog, None, options)?,
+        })
+    }
+
+    /// The statements `request` sends, in order, with parameters left as
+    /// their `$n` placeholders so the output does not depend on argument
+    /// values. Fails with the response errors when the request is invalid.
+    ///
+    /// Each field stops at its first transaction, so statements that depend
+    /// on the rows of another (`@ref` relations, permission fields) are not
+    /// rendered. Foreign key relations are part of their parent's statement.
+    pub async fn plan(
+        &self,
+        request: impl Into<async_graphql::Request>,
+    ) -> Result<Vec<String>, Vec<ServerError>> {
+        let state = Arc::new(RequestState::planning());
+        let response = self
+            .server
+            .execute_with_state(request.into(), state.clone())
+            .await;
+        let errors: Vec<ServerError> = response
+            .errors
+            .into_iter()
+            .filter(|e| {
+                !e.extensions
+                    .as_ref()
+                    .and_then(|ext| ext.get("code"))
+                    .is_some_and(|code| *code == async_graphql::Value::from(PLANNED))
+            })
+            .collect();
+        if errors.is_empty() {
+            Ok(state.statements())
+        } else {
+            Err(errors)
+        }
+    }
+}
+
+/// Compares `actual` with the checked-in snapshot `tests/snapshots/{name}.graphql`
+/// (relative to the crate under test), in the spirit of `insta`.
+///
+/// - A missing snapshot fails the assertion, with the output written next to
+///   where it belongs as `{name}.graphql.new`.
+/// - On a mismatch the new output is written next to it as `{name}.graphql.new`
+///   and the assertion panics, pointing at the first differing line.
+/// - With `TURBOGRAPH_UPDATE_SNAPSHOTS=1` missing snapshots are written and
+///   mismatching ones overwritten.
+///
+/// Plugin authors can use this to pin down how their plugin changes the
+/// generated SDL.
+pub fn assert_snapshot(name: &str, actual: &str) {
+    assert_golden(&format!("{name}.graphql"), actual);
+}
+
+/// Like [`assert_snapshot`] for SQL statements, e.g. from
+/// [`SqlPlanner::plan`]: compares them with `tests/snapshots/{name}.sql`, one
+/// statement per line.
+pub fn assert_sql_snapshot(name: &str, statements: &[String]) {
+    let sql: String = statements.iter().map(|stmt| format!("{stmt};\n")).collect();
+    assert_golden(&format!("{name}.sql"), &sql);
+}
+
+/// Compares `actual` with the checked-in file `tests/snapshots/{file}`; see
+/// [`assert_snapshot`].
+fn assert_golden(file: &str, actual: &str) {
+    let dir = std::env::var("CARGO_MANIFEST_DIR")
+        .map(std::path::PathBuf::from)
+        .unwrap_or_default()
+        .join("tests")
+        .join("snapshots");
+    let path = dir.join(file);
+    let pending = dir.join(format!("{file}.new"));
+    let actual = normalize_snapshot(actual);
+    let update = std::env::var("TURBOGRAPH_UPDATE_SNAPSHOTS").is_ok_and(|v| v == "1");
+
+    let expected = match std::fs::read_to_string(&path) {
+        Ok(expected) => normalize_snapshot(&expected),
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => {
+            std::fs::create_dir_all(&dir).expect("failed to create snapshot directory");
+            if update {
+                std::fs::write(&path, &actual).expect("failed to write snapshot");
+                eprintln!("[turbograph] wrote new snapshot {}", path.display());
+                return;
+            }
+            std::fs::write(&pending, &actual).expect("failed to write pending snapshot");
+            panic!(
+                "snapshot {file} is missing\nnew output written to {}; rerun with TURBOGRAPH_UPDATE_SNAPSHOTS=1 to accept it",
+                pending.display()
+            );
+        }
+        Err(e) => panic!("failed to read snapshot {}: {e}", path.display()),
+    };
+
+    if expected == actual {
+        let _ = std::fs::remove_file(&pending);
+        return;
+    }
+
+    if update {
+        std::fs::write(&path, &actual).expect("failed to update snapshot");
+        eprintln!("[turbograph] updated snapshot {}", path.display());
+        return;
+    }
+
+    std::fs::write(&pending, &actual).expect("failed to write pending snapshot");
+    let (line, old, new) = expected
+        .lines()
+        .map(Some)
+        .chain(std::iter::repeat(None))
+        .zip(actual.lines().map(Some).chain(std::iter::repeat(None)))
+        .enumerate()
+        .find(|(_, (old, new))| old != new)
+        .map(|(i, (old, new))| (i + 1, old.unwrap_or("<eof>"), new.unwrap_or("<eof>")))
+        .unwrap_or((0, "", ""));
+    panic!(
+        "snapshot {file} does not match (first difference at line {line}):\n  expected: {old}\n    actual: {new}\nnew output written to {}; rerun with TURBOGRAPH_UPDATE_SNAPSHOTS=1 to accept it",
+        pending.display()
+    );
+}
+
+/// Trims trailing whitespace so editor settings do not cause spurious diffs.
+fn normalize_snapshot(sdl: &str) -> String {
+    let mut out: String = sdl
+        .lines()
+        .map(str::trim_end)
+        .collect::<Vec<_>>()
+        .join("\n");
+    out.push('\n');
+    out
+}
@@ -0,0 +1,51 @@
+use regex::Regex;
+
+/// Matches `text` against a shell-style glob where `*` matches any run of
+/// characters and `?` matches exactly one. Everything else is literal.
+/// Examples:
+/// - "audit_*" matches "audit_log" and "audit_"
+/// - "user?" matches "users" but not "user"
+pub fn glob_match(pattern: &str, text: &str) -> bool {
+    let mut re = String::with_capacity(pattern.len() + 2);
+    re.push('^');
+    for ch in pattern.chars() {
+        match ch {
+            '*' => re.push_str(".*"),
+            '?' => re.push('.'),
+            _ => re.push_str(&regex::escape(ch.encode_utf8(&mut [0; 4]))),
+        }
+    }
+    re.push('$');
+
+    Regex::new(&re).is_ok_and(|re| re.is_match(text))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_glob_literal() {
+        assert!(glob_match("users", "users"));
+        assert!(!glob_match("users", "users_archive"));
+    }
+
+    #[test]
+    fn test_glob_star() {
+        assert!(glob_match("audit_*", "audit_log"));
+        assert!(glob_match("audit_*", "audit_"));
+        assert!(!glob_match("audit_*", "audits"));
+    }
+
+    #[test]
+    fn test_glob_question_mark() {
+        assert!(glob_match("user?", "users"));
+        assert!(!glob_match("user?", "user"));
+    }
+
+    #[test]
+    fn test_glob_escapes_regex_metacharacters() {
+        assert!(glob_match("public.users", "public.users"));
+        assert!(!glob_match("public.users", "publicXusers"));
+    }
+}
@@ -1,39 +1,85 @@
 use regex::Regex;
-use std::sync::LazyLock;
+use std::collections::HashMap;
+use std::sync::{LazyLock, RwLock};
+
+use crate::models::table::Table;
 
 /// Convert a string to camelCase. It can handle PascalCase, snake_case, and kebab-case.
 /// Examples:
 /// - "PascalCase" -> "pascalCase"
 /// - "snake_case" -> "snakeCase"
 /// - "kebab-case" -> "kebabCase"   
+///
 /// Note: It will also handle mixed cases like "APIResponse" -> "apiResponse"
 /// - "API_Response" -> "apiResponse"
+///
+/// Words registered with [`register_acronym`] keep their casing after the
+/// first word: "api_response_id" -> "apiResponseID".
 pub fn to_camel_case(text: &str) -> String {
-    // Insert a space before capital letters (PascalCase → Pascal Case)
-    static RE_PASCAL: LazyLock<Regex> = LazyLock::new(|| Regex::new(r"([a-z0-9])([A-Z])").unwrap());
-
-    // Replace underscores and hyphens with spaces
-    static RE_SEP: LazyLock<Regex> = LazyLock::new(|| Regex::new(r"[_-]+").unwrap());
-
-    // Split with one or more space
-    static RE_SPACE: LazyLock<Regex> = LazyLock::new(|| Regex::new(r"\s+").unwrap());
-
-    let res = RE_PASCAL.replace_all(text, "$1 $2");
-    let res = RE_SEP.replace_all(&res, " ");
-    let res = RE_SPACE.split(&res);
+    join_words(text, false)
+}
 
-    res.enumerate()
+/// Joins the words of `text` in camelCase, or in PascalCase when `pascal` is
+/// set. Registered acronyms keep their casing, except as the first word of a
+/// camelCase name.
+fn join_words(text: &str, pascal: bool) -> String {
+    let acronyms = ACRONYMS.read().unwrap();
+    split_words(text, true)
+        .into_iter()
+        .enumerate()
         .map(|(idx, word)| {
-            if idx == 0 {
+            if idx == 0 && !pascal {
                 word.to_lowercase()
+            } else if let Some(acronym) = acronyms.get(&word.to_lowercase()) {
+                acronym.clone()
+            } else if idx == 0 {
+                capitalize_first(&word.to_lowercase())
             } else {
                 capitalize_first(word)
             }
         })
-        .collect::<Vec<String>>()
-        .join("")
-        .trim()
-        .to_owned()
+        .collect()
+}
+
+/// Splits `text` into words. Words end at every character that is neither a
+/// letter nor a digit (`_`, `-`, spaces, ...), before an uppercase letter
+/// that follows a lowercase letter or a digit ("camelCase"), and before the
+/// last capital of an acronym followed by a lowercase letter
+/// ("APIResponse" -> "API", "Response"). Letter classes are Unicode-aware.
+///
+/// With `digit_boundaries`, a letter after a digit starts a new word as well:
+/// "user2fa" -> "user2", "fa".
+fn split_words(text: &str, digit_boundaries: bool) -> Vec<&str> {
+    let mut words = Vec::new();
+    let mut start = None;
+    let mut prev: Option<char> = None;
+    let mut chars = text.char_indices().peekable();
+
+    while let Some((i, c)) = chars.next() {
+        if !c.is_alphanumeric() {
+            if let Some(s) = start.take() {
+                words.push(&text[s..i]);
+            }
+            prev = None;
+            continue;
+        }
+
+        let next = chars.peek().map(|&(_, n)| n);
+        let boundary = prev.is_some_and(|p| {
+            (c.is_uppercase() && (p.is_lowercase() || p.is_numeric()))
+                || (c.is_uppercase() && p.is_uppercase() && next.is_some_and(char::is_lowercase))
+                || (digit_boundaries && p.is_numeric() && c.is_alphabetic())
+        });
+        if boundary && let Some(s) = start.replace(i) {
+            words.push(&text[s..i]);
+        }
+        start.get_or_insert(i);
+        prev = Some(c);
+    }
+    if let Some(s) = start {
+        words.push(&text[s..]);
+    }
+    words
 }
 
 fn capitalize_first(s: &str) -> String {
@@ -54,40 +100,248 @@ fn capitalize_first(s: &str) -> String {
 /// - "PascalCase" -> "pascal_case"
 /// - "camelCase" -> "camel_case"
 /// - "kebab-case" -> "kebab_case"
+///
 /// Note: It will also handle mixed cases like "APIResponse" -> "api_response"
 /// - "API_Response" -> "api_response"
+#[allow(dead_code)]
 pub fn to_snake_case(text: &str) -> String {
-    // 1. Compile Regexes only once for performance
-    static RE_HYPHEN: LazyLock<Regex> = LazyLock::new(|| Regex::new(r"-").unwrap());
-    static RE_LOWER_UPPER: LazyLock<Regex> =
-        LazyLock::new(|| Regex::new(r"([a-z0-9])([A-Z])").unwrap());
-    static RE_ACRONYM: LazyLock<Regex> =
-        LazyLock::new(|| Regex::new(r"([A-Z]+)([A-Z][a-z0-9])").unwrap());
-
-    // 2. Apply transformations
-    let res = RE_HYPHEN.replace_all(text, "_");
-
-    // First, handle cases like "APIResponse" -> "API_Response"
-    let res = RE_ACRONYM.replace_all(&res, "${1}_$2");
-
-    // Then, handle cases like "camelCase" -> "camel_Case"
-    let res = RE_LOWER_UPPER.replace_all(&res, "${1}_$2");
-
-    res.to_lowercase()
+    split_words(text, false)
+        .iter()
+        .map(|word| word.to_lowercase())
+        .collect::<Vec<_>>()
+        .join("_")
 }
 
 /// Convert a string to PascalCase. It can handle snake_case, camelCase, and kebab-case.
 /// Examples:
 /// - "snake_case" -> "SnakeCase"
 /// - "blog_posts" -> "BlogPosts" (use with singularize for "BlogPost")
+/// - "api_response" -> "APIResponse" once "API" is a registered acronym
 pub fn to_pascal_case(text: &str) -> String {
-    capitalize_first(&to_camel_case(text))
+    join_words(text, true)
+}
+
+/// Acronyms registered with [`register_acronym`], by lowercase word.
+static ACRONYMS: LazyLock<RwLock<HashMap<String, String>>> = LazyLock::new(Default::default);
+
+/// Registers an acronym, e.g. `"ID"` or `"URL"`, that keeps its casing in
+/// generated camelCase and PascalCase names: an `api_response_id` column
+/// becomes `apiResponseID` and an `api_responses` table `APIResponse`. Call
+/// it before building the schema; later registrations take effect on the
+/// next rebuild.
+pub fn register_acronym(acronym: &str) {
+    ACRONYMS
+        .write()
+        .unwrap()
+        .insert(acronym.to_lowercase(), acronym.to_string());
+}
+
+/// Convert a value to a GraphQL enum item name in CONSTANT_CASE, e.g.
+/// "in_review" -> "IN_REVIEW", "In review" -> "IN_REVIEW" and "createdAt" ->
+/// "CREATED_AT". Digits stay in the word around them ("user2fa" ->
+/// "USER2FA"). Characters GraphQL names cannot contain become `_`, and names
+/// that would start with a digit are prefixed with an underscore.
+pub fn to_constant_case(text: &str) -> String {
+    static RE_INVALID: LazyLock<Regex> = LazyLock::new(|| Regex::new(r"[^A-Z0-9]+").unwrap());
+
+    let name = split_words(text, false)
+        .iter()
+        .map(|word| word.to_uppercase())
+        .collect::<Vec<_>>()
+        .join("_");
+    let name = RE_INVALID.replace_all(&name, "_");
+    if name.is_empty() || name.starts_with(|c: char| c.is_ascii_digit()) {
+        format!("_{name}")
+    } else {
+        name.into_owned()
+    }
+}
+
+/// Turn `text` into a valid GraphQL name: characters other than ASCII letters,
+/// digits and `_` become `_`, and a leading digit gets a `_` prefix.
+/// Examples:
+/// - "first name" -> "first_name"
+/// - "2fa" -> "_2fa"
+pub fn sanitize_name(text: &str) -> String {
+    let name: String = text
+        .chars()
+        .map(|c| if c.is_ascii_alphanumeric() { c } else { '_' })
+        .collect();
+    if name.is_empty() || name.starts_with(|c: char| c.is_ascii_digit()) {
+        format!("_{name}")
+    } else {
+        name
+    }
+}
+
+/// The GraphQL field name of the column `column`: camelCase when
+/// `camel_case` is set, the column name as is otherwise, sanitized either way.
+/// Example: "created_at" -> "createdAt" or "created_at"
+pub fn column_field_name(column: &str, camel_case: bool) -> String {
+    if camel_case {
+        sanitize_name(&to_camel_case(column))
+    } else {
+        sanitize_name(column)
+    }
+}
+
+/// Singular/plural pairs registered with [`register_plural`], by lowercase
+/// word.
+#[derive(Default)]
+struct PluralOverrides {
+    plurals: HashMap<String, String>,
+    singulars: HashMap<String, String>,
+}
+
+static PLURAL_OVERRIDES: LazyLock<RwLock<PluralOverrides>> = LazyLock::new(Default::default);
+
+/// Registers an irregular singular/plural pair that [`singularize`] and
+/// [`pluralize`] use instead of the built-in rules, e.g. `("staff", "staff")`
+/// for an uncountable word or `("datum", "data")`. It also applies as the last
+/// word of snake_case names, so `warehouse_staff` keeps its name too. Call it
+/// before building the schema; later registrations take effect on the next
+/// rebuild.
+pub fn register_plural(singular: &str, plural: &str) {
+    let (singular, plural) = (singular.to_lowercase(), plural.to_lowercase());
+    let mut overrides = PLURAL_OVERRIDES.write().unwrap();
+    overrides.plurals.insert(singular.clone(), plural.clone());
+    overrides.plurals.insert(plural.clone(), plural.clone());
+    overrides.singulars.insert(plural, singular.clone());
+    overrides.singulars.insert(singular.clone(), singular);
+}
+
+/// The registered replacement for the last word of `text`, keeping the
+/// words before it.
+fn plural_override(
+    text: &str,
+    words: impl Fn(&PluralOverrides) -> &HashMap<String, String>,
+) -> Option<String> {
+    let overrides = PLURAL_OVERRIDES.read().unwrap();
+    let (prefix, last) = text.split_at(text.rfind('_').map_or(0, |i| i + 1));
+    let word = words(&overrides).get(&last.to_lowercase())?;
+    Some(format!("{prefix}{word}"))
 }
 
 pub fn singularize(text: &str) -> String {
-    pluralizer::pluralize(text, 1, false)
+    plural_override(text, |o| &o.singulars).unwrap_or_else(|| pluralizer::pluralize(text, 1, false))
+}
+
+pub fn pluralize(text: &str) -> String {
+    plural_override(text, |o| &o.plurals).unwrap_or_else(|| pluralizer::pluralize(text, 2, false))
+}
+
+/// The `By{Columns}` suffix shared by relation field names, e.g.
+/// `["author_id"]` -> "ByAuthorId", `["org_id", "user_id"]` -> "ByOrgIdAndUserId".
+fn relation_suffix(columns: &[String]) -> String {
+    let columns: Vec<String> = columns.iter().map(|c| to_pascal_case(c)).collect();
+    format!("By{}", columns.join("And"))
+}
+
+/// Default name of a forward relation field (referencing row -> referenced row).
+/// Example: table "users" via ["author_id"] -> "userByAuthorId"
+pub fn forward_relation_name(foreign_table: &str, columns: &[String]) -> String {
+    to_camel_case(&singularize(foreign_table)) + &relation_suffix(columns)
+}
+
+/// Default name of a backward relation field (referenced row -> referencing rows).
+/// Example: table "posts" via ["author_id"] -> "postsByAuthorId"
+pub fn backward_relation_name(table: &str, columns: &[String]) -> String {
+    to_camel_case(&pluralize(table)) + &relation_suffix(columns)
 }
 
+/// Name of the root field fetching a row of `type_name` by unique fields.
+/// Example: "User" by ["email"] -> "userByEmail"
+pub fn finder_name(type_name: &str, fields: &[&str]) -> String {
+    let fields: Vec<String> = fields.iter().map(|f| f.to_string()).collect();
+    to_camel_case(type_name) + &relation_suffix(&fields)
+}
+
+/// The `By{Fields}` suffix of the mutations targeting a row by its primary
+/// key fields.
+/// Example: ["id"] -> "ById", as in "updateUserById"
+pub fn key_suffix(fields: &[&str]) -> String {
+    let fields: Vec<String> = fields.iter().map(|f| f.to_string()).collect();
+    relation_suffix(&fields)
+}
+
+/// Names the GraphQL types, fields and enum items generated from the
+/// catalog, see [`Config::inflector`](crate::Config::inflector). Every
+/// method defaults to the built-in naming it documents, so an inflector
+/// overrides only what it renames:
+///
+/// ```rust,ignore
+/// /// `Blog_Post`, `allBlogPosts`, ... for `blog.posts`.
+/// struct SchemaPrefix;
+///
+/// impl Inflector for SchemaPrefix {
+///     fn type_name(&self, table: &Table) -> String {
+///         let name = DefaultInflector.type_name(table);
+///         format!("{}_{name}", to_pascal_case(table.schema_name()))
+///     }
+/// }
+/// ```
+///
+/// `@name` smart tags take precedence over the inflector.
+pub trait Inflector: Send + Sync {
+    /// The object type of the rows of `table`, the singular PascalCase
+    /// table name: `BlogPost` for `blog_posts`. Connection, edge, input and
+    /// mutation names are derived from it.
+    fn type_name(&self, table: &Table) -> String {
+        to_pascal_case(&singularize(table.name()))
+    }
+
+    /// The field of the table column `column`, see [`column_field_name`].
+    fn field_name(&self, column: &str, camel_case: bool) -> String {
+        column_field_name(column, camel_case)
+    }
+
+    /// The root field listing the rows of `table`: `allBlogPosts`.
+    fn all_rows_field(&self, table: &Table) -> String {
+        format!("all{}", to_pascal_case(table.name()))
+    }
+
+    /// The connection type of `type_name`: `BlogPostConnection`.
+    fn connection_type(&self, type_name: &str) -> String {
+        format!("{type_name}Connection")
+    }
+
+    /// The edge type of `type_name`: `BlogPostEdge`.
+    fn edge_type(&self, type_name: &str) -> String {
+        format!("{type_name}Edge")
+    }
+
+    /// The mutation creating a row of `type_name`: `createBlogPost`.
+    fn create_field(&self, type_name: &str) -> String {
+        format!("create{type_name}")
+    }
+
+    /// The mutation updating rows of `type_name`. `key` is the
+    /// [`key_suffix`] of a mutation updating one row (`updateBlogPostById`)
+    /// and empty for the one taking a condition (`updateBlogPost`).
+    fn update_field(&self, type_name: &str, key: &str) -> String {
+        format!("update{type_name}{key}")
+    }
+
+    /// The mutation deleting rows of `type_name`, like
+    /// [`update_field`](Self::update_field).
+    fn delete_field(&self, type_name: &str, key: &str) -> String {
+        format!("delete{type_name}{key}")
+    }
+
+    /// The enum item of a value of an `@enum` table, see
+    /// [`to_constant_case`]: `IN_REVIEW` for `in_review`.
+    fn enum_value(&self, value: &str) -> String {
+        to_constant_case(value)
+    }
+}
+
+/// The built-in naming, used when [`Config::inflector`](crate::Config::inflector)
+/// is `None`.
+#[derive(Clone, Copy, Debug, Default)]
+pub struct DefaultInflector;
+
+impl Inflector for DefaultInflector {}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -128,9 +382,114 @@ mod tests {
         assert_eq!(res, "kebab_case");
     }
 
+    #[test]
+    fn test_pascal_case() {
+        assert_eq!(to_pascal_case("blog_posts"), "BlogPosts");
+        assert_eq!(to_pascal_case("blogPost"), "BlogPost");
+        assert_eq!(to_pascal_case("HTTPServer"), "HttpServer");
+        assert_eq!(to_pascal_case("order--items  v2"), "OrderItemsV2");
+        assert_eq!(to_pascal_case("__meta__"), "Meta");
+        assert_eq!(to_pascal_case(""), "");
+    }
+
+    #[test]
+    fn test_digit_boundaries() {
+        assert_eq!(to_pascal_case("user2fa"), "User2Fa");
+        assert_eq!(to_pascal_case("address2"), "Address2");
+        assert_eq!(to_pascal_case("v2Items"), "V2Items");
+        assert_eq!(to_camel_case("oauth2_token"), "oauth2Token");
+        assert_eq!(to_snake_case("user2fa"), "user2fa");
+        assert_eq!(to_constant_case("user2fa"), "USER2FA");
+    }
+
+    #[test]
+    fn test_unicode_words() {
+        assert_eq!(to_pascal_case("élève_notes"), "ÉlèveNotes");
+        assert_eq!(to_camel_case("ÜberGröße"), "überGröße");
+        assert_eq!(to_snake_case("naïveBayes"), "naïve_bayes");
+        assert_eq!(to_constant_case("café au lait"), "CAF_AU_LAIT");
+    }
+
+    #[test]
+    fn test_constant_case_from_other_cases() {
+        assert_eq!(to_constant_case("createdAt"), "CREATED_AT");
+        assert_eq!(to_constant_case("APIResponse"), "API_RESPONSE");
+        assert_eq!(to_constant_case("kebab-case"), "KEBAB_CASE");
+        assert_eq!(to_constant_case(" padded "), "PADDED");
+        assert_eq!(to_constant_case("!!"), "_");
+    }
+
+    #[test]
+    fn test_constant_case() {
+        assert_eq!(to_constant_case("in_review"), "IN_REVIEW");
+        assert_eq!(to_constant_case("In review"), "IN_REVIEW");
+        assert_eq!(to_constant_case("draft"), "DRAFT");
+        assert_eq!(to_constant_case("2fa"), "_2FA");
+    }
+
+    #[test]
+    fn test_sanitize_name() {
+        assert_eq!(sanitize_name("created_at"), "created_at");
+        assert_eq!(sanitize_name("first name"), "first_name");
+        assert_eq!(sanitize_name("prix-€"), "prix__");
+        assert_eq!(sanitize_name("2fa"), "_2fa");
+    }
+
+    #[test]
+    fn test_column_field_name() {
+        assert_eq!(column_field_name("created_at", true), "createdAt");
+        assert_eq!(column_field_name("created_at", false), "created_at");
+        assert_eq!(column_field_name("createdAt", false), "createdAt");
+        assert_eq!(column_field_name("2fa code", true), "_2FaCode");
+        assert_eq!(column_field_name("2fa code", false), "_2fa_code");
+    }
+
     #[test]
     fn test_singularize() {
         assert_eq!(singularize("countries"), "country");
         assert_eq!(singularize("states"), "state");
     }
+
+    #[test]
+    fn test_registered_plurals_take_precedence() {
+        register_plural("equipment", "equipment");
+        register_plural("cactus", "cacti");
+        assert_eq!(singularize("equipment"), "equipment");
+        assert_eq!(pluralize("gym_equipment"), "gym_equipment");
+        assert_eq!(pluralize("cactus"), "cacti");
+        assert_eq!(singularize("desert_cacti"), "desert_cactus");
+        assert_eq!(pluralize("cacti"), "cacti");
+    }
+
+    #[test]
+    fn test_registered_acronyms_keep_their_casing() {
+        register_acronym("HTTP");
+        register_acronym("URL");
+        assert_eq!(to_camel_case("callback_url"), "callbackURL");
+        assert_eq!(to_camel_case("http_callback_url"), "httpCallbackURL");
+        assert_eq!(to_pascal_case("http_callbacks"), "HTTPCallbacks");
+        assert_eq!(to_pascal_case("urlShortener"), "URLShortener");
+    }
+
+    #[test]
+    fn test_relation_names() {
+        let cols = vec!["org_id".to_string(), "user_id".to_string()];
+        assert_eq!(
+            forward_relation_name("memberships", &cols),
+            "membershipByOrgIdAndUserId"
+        );
+        assert_eq!(
+            backward_relation_name("blog_posts", &["author_id".to_string()]),
+            "blogPostsByAuthorId"
+        );
+    }
+
+    #[test]
+    fn test_finder_name() {
+        assert_eq!(finder_name("BlogPost", &["slug"]), "blogPostBySlug");
+        assert_eq!(
+            finder_name("Membership", &["org_id", "rowId"]),
+            "membershipByOrgIdAndRowId"
+        );
+    }
 }
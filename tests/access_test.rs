@@ -0,0 +1,367 @@
+mod common;
+
+use async_graphql::futures_util::StreamExt;
+use turbograph::{PermissionField, TransactionConfig};
+
+/// `poolStatus` only exists with admin roles configured and only answers
+/// requests running as one of them.
+#[tokio::test]
+async fn pool_status_is_restricted_to_admin_roles() {
+    let db = common::fixture("CREATE TABLE widgets (id serial PRIMARY KEY);").await;
+
+    let schema = db.build().await.expect("schema build failed");
+    assert!(!schema.sdl().await.contains("poolStatus"));
+
+    let schema = db
+        .build_with(|config| config.admin_roles = vec!["ops".into()])
+        .await
+        .expect("schema build failed");
+    let query = "{ poolStatus { maxSize size available waiting timeouts } }";
+
+    let res = schema.execute(query).await;
+    assert_eq!(
+        res.response.errors[0]
+            .extensions
+            .as_ref()
+            .unwrap()
+            .get("code"),
+        Some(&async_graphql::Value::from("FORBIDDEN"))
+    );
+
+    let as_role = |role: &str| {
+        async_graphql::Request::new(query).data(TransactionConfig {
+            role: Some(role.into()),
+            ..TransactionConfig::default()
+        })
+    };
+    assert!(schema.execute(as_role("app_user")).await.response.is_err());
+
+    let res = schema.execute(as_role("ops")).await;
+    let status = &res.assert_ok().data()["poolStatus"];
+    assert!(status["maxSize"].as_i64().unwrap() > 0);
+    // Connections opened during introspection are idle again.
+    assert!(status["size"].as_i64().unwrap() > 0);
+    assert_eq!(status["available"], status["size"]);
+    assert_eq!(status["waiting"], 0);
+    assert_eq!(status["timeouts"], 0);
+
+    db.drop().await.expect("failed to drop test schema");
+}
+
+/// `refresh{T}` exists for `@refreshable` materialized views once refresh
+/// roles are configured, and only answers requests running as one of them.
+#[tokio::test]
+async fn refreshable_views_refresh_for_refresh_roles() {
+    let db = common::fixture(
+        "CREATE TABLE sales (id serial PRIMARY KEY, amount int NOT NULL);
+         INSERT INTO sales (amount) VALUES (5);
+         CREATE MATERIALIZED VIEW sales_totals AS
+             SELECT 1 AS id, sum(amount)::int AS total FROM sales;
+         CREATE UNIQUE INDEX ON sales_totals (id);
+         CREATE MATERIALIZED VIEW sales_counts AS SELECT count(*)::int AS n FROM sales;
+         COMMENT ON MATERIALIZED VIEW sales_totals IS '@refreshable';
+         COMMENT ON TABLE sales IS '@refreshable';",
+    )
+    .await;
+
+    let schema = db.build().await.expect("schema build failed");
+    assert!(!schema.sdl().await.contains("refreshSalesTotal"));
+
+    let schema = db
+        .build_with(|config| config.refresh_roles = vec!["postgres".into()])
+        .await
+        .expect("schema build failed");
+    let sdl = schema.sdl().await;
+    assert!(sdl.contains("refreshSalesTotal: Boolean!"), "{sdl}");
+    assert!(!sdl.contains("refreshSalesCount"), "{sdl}");
+    assert!(!sdl.contains("refreshSale:"), "{sdl}");
+
+    db.execute_sql("INSERT INTO sales (amount) VALUES (7);")
+        .await
+        .expect("insert failed");
+    let mutation = "mutation { refreshSalesTotal }";
+
+    let res = schema.execute(mutation).await;
+    assert_eq!(
+        res.response.errors[0]
+            .extensions
+            .as_ref()
+            .unwrap()
+            .get("code"),
+        Some(&async_graphql::Value::from("FORBIDDEN"))
+    );
+
+    let request = async_graphql::Request::new(mutation).data(TransactionConfig {
+        role: Some("postgres".into()),
+        ..TransactionConfig::default()
+    });
+    let res = schema.execute(request).await;
+    res.assert_ok().assert_sql_contains(
+        "REFRESH MATERIALIZED VIEW CONCURRENTLY \"{schema}\".\"sales_totals\"",
+    );
+    assert_eq!(res.data()["refreshSalesTotal"], true);
+
+    let res = schema
+        .execute("{ allSalesTotals { nodes { total } } }")
+        .await;
+    assert_eq!(
+        res.assert_ok().data()["allSalesTotals"]["nodes"][0]["total"],
+        12
+    );
+
+    db.drop().await.expect("failed to drop test schema");
+}
+
+/// Permission fields evaluate their expression for each row, and are left
+/// out when the expression would not pass the SQL audit.
+#[tokio::test]
+async fn permission_fields_evaluate_per_row() {
+    let db = common::test_database().await;
+    let schema_name = db.schema_name().to_string();
+    db.execute_sql(&format!(
+        r#"CREATE TABLE posts (id int PRIMARY KEY, author text NOT NULL);
+           INSERT INTO posts VALUES (1, current_user), (2, 'someone else');
+           CREATE FUNCTION "{schema_name}".can_edit(p posts) RETURNS boolean
+               LANGUAGE sql STABLE AS $$ SELECT p.author = current_user $$;"#
+    ))
+    .await
+    .expect("fixture failed");
+
+    let schema = db
+        .build_with(|config| {
+            config.permission_fields = vec![
+                PermissionField {
+                    table: "posts".into(),
+                    field: "canEdit".into(),
+                    expression: format!("\"{schema_name}\".can_edit(posts)"),
+                },
+                PermissionField {
+                    table: "posts".into(),
+                    field: "canDelete".into(),
+                    expression: "posts.author = 'admin'".into(),
+                },
+            ]
+        })
+        .await
+        .expect("schema build failed");
+    let sdl = schema.sdl().await;
+    assert!(sdl.contains("canEdit: Boolean!"), "{sdl}");
+    assert!(!sdl.contains("canDelete"), "{sdl}");
+
+    let res = schema
+        .execute("{ allPosts(orderBy: [ID_ASC]) { nodes { id canEdit } } }")
+        .await;
+    res.assert_ok()
+        .assert_sql_contains("SELECT coalesce((\"{schema}\".can_edit(posts)), false)");
+    assert_eq!(
+        res.data()["allPosts"]["nodes"],
+        serde_json::json!([{ "id": 1, "canEdit": true }, { "id": 2, "canEdit": false }])
+    );
+
+    db.drop().await.expect("failed to drop test schema");
+}
+
+/// The admin schema ignores `@omit` and serves admin roles, while everyone
+/// else keeps the restricted schema.
+#[tokio::test]
+async fn admin_schema_ignores_omit_for_admin_roles() {
+    let db = common::fixture(
+        "CREATE TABLE accounts (id serial PRIMARY KEY, email text, password_hash text);
+         COMMENT ON COLUMN accounts.password_hash IS '@omit';
+         CREATE TABLE audits (id serial PRIMARY KEY, entry text);
+         COMMENT ON TABLE audits IS '@omit';
+         INSERT INTO accounts (email, password_hash) VALUES ('a@example.com', 'x');
+         INSERT INTO audits (entry) VALUES ('login');",
+    )
+    .await;
+
+    let schema = db
+        .build_with(|config| {
+            config.admin_roles = vec!["postgres".into()];
+            config.admin_schema = true;
+            config.subscriptions = true;
+        })
+        .await
+        .expect("schema build failed");
+
+    let sdl = schema.sdl().await;
+    assert!(!sdl.contains("passwordHash") && !sdl.contains("password_hash"));
+    assert!(!sdl.contains("allAudits"));
+
+    let query = "{ allAccounts { nodes { password_hash } } allAudits { totalCount } }";
+    assert!(schema.execute(query).await.response.is_err());
+
+    let as_admin = async_graphql::Request::new(query).data(TransactionConfig {
+        role: Some("postgres".into()),
+        ..TransactionConfig::default()
+    });
+    let res = schema.execute(as_admin).await;
+    let data = res.assert_ok().data();
+    assert_eq!(data["allAccounts"]["nodes"][0]["password_hash"], "x");
+    assert_eq!(data["allAudits"]["totalCount"], 1);
+
+    let res = schema.server().execute_admin(query.into()).await;
+    assert!(res.is_ok(), "{:?}", res.errors);
+
+    // Streaming transports pick the schema by role too.
+    let as_admin = async_graphql::Request::new(query).data(TransactionConfig {
+        role: Some("postgres".into()),
+        ..TransactionConfig::default()
+    });
+    let streamed: Vec<_> = schema.server().execute_stream(as_admin).collect().await;
+    assert_eq!(streamed.len(), 1);
+    assert!(streamed[0].is_ok(), "{:?}", streamed[0].errors);
+    let data = streamed[0].data.clone().into_json().unwrap();
+    assert_eq!(data["allAccounts"]["nodes"][0]["password_hash"], "x");
+    let streamed: Vec<_> = schema.server().execute_stream(query.into()).collect().await;
+    assert!(streamed[0].errors[0].message.contains("password_hash"));
+
+    db.drop().await.expect("failed to drop test schema");
+}
+
+/// `@omit read` on a table also hides everything that would lead to it:
+/// relations from other tables, its finders and connection on Query, its
+/// `Node` membership and the functions returning its rows.
+#[tokio::test]
+async fn omit_read_on_a_table_hides_what_leads_to_it() {
+    let db = common::fixture(
+        "CREATE TABLE reviewers (id serial PRIMARY KEY, name text);
+         COMMENT ON TABLE reviewers IS '@omit read';
+         CREATE TABLE drafts (
+             id serial PRIMARY KEY,
+             title text,
+             reviewer_id int REFERENCES reviewers (id)
+         );
+         CREATE FUNCTION all_reviewers() RETURNS SETOF reviewers
+             LANGUAGE sql STABLE AS 'SELECT * FROM reviewers';
+         CREATE FUNCTION drafts_reviewer(d drafts) RETURNS reviewers
+             LANGUAGE sql STABLE AS 'SELECT * FROM reviewers WHERE id = d.reviewer_id';
+         INSERT INTO reviewers (name) VALUES ('Grace');
+         INSERT INTO drafts (title, reviewer_id) VALUES ('Intro', 1);",
+    )
+    .await;
+
+    let schema = db.build().await.expect("schema build failed");
+    let sdl = schema.sdl().await;
+    for hidden in [
+        "type Reviewer ",
+        "allReviewers",
+        "reviewerById",
+        "reviewerByReviewerId",
+        "draftsByReviewerId",
+        "reviewer:",
+    ] {
+        assert!(!sdl.contains(hidden), "{hidden} is exposed:\n{sdl}");
+    }
+    assert!(sdl.contains("reviewer_id: Int"));
+
+    let res = schema
+        .execute("{ allDrafts { nodes { title reviewer_id } } }")
+        .await;
+    let data = res.assert_ok().data();
+    assert_eq!(data["allDrafts"]["nodes"][0]["reviewer_id"], 1);
+
+    db.drop().await.expect("failed to drop test schema");
+}
+
+/// A `read_only` server generates no mutations and rejects mutation
+/// operations up front.
+#[tokio::test]
+async fn read_only_server_rejects_mutations() {
+    let db = common::fixture(
+        "CREATE TABLE notes (id serial PRIMARY KEY, body text);
+         INSERT INTO notes (body) VALUES ('hi');
+         CREATE PROCEDURE touch() LANGUAGE sql AS $$ UPDATE notes SET body = body $$;",
+    )
+    .await;
+
+    let schema = db
+        .build_with(|config| config.read_only = true)
+        .await
+        .expect("schema build failed");
+    let sdl = schema.sdl().await;
+    assert!(!sdl.contains("type Mutation"), "{sdl}");
+    assert!(!sdl.contains("callTouch"), "{sdl}");
+
+    let query = r#"query Read { allNotes { totalCount } }
+                   mutation Write { createNote(input: { body: "x" }) { id } }"#;
+    let res = schema
+        .execute(async_graphql::Request::new(query).operation_name("Write"))
+        .await;
+    let err = &res.response.errors[0];
+    assert_eq!(
+        err.extensions.as_ref().unwrap().get("code"),
+        Some(&async_graphql::Value::from("READ_ONLY"))
+    );
+    let res = schema
+        .execute(r#"mutation { createNote(input: { body: "x" }) { id } }"#)
+        .await;
+    assert!(res.response.errors[0].message.contains("disabled"));
+    let res = schema.execute("{ allNotes { totalCount } }").await;
+    assert_eq!(res.assert_ok().data()["allNotes"]["totalCount"], 1);
+
+    db.drop().await.expect("failed to drop test schema");
+}
+
+/// The exported matrix lists the roles each root field's grants allow, and
+/// a configured one rejects the fields it does not allow for the request's
+/// role.
+#[tokio::test]
+async fn operation_matrix_is_exported_from_grants_and_enforced() {
+    let db = common::test_database().await;
+    let role = format!("{}_reader", db.schema_name());
+    db.execute_sql(&format!(
+        "CREATE TABLE notes (id serial PRIMARY KEY, body text);
+         INSERT INTO notes (body) VALUES ('hello');
+         CREATE ROLE \"{role}\";
+         GRANT USAGE ON SCHEMA \"{schema}\" TO \"{role}\";
+         GRANT SELECT ON notes TO \"{role}\";",
+        schema = db.schema_name()
+    ))
+    .await
+    .expect("fixture failed");
+
+    let matrix = db
+        .build()
+        .await
+        .expect("schema build failed")
+        .server()
+        .operation_matrix()
+        .await;
+    assert!(matrix.roles("Query.allNotes").unwrap().contains(&role));
+    assert!(!matrix.roles("Mutation.createNote").unwrap().contains(&role));
+    let json = matrix.to_json().unwrap();
+    assert_eq!(
+        turbograph::OperationMatrix::from_json(&json).unwrap(),
+        matrix
+    );
+
+    let default_role = role.clone();
+    let schema = db
+        .build_with(move |config| {
+            config.default_role = Some(default_role);
+            config.operation_matrix = Some(matrix);
+        })
+        .await
+        .expect("schema build failed");
+    let res = schema.execute("{ allNotes { nodes { body } } }").await;
+    assert_eq!(
+        res.assert_ok().data()["allNotes"]["nodes"],
+        serde_json::json!([{ "body": "hello" }])
+    );
+
+    let res = schema
+        .execute(r#"mutation { createNote(input: { body: "x" }) { id } }"#)
+        .await;
+    let err = &res.response.errors[0];
+    assert_eq!(
+        err.extensions.as_ref().unwrap().get("code"),
+        Some(&async_graphql::Value::from("FORBIDDEN"))
+    );
+    assert!(res.sql.is_empty(), "rejected before any SQL: {:?}", res.sql);
+
+    db.execute_sql(&format!("DROP OWNED BY \"{role}\"; DROP ROLE \"{role}\";"))
+        .await
+        .expect("dropping the role failed");
+    db.drop().await.expect("failed to drop test schema");
+}
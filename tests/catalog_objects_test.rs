@@ -0,0 +1,124 @@
+mod common;
+
+/// `asOf` on a `@temporal` table reads the rows valid at that time from the
+/// table and its history table.
+#[tokio::test]
+async fn temporal_tables_answer_as_of_queries() {
+    let db = common::fixture("CREATE TABLE prices (id int PRIMARY KEY, amount int NOT NULL, sys_period tstzrange NOT NULL);
+         CREATE TABLE prices_history (LIKE prices);
+         COMMENT ON TABLE prices IS '@temporal history_table:prices_history';
+         INSERT INTO prices VALUES
+             (1, 30, tstzrange('2024-03-01', NULL)),
+             (2, 7, tstzrange('2024-01-01', NULL));
+         INSERT INTO prices_history VALUES
+             (1, 10, tstzrange('2024-01-01', '2024-02-01')),
+             (1, 20, tstzrange('2024-02-01', '2024-03-01'));",).await;
+
+    let schema = db.build().await.expect("schema build failed");
+    let sdl = schema.sdl().await;
+    assert!(sdl.contains("asOf: Datetime"), "{sdl}");
+
+    let amounts = |res: &turbograph::testing::TestResponse| {
+        res.data()["allPrices"]["nodes"]
+            .as_array()
+            .unwrap()
+            .iter()
+            .map(|node| node["amount"].as_i64().unwrap())
+            .collect::<Vec<_>>()
+    };
+    let query = |as_of: &str| {
+        format!("{{ allPrices(orderBy: [ID_ASC]{as_of}) {{ totalCount nodes {{ amount }} }} }}")
+    };
+
+    let res = schema.execute(&query("")).await;
+    assert_eq!(amounts(res.assert_ok()), [30, 7]);
+
+    let res = schema
+        .execute(&query(r#", asOf: "2024-02-15T00:00:00Z""#))
+        .await;
+    res.assert_ok().assert_sql_contains(
+        "UNION ALL SELECT \"id\", \"amount\", \"sys_period\" FROM \"{schema}\".\"prices_history\"",
+    );
+    assert_eq!(amounts(&res), [20, 7]);
+
+    let res = schema
+        .execute(&query(r#", asOf: "2023-12-31T00:00:00""#))
+        .await;
+    assert_eq!(res.assert_ok().data()["allPrices"]["totalCount"], 0);
+
+    let res = schema
+        .execute(
+            "{ allPrices(asOf: \"2024-01-15T00:00:00Z\", condition: { amount: { equal: 10 } }) { nodes { id } } }",
+        )
+        .await;
+    assert_eq!(
+        res.assert_ok().data()["allPrices"]["nodes"],
+        serde_json::json!([{ "id": 1 }])
+    );
+
+    db.drop().await.expect("failed to drop test schema");
+}
+
+/// Partitioned tables are exposed as one table without their partitions, and
+/// views take only the mutations Postgres can run on them.
+#[tokio::test]
+async fn views_and_partitioned_tables_are_exposed() {
+    let db = common::fixture(
+        "CREATE TYPE mood AS ENUM ('happy', 'sad');
+         CREATE TABLE events (id int, at date, mood mood NOT NULL, PRIMARY KEY (id, at))
+           PARTITION BY RANGE (at);
+         CREATE TABLE events_2024 PARTITION OF events
+           FOR VALUES FROM ('2024-01-01') TO ('2025-01-01');
+         CREATE VIEW happy_events AS
+           SELECT id, at, mood, id * 2 AS double_id FROM events WHERE mood = 'happy';
+         CREATE VIEW mood_counts AS SELECT mood, count(*) AS total FROM events GROUP BY mood;",
+    )
+    .await;
+
+    let schema = db.build().await.expect("schema build failed");
+    let sdl = schema.sdl().await;
+    assert!(sdl.contains("allEvents("), "{sdl}");
+    assert!(!sdl.contains("Events2024"), "partitions stay hidden: {sdl}");
+    assert!(sdl.contains("createHappyEvent("), "{sdl}");
+    assert!(sdl.contains("allMoodCounts("), "{sdl}");
+    assert!(!sdl.contains("createMoodCount("), "{sdl}");
+    assert!(sdl.contains("mood: Mood!"), "{sdl}");
+
+    schema
+        .execute(
+            r#"mutation { createEvent(input: { id: 1, at: "2024-03-01", mood: SAD }) { id } }"#,
+        )
+        .await
+        .assert_ok();
+    let res = schema
+        .execute(
+            r#"mutation {
+                createHappyEvent(input: { id: 2, at: "2024-04-01", mood: HAPPY }) { double_id }
+            }"#,
+        )
+        .await;
+    assert_eq!(
+        res.assert_ok().data()["createHappyEvent"],
+        serde_json::json!({ "double_id": 4 })
+    );
+    let res = schema
+        .execute(r#"mutation { createHappyEvent(input: { id: 3, at: "2024-04-01", double_id: 1 }) { id } }"#)
+        .await;
+    assert!(
+        !res.response.errors.is_empty(),
+        "derived columns are not inputs"
+    );
+
+    let res = schema
+        .execute("{ allMoodCounts(orderBy: [MOOD_ASC]) { nodes { mood total } } }")
+        .await;
+    assert_eq!(
+        res.assert_ok().data()["allMoodCounts"]["nodes"],
+        serde_json::json!([
+            { "mood": "HAPPY", "total": "1" },
+            { "mood": "SAD", "total": "1" },
+        ])
+    );
+
+    db.drop().await.expect("failed to drop test schema");
+}
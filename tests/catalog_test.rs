@@ -0,0 +1,246 @@
+mod common;
+
+use std::time::Duration;
+
+use tokio_postgres::types::{FromSql, Type};
+use turbograph::{
+    Catalog, CircuitBreakerConfig, Config, CustomScalar, FkAction, PoolConfig, TurboGraph,
+};
+
+/// Foreign keys are introspected together with their referential actions
+/// and the relation field names their `@fieldName` / `@foreignFieldName`
+/// tags select.
+#[tokio::test]
+async fn foreign_keys_are_introspected_with_field_names() {
+    let db = common::test_database().await;
+    db.load_fixture(concat!(
+        env!("CARGO_MANIFEST_DIR"),
+        "/tests/fixtures/blog.sql"
+    ))
+    .await
+    .expect("fixture failed");
+
+    let catalog = turbograph::introspect(Config::new(
+        PoolConfig::ConnectionString(common::db_url()),
+        vec![db.schema_name().to_string()],
+    ))
+    .await
+    .expect("introspection failed");
+    db.drop().await.expect("failed to drop test schema");
+
+    let fks: Vec<_> = catalog.foreign_keys().collect();
+    assert_eq!(fks.len(), 1);
+    let fk = fks[0];
+    let posts = catalog.table(fk.table_oid()).unwrap();
+    let users = catalog.table(fk.foreign_table_oid()).unwrap();
+
+    assert_eq!(posts.name(), "posts");
+    assert_eq!(users.name(), "users");
+    assert_eq!(fk.columns(), ["author_id"]);
+    assert_eq!(fk.foreign_columns(), ["id"]);
+    assert_eq!(fk.on_delete(), FkAction::Cascade);
+    assert_eq!(fk.on_update(), FkAction::NoAction);
+    assert_eq!(fk.field_name(users), "author");
+    assert_eq!(fk.foreign_field_name(posts), "writtenPosts");
+}
+
+/// A catalog exported with `static_catalog_module` serves queries without
+/// introspecting again.
+#[tokio::test]
+async fn static_catalog_serves_queries() {
+    let db = common::test_database().await;
+    db.load_fixture(concat!(
+        env!("CARGO_MANIFEST_DIR"),
+        "/tests/fixtures/blog.sql"
+    ))
+    .await
+    .expect("fixture failed");
+
+    let config = || {
+        Config::new(
+            PoolConfig::ConnectionString(common::db_url()),
+            vec![db.schema_name().to_string()],
+        )
+    };
+    let catalog = turbograph::introspect(config())
+        .await
+        .expect("introspection failed");
+    let module = turbograph::static_catalog_module(&catalog).unwrap();
+    let json = module
+        .split_once("r#\"")
+        .and_then(|(_, rest)| rest.split_once("\"#;"))
+        .map(|(json, _)| json)
+        .unwrap();
+
+    let server = TurboGraph::from_catalog(config(), Catalog::from_json(json).unwrap()).unwrap();
+    let res = server
+        .execute(async_graphql::Request::new("{ allUsers { totalCount } }"))
+        .await;
+    db.drop().await.expect("failed to drop test schema");
+
+    assert!(res.errors.is_empty(), "{:?}", res.errors);
+    assert!(res.data.to_string().contains("totalCount"));
+}
+
+/// Comments attach to columns by relation OID and attribute number, so
+/// same-named tables in different schemas keep their own column tags even
+/// when their columns sit at different positions.
+#[tokio::test]
+async fn column_comments_stay_with_their_schema() {
+    let db = common::test_database().await;
+    let own = db.schema_name().to_string();
+    let other = db
+        .create_schema("other")
+        .await
+        .expect("failed to create second schema");
+    db.execute_sql(&format!(
+        "CREATE TABLE widgets (id serial PRIMARY KEY, name text);
+         COMMENT ON COLUMN widgets.name IS '@description Primary name.';
+         CREATE TABLE \"{other}\".widgets (id serial PRIMARY KEY, dropped int, label text, name text);
+         ALTER TABLE \"{other}\".widgets DROP COLUMN dropped;
+         COMMENT ON COLUMN \"{other}\".widgets.label IS '@description Other label.';
+         COMMENT ON COLUMN \"{other}\".widgets.name IS '@description Other name.';"
+    ))
+    .await
+    .expect("fixture failed");
+
+    let mut config = db.config();
+    config.schemas.push(other.clone());
+    let catalog = turbograph::introspect(config).await;
+    db.drop().await.expect("failed to drop test schema");
+    let catalog = catalog.expect("introspection failed");
+
+    let description = |schema: &str, column: &str| {
+        let table = catalog
+            .tables()
+            .find(|t| t.schema_name() == schema && t.name() == "widgets")
+            .expect("widgets table");
+        let column = table
+            .columns()
+            .iter()
+            .find(|c| c.name() == column)
+            .expect("column");
+        column.descriptions().get(None).map(str::to_string)
+    };
+    assert_eq!(description(&own, "id"), None);
+    assert_eq!(description(&own, "name").as_deref(), Some("Primary name."));
+    assert_eq!(description(&other, "id"), None);
+    assert_eq!(
+        description(&other, "label").as_deref(),
+        Some("Other label.")
+    );
+    assert_eq!(description(&other, "name").as_deref(), Some("Other name."));
+}
+
+/// Once the database has failed to connect `failure_threshold` times in a
+/// row, requests fail at once with `SERVICE_UNAVAILABLE` and a
+/// `Retry-After` hint instead of waiting on the pool.
+#[tokio::test]
+async fn circuit_breaker_fails_fast_while_the_database_is_down() {
+    let db = common::test_database().await;
+    db.load_fixture(concat!(
+        env!("CARGO_MANIFEST_DIR"),
+        "/tests/fixtures/blog.sql"
+    ))
+    .await
+    .expect("fixture failed");
+    let mut config = db.config();
+    let catalog = turbograph::introspect(db.config()).await;
+    db.drop().await.expect("failed to drop test schema");
+    let catalog = catalog.expect("introspection failed");
+
+    // Nothing listens on port 1, so every connection attempt is refused.
+    config.pool = PoolConfig::ConnectionString("postgres://postgres@127.0.0.1:1/app-db".into());
+    config.circuit_breaker = Some(CircuitBreakerConfig {
+        failure_threshold: 2,
+        probe_interval: Duration::from_secs(30),
+    });
+    let server = TurboGraph::from_catalog(config, catalog).unwrap();
+    let query = || async_graphql::Request::new("{ allUsers { totalCount } }");
+
+    for _ in 0..2 {
+        let res = server.execute(query()).await;
+        assert!(
+            res.errors[0].message.contains("Pool error"),
+            "{:?}",
+            res.errors
+        );
+    }
+    let res = server.execute(query()).await;
+    let extensions = res.errors[0].extensions.as_ref().unwrap();
+    assert_eq!(
+        extensions.get("code"),
+        Some(&async_graphql::Value::from("SERVICE_UNAVAILABLE"))
+    );
+    assert_eq!(
+        extensions.get("retryAfter"),
+        Some(&async_graphql::Value::from(30))
+    );
+    assert_eq!(res.http_headers.get("retry-after").unwrap(), "30");
+}
+
+/// A `point`, decoded from its two big-endian `float8`s.
+#[derive(serde::Serialize)]
+struct Point {
+    x: f64,
+    y: f64,
+}
+
+impl<'a> FromSql<'a> for Point {
+    fn from_sql(_: &Type, raw: &'a [u8]) -> Result<Self, Box<dyn std::error::Error + Sync + Send>> {
+        let (x, y) = raw.split_at_checked(8).ok_or("a point takes 16 bytes")?;
+        Ok(Point {
+            x: f64::from_be_bytes(x.try_into()?),
+            y: f64::from_be_bytes(y.try_into()?),
+        })
+    }
+
+    fn accepts(ty: &Type) -> bool {
+        *ty == Type::POINT
+    }
+}
+
+/// A type in `Config::custom_scalars` is exposed as its scalar and
+/// serialized with its `FromSql` implementation, arrays included. Schemas
+/// built without it do not see the scalar.
+#[tokio::test]
+async fn custom_scalars_decode_with_their_from_sql_type() {
+    let db = common::fixture(
+        "CREATE TABLE stops (id serial PRIMARY KEY, location point NOT NULL, route point[]);
+         INSERT INTO stops (location, route)
+         VALUES (point(1, 2), ARRAY[point(1, 2), NULL, point(3.5, -4)]);",
+    )
+    .await;
+    let schema = db
+        .build_with(|config| {
+            config.custom_scalars = vec![(
+                Type::POINT.oid(),
+                CustomScalar::new::<Point>("Point").description("A point as `{ x, y }`."),
+            )]
+        })
+        .await
+        .expect("schema build failed");
+
+    let sdl = schema.sdl().await;
+    assert!(sdl.contains("scalar Point"), "{sdl}");
+    assert!(sdl.contains("location: Point!"), "{sdl}");
+    assert!(sdl.contains("route: [Point]"), "{sdl}");
+
+    let res = schema
+        .execute("{ allStops { nodes { location route } } }")
+        .await;
+    let data = res.assert_ok().data();
+    let stop = &data["allStops"]["nodes"][0];
+    assert_eq!(stop["location"], serde_json::json!({ "x": 1.0, "y": 2.0 }));
+    assert_eq!(
+        stop["route"],
+        serde_json::json!([{ "x": 1.0, "y": 2.0 }, null, { "x": 3.5, "y": -4.0 }])
+    );
+
+    let plain = db.build().await.expect("schema build failed");
+    let sdl = plain.sdl().await;
+    assert!(!sdl.contains("scalar Point"), "{sdl}");
+    assert!(sdl.contains("location: String!"), "{sdl}");
+
+    db.drop().await.expect("failed to drop test schema");
+}
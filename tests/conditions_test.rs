@@ -0,0 +1,103 @@
+mod common;
+
+/// `condition` equality ignores case on `@caseInsensitive` columns, and on
+/// every text column without `@caseSensitive` when the toggle is on.
+#[tokio::test]
+async fn case_insensitive_conditions() {
+    let db = common::fixture(
+        "CREATE TABLE users (id serial PRIMARY KEY, email text, handle text, code text);
+         COMMENT ON COLUMN users.email IS '@caseInsensitive';
+         COMMENT ON COLUMN users.code IS '@caseSensitive';
+         INSERT INTO users (email, handle, code) VALUES ('Ann@Example.com', 'Ann', 'AB');",
+    )
+    .await;
+
+    let schema = db.build().await.expect("schema build failed");
+    let res = schema
+        .execute(r#"{ allUsers(condition: { email: "ann@example.COM" }) { nodes { id } } }"#)
+        .await;
+    res.assert_sql_contains(r#"lower("email") = lower($1)"#);
+    assert_eq!(
+        res.assert_ok().data()["allUsers"]["nodes"],
+        serde_json::json!([{ "id": 1 }])
+    );
+    let res = schema
+        .execute(r#"{ allUsers(condition: { handle: "ann" }) { nodes { id } } }"#)
+        .await;
+    assert_eq!(
+        res.assert_ok().data()["allUsers"]["nodes"],
+        serde_json::json!([])
+    );
+
+    let schema = db
+        .build_with(|config| config.case_insensitive_conditions = true)
+        .await
+        .expect("schema build failed");
+    let res = schema
+        .execute(r#"{ allUsers(condition: { handle: "ann" }) { nodes { id } } }"#)
+        .await;
+    assert_eq!(
+        res.assert_ok().data()["allUsers"]["nodes"],
+        serde_json::json!([{ "id": 1 }])
+    );
+    let res = schema
+        .execute(r#"{ allUsers(condition: { code: "ab" }) { nodes { id } } }"#)
+        .await;
+    assert_eq!(
+        res.assert_ok().data()["allUsers"]["nodes"],
+        serde_json::json!([])
+    );
+
+    db.drop().await.expect("failed to drop test schema");
+}
+
+/// Pattern filters no index serves are left out or described as slow.
+#[tokio::test]
+async fn unindexed_pattern_filters_are_omitted_or_described() {
+    let db = common::fixture(
+        "CREATE TABLE posts (id serial PRIMARY KEY, title text, body text);
+         CREATE INDEX ON posts (title text_pattern_ops);
+         INSERT INTO posts (title, body) VALUES ('Hello', 'first'), ('Help', 'second');",
+    )
+    .await;
+
+    let schema = db
+        .build_with(|config| {
+            config.unindexed_pattern_filters = turbograph::UnindexedPatternFilters::Omit
+        })
+        .await
+        .expect("schema build failed");
+    let sdl = schema.sdl().await;
+    let filter = |name: &str| {
+        let start = sdl.find(&format!("input {name} {{")).unwrap();
+        sdl[start..start + sdl[start..].find('}').unwrap()].to_string()
+    };
+    let title = filter("PostTitleFilter");
+    assert!(title.contains("like: String"), "{title}");
+    assert!(!title.contains("likeInsensitive"), "{title}");
+    assert!(!filter("PostBodyFilter").contains("like"));
+
+    let res = schema
+        .execute(r#"{ allPosts(condition: { title: { like: "Hel%" } }) { totalCount } }"#)
+        .await;
+    assert_eq!(res.assert_ok().data()["allPosts"]["totalCount"], 2);
+    res.assert_sql_contains("LIKE");
+
+    let schema = db
+        .build_with(|config| {
+            config.unindexed_pattern_filters = turbograph::UnindexedPatternFilters::Describe
+        })
+        .await
+        .expect("schema build failed");
+    let sdl = schema.sdl().await;
+    assert!(sdl.contains("no index on `body` serves `ILIKE`"), "{sdl}");
+    let res = schema
+        .execute(r#"{ allPosts(condition: { body: { likeInsensitive: "%COND" } }) { nodes { title } } }"#)
+        .await;
+    assert_eq!(
+        res.assert_ok().data()["allPosts"]["nodes"],
+        serde_json::json!([{ "title": "Help" }])
+    );
+
+    db.drop().await.expect("failed to drop test schema");
+}
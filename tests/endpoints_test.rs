@@ -0,0 +1,177 @@
+mod common;
+
+use turbograph::{Endpoint, Endpoints, TransactionConfig};
+
+/// Each endpoint serves its own schemas and only admits its allowed roles.
+#[tokio::test]
+async fn endpoints_route_to_independent_schemas() {
+    let db = common::test_database().await;
+    let admin_schema = db
+        .create_schema("admin")
+        .await
+        .expect("failed to create admin schema");
+    db.execute_sql(&format!(
+        r#"CREATE TABLE posts (id serial PRIMARY KEY, title text);
+           INSERT INTO posts (title) VALUES ('hello');
+           CREATE TABLE "{admin_schema}".audits (id serial PRIMARY KEY, entry text);
+           INSERT INTO "{admin_schema}".audits (entry) VALUES ('login');"#
+    ))
+    .await
+    .expect("fixture failed");
+
+    let mut admin_config = db.config();
+    admin_config.schemas = vec![admin_schema.clone()];
+    let endpoints = Endpoints::new(vec![
+        Endpoint {
+            path: "/graphql".into(),
+            config: db.config(),
+            allowed_roles: vec![],
+            default_transaction: None,
+        },
+        Endpoint {
+            path: "/admin/graphql".into(),
+            config: admin_config,
+            allowed_roles: vec!["postgres".into()],
+            default_transaction: None,
+        },
+    ])
+    .await
+    .expect("endpoints failed to build");
+
+    let mut paths: Vec<_> = endpoints.paths().collect();
+    paths.sort();
+    assert_eq!(paths, ["/admin/graphql", "/graphql"]);
+    assert!(
+        endpoints
+            .execute("/missing", "{ __typename }".into())
+            .await
+            .is_none()
+    );
+
+    let res = endpoints
+        .execute("/graphql", "{ allPosts { nodes { title } } }".into())
+        .await
+        .unwrap();
+    assert!(res.is_ok(), "{:?}", res.errors);
+    let res = endpoints
+        .execute("/graphql", "{ allAudits { totalCount } }".into())
+        .await
+        .unwrap();
+    assert!(res.is_err());
+
+    let query = "{ allAudits { totalCount } }";
+    let res = endpoints
+        .execute("/admin/graphql", query.into())
+        .await
+        .unwrap();
+    assert_eq!(
+        res.errors[0].extensions.as_ref().unwrap().get("code"),
+        Some(&async_graphql::Value::from("FORBIDDEN"))
+    );
+    let as_admin = async_graphql::Request::new(query).data(TransactionConfig {
+        role: Some("postgres".into()),
+        ..TransactionConfig::default()
+    });
+    let res = endpoints.execute("/admin/graphql", as_admin).await.unwrap();
+    assert!(res.is_ok(), "{:?}", res.errors);
+    assert_eq!(res.data.into_json().unwrap()["allAudits"]["totalCount"], 1);
+
+    db.drop().await.expect("failed to drop test schema");
+}
+
+/// Named databases are built side by side and looked up by name.
+#[tokio::test]
+async fn endpoints_serve_named_databases() {
+    let db = common::test_database().await;
+    let crm_schema = db
+        .create_schema("crm")
+        .await
+        .expect("failed to create crm schema");
+    db.execute_sql(&format!(
+        r#"CREATE TABLE invoices (id serial PRIMARY KEY, total int);
+           INSERT INTO invoices (total) VALUES (10), (20);
+           CREATE TABLE "{crm_schema}".contacts (id serial PRIMARY KEY, name text);
+           INSERT INTO "{crm_schema}".contacts (name) VALUES ('ada');"#
+    ))
+    .await
+    .expect("fixture failed");
+
+    let mut crm_config = db.config();
+    crm_config.schemas = vec![crm_schema.clone()];
+    let databases = Endpoints::databases(vec![
+        ("billing".into(), db.config()),
+        ("crm".into(), crm_config),
+    ])
+    .await
+    .expect("databases failed to build");
+
+    let billing = databases.get("billing").expect("billing is served");
+    assert!(billing.export_sdl().await.contains("allInvoices"));
+    let res = databases
+        .execute("crm", "{ allContacts { nodes { name } } }".into())
+        .await
+        .unwrap();
+    assert!(res.is_ok(), "{:?}", res.errors);
+    assert_eq!(
+        res.data.into_json().unwrap()["allContacts"]["nodes"][0]["name"],
+        "ada"
+    );
+    assert!(databases.get("hr").is_none());
+
+    db.drop().await.expect("failed to drop test schema");
+}
+
+/// `@since` and `@until` tags build a different schema for each configured
+/// API version from the same database.
+#[tokio::test]
+async fn api_versions_include_tagged_objects() {
+    let db = common::fixture(
+        "CREATE TABLE users (id serial PRIMARY KEY, nickname text, display_name text);
+         COMMENT ON COLUMN users.nickname IS '@until v2';
+         COMMENT ON COLUMN users.display_name IS '@since v2';
+         CREATE TABLE badges (id serial PRIMARY KEY);
+         COMMENT ON TABLE badges IS '@since 2.1';
+         CREATE FUNCTION legacy_count() RETURNS int LANGUAGE sql STABLE AS 'SELECT 1';
+         COMMENT ON FUNCTION legacy_count() IS '@until v2';
+         INSERT INTO users (nickname, display_name) VALUES ('al', 'Alice');",
+    )
+    .await;
+
+    let version = |version: &str| {
+        let version = version.to_string();
+        move |config: &mut turbograph::Config| config.api_version = Some(version)
+    };
+    let v1 = db
+        .build_with(version("v1"))
+        .await
+        .expect("schema build failed");
+    let sdl = v1.sdl().await;
+    assert!(sdl.contains("nickname: String"), "{sdl}");
+    assert!(!sdl.contains("display_name"), "{sdl}");
+    assert!(!sdl.contains("allBadges"), "{sdl}");
+    assert!(sdl.contains("legacyCount"), "{sdl}");
+
+    let v2 = db
+        .build_with(version("v2"))
+        .await
+        .expect("schema build failed");
+    let sdl = v2.sdl().await;
+    assert!(!sdl.contains("nickname"), "{sdl}");
+    assert!(!sdl.contains("allBadges"), "{sdl}");
+    assert!(!sdl.contains("legacyCount"), "{sdl}");
+    let res = v2.execute("{ allUsers { nodes { display_name } } }").await;
+    assert_eq!(
+        res.assert_ok().data()["allUsers"]["nodes"][0]["display_name"],
+        "Alice"
+    );
+
+    let sdl = db
+        .build_with(version("2.10"))
+        .await
+        .expect("schema build failed")
+        .sdl()
+        .await;
+    assert!(sdl.contains("allBadges"), "{sdl}");
+
+    db.drop().await.expect("failed to drop test schema");
+}
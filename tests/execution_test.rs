@@ -0,0 +1,358 @@
+mod common;
+
+use turbograph::{ExecutionLane, PgSettings, PoolConfig};
+
+/// Repeating a selection under different aliases runs its SQL only once.
+#[tokio::test]
+async fn identical_statements_are_deduplicated_per_request() {
+    let db = common::fixture(
+        "CREATE TABLE widgets (id serial PRIMARY KEY, name text NOT NULL);
+         INSERT INTO widgets (name) VALUES ('sprocket'), ('gear');",
+    )
+    .await;
+
+    let schema = db.build().await.expect("schema build failed");
+    let res = schema
+        .execute(
+            "{ a: allWidgets(first: 1) { totalCount } b: allWidgets(first: 1) { nodes { name } }
+               c: allWidgets(first: 2) { totalCount } }",
+        )
+        .await;
+
+    res.assert_ok();
+    let data = res.data();
+    assert_eq!(data["a"]["totalCount"], 2);
+    assert_eq!(data["b"]["nodes"][0]["name"], "sprocket");
+    // `a` and `b` share one count + select pair; `c` differs in its limit only,
+    // which is a bound parameter, so its identical SQL text appears again.
+    assert_eq!(res.sql.len(), 4, "{:#?}", res.sql);
+
+    db.drop().await.expect("failed to drop test schema");
+}
+
+/// Extension factories from the config are attached to the built schema.
+#[tokio::test]
+async fn configured_extensions_see_every_request() {
+    use std::sync::Arc;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+
+    use async_graphql::extensions::{Extension, ExtensionContext, ExtensionFactory, NextRequest};
+
+    struct Counter(Arc<AtomicUsize>);
+
+    #[async_graphql::async_trait::async_trait]
+    impl Extension for Counter {
+        async fn request(
+            &self,
+            ctx: &ExtensionContext<'_>,
+            next: NextRequest<'_>,
+        ) -> async_graphql::Response {
+            self.0.fetch_add(1, Ordering::SeqCst);
+            next.run(ctx).await
+        }
+    }
+
+    struct CounterFactory(Arc<AtomicUsize>);
+
+    impl ExtensionFactory for CounterFactory {
+        fn create(&self) -> Arc<dyn Extension> {
+            Arc::new(Counter(self.0.clone()))
+        }
+    }
+
+    let db =
+        common::fixture("CREATE TABLE widgets (id serial PRIMARY KEY, name text NOT NULL);").await;
+
+    let requests = Arc::new(AtomicUsize::new(0));
+    let factory = CounterFactory(requests.clone());
+    let schema = db
+        .build_with(|config| config.extensions.push(Arc::new(factory)))
+        .await
+        .expect("schema build failed");
+
+    schema
+        .execute("{ allWidgets { totalCount } }")
+        .await
+        .assert_ok();
+    schema
+        .execute("{ allWidgets { totalCount } }")
+        .await
+        .assert_ok();
+    assert_eq!(requests.load(Ordering::SeqCst), 2);
+
+    db.drop().await.expect("failed to drop test schema");
+}
+
+/// With a complexity limit, successful responses report their cost and
+/// heavier queries are rejected.
+#[tokio::test]
+async fn complexity_limit_reports_cost() {
+    let db =
+        common::fixture("CREATE TABLE widgets (id serial PRIMARY KEY, name text NOT NULL);").await;
+
+    let schema = db
+        .build_with(|config| config.max_complexity = Some(5))
+        .await
+        .expect("schema build failed");
+
+    let res = schema.execute("{ allWidgets { totalCount } }").await;
+    res.assert_ok();
+    assert_eq!(
+        res.response.extensions.get("cost"),
+        Some(&async_graphql::value!({ "complexity": 2, "limit": 5 }))
+    );
+
+    let res = schema
+        .execute("{ allWidgets { totalCount nodes { id name } edges { cursor } } }")
+        .await;
+    assert!(
+        res.response.errors[0].message.contains("too complex"),
+        "{:?}",
+        res.response.errors
+    );
+    assert!(!res.response.extensions.contains_key("cost"));
+
+    db.drop().await.expect("failed to drop test schema");
+}
+
+/// Requests in the background lane run through the same resolvers on the
+/// dedicated background pool.
+#[tokio::test]
+async fn background_lane_uses_the_background_pool() {
+    let db = common::fixture(
+        "CREATE TABLE visits (
+             id serial PRIMARY KEY,
+             note text,
+             app_name text NOT NULL DEFAULT current_setting('application_name')
+         );",
+    )
+    .await;
+
+    let url = common::db_url();
+    let separator = if url.contains('?') { '&' } else { '?' };
+    let background_url = format!("{url}{separator}application_name=turbograph_background");
+    let schema = db
+        .build_with(|config| {
+            config.background_pool = Some(PoolConfig::ConnectionString(background_url))
+        })
+        .await
+        .expect("schema build failed");
+
+    let query = r#"mutation { createVisit(input: { note: "hi" }) { app_name } }"#;
+    let app_name = |res: &turbograph::testing::TestResponse| {
+        res.assert_ok().data()["createVisit"]["app_name"].clone()
+    };
+
+    let res = schema.execute(query).await;
+    assert_ne!(app_name(&res), "turbograph_background");
+
+    let res = schema
+        .execute(async_graphql::Request::new(query).data(ExecutionLane::Background))
+        .await;
+    assert_eq!(app_name(&res), "turbograph_background");
+
+    db.drop().await.expect("failed to drop test schema");
+}
+
+/// Queries and mutations only fetch the columns their selection reads.
+#[tokio::test]
+async fn selections_prune_fetched_columns() {
+    let db =
+        common::fixture("CREATE TABLE notes (id serial PRIMARY KEY, title text, body text);").await;
+
+    let schema = db.build().await.expect("schema build failed");
+    let res = schema
+        .execute(r#"mutation { createNote(input: { title: "a", body: "b" }) { id } }"#)
+        .await;
+    res.assert_sql_contains(r#"RETURNING "id""#);
+    assert_eq!(res.assert_ok().data()["createNote"]["id"], 1);
+
+    let res = schema
+        .execute(
+            r#"mutation { updateNote(patch: { title: "c" }, condition: { id: 1 }) { ...F } }
+               fragment F on Note { title __typename }"#,
+        )
+        .await;
+    res.assert_sql_contains(r#"RETURNING "title""#);
+    assert_eq!(res.assert_ok().data()["updateNote"][0]["title"], "c");
+
+    let res = schema
+        .execute("{ allNotes { nodes { body } edges { node { id } } } }")
+        .await;
+    res.assert_sql_contains(r#"SELECT "body", "id" FROM"#);
+    assert_eq!(res.assert_ok().data()["allNotes"]["nodes"][0]["body"], "b");
+
+    db.drop().await.expect("failed to drop test schema");
+}
+
+/// `PgSettings` from JWT claims drive row-level security, and requests
+/// without a role run as the default role.
+#[tokio::test]
+async fn pg_settings_apply_role_and_jwt_claims() {
+    let db = common::test_database().await;
+    let role = format!("{}_user", db.schema_name());
+    db.execute_sql(&format!(
+        "CREATE TABLE notes (id serial PRIMARY KEY, author_id int NOT NULL, body text);
+         INSERT INTO notes (author_id, body) VALUES (1, 'mine'), (2, 'theirs');
+         ALTER TABLE notes ENABLE ROW LEVEL SECURITY;
+         CREATE POLICY own_notes ON notes
+           USING (author_id = current_setting('jwt.claims.user_id', true)::int);
+         CREATE ROLE \"{role}\";
+         GRANT USAGE ON SCHEMA \"{schema}\" TO \"{role}\";
+         GRANT SELECT ON notes TO \"{role}\";",
+        schema = db.schema_name()
+    ))
+    .await
+    .expect("fixture failed");
+
+    let default_role = role.clone();
+    let schema = db
+        .build_with(move |config| config.default_role = Some(default_role))
+        .await
+        .expect("schema build failed");
+    let query = "{ allNotes { nodes { body } } }";
+
+    // The default role sees no notes without a user id.
+    let res = schema.execute(query).await;
+    assert_eq!(
+        res.assert_ok().data()["allNotes"]["nodes"],
+        serde_json::json!([])
+    );
+
+    let claims = serde_json::json!({ "role": role, "user_id": 1 });
+    let request = async_graphql::Request::new(query)
+        .data(PgSettings::from_jwt_claims(claims.as_object().unwrap()));
+    let res = schema.execute(request).await;
+    assert_eq!(
+        res.assert_ok().data()["allNotes"]["nodes"],
+        serde_json::json!([{ "body": "mine" }])
+    );
+
+    db.execute_sql(&format!("DROP OWNED BY \"{role}\"; DROP ROLE \"{role}\";"))
+        .await
+        .expect("dropping the role failed");
+    db.drop().await.expect("failed to drop test schema");
+}
+
+/// Foreign key relations selected below a connection or finder are fetched
+/// by the statement that fetches their parents, however deep they nest.
+#[tokio::test]
+async fn nested_relations_are_fetched_in_one_statement() {
+    let db = common::fixture("CREATE TABLE users (id int PRIMARY KEY, name text NOT NULL, balance numeric, avatar bytea);
+         CREATE TABLE posts (id int PRIMARY KEY, author_id int REFERENCES users, title text NOT NULL);
+         CREATE TABLE comments (id int PRIMARY KEY, post_id int NOT NULL REFERENCES posts,
+                                author_id int REFERENCES users, body text NOT NULL,
+                                created_at timestamptz NOT NULL);
+         INSERT INTO users VALUES (1, 'ada', 12.50, '\\x0102'), (2, 'grace', NULL, NULL);
+         INSERT INTO posts VALUES (10, 1, 'First'), (11, NULL, 'Second');
+         INSERT INTO comments VALUES
+           (100, 10, 2, 'Nice', '2024-01-31 12:00:00.5+00'),
+           (101, 10, 1, 'Thanks', '2024-02-01 08:30:00+00');",).await;
+
+    let schema = db.build().await.expect("schema build failed");
+    let res = schema
+        .execute(
+            "{ allPosts(orderBy: [ID_ASC]) { nodes {
+                 title
+                 userByAuthorId { name balance avatar }
+                 commentsByPostId { body created_at userByAuthorId { name } }
+                 more: commentsByPostId { id }
+             } } }",
+        )
+        .await;
+    assert_eq!(
+        res.assert_ok().data()["allPosts"]["nodes"],
+        serde_json::json!([
+            {
+                "title": "First",
+                "userByAuthorId": { "name": "ada", "balance": "12.50", "avatar": "AQI=" },
+                "commentsByPostId": [
+                    {
+                        "body": "Nice",
+                        "created_at": "2024-01-31T12:00:00.500+00:00",
+                        "userByAuthorId": { "name": "grace" }
+                    },
+                    {
+                        "body": "Thanks",
+                        "created_at": "2024-02-01T08:30:00+00:00",
+                        "userByAuthorId": { "name": "ada" }
+                    }
+                ],
+                "more": [{ "id": 100 }, { "id": 101 }]
+            },
+            {
+                "title": "Second",
+                "userByAuthorId": null,
+                "commentsByPostId": [],
+                "more": []
+            }
+        ])
+    );
+    // The count and the page, nothing per post or comment.
+    assert_eq!(res.sql.len(), 2, "{:#?}", res.sql);
+    res.assert_sql_contains("AS \"__turbograph_prefetch\"");
+
+    let res = schema
+        .execute("{ userById(id: 2) { name commentsByAuthorId { postByPostId { title } } } }")
+        .await;
+    assert_eq!(
+        res.assert_ok().data()["userById"],
+        serde_json::json!({
+            "name": "grace",
+            "commentsByAuthorId": [{ "postByPostId": { "title": "First" } }]
+        })
+    );
+    assert_eq!(res.sql.len(), 1, "{:#?}", res.sql);
+
+    db.drop().await.expect("failed to drop test schema");
+}
+
+/// A row the request already fetched in full is not fetched again when a
+/// relation leads back to it, and reads the same along every path.
+#[tokio::test]
+async fn identity_map_reuses_rows_fetched_earlier() {
+    let db = common::fixture(
+        "CREATE TABLE users (id int PRIMARY KEY, name text NOT NULL);
+         CREATE TABLE posts (id int PRIMARY KEY, author_id int REFERENCES users, title text);
+         COMMENT ON TABLE users IS '@ref articles to:Post plural via:(id)->posts(author_id)';
+         INSERT INTO users VALUES (1, 'ann'), (2, 'bob');
+         INSERT INTO posts VALUES (1, 1, 'first'), (2, 1, 'second'), (3, 2, 'third');",
+    )
+    .await;
+
+    let schema = db.build().await.expect("schema build failed");
+    let res = schema
+        .execute(
+            "{ allUsers(orderBy: [ID_ASC]) {
+                 nodes { id name articles { title userByAuthorId { id name } } }
+             } }",
+        )
+        .await;
+    let nodes = &res.assert_ok().data()["allUsers"]["nodes"];
+    assert_eq!(nodes[0]["articles"][1]["userByAuthorId"]["name"], "ann");
+    assert_eq!(nodes[1]["articles"][0]["userByAuthorId"]["name"], "bob");
+    // The authors come from the identity map, not from a statement of
+    // their own.
+    let users = format!("FROM \"{}\".\"users\" WHERE", db.schema_name());
+    assert!(
+        !res.sql.iter().any(|sql| sql.contains(&users)),
+        "{:#?}",
+        res.sql
+    );
+
+    // A mutation may change any row, so later reads go to the database.
+    let res = schema
+        .execute(
+            "mutation { createPost(input: { id: 4, author_id: 2, title: \"fourth\" }) {
+                 userByAuthorId { name }
+             } }",
+        )
+        .await;
+    assert_eq!(
+        res.assert_ok().data()["createPost"]["userByAuthorId"]["name"],
+        "bob"
+    );
+    res.assert_sql_contains("FROM \"{schema}\".\"users\" WHERE");
+
+    db.drop().await.expect("failed to drop test schema");
+}
@@ -0,0 +1,99 @@
+mod common;
+
+use async_graphql::futures_util::StreamExt;
+use turbograph::{ExportFormat, ExportRequest, TransactionConfig};
+
+/// Exports stream filtered rows through `COPY` as CSV or NDJSON, only for
+/// export roles.
+#[tokio::test]
+async fn exports_stream_filtered_rows() {
+    let db = common::fixture(
+        r#"CREATE TABLE orders (id int PRIMARY KEY, status text NOT NULL, amount int, note text);
+           INSERT INTO orders VALUES
+               (1, 'open', 10, 'say "hi", then C:\dir'),
+               (2, 'open', 3, NULL),
+               (3, 'closed', 20, 'two
+lines');"#,
+    )
+    .await;
+
+    let schema = db
+        .build_with(|config| config.export_roles = vec!["postgres".into()])
+        .await
+        .expect("schema build failed");
+    let as_role = |read_only| TransactionConfig {
+        role: Some("postgres".into()),
+        read_only,
+        ..TransactionConfig::default()
+    };
+    let export = |format, filter: Option<serde_json::Value>, tx: TransactionConfig| {
+        let request = ExportRequest {
+            table: "orders".into(),
+            filter,
+            format,
+        };
+        let server = schema.server().clone();
+        async move {
+            let stream = server.export(request, tx).await?;
+            let chunks: Vec<_> = stream.collect().await;
+            let mut body = Vec::new();
+            for chunk in chunks {
+                body.extend_from_slice(&chunk?);
+            }
+            Ok::<_, async_graphql::Error>(String::from_utf8(body).unwrap())
+        }
+    };
+
+    let csv = export(ExportFormat::Csv, None, as_role(false))
+        .await
+        .unwrap();
+    assert_eq!(
+        csv,
+        "id,status,amount,note\n\
+         1,open,10,\"say \"\"hi\"\", then C:\\dir\"\n\
+         2,open,3,\n\
+         3,closed,20,\"two\nlines\"\n"
+    );
+
+    let filter =
+        serde_json::json!({ "status": { "equal": "open" }, "amount": { "greaterThan": 5 } });
+    let ndjson = export(ExportFormat::Ndjson, Some(filter.clone()), as_role(true))
+        .await
+        .unwrap();
+    let rows: Vec<serde_json::Value> = ndjson
+        .lines()
+        .map(|line| serde_json::from_str(line).unwrap())
+        .collect();
+    assert_eq!(
+        rows,
+        vec![serde_json::json!({
+            "id": 1, "status": "open", "amount": 10, "note": "say \"hi\", then C:\\dir"
+        })]
+    );
+
+    let code = |err: async_graphql::Error| {
+        err.extensions
+            .and_then(|ext| ext.get("code").cloned())
+            .unwrap()
+    };
+    let invalid = serde_json::json!({ "amount": { "greaterThan": "many" } });
+    let err = export(ExportFormat::Csv, Some(invalid), as_role(false))
+        .await
+        .unwrap_err();
+    assert_eq!(code(err), async_graphql::Value::from("INVALID_FILTER"));
+    let unknown = serde_json::json!({ "owner": { "equal": 1 } });
+    let err = export(ExportFormat::Csv, Some(unknown), as_role(false))
+        .await
+        .unwrap_err();
+    assert_eq!(code(err), async_graphql::Value::from("INVALID_FILTER"));
+    let err = export(
+        ExportFormat::Csv,
+        Some(filter),
+        TransactionConfig::default(),
+    )
+    .await
+    .unwrap_err();
+    assert_eq!(code(err), async_graphql::Value::from("FORBIDDEN"));
+
+    db.drop().await.expect("failed to drop test schema");
+}
@@ -0,0 +1,33 @@
+-- Minimal blog schema used by the SDL snapshot tests. Unqualified names are
+-- created in the per-test schema set up by `turbograph::testing`.
+
+CREATE TABLE users (
+    id         SERIAL       PRIMARY KEY,
+    username   VARCHAR(50)  NOT NULL UNIQUE,
+    bio        TEXT,
+    is_active  BOOLEAN      NOT NULL DEFAULT TRUE,
+    created_at TIMESTAMPTZ  NOT NULL DEFAULT NOW()
+);
+
+COMMENT ON TABLE users IS 'Registered users of the application.';
+COMMENT ON COLUMN users.bio IS 'Optional short biography.';
+
+CREATE TABLE posts (
+    id           SERIAL       PRIMARY KEY,
+    author_id    INT          NOT NULL REFERENCES users(id) ON DELETE CASCADE,
+    title        VARCHAR(255) NOT NULL,
+    tags         TEXT[],
+    metadata     JSONB,
+    views        BIGINT       NOT NULL DEFAULT 0
+);
+
+COMMENT ON TABLE posts IS 'Blog posts written by users.';
+
+CREATE TABLE audit_log (
+    id      SERIAL PRIMARY KEY,
+    message TEXT   NOT NULL
+);
+
+COMMENT ON TABLE audit_log IS 'Written by triggers only. @omit create,update,delete';
+
+COMMENT ON CONSTRAINT posts_author_id_fkey ON posts IS '@fieldName author @foreignFieldName writtenPosts';
@@ -0,0 +1,229 @@
+mod common;
+
+/// Procedures become `call{P}` mutations; INOUT and OUT arguments come back in
+/// the payload.
+#[tokio::test]
+async fn procedures_are_called_as_mutations() {
+    let db = common::fixture(
+        "CREATE TABLE accounts (id int PRIMARY KEY, balance int NOT NULL);
+         INSERT INTO accounts VALUES (1, 100), (2, 0);
+         CREATE PROCEDURE transfer(from_id int, to_id int, amount int,
+                                   INOUT remaining int, OUT note text)
+         LANGUAGE plpgsql SET search_path FROM CURRENT AS $$
+         BEGIN
+             UPDATE accounts SET balance = balance - amount WHERE id = from_id
+                 RETURNING balance INTO remaining;
+             UPDATE accounts SET balance = balance + amount WHERE id = to_id;
+             note := 'moved ' || amount;
+         END $$;
+         CREATE PROCEDURE reset_balances(value int DEFAULT 0)
+         LANGUAGE sql SET search_path FROM CURRENT AS $$ UPDATE accounts SET balance = value $$;
+         CREATE PROCEDURE hidden() LANGUAGE sql AS $$ SELECT 1 $$;
+         COMMENT ON PROCEDURE hidden() IS '@omit';
+         CREATE PROCEDURE audit() LANGUAGE sql SECURITY DEFINER AS $$ SELECT 1 $$;",
+    )
+    .await;
+
+    let schema = db.build().await.expect("schema build failed");
+    let sdl = schema.sdl().await;
+    assert!(
+        sdl.contains("callTransfer(input: CallTransferInput!): CallTransferPayload"),
+        "{sdl}"
+    );
+    assert!(sdl.contains("callResetBalances(input: CallResetBalancesInput!): Boolean!"));
+    assert!(!sdl.contains("callHidden"));
+    assert!(
+        sdl.contains("(`SECURITY DEFINER`).\n\t\"\"\"\n\tcallAudit: Boolean!"),
+        "{sdl}"
+    );
+
+    let res = schema
+        .execute(
+            "mutation { callTransfer(input: { from_id: 1, to_id: 2, amount: 30 }) { remaining note } }",
+        )
+        .await;
+    let data = res.assert_ok().data();
+    assert_eq!(data["callTransfer"]["remaining"], 70);
+    assert_eq!(data["callTransfer"]["note"], "moved 30");
+    res.assert_sql_contains("CALL");
+
+    let res = schema
+        .execute("{ allAccounts(orderBy: ID_ASC) { nodes { balance } } }")
+        .await;
+    assert_eq!(
+        res.assert_ok().data()["allAccounts"]["nodes"],
+        serde_json::json!([{ "balance": 70 }, { "balance": 30 }])
+    );
+
+    let res = schema
+        .execute("mutation { callResetBalances(input: {}) }")
+        .await;
+    assert_eq!(res.assert_ok().data()["callResetBalances"], true);
+    let res = schema
+        .execute("{ allAccounts { nodes { balance } } }")
+        .await;
+    assert_eq!(
+        res.assert_ok().data()["allAccounts"]["nodes"],
+        serde_json::json!([{ "balance": 0 }, { "balance": 0 }])
+    );
+
+    db.drop().await.expect("failed to drop test schema");
+}
+
+/// Stable and immutable functions become query fields, volatile ones
+/// mutations, and `{table}_{field}` functions taking a row computed columns.
+#[tokio::test]
+async fn functions_become_queries_mutations_and_computed_columns() {
+    let db = common::fixture(
+        "CREATE TABLE posts (id int PRIMARY KEY, title text NOT NULL, published bool NOT NULL);
+         INSERT INTO posts VALUES (1, 'Hello world', false), (2, 'Second post', true);
+         CREATE FUNCTION search_posts(term text) RETURNS SETOF posts
+         LANGUAGE sql STABLE SET search_path FROM CURRENT AS $$
+             SELECT * FROM posts WHERE title ILIKE '%' || term || '%' ORDER BY id $$;
+         CREATE FUNCTION add(a int, b int DEFAULT 1) RETURNS int
+         LANGUAGE sql IMMUTABLE STRICT AS $$ SELECT a + b $$;
+         COMMENT ON FUNCTION add(int, int) IS '@description Adds two numbers.';
+         CREATE FUNCTION publish_post(post_id int) RETURNS posts
+         LANGUAGE sql VOLATILE SET search_path FROM CURRENT AS $$
+             UPDATE posts SET published = true WHERE id = post_id RETURNING * $$;
+         CREATE FUNCTION posts_excerpt(p posts, length int DEFAULT 5) RETURNS text
+         LANGUAGE sql STABLE AS $$ SELECT left(p.title, length) $$;
+         CREATE FUNCTION hidden() RETURNS int LANGUAGE sql STABLE AS $$ SELECT 1 $$;
+         COMMENT ON FUNCTION hidden() IS '@omit';",
+    )
+    .await;
+
+    let schema = db.build().await.expect("schema build failed");
+    let sdl = schema.sdl().await;
+    for field in [
+        "searchPosts(term: String): [Post!]!",
+        "add(a: Int, b: Int): Int",
+        "publishPost(input: PublishPostInput!): Post",
+        "excerpt(length: Int): String",
+    ] {
+        assert!(sdl.contains(field), "{field} missing from {sdl}");
+    }
+    assert!(
+        sdl.contains("\"\"\"\n\tAdds two numbers.\n\t\"\"\"\n\tadd("),
+        "{sdl}"
+    );
+    assert!(!sdl.contains("hidden"), "{sdl}");
+
+    let res = schema
+        .execute(r#"{ searchPosts(term: "post") { id excerpt } add(a: 2) }"#)
+        .await;
+    assert_eq!(
+        res.assert_ok().data(),
+        serde_json::json!({ "searchPosts": [{ "id": 2, "excerpt": "Secon" }], "add": 3 })
+    );
+    res.assert_sql_contains("\"search_posts\"(\"term\" => $1::");
+
+    let res = schema
+        .execute(r#"{ a: add(a: null) postById(id: 1) { excerpt(length: 2) } }"#)
+        .await;
+    assert_eq!(
+        res.assert_ok().data(),
+        serde_json::json!({ "a": null, "postById": { "excerpt": "He" } })
+    );
+
+    let res = schema
+        .execute("mutation { publishPost(input: { post_id: 1 }) { id published } }")
+        .await;
+    assert_eq!(
+        res.assert_ok().data()["publishPost"],
+        serde_json::json!({ "id": 1, "published": true })
+    );
+
+    db.drop().await.expect("failed to drop test schema");
+}
+
+/// Routine arguments keep their (inflected) names and take descriptions
+/// from `@arg` tags; unnamed ones become `arg{n}` and are passed by position.
+#[tokio::test]
+async fn function_arguments_take_names_and_descriptions() {
+    let db = common::fixture(
+        "CREATE FUNCTION repeat_word(word_text text, times int DEFAULT 2) RETURNS text
+         LANGUAGE sql IMMUTABLE AS $$ SELECT repeat(word_text, times) $$;
+         COMMENT ON FUNCTION repeat_word(text, int) IS
+           '@arg word_text The word to repeat
+            @arg times How often';
+         CREATE FUNCTION scale(int, int DEFAULT 10) RETURNS int
+         LANGUAGE sql IMMUTABLE AS $$ SELECT $1 * $2 $$;
+         COMMENT ON FUNCTION scale(int, int) IS '@arg arg1 The value to scale';",
+    )
+    .await;
+
+    let schema = db
+        .build_with(|config| config.camel_case_fields = true)
+        .await
+        .expect("schema build failed");
+    let sdl = schema.sdl().await;
+    for field in [
+        "\"\"\"\n\t\tThe word to repeat\n\t\t\"\"\"\n\t\twordText: String",
+        "\"\"\"\n\t\tHow often\n\t\t\"\"\"\n\t\ttimes: Int",
+        "\"\"\"\n\t\tThe value to scale\n\t\t\"\"\"\n\t\targ1: Int",
+    ] {
+        assert!(sdl.contains(field), "{field} missing from {sdl}");
+    }
+
+    let res = schema
+        .execute(r#"{ repeatWord(wordText: "ab") scale(arg1: 3) scaled: scale(arg1: 3, arg2: 2) }"#)
+        .await;
+    assert_eq!(
+        res.assert_ok().data(),
+        serde_json::json!({ "repeatWord": "abab", "scale": 30, "scaled": 6 })
+    );
+    res.assert_sql_contains("\"scale\"($1::");
+
+    db.drop().await.expect("failed to drop test schema");
+}
+
+/// Functions with `OUT` arguments or `RETURNS TABLE` columns return records
+/// of a generated `{F}Record` type, as root fields and computed columns.
+#[tokio::test]
+async fn record_returning_functions_get_generated_types() {
+    let db = common::fixture("CREATE TABLE posts (id int PRIMARY KEY, title text NOT NULL, likes int NOT NULL);
+         INSERT INTO posts VALUES (1, 'First', 3), (2, 'Second', 7);
+         CREATE FUNCTION post_stats(OUT total bigint, OUT top_title text)
+         LANGUAGE sql STABLE SET search_path FROM CURRENT AS $$
+           SELECT count(*), (SELECT title FROM posts ORDER BY likes DESC LIMIT 1) FROM posts
+         $$;
+         COMMENT ON FUNCTION post_stats() IS '@arg top_title The most liked title';
+         CREATE FUNCTION likes_above(min int) RETURNS TABLE (post_id int, likes int)
+         LANGUAGE sql STABLE SET search_path FROM CURRENT AS $$ SELECT id, likes FROM posts WHERE likes > min ORDER BY id $$;
+         CREATE FUNCTION posts_split(p posts, OUT head text, OUT tail text)
+         LANGUAGE sql STABLE AS $$ SELECT left(p.title, 1), substr(p.title, 2) $$;
+         CREATE FUNCTION post_count(OUT n bigint)
+         LANGUAGE sql STABLE SET search_path FROM CURRENT AS $$ SELECT count(*) FROM posts $$;",).await;
+
+    let schema = db.build().await.expect("schema build failed");
+    let sdl = schema.sdl().await;
+    for expected in [
+        "type PostStatsRecord {",
+        "\"\"\"\n\tThe most liked title\n\t\"\"\"\n\ttop_title: String",
+        "postStats: PostStatsRecord",
+        "likesAbove(min: Int): [LikesAboveRecord!]!",
+        "split: PostsSplitRecord",
+        "postCount: BigInt",
+    ] {
+        assert!(sdl.contains(expected), "{expected} missing from {sdl}");
+    }
+
+    let res = schema
+        .execute(
+            "{ postStats { total top_title } likesAbove(min: 1) { post_id likes }
+               postCount postById(id: 2) { split { head tail } } }",
+        )
+        .await;
+    assert_eq!(
+        res.assert_ok().data(),
+        serde_json::json!({
+            "postStats": { "total": "2", "top_title": "Second" },
+            "likesAbove": [{ "post_id": 1, "likes": 3 }, { "post_id": 2, "likes": 7 }],
+            "postCount": "2",
+            "postById": { "split": { "head": "S", "tail": "econd" } },
+        })
+    );
+
+    db.drop().await.expect("failed to drop test schema");
+}
@@ -0,0 +1,191 @@
+mod common;
+
+use axum::body::Body;
+use axum::http::{Request, StatusCode, header};
+use tower::ServiceExt;
+use turbograph::{HttpServer, PersistedOperations, PgSettings, operation_hash};
+
+fn graphql_request(query: &str, token: Option<&str>) -> Request<Body> {
+    let mut request = Request::post("/graphql").header(header::CONTENT_TYPE, "application/json");
+    if let Some(token) = token {
+        request = request.header(header::AUTHORIZATION, format!("Bearer {token}"));
+    }
+    request
+        .body(Body::from(
+            serde_json::json!({ "query": query }).to_string(),
+        ))
+        .unwrap()
+}
+
+async fn body_text(response: axum::response::Response) -> String {
+    let bytes = axum::body::to_bytes(response.into_body(), usize::MAX)
+        .await
+        .unwrap();
+    String::from_utf8(bytes.to_vec()).unwrap()
+}
+
+/// `/graphql` answers with the schema, settings come from the auth hook,
+/// and `/graphiql` serves the IDE.
+#[tokio::test]
+async fn router_serves_graphql_graphiql_and_auth_settings() {
+    let db = common::fixture(
+        "CREATE TABLE notes (id serial PRIMARY KEY, body text NOT NULL);
+         INSERT INTO notes (body) VALUES ('hello');
+         CREATE FUNCTION whoami() RETURNS text AS $$
+           SELECT current_setting('jwt.claims.user_id', true)
+         $$ LANGUAGE sql STABLE;",
+    )
+    .await;
+    let schema = db.build().await.expect("schema build failed");
+
+    let router = HttpServer::new(schema.server().clone())
+        .auth(|headers| async move {
+            let Some(value) = headers.get(header::AUTHORIZATION) else {
+                return Ok(None);
+            };
+            match value
+                .to_str()
+                .unwrap_or_default()
+                .strip_prefix("Bearer user-")
+            {
+                Some(id) => Ok(Some(PgSettings::new().set("jwt.claims.user_id", id))),
+                None => Err("invalid token".to_string()),
+            }
+        })
+        .router();
+
+    let response = router
+        .clone()
+        .oneshot(graphql_request(
+            "{ allNotes { nodes { body } } whoami }",
+            None,
+        ))
+        .await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::OK);
+    assert!(response.headers().contains_key("x-graphql-schema-hash"));
+    let body: serde_json::Value = serde_json::from_str(&body_text(response).await).unwrap();
+    assert_eq!(
+        body["data"],
+        serde_json::json!({ "allNotes": { "nodes": [{ "body": "hello" }] }, "whoami": null })
+    );
+
+    let response = router
+        .clone()
+        .oneshot(graphql_request("{ whoami }", Some("user-7")))
+        .await
+        .unwrap();
+    let body: serde_json::Value = serde_json::from_str(&body_text(response).await).unwrap();
+    assert_eq!(body["data"]["whoami"], "7");
+
+    let response = router
+        .clone()
+        .oneshot(graphql_request("{ whoami }", Some("forged")))
+        .await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::UNAUTHORIZED);
+    assert_eq!(body_text(response).await, "invalid token");
+
+    let response = router
+        .oneshot(Request::get("/graphiql").body(Body::empty()).unwrap())
+        .await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::OK);
+    assert!(body_text(response).await.contains("graphiql"));
+
+    db.drop().await.expect("failed to drop test schema");
+}
+
+/// The manifest holds the valid operations of a directory, reporting every
+/// invalid one, and strict persisted mode only runs its documents.
+#[tokio::test]
+async fn persisted_operations_are_validated_and_enforced() {
+    let db = common::fixture(
+        "CREATE TABLE notes (id serial PRIMARY KEY, body text NOT NULL);
+         INSERT INTO notes (body) VALUES ('hello');",
+    )
+    .await;
+    let schema = db
+        .build_with(|config| config.subscriptions = true)
+        .await
+        .expect("schema build failed");
+
+    let dir = std::env::temp_dir().join(format!("turbograph_ops_{}", db.schema_name()));
+    std::fs::create_dir_all(dir.join("notes")).unwrap();
+    let note = "query Note($id: Int!) { noteById(id: $id) { body } }\n";
+    std::fs::write(dir.join("notes/note.graphql"), note).unwrap();
+    std::fs::write(
+        dir.join("notes/create.gql"),
+        "mutation { createNote(input: { body: \"hi\" }) { id } }",
+    )
+    .unwrap();
+    std::fs::write(
+        dir.join("listen.graphql"),
+        "subscription { listen(topic: \"notes\") { topic } }",
+    )
+    .unwrap();
+    std::fs::write(dir.join("README.md"), "not an operation").unwrap();
+    std::fs::write(dir.join("typo.graphql"), "{ allNotes { nodes { title } } }").unwrap();
+
+    let err = schema
+        .server()
+        .persisted_operations(&dir)
+        .await
+        .unwrap_err()
+        .to_string();
+    assert!(
+        err.starts_with("1 problem in the operations under"),
+        "{err}"
+    );
+    assert!(
+        err.contains("typo.graphql: Unknown field \"title\""),
+        "{err}"
+    );
+
+    std::fs::remove_file(dir.join("typo.graphql")).unwrap();
+    let manifest = schema
+        .server()
+        .persisted_operations(&dir)
+        .await
+        .expect("operations are valid");
+    let sdl = schema.server().export_sdl().await;
+    let from_sdl = PersistedOperations::from_sdl(&sdl, &dir)
+        .await
+        .expect("operations are valid against the SDL");
+    std::fs::remove_dir_all(&dir).unwrap();
+    assert_eq!(from_sdl, manifest);
+    assert_eq!(manifest.len(), 3);
+    assert_eq!(manifest.get(&operation_hash(note)), Some(note));
+
+    let router = HttpServer::new(schema.server().clone())
+        .persisted_operations(manifest)
+        .router();
+    let by_hash = serde_json::json!({
+        "variables": { "id": 1 },
+        "extensions": { "persistedQuery": { "version": 1, "sha256Hash": operation_hash(note) } },
+    });
+    let response = router
+        .clone()
+        .oneshot(
+            Request::post("/graphql")
+                .header(header::CONTENT_TYPE, "application/json")
+                .body(Body::from(by_hash.to_string()))
+                .unwrap(),
+        )
+        .await
+        .unwrap();
+    let body: serde_json::Value = serde_json::from_str(&body_text(response).await).unwrap();
+    assert_eq!(body["data"]["noteById"]["body"], "hello");
+
+    let response = router
+        .oneshot(graphql_request("{ allNotes { nodes { body } } }", None))
+        .await
+        .unwrap();
+    let body: serde_json::Value = serde_json::from_str(&body_text(response).await).unwrap();
+    assert_eq!(
+        body["errors"][0]["extensions"]["code"],
+        "PERSISTED_QUERY_NOT_FOUND"
+    );
+
+    db.drop().await.expect("failed to drop test schema");
+}
@@ -0,0 +1,200 @@
+mod common;
+
+use turbograph::{ExportFormat, ExportRequest, RowTransform, TransactionConfig};
+
+/// Operations built without GraphQL are planned against the exposed tables
+/// and run in the request's transaction settings.
+#[tokio::test]
+async fn ir_operations_run_without_graphql() {
+    use turbograph::ir::{Delete, Filter, FilterOp, Insert, Operation, Order, Select, Update};
+
+    let db = common::fixture(
+        "CREATE TABLE orders (id serial PRIMARY KEY, status text NOT NULL, total int);
+         INSERT INTO orders (status, total) VALUES ('open', 5), ('open', 9), ('paid', 3);",
+    )
+    .await;
+    let schema = db.build().await.expect("schema build failed");
+    let server = schema.server();
+    let values = |json: serde_json::Value| json.as_object().unwrap().clone();
+
+    let rows = server
+        .run(
+            Operation::Select(Select {
+                table: "orders".into(),
+                fields: vec!["id".into(), "total".into()],
+                filters: vec![Filter::new("status", FilterOp::Eq, "open".into())],
+                order_by: vec![Order::desc("total")],
+                ..Select::default()
+            }),
+            TransactionConfig::default(),
+        )
+        .await
+        .unwrap();
+    assert_eq!(
+        serde_json::Value::from(rows),
+        serde_json::json!([{ "id": 2, "total": 9 }, { "id": 1, "total": 5 }])
+    );
+
+    let rows = server
+        .run(
+            Operation::Insert(Insert {
+                table: "orders".into(),
+                values: values(serde_json::json!({ "status": "open", "total": 7 })),
+                returning: vec!["id".into()],
+            }),
+            TransactionConfig::default(),
+        )
+        .await
+        .unwrap();
+    assert_eq!(rows, [serde_json::json!({ "id": 4 })]);
+
+    let rows = server
+        .run(
+            Operation::Update(Update {
+                table: "orders".into(),
+                filters: vec![Filter::new("total", FilterOp::Gte, 7.into())],
+                values: values(serde_json::json!({ "status": "paid" })),
+                returning: vec!["id".into()],
+            }),
+            TransactionConfig::default(),
+        )
+        .await
+        .unwrap();
+    assert_eq!(rows.len(), 2);
+
+    let delete = Operation::Delete(Delete {
+        table: "orders".into(),
+        filters: vec![Filter::new("status", FilterOp::Eq, "paid".into())],
+        returning: Vec::new(),
+    });
+    let plan = server.plan(&delete).unwrap();
+    assert_eq!(
+        plan.sql(),
+        format!(
+            "DELETE FROM \"{}\".\"orders\" WHERE \"status\" = $1 RETURNING \"id\" AS \"id\", \"status\" AS \"status\", \"total\" AS \"total\"",
+            db.schema_name()
+        )
+    );
+    let rows = server
+        .run(delete, TransactionConfig::default())
+        .await
+        .unwrap();
+    assert_eq!(rows.len(), 3);
+
+    let res = schema
+        .execute("{ allOrders { nodes { id status } } }")
+        .await;
+    assert_eq!(
+        res.assert_ok().data()["allOrders"]["nodes"],
+        serde_json::json!([{ "id": 1, "status": "open" }])
+    );
+
+    let err = server
+        .run(
+            Operation::Select(Select {
+                table: "orders".into(),
+                filters: vec![Filter::new("total", FilterOp::Eq, "many".into())],
+                ..Select::default()
+            }),
+            TransactionConfig::default(),
+        )
+        .await
+        .unwrap_err();
+    assert_eq!(err.message, "invalid value for `total`");
+
+    db.drop().await.expect("failed to drop test schema");
+}
+
+/// Row transforms see whole rows and rewrite them wherever the table's rows
+/// are returned.
+#[tokio::test]
+async fn row_transforms_rewrite_rows_before_fields_resolve() {
+    use turbograph::ir::{Filter, FilterOp, Operation, Select};
+
+    let db = common::fixture("CREATE TABLE files (id serial PRIMARY KEY, bucket text NOT NULL, storage_key text NOT NULL);
+         INSERT INTO files (bucket, storage_key) VALUES ('media', 'a.png');",).await;
+
+    let schema = db
+        .build_with(|config| {
+            config.export_roles = vec!["postgres".into()];
+            config.row_transforms = vec![RowTransform::new("files", |mut row, ctx| async move {
+                assert_eq!(ctx.table, "files");
+                let url = format!(
+                    "https://{}.example.com/{}?role={}",
+                    row["bucket"].as_str().unwrap_or_default(),
+                    row["storage_key"].as_str().unwrap_or_default(),
+                    ctx.role.as_deref().unwrap_or("none"),
+                );
+                row["storage_key"] = url.into();
+                Ok(row)
+            })];
+        })
+        .await
+        .expect("schema build failed");
+
+    // Only `storage_key` is selected, yet the transform reads `bucket`.
+    let res = schema
+        .execute("{ allFiles { nodes { storage_key } } fileById(id: 1) { storage_key } }")
+        .await;
+    assert_eq!(
+        res.assert_ok().data(),
+        serde_json::json!({
+            "allFiles": { "nodes": [{ "storage_key": "https://media.example.com/a.png?role=none" }] },
+            "fileById": { "storage_key": "https://media.example.com/a.png?role=none" },
+        })
+    );
+
+    let request = async_graphql::Request::new(
+        r#"mutation { createFile(input: { bucket: "docs", storage_key: "b.pdf" }) { storage_key } }"#,
+    )
+    .data(TransactionConfig {
+        role: Some("postgres".into()),
+        ..TransactionConfig::default()
+    });
+    let res = schema.execute(request).await;
+    assert_eq!(
+        res.assert_ok().data()["createFile"]["storage_key"],
+        "https://docs.example.com/b.pdf?role=postgres"
+    );
+
+    // Operations run without GraphQL are transformed too, and exports,
+    // which `COPY` would stream untransformed, are refused.
+    let rows = schema
+        .server()
+        .run(
+            Operation::Select(Select {
+                table: "files".into(),
+                fields: vec!["storage_key".into()],
+                filters: vec![Filter::new("id", FilterOp::Eq, 1.into())],
+                ..Select::default()
+            }),
+            TransactionConfig::default(),
+        )
+        .await
+        .unwrap();
+    assert_eq!(
+        rows,
+        [serde_json::json!({ "storage_key": "https://media.example.com/a.png?role=none" })]
+    );
+    let export = schema
+        .server()
+        .export(
+            ExportRequest {
+                table: "files".into(),
+                filter: None,
+                format: ExportFormat::Csv,
+            },
+            TransactionConfig {
+                role: Some("postgres".into()),
+                ..TransactionConfig::default()
+            },
+        )
+        .await;
+    let err = export.err().expect("transformed tables are not exported");
+    assert_eq!(
+        err.extensions.and_then(|ext| ext.get("code").cloned()),
+        Some(async_graphql::Value::from("FORBIDDEN"))
+    );
+
+    db.drop().await.expect("failed to drop test schema");
+}
@@ -0,0 +1,162 @@
+mod common;
+
+/// Unique keys get single-row finders taking every key column; partial
+/// unique indexes do not.
+#[tokio::test]
+async fn unique_keys_get_finders() {
+    let db = common::fixture(
+        "CREATE TABLE memberships (
+             id serial PRIMARY KEY,
+             org_id int NOT NULL,
+             user_id int NOT NULL,
+             active boolean NOT NULL,
+             UNIQUE (org_id, user_id)
+         );
+         CREATE UNIQUE INDEX one_active_membership ON memberships (user_id) WHERE active;
+         INSERT INTO memberships (org_id, user_id, active) VALUES (1, 7, true), (2, 7, false);",
+    )
+    .await;
+
+    let schema = db.build().await.expect("schema build failed");
+    let sdl = schema.sdl().await;
+    assert!(
+        sdl.contains("membershipById(id: Int!): Membership"),
+        "{sdl}"
+    );
+    assert!(!sdl.contains("membershipByUserId"), "{sdl}");
+
+    let res = schema
+        .execute(
+            "{ a: membershipByOrgIdAndUserId(org_id: 2, user_id: 7) { id active }
+               b: membershipByOrgIdAndUserId(org_id: 3, user_id: 7) { id } }",
+        )
+        .await;
+    res.assert_ok().assert_sql_contains(
+        "SELECT * FROM \"{schema}\".\"memberships\" WHERE \"org_id\" = $1 AND \"user_id\" = $2",
+    );
+    let data = res.data();
+    assert_eq!(data["a"], serde_json::json!({ "id": 2, "active": false }));
+    assert_eq!(data["b"], serde_json::Value::Null);
+
+    db.drop().await.expect("failed to drop test schema");
+}
+
+/// Constraint and index comments tag the finders generated from them.
+#[tokio::test]
+async fn constraint_comments_tag_finders() {
+    let db = common::fixture(
+        "CREATE TABLE accounts (
+             id serial PRIMARY KEY,
+             email text NOT NULL CONSTRAINT accounts_email_key UNIQUE,
+             login text NOT NULL CONSTRAINT accounts_login_key UNIQUE,
+             handle text NOT NULL
+         );
+         CREATE UNIQUE INDEX accounts_handle_idx ON accounts (handle);
+         COMMENT ON CONSTRAINT accounts_email_key ON accounts IS '@fieldName accountByMail';
+         COMMENT ON CONSTRAINT accounts_login_key ON accounts IS 'Legacy. @omit';
+         COMMENT ON INDEX accounts_handle_idx IS '@fieldName accountByAt';
+         INSERT INTO accounts (email, login, handle) VALUES ('a@example.com', 'a', 'aa');",
+    )
+    .await;
+
+    let schema = db.build().await.expect("schema build failed");
+    let sdl = schema.sdl().await;
+    assert!(sdl.contains("accountByMail(email: String!)"), "{sdl}");
+    assert!(sdl.contains("accountByAt(handle: String!)"), "{sdl}");
+    assert!(!sdl.contains("accountByEmail"), "{sdl}");
+    assert!(!sdl.contains("accountByLogin"), "{sdl}");
+
+    let res = schema
+        .execute(r#"{ accountByMail(email: "a@example.com") { login } }"#)
+        .await;
+    res.assert_ok();
+    assert_eq!(
+        res.data()["accountByMail"],
+        serde_json::json!({ "login": "a" })
+    );
+
+    db.drop().await.expect("failed to drop test schema");
+}
+
+/// Exclusion constraint violations name the constraint and its columns.
+#[tokio::test]
+async fn exclusion_violations_are_structured() {
+    let db = common::fixture(
+        "CREATE TABLE bookings (
+             id serial PRIMARY KEY,
+             room int NOT NULL,
+             slot int NOT NULL,
+             CONSTRAINT no_double_booking EXCLUDE USING btree (room WITH =, slot WITH =)
+         );
+         INSERT INTO bookings (room, slot) VALUES (1, 9);",
+    )
+    .await;
+
+    let schema = db.build().await.expect("schema build failed");
+    let res = schema
+        .execute("mutation { createBooking(input: { room: 1, slot: 9 }) { id } }")
+        .await;
+    let error = &res.response.errors[0];
+    assert!(
+        error
+            .message
+            .contains("(room, slot) conflicts with an existing row"),
+        "{}",
+        error.message
+    );
+    let ext = error.extensions.as_ref().unwrap();
+    assert_eq!(
+        ext.get("code"),
+        Some(&async_graphql::Value::from("EXCLUSION_VIOLATION"))
+    );
+    assert_eq!(
+        ext.get("constraint"),
+        Some(&async_graphql::Value::from("no_double_booking"))
+    );
+    assert_eq!(
+        ext.get("columns"),
+        Some(&async_graphql::value!(["room", "slot"]))
+    );
+
+    db.drop().await.expect("failed to drop test schema");
+}
+
+/// `@name` renames a table's type and collection, `@fieldName` a column, and
+/// `@primaryKey` / `@foreignKey` give a materialized view a finder and nest
+/// it under the table it references.
+#[tokio::test]
+async fn smart_tags_rename_types_and_declare_keys() {
+    let db = common::fixture(
+        "CREATE TABLE widgets (id serial PRIMARY KEY, label text NOT NULL);
+         COMMENT ON TABLE widgets IS 'Things we sell. @name Gadget';
+         COMMENT ON COLUMN widgets.label IS '@fieldName title';
+         INSERT INTO widgets (label) VALUES ('lamp'), ('desk');
+         CREATE MATERIALIZED VIEW widget_totals AS
+             SELECT id AS widget_id, length(label)::int AS letters FROM widgets;
+         COMMENT ON MATERIALIZED VIEW widget_totals IS
+             '@primaryKey widget_id
+              @foreignKey (widget_id) references widgets(id)';",
+    )
+    .await;
+
+    let schema = db.build().await.expect("schema build failed");
+    let sdl = schema.sdl().await;
+    assert!(sdl.contains("type Gadget "), "{sdl}");
+    assert!(!sdl.contains("type Widget "), "{sdl}");
+    assert!(sdl.contains("createGadget("), "{sdl}");
+
+    let res = schema
+        .execute(
+            "{ allGadgets(orderBy: [ID_ASC]) { nodes { title widgetTotalsByWidgetId { letters } } }
+               widgetTotalByWidgetId(widget_id: 2) { letters } }",
+        )
+        .await;
+    let data = res.assert_ok().data();
+    let nodes = &data["allGadgets"]["nodes"];
+    assert_eq!(nodes[0]["title"], "lamp");
+    // The referencing columns are the view's key, so it nests as one row.
+    assert_eq!(nodes[0]["widgetTotalsByWidgetId"]["letters"], 4);
+    assert_eq!(data["widgetTotalByWidgetId"]["letters"], 4);
+
+    db.drop().await.expect("failed to drop test schema");
+}
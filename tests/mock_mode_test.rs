@@ -0,0 +1,77 @@
+mod common;
+
+use serde_json::json;
+use turbograph::{Catalog, Config, MockData, PoolConfig, SchemaOptions, TurboGraph};
+
+/// A catalog saved from the database yields the same schema in mock mode,
+/// and queries are answered from the fixtures.
+#[tokio::test]
+async fn mock_schema_matches_live_schema() {
+    let db = common::test_database().await;
+    db.load_fixture(concat!(
+        env!("CARGO_MANIFEST_DIR"),
+        "/tests/fixtures/blog.sql"
+    ))
+    .await
+    .expect("fixture failed");
+
+    let live_sdl = db.build().await.expect("schema build failed").sdl().await;
+    let catalog = turbograph::introspect(Config::new(
+        PoolConfig::ConnectionString(common::db_url()),
+        vec![db.schema_name().to_string()],
+    ))
+    .await
+    .expect("introspection failed");
+    db.drop().await.expect("failed to drop test schema");
+
+    // Round-trip through JSON as a saved catalog would be.
+    let saved = serde_json::to_string(&catalog).unwrap();
+    let catalog: Catalog = serde_json::from_str(&saved).unwrap();
+
+    let data = MockData::from_json(json!({
+        "users": [
+            { "id": 1, "username": "alice", "bio": null, "is_active": true, "created_at": "2024-01-01T00:00:00+00:00" },
+            { "id": 2, "username": "bob", "bio": "hi", "is_active": false, "created_at": "2024-02-01T00:00:00+00:00" },
+            { "id": 3, "username": "carol", "bio": null, "is_active": true, "created_at": "2024-03-01T00:00:00+00:00" }
+        ]
+    }))
+    .unwrap();
+    let server = TurboGraph::mock(catalog, data, SchemaOptions::default())
+        .expect("mock schema build failed");
+    assert_eq!(server.schema().await.sdl(), live_sdl);
+
+    let res = server
+        .execute(async_graphql::Request::new(
+            "{ allUsers(condition: { is_active: { equal: true } }, orderBy: [USERNAME_DESC], first: 1) {
+                 totalCount pageInfo { hasNextPage } nodes { username }
+               } }",
+        ))
+        .await;
+    assert!(res.errors.is_empty(), "{:?}", res.errors);
+    let data = res.data.into_json().unwrap();
+    assert_eq!(data["allUsers"]["totalCount"], 2);
+    assert_eq!(data["allUsers"]["pageInfo"]["hasNextPage"], true);
+    assert_eq!(data["allUsers"]["nodes"][0]["username"], "carol");
+
+    let res = server
+        .execute(async_graphql::Request::new(
+            r#"mutation { updateUser(patch: { bio: "new" }, condition: { id: { equal: 2 } }) { id bio } }"#,
+        ))
+        .await;
+    assert!(res.errors.is_empty(), "{:?}", res.errors);
+    assert_eq!(
+        res.data.into_json().unwrap(),
+        json!({ "updateUser": [{ "id": 2, "bio": "new" }] })
+    );
+
+    // Mutations never change the fixtures.
+    let res = server
+        .execute(async_graphql::Request::new(
+            "{ allUsers(condition: { id: { equal: 2 } }) { nodes { bio } } }",
+        ))
+        .await;
+    assert_eq!(
+        res.data.into_json().unwrap()["allUsers"]["nodes"][0]["bio"],
+        "hi"
+    );
+}
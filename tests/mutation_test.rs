@@ -0,0 +1,344 @@
+mod common;
+
+/// `bytea` and `@largeObject` columns take uploads in mutations, within the
+/// configured size limit.
+#[tokio::test]
+async fn uploads_write_bytea_and_large_objects() {
+    let db = common::fixture(
+        "CREATE TABLE documents (id serial PRIMARY KEY, name text, body bytea, scan oid);
+         COMMENT ON COLUMN documents.scan IS '@largeObject';",
+    )
+    .await;
+
+    let schema = db
+        .build_with(|config| {
+            config.uploads = true;
+            config.max_upload_bytes = Some(16);
+        })
+        .await
+        .expect("schema build failed");
+    let sdl = schema.sdl().await;
+    assert!(sdl.contains("scalar Upload"), "{sdl}");
+    assert!(sdl.contains("body: Upload"), "{sdl}");
+    assert!(sdl.contains("scan: Upload"), "{sdl}");
+
+    let upload = |name: &str, contents: &str| {
+        let path = std::env::temp_dir().join(format!("{}-{name}", db.schema_name()));
+        std::fs::write(&path, contents).unwrap();
+        async_graphql::UploadValue {
+            filename: "notes.txt".into(),
+            content_type: Some("text/plain".into()),
+            content: std::fs::File::open(&path).unwrap(),
+        }
+    };
+    let create = |body: &str, scan: &str| {
+        let mut request = async_graphql::Request::new(
+            "mutation ($body: Upload, $scan: Upload) {
+               createDocument(input: { name: \"notes\", body: $body, scan: $scan }) { id body scan }
+             }",
+        )
+        .variables(async_graphql::Variables::from_json(
+            serde_json::json!({ "body": null, "scan": null }),
+        ));
+        request.set_upload("variables.body", upload("body", body));
+        request.set_upload("variables.scan", upload("scan", scan));
+        request
+    };
+
+    let res = schema.execute(create("hello", "scanned page")).await;
+    res.assert_ok();
+    let document = &res.data()["createDocument"];
+    assert_eq!(document["body"], "aGVsbG8=");
+    assert!(document["scan"].as_str().unwrap().parse::<u32>().is_ok());
+    db.execute_sql(
+        "DO $$ BEGIN
+           IF (SELECT convert_from(lo_get(scan), 'UTF8') FROM documents) <> 'scanned page' THEN
+             RAISE EXCEPTION 'large object not written';
+           END IF;
+         END $$;",
+    )
+    .await
+    .expect("large object mismatch");
+
+    let res = schema
+        .execute(create("hello", "a page longer than the limit"))
+        .await;
+    let error = &res.response.errors[0];
+    let code = error.extensions.as_ref().and_then(|ext| ext.get("code"));
+    assert_eq!(code, Some(&async_graphql::Value::from("UPLOAD_TOO_LARGE")));
+
+    for name in ["body", "scan"] {
+        let _ =
+            std::fs::remove_file(std::env::temp_dir().join(format!("{}-{name}", db.schema_name())));
+    }
+    db.drop().await.expect("failed to drop test schema");
+}
+
+/// Tables with a primary key get `update{T}By{Key}` / `delete{T}By{Key}`,
+/// which leave generated and `GENERATED ALWAYS` identity columns out of
+/// their inputs and answer the affected row.
+#[tokio::test]
+async fn primary_key_mutations_target_one_row() {
+    let db = common::fixture(
+        "CREATE TABLE items (
+             id int GENERATED ALWAYS AS IDENTITY PRIMARY KEY,
+             name text NOT NULL,
+             upper_name text GENERATED ALWAYS AS (upper(name)) STORED
+         );
+         CREATE TABLE memberships (
+             org_id int,
+             user_id int,
+             role text NOT NULL,
+             PRIMARY KEY (org_id, user_id)
+         );
+         CREATE TABLE log_lines (message text);
+         INSERT INTO items (name) VALUES ('gear'), ('bolt');
+         INSERT INTO memberships VALUES (1, 2, 'member');",
+    )
+    .await;
+
+    let schema = db.build().await.expect("failed to build schema");
+    let sdl = schema.sdl().await;
+    assert!(
+        sdl.contains("updateItemById(input: UpdateItemByIdInput!): Item"),
+        "{sdl}"
+    );
+    assert!(
+        sdl.contains("deleteMembershipByOrgIdAndUserId(input: DeleteMembershipByOrgIdAndUserIdInput!): Membership"),
+        "{sdl}"
+    );
+    assert!(!sdl.contains("LogLineBy"), "{sdl}");
+    let create_input = sdl
+        .split("input CreateItemInput {")
+        .nth(1)
+        .and_then(|rest| rest.split('}').next())
+        .unwrap();
+    assert_eq!(create_input.trim(), "name: String!");
+
+    let res = schema
+        .execute(
+            r#"mutation { updateItemById(input: { id: 2, patch: { name: "nut" } }) { id upper_name } }"#,
+        )
+        .await;
+    res.assert_ok()
+        .assert_sql_contains("UPDATE \"{schema}\".\"items\"");
+    assert_eq!(
+        res.data()["updateItemById"],
+        serde_json::json!({ "id": 2, "upper_name": "NUT" })
+    );
+
+    let res = schema
+        .execute(
+            "mutation { deleteMembershipByOrgIdAndUserId(input: { org_id: 1, user_id: 2 }) { role } }",
+        )
+        .await;
+    res.assert_ok();
+    assert_eq!(
+        res.data()["deleteMembershipByOrgIdAndUserId"],
+        serde_json::json!({ "role": "member" })
+    );
+
+    let res = schema
+        .execute("mutation { deleteItemById(input: { id: 99 }) { id } }")
+        .await;
+    res.assert_ok();
+    assert_eq!(res.data()["deleteItemById"], serde_json::Value::Null);
+
+    db.drop().await.expect("failed to drop test schema");
+}
+
+/// A `uuid` key with a generating default is filled in by the database, or
+/// taken from the client when supplied.
+#[tokio::test]
+async fn generated_uuid_keys_may_be_supplied() {
+    let db = common::fixture(
+        "CREATE TABLE notes (
+             id uuid PRIMARY KEY DEFAULT gen_random_uuid(),
+             body text NOT NULL
+         );",
+    )
+    .await;
+
+    let schema = db.build().await.expect("failed to build schema");
+    let sdl = schema.sdl().await;
+    assert!(
+        sdl.contains("Generated by the database when omitted"),
+        "{sdl}"
+    );
+
+    let res = schema
+        .execute(r#"mutation { createNote(input: { body: "server" }) { id } }"#)
+        .await;
+    res.assert_ok();
+    let id = res.data()["createNote"]["id"].as_str().unwrap().to_string();
+    assert_eq!(id.len(), 36);
+
+    let res = schema
+        .execute(
+            r#"mutation { createNote(input: { id: "a0eebc99-9c0b-4ef8-bb6d-6bb9bd380a11", body: "client" }) { id } }"#,
+        )
+        .await;
+    res.assert_ok();
+    assert_eq!(
+        res.data()["createNote"]["id"],
+        "a0eebc99-9c0b-4ef8-bb6d-6bb9bd380a11"
+    );
+
+    let res = schema
+        .execute(r#"mutation { createNote(input: { id: "not-a-uuid", body: "bad" }) { id } }"#)
+        .await;
+    assert!(!res.response.errors.is_empty());
+
+    let res = schema.execute("{ allNotes { totalCount } }").await;
+    res.assert_ok();
+    assert_eq!(res.data()["allNotes"]["totalCount"], 2);
+
+    db.drop().await.expect("failed to drop test schema");
+}
+
+/// `@insertOnly` columns can be set on create but are not part of the patch.
+#[tokio::test]
+async fn insert_only_columns_are_not_patchable() {
+    let db = common::fixture(
+        "CREATE TABLE notes (id serial PRIMARY KEY, body text, created_by text NOT NULL);
+         COMMENT ON COLUMN notes.created_by IS '@insertOnly';",
+    )
+    .await;
+
+    let schema = db.build().await.expect("schema build failed");
+    let res = schema
+        .execute(
+            r#"mutation { createNote(input: { body: "hi", created_by: "ann" }) { created_by } }"#,
+        )
+        .await;
+    res.assert_ok();
+    assert_eq!(res.data()["createNote"]["created_by"], "ann");
+
+    let res = schema
+        .execute(r#"mutation { updateNote(patch: { created_by: "bob" }) { id } }"#)
+        .await;
+    let message = &res.response.errors[0].message;
+    assert!(message.contains("created_by"), "{message}");
+
+    db.drop().await.expect("failed to drop test schema");
+}
+
+/// `@createdAt` / `@updatedAt` columns are set by the mutations and are not
+/// part of their inputs.
+#[tokio::test]
+async fn timestamp_tags_are_maintained_by_mutations() {
+    let db = common::fixture(
+        "CREATE TABLE notes (
+             id serial PRIMARY KEY,
+             body text,
+             created_at timestamptz NOT NULL,
+             updated_at timestamptz NOT NULL
+         );
+         COMMENT ON COLUMN notes.created_at IS '@createdAt';
+         COMMENT ON COLUMN notes.updated_at IS '@updatedAt';",
+    )
+    .await;
+
+    let schema = db.build().await.expect("schema build failed");
+    let sdl = schema.sdl().await;
+    let create_input = sdl.split("input CreateNoteInput").nth(1).unwrap();
+    let create_input = &create_input[..create_input.find('}').unwrap()];
+    assert!(!create_input.contains("created_at"), "{sdl}");
+    assert!(!create_input.contains("updated_at"), "{sdl}");
+
+    let res = schema
+        .execute(r#"mutation { createNote(input: { body: "hi" }) { created_at updated_at } }"#)
+        .await;
+    res.assert_ok().assert_sql_contains(
+        "INSERT INTO \"{schema}\".\"notes\" (\"body\", \"created_at\", \"updated_at\") VALUES ($1, now(), now())",
+    );
+    let created = res.data()["createNote"]["created_at"].clone();
+
+    let res = schema
+        .execute(r#"mutation { updateNote(patch: { body: "edited" }) { created_at } }"#)
+        .await;
+    res.assert_ok()
+        .assert_sql_contains("SET \"body\" = $1, \"updated_at\" = now()");
+    assert_eq!(res.data()["updateNote"][0]["created_at"], created);
+
+    db.drop().await.expect("failed to drop test schema");
+}
+
+/// `NOT NULL` columns the database fills in are optional in the create input
+/// but non-null in the output; columns it always computes are not writable.
+#[tokio::test]
+async fn defaulted_columns_are_optional_on_create() {
+    let db = common::fixture(
+        "CREATE TABLE items (
+             id int GENERATED ALWAYS AS IDENTITY PRIMARY KEY,
+             code int GENERATED BY DEFAULT AS IDENTITY,
+             qty int NOT NULL DEFAULT 1,
+             price int NOT NULL,
+             total int GENERATED ALWAYS AS (qty * price) STORED
+         );",
+    )
+    .await;
+
+    let schema = db.build().await.expect("schema build failed");
+    let sdl = schema.sdl().await;
+    let input = |name: &str| {
+        let body = sdl.split(&format!("input {name} ")).nth(1).unwrap();
+        body[..body.find('}').unwrap()].to_string()
+    };
+    let create = input("CreateItemInput");
+    assert!(create.contains("code: Int\n"), "{create}");
+    assert!(create.contains("qty: Int\n"), "{create}");
+    assert!(create.contains("price: Int!"), "{create}");
+    assert!(
+        !create.contains("id:") && !create.contains("total:"),
+        "{create}"
+    );
+    let patch = input("UpdateItemPatch");
+    assert!(
+        !patch.contains("id:") && !patch.contains("total:"),
+        "{patch}"
+    );
+    assert!(sdl.contains("code: Int!"), "{sdl}");
+
+    let res = schema
+        .execute("mutation { createItem(input: { price: 5 }) { id code qty total } }")
+        .await;
+    res.assert_ok();
+    assert_eq!(
+        res.data()["createItem"],
+        serde_json::json!({ "id": 1, "code": 1, "qty": 1, "total": 5 })
+    );
+
+    db.drop().await.expect("failed to drop test schema");
+}
+
+/// Each mutation field reads after the writes before it: a relation read
+/// again after an update of its row sees the new value, not the one the
+/// first field memoized.
+#[tokio::test]
+async fn mutation_fields_reread_rows_changed_before_them() {
+    let db = common::fixture(
+        "CREATE TABLE users (id int PRIMARY KEY, bio text);
+         CREATE TABLE posts (id int PRIMARY KEY, author_id int REFERENCES users, views int);
+         INSERT INTO users VALUES (1, 'original');
+         INSERT INTO posts VALUES (1, 1, 0);",
+    )
+    .await;
+
+    let schema = db.build().await.expect("schema build failed");
+    let res = schema
+        .execute(
+            r#"mutation {
+                 a: updatePostById(input: { id: 1, patch: { views: 320 } }) { userByAuthorId { bio } }
+                 b: updateUserById(input: { id: 1, patch: { bio: "CHANGED" } }) { bio }
+                 c: updatePostById(input: { id: 1, patch: { views: 320 } }) { userByAuthorId { bio } }
+             }"#,
+        )
+        .await;
+    let data = res.assert_ok().data();
+    assert_eq!(data["a"]["userByAuthorId"]["bio"], "original");
+    assert_eq!(data["b"]["bio"], "CHANGED");
+    assert_eq!(data["c"]["userByAuthorId"]["bio"], "CHANGED");
+
+    db.drop().await.expect("failed to drop test schema");
+}
@@ -0,0 +1,260 @@
+mod common;
+
+/// `camel_case_fields` exposes camelCase field names; names are sanitized
+/// either way.
+#[tokio::test]
+async fn camel_case_fields_toggle_field_names() {
+    let db = common::fixture(
+        "CREATE TABLE people (id serial PRIMARY KEY, first_name text, \"last name\" text);
+         CREATE PROCEDURE rename_person(person_id int, new_name text)
+           LANGUAGE sql SET search_path FROM CURRENT
+           AS $$ UPDATE people SET first_name = new_name WHERE id = person_id $$;",
+    )
+    .await;
+
+    let schema = db
+        .build_with(|config| config.camel_case_fields = true)
+        .await
+        .expect("schema build failed");
+    let res = schema
+        .execute(
+            r#"mutation { createPerson(input: { firstName: "Ada", lastName: "Lovelace" }) { id } }"#,
+        )
+        .await;
+    res.assert_ok();
+    let res = schema
+        .execute(r#"mutation { callRenamePerson(input: { personId: 1, newName: "Grace" }) }"#)
+        .await;
+    res.assert_ok();
+    let res = schema
+        .execute(
+            r#"{ allPeople(condition: { firstName: "Grace" }) { nodes { firstName lastName } } }"#,
+        )
+        .await;
+    assert_eq!(
+        res.assert_ok().data()["allPeople"]["nodes"],
+        serde_json::json!([{ "firstName": "Grace", "lastName": "Lovelace" }])
+    );
+
+    let schema = db.build().await.expect("schema build failed");
+    let res = schema
+        .execute("{ allPeople { nodes { first_name last_name } } }")
+        .await;
+    assert_eq!(
+        res.assert_ok().data()["allPeople"]["nodes"],
+        serde_json::json!([{ "first_name": "Grace", "last_name": "Lovelace" }])
+    );
+
+    db.drop().await.expect("failed to drop test schema");
+}
+
+/// `@omit` and `@subscribe` on a schema comment apply to every table in the
+/// schema, before the tables' own tags.
+#[tokio::test]
+async fn schema_comment_tags_apply_to_every_table() {
+    let db = common::test_database().await;
+    db.execute_sql(&format!(
+        "CREATE TABLE widgets (id serial PRIMARY KEY, name text);
+         CREATE TABLE gadgets (id serial PRIMARY KEY, name text);
+         COMMENT ON TABLE gadgets IS '@omit update';
+         COMMENT ON SCHEMA \"{}\" IS '@omit create,delete @subscribe';",
+        db.schema_name()
+    ))
+    .await
+    .expect("fixture failed");
+
+    let schema = db
+        .build_with(|config| config.subscriptions = true)
+        .await
+        .expect("schema build failed");
+    let sdl = schema.sdl().await;
+    assert!(sdl.contains("allWidgets("), "{sdl}");
+    assert!(sdl.contains("updateWidget("), "{sdl}");
+    for hidden in [
+        "createWidget(",
+        "deleteWidget(",
+        "createGadget(",
+        "updateGadget(",
+    ] {
+        assert!(!sdl.contains(hidden), "{hidden} in {sdl}");
+    }
+    assert!(sdl.contains("widgetChanged"), "{sdl}");
+    assert!(sdl.contains("gadgetChanged"), "{sdl}");
+
+    db.drop().await.expect("failed to drop test schema");
+}
+
+/// `@example` values show up in descriptions and preload GraphiQL.
+#[tokio::test]
+async fn example_tags_document_and_prefill_graphiql() {
+    let db = common::fixture(
+        r#"CREATE TABLE posts (id serial PRIMARY KEY, title text NOT NULL);
+           COMMENT ON TABLE posts IS '@example {"title": "Hello"}';
+           COMMENT ON COLUMN posts.title IS '@example "Hello"';"#,
+    )
+    .await;
+
+    let schema = db.build().await.expect("schema build failed");
+    let sdl = schema.sdl().await;
+    assert!(
+        sdl.contains("\"\"\"\nExample: `{\"title\":\"Hello\"}`\n\"\"\"\ntype Post"),
+        "{sdl}"
+    );
+    assert!(
+        sdl.contains("\"\"\"\n\tExample: `\"Hello\"`\n\t\"\"\"\n\ttitle: String!"),
+        "{sdl}"
+    );
+
+    let page = schema.server().graphiql_with_examples("/graphql").await;
+    assert!(
+        page.contains(r#"defaultQuery: "mutation CreatePostExample($postInput: CreatePostInput!)"#),
+        "{page}"
+    );
+    assert!(page.contains(r#"\"title\": \"Hello\""#), "{page}");
+
+    db.drop().await.expect("failed to drop test schema");
+}
+
+/// `@description(<locale>)` tags are picked by the configured locale.
+#[tokio::test]
+async fn descriptions_follow_the_configured_locale() {
+    let db = common::fixture(
+        "CREATE TABLE users (id serial PRIMARY KEY, name text NOT NULL);
+         COMMENT ON TABLE users IS
+             '@description Registered users
+              @description(fr) Utilisateurs inscrits';
+         COMMENT ON COLUMN users.name IS '@description Display name';",
+    )
+    .await;
+
+    let schema = db.build().await.expect("schema build failed");
+    let sdl = schema.sdl().await;
+    assert!(
+        sdl.contains("\"\"\"\nRegistered users\n\"\"\"\ntype User"),
+        "{sdl}"
+    );
+
+    let schema = db
+        .build_with(|config| config.locale = Some("fr-CA".into()))
+        .await
+        .expect("schema build failed");
+    let sdl = schema.sdl().await;
+    assert!(
+        sdl.contains("\"\"\"\nUtilisateurs inscrits\n\"\"\"\ntype User"),
+        "{sdl}"
+    );
+    // No French text for the column: the default description is kept.
+    assert!(
+        sdl.contains("\"\"\"\n\tDisplay name\n\t\"\"\"\n\tname: String!"),
+        "{sdl}"
+    );
+
+    db.drop().await.expect("failed to drop test schema");
+}
+
+/// Federation smart tags become directives in the subgraph SDL.
+#[tokio::test]
+async fn federation_tags_reach_subgraph_sdl() {
+    let db = common::fixture(
+        "CREATE TABLE products (id serial PRIMARY KEY, price int NOT NULL);
+         COMMENT ON TABLE products IS '@shareable @tag catalog';
+         COMMENT ON COLUMN products.price IS 'Unit price. @override inventory';",
+    )
+    .await;
+
+    let schema = db.build().await.expect("schema build failed");
+    let res = schema.execute("{ _service { sdl } }").await;
+    res.assert_ok();
+
+    let data = res.data();
+    let sdl = data["_service"]["sdl"].as_str().unwrap();
+    assert!(
+        sdl.contains("type Product @shareable @tag(name: \"catalog\")"),
+        "{sdl}"
+    );
+    assert!(
+        sdl.contains("price: Int! @override(from: \"inventory\")"),
+        "{sdl}"
+    );
+
+    db.drop().await.expect("failed to drop test schema");
+}
+
+/// Names the test schema's types after a blog and lists rows by their table
+/// name, keeping the stored values as enum items.
+struct BlogInflector;
+
+impl turbograph::Inflector for BlogInflector {
+    fn type_name(&self, table: &turbograph::Table) -> String {
+        format!("Blog{}", turbograph::DefaultInflector.type_name(table))
+    }
+
+    fn field_name(&self, column: &str, _camel_case: bool) -> String {
+        turbograph::to_camel_case(column)
+    }
+
+    fn all_rows_field(&self, table: &turbograph::Table) -> String {
+        turbograph::to_camel_case(table.name())
+    }
+
+    fn connection_type(&self, type_name: &str) -> String {
+        format!("{type_name}Page")
+    }
+
+    fn create_field(&self, type_name: &str) -> String {
+        format!("new{type_name}")
+    }
+
+    fn enum_value(&self, value: &str) -> String {
+        value.to_string()
+    }
+}
+
+/// A configured inflector names types, fields, root fields and enum items,
+/// and resolvers follow the names it picked.
+#[tokio::test]
+async fn custom_inflector_names_the_schema() {
+    let db = common::fixture(
+        "CREATE TABLE post_statuses (status text PRIMARY KEY);
+         COMMENT ON TABLE post_statuses IS '@enum';
+         INSERT INTO post_statuses VALUES ('draft'), ('in_review');
+         CREATE TABLE articles (
+             id serial PRIMARY KEY,
+             status text NOT NULL REFERENCES post_statuses,
+             created_at timestamptz NOT NULL DEFAULT now()
+         );
+         CREATE TABLE tags (id serial PRIMARY KEY, label text);
+         COMMENT ON TABLE tags IS E'@name Label';
+         INSERT INTO articles (status) VALUES ('draft'), ('in_review');",
+    )
+    .await;
+
+    let schema = db
+        .build_with(|config| config.inflector = Some(std::sync::Arc::new(BlogInflector)))
+        .await
+        .expect("schema build failed");
+    let sdl = schema.sdl().await;
+    assert!(sdl.contains("type BlogArticle "), "{sdl}");
+    assert!(sdl.contains("type BlogArticlePage "), "{sdl}");
+    assert!(sdl.contains("type BlogArticleEdge "), "{sdl}");
+    assert!(sdl.contains("enum BlogPostStatus "), "{sdl}");
+    assert!(sdl.contains("createdAt: Datetime!"), "{sdl}");
+    assert!(sdl.contains("updateBlogArticleById("), "{sdl}");
+    // `@name` takes precedence over the inflector.
+    assert!(sdl.contains("type Label "), "{sdl}");
+
+    let res = schema
+        .execute(
+            "{ articles(condition: { status: { equal: in_review } }) { nodes { id status createdAt } } }",
+        )
+        .await;
+    let nodes = &res.assert_ok().data()["articles"]["nodes"];
+    assert_eq!(nodes[0]["status"], "in_review");
+
+    let res = schema
+        .execute("mutation { newBlogArticle(input: { status: draft }) { status } }")
+        .await;
+    assert_eq!(res.assert_ok().data()["newBlogArticle"]["status"], "draft");
+
+    db.drop().await.expect("failed to drop test schema");
+}
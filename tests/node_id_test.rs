@@ -0,0 +1,202 @@
+mod common;
+
+use turbograph::NodeIdStrategy;
+
+const FIXTURE: &str = "
+    CREATE TABLE widgets (id serial PRIMARY KEY, name text NOT NULL);
+    CREATE TABLE log_lines (line text);
+    INSERT INTO widgets (name) VALUES ('sprocket'), ('gear');
+";
+
+/// `NodeIdStrategy::Field` adds `nodeId` next to the `id` column, and only
+/// tables with a primary key implement `Node`.
+#[tokio::test]
+async fn node_id_field_strategy() {
+    let db = common::fixture(FIXTURE).await;
+
+    let schema = db
+        .build_with(|c| c.node_id = Some(NodeIdStrategy::Field("nodeId".into())))
+        .await
+        .expect("schema build failed");
+
+    let sdl = schema.sdl().await;
+    assert!(sdl.contains("type Widget implements Node"), "{sdl}");
+    assert!(sdl.contains("node(nodeId: ID!): Node"), "{sdl}");
+    assert!(!sdl.contains("type LogLine implements Node"), "{sdl}");
+
+    let res = schema
+        .execute("{ allWidgets(orderBy: [ID_ASC]) { nodes { nodeId id name } } }")
+        .await;
+    res.assert_ok();
+    let node_id = res.data()["allWidgets"]["nodes"][1]["nodeId"]
+        .as_str()
+        .unwrap()
+        .to_string();
+
+    let res = schema
+        .execute(format!(
+            r#"{{ node(nodeId: "{node_id}") {{ nodeId ... on Widget {{ id name }} }} }}"#
+        ))
+        .await;
+    res.assert_ok().assert_sql_contains("WHERE \"id\" = $1");
+    let data = res.data();
+    assert_eq!(data["node"]["nodeId"], node_id);
+    assert_eq!(data["node"]["id"], 2);
+    assert_eq!(data["node"]["name"], "gear");
+
+    db.drop().await.unwrap();
+}
+
+/// `NodeIdStrategy::RenameIdColumn` makes `id` the global ID and exposes the
+/// column as `rowId` everywhere it appears.
+#[tokio::test]
+async fn rename_id_column_strategy() {
+    let db = common::fixture(FIXTURE).await;
+
+    let schema = db
+        .build_with(|c| c.node_id = Some(NodeIdStrategy::RenameIdColumn("rowId".into())))
+        .await
+        .expect("schema build failed");
+
+    let sdl = schema.sdl().await;
+    assert!(sdl.contains("node(id: ID!): Node"), "{sdl}");
+    assert!(sdl.contains("rowId: WidgetRowIdFilter"), "{sdl}");
+    assert!(sdl.contains("ROWID_ASC"), "{sdl}");
+
+    let res = schema
+        .execute(
+            "{ allWidgets(condition: { rowId: { equal: 1 } }, orderBy: [ROWID_DESC]) {
+                 nodes { id rowId name }
+               } }",
+        )
+        .await;
+    res.assert_ok().assert_sql_contains("WHERE \"id\" = $1");
+    let node = &res.data()["allWidgets"]["nodes"][0];
+    assert_eq!(node["rowId"], 1);
+    assert_eq!(node["name"], "sprocket");
+
+    let res = schema
+        .execute(format!(
+            r#"{{ node(id: "{}") {{ ... on Widget {{ rowId }} }} }}"#,
+            node["id"].as_str().unwrap()
+        ))
+        .await;
+    res.assert_ok();
+    assert_eq!(res.data()["node"]["rowId"], 1);
+
+    let res = schema
+        .execute(r#"mutation { updateWidget(patch: { name: "cog" }, condition: { rowId: { equal: 2 } }) { rowId name } }"#)
+        .await;
+    res.assert_ok();
+    assert_eq!(res.data()["updateWidget"][0]["name"], "cog");
+
+    db.drop().await.unwrap();
+}
+
+/// `update{T}ByNodeId` / `delete{T}ByNodeId` target the row behind a global
+/// ID and reject IDs of other types.
+#[tokio::test]
+async fn node_id_mutations() {
+    let db = common::fixture(FIXTURE).await;
+
+    let schema = db
+        .build_with(|c| c.node_id = Some(NodeIdStrategy::RenameIdColumn("rowId".into())))
+        .await
+        .expect("schema build failed");
+
+    let sdl = schema.sdl().await;
+    assert!(
+        sdl.contains("updateWidgetByNodeId(input: UpdateWidgetByNodeIdInput!): Widget"),
+        "{sdl}"
+    );
+    assert!(!sdl.contains("updateLogLineByNodeId"), "{sdl}");
+
+    let res = schema
+        .execute("{ allWidgets(orderBy: [ROWID_ASC]) { nodes { id } } }")
+        .await;
+    let gear = res.data()["allWidgets"]["nodes"][1]["id"]
+        .as_str()
+        .unwrap()
+        .to_string();
+
+    let res = schema
+        .execute(format!(
+            r#"mutation {{ updateWidgetByNodeId(input: {{ id: "{gear}", patch: {{ name: "cog" }} }}) {{ rowId name }} }}"#
+        ))
+        .await;
+    res.assert_ok().assert_sql_contains("WHERE \"id\" = $2");
+    assert_eq!(res.data()["updateWidgetByNodeId"]["rowId"], 2);
+    assert_eq!(res.data()["updateWidgetByNodeId"]["name"], "cog");
+
+    let res = schema
+        .execute(format!(
+            r#"mutation {{ deleteWidgetByNodeId(input: {{ id: "{gear}" }}) {{ name }} }}"#
+        ))
+        .await;
+    res.assert_ok();
+    assert_eq!(res.data()["deleteWidgetByNodeId"]["name"], "cog");
+
+    // `["LogLine",1]` is not a Widget ID.
+    let res = schema
+        .execute(
+            r#"mutation { deleteWidgetByNodeId(input: { id: "WyJMb2dMaW5lIiwxXQ==" }) { name } }"#,
+        )
+        .await;
+    assert!(!res.response.errors.is_empty());
+
+    let res = schema.execute("{ allWidgets { totalCount } }").await;
+    assert_eq!(res.data()["allWidgets"]["totalCount"], 1);
+
+    db.drop().await.unwrap();
+}
+
+/// A table whose columns are all `@omit read` keeps a type with just its
+/// global ID, reachable through `node` and relations; without a primary key
+/// it is left out.
+#[tokio::test]
+async fn fully_omitted_table_keeps_its_global_id() {
+    let db = common::test_database().await;
+    db.execute_sql(
+        "CREATE TABLE badges (id serial PRIMARY KEY, code text);
+         COMMENT ON COLUMN badges.id IS '@omit read';
+         COMMENT ON COLUMN badges.code IS '@omit read';
+         CREATE TABLE notes (body text);
+         COMMENT ON COLUMN notes.body IS '@omit read';
+         CREATE TABLE widgets (id serial PRIMARY KEY, badge_id int REFERENCES badges);
+         INSERT INTO badges (code) VALUES ('gold');
+         INSERT INTO widgets (badge_id) VALUES (1);",
+    )
+    .await
+    .expect("fixture failed");
+
+    let schema = db
+        .build_with(|c| c.node_id = Some(NodeIdStrategy::Field("nodeId".into())))
+        .await
+        .expect("schema build failed");
+    let sdl = schema.sdl().await;
+    assert!(sdl.contains("type Badge implements Node"), "{sdl}");
+    assert!(!sdl.contains("allBadges"), "{sdl}");
+    assert!(!sdl.contains("createBadge"), "{sdl}");
+    assert!(!sdl.contains("type Note"), "{sdl}");
+
+    let res = schema
+        .execute("{ allWidgets { nodes { badgeByBadgeId { nodeId } } } }")
+        .await;
+    let node_id = res.assert_ok().data()["allWidgets"]["nodes"][0]["badgeByBadgeId"]["nodeId"]
+        .as_str()
+        .unwrap()
+        .to_string();
+    let res = schema
+        .execute(format!(
+            r#"{{ node(nodeId: "{node_id}") {{ __typename nodeId }} }}"#
+        ))
+        .await;
+    assert_eq!(res.assert_ok().data()["node"]["__typename"], "Badge");
+
+    // Without global IDs there is nothing left to expose.
+    let sdl = db.build().await.expect("schema build failed").sdl().await;
+    assert!(!sdl.contains("type Badge"), "{sdl}");
+    assert!(!sdl.contains("badgeByBadgeId"), "{sdl}");
+
+    db.drop().await.unwrap();
+}
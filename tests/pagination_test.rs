@@ -0,0 +1,301 @@
+mod common;
+
+use async_graphql::futures_util::StreamExt;
+
+/// A `@defaultSort` tag orders rows when the query passes no `orderBy`.
+#[tokio::test]
+async fn default_sort_tag_applies_without_order_by() {
+    let db = common::fixture(
+        "CREATE TABLE widgets (id serial PRIMARY KEY, name text NOT NULL);
+         COMMENT ON TABLE widgets IS '@defaultSort name DESC';
+         INSERT INTO widgets (name) VALUES ('gear'), ('sprocket'), ('axle');",
+    )
+    .await;
+
+    let schema = db.build().await.expect("schema build failed");
+    let res = schema
+        .execute("{ allWidgets { nodes { name } edges { cursor } } }")
+        .await;
+
+    res.assert_ok()
+        .assert_sql_contains("ORDER BY \"name\" DESC");
+
+    let data = res.data();
+    assert_eq!(data["allWidgets"]["nodes"][0]["name"], "sprocket");
+    assert_eq!(data["allWidgets"]["nodes"][2]["name"], "axle");
+    // The cursor records the default ordering, so it matches an explicit one.
+    let explicit = schema
+        .execute("{ allWidgets(orderBy: [NAME_DESC]) { edges { cursor } } }")
+        .await;
+    assert_eq!(
+        data["allWidgets"]["edges"][0]["cursor"],
+        explicit.assert_ok().data()["allWidgets"]["edges"][0]["cursor"]
+    );
+
+    db.drop().await.expect("failed to drop test schema");
+}
+
+/// `after` resumes from a cursor, and cursors from another ordering are
+/// rejected with `INVALID_CURSOR`.
+#[tokio::test]
+async fn after_cursor_pages_and_rejects_foreign_orderings() {
+    let db = common::fixture(
+        "CREATE TABLE widgets (id serial PRIMARY KEY, name text NOT NULL);
+         INSERT INTO widgets (name) VALUES ('axle'), ('gear'), ('sprocket');",
+    )
+    .await;
+
+    let schema = db.build().await.expect("schema build failed");
+    let res = schema
+        .execute("{ allWidgets(orderBy: [NAME_ASC], first: 1) { edges { cursor } } }")
+        .await;
+    let data = res.assert_ok().data();
+    let cursor = data["allWidgets"]["edges"][0]["cursor"].as_str().unwrap();
+
+    let res = schema
+        .execute(format!(
+            r#"{{ allWidgets(orderBy: [NAME_ASC], after: "{cursor}") {{ nodes {{ name }} }} }}"#
+        ))
+        .await;
+    assert_eq!(
+        res.assert_ok().data()["allWidgets"]["nodes"],
+        serde_json::json!([{ "name": "gear" }, { "name": "sprocket" }])
+    );
+
+    let res = schema
+        .execute(format!(
+            r#"{{ allWidgets(orderBy: [NAME_DESC], after: "{cursor}") {{ nodes {{ name }} }} }}"#
+        ))
+        .await;
+    let error = &res.response.errors[0];
+    let code = error.extensions.as_ref().and_then(|ext| ext.get("code"));
+    assert_eq!(
+        code,
+        Some(&async_graphql::Value::from("INVALID_CURSOR")),
+        "{}",
+        error.message
+    );
+
+    db.drop().await.expect("failed to drop test schema");
+}
+
+/// `last` pages back from the end or from a `before` cursor, and `before`
+/// with `after` selects the rows between two cursors.
+#[tokio::test]
+async fn last_and_before_page_backwards() {
+    let db = common::fixture(
+        "CREATE TABLE steps (id int PRIMARY KEY);
+         INSERT INTO steps SELECT generate_series(1, 10);",
+    )
+    .await;
+
+    let schema = db.build().await.expect("schema build failed");
+    let page = |args: String| {
+        let schema = &schema;
+        async move {
+            let res = schema
+                .execute(format!(
+                    "{{ allSteps(orderBy: [ID_ASC]{args}) {{
+                         edges {{ cursor node {{ id }} }}
+                         pageInfo {{ hasNextPage hasPreviousPage }}
+                       }} }}"
+                ))
+                .await;
+            res.assert_ok().data()["allSteps"].clone()
+        }
+    };
+    let ids = |page: &serde_json::Value| -> Vec<i64> {
+        page["edges"]
+            .as_array()
+            .unwrap()
+            .iter()
+            .map(|edge| edge["node"]["id"].as_i64().unwrap())
+            .collect()
+    };
+
+    let tail = page(", last: 3".to_string()).await;
+    assert_eq!(ids(&tail), [8, 9, 10]);
+    assert_eq!(
+        tail["pageInfo"],
+        serde_json::json!({ "hasNextPage": false, "hasPreviousPage": true })
+    );
+    let eighth = tail["edges"][0]["cursor"].as_str().unwrap().to_string();
+
+    let before = page(format!(r#", last: 2, before: "{eighth}""#)).await;
+    assert_eq!(ids(&before), [6, 7]);
+    let sixth = before["edges"][0]["cursor"].as_str().unwrap().to_string();
+
+    let between = page(format!(r#", after: "{sixth}", before: "{eighth}""#)).await;
+    assert_eq!(ids(&between), [7]);
+
+    let first = page(format!(r#", first: 2, before: "{eighth}""#)).await;
+    assert_eq!(ids(&first), [1, 2]);
+
+    db.drop().await.expect("failed to drop test schema");
+}
+
+/// Selecting only `totalCount` runs the count query without fetching rows.
+#[tokio::test]
+async fn total_count_only_skips_the_row_query() {
+    let db = common::fixture(
+        "CREATE TABLE widgets (id serial PRIMARY KEY, name text NOT NULL);
+         INSERT INTO widgets (name) VALUES ('sprocket'), ('gear'), ('axle');",
+    )
+    .await;
+
+    let schema = db.build().await.expect("schema build failed");
+    let res = schema
+        .execute(r#"{ allWidgets(condition: { name: "gear" }) { totalCount } }"#)
+        .await;
+    res.assert_ok().assert_sql_contains("SELECT COUNT(*)");
+    assert_eq!(res.data()["allWidgets"]["totalCount"], 1);
+    assert_eq!(res.sql.len(), 1, "{:#?}", res.sql);
+
+    let res = schema
+        .execute("{ allWidgets { totalCount ... on WidgetConnection { nodes { name } } } }")
+        .await;
+    res.assert_ok().assert_sql_contains("LIMIT");
+    assert_eq!(
+        res.data()["allWidgets"]["nodes"].as_array().unwrap().len(),
+        3
+    );
+
+    db.drop().await.expect("failed to drop test schema");
+}
+
+/// Keyless tables order ties by `@stableOrder`, or warn about offset cursors.
+#[tokio::test]
+async fn keyless_connections_use_stable_order_or_warn() {
+    let db = common::fixture(
+        "CREATE TABLE readings (sensor text, taken_at int, value int);
+         COMMENT ON TABLE readings IS '@stableOrder sensor, taken_at';
+         INSERT INTO readings VALUES ('b', 2, 1), ('a', 1, 1), ('a', 2, 1);
+         CREATE TABLE samples (value int);",
+    )
+    .await;
+
+    let schema = db.build().await.expect("schema build failed");
+    let res = schema
+        .execute(
+            "{ allReadings(orderBy: [VALUE_ASC, TAKEN_AT_DESC]) { nodes { sensor taken_at } } }",
+        )
+        .await;
+    res.assert_ok()
+        .assert_sql_contains("ORDER BY \"value\" ASC, \"taken_at\" DESC, \"sensor\" ASC");
+    assert_eq!(
+        res.data()["allReadings"]["nodes"],
+        serde_json::json!([
+            { "sensor": "a", "taken_at": 2 },
+            { "sensor": "b", "taken_at": 2 },
+            { "sensor": "a", "taken_at": 1 },
+        ])
+    );
+
+    // Only the keyless table without a tag warns, right above its field.
+    let sdl = schema.sdl().await;
+    assert_eq!(sdl.matches("cursors are plain offsets").count(), 1, "{sdl}");
+    let warning = sdl.find("cursors are plain offsets").unwrap();
+    let (before, _) = sdl[warning..]
+        .split_once("allSamples(")
+        .expect("allSamples field");
+    assert!(!before.contains('('), "{sdl}");
+
+    db.drop().await.expect("failed to drop test schema");
+}
+
+/// Connections over tables estimated above `large_table_rows` hint at
+/// pagination when they fall back to the default page.
+#[tokio::test]
+async fn large_tables_hint_at_pagination() {
+    let db = common::fixture(
+        "CREATE TABLE events (id serial PRIMARY KEY, kind text);
+         INSERT INTO events (kind) SELECT 'click' FROM generate_series(1, 500);
+         CREATE TABLE tags (id serial PRIMARY KEY, label text);
+         INSERT INTO tags (label) VALUES ('a'), ('b');
+         ANALYZE events;
+         ANALYZE tags;",
+    )
+    .await;
+    let schema = db
+        .build_with(|config| config.large_table_rows = Some(200))
+        .await
+        .expect("schema build failed");
+
+    let res = schema.execute("{ allEvents { nodes { id } } }").await;
+    assert_eq!(
+        res.assert_ok().data()["allEvents"]["nodes"]
+            .as_array()
+            .unwrap()
+            .len(),
+        100
+    );
+    let warnings = res.response.extensions.get("warnings").unwrap();
+    let warnings = warnings.clone().into_json().unwrap();
+    assert_eq!(warnings.as_array().unwrap().len(), 1);
+    assert!(
+        warnings[0]
+            .as_str()
+            .unwrap()
+            .starts_with("allEvents returned only the first 100 of about 500 rows")
+    );
+
+    // Streamed responses carry the request's warnings as well.
+    let streamed: Vec<_> = schema
+        .server()
+        .execute_stream("{ allEvents { nodes { id } } }".into())
+        .collect()
+        .await;
+    assert!(streamed[0].extensions.contains_key("warnings"));
+
+    let res = schema
+        .execute("{ allEvents(first: 10) { nodes { id } } }")
+        .await;
+    res.assert_ok();
+    assert!(!res.response.extensions.contains_key("warnings"));
+
+    let res = schema.execute("{ allTags { nodes { id } } }").await;
+    res.assert_ok();
+    assert!(!res.response.extensions.contains_key("warnings"));
+
+    db.drop().await.expect("failed to drop test schema");
+}
+
+/// Above `indexed_order_by_rows`, `orderBy` offers only indexed and
+/// `@sortable` columns.
+#[tokio::test]
+async fn large_tables_order_by_indexed_columns() {
+    let db = common::fixture(
+        "CREATE TABLE events (id serial PRIMARY KEY, title text, at timestamptz, score int);
+         CREATE INDEX ON events (at);
+         CREATE INDEX ON events (title) WHERE score > 0;
+         COMMENT ON COLUMN events.score IS '@sortable';
+         INSERT INTO events (title, at, score)
+           SELECT 'e' || i, now(), i FROM generate_series(1, 10) i;
+         CREATE TABLE tags (id serial PRIMARY KEY, label text);
+         ANALYZE events;",
+    )
+    .await;
+
+    let schema = db
+        .build_with(|config| config.indexed_order_by_rows = Some(5))
+        .await
+        .expect("schema build failed");
+    let sdl = schema.sdl().await;
+    let order_by = |type_name: &str| {
+        let start = sdl.find(&format!("enum {type_name} {{")).unwrap();
+        sdl[start..start + sdl[start..].find('}').unwrap()].to_string()
+    };
+    let events = order_by("EventOrderBy");
+    for value in ["ID_ASC", "AT_DESC", "SCORE_ASC"] {
+        assert!(events.contains(value), "{events}");
+    }
+    assert!(!events.contains("TITLE_ASC"), "{events}");
+    assert!(order_by("TagOrderBy").contains("LABEL_ASC"));
+
+    let res = schema
+        .execute("{ allEvents(orderBy: [AT_DESC], first: 1) { nodes { id } } }")
+        .await;
+    res.assert_ok().assert_sql_contains("ORDER BY");
+
+    db.drop().await.expect("failed to drop test schema");
+}
@@ -0,0 +1,341 @@
+mod common;
+
+/// Foreign key constraints become forward and backward relation fields,
+/// unless the constraint is tagged `@omit`.
+#[tokio::test]
+async fn foreign_keys_become_relation_fields() {
+    let db = common::fixture(
+        "CREATE TABLE users (id int PRIMARY KEY, name text NOT NULL);
+         CREATE TABLE posts (
+             id int PRIMARY KEY,
+             author_id int REFERENCES users,
+             editor_id int CONSTRAINT posts_editor_fkey REFERENCES users
+         );
+         COMMENT ON CONSTRAINT posts_editor_fkey ON posts IS '@omit';
+         INSERT INTO users VALUES (1, 'ada'), (2, 'grace');
+         INSERT INTO posts VALUES (10, 1, 2), (11, 1, NULL), (12, NULL, NULL);",
+    )
+    .await;
+
+    let schema = db.build().await.expect("schema build failed");
+    let sdl = schema.sdl().await;
+    assert!(sdl.contains("userByAuthorId: User"), "{sdl}");
+    assert!(sdl.contains("postsByAuthorId: [Post!]!"), "{sdl}");
+    assert!(!sdl.contains("userByEditorId"), "{sdl}");
+    assert!(!sdl.contains("postsByEditorId"), "{sdl}");
+
+    let res = schema
+        .execute(
+            "{ allPosts(orderBy: [ID_ASC]) { nodes { id userByAuthorId { name } } }
+               allUsers(orderBy: [ID_ASC]) { nodes { name postsByAuthorId { id } } } }",
+        )
+        .await;
+    res.assert_ok().assert_sql_contains(
+        "FROM \"{schema}\".\"users\" __r1 WHERE __r1.\"id\" = \"posts\".\"author_id\"",
+    );
+    assert_eq!(
+        res.data(),
+        serde_json::json!({
+            "allPosts": { "nodes": [
+                { "id": 10, "userByAuthorId": { "name": "ada" } },
+                { "id": 11, "userByAuthorId": { "name": "ada" } },
+                { "id": 12, "userByAuthorId": null },
+            ] },
+            "allUsers": { "nodes": [
+                { "name": "ada", "postsByAuthorId": [{ "id": 10 }, { "id": 11 }] },
+                { "name": "grace", "postsByAuthorId": [] },
+            ] },
+        })
+    );
+
+    db.drop().await.expect("failed to drop test schema");
+}
+
+/// A relation takes a column's field name, which moves to `{name}_raw`,
+/// unless the column is named with `@name`.
+#[tokio::test]
+async fn relations_take_precedence_over_column_names() {
+    let db = common::fixture(
+        "CREATE TABLE users (id serial PRIMARY KEY, name text);
+         CREATE TABLE posts (id serial PRIMARY KEY, author text, author_id int);
+         COMMENT ON TABLE posts IS '@ref author to:User via:(author_id)->users(id)';
+         CREATE TABLE notes (id serial PRIMARY KEY, author text, author_id int);
+         COMMENT ON TABLE notes IS '@ref author to:User via:(author_id)->users(id)';
+         COMMENT ON COLUMN notes.author IS '@name author';
+         INSERT INTO users (name) VALUES ('Ada');
+         INSERT INTO posts (author, author_id) VALUES ('ada', 1);
+         INSERT INTO notes (author, author_id) VALUES ('ada', 1);",
+    )
+    .await;
+
+    let schema = db.build().await.expect("schema build failed");
+    let res = schema
+        .execute(
+            r#"{ allPosts(condition: { author_raw: "ada" }) { nodes { author_raw author { name } } } }"#,
+        )
+        .await;
+    assert_eq!(
+        res.assert_ok().data()["allPosts"]["nodes"],
+        serde_json::json!([{ "author_raw": "ada", "author": { "name": "Ada" } }])
+    );
+
+    let res = schema.execute("{ allNotes { nodes { author } } }").await;
+    assert_eq!(
+        res.assert_ok().data()["allNotes"]["nodes"][0]["author"],
+        "ada"
+    );
+
+    let schema = db
+        .build_with(|config| config.camel_case_fields = true)
+        .await
+        .expect("schema build failed");
+    assert!(schema.sdl().await.contains("authorRaw: String"));
+
+    db.drop().await.expect("failed to drop test schema");
+}
+
+/// `expose_fk_columns: false` hides the columns behind a relation field from
+/// the type while keeping them usable in conditions and inputs.
+#[tokio::test]
+async fn fk_columns_can_be_hidden_behind_relations() {
+    let db = common::fixture(
+        "CREATE TABLE users (id serial PRIMARY KEY, name text);
+         CREATE TABLE posts (id serial PRIMARY KEY, title text, author_id int);
+         COMMENT ON TABLE posts IS '@ref author to:User via:(author_id)->users(id)';
+         INSERT INTO users (name) VALUES ('Ada');",
+    )
+    .await;
+
+    let schema = db.build().await.expect("schema build failed");
+    let res = schema.execute("{ allPosts { nodes { author_id } } }").await;
+    res.assert_ok();
+
+    let schema = db
+        .build_with(|config| config.expose_fk_columns = false)
+        .await
+        .expect("schema build failed");
+    let res = schema
+        .execute(r#"mutation { createPost(input: { title: "Hi", author_id: 1 }) { title author { name } } }"#)
+        .await;
+    assert_eq!(
+        res.assert_ok().data()["createPost"],
+        serde_json::json!({ "title": "Hi", "author": { "name": "Ada" } })
+    );
+    let res = schema
+        .execute("{ allPosts(condition: { author_id: 1 }) { totalCount } }")
+        .await;
+    assert_eq!(res.assert_ok().data()["allPosts"]["totalCount"], 1);
+    let res = schema.execute("{ allPosts { nodes { author_id } } }").await;
+    assert!(!res.response.errors.is_empty());
+
+    db.drop().await.expect("failed to drop test schema");
+}
+
+/// Relation fields backed by a foreign key document its referential actions.
+#[tokio::test]
+async fn relation_descriptions_show_fk_actions() {
+    let db = common::fixture("CREATE TABLE users (id serial PRIMARY KEY);
+         CREATE TABLE posts (
+             id serial PRIMARY KEY,
+             author_id int REFERENCES users (id) ON DELETE SET NULL ON UPDATE CASCADE,
+             editor_id int
+         );
+         COMMENT ON TABLE posts IS
+             E'@ref author to:User via:(author_id)->users(id)\\n@ref editor to:User via:(editor_id)->users(id)';",).await;
+
+    let schema = db.build().await.expect("schema build failed");
+    let sdl = schema.sdl().await;
+    assert!(
+        sdl.contains(
+            "\t\"\"\"\n\tForeign key `posts_author_id_fkey`: ON DELETE SET NULL, ON UPDATE CASCADE.\n\t\"\"\"\n\tauthor: User"
+        ),
+        "{sdl}"
+    );
+    assert!(sdl.contains("\tauthor: User\n\teditor: User\n"), "{sdl}");
+
+    db.drop().await.expect("failed to drop test schema");
+}
+
+/// Relations cross between exposed schemas; foreign keys into a schema that
+/// is not exposed leave plain columns, or none with
+/// `expose_external_fk_columns: false`.
+#[tokio::test]
+async fn cross_schema_foreign_keys() {
+    let db = common::test_database().await;
+    let accounts = db
+        .create_schema("accounts")
+        .await
+        .expect("failed to create accounts schema");
+    let billing = db
+        .create_schema("billing")
+        .await
+        .expect("failed to create billing schema");
+    db.execute_sql(&format!(
+        r#"CREATE TABLE "{accounts}".users (id serial PRIMARY KEY, name text);
+           CREATE TABLE "{billing}".plans (id serial PRIMARY KEY);
+           CREATE TABLE posts (
+               id serial PRIMARY KEY,
+               author_id int REFERENCES "{accounts}".users (id) ON DELETE CASCADE,
+               plan_id int REFERENCES "{billing}".plans (id)
+           );
+           COMMENT ON TABLE posts IS
+               '@ref author to:User via:(author_id)->{accounts}.users(id)';
+           INSERT INTO "{accounts}".users (name) VALUES ('ann');
+           INSERT INTO "{billing}".plans DEFAULT VALUES;
+           INSERT INTO posts (author_id, plan_id) VALUES (1, 1);"#
+    ))
+    .await
+    .expect("fixture failed");
+
+    let schemas = vec![db.schema_name().to_string(), accounts.clone()];
+    let schema = db
+        .build_with(|config| config.schemas = schemas.clone())
+        .await
+        .expect("schema build failed");
+    let post_type = |sdl: &str| {
+        let start = sdl.find("type Post {").unwrap();
+        let end = start + sdl[start..].find('}').unwrap();
+        sdl[start..end].to_string()
+    };
+    let post = post_type(&schema.sdl().await);
+    assert!(post.contains("ON DELETE CASCADE"), "{post}");
+    assert!(post.contains("\tplan_id: Int\n"), "{post}");
+    let res = schema
+        .execute("{ allPosts { nodes { plan_id author { name } } } }")
+        .await;
+    assert_eq!(
+        res.assert_ok().data()["allPosts"]["nodes"],
+        serde_json::json!([{ "plan_id": 1, "author": { "name": "ann" } }])
+    );
+
+    let schema = db
+        .build_with(|config| {
+            config.schemas = schemas.clone();
+            config.expose_external_fk_columns = false;
+        })
+        .await
+        .expect("schema build failed");
+    let post = post_type(&schema.sdl().await);
+    assert!(!post.contains("plan_id"), "{post}");
+    assert!(post.contains("\tauthor_id: Int\n"), "{post}");
+    schema
+        .execute("{ allPosts(condition: { plan_id: 1 }) { totalCount } }")
+        .await
+        .assert_ok();
+
+    db.drop().await.expect("failed to drop test schema");
+}
+
+/// Foreign keys to an `@enum` table are exposed as its enum in outputs,
+/// conditions and mutation inputs.
+#[tokio::test]
+async fn enum_table_references_use_the_enum() {
+    let db = common::fixture(
+        "CREATE TABLE post_statuses (status text PRIMARY KEY);
+         COMMENT ON TABLE post_statuses IS '@enum';
+         INSERT INTO post_statuses VALUES ('draft'), ('in_review'), ('published');
+         CREATE TABLE articles (
+             id serial PRIMARY KEY,
+             status text NOT NULL REFERENCES post_statuses
+         );
+         INSERT INTO articles (status) VALUES ('draft'), ('in_review');",
+    )
+    .await;
+
+    let schema = db.build().await.expect("schema build failed");
+    let sdl = schema.sdl().await;
+    assert!(sdl.contains("enum PostStatus"), "{sdl}");
+    assert!(sdl.contains("status: PostStatus!"), "{sdl}");
+    assert!(!sdl.contains("allPostStatuses"), "{sdl}");
+
+    let res = schema
+        .execute(
+            "{ allArticles(condition: { status: { equal: IN_REVIEW } }) { nodes { id status } } }",
+        )
+        .await;
+    res.assert_ok();
+    assert_eq!(res.data()["allArticles"]["nodes"][0]["status"], "IN_REVIEW");
+
+    let res = schema
+        .execute("mutation { createArticle(input: { status: PUBLISHED }) { status } }")
+        .await;
+    res.assert_ok();
+    assert_eq!(res.data()["createArticle"]["status"], "PUBLISHED");
+
+    db.drop().await.expect("failed to drop test schema");
+}
+
+/// A materialized view tagged `@belongsTo` is reachable from its parent type.
+#[tokio::test]
+async fn belongs_to_view_nests_under_parent() {
+    let db = common::fixture(
+        "CREATE TABLE authors (id serial PRIMARY KEY, name text NOT NULL);
+         CREATE TABLE books (id serial PRIMARY KEY, author_id int NOT NULL, title text);
+         INSERT INTO authors (name) VALUES ('ann'), ('bob');
+         INSERT INTO books (author_id, title) VALUES (1, 'a'), (1, 'b');
+         CREATE MATERIALIZED VIEW author_stats AS
+             SELECT author_id, count(*)::int AS book_count FROM books GROUP BY author_id;
+         COMMENT ON MATERIALIZED VIEW author_stats IS '@belongsTo authors(author_id)';",
+    )
+    .await;
+
+    let schema = db.build().await.expect("schema build failed");
+    let res = schema
+        .execute(
+            "{ allAuthors(orderBy: [NAME_ASC]) { nodes { name authorStatsByAuthorId { book_count } } } }",
+        )
+        .await;
+    res.assert_ok().assert_sql_contains(
+        "FROM \"{schema}\".\"author_stats\" __r1 WHERE __r1.\"author_id\" = \"authors\".\"id\"",
+    );
+
+    let data = res.data();
+    let nodes = &data["allAuthors"]["nodes"];
+    assert_eq!(nodes[0]["authorStatsByAuthorId"][0]["book_count"], 2);
+    assert_eq!(nodes[1]["authorStatsByAuthorId"], serde_json::json!([]));
+
+    db.drop().await.expect("failed to drop test schema");
+}
+
+/// `@ref` declares relations without constraints, across join tables, and
+/// `@refVia` adds alternative paths.
+#[tokio::test]
+async fn ref_tags_declare_relations() {
+    let db = common::fixture(
+        "CREATE TABLE people (id int PRIMARY KEY, name text NOT NULL);
+         CREATE TABLE labels (id int PRIMARY KEY, label text NOT NULL);
+         CREATE TABLE entries (id int PRIMARY KEY, person_id int, bot_id int);
+         CREATE TABLE entry_labels (entry_id int, label_id int);
+         COMMENT ON TABLE entries IS
+             '@ref writer to:Person via:(person_id)->people(id)
+              @refVia writer via:(bot_id)->people(id)
+              @ref labels to:Label plural via:(id)->entry_labels(entry_id);(label_id)->labels(id)';
+         INSERT INTO people VALUES (1, 'ann'), (7, 'relay');
+         INSERT INTO labels VALUES (1, 'news'), (2, 'tech'), (3, 'misc');
+         INSERT INTO entries VALUES (1, 1, NULL), (2, NULL, 7);
+         INSERT INTO entry_labels VALUES (1, 2), (1, 1);",
+    )
+    .await;
+
+    let schema = db.build().await.expect("schema build failed");
+    let res = schema
+        .execute(
+            "{ allEntries(orderBy: [ID_ASC]) { nodes { id writer { name } labels { label } } } }",
+        )
+        .await;
+    res.assert_ok().assert_sql_contains(
+        "SELECT t2.* FROM \"{schema}\".\"entry_labels\" t1 JOIN \"{schema}\".\"labels\" t2 ON t2.\"id\" = t1.\"label_id\"",
+    );
+
+    let data = res.data();
+    let nodes = &data["allEntries"]["nodes"];
+    assert_eq!(nodes[0]["writer"]["name"], "ann");
+    assert_eq!(
+        nodes[0]["labels"],
+        serde_json::json!([{ "label": "news" }, { "label": "tech" }])
+    );
+    assert_eq!(nodes[1]["writer"]["name"], "relay");
+    assert_eq!(nodes[1]["labels"], serde_json::json!([]));
+
+    db.drop().await.expect("failed to drop test schema");
+}
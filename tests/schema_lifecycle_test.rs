@@ -0,0 +1,208 @@
+mod common;
+
+/// `schemaVersion` and the `X-GraphQL-Schema-Hash` header carry the same
+/// hash of the SDL, which changes along with the schema.
+#[tokio::test]
+async fn schema_hash_is_exposed_and_tracks_changes() {
+    let db = common::fixture("CREATE TABLE users (id int PRIMARY KEY, name text NOT NULL);").await;
+
+    let schema = db.build().await.expect("failed to build schema");
+    let res = schema.execute("{ schemaVersion }").await;
+    res.assert_ok();
+    let hash = schema.server().schema_hash().await;
+    assert_eq!(res.data()["schemaVersion"], serde_json::json!(hash));
+    assert_eq!(
+        res.response
+            .http_headers
+            .get("X-GraphQL-Schema-Hash")
+            .unwrap(),
+        hash.as_str()
+    );
+    let rebuilt = db.build().await.expect("failed to build schema");
+    assert_eq!(rebuilt.server().schema_hash().await, hash);
+
+    db.execute_sql("ALTER TABLE users ADD COLUMN email text;")
+        .await
+        .expect("failed to alter table");
+    let changed = db.build().await.expect("failed to build schema");
+    assert_ne!(changed.server().schema_hash().await, hash);
+
+    db.drop().await.expect("failed to drop test schema");
+}
+
+/// Objects dropped after the schema was built yield `SCHEMA_OUT_OF_DATE`
+/// and a rebuild instead of raw SQL errors.
+#[tokio::test]
+async fn dropped_columns_report_schema_out_of_date_and_rebuild() {
+    let db = common::fixture("CREATE TABLE widgets (id serial PRIMARY KEY, color text);").await;
+
+    let schema = db.build().await.expect("schema build failed");
+    db.execute_sql("ALTER TABLE widgets DROP COLUMN color;")
+        .await
+        .expect("drop failed");
+
+    let res = schema
+        .execute("{ allWidgets(orderBy: [COLOR_ASC]) { nodes { id } } }")
+        .await;
+    let err = &res.response.errors[0];
+    assert!(err.message.contains("schema changed"), "{}", err.message);
+    assert_eq!(
+        err.extensions.as_ref().unwrap().get("code"),
+        Some(&async_graphql::Value::from("SCHEMA_OUT_OF_DATE"))
+    );
+
+    let mut rebuilt = false;
+    for _ in 0..50 {
+        tokio::time::sleep(std::time::Duration::from_millis(100)).await;
+        if !schema.sdl().await.contains("COLOR_ASC") {
+            rebuilt = true;
+            break;
+        }
+    }
+    assert!(rebuilt, "schema was not rebuilt after SCHEMA_OUT_OF_DATE");
+    schema
+        .execute("{ allWidgets { totalCount } }")
+        .await
+        .assert_ok();
+
+    db.drop().await.expect("failed to drop test schema");
+}
+
+/// Watch mode rebuilds the schema after DDL, and a request that started on
+/// the old schema neither blocks the swap nor fails because of it.
+#[tokio::test]
+async fn watch_mode_swaps_schema_without_waiting_for_requests() {
+    let db = common::fixture(
+        "CREATE TABLE gauges (id serial PRIMARY KEY, reading int);
+         INSERT INTO gauges (reading) VALUES (7);
+         CREATE FUNCTION slow_reading() RETURNS int STABLE LANGUAGE sql
+             AS $$ SELECT 7 FROM pg_sleep(3) $$;",
+    )
+    .await;
+
+    let schema = db
+        .build_with(|config| config.watch_pg = true)
+        .await
+        .expect("schema build failed");
+    let server = schema.server().clone();
+    let slow = tokio::spawn(async move { server.execute("{ slowReading }".into()).await });
+    tokio::time::sleep(std::time::Duration::from_millis(200)).await;
+
+    db.execute_sql("ALTER TABLE gauges ADD COLUMN unit text;")
+        .await
+        .expect("alter failed");
+    let started = std::time::Instant::now();
+    let mut rebuilt = false;
+    while started.elapsed() < std::time::Duration::from_millis(2500) {
+        tokio::time::sleep(std::time::Duration::from_millis(100)).await;
+        if schema.sdl().await.contains("unit: String") {
+            rebuilt = true;
+            break;
+        }
+    }
+    assert!(
+        rebuilt,
+        "schema was not rebuilt while a request was running"
+    );
+    assert!(!slow.is_finished());
+
+    let response = slow.await.unwrap();
+    assert!(response.errors.is_empty(), "{:?}", response.errors);
+    assert_eq!(
+        response.data.into_json().unwrap(),
+        serde_json::json!({ "slowReading": 7 })
+    );
+    schema
+        .execute("{ allGauges { nodes { reading unit } } }")
+        .await
+        .assert_ok();
+
+    db.drop().await.expect("failed to drop test schema");
+}
+
+/// A schema that cannot be built fails with every problem at once, each
+/// naming the tables and columns it comes from.
+#[tokio::test]
+async fn invalid_schema_reports_every_problem() {
+    let db = common::fixture(
+        "CREATE TABLE users (id int PRIMARY KEY, email text, nickname text);
+         COMMENT ON COLUMN users.nickname IS '@name email';
+         CREATE TABLE authors (id int PRIMARY KEY);
+         CREATE TABLE author_connections (id int PRIMARY KEY);
+         CREATE FUNCTION all_authors() RETURNS int LANGUAGE sql STABLE AS 'SELECT 1';
+         CREATE TABLE posts (id int PRIMARY KEY, author_id int);
+         COMMENT ON TABLE posts IS '@ref writer to:User via:(author_id)->writers(id)';",
+    )
+    .await;
+
+    let err = db
+        .build()
+        .await
+        .err()
+        .expect("build should fail")
+        .to_string();
+    let schema = db.schema_name().to_string();
+    db.drop().await.expect("failed to drop test schema");
+
+    assert!(err.contains("(4 problems)"), "{err}");
+    assert!(
+        err.contains("type AuthorConnection is generated for both"),
+        "{err}"
+    );
+    assert!(
+        err.contains(&format!("table {schema}.author_connections")),
+        "{err}"
+    );
+    assert!(
+        err.contains(&format!(
+            "field User.email is generated for both column {schema}.users.email \
+             and column {schema}.users.nickname"
+        )),
+        "{err}"
+    );
+    assert!(
+        err.contains(&format!(
+            "field Query.allAuthors is generated for both table {schema}.authors \
+             and function {schema}.all_authors"
+        )),
+        "{err}"
+    );
+    assert!(
+        err.contains(&format!(
+            "@ref writer on table {schema}.posts cannot be resolved"
+        )),
+        "{err}"
+    );
+}
+
+/// `refresh{T}` mutations take part in the collision check like every other
+/// root field.
+#[tokio::test]
+async fn refresh_mutations_report_collisions() {
+    let db = common::fixture(
+        "CREATE TABLE sales (id serial PRIMARY KEY, amount int NOT NULL);
+         CREATE MATERIALIZED VIEW sales_totals AS
+             SELECT 1 AS id, sum(amount)::int AS total FROM sales;
+         CREATE UNIQUE INDEX ON sales_totals (id);
+         COMMENT ON MATERIALIZED VIEW sales_totals IS '@refreshable';
+         CREATE FUNCTION refresh_sales_total() RETURNS int LANGUAGE sql VOLATILE AS 'SELECT 1';",
+    )
+    .await;
+
+    let err = db
+        .build_with(|config| config.refresh_roles = vec!["postgres".into()])
+        .await
+        .err()
+        .expect("build should fail")
+        .to_string();
+    let schema = db.schema_name().to_string();
+    db.drop().await.expect("failed to drop test schema");
+
+    assert!(
+        err.contains(&format!(
+            "field Mutation.refreshSalesTotal is generated for both \
+             table {schema}.sales_totals and function {schema}.refresh_sales_total"
+        )),
+        "{err}"
+    );
+}
@@ -0,0 +1,90 @@
+mod common;
+
+use turbograph::testing::{SqlPlanner, assert_snapshot, assert_sql_snapshot};
+use turbograph::{Catalog, SchemaOptions, TurboGraph};
+
+/// Pins the SDL generated for `tests/fixtures/blog.sql`.
+#[tokio::test]
+async fn blog_fixture_sdl_matches_snapshot() {
+    let db = common::test_database().await;
+    db.load_fixture(concat!(
+        env!("CARGO_MANIFEST_DIR"),
+        "/tests/fixtures/blog.sql"
+    ))
+    .await
+    .expect("fixture failed");
+
+    let schema = db.build().await.expect("schema build failed");
+    let sdl = schema.sdl().await;
+    db.drop().await.expect("failed to drop test schema");
+
+    assert_snapshot("blog", &sdl);
+}
+
+/// A catalog snapshot saved as JSON rebuilds the same schema with no
+/// database to introspect.
+#[tokio::test]
+async fn blog_fixture_snapshot_rebuilds_the_same_sdl() {
+    let db = common::test_database().await;
+    db.load_fixture(concat!(
+        env!("CARGO_MANIFEST_DIR"),
+        "/tests/fixtures/blog.sql"
+    ))
+    .await
+    .expect("fixture failed");
+    let catalog = turbograph::introspect(db.config())
+        .await
+        .expect("introspection failed");
+    let snapshot = catalog.to_json().expect("snapshot failed");
+    db.drop().await.expect("failed to drop test schema");
+
+    let catalog = Catalog::from_json(&snapshot).expect("snapshot does not load");
+    assert_eq!(catalog.to_json().unwrap(), snapshot);
+    let server =
+        TurboGraph::from_snapshot(catalog, SchemaOptions::default()).expect("schema build failed");
+    assert_snapshot("blog", &server.export_sdl().await);
+}
+
+/// Pins the SQL planned for a few documents against the blog catalog, with
+/// no database connected while planning.
+#[tokio::test]
+async fn blog_fixture_sql_matches_snapshot() {
+    let db = common::test_database().await;
+    db.load_fixture(concat!(
+        env!("CARGO_MANIFEST_DIR"),
+        "/tests/fixtures/blog.sql"
+    ))
+    .await
+    .expect("fixture failed");
+    let catalog = turbograph::introspect(db.config())
+        .await
+        .expect("introspection failed");
+    let schema_name = db.schema_name().to_string();
+    db.drop().await.expect("failed to drop test schema");
+
+    let planner = SqlPlanner::new(catalog, SchemaOptions::default()).expect("schema build failed");
+    let mut sql = Vec::new();
+    for document in [
+        "{ allPosts(condition: { author_id: { equal: 1 } }, orderBy: [VIEWS_DESC], first: 5) {
+             totalCount nodes { id title }
+           } }",
+        "{ userByUsername(username: \"alice\") { id } }",
+        "{ allPosts(first: 5) { nodes { title author { username } } } }",
+        "mutation { createUser(input: { username: \"bob\" }) { id } }",
+        "mutation { deletePostById(input: { id: 1 }) { id } }",
+    ] {
+        sql.extend(planner.plan(document).await.expect("planning failed"));
+    }
+    // The test schema is named at random; golden files should not be.
+    let sql: Vec<String> = sql
+        .iter()
+        .map(|stmt| stmt.replace(&schema_name, "blog"))
+        .collect();
+    assert_sql_snapshot("blog", &sql);
+
+    let errors = planner
+        .plan("{ allPosts { nodes { missing } } }")
+        .await
+        .expect_err("invalid documents fail");
+    assert!(errors[0].message.contains("missing"), "{errors:?}");
+}
@@ -0,0 +1,7 @@
+SELECT COUNT(*) FROM "blog"."posts" WHERE "author_id" = $1;
+SELECT "id", "title" FROM "blog"."posts" WHERE "author_id" = $1 ORDER BY "views" DESC LIMIT $2 OFFSET $3;
+SELECT * FROM "blog"."users" WHERE "username" = $1 ORDER BY "id";
+SELECT COUNT(*) FROM "blog"."posts";
+SELECT *, ROW(ARRAY(SELECT ROW(__r1."id", __r1."username", __r1."bio", __r1."is_active", __r1."created_at") FROM "blog"."users" __r1 WHERE __r1."id" = "posts"."author_id" ORDER BY __r1."id")) AS "__turbograph_prefetch" FROM "blog"."posts" LIMIT $1 OFFSET $2;
+INSERT INTO "blog"."users" ("username") VALUES ($1) RETURNING "id";
+DELETE FROM "blog"."posts" WHERE "id" = $1 RETURNING "id";
@@ -0,0 +1,152 @@
+mod common;
+
+use async_graphql::futures_util::StreamExt;
+
+/// `@subscribe` tables get a change trigger and a `{t}Changed` subscription.
+#[tokio::test]
+async fn subscribe_tag_streams_row_changes() {
+    let db = common::fixture(
+        "CREATE TABLE posts (id serial PRIMARY KEY, title text NOT NULL);
+         COMMENT ON TABLE posts IS '@subscribe';
+         CREATE TABLE drafts (id serial PRIMARY KEY, title text);",
+    )
+    .await;
+
+    let schema = db
+        .build_with(|config| config.subscriptions = true)
+        .await
+        .expect("schema build failed");
+    let sdl = schema.sdl().await;
+    assert!(sdl.contains("postChanged: PostChangedPayload!"), "{sdl}");
+    assert!(!sdl.contains("draftChanged"));
+
+    let mut events = schema
+        .server()
+        .execute_stream("subscription { postChanged { op key node { title } } }".into());
+    let (tx, mut rx) = tokio::sync::mpsc::unbounded_channel();
+    tokio::spawn(async move {
+        while let Some(response) = events.next().await {
+            let _ = tx.send(response);
+        }
+    });
+    let mut next_event = async || {
+        tokio::time::timeout(std::time::Duration::from_secs(5), rx.recv())
+            .await
+            .expect("no change event arrived")
+            .unwrap()
+    };
+    tokio::time::sleep(std::time::Duration::from_millis(300)).await;
+
+    db.execute_sql(
+        "INSERT INTO drafts (title) VALUES ('ignored');
+         INSERT INTO posts (title) VALUES ('hello');",
+    )
+    .await
+    .expect("insert failed");
+    let inserted = next_event().await;
+    db.execute_sql("DELETE FROM posts")
+        .await
+        .expect("delete failed");
+    let deleted = next_event().await;
+
+    assert!(inserted.is_ok(), "{:?}", inserted.errors);
+    assert_eq!(
+        inserted.data.into_json().unwrap()["postChanged"],
+        serde_json::json!({ "op": "INSERT", "key": "[1]", "node": { "title": "hello" } })
+    );
+    assert_eq!(
+        deleted.data.into_json().unwrap()["postChanged"],
+        serde_json::json!({ "op": "DELETE", "key": "[1]", "node": null })
+    );
+
+    db.drop().await.expect("failed to drop test schema");
+}
+
+/// `listen(topic:)` relays NOTIFYs on its prefixed channel, and the
+/// per-kind change fields only report their kind of change.
+#[tokio::test]
+async fn listen_and_per_kind_subscriptions_stream_notifications() {
+    let db = common::fixture(
+        "CREATE TABLE orders (id serial PRIMARY KEY, total int NOT NULL);
+         COMMENT ON TABLE orders IS '@subscribe';",
+    )
+    .await;
+
+    let schema = db
+        .build_with(|config| config.subscriptions = true)
+        .await
+        .expect("schema build failed");
+    let sdl = schema.sdl().await;
+    assert!(
+        sdl.contains("listen(topic: String!): ListenPayload!"),
+        "{sdl}"
+    );
+    assert!(sdl.contains("orderCreated: OrderChangedPayload!"), "{sdl}");
+    assert!(sdl.contains("orderDeleted: OrderChangedPayload!"), "{sdl}");
+
+    let (tx, mut rx) = tokio::sync::mpsc::unbounded_channel();
+    for query in [
+        r#"subscription { listen(topic: "orders") { topic payload } }"#,
+        "subscription { orderUpdated { op node { total } } }",
+    ] {
+        let mut events = schema.server().execute_stream(query.into());
+        let tx = tx.clone();
+        tokio::spawn(async move {
+            while let Some(response) = events.next().await {
+                let _ = tx.send(response);
+            }
+        });
+    }
+    let mut next_event = async || {
+        let response = tokio::time::timeout(std::time::Duration::from_secs(5), rx.recv())
+            .await
+            .expect("no event arrived")
+            .unwrap();
+        assert!(response.is_ok(), "{:?}", response.errors);
+        response.data.into_json().unwrap()
+    };
+    tokio::time::sleep(std::time::Duration::from_millis(300)).await;
+
+    db.execute_sql(
+        "SELECT pg_notify('turbograph:other', 'ignored');
+         SELECT pg_notify('orders', 'ignored');
+         INSERT INTO orders (total) VALUES (5);",
+    )
+    .await
+    .expect("insert failed");
+    db.execute_sql("SELECT pg_notify('turbograph:orders', '{\"id\":1}')")
+        .await
+        .expect("notify failed");
+    assert_eq!(
+        next_event().await,
+        serde_json::json!({ "listen": { "topic": "orders", "payload": "{\"id\":1}" } })
+    );
+
+    db.execute_sql("UPDATE orders SET total = 7")
+        .await
+        .expect("update failed");
+    assert_eq!(
+        next_event().await,
+        serde_json::json!({ "orderUpdated": { "op": "UPDATE", "node": { "total": 7 } } })
+    );
+
+    let err = schema
+        .server()
+        .execute_stream(
+            format!(
+                r#"subscription {{ listen(topic: "{}") {{ topic }} }}"#,
+                "x".repeat(60)
+            )
+            .into(),
+        )
+        .next()
+        .await
+        .unwrap();
+    assert!(
+        err.errors[0].message.contains("topic must be"),
+        "{:?}",
+        err.errors
+    );
+
+    db.drop().await.expect("failed to drop test schema");
+}
@@ -0,0 +1,27 @@
+mod common;
+
+/// Builds a schema from an inline fixture and checks both the GraphQL result
+/// and the SQL it was resolved with.
+#[tokio::test]
+async fn harness_builds_schema_from_fixture() {
+    let db = common::fixture(
+        "CREATE TABLE widgets (id serial PRIMARY KEY, name text NOT NULL);
+         INSERT INTO widgets (name) VALUES ('sprocket'), ('gear');",
+    )
+    .await;
+
+    let schema = db.build().await.expect("schema build failed");
+    let res = schema
+        .execute("{ allWidgets(orderBy: [NAME_ASC]) { totalCount nodes { name } } }")
+        .await;
+
+    res.assert_ok()
+        .assert_sql_contains("FROM \"{schema}\".\"widgets\"")
+        .assert_sql_contains("ORDER BY \"name\" ASC");
+
+    let data = res.data();
+    assert_eq!(data["allWidgets"]["totalCount"], 2);
+    assert_eq!(data["allWidgets"]["nodes"][0]["name"], "gear");
+
+    db.drop().await.expect("failed to drop test schema");
+}
@@ -0,0 +1,357 @@
+mod common;
+
+use turbograph::{Encryption, NonFiniteFloats, UnsupportedTypes};
+
+/// Columns of types without a GraphQL mapping are exposed as `String`, left
+/// out, or fail the build, as configured globally or by `@unsupported`.
+#[tokio::test]
+async fn unsupported_column_types_follow_their_mode() {
+    let db = common::fixture(
+        "CREATE TABLE places (
+             id int PRIMARY KEY,
+             location point,
+             search tsvector
+         );
+         COMMENT ON COLUMN places.search IS '@unsupported skip';
+         INSERT INTO places VALUES (1, point(1, 2), 'a');",
+    )
+    .await;
+
+    let schema = db.build().await.expect("failed to build schema");
+    let sdl = schema.sdl().await;
+    assert!(sdl.contains("location: String"), "{sdl}");
+    assert!(!sdl.contains("search"), "{sdl}");
+    let res = schema.execute("{ allPlaces { nodes { location } } }").await;
+    res.assert_ok();
+
+    let schema = db
+        .build_with(|config| config.unsupported_types = UnsupportedTypes::Skip)
+        .await
+        .expect("failed to build schema");
+    let sdl = schema.sdl().await;
+    assert!(!sdl.contains("location"), "{sdl}");
+    schema
+        .execute("{ allPlaces { nodes { id } } }")
+        .await
+        .assert_ok();
+
+    let err = db
+        .build_with(|config| config.unsupported_types = UnsupportedTypes::Error)
+        .await
+        .err()
+        .expect("build should fail");
+    assert!(
+        err.to_string()
+            .contains("column places.location has unsupported type point"),
+        "{err}"
+    );
+
+    db.execute_sql("COMMENT ON COLUMN places.location IS '@unsupported error';")
+        .await
+        .expect("failed to tag column");
+    assert!(db.build().await.is_err());
+
+    db.drop().await.expect("failed to drop test schema");
+}
+
+/// Values of every mapped type read back exactly as they were written,
+/// including digits a float would lose.
+#[tokio::test]
+async fn scalar_types_round_trip() {
+    let db = common::fixture(
+        "CREATE TABLE readings (
+             id int PRIMARY KEY,
+             amount numeric NOT NULL,
+             device uuid NOT NULL,
+             counter bigint NOT NULL,
+             taken_on date NOT NULL,
+             taken_at timestamptz NOT NULL,
+             samples numeric[] NOT NULL,
+             payload bytea NOT NULL
+         );",
+    )
+    .await;
+
+    let schema = db.build().await.expect("failed to build schema");
+    let sdl = schema.sdl().await;
+    for field in [
+        "amount: BigFloat!",
+        "device: UUID!",
+        "counter: BigInt!",
+        "taken_on: Date!",
+        "taken_at: Datetime!",
+        "samples: [BigFloat!]",
+    ] {
+        assert!(sdl.contains(field), "{field} missing from {sdl}");
+    }
+
+    let fields = "amount device counter taken_on taken_at samples payload";
+    let res = schema
+        .execute(&format!(
+            r#"mutation {{ createReading(input: {{
+                 id: 1,
+                 amount: "12345678901234567890.123456789",
+                 device: "a0eebc99-9c0b-4ef8-bb6d-6bb9bd380a11",
+                 counter: "9223372036854775807",
+                 taken_on: "2024-01-31",
+                 taken_at: "2024-01-31T12:00:00+00:00",
+                 samples: ["0.10", "-3"],
+                 payload: "AAEC/w=="
+               }}) {{ {fields} }} }}"#
+        ))
+        .await;
+    res.assert_ok();
+    let expected = serde_json::json!({
+        "amount": "12345678901234567890.123456789",
+        "device": "a0eebc99-9c0b-4ef8-bb6d-6bb9bd380a11",
+        "counter": "9223372036854775807",
+        "taken_on": "2024-01-31",
+        "taken_at": "2024-01-31T12:00:00+00:00",
+        "samples": ["0.10", "-3"],
+        "payload": "AAEC/w=="
+    });
+    assert_eq!(res.data()["createReading"], expected);
+
+    let res = schema
+        .execute(&format!(
+            r#"{{ allReadings(condition: {{ device: "A0EEBC999C0B4EF8BB6D6BB9BD380A11" }}) {{ nodes {{ {fields} }} }} }}"#
+        ))
+        .await;
+    res.assert_ok();
+    assert_eq!(res.data()["allReadings"]["nodes"][0], expected);
+
+    let res = schema
+        .execute(r#"mutation { createReading(input: { id: 2, amount: "1e5", device: "x", counter: "1", taken_on: "2024-01-31", taken_at: "2024-01-31T12:00:00Z", samples: [], payload: "" }) { id } }"#)
+        .await;
+    assert!(!res.response.errors.is_empty());
+
+    db.drop().await.expect("failed to drop test schema");
+}
+
+/// `NaN` and the infinities come back as `null`, as their text, or as a
+/// field error, as `non_finite_floats` chooses.
+#[tokio::test]
+async fn non_finite_floats_follow_config() {
+    let db = common::fixture(
+        "CREATE TABLE samples (id int PRIMARY KEY, value float8, ratio real);
+         INSERT INTO samples VALUES (1, 'NaN', '-Infinity'), (2, 1.5, 0.5);",
+    )
+    .await;
+    let query = "{ allSamples(orderBy: [ID_ASC]) { nodes { value ratio } } }";
+
+    let schema = db.build().await.expect("failed to build schema");
+    let res = schema.execute(query).await;
+    assert_eq!(
+        res.assert_ok().data()["allSamples"]["nodes"],
+        serde_json::json!([
+            { "value": null, "ratio": null },
+            { "value": 1.5, "ratio": 0.5 }
+        ])
+    );
+
+    let schema = db
+        .build_with(|config| config.non_finite_floats = NonFiniteFloats::String)
+        .await
+        .expect("failed to build schema");
+    let res = schema.execute(query).await;
+    assert_eq!(
+        res.assert_ok().data()["allSamples"]["nodes"][0],
+        serde_json::json!({ "value": "NaN", "ratio": "-Infinity" })
+    );
+
+    let schema = db
+        .build_with(|config| config.non_finite_floats = NonFiniteFloats::Error)
+        .await
+        .expect("failed to build schema");
+    let res = schema.execute(query).await;
+    let error = &res.response.errors[0];
+    let code = error.extensions.as_ref().and_then(|ext| ext.get("code"));
+    assert_eq!(
+        code,
+        Some(&async_graphql::Value::from("NON_FINITE_FLOAT")),
+        "{}",
+        error.message
+    );
+    let res = schema
+        .execute("{ sampleById(id: 2) { value ratio } }")
+        .await;
+    res.assert_ok();
+
+    db.drop().await.expect("failed to drop test schema");
+}
+
+/// `@encrypted` columns pass through the configured hook on write and read.
+#[tokio::test]
+async fn encrypted_columns_are_transformed_on_write_and_read() {
+    let db = common::fixture(
+        "CREATE TABLE secrets (id serial PRIMARY KEY, label text, token text);
+         COMMENT ON COLUMN secrets.token IS '@encrypted';",
+    )
+    .await;
+
+    let schema = db
+        .build_with(|config| {
+            config.encryption = Some(Encryption::new(
+                |column, plaintext| async move {
+                    assert_eq!(column.table, "secrets");
+                    Ok(format!(
+                        "enc:{}",
+                        plaintext.chars().rev().collect::<String>()
+                    ))
+                },
+                |_, ciphertext| async move {
+                    let reversed = ciphertext.strip_prefix("enc:").ok_or("not ciphertext")?;
+                    Ok(reversed.chars().rev().collect())
+                },
+            ));
+        })
+        .await
+        .expect("schema build failed");
+    let sdl = schema.sdl().await;
+    assert!(!sdl.contains("SecretTokenFilter"), "{sdl}");
+    assert!(!sdl.contains("TOKEN_ASC"), "{sdl}");
+
+    let res = schema
+        .execute(r#"mutation { createSecret(input: { label: "api", token: "s3cret" }) { token } }"#)
+        .await;
+    assert_eq!(res.assert_ok().data()["createSecret"]["token"], "s3cret");
+    db.execute_sql(
+        "DO $$ BEGIN
+           IF NOT EXISTS (SELECT 1 FROM secrets WHERE token = 'enc:terc3s') THEN
+             RAISE EXCEPTION 'token was not encrypted';
+           END IF;
+         END $$;",
+    )
+    .await
+    .expect("stored value is not the ciphertext");
+
+    let res = schema
+        .execute(r#"mutation { updateSecret(patch: { token: "rotated" }) { token } }"#)
+        .await;
+    assert_eq!(
+        res.assert_ok().data()["updateSecret"][0]["token"],
+        "rotated"
+    );
+    let res = schema
+        .execute("{ allSecrets { nodes { label token } } }")
+        .await;
+    assert_eq!(
+        res.assert_ok().data()["allSecrets"]["nodes"][0],
+        serde_json::json!({ "label": "api", "token": "rotated" })
+    );
+
+    let unconfigured = db.build().await.expect("schema build failed");
+    let res = unconfigured
+        .execute("{ allSecrets { nodes { token } } }")
+        .await;
+    assert!(res.response.is_err());
+
+    db.drop().await.expect("failed to drop test schema");
+}
+
+/// Enum, domain and composite column types are resolved from `pg_type` and
+/// exposed as GraphQL enums, their base scalar and objects.
+#[tokio::test]
+async fn user_defined_types_are_resolved() {
+    let db = common::fixture(
+        "CREATE TYPE mood AS ENUM ('happy', 'so_so');
+         CREATE DOMAIN rating AS int CHECK (VALUE BETWEEN 1 AND 5);
+         CREATE TYPE postal_address AS (street text, zip int, mood mood);
+         CREATE TABLE reviews (
+             id serial PRIMARY KEY,
+             mood mood NOT NULL,
+             rating rating,
+             address postal_address
+         );
+         INSERT INTO reviews (mood, rating, address)
+             VALUES ('so_so', 3, ROW('Main St', 12345, 'happy'));",
+    )
+    .await;
+
+    let schema = db.build().await.expect("schema build failed");
+    let sdl = schema.sdl().await;
+    assert!(sdl.contains("enum Mood {\n\tHAPPY\n\tSO_SO\n}"), "{sdl}");
+    assert!(sdl.contains("mood: Mood!"), "{sdl}");
+    assert!(sdl.contains("rating: Int\n"), "{sdl}");
+    assert!(sdl.contains("address: PostalAddress\n"), "{sdl}");
+
+    let res = schema
+        .execute("mutation { createReview(input: { mood: HAPPY, rating: 5 }) { id mood rating } }")
+        .await;
+    assert_eq!(
+        res.assert_ok().data()["createReview"],
+        serde_json::json!({ "id": 2, "mood": "HAPPY", "rating": 5 })
+    );
+
+    let res = schema
+        .execute(
+            "{ allReviews(condition: { mood: SO_SO }) {
+                 nodes { rating address { street zip mood } } } }",
+        )
+        .await;
+    assert_eq!(
+        res.assert_ok().data()["allReviews"]["nodes"],
+        serde_json::json!([{
+            "rating": 3,
+            "address": { "street": "Main St", "zip": 12345, "mood": "HAPPY" }
+        }])
+    );
+
+    db.drop().await.expect("failed to drop test schema");
+}
+
+/// Arrays of enum and composite types are read as lists and written from
+/// list inputs, composites from `{T}Input` objects.
+#[tokio::test]
+async fn enum_and_composite_arrays_round_trip() {
+    let db = common::fixture(
+        "CREATE TYPE mood AS ENUM ('happy', 'so_so');
+         CREATE TYPE postal_address AS (street text, zip int);
+         CREATE TABLE profiles (
+             id serial PRIMARY KEY,
+             moods mood[] NOT NULL DEFAULT '{}',
+             home postal_address,
+             addresses postal_address[]
+         );
+         INSERT INTO profiles (moods, addresses)
+             VALUES ('{so_so,happy}', ARRAY[ROW('Main St', 1), NULL]::postal_address[]);",
+    )
+    .await;
+
+    let schema = db.build().await.expect("schema build failed");
+    let sdl = schema.sdl().await;
+    assert!(sdl.contains("moods: [Mood!]"), "{sdl}");
+    assert!(sdl.contains("addresses: [PostalAddress]"), "{sdl}");
+    assert!(sdl.contains("input PostalAddressInput"), "{sdl}");
+
+    let res = schema
+        .execute("{ allProfiles { nodes { moods addresses { street zip } } } }")
+        .await;
+    assert_eq!(
+        res.assert_ok().data()["allProfiles"]["nodes"],
+        serde_json::json!([{
+            "moods": ["SO_SO", "HAPPY"],
+            "addresses": [{ "street": "Main St", "zip": 1 }]
+        }])
+    );
+
+    let res = schema
+        .execute(
+            r#"mutation { createProfile(input: {
+                 moods: [HAPPY],
+                 home: { street: "Elm St" },
+                 addresses: [{ street: "Oak St", zip: 2 }, null]
+               }) { moods home { street zip } addresses { street zip } } }"#,
+        )
+        .await;
+    assert_eq!(
+        res.assert_ok().data()["createProfile"],
+        serde_json::json!({
+            "moods": ["HAPPY"],
+            "home": { "street": "Elm St", "zip": null },
+            "addresses": [{ "street": "Oak St", "zip": 2 }]
+        })
+    );
+
+    db.drop().await.expect("failed to drop test schema");
+}